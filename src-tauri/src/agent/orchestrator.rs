@@ -1,22 +1,27 @@
 use crate::agent::prompts::CONTROLLER_PROMPT;
 use crate::db::{
-    AgentConfig, AgentSession, AgentSessionOperations, MessageToolExecutionInput, PhaseKind, Plan,
-    PlanStep, ResumeTarget, StepAction, StepResult, StepStatus, ToolExecutionRecord,
+    AgentConfig, AgentSession, AgentSessionOperations, ApprovalDecision, MessageToolExecutionInput,
+    PhaseKind, Plan, PlanStep, ResumeTarget, StepAction, StepApproval, StepResult, StepStatus,
+    ToolExecutionRecord,
 };
 use crate::events::{
     AgentEvent, EventBus, EVENT_AGENT_COMPLETED, EVENT_AGENT_PHASE_CHANGED,
     EVENT_AGENT_PLAN_ADJUSTED, EVENT_AGENT_PLAN_CREATED, EVENT_AGENT_STEP_COMPLETED,
     EVENT_AGENT_STEP_PROPOSED, EVENT_AGENT_STEP_STARTED, EVENT_TOOL_EXECUTION_APPROVED,
-    EVENT_TOOL_EXECUTION_COMPLETED, EVENT_TOOL_EXECUTION_DENIED, EVENT_TOOL_EXECUTION_PROPOSED,
-    EVENT_TOOL_EXECUTION_STARTED,
+    EVENT_TOOL_EXECUTION_COMPLETED, EVENT_TOOL_EXECUTION_DENIED, EVENT_TOOL_EXECUTION_PROGRESS,
+    EVENT_TOOL_EXECUTION_PROPOSED, EVENT_TOOL_EXECUTION_STARTED,
 };
 use crate::llm::{json_schema_output_format, LlmMessage, StreamResult};
 use crate::tool_outputs::{store_tool_output, ToolOutputRecord};
 use crate::tools::{
-    get_conversation_tool_approval_override, get_tool_approval_override,
-    load_conversation_tool_approval_overrides, load_tool_approval_overrides, ApprovalStore,
-    PendingToolApprovalInput, ToolApprovalDecision, ToolExecutionContext, ToolRegistry,
-    ToolResultMode,
+    external_resource_id, load_conversation_tool_approval_overrides, load_tool_approval_overrides,
+    record_tool_audit, resolve_tool_approval, ApprovalStore, CancellationToken,
+    PendingToolApprovalInput, ToolApprovalDecision, ToolAuditRecord, ToolDefinition, ToolError,
+    ToolErrorKind, ToolExecutionContext, ToolProgress, ToolProgressSink, ToolRegistry,
+    ToolResultMode, AUDIT_DECISION_APPROVED, AUDIT_DECISION_AUTO_APPROVED,
+    AUDIT_DECISION_CANCELLED, AUDIT_DECISION_DENIED, AUDIT_DECISION_MODIFIED,
+    AUDIT_DECISION_TIMED_OUT, AUDIT_OUTCOME_ERROR, AUDIT_OUTCOME_NOT_EXECUTED,
+    AUDIT_OUTCOME_SUCCESS, DELEGATE_TOOL_NAME,
 };
 use chrono::Utc;
 use serde::Deserialize;
@@ -32,6 +37,21 @@ const PERSISTED_RESULT_PREVIEW_MAX_CHARS: usize = 1_200;
 const CONTROLLER_HISTORY_MAX_CHARS: usize = 48_000;
 const CONTROLLER_HISTORY_STABLE_PREFIX_MESSAGES: usize = 8;
 const CONTROLLER_HISTORY_RECENT_TAIL_MESSAGES: usize = 20;
+const TOOL_CANCEL_GRACE_MS: u64 = 2_000;
+const MAX_DELEGATION_DEPTH: u32 = 1;
+const DELEGATE_DEFAULT_MAX_TURNS: u32 = 8;
+const DELEGATE_DEFAULT_RESULT_CHARS: usize = 2_000;
+const TOOL_RETRY_BASE_DELAY_MS: u64 = 500;
+const TOOL_RETRY_MAX_DELAY_MS: u64 = 30_000;
+
+/// How approval-gated tools behave in runs nobody is watching, such as scheduled jobs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnattendedApprovalPolicy {
+    /// Deny the call and let the agent continue without it.
+    Deny,
+    /// Run the call as if the user had approved it, unless an approval rule says to ask.
+    Allow,
+}
 
 pub struct DynamicController {
     db: crate::db::Db,
@@ -47,6 +67,8 @@ pub struct DynamicController {
     last_step_result: Option<StepResult>,
     tool_calls_in_current_step: u32,
     requested_user_input: bool,
+    depth: u32,
+    unattended_approval: Option<UnattendedApprovalPolicy>,
 }
 
 impl DynamicController {
@@ -68,6 +90,8 @@ impl DynamicController {
             id: Uuid::new_v4().to_string(),
             conversation_id,
             message_id,
+            parent_session_id: None,
+            parent_step_id: None,
             phase: PhaseKind::Controller,
             plan: None,
             gathered_info: Vec::new(),
@@ -94,6 +118,8 @@ impl DynamicController {
             last_step_result: None,
             tool_calls_in_current_step: 0,
             requested_user_input: false,
+            depth: 0,
+            unattended_approval: None,
         })
     }
 
@@ -265,7 +291,11 @@ impl DynamicController {
                 .get(tool)
                 .and_then(|tool_def| tool_def.preview.as_ref())
                 .and_then(|preview| {
-                    preview(normalize_tool_args(args.clone()), ToolExecutionContext).ok()
+                    preview(
+                        normalize_tool_args(args.clone()),
+                        self.tool_context(None, CancellationToken::new()),
+                    )
+                    .ok()
                 }),
             _ => None,
         };
@@ -314,13 +344,14 @@ impl DynamicController {
 
         let result = match step {
             ControllerStep::Tool { tool, args, .. } => {
-                self.execute_tool(&step_id, &tool, normalize_tool_args(args))?
+                self.execute_tool(call_llm, &step_id, &tool, normalize_tool_args(args))?
             }
             ControllerStep::Respond { message, .. } => StepResult {
                 step_id: step_id.clone(),
                 success: true,
                 output: Some(json!({ "message": message })),
                 error: None,
+                error_kind: None,
                 tool_executions: Vec::new(),
                 duration_ms: 0,
                 completed_at: Utc::now(),
@@ -332,6 +363,7 @@ impl DynamicController {
                     success: true,
                     output: Some(json!({ "note": output })),
                     error: None,
+                    error_kind: None,
                     tool_executions: Vec::new(),
                     duration_ms: 0,
                     completed_at: Utc::now(),
@@ -342,6 +374,7 @@ impl DynamicController {
                 success: true,
                 output: Some(json!({ "question": question })),
                 error: None,
+                error_kind: None,
                 tool_executions: Vec::new(),
                 duration_ms: 0,
                 completed_at: Utc::now(),
@@ -377,18 +410,17 @@ impl DynamicController {
             Utc::now().timestamp_millis(),
         ));
 
-        let result_error = result.error.clone();
+        let result_error_kind = result.error_kind.clone();
         self.last_step_result = Some(result.clone());
         self.session.step_results.push(result);
         if ask_user_payload.is_none() {
             self.set_phase(PhaseKind::Controller)?;
         }
 
-        if let Some(error) = result_error.as_deref() {
-            if error == "Tool execution denied by approval"
-                || error == "Tool approval timed out"
-                || error == "Tool execution cancelled"
-            {
+        if let Some(kind) = result_error_kind.as_deref() {
+            let denied = kind == ToolErrorKind::Denied.as_str();
+            let denied_with_feedback = denied && self.step_approval_feedback(&step_id).is_some();
+            if !denied_with_feedback && (denied || kind == ToolErrorKind::Cancelled.as_str()) {
                 return Ok(StepExecutionOutcome::Complete(
                     "Okay, stopping since the tool request wasn't approved. Let me know how you'd like to continue."
                         .to_string(),
@@ -414,12 +446,16 @@ impl DynamicController {
         Ok(StepExecutionOutcome::Continue)
     }
 
-    fn execute_tool(
+    fn execute_tool<F>(
         &mut self,
+        call_llm: &mut F,
         step_id: &str,
         tool_name: &str,
-        args: Value,
-    ) -> Result<StepResult, String> {
+        mut args: Value,
+    ) -> Result<StepResult, String>
+    where
+        F: FnMut(&[LlmMessage], Option<&str>, Option<Value>) -> Result<StreamResult, String>,
+    {
         if self.tool_calls_in_current_step >= self.session.config.max_tool_calls_per_step {
             return Err("Exceeded tool call limit".to_string());
         }
@@ -439,38 +475,189 @@ impl DynamicController {
 
         let execution_id = Uuid::new_v4().to_string();
         let mut tool_executions = Vec::new();
-        let requires_approval = match get_conversation_tool_approval_override(
+        let approval = resolve_tool_approval(
             &self.db,
-            &self.session.conversation_id,
+            Some(&self.session.conversation_id),
             tool_name,
-        ) {
-            Ok(Some(value)) => value,
-            Ok(None) => match get_tool_approval_override(&self.db, tool_name) {
-                Ok(Some(value)) => value,
-                Ok(None) => tool.metadata.requires_approval,
-                Err(err) => {
-                    log::warn!(
-                        "Failed to load global tool approval override for {}: {}",
-                        tool_name,
-                        err
-                    );
-                    tool.metadata.requires_approval
-                }
-            },
-            Err(err) => {
-                log::warn!(
-                    "Failed to load conversation tool approval override for {}: {}",
+            &args,
+            tool.metadata.requires_approval,
+        );
+        let requires_approval = approval.requires_approval;
+
+        if approval.denied {
+            let timestamp_ms = Utc::now().timestamp_millis();
+            log::warn!(
+                "[tool] denied by approval rule: tool={} execution_id={} rule_id={} iteration={} session_id={} conversation_id={} message_id={}",
+                tool_name,
+                execution_id,
+                approval.rule_id.as_deref().unwrap_or(""),
+                iteration,
+                self.session.id,
+                self.session.conversation_id,
+                self.assistant_message_id
+            );
+            self.event_bus.publish(AgentEvent::new_with_timestamp(
+                EVENT_TOOL_EXECUTION_DENIED,
+                json!({
+                    "execution_id": execution_id.clone(),
+                    "approval_id": null,
+                    "tool_name": tool_name,
+                    "iteration": iteration,
+                    "approval_source": approval.source,
+                    "approval_rule_id": approval.rule_id.clone(),
+                    "conversation_id": self.session.conversation_id,
+                    "message_id": self.assistant_message_id,
+                    "timestamp_ms": timestamp_ms,
+                }),
+                timestamp_ms,
+            ));
+            record_step_approval(
+                &self.db,
+                &mut self.session,
+                step_id,
+                ApprovalDecision::Denied,
+                None,
+                approval.rule_id.clone(),
+            );
+            self.audit_denial(
+                tool,
+                &execution_id,
+                &args,
+                AUDIT_DECISION_DENIED,
+                Some(approval.source),
+                approval.rule_id.as_deref(),
+            );
+            return Ok(self.denied_step_result(
+                step_id,
+                execution_id,
+                tool_name,
+                args,
+                iteration,
+                ToolError::permission("Tool execution denied by approval rule").with_hint(
+                    "An approval rule blocks this call. Do not retry it; choose another approach or ask the user.",
+                ),
+                timestamp_ms,
+            ));
+        }
+
+        // Who let the call through, for the audit log. Overwritten below when the user or the
+        // unattended policy decides.
+        let mut audit_decision = (AUDIT_DECISION_AUTO_APPROVED, Some(approval.source));
+        if !requires_approval && tool.metadata.requires_approval && approval.source == "rule" {
+            let rule_id = approval.rule_id.clone().unwrap_or_default();
+            record_step_approval(
+                &self.db,
+                &mut self.session,
+                step_id,
+                ApprovalDecision::AutoApproved {
+                    reason: format!("Allowed by approval rule {rule_id}"),
+                },
+                None,
+                approval.rule_id.clone(),
+            );
+        }
+
+        // An "ask" rule is the user's explicit choice, so an unattended run cannot approve the
+        // call on their behalf even under the Allow policy.
+        let ask_rule_id = approval
+            .rule_id
+            .clone()
+            .filter(|_| requires_approval && approval.source == "rule");
+        let unattended_policy = match self.unattended_approval {
+            Some(_) if ask_rule_id.is_some() => Some(UnattendedApprovalPolicy::Deny),
+            policy => policy.filter(|_| requires_approval),
+        };
+        match unattended_policy {
+            Some(UnattendedApprovalPolicy::Deny) => {
+                let timestamp_ms = Utc::now().timestamp_millis();
+                let denied_by = if ask_rule_id.is_some() {
+                    "rule"
+                } else {
+                    "unattended"
+                };
+                log::info!(
+                    "[tool] denied by unattended policy: tool={} execution_id={} denied_by={} rule_id={} iteration={} session_id={} conversation_id={} message_id={}",
                     tool_name,
-                    err
+                    execution_id,
+                    denied_by,
+                    ask_rule_id.as_deref().unwrap_or(""),
+                    iteration,
+                    self.session.id,
+                    self.session.conversation_id,
+                    self.assistant_message_id
+                );
+                self.event_bus.publish(AgentEvent::new_with_timestamp(
+                    EVENT_TOOL_EXECUTION_DENIED,
+                    json!({
+                        "execution_id": execution_id.clone(),
+                        "approval_id": null,
+                        "tool_name": tool_name,
+                        "iteration": iteration,
+                        "approval_source": denied_by,
+                        "approval_rule_id": ask_rule_id.clone(),
+                        "conversation_id": self.session.conversation_id,
+                        "message_id": self.assistant_message_id,
+                        "timestamp_ms": timestamp_ms,
+                    }),
+                    timestamp_ms,
+                ));
+                record_step_approval(
+                    &self.db,
+                    &mut self.session,
+                    step_id,
+                    ApprovalDecision::Denied,
+                    None,
+                    ask_rule_id.clone(),
                 );
-                tool.metadata.requires_approval
+                self.audit_denial(
+                    tool,
+                    &execution_id,
+                    &args,
+                    AUDIT_DECISION_DENIED,
+                    Some(denied_by),
+                    ask_rule_id.as_deref(),
+                );
+                let message = if ask_rule_id.is_some() {
+                    "An approval rule requires asking the user, which is not possible in an unattended run"
+                } else {
+                    "Tool requires approval, which is not available in an unattended run"
+                };
+                return Ok(self.denied_step_result(
+                    step_id,
+                    execution_id,
+                    tool_name,
+                    args,
+                    iteration,
+                    ToolError::permission(message).with_hint(
+                        "Skip this action and describe in your answer what would have been done.",
+                    ),
+                    timestamp_ms,
+                ));
             }
-        };
+            Some(UnattendedApprovalPolicy::Allow) => {
+                record_step_approval(
+                    &self.db,
+                    &mut self.session,
+                    step_id,
+                    ApprovalDecision::AutoApproved {
+                        reason: "Allowed by unattended run policy".to_string(),
+                    },
+                    None,
+                    None,
+                );
+                audit_decision = (AUDIT_DECISION_AUTO_APPROVED, Some("unattended"));
+            }
+            None => {}
+        }
 
-        if requires_approval {
+        if requires_approval && unattended_policy.is_none() {
             let preview = match tool.preview.as_ref() {
                 Some(preview_fn) => Some(
-                    preview_fn(args.clone(), ToolExecutionContext).map_err(|err| err.message)?,
+                    preview_fn(
+                        args.clone(),
+                        self.tool_context(Some(&execution_id), CancellationToken::new()),
+                    )
+                    .map_err(|err| err.message)?,
                 ),
                 None => None,
             };
@@ -505,6 +692,7 @@ impl DynamicController {
                     "args": args.clone(),
                     "preview": preview,
                     "iteration": iteration,
+                    "approval_rule_id": approval.rule_id.clone(),
                     "conversation_id": self.session.conversation_id,
                     "message_id": self.assistant_message_id,
                     "timestamp_ms": timestamp_ms,
@@ -513,12 +701,12 @@ impl DynamicController {
             ));
 
             let approval_start = Instant::now();
-            let mut forced_denial_reason: Option<&'static str> = None;
+            let mut forced_denial: Option<ToolError> = None;
             let decision = loop {
                 if self.is_cancelled() {
                     let _ = self.approvals.cancel(&approval_id);
-                    forced_denial_reason = Some("Tool execution cancelled");
-                    break ToolApprovalDecision::Denied;
+                    forced_denial = Some(ToolError::cancelled("Tool execution cancelled"));
+                    break ToolApprovalDecision::Denied { feedback: None };
                 }
 
                 match approval_rx.recv_timeout(Duration::from_millis(200)) {
@@ -528,8 +716,8 @@ impl DynamicController {
                             >= self.session.config.approval_timeout_ms
                         {
                             let _ = self.approvals.cancel(&approval_id);
-                            forced_denial_reason = Some("Tool approval timed out");
-                            break ToolApprovalDecision::Denied;
+                            forced_denial = Some(ToolError::denied("Tool approval timed out"));
+                            break ToolApprovalDecision::Denied { feedback: None };
                         }
                     }
                     Err(_) => return Err("Approval channel closed".to_string()),
@@ -537,8 +725,9 @@ impl DynamicController {
             };
 
             let timestamp_ms = Utc::now().timestamp_millis();
+            let feedback = decision.feedback().map(|value| value.to_string());
             match decision {
-                ToolApprovalDecision::Approved => {
+                ToolApprovalDecision::Approved { .. } => {
                     log::info!(
                         "[tool] approval approved: tool={} execution_id={} approval_id={} iteration={} session_id={} conversation_id={} message_id={}",
                         tool_name,
@@ -556,17 +745,102 @@ impl DynamicController {
                             "approval_id": approval_id,
                             "tool_name": tool_name,
                             "iteration": iteration,
+                            "modified": false,
+                            "feedback": feedback,
+                            "conversation_id": self.session.conversation_id,
+                            "message_id": self.assistant_message_id,
+                            "timestamp_ms": timestamp_ms,
+                        }),
+                        timestamp_ms,
+                    ));
+                    record_step_approval(
+                        &self.db,
+                        &mut self.session,
+                        step_id,
+                        ApprovalDecision::Approved,
+                        feedback,
+                        approval.rule_id.clone(),
+                    );
+                    audit_decision = (AUDIT_DECISION_APPROVED, Some("user"));
+                }
+                ToolApprovalDecision::Modified {
+                    args: modified_args,
+                    ..
+                } => {
+                    let modified_args = normalize_tool_args(modified_args);
+                    self.tool_registry
+                        .validate_args(&tool.metadata, &modified_args)
+                        .map_err(|err| err.message)?;
+                    let modified_preview = match tool.preview.as_ref() {
+                        Some(preview_fn) => Some(
+                            preview_fn(
+                                modified_args.clone(),
+                                self.tool_context(Some(&execution_id), CancellationToken::new()),
+                            )
+                            .map_err(|err| err.message)?,
+                        ),
+                        None => None,
+                    };
+                    log::info!(
+                        "[tool] approval approved with modifications: tool={} execution_id={} approval_id={} iteration={} session_id={} conversation_id={} message_id={} args={}",
+                        tool_name,
+                        execution_id,
+                        approval_id,
+                        iteration,
+                        self.session.id,
+                        self.session.conversation_id,
+                        self.assistant_message_id,
+                        summarize_tool_args(&modified_args, 500)
+                    );
+                    self.event_bus.publish(AgentEvent::new_with_timestamp(
+                        EVENT_TOOL_EXECUTION_APPROVED,
+                        json!({
+                            "execution_id": execution_id.clone(),
+                            "approval_id": approval_id,
+                            "tool_name": tool_name,
+                            "iteration": iteration,
+                            "modified": true,
+                            "original_args": args.clone(),
+                            "args": modified_args.clone(),
+                            "preview": modified_preview,
+                            "feedback": feedback,
                             "conversation_id": self.session.conversation_id,
                             "message_id": self.assistant_message_id,
                             "timestamp_ms": timestamp_ms,
                         }),
                         timestamp_ms,
                     ));
+                    if let Some(step) = self
+                        .session
+                        .plan
+                        .as_mut()
+                        .and_then(|plan| plan.steps.iter_mut().find(|s| s.id == step_id))
+                    {
+                        step.action = StepAction::ToolCall {
+                            tool: tool_name.to_string(),
+                            args: modified_args.clone(),
+                        };
+                    }
+                    record_step_approval(
+                        &self.db,
+                        &mut self.session,
+                        step_id,
+                        ApprovalDecision::Modified,
+                        feedback,
+                        approval.rule_id.clone(),
+                    );
+                    audit_decision = (AUDIT_DECISION_MODIFIED, Some("user"));
+                    args = modified_args;
                 }
-                ToolApprovalDecision::Denied => {
-                    let denied_error = forced_denial_reason
-                        .unwrap_or("Tool execution denied by approval")
-                        .to_string();
+                ToolApprovalDecision::Denied { .. } => {
+                    let forced = forced_denial.is_some();
+                    let (decision, denied_by) = match forced_denial.as_ref().map(|err| err.kind) {
+                        Some(ToolErrorKind::Cancelled) => (AUDIT_DECISION_CANCELLED, None),
+                        Some(_) => (AUDIT_DECISION_TIMED_OUT, None),
+                        None => (AUDIT_DECISION_DENIED, Some("user")),
+                    };
+                    let denied_error = forced_denial
+                        .unwrap_or_else(|| ToolError::denied("Tool execution denied by approval"));
                     log::warn!(
                         "[tool] approval denied: tool={} execution_id={} approval_id={} iteration={} session_id={} conversation_id={} message_id={}",
                         tool_name,
@@ -584,45 +858,40 @@ impl DynamicController {
                             "approval_id": approval_id,
                             "tool_name": tool_name,
                             "iteration": iteration,
+                            "feedback": feedback,
                             "conversation_id": self.session.conversation_id,
                             "message_id": self.assistant_message_id,
                             "timestamp_ms": timestamp_ms,
                         }),
                         timestamp_ms,
                     ));
-                    tool_executions.push(ToolExecutionRecord {
-                        execution_id: execution_id.clone(),
-                        tool_name: tool_name.to_string(),
-                        args: args.clone(),
-                        result: None,
-                        success: false,
-                        error: Some(denied_error.clone()),
-                        duration_ms: 0,
-                        iteration: iteration as usize,
+                    if !forced {
+                        record_step_approval(
+                            &self.db,
+                            &mut self.session,
+                            step_id,
+                            ApprovalDecision::Denied,
+                            feedback,
+                            approval.rule_id.clone(),
+                        );
+                    }
+                    self.audit_denial(
+                        tool,
+                        &execution_id,
+                        &args,
+                        decision,
+                        denied_by,
+                        approval.rule_id.as_deref(),
+                    );
+                    return Ok(self.denied_step_result(
+                        step_id,
+                        execution_id,
+                        tool_name,
+                        args,
+                        iteration,
+                        denied_error,
                         timestamp_ms,
-                    });
-                    self.pending_tool_executions
-                        .push(MessageToolExecutionInput {
-                            id: execution_id,
-                            message_id: self.assistant_message_id.clone(),
-                            tool_name: tool_name.to_string(),
-                            parameters: args,
-                            result: json!(null),
-                            success: false,
-                            duration_ms: 0,
-                            timestamp_ms,
-                            error: Some(denied_error.clone()),
-                            iteration_number: iteration as i64,
-                        });
-                    return Ok(StepResult {
-                        step_id: step_id.to_string(),
-                        success: false,
-                        output: None,
-                        error: Some(denied_error),
-                        tool_executions,
-                        duration_ms: 0,
-                        completed_at: Utc::now(),
-                    });
+                    ));
                 }
             }
         }
@@ -652,6 +921,8 @@ impl DynamicController {
                 "tool_name": tool_name,
                 "args": args.clone(),
                 "requires_approval": requires_approval,
+                "approval_source": approval.source,
+                "approval_rule_id": approval.rule_id.clone(),
                 "iteration": self.tool_calls_in_current_step,
                 "conversation_id": self.session.conversation_id,
                 "message_id": self.assistant_message_id,
@@ -661,12 +932,50 @@ impl DynamicController {
         ));
 
         let start = Instant::now();
-        let result = self.execute_tool_with_timeout(tool, args.clone());
+        let mut attempts = 0u32;
+        let result = loop {
+            attempts += 1;
+            let outcome = if tool_name == DELEGATE_TOOL_NAME {
+                self.run_delegate(call_llm, step_id, &execution_id, &args)
+            } else {
+                self.execute_tool_with_timeout(tool, args.clone(), &execution_id)
+            };
+            match outcome {
+                Err(err)
+                    if attempts <= self.session.config.tool_max_retries
+                        && should_auto_retry(&err, tool.metadata.requires_approval) =>
+                {
+                    let delay = retry_delay(&err, attempts);
+                    log::warn!(
+                        "[tool] retrying after transient failure: tool={} execution_id={} attempt={} kind={} delay_ms={} error={}",
+                        tool_name,
+                        execution_id,
+                        attempts,
+                        err.kind.as_str(),
+                        delay.as_millis(),
+                        err.message
+                    );
+                    (self.progress_sink(tool_name, &execution_id))(ToolProgress {
+                        message: format!("Retrying after {}: {}", err.kind.as_str(), err.message),
+                        current: Some(attempts as u64),
+                        total: Some(self.session.config.tool_max_retries as u64 + 1),
+                        data: Some(json!({ "retry": true, "error_kind": err.kind.as_str() })),
+                    });
+                    if !self.sleep_unless_cancelled(delay) {
+                        break Err(ToolError::cancelled("Tool execution cancelled"));
+                    }
+                }
+                other => break other,
+            }
+        };
         let duration_ms = start.elapsed().as_millis() as i64;
         let completed_at = Utc::now();
         let timestamp_ms = completed_at.timestamp_millis();
+        let mut failure: Option<ToolError> = None;
+        let mut resource_id = None;
         let (success, output, error) = match result {
             Ok(output_value) => {
+                resource_id = external_resource_id(&output_value);
                 let output_chars = value_char_len(&output_value);
                 let persist_output =
                     should_persist_tool_output(tool_name, &tool.metadata.result_mode, output_chars);
@@ -718,14 +1027,25 @@ impl DynamicController {
                     }
                 }
             }
-            Err(error_message) => {
-                let message = json!({
-                    "message": error_message,
-                    "success": false
-                });
+            Err(err) => {
+                let message = err.to_value();
+                let error_message = err.message.clone();
+                failure = Some(err);
                 (false, Some(message), Some(error_message))
             }
         };
+        let error_kind = if success {
+            None
+        } else {
+            Some(
+                failure
+                    .as_ref()
+                    .map(|err| err.kind)
+                    .unwrap_or(ToolErrorKind::Internal)
+                    .as_str()
+                    .to_string(),
+            )
+        };
 
         if success {
             let result_for_event = output.clone().unwrap_or_else(|| json!(null));
@@ -774,6 +1094,10 @@ impl DynamicController {
                     "tool_name": tool_name,
                     "success": false,
                     "error": error_message,
+                    "error_kind": error_kind.clone(),
+                    "retryable": failure.as_ref().map(|err| err.retryable),
+                    "hint": failure.as_ref().and_then(|err| err.hint.clone()),
+                    "attempts": attempts,
                     "duration_ms": duration_ms,
                     "iteration": self.tool_calls_in_current_step,
                     "conversation_id": self.session.conversation_id,
@@ -784,6 +1108,29 @@ impl DynamicController {
             ));
         }
 
+        self.audit_tool_call(
+            tool,
+            ToolAuditRecord {
+                execution_id: &execution_id,
+                tool_name,
+                args: &args,
+                decision: audit_decision.0,
+                approved_by: audit_decision.1,
+                approval_rule_id: approval.rule_id.as_deref(),
+                outcome: if success {
+                    AUDIT_OUTCOME_SUCCESS
+                } else {
+                    AUDIT_OUTCOME_ERROR
+                },
+                error: error.as_deref(),
+                error_kind: error_kind.as_deref(),
+                external_resource_id: resource_id,
+                conversation_id: Some(&self.session.conversation_id),
+                message_id: Some(&self.assistant_message_id),
+                duration_ms,
+            },
+        );
+
         tool_executions.push(ToolExecutionRecord {
             execution_id: execution_id.clone(),
             tool_name: tool_name.to_string(),
@@ -791,6 +1138,8 @@ impl DynamicController {
             result: output.clone(),
             success,
             error: error.clone(),
+            error_kind: error_kind.clone(),
+            attempts,
             duration_ms,
             iteration: self.tool_calls_in_current_step as usize,
             timestamp_ms,
@@ -807,6 +1156,7 @@ impl DynamicController {
                 duration_ms,
                 timestamp_ms,
                 error: error.clone(),
+                error_kind: error_kind.clone(),
                 iteration_number: self.tool_calls_in_current_step as i64,
             });
 
@@ -815,38 +1165,139 @@ impl DynamicController {
             success,
             output,
             error,
+            error_kind,
             tool_executions,
             duration_ms,
             completed_at,
         })
     }
 
+    /// Adds the call to the audit log if the tool has side effects.
+    fn audit_tool_call(&self, tool: &ToolDefinition, record: ToolAuditRecord<'_>) {
+        if tool.metadata.requires_approval {
+            record_tool_audit(&self.db, record);
+        }
+    }
+
+    fn audit_denial(
+        &self,
+        tool: &ToolDefinition,
+        execution_id: &str,
+        args: &Value,
+        decision: &str,
+        denied_by: Option<&str>,
+        approval_rule_id: Option<&str>,
+    ) {
+        self.audit_tool_call(
+            tool,
+            ToolAuditRecord {
+                execution_id,
+                tool_name: &tool.metadata.name,
+                args,
+                decision,
+                approved_by: denied_by,
+                approval_rule_id,
+                outcome: AUDIT_OUTCOME_NOT_EXECUTED,
+                error: None,
+                error_kind: None,
+                external_resource_id: None,
+                conversation_id: Some(&self.session.conversation_id),
+                message_id: Some(&self.assistant_message_id),
+                duration_ms: 0,
+            },
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn denied_step_result(
+        &mut self,
+        step_id: &str,
+        execution_id: String,
+        tool_name: &str,
+        args: Value,
+        iteration: u32,
+        denied_error: ToolError,
+        timestamp_ms: i64,
+    ) -> StepResult {
+        let error_kind = Some(denied_error.kind.as_str().to_string());
+        let record = ToolExecutionRecord {
+            execution_id: execution_id.clone(),
+            tool_name: tool_name.to_string(),
+            args: args.clone(),
+            result: Some(denied_error.to_value()),
+            success: false,
+            error: Some(denied_error.message.clone()),
+            error_kind: error_kind.clone(),
+            attempts: 0,
+            duration_ms: 0,
+            iteration: iteration as usize,
+            timestamp_ms,
+        };
+        self.pending_tool_executions
+            .push(MessageToolExecutionInput {
+                id: execution_id,
+                message_id: self.assistant_message_id.clone(),
+                tool_name: tool_name.to_string(),
+                parameters: args,
+                result: denied_error.to_value(),
+                success: false,
+                duration_ms: 0,
+                timestamp_ms,
+                error: Some(denied_error.message.clone()),
+                error_kind: error_kind.clone(),
+                iteration_number: iteration as i64,
+            });
+        StepResult {
+            step_id: step_id.to_string(),
+            success: false,
+            output: Some(denied_error.to_value()),
+            error: Some(denied_error.message),
+            error_kind,
+            tool_executions: vec![record],
+            duration_ms: 0,
+            completed_at: Utc::now(),
+        }
+    }
+
     fn execute_tool_with_timeout(
         &self,
         tool: &crate::tools::ToolDefinition,
         args: Value,
-    ) -> Result<Value, String> {
+        execution_id: &str,
+    ) -> Result<Value, ToolError> {
+        let cancellation = CancellationToken::with_parent(self.cancel_flag.clone());
+        let ctx = self
+            .tool_context(Some(execution_id), cancellation.clone())
+            .with_progress(self.progress_sink(&tool.metadata.name, execution_id));
         let timeout_ms = self.session.config.tool_execution_timeout_ms;
         if timeout_ms == 0 {
-            return (tool.handler)(args, ToolExecutionContext).map_err(|err| err.message);
+            return (tool.handler)(args, ctx);
         }
 
         let handler = tool.handler.clone();
         let (tx, rx) = mpsc::channel();
         std::thread::spawn(move || {
-            let _ = tx.send((handler)(args, ToolExecutionContext));
+            let _ = tx.send((handler)(args, ctx));
         });
 
         let timeout = Duration::from_millis(timeout_ms);
         let started = Instant::now();
         loop {
             if self.is_cancelled() {
-                return Err("Tool execution cancelled".to_string());
+                cancellation.cancel();
+                self.wait_for_cancelled_worker(&rx, &tool.metadata.name);
+                return Err(ToolError::cancelled("Tool execution cancelled"));
             }
 
             let elapsed = started.elapsed();
             if elapsed >= timeout {
-                return Err(format!("Tool execution timed out after {timeout_ms} ms"));
+                cancellation.cancel();
+                self.wait_for_cancelled_worker(&rx, &tool.metadata.name);
+                // The worker may still be finishing side effects, so this is not auto-retried.
+                return Err(ToolError::timeout(format!(
+                    "Tool execution timed out after {timeout_ms} ms"
+                ))
+                .retryable(false));
             }
             let remaining = timeout.saturating_sub(elapsed);
             let wait_for = if remaining > Duration::from_millis(200) {
@@ -856,15 +1307,224 @@ impl DynamicController {
             };
 
             match rx.recv_timeout(wait_for) {
-                Ok(result) => return result.map_err(|err| err.message),
+                Ok(result) => return result,
                 Err(mpsc::RecvTimeoutError::Timeout) => continue,
                 Err(mpsc::RecvTimeoutError::Disconnected) => {
-                    return Err("Tool execution worker disconnected".to_string());
+                    return Err(ToolError::new("Tool execution worker disconnected"));
                 }
             }
         }
     }
 
+    /// Gives a cancelled tool a short window to observe its token and return, so the worker
+    /// thread does not keep doing work in the background.
+    fn wait_for_cancelled_worker<T>(&self, rx: &mpsc::Receiver<T>, tool_name: &str) {
+        match rx.recv_timeout(Duration::from_millis(TOOL_CANCEL_GRACE_MS)) {
+            Ok(_) | Err(mpsc::RecvTimeoutError::Disconnected) => {}
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                log::warn!(
+                    "[tool] worker still running after cancellation: tool={} session_id={}",
+                    tool_name,
+                    self.session.id
+                );
+            }
+        }
+    }
+
+    /// Runs `agent.delegate` as a child controller with its own session, tool subset and limits.
+    /// It runs inline rather than on a worker thread because it drives the parent's LLM client.
+    fn run_delegate<F>(
+        &self,
+        call_llm: &mut F,
+        step_id: &str,
+        execution_id: &str,
+        args: &Value,
+    ) -> Result<Value, ToolError>
+    where
+        F: FnMut(&[LlmMessage], Option<&str>, Option<Value>) -> Result<StreamResult, String>,
+    {
+        if self.depth >= MAX_DELEGATION_DEPTH {
+            return Err(ToolError::permission("Sub-agents cannot delegate further")
+                .with_hint("Do the work directly with the tools you have."));
+        }
+        let task = args
+            .get("task")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| ToolError::validation("Missing 'task'"))?;
+        let context = args
+            .get("context")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let patterns = args
+            .get("tools")
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let max_result_chars = args
+            .get("max_result_chars")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .unwrap_or(DELEGATE_DEFAULT_RESULT_CHARS);
+
+        let mut registry = self.tool_registry.restricted(&patterns);
+        registry.remove(DELEGATE_TOOL_NAME);
+        let mut tool_names = registry
+            .list_metadata()
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect::<Vec<_>>();
+        tool_names.sort();
+
+        let mut config = self.session.config.clone();
+        config.max_total_llm_turns = args
+            .get("max_turns")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+            .unwrap_or(DELEGATE_DEFAULT_MAX_TURNS)
+            .min(self.session.config.max_total_llm_turns);
+        if let Some(max_calls) = args.get("max_tool_calls_per_step").and_then(|v| v.as_u64()) {
+            config.max_tool_calls_per_step = max_calls as u32;
+        }
+
+        let mut scope = serde_json::Map::new();
+        scope.insert("parent_session_id".to_string(), json!(self.session.id));
+        scope.insert("parent_step_id".to_string(), json!(step_id));
+        scope.insert("parent_execution_id".to_string(), json!(execution_id));
+        scope.insert("agent_depth".to_string(), json!(self.depth + 1));
+
+        let now = Utc::now();
+        let session = AgentSession {
+            id: Uuid::new_v4().to_string(),
+            conversation_id: self.session.conversation_id.clone(),
+            message_id: self.session.message_id.clone(),
+            parent_session_id: Some(self.session.id.clone()),
+            parent_step_id: Some(step_id.to_string()),
+            phase: PhaseKind::Controller,
+            plan: None,
+            gathered_info: Vec::new(),
+            step_results: Vec::new(),
+            config,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+        };
+        AgentSessionOperations::save_agent_session(&self.db, &session)
+            .map_err(|e| ToolError::new(format!("Failed to create sub-agent session: {e}")))?;
+        let child_session_id = session.id.clone();
+
+        let mut child = DynamicController {
+            db: self.db.clone(),
+            event_bus: self.event_bus.scoped(scope),
+            tool_registry: registry,
+            approvals: self.approvals.clone(),
+            cancel_flag: self.cancel_flag.clone(),
+            session,
+            messages: Vec::new(),
+            base_system_prompt: self.base_system_prompt.clone(),
+            assistant_message_id: self.assistant_message_id.clone(),
+            pending_tool_executions: Vec::new(),
+            last_step_result: None,
+            tool_calls_in_current_step: 0,
+            requested_user_input: false,
+            depth: self.depth + 1,
+            unattended_approval: self.unattended_approval,
+        };
+
+        let task_text = match context {
+            Some(context) => format!("{task}\n\nContext from the parent agent:\n{context}"),
+            None => task.to_string(),
+        };
+        log::info!(
+            "[agent] delegating subtask: parent_session_id={} child_session_id={} step_id={} tools={}",
+            self.session.id,
+            child_session_id,
+            step_id,
+            tool_names.join(",")
+        );
+        let outcome = child.run(&task_text, call_llm);
+        let tool_calls = child.take_tool_executions().len();
+
+        match outcome {
+            Ok(response) => {
+                let (result, truncated) = truncate_chars(&response, max_result_chars);
+                Ok(json!({
+                    "session_id": child_session_id,
+                    "success": true,
+                    "result": result,
+                    "truncated": truncated,
+                    "needs_input": child.requested_user_input(),
+                    "tool_calls": tool_calls,
+                    "tools": tool_names,
+                }))
+            }
+            Err(error) if error == "Cancelled" => {
+                Err(ToolError::cancelled("Tool execution cancelled"))
+            }
+            Err(error) => Err(ToolError::new(format!("Sub-agent failed: {error}"))
+                .with_hint("Break the task into smaller pieces or handle it directly.")),
+        }
+    }
+
+    /// Sleeps in short slices so a cancel during backoff is noticed. Returns false if cancelled.
+    fn sleep_unless_cancelled(&self, delay: Duration) -> bool {
+        let deadline = Instant::now() + delay;
+        while Instant::now() < deadline {
+            if self.is_cancelled() {
+                return false;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            std::thread::sleep(remaining.min(Duration::from_millis(200)));
+        }
+        !self.is_cancelled()
+    }
+
+    fn tool_context(
+        &self,
+        execution_id: Option<&str>,
+        cancellation: CancellationToken,
+    ) -> ToolExecutionContext {
+        let mut ctx = ToolExecutionContext::new(cancellation);
+        ctx.conversation_id = Some(self.session.conversation_id.clone());
+        ctx.message_id = Some(self.assistant_message_id.clone());
+        ctx.session_id = Some(self.session.id.clone());
+        ctx.execution_id = execution_id.map(str::to_string);
+        ctx
+    }
+
+    fn progress_sink(&self, tool_name: &str, execution_id: &str) -> Arc<ToolProgressSink> {
+        let event_bus = self.event_bus.clone();
+        let tool_name = tool_name.to_string();
+        let execution_id = execution_id.to_string();
+        let conversation_id = self.session.conversation_id.clone();
+        let message_id = self.assistant_message_id.clone();
+        Arc::new(move |progress: ToolProgress| {
+            let timestamp_ms = Utc::now().timestamp_millis();
+            event_bus.publish(AgentEvent::new_with_timestamp(
+                EVENT_TOOL_EXECUTION_PROGRESS,
+                json!({
+                    "execution_id": execution_id,
+                    "tool_name": tool_name,
+                    "message": progress.message,
+                    "current": progress.current,
+                    "total": progress.total,
+                    "data": progress.data,
+                    "conversation_id": conversation_id,
+                    "message_id": message_id,
+                    "timestamp_ms": timestamp_ms,
+                }),
+                timestamp_ms,
+            ));
+        })
+    }
+
     fn call_think<F>(&mut self, call_llm: &mut F, prompt: &str) -> Result<String, String>
     where
         F: FnMut(&[LlmMessage], Option<&str>, Option<Value>) -> Result<StreamResult, String>,
@@ -1003,6 +1663,14 @@ impl DynamicController {
             for step in plan.steps.iter().rev().take(3) {
                 let status = format!("{:?}", step.status);
                 lines.push(format!("- {} [{}]", step.description, status));
+                if let Some(approval) = step.approval.as_ref() {
+                    if approval.decision == ApprovalDecision::Modified {
+                        lines.push("  approval: user edited the tool args".to_string());
+                    }
+                    if let Some(feedback) = approval.feedback.as_ref() {
+                        lines.push(format!("  user feedback: {}", feedback));
+                    }
+                }
                 if let Some(result) = step.result.as_ref() {
                     if let Some(output) = result.output.as_ref() {
                         lines.push(format!("  result: {}", output));
@@ -1048,6 +1716,15 @@ impl DynamicController {
         )
     }
 
+    fn step_approval_feedback(&self, step_id: &str) -> Option<&str> {
+        self.session
+            .plan
+            .as_ref()
+            .and_then(|plan| plan.steps.iter().find(|step| step.id == step_id))
+            .and_then(|step| step.approval.as_ref())
+            .and_then(|approval| approval.feedback.as_deref())
+    }
+
     fn is_cancelled(&self) -> bool {
         self.cancel_flag.load(Ordering::Relaxed)
     }
@@ -1084,6 +1761,11 @@ impl DynamicController {
     pub fn requested_user_input(&self) -> bool {
         self.requested_user_input
     }
+
+    /// Resolves approval prompts with `policy` instead of waiting for the user.
+    pub fn set_unattended_approval(&mut self, policy: UnattendedApprovalPolicy) {
+        self.unattended_approval = Some(policy);
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -1151,6 +1833,32 @@ enum StepExecutionOutcome {
     Complete(String),
 }
 
+fn record_step_approval(
+    db: &crate::db::Db,
+    session: &mut AgentSession,
+    step_id: &str,
+    decision: ApprovalDecision,
+    feedback: Option<String>,
+    rule_id: Option<String>,
+) {
+    let approval = StepApproval {
+        decision,
+        feedback,
+        rule_id,
+        decided_at: Utc::now(),
+    };
+    if let Err(err) = AgentSessionOperations::save_step_approval(db, step_id, &approval) {
+        log::warn!("Failed to save step approval for {}: {}", step_id, err);
+    }
+    if let Some(step) = session
+        .plan
+        .as_mut()
+        .and_then(|plan| plan.steps.iter_mut().find(|s| s.id == step_id))
+    {
+        step.approval = Some(approval);
+    }
+}
+
 fn default_resume_target() -> ResumeTarget {
     ResumeTarget::Reflecting
 }
@@ -1384,6 +2092,19 @@ fn should_persist_tool_output(
     }
 }
 
+/// Tools that need approval usually have side effects, so only retry them when the upstream
+/// rejected the call outright (rate limited) and nothing could have been applied.
+fn should_auto_retry(err: &ToolError, requires_approval: bool) -> bool {
+    err.retryable && (!requires_approval || err.kind == ToolErrorKind::RateLimited)
+}
+
+fn retry_delay(err: &ToolError, attempt: u32) -> Duration {
+    let backoff_ms = err.retry_after_ms.unwrap_or_else(|| {
+        TOOL_RETRY_BASE_DELAY_MS.saturating_mul(1 << attempt.min(6).saturating_sub(1))
+    });
+    Duration::from_millis(backoff_ms.min(TOOL_RETRY_MAX_DELAY_MS))
+}
+
 fn value_char_len(value: &Value) -> usize {
     serde_json::to_string(value)
         .map(|text| text.chars().count())
@@ -1449,3 +2170,94 @@ fn value_to_string(value: &serde_json::Value) -> String {
 
     value.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DynamicController, UnattendedApprovalPolicy};
+    use crate::db::{
+        AuditLogOperations, AuditLogQuery, ConversationOperations, CreateToolApprovalRuleInput, Db,
+        ToolApprovalRuleOperations,
+    };
+    use crate::events::EventBus;
+    use crate::llm::{LlmMessage, StreamResult};
+    use crate::tools::{
+        ApprovalStore, ToolDefinition, ToolExecutionContext, ToolMetadata, ToolRegistry,
+        ToolResultMode, RULE_SCOPE_GLOBAL,
+    };
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    #[test]
+    fn ask_rules_are_not_overridden_by_the_unattended_allow_policy() {
+        let db_path = std::env::temp_dir().join(format!("orchestrator-{}.db", Uuid::new_v4()));
+        let mut db = Db::new(db_path.to_str().unwrap()).expect("db init failed");
+        db.run_migrations().expect("db migrations failed");
+        db.get_or_create_conversation("conv-1").unwrap();
+        let rule = db
+            .create_tool_approval_rule(&CreateToolApprovalRuleInput {
+                name: None,
+                tool_pattern: "test.write".to_string(),
+                arg_conditions: Vec::new(),
+                action: "ask".to_string(),
+                scope: RULE_SCOPE_GLOBAL.to_string(),
+                conversation_id: None,
+                session_id: None,
+                expires_at: None,
+            })
+            .unwrap();
+
+        let ran = Arc::new(AtomicBool::new(false));
+        let handler_ran = ran.clone();
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(ToolDefinition {
+                metadata: ToolMetadata {
+                    name: "test.write".to_string(),
+                    description: "Writes something".to_string(),
+                    args_schema: json!({ "type": "object" }),
+                    result_schema: json!({ "type": "object" }),
+                    requires_approval: true,
+                    result_mode: ToolResultMode::Inline,
+                },
+                handler: Arc::new(move |_args: Value, _ctx: ToolExecutionContext| {
+                    handler_ran.store(true, Ordering::SeqCst);
+                    Ok(json!({}))
+                }),
+                preview: None,
+            })
+            .unwrap();
+
+        let mut controller = DynamicController::new(
+            db.clone(),
+            EventBus::new(),
+            registry,
+            ApprovalStore::new(),
+            Arc::new(AtomicBool::new(false)),
+            Vec::new(),
+            None,
+            "conv-1".to_string(),
+            "msg-1".to_string(),
+            "assistant-1".to_string(),
+        )
+        .unwrap();
+        controller.set_unattended_approval(UnattendedApprovalPolicy::Allow);
+        let mut call_llm = |_: &[LlmMessage], _: Option<&str>, _: Option<Value>| {
+            Err::<StreamResult, String>("not used".to_string())
+        };
+
+        let result = controller
+            .execute_tool(&mut call_llm, "step-1", "test.write", json!({}))
+            .unwrap();
+        assert!(!result.success);
+        assert!(!ran.load(Ordering::SeqCst));
+        assert_eq!(result.error_kind.as_deref(), Some("permission"));
+
+        let audit = db.query_audit_log(&AuditLogQuery::default()).unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].decision, "denied");
+        assert_eq!(audit[0].approved_by.as_deref(), Some("rule"));
+        assert_eq!(audit[0].approval_rule_id.as_deref(), Some(rule.id.as_str()));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::agent::prompts::RESPONDER_PROMPT;
-use crate::agent::DynamicController;
+use crate::agent::{DynamicController, UnattendedApprovalPolicy};
 use crate::db::{
     BranchOperations, ConversationOperations, CustomBackendOperations, Db, IncomingAttachment,
     MessageAttachment, MessageOperations, MessageToolExecution, MessageToolExecutionInput,
@@ -25,6 +25,7 @@ use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
 use std::time::Duration;
 use tauri::State;
 use uuid::Uuid;
@@ -376,6 +377,20 @@ pub struct AgentGenerateTitleResult {
     pub title: String,
 }
 
+/// Options for runs started outside the chat UI, such as scheduled jobs.
+#[derive(Debug, Default, Clone)]
+pub struct AgentRunOptions {
+    pub unattended_approval: Option<UnattendedApprovalPolicy>,
+}
+
+/// What an agent run produced, returned by the worker thread once it finishes.
+#[derive(Debug, Clone, Default)]
+pub struct AgentRunOutcome {
+    pub response: String,
+    pub error: Option<String>,
+    pub cancelled: bool,
+}
+
 #[tauri::command(rename_all = "snake_case")]
 pub fn agent_send_message(
     state: State<'_, Db>,
@@ -384,6 +399,27 @@ pub fn agent_send_message(
     approvals: State<'_, ApprovalStore>,
     payload: AgentSendMessagePayload,
 ) -> Result<AgentSendMessageResult, String> {
+    let (result, _worker) = start_agent_run(
+        state.inner(),
+        event_bus.inner(),
+        tool_registry.inner(),
+        approvals.inner(),
+        payload,
+        AgentRunOptions::default(),
+    )?;
+    Ok(result)
+}
+
+/// Saves the user message and runs the agent on a worker thread. The returned handle
+/// yields the run outcome; the chat command detaches it, background jobs join it.
+pub fn start_agent_run(
+    state: &Db,
+    event_bus: &EventBus,
+    tool_registry: &ToolRegistry,
+    approvals: &ApprovalStore,
+    payload: AgentSendMessagePayload,
+    options: AgentRunOptions,
+) -> Result<(AgentSendMessageResult, JoinHandle<AgentRunOutcome>), String> {
     let AgentSendMessagePayload {
         conversation_id,
         model,
@@ -398,11 +434,11 @@ pub fn agent_send_message(
     } = payload;
 
     let conversation_id = conversation_id.unwrap_or_else(|| Uuid::new_v4().to_string());
-    ConversationOperations::get_or_create_conversation(&*state, &conversation_id)
+    ConversationOperations::get_or_create_conversation(state, &conversation_id)
         .map_err(|e| e.to_string())?;
 
     let user_message_id = MessageOperations::save_message(
-        &*state,
+        state,
         &conversation_id,
         "user",
         &content,
@@ -428,9 +464,9 @@ pub fn agent_send_message(
     let assistant_message_id = assistant_message_id.unwrap_or_else(|| Uuid::new_v4().to_string());
 
     let history =
-        MessageOperations::get_messages(&*state, &conversation_id).map_err(|e| e.to_string())?;
+        MessageOperations::get_messages(state, &conversation_id).map_err(|e| e.to_string())?;
 
-    let main_branch = BranchOperations::get_or_create_main_branch(&*state, &conversation_id)
+    let main_branch = BranchOperations::get_or_create_main_branch(state, &conversation_id)
         .map_err(|e| e.to_string())?;
 
     let parent_message_id = history
@@ -441,7 +477,7 @@ pub fn agent_send_message(
         .map(|message| message.id.clone());
 
     let _ = BranchOperations::create_message_tree_node(
-        &*state,
+        state,
         &user_message_id,
         parent_message_id.as_deref(),
         &main_branch.id,
@@ -479,7 +515,7 @@ pub fn agent_send_message(
     let model = model.clone();
     match provider.as_str() {
         "openai" | "anthropic" | "deepseek" => {
-            let api_key = ModelOperations::get_api_key(&*state, &provider)
+            let api_key = ModelOperations::get_api_key(state, &provider)
                 .map_err(|e| e.to_string())?
                 .unwrap_or_default();
             if api_key.is_empty() {
@@ -490,7 +526,7 @@ pub fn agent_send_message(
             let backend_id = custom_backend_id
                 .clone()
                 .ok_or_else(|| "Custom provider requires custom_backend_id".to_string())?;
-            let backend = CustomBackendOperations::get_custom_backend_by_id(&*state, &backend_id)
+            let backend = CustomBackendOperations::get_custom_backend_by_id(state, &backend_id)
                 .map_err(|e| e.to_string())?;
             if backend.is_none() {
                 return Err("Custom backend not found".to_string());
@@ -500,8 +536,8 @@ pub fn agent_send_message(
         _ => {}
     }
 
-    let db = state.inner().clone();
-    let bus = event_bus.inner().clone();
+    let db = state.clone();
+    let bus = event_bus.clone();
     let custom_backend_id = custom_backend_id.clone();
     let system_prompt_for_thread = system_prompt.clone();
     let conversation_id_for_thread = conversation_id.clone();
@@ -509,11 +545,12 @@ pub fn agent_send_message(
     let model_for_thread = model.clone();
     let main_branch_id_for_thread = main_branch.id.clone();
     let user_message_id_for_thread = user_message_id.clone();
-    let tool_registry_for_thread = tool_registry.inner().clone();
-    let approvals_for_thread = approvals.inner().clone();
+    let tool_registry_for_thread = tool_registry.clone();
+    let approvals_for_thread = approvals.clone();
     let cancel_token_for_thread = register_cancel_token(&assistant_message_id);
+    let unattended_approval = options.unattended_approval;
 
-    std::thread::spawn(move || {
+    let worker = std::thread::spawn(move || {
         let panic_bus = bus.clone();
         let panic_conversation_id = conversation_id_for_thread.clone();
         let panic_message_id = assistant_message_id_for_thread.clone();
@@ -531,6 +568,7 @@ pub fn agent_send_message(
             let mut controller_cache_diagnostics = CacheDiagnostics::default();
             let mut responder_cache_diagnostics = CacheDiagnostics::default();
             let mut requested_user_input = false;
+            let mut run_error: Option<String> = None;
             let openai_api_key = ModelOperations::get_api_key(&db, "openai")
                 .ok()
                 .flatten()
@@ -703,9 +741,15 @@ pub fn agent_send_message(
                 user_message_id_for_thread.clone(),
                 assistant_message_id_for_thread.clone(),
             ) {
-                Ok(controller) => Some(controller),
+                Ok(mut controller) => {
+                    if let Some(policy) = unattended_approval {
+                        controller.set_unattended_approval(policy);
+                    }
+                    Some(controller)
+                }
                 Err(error) => {
                     draft = format!("Agent setup error: {}", error);
+                    run_error = Some(error);
                     None
                 }
             };
@@ -722,6 +766,7 @@ pub fn agent_send_message(
                             draft.clear();
                         } else {
                             draft = format!("Agent error: {}", error);
+                            run_error = Some(error);
                         }
                     }
                 }
@@ -1091,47 +1136,65 @@ pub fn agent_send_message(
                 }
             }
 
+            if cancelled {
+                final_response.clear();
+            }
             let timestamp_ms = Utc::now().timestamp_millis();
             bus.publish(AgentEvent::new_with_timestamp(
                 EVENT_ASSISTANT_STREAM_COMPLETED,
                 json!({
                     "conversation_id": conversation_id_for_thread,
                     "message_id": assistant_message_id_for_thread,
-                    "content": if cancelled { String::new() } else { final_response },
+                    "content": final_response,
                     "timestamp_ms": timestamp_ms
                 }),
                 timestamp_ms,
             ));
 
             remove_cancel_token(&assistant_message_id_for_thread);
+            AgentRunOutcome {
+                response: final_response,
+                error: run_error,
+                cancelled,
+            }
         }));
 
-        if worker_result.is_err() {
-            log::error!(
-                "[agent] worker panicked: conversation_id={} message_id={}",
-                panic_conversation_id,
-                panic_message_id
-            );
-            let timestamp_ms = Utc::now().timestamp_millis();
-            panic_bus.publish(AgentEvent::new_with_timestamp(
-                EVENT_ASSISTANT_STREAM_COMPLETED,
-                json!({
-                    "conversation_id": panic_conversation_id,
-                    "message_id": panic_message_id,
-                    "content": "Agent error: internal worker panic",
-                    "timestamp_ms": timestamp_ms
-                }),
-                timestamp_ms,
-            ));
-            remove_cancel_token(&panic_message_id);
+        match worker_result {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                log::error!(
+                    "[agent] worker panicked: conversation_id={} message_id={}",
+                    panic_conversation_id,
+                    panic_message_id
+                );
+                let timestamp_ms = Utc::now().timestamp_millis();
+                panic_bus.publish(AgentEvent::new_with_timestamp(
+                    EVENT_ASSISTANT_STREAM_COMPLETED,
+                    json!({
+                        "conversation_id": panic_conversation_id,
+                        "message_id": panic_message_id,
+                        "content": "Agent error: internal worker panic",
+                        "timestamp_ms": timestamp_ms
+                    }),
+                    timestamp_ms,
+                ));
+                remove_cancel_token(&panic_message_id);
+                AgentRunOutcome {
+                    error: Some("internal worker panic".to_string()),
+                    ..AgentRunOutcome::default()
+                }
+            }
         }
     });
 
-    Ok(AgentSendMessageResult {
-        conversation_id,
-        user_message_id,
-        assistant_message_id,
-    })
+    Ok((
+        AgentSendMessageResult {
+            conversation_id,
+            user_message_id,
+            assistant_message_id,
+        },
+        worker,
+    ))
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -1478,7 +1541,10 @@ fn render_tool_outputs(tool_execution_inputs: &[MessageToolExecutionInput]) -> S
         let error = input
             .error
             .as_ref()
-            .map(|err| format!("\nError: {}", err))
+            .map(|err| match input.error_kind.as_deref() {
+                Some(kind) => format!("\nError ({kind}): {err}"),
+                None => format!("\nError: {err}"),
+            })
             .unwrap_or_default();
 
         blocks.push(format!(
@@ -0,0 +1,72 @@
+use crate::db::{
+    CreateFeedSubscriptionInput, Db, FeedItem, FeedItemQuery, FeedOperations, FeedSubscription,
+    UpdateFeedSubscriptionInput,
+};
+use crate::feeds::{validate_feed_url, validate_poll_interval, FeedPoller};
+use tauri::State;
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_feed_subscriptions(state: State<'_, Db>) -> Result<Vec<FeedSubscription>, String> {
+    FeedOperations::get_feed_subscriptions(&*state).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_feed_subscription(
+    state: State<'_, Db>,
+    poller: State<'_, FeedPoller>,
+    input: CreateFeedSubscriptionInput,
+) -> Result<FeedSubscription, String> {
+    let poll_interval_minutes = validate_poll_interval(input.poll_interval_minutes)?;
+    let input = CreateFeedSubscriptionInput {
+        url: validate_feed_url(&input.url)?,
+        title: input
+            .title
+            .map(|title| title.trim().to_string())
+            .filter(|title| !title.is_empty()),
+        poll_interval_minutes: Some(poll_interval_minutes),
+    };
+    let feed = FeedOperations::create_feed_subscription(&*state, &input, poll_interval_minutes)
+        .map_err(|e| {
+            if e.to_string().contains("UNIQUE") {
+                "Already subscribed to this feed".to_string()
+            } else {
+                e.to_string()
+            }
+        })?;
+    poller.poll_now(&feed.id)?;
+    Ok(feed)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_feed_subscription(
+    state: State<'_, Db>,
+    input: UpdateFeedSubscriptionInput,
+) -> Result<Option<FeedSubscription>, String> {
+    if input.poll_interval_minutes.is_some() {
+        validate_poll_interval(input.poll_interval_minutes)?;
+    }
+    FeedOperations::update_feed_subscription(&*state, &input).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_feed_subscription(state: State<'_, Db>, id: String) -> Result<bool, String> {
+    FeedOperations::delete_feed_subscription(&*state, &id).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn refresh_feed(poller: State<'_, FeedPoller>, id: String) -> Result<bool, String> {
+    poller.poll_now(&id)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_feed_items(
+    state: State<'_, Db>,
+    query: FeedItemQuery,
+) -> Result<Vec<FeedItem>, String> {
+    FeedOperations::get_feed_items(&*state, &query).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn mark_feed_items_read(state: State<'_, Db>, ids: Vec<String>) -> Result<usize, String> {
+    FeedOperations::mark_feed_items_read(&*state, &ids).map_err(|e| e.to_string())
+}
@@ -4,6 +4,7 @@ mod branches;
 mod claude_cli;
 mod conversations;
 mod custom_backends;
+mod feeds;
 pub mod file_versioning;
 mod files;
 mod integrations;
@@ -11,8 +12,10 @@ mod mcp_servers;
 mod models;
 mod ollama;
 mod preferences;
+mod scheduled_jobs;
 mod system_prompts;
 mod tools;
+mod triggers;
 mod usage;
 
 pub use agent::*;
@@ -21,6 +24,7 @@ pub use branches::*;
 pub use claude_cli::*;
 pub use conversations::*;
 pub use custom_backends::*;
+pub use feeds::*;
 pub use file_versioning::*;
 pub use files::*;
 pub use integrations::*;
@@ -28,6 +32,8 @@ pub use mcp_servers::*;
 pub use models::*;
 pub use ollama::*;
 pub use preferences::*;
+pub use scheduled_jobs::*;
 pub use system_prompts::*;
 pub use tools::*;
+pub use triggers::*;
 pub use usage::*;
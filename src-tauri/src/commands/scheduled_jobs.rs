@@ -0,0 +1,118 @@
+use crate::db::{
+    CreateScheduledJobInput, Db, ScheduledJob, ScheduledJobOperations, ScheduledJobRun,
+    UpdateScheduledJobInput,
+};
+use crate::scheduler::{
+    next_run_at, parse_approval_policy, CronSchedule, Scheduler, APPROVAL_POLICY_DENY,
+};
+use chrono::Utc;
+use tauri::State;
+
+const DEFAULT_RUN_HISTORY_LIMIT: u32 = 50;
+
+fn validate_job_fields(
+    name: Option<&str>,
+    prompt: Option<&str>,
+    schedule: Option<&str>,
+    approval_policy: Option<&str>,
+) -> Result<(), String> {
+    if name.is_some_and(|name| name.trim().is_empty()) {
+        return Err("Scheduled job name is required".to_string());
+    }
+    if prompt.is_some_and(|prompt| prompt.trim().is_empty()) {
+        return Err("Scheduled job prompt is required".to_string());
+    }
+    if let Some(schedule) = schedule {
+        CronSchedule::parse(schedule)?;
+    }
+    if let Some(policy) = approval_policy {
+        parse_approval_policy(policy)?;
+    }
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_scheduled_jobs(state: State<'_, Db>) -> Result<Vec<ScheduledJob>, String> {
+    ScheduledJobOperations::get_scheduled_jobs(&*state).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_scheduled_job(
+    state: State<'_, Db>,
+    input: CreateScheduledJobInput,
+) -> Result<ScheduledJob, String> {
+    validate_job_fields(
+        Some(&input.name),
+        Some(&input.prompt),
+        Some(&input.schedule),
+        input.approval_policy.as_deref(),
+    )?;
+    let approval_policy = input
+        .approval_policy
+        .clone()
+        .unwrap_or_else(|| APPROVAL_POLICY_DENY.to_string());
+    let next_run = if input.enabled.unwrap_or(true) {
+        next_run_at(&input.schedule, Utc::now().timestamp_millis())?
+    } else {
+        None
+    };
+    ScheduledJobOperations::create_scheduled_job(&*state, &input, &approval_policy, next_run)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_scheduled_job(
+    state: State<'_, Db>,
+    input: UpdateScheduledJobInput,
+) -> Result<Option<ScheduledJob>, String> {
+    validate_job_fields(
+        input.name.as_deref(),
+        input.prompt.as_deref(),
+        input.schedule.as_deref(),
+        input.approval_policy.as_deref(),
+    )?;
+    let Some(job) =
+        ScheduledJobOperations::update_scheduled_job(&*state, &input).map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+
+    let next_run = if job.enabled {
+        next_run_at(&job.schedule, Utc::now().timestamp_millis())?
+    } else {
+        None
+    };
+    ScheduledJobOperations::set_scheduled_job_next_run(&*state, &job.id, next_run)
+        .map_err(|e| e.to_string())?;
+    Ok(Some(ScheduledJob {
+        next_run_at: next_run,
+        ..job
+    }))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_scheduled_job(state: State<'_, Db>, id: String) -> Result<bool, String> {
+    ScheduledJobOperations::delete_scheduled_job(&*state, &id).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn run_scheduled_job_now(
+    scheduler: State<'_, Scheduler>,
+    id: String,
+) -> Result<ScheduledJobRun, String> {
+    scheduler.run_now(&id)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_scheduled_job_runs(
+    state: State<'_, Db>,
+    job_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<ScheduledJobRun>, String> {
+    ScheduledJobOperations::get_scheduled_job_runs(
+        &*state,
+        &job_id,
+        limit.unwrap_or(DEFAULT_RUN_HISTORY_LIMIT),
+    )
+    .map_err(|e| e.to_string())
+}
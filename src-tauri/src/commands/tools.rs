@@ -1,52 +1,115 @@
-use crate::db::Db;
+use crate::db::{
+    AuditLogEntry, AuditLogOperations, AuditLogQuery, CreateToolApprovalRuleInput, Db,
+    SaveWebAuthProfileInput, ToolApprovalRule, ToolApprovalRuleOperations, WebAuthProfile,
+    WebAuthProfileOperations,
+};
 use crate::tools::{
-    load_tool_approval_overrides, set_conversation_tool_approval_override,
-    set_tool_approval_override as persist_tool_approval_override, ApprovalStore,
-    PendingToolApproval, ToolMetadata, ToolRegistry,
+    app_session_id, cancel_web_crawl_job, export_audit_entries, list_web_crawl_jobs,
+    load_tool_approval_overrides, load_web_access_rules, normalize_web_auth_profile_input,
+    set_conversation_tool_approval_override,
+    set_tool_approval_override as persist_tool_approval_override, upsert_web_access_rule,
+    validate_tool_approval_rule_input, ApprovalStore, PendingToolApproval, ToolApprovalDecision,
+    ToolMetadata, ToolRegistry, WebAccessRule, WebAccessRuleInput, WebCrawlJob, RULE_ACTION_ALLOW,
+    RULE_SCOPE_CONVERSATION, RULE_SCOPE_GLOBAL, RULE_SCOPE_SESSION,
 };
+use chrono::Utc;
+use serde_json::Value;
 use tauri::State;
 
 #[tauri::command(rename_all = "snake_case")]
 pub fn resolve_tool_execution_approval(
     approvals: State<'_, ApprovalStore>,
     db: State<'_, Db>,
+    tool_registry: State<'_, ToolRegistry>,
     approval_id: String,
     approved: bool,
     scope: Option<String>,
+    modified_args: Option<Value>,
+    feedback: Option<String>,
+    grant_ttl_ms: Option<i64>,
 ) -> Result<(), String> {
+    let decision = match (approved, modified_args) {
+        (false, _) => ToolApprovalDecision::Denied { feedback },
+        (true, None) => ToolApprovalDecision::Approved { feedback },
+        (true, Some(args)) => {
+            let pending = approvals
+                .get_pending(&approval_id)
+                .ok_or_else(|| format!("Unknown approval id: {approval_id}"))?;
+            let tool = tool_registry
+                .get(&pending.tool_name)
+                .ok_or_else(|| format!("Unknown tool: {}", pending.tool_name))?;
+            tool_registry
+                .validate_args(&tool.metadata, &args)
+                .map_err(|err| err.message)?;
+            ToolApprovalDecision::Modified { args, feedback }
+        }
+    };
+
     if approved {
         let selected_scope = scope.unwrap_or_else(|| "once".to_string());
         if selected_scope != "once" {
             let pending = approvals
                 .get_pending(&approval_id)
                 .ok_or_else(|| format!("Unknown approval id: {approval_id}"))?;
+            let expires_at = grant_ttl_ms
+                .filter(|ttl| *ttl > 0)
+                .map(|ttl| Utc::now().timestamp_millis() + ttl);
 
             match selected_scope.as_str() {
+                "session" => {
+                    create_grant_rule(
+                        &db,
+                        &pending.tool_name,
+                        RULE_SCOPE_SESSION,
+                        None,
+                        expires_at,
+                    )?;
+                }
                 "conversation" => {
                     let conversation_id = pending
                         .conversation_id
                         .ok_or_else(|| "Missing conversation context for approval".to_string())?;
-                    set_conversation_tool_approval_override(
-                        &db,
-                        &conversation_id,
-                        &pending.tool_name,
-                        Some(false),
-                    )?;
+                    if expires_at.is_some() {
+                        create_grant_rule(
+                            &db,
+                            &pending.tool_name,
+                            RULE_SCOPE_CONVERSATION,
+                            Some(conversation_id),
+                            expires_at,
+                        )?;
+                    } else {
+                        set_conversation_tool_approval_override(
+                            &db,
+                            &conversation_id,
+                            &pending.tool_name,
+                            Some(false),
+                        )?;
+                    }
                 }
                 "always" => {
-                    persist_tool_approval_override(&db, &pending.tool_name, Some(false))?;
+                    if expires_at.is_some() {
+                        create_grant_rule(
+                            &db,
+                            &pending.tool_name,
+                            RULE_SCOPE_GLOBAL,
+                            None,
+                            expires_at,
+                        )?;
+                    } else {
+                        persist_tool_approval_override(&db, &pending.tool_name, Some(false))?;
+                    }
                 }
                 "once" => {}
                 other => {
                     return Err(format!(
-                        "Invalid approval scope: {other}. Expected once, conversation, or always."
+                        "Invalid approval scope: {other}. Expected once, session, conversation, or always."
                     ));
                 }
             }
         }
     }
 
-    approvals.resolve(&approval_id, approved)
+    approvals.resolve(&approval_id, decision)
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -85,3 +148,123 @@ pub fn set_tool_approval_override(
 ) -> Result<(), String> {
     persist_tool_approval_override(&db, &tool_name, requires_approval)
 }
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_tool_approval_rules(db: State<'_, Db>) -> Result<Vec<ToolApprovalRule>, String> {
+    ToolApprovalRuleOperations::get_tool_approval_rules(&*db).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_tool_approval_rule(
+    db: State<'_, Db>,
+    mut input: CreateToolApprovalRuleInput,
+) -> Result<ToolApprovalRule, String> {
+    validate_tool_approval_rule_input(&input)?;
+    if input.scope == RULE_SCOPE_SESSION {
+        input.session_id = Some(app_session_id().to_string());
+    }
+    ToolApprovalRuleOperations::create_tool_approval_rule(&*db, &input).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_tool_approval_rule(db: State<'_, Db>, id: String) -> Result<bool, String> {
+    ToolApprovalRuleOperations::delete_tool_approval_rule(&*db, &id).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_audit_log(
+    db: State<'_, Db>,
+    query: Option<AuditLogQuery>,
+) -> Result<Vec<AuditLogEntry>, String> {
+    AuditLogOperations::query_audit_log(&*db, &query.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+/// Returns the matching entries as `jsonl` or `csv` text, all of them unless `query.limit`
+/// is set.
+#[tauri::command(rename_all = "snake_case")]
+pub fn export_audit_log(
+    db: State<'_, Db>,
+    query: Option<AuditLogQuery>,
+    format: String,
+) -> Result<String, String> {
+    let mut query = query.unwrap_or_default();
+    query.limit = Some(query.limit.unwrap_or(u32::MAX));
+    let entries = AuditLogOperations::query_audit_log(&*db, &query).map_err(|e| e.to_string())?;
+    export_audit_entries(&entries, &format)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_web_access_rules(db: State<'_, Db>) -> Result<Vec<WebAccessRule>, String> {
+    load_web_access_rules(&db).map_err(|e| e.to_string())
+}
+
+/// Creates a rule, or edits the one named by `input.id`.
+#[tauri::command(rename_all = "snake_case")]
+pub fn save_web_access_rule(
+    db: State<'_, Db>,
+    input: WebAccessRuleInput,
+) -> Result<WebAccessRule, String> {
+    upsert_web_access_rule(&db, input).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn revoke_web_access_rule(db: State<'_, Db>, id: String) -> Result<bool, String> {
+    crate::tools::revoke_web_access_rule(&db, &id).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_web_auth_profiles(db: State<'_, Db>) -> Result<Vec<WebAuthProfile>, String> {
+    db.get_web_auth_profiles().map_err(|e| e.to_string())
+}
+
+/// Creates a profile, or edits the one named by `input.id`; omit `secret` to keep the stored one.
+#[tauri::command(rename_all = "snake_case")]
+pub fn save_web_auth_profile(
+    db: State<'_, Db>,
+    input: SaveWebAuthProfileInput,
+) -> Result<WebAuthProfile, String> {
+    let input = normalize_web_auth_profile_input(input)?;
+    db.save_web_auth_profile(&input).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_web_auth_profile(db: State<'_, Db>, id: String) -> Result<bool, String> {
+    db.delete_web_auth_profile(&id).map_err(|e| e.to_string())
+}
+
+/// Crawl jobs started by `web.crawl` since the app launched, running ones first.
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_web_crawls() -> Result<Vec<WebCrawlJob>, String> {
+    Ok(list_web_crawl_jobs())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn cancel_web_crawl(job_id: String) -> Result<bool, String> {
+    Ok(cancel_web_crawl_job(&job_id))
+}
+
+fn create_grant_rule(
+    db: &Db,
+    tool_name: &str,
+    scope: &str,
+    conversation_id: Option<String>,
+    expires_at: Option<i64>,
+) -> Result<ToolApprovalRule, String> {
+    let session_id = if scope == RULE_SCOPE_SESSION {
+        Some(app_session_id().to_string())
+    } else {
+        None
+    };
+    let input = CreateToolApprovalRuleInput {
+        name: Some(format!("Approved {tool_name} ({scope})")),
+        tool_pattern: tool_name.to_string(),
+        arg_conditions: Vec::new(),
+        action: RULE_ACTION_ALLOW.to_string(),
+        scope: scope.to_string(),
+        conversation_id,
+        session_id,
+        expires_at,
+    };
+    validate_tool_approval_rule_input(&input)?;
+    ToolApprovalRuleOperations::create_tool_approval_rule(db, &input).map_err(|e| e.to_string())
+}
@@ -0,0 +1,104 @@
+use crate::db::{
+    AgentTrigger, AgentTriggerEvent, AgentTriggerOperations, CreateAgentTriggerInput, Db,
+    UpdateAgentTriggerInput,
+};
+use crate::scheduler::{parse_approval_policy, APPROVAL_POLICY_DENY};
+use crate::triggers::{validate_trigger_config, TriggerEngine};
+use tauri::State;
+
+const DEFAULT_EVENT_HISTORY_LIMIT: u32 = 50;
+
+fn validate_trigger_fields(
+    name: Option<&str>,
+    prompt: Option<&str>,
+    approval_policy: Option<&str>,
+    max_runs_per_hour: Option<u32>,
+) -> Result<(), String> {
+    if name.is_some_and(|name| name.trim().is_empty()) {
+        return Err("Trigger name is required".to_string());
+    }
+    if prompt.is_some_and(|prompt| prompt.trim().is_empty()) {
+        return Err("Trigger prompt is required".to_string());
+    }
+    if let Some(policy) = approval_policy {
+        parse_approval_policy(policy)?;
+    }
+    if max_runs_per_hour == Some(0) {
+        return Err("max_runs_per_hour must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_agent_triggers(state: State<'_, Db>) -> Result<Vec<AgentTrigger>, String> {
+    AgentTriggerOperations::get_agent_triggers(&*state).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_agent_trigger(
+    state: State<'_, Db>,
+    input: CreateAgentTriggerInput,
+) -> Result<AgentTrigger, String> {
+    validate_trigger_fields(
+        Some(&input.name),
+        Some(&input.prompt),
+        input.approval_policy.as_deref(),
+        input.max_runs_per_hour,
+    )?;
+    validate_trigger_config(&input.source, &input.config)?;
+    let approval_policy = input
+        .approval_policy
+        .clone()
+        .unwrap_or_else(|| APPROVAL_POLICY_DENY.to_string());
+    AgentTriggerOperations::create_agent_trigger(&*state, &input, &approval_policy)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_agent_trigger(
+    state: State<'_, Db>,
+    input: UpdateAgentTriggerInput,
+) -> Result<Option<AgentTrigger>, String> {
+    validate_trigger_fields(
+        input.name.as_deref(),
+        input.prompt.as_deref(),
+        input.approval_policy.as_deref(),
+        input.max_runs_per_hour,
+    )?;
+    if let Some(config) = input.config.as_ref() {
+        let Some(existing) = AgentTriggerOperations::get_agent_trigger_by_id(&*state, &input.id)
+            .map_err(|e| e.to_string())?
+        else {
+            return Ok(None);
+        };
+        validate_trigger_config(&existing.source, config)?;
+    }
+    AgentTriggerOperations::update_agent_trigger(&*state, &input).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_agent_trigger(state: State<'_, Db>, id: String) -> Result<bool, String> {
+    AgentTriggerOperations::delete_agent_trigger(&*state, &id).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_agent_trigger_events(
+    state: State<'_, Db>,
+    trigger_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<AgentTriggerEvent>, String> {
+    AgentTriggerOperations::get_agent_trigger_events(
+        &*state,
+        &trigger_id,
+        limit.unwrap_or(DEFAULT_EVENT_HISTORY_LIMIT),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn poll_agent_trigger_now(
+    engine: State<'_, TriggerEngine>,
+    id: String,
+) -> Result<usize, String> {
+    engine.poll_now(&id)
+}
@@ -35,6 +35,13 @@ impl PreferenceOperations for Db {}
 impl AgentSessionOperations for Db {}
 impl McpServerOperations for Db {}
 impl IntegrationConnectionOperations for Db {}
+impl ToolApprovalRuleOperations for Db {}
+impl ScheduledJobOperations for Db {}
+impl AgentTriggerOperations for Db {}
+impl IntegrationCacheOperations for Db {}
+impl AuditLogOperations for Db {}
+impl WebAuthProfileOperations for Db {}
+impl FeedOperations for Db {}
 
 impl Db {
     pub fn new(db_path: &str) -> Result<Self, DatabaseError> {
@@ -366,6 +373,217 @@ impl Db {
                 updated_at INTEGER NOT NULL
             );"),
             M::up("CREATE INDEX IF NOT EXISTS idx_integration_connections_integration ON integration_connections(integration_id);"),
+            // Rule-based tool approval grants
+            M::up("CREATE TABLE IF NOT EXISTS tool_approval_rules (
+                id TEXT PRIMARY KEY,
+                name TEXT,
+                tool_pattern TEXT NOT NULL,
+                arg_conditions TEXT NOT NULL DEFAULT '[]',
+                action TEXT NOT NULL,
+                scope TEXT NOT NULL,
+                conversation_id TEXT,
+                session_id TEXT,
+                expires_at INTEGER,
+                created_at INTEGER NOT NULL
+            );"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_tool_approval_rules_scope ON tool_approval_rules(scope);"),
+            M::up("ALTER TABLE agent_step_approvals ADD COLUMN rule_id TEXT;"),
+            M::up("ALTER TABLE message_tool_executions ADD COLUMN error_kind TEXT;"),
+            M::up("ALTER TABLE agent_step_results ADD COLUMN error_kind TEXT;"),
+            M::up("ALTER TABLE agent_sessions ADD COLUMN parent_session_id TEXT REFERENCES agent_sessions(id) ON DELETE CASCADE;"),
+            M::up("ALTER TABLE agent_sessions ADD COLUMN parent_step_id TEXT;"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_agent_sessions_parent ON agent_sessions(parent_session_id);"),
+            // Scheduled agent jobs
+            M::up("CREATE TABLE IF NOT EXISTS scheduled_jobs (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                schedule TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                custom_backend_id TEXT,
+                system_prompt TEXT,
+                conversation_id TEXT,
+                approval_policy TEXT NOT NULL DEFAULT 'deny',
+                enabled INTEGER NOT NULL DEFAULT 1,
+                next_run_at INTEGER,
+                last_run_at INTEGER,
+                last_status TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_scheduled_jobs_next_run ON scheduled_jobs(enabled, next_run_at);"),
+            M::up("CREATE TABLE IF NOT EXISTS scheduled_job_runs (
+                id TEXT PRIMARY KEY,
+                job_id TEXT NOT NULL,
+                triggered_by TEXT NOT NULL,
+                status TEXT NOT NULL,
+                conversation_id TEXT,
+                message_id TEXT,
+                output_preview TEXT,
+                error TEXT,
+                started_at INTEGER NOT NULL,
+                finished_at INTEGER,
+                FOREIGN KEY (job_id) REFERENCES scheduled_jobs(id) ON DELETE CASCADE
+            );"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_scheduled_job_runs_job ON scheduled_job_runs(job_id, started_at);"),
+            // Event-triggered agent runs
+            M::up("CREATE TABLE IF NOT EXISTS agent_triggers (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                source TEXT NOT NULL,
+                config TEXT NOT NULL DEFAULT '{}',
+                prompt TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                custom_backend_id TEXT,
+                system_prompt TEXT,
+                conversation_id TEXT,
+                approval_policy TEXT NOT NULL DEFAULT 'deny',
+                enabled INTEGER NOT NULL DEFAULT 1,
+                max_runs_per_hour INTEGER NOT NULL DEFAULT 6,
+                cursor TEXT,
+                last_fired_at INTEGER,
+                last_error TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );"),
+            M::up("CREATE TABLE IF NOT EXISTS agent_trigger_events (
+                id TEXT PRIMARY KEY,
+                trigger_id TEXT NOT NULL,
+                dedupe_key TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                status TEXT NOT NULL,
+                run_id TEXT,
+                conversation_id TEXT,
+                message_id TEXT,
+                error TEXT,
+                created_at INTEGER NOT NULL,
+                dispatched_at INTEGER,
+                finished_at INTEGER,
+                UNIQUE (trigger_id, dedupe_key),
+                FOREIGN KEY (trigger_id) REFERENCES agent_triggers(id) ON DELETE CASCADE
+            );"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_agent_trigger_events_status ON agent_trigger_events(trigger_id, status, created_at);"),
+            // Integration sync cache
+            M::up("CREATE TABLE IF NOT EXISTS integration_sync_state (
+                connection_id TEXT NOT NULL,
+                resource TEXT NOT NULL,
+                cursor TEXT,
+                status TEXT NOT NULL,
+                last_error TEXT,
+                item_count INTEGER NOT NULL DEFAULT 0,
+                last_synced_at INTEGER,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (connection_id, resource)
+            );"),
+            M::up("CREATE TABLE IF NOT EXISTS cached_gmail_messages (
+                connection_id TEXT NOT NULL,
+                id TEXT NOT NULL,
+                thread_id TEXT NOT NULL,
+                internal_date INTEGER NOT NULL,
+                from_addr TEXT NOT NULL DEFAULT '',
+                to_addr TEXT NOT NULL DEFAULT '',
+                subject TEXT NOT NULL DEFAULT '',
+                snippet TEXT NOT NULL DEFAULT '',
+                label_ids TEXT NOT NULL DEFAULT '[]',
+                PRIMARY KEY (connection_id, id)
+            );"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_cached_gmail_messages_date ON cached_gmail_messages(connection_id, internal_date);"),
+            M::up("CREATE TABLE IF NOT EXISTS cached_calendar_events (
+                connection_id TEXT NOT NULL,
+                calendar_id TEXT NOT NULL,
+                id TEXT NOT NULL,
+                summary TEXT NOT NULL DEFAULT '',
+                start_at INTEGER,
+                end_at INTEGER,
+                raw TEXT NOT NULL,
+                PRIMARY KEY (connection_id, calendar_id, id)
+            );"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_cached_calendar_events_start ON cached_calendar_events(connection_id, calendar_id, start_at);"),
+            M::up("CREATE TABLE IF NOT EXISTS cached_todoist_tasks (
+                connection_id TEXT NOT NULL,
+                id TEXT NOT NULL,
+                project_id TEXT,
+                content TEXT NOT NULL DEFAULT '',
+                checked INTEGER NOT NULL DEFAULT 0,
+                raw TEXT NOT NULL,
+                PRIMARY KEY (connection_id, id)
+            );"),
+            // No foreign keys: entries must outlive conversations and connections.
+            M::up("CREATE TABLE IF NOT EXISTS audit_log (
+                id TEXT PRIMARY KEY,
+                created_at INTEGER NOT NULL,
+                execution_id TEXT NOT NULL,
+                tool_name TEXT NOT NULL,
+                integration_id TEXT,
+                connection_id TEXT,
+                account_label TEXT,
+                args TEXT NOT NULL,
+                decision TEXT NOT NULL,
+                approved_by TEXT,
+                approval_rule_id TEXT,
+                outcome TEXT NOT NULL,
+                error TEXT,
+                error_kind TEXT,
+                external_resource_id TEXT,
+                conversation_id TEXT,
+                message_id TEXT,
+                duration_ms INTEGER NOT NULL DEFAULT 0
+            );"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at);"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_audit_log_tool_name ON audit_log(tool_name, created_at);"),
+            M::up("CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'audit_log is append-only');
+            END;"),
+            M::up("CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'audit_log is append-only');
+            END;"),
+            M::up("CREATE TABLE IF NOT EXISTS web_auth_profiles (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                kind TEXT NOT NULL,
+                host_patterns TEXT NOT NULL,
+                header_name TEXT,
+                username TEXT,
+                secret TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );"),
+            M::up("CREATE TABLE IF NOT EXISTS feed_subscriptions (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL UNIQUE,
+                title TEXT,
+                site_url TEXT,
+                poll_interval_minutes INTEGER NOT NULL DEFAULT 60,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                etag TEXT,
+                last_modified TEXT,
+                last_polled_at INTEGER,
+                next_poll_at INTEGER,
+                last_error TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );"),
+            // Items are kept after they are read so a re-published entry is not shown again.
+            M::up("CREATE TABLE IF NOT EXISTS feed_items (
+                id TEXT PRIMARY KEY,
+                feed_id TEXT NOT NULL,
+                guid TEXT NOT NULL,
+                title TEXT NOT NULL DEFAULT '',
+                url TEXT,
+                summary TEXT,
+                author TEXT,
+                published_at INTEGER,
+                fetched_at INTEGER NOT NULL,
+                read_at INTEGER,
+                UNIQUE (feed_id, guid),
+                FOREIGN KEY (feed_id) REFERENCES feed_subscriptions(id) ON DELETE CASCADE
+            );"),
+            M::up("CREATE INDEX IF NOT EXISTS idx_feed_items_unread ON feed_items(feed_id, read_at);"),
         ]);
 
         let mut conn = self.conn.lock().unwrap();
@@ -6,6 +6,11 @@ pub struct AgentSession {
     pub id: String,
     pub conversation_id: String,
     pub message_id: String,
+    /// Set for sub-agent sessions spawned by `agent.delegate`.
+    #[serde(default)]
+    pub parent_session_id: Option<String>,
+    #[serde(default)]
+    pub parent_step_id: Option<String>,
     pub phase: PhaseKind,
     pub plan: Option<Plan>,
     pub gathered_info: Vec<GatheredInfo>,
@@ -142,6 +147,8 @@ pub struct StepResult {
     pub success: bool,
     pub output: Option<serde_json::Value>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub error_kind: Option<String>,
     pub tool_executions: Vec<ToolExecutionRecord>,
     pub duration_ms: i64,
     pub completed_at: DateTime<Utc>,
@@ -151,6 +158,8 @@ pub struct StepResult {
 pub struct StepApproval {
     pub decision: ApprovalDecision,
     pub feedback: Option<String>,
+    #[serde(default)]
+    pub rule_id: Option<String>,
     pub decided_at: DateTime<Utc>,
 }
 
@@ -186,6 +195,12 @@ pub struct AgentConfig {
     pub max_tool_calls_per_step: u32,
     pub approval_timeout_ms: u64,
     pub tool_execution_timeout_ms: u64,
+    #[serde(default = "default_tool_max_retries")]
+    pub tool_max_retries: u32,
+}
+
+fn default_tool_max_retries() -> u32 {
+    2
 }
 
 impl Default for AgentConfig {
@@ -197,6 +212,7 @@ impl Default for AgentConfig {
             max_tool_calls_per_step: 5,
             approval_timeout_ms: 60_000,
             tool_execution_timeout_ms: 120_000,
+            tool_max_retries: default_tool_max_retries(),
         }
     }
 }
@@ -209,6 +225,10 @@ pub struct ToolExecutionRecord {
     pub result: Option<serde_json::Value>,
     pub success: bool,
     pub error: Option<String>,
+    #[serde(default)]
+    pub error_kind: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
     pub duration_ms: i64,
     pub iteration: usize,
     pub timestamp_ms: i64,
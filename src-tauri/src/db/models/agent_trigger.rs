@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct AgentTrigger {
+    pub id: String,
+    pub name: String,
+    /// Event source: `gmail`, `todoist`, `vault` or `webhook`.
+    pub source: String,
+    /// Source-specific settings, e.g. `{ "label": "Receipts" }` or `{ "path_prefix": "Inbox/" }`.
+    pub config: Value,
+    pub prompt: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_backend_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    pub approval_policy: String,
+    pub enabled: bool,
+    pub max_runs_per_hour: u32,
+    /// Polling state owned by the source, e.g. whether the initial baseline was taken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_fired_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct CreateAgentTriggerInput {
+    pub name: String,
+    pub source: String,
+    #[serde(default)]
+    pub config: Value,
+    pub prompt: String,
+    pub provider: String,
+    pub model: String,
+    pub custom_backend_id: Option<String>,
+    pub system_prompt: Option<String>,
+    pub conversation_id: Option<String>,
+    pub approval_policy: Option<String>,
+    pub enabled: Option<bool>,
+    pub max_runs_per_hour: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct UpdateAgentTriggerInput {
+    pub id: String,
+    pub name: Option<String>,
+    pub config: Option<Value>,
+    pub prompt: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub custom_backend_id: Option<String>,
+    pub system_prompt: Option<String>,
+    pub conversation_id: Option<String>,
+    pub approval_policy: Option<String>,
+    pub enabled: Option<bool>,
+    pub max_runs_per_hour: Option<u32>,
+}
+
+/// One observed change for a trigger. Events dispatched together share a `run_id`.
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct AgentTriggerEvent {
+    pub id: String,
+    pub trigger_id: String,
+    pub dedupe_key: String,
+    pub summary: String,
+    pub payload: Value,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dispatched_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+/// One side-effecting tool call. Rows are append-only and are not tied to a conversation,
+/// so they outlive it.
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct AuditLogEntry {
+    pub id: String,
+    pub created_at: i64,
+    pub execution_id: String,
+    pub tool_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integration_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_label: Option<String>,
+    /// Arguments as executed, with secret-looking values redacted.
+    pub args: Value,
+    /// `approved`, `modified`, `auto_approved`, `denied`, `timed_out` or `cancelled`.
+    pub decision: String,
+    /// `user`, `rule`, `conversation_override`, `global_override` or `unattended`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approved_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_rule_id: Option<String>,
+    /// `success`, `error` or `not_executed`.
+    pub outcome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    /// Id of the message, event, task, issue or file the call created or changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_resource_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    pub duration_ms: i64,
+}
+
+#[derive(Debug, Deserialize, Clone, Type)]
+pub struct AppendAuditLogInput {
+    pub execution_id: String,
+    pub tool_name: String,
+    pub integration_id: Option<String>,
+    pub connection_id: Option<String>,
+    pub account_label: Option<String>,
+    pub args: Value,
+    pub decision: String,
+    pub approved_by: Option<String>,
+    pub approval_rule_id: Option<String>,
+    pub outcome: String,
+    pub error: Option<String>,
+    pub error_kind: Option<String>,
+    pub external_resource_id: Option<String>,
+    pub conversation_id: Option<String>,
+    pub message_id: Option<String>,
+    pub duration_ms: i64,
+}
+
+/// Filters for querying and exporting the audit log. All fields are optional.
+#[derive(Debug, Deserialize, Clone, Default, Type)]
+pub struct AuditLogQuery {
+    /// Exact tool name, or a prefix ending in `*` such as `gmail.*`.
+    pub tool_name: Option<String>,
+    pub integration_id: Option<String>,
+    pub connection_id: Option<String>,
+    pub conversation_id: Option<String>,
+    pub decision: Option<String>,
+    pub outcome: Option<String>,
+    /// Inclusive lower bound on `created_at`, in ms.
+    pub since: Option<i64>,
+    /// Exclusive upper bound on `created_at`, in ms.
+    pub until: Option<i64>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// An RSS, Atom or JSON Feed the poller checks on its own interval.
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct FeedSubscription {
+    pub id: String,
+    pub url: String,
+    /// Custom title, or the one the feed declares.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_url: Option<String>,
+    pub poll_interval_minutes: i64,
+    pub enabled: bool,
+    /// Validators from the last response, sent back as `If-None-Match` / `If-Modified-Since`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_polled_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_poll_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub unread_count: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct CreateFeedSubscriptionInput {
+    pub url: String,
+    pub title: Option<String>,
+    pub poll_interval_minutes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct UpdateFeedSubscriptionInput {
+    pub id: String,
+    pub title: Option<String>,
+    pub poll_interval_minutes: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+/// Outcome of one poll, stored on the subscription.
+#[derive(Debug, Clone, Default)]
+pub struct FeedPollUpdate {
+    /// Title and site URL the feed declares; only fill in a subscription without a custom one.
+    pub feed_title: Option<String>,
+    pub site_url: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub error: Option<String>,
+    pub polled_at: i64,
+    pub next_poll_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct FeedItem {
+    pub id: String,
+    pub feed_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed_title: Option<String>,
+    /// The entry's id or guid, falling back to its link; unique within the feed.
+    pub guid: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Plain-text summary, shortened.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<i64>,
+    pub fetched_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_at: Option<i64>,
+}
+
+/// An entry parsed from a feed document, before it is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewFeedItem {
+    pub guid: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub summary: Option<String>,
+    pub author: Option<String>,
+    pub published_at: Option<i64>,
+}
+
+/// Filters for listing feed items, newest first.
+#[derive(Debug, Deserialize, Clone, Default, Type)]
+pub struct FeedItemQuery {
+    pub feed_id: Option<String>,
+    #[serde(default)]
+    pub unread_only: bool,
+    /// Inclusive lower bound on the published (or fetched) time, in ms.
+    pub since: Option<i64>,
+    pub limit: Option<u32>,
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+pub const SYNC_RESOURCE_GMAIL: &str = "gmail_messages";
+pub const SYNC_RESOURCE_TODOIST: &str = "todoist_items";
+/// Calendars are synced separately, as `calendar:{calendar_id}`.
+pub const SYNC_RESOURCE_CALENDAR_PREFIX: &str = "calendar:";
+
+/// Progress of one synced resource of a connection, e.g. `gmail_messages` or
+/// `calendar:primary`.
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct IntegrationSyncState {
+    pub connection_id: String,
+    pub resource: String,
+    /// Provider cursor: Gmail historyId, Calendar syncToken or Todoist sync_token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// `ok`, `error` or `stale` (a local write happened since the last sync).
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub item_count: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_synced_at: Option<i64>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Type)]
+pub struct CachedGmailMessage {
+    pub connection_id: String,
+    pub id: String,
+    pub thread_id: String,
+    pub internal_date: i64,
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub snippet: String,
+    pub label_ids: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct GmailCacheQuery {
+    /// Substring of the From header.
+    pub from: Option<String>,
+    /// Substring of the To/Cc headers.
+    pub to: Option<String>,
+    /// Substring of the subject or snippet.
+    pub text: Option<String>,
+    pub label_id: Option<String>,
+    pub after_ms: Option<i64>,
+    pub before_ms: Option<i64>,
+    pub limit: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct CachedCalendarEvent {
+    pub connection_id: String,
+    pub calendar_id: String,
+    pub id: String,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_at: Option<i64>,
+    /// Event resource as returned by the Calendar API.
+    pub raw: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct CachedTodoistTask {
+    pub connection_id: String,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    pub content: String,
+    pub checked: bool,
+    /// Task in the REST API shape, so cached and live results look the same.
+    pub raw: Value,
+}
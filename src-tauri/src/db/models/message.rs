@@ -55,6 +55,8 @@ pub struct MessageToolExecution {
     pub duration_ms: i64,
     pub timestamp_ms: i64,
     pub error: Option<String>,
+    #[serde(default)]
+    pub error_kind: Option<String>,
     pub iteration_number: i64,
 }
 
@@ -69,5 +71,7 @@ pub struct MessageToolExecutionInput {
     pub duration_ms: i64,
     pub timestamp_ms: i64,
     pub error: Option<String>,
+    #[serde(default)]
+    pub error_kind: Option<String>,
     pub iteration_number: i64,
 }
@@ -1,21 +1,35 @@
 mod agent;
+mod agent_trigger;
+mod audit_log;
 mod branch;
 mod conversation;
 mod custom_backend;
+mod feed;
+mod integration_cache;
 mod integration_connection;
 mod mcp_server;
 mod message;
 mod model;
+mod scheduled_job;
 mod system_prompt;
+mod tool_approval_rule;
 mod usage;
+mod web_auth_profile;
 
 pub use agent::*;
+pub use agent_trigger::*;
+pub use audit_log::*;
 pub use branch::*;
 pub use conversation::*;
 pub use custom_backend::*;
+pub use feed::*;
+pub use integration_cache::*;
 pub use integration_connection::*;
 pub use mcp_server::*;
 pub use message::*;
 pub use model::*;
+pub use scheduled_job::*;
 pub use system_prompt::*;
+pub use tool_approval_rule::*;
 pub use usage::*;
+pub use web_auth_profile::*;
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct ScheduledJob {
+    pub id: String,
+    pub name: String,
+    pub prompt: String,
+    pub schedule: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_backend_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Conversation the job posts into; a new conversation is created per run when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    pub approval_policy: String,
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_status: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct CreateScheduledJobInput {
+    pub name: String,
+    pub prompt: String,
+    pub schedule: String,
+    pub provider: String,
+    pub model: String,
+    pub custom_backend_id: Option<String>,
+    pub system_prompt: Option<String>,
+    pub conversation_id: Option<String>,
+    pub approval_policy: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct UpdateScheduledJobInput {
+    pub id: String,
+    pub name: Option<String>,
+    pub prompt: Option<String>,
+    pub schedule: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub custom_backend_id: Option<String>,
+    pub system_prompt: Option<String>,
+    pub conversation_id: Option<String>,
+    pub approval_policy: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct ScheduledJobRun {
+    pub id: String,
+    pub job_id: String,
+    pub triggered_by: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_preview: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct ToolApprovalRule {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub tool_pattern: String,
+    #[serde(default)]
+    pub arg_conditions: Vec<ToolApprovalArgCondition>,
+    pub action: String,
+    pub scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct ToolApprovalArgCondition {
+    pub arg: String,
+    pub op: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct CreateToolApprovalRuleInput {
+    pub name: Option<String>,
+    pub tool_pattern: String,
+    #[serde(default)]
+    pub arg_conditions: Vec<ToolApprovalArgCondition>,
+    pub action: String,
+    pub scope: String,
+    pub conversation_id: Option<String>,
+    pub session_id: Option<String>,
+    pub expires_at: Option<i64>,
+}
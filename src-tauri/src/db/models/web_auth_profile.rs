@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+pub const WEB_AUTH_BEARER: &str = "bearer";
+pub const WEB_AUTH_BASIC: &str = "basic";
+pub const WEB_AUTH_API_KEY: &str = "api_key";
+pub const WEB_AUTH_HMAC: &str = "hmac";
+
+/// Stored credentials `web.request` can use by name. The secret is never serialized, so it
+/// cannot reach the frontend or the model.
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct WebAuthProfile {
+    pub id: String,
+    pub name: String,
+    /// `bearer`, `basic`, `api_key` or `hmac`.
+    pub kind: String,
+    /// Hosts the profile may be sent to, exact or `*.example.com`.
+    pub host_patterns: Vec<String>,
+    /// Header carrying the key (`api_key`) or the signature (`hmac`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header_name: Option<String>,
+    /// User name for `basic`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Creates a profile, or updates the one with `id`. Updates without a `secret` keep the
+/// stored one.
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct SaveWebAuthProfileInput {
+    pub id: Option<String>,
+    pub name: String,
+    pub kind: String,
+    pub host_patterns: Vec<String>,
+    pub header_name: Option<String>,
+    pub username: Option<String>,
+    pub secret: Option<String>,
+}
@@ -1,10 +1,12 @@
 use chrono::{TimeZone, Utc};
 use rusqlite::{params, Result as RusqliteResult};
 use serde_json::Value;
+use uuid::Uuid;
 
 use super::DbOperations;
 use crate::db::models::{
-    AgentConfig, AgentSession, PhaseKind, Plan, PlanStep, StepAction, StepResult, StepStatus,
+    AgentConfig, AgentSession, ApprovalDecision, PhaseKind, Plan, PlanStep, StepAction,
+    StepApproval, StepResult, StepStatus,
 };
 
 pub trait AgentSessionOperations: DbOperations {
@@ -15,9 +17,9 @@ pub trait AgentSessionOperations: DbOperations {
         conn.execute(
             "INSERT INTO agent_sessions (
                 id, conversation_id, message_id, phase, phase_data, config,
-                created_at, updated_at, completed_at
+                created_at, updated_at, completed_at, parent_session_id, parent_step_id
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 session.id,
                 session.conversation_id,
@@ -28,6 +30,8 @@ pub trait AgentSessionOperations: DbOperations {
                 session.created_at.timestamp(),
                 session.updated_at.timestamp(),
                 session.completed_at.map(|v| v.timestamp()),
+                session.parent_session_id,
+                session.parent_step_id,
             ],
         )?;
 
@@ -154,9 +158,9 @@ pub trait AgentSessionOperations: DbOperations {
 
         conn.execute(
             "INSERT INTO agent_step_results (
-                id, step_id, session_id, success, output, error, duration_ms, completed_at
+                id, step_id, session_id, success, output, error, duration_ms, completed_at, error_kind
             )
-            VALUES (?1, ?2, (SELECT session_id FROM agent_plans WHERE id = (SELECT plan_id FROM agent_plan_steps WHERE id = ?2)), ?3, ?4, ?5, ?6, ?7)",
+            VALUES (?1, ?2, (SELECT session_id FROM agent_plans WHERE id = (SELECT plan_id FROM agent_plan_steps WHERE id = ?2)), ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 result.step_id.clone(),
                 result.step_id,
@@ -165,6 +169,34 @@ pub trait AgentSessionOperations: DbOperations {
                 result.error,
                 result.duration_ms,
                 result.completed_at.timestamp(),
+                result.error_kind,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn save_step_approval(&self, step_id: &str, approval: &StepApproval) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let auto_approve_reason = match &approval.decision {
+            ApprovalDecision::AutoApproved { reason } => Some(reason.clone()),
+            _ => None,
+        };
+
+        conn.execute(
+            "INSERT INTO agent_step_approvals (
+                id, step_id, decision, auto_approve_reason, feedback, rule_id, decided_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                Uuid::new_v4().to_string(),
+                step_id,
+                approval_decision_to_str(&approval.decision),
+                auto_approve_reason,
+                approval.feedback,
+                approval.rule_id,
+                approval.decided_at.timestamp(),
             ],
         )?;
 
@@ -182,7 +214,7 @@ pub trait AgentSessionOperations: DbOperations {
         let mut stmt = conn.prepare(
             "SELECT id, conversation_id, message_id, phase_data, config, created_at, updated_at, completed_at
              FROM agent_sessions
-             WHERE conversation_id = ?1 AND completed_at IS NULL
+             WHERE conversation_id = ?1 AND completed_at IS NULL AND parent_session_id IS NULL
              ORDER BY updated_at DESC
              LIMIT 1",
         )?;
@@ -210,6 +242,8 @@ pub trait AgentSessionOperations: DbOperations {
                 id: row.get(0)?,
                 conversation_id: row.get(1)?,
                 message_id: row.get(2)?,
+                parent_session_id: None,
+                parent_step_id: None,
                 phase,
                 plan,
                 gathered_info,
@@ -359,6 +393,16 @@ fn parse_step_action(action_type: &str, action_data: &str) -> StepAction {
     }
 }
 
+fn approval_decision_to_str(decision: &ApprovalDecision) -> &'static str {
+    match decision {
+        ApprovalDecision::Approved => "approved",
+        ApprovalDecision::Skipped => "skipped",
+        ApprovalDecision::Modified => "modified",
+        ApprovalDecision::Denied => "denied",
+        ApprovalDecision::AutoApproved { .. } => "auto_approved",
+    }
+}
+
 fn step_status_to_str(status: &StepStatus) -> &'static str {
     match status {
         StepStatus::Pending => "pending",
@@ -0,0 +1,455 @@
+use super::DbOperations;
+use crate::db::models::{
+    AgentTrigger, AgentTriggerEvent, CreateAgentTriggerInput, UpdateAgentTriggerInput,
+};
+use rusqlite::{params, Result as RusqliteResult, Row};
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+const TRIGGER_COLUMNS: &str = "id, name, source, config, prompt, provider, model, custom_backend_id, system_prompt, conversation_id, approval_policy, enabled, max_runs_per_hour, cursor, last_fired_at, last_error, created_at, updated_at";
+const EVENT_COLUMNS: &str = "id, trigger_id, dedupe_key, summary, payload, status, run_id, conversation_id, message_id, error, created_at, dispatched_at, finished_at";
+const DEFAULT_MAX_RUNS_PER_HOUR: u32 = 6;
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+pub trait AgentTriggerOperations: DbOperations {
+    fn create_agent_trigger(
+        &self,
+        input: &CreateAgentTriggerInput,
+        approval_policy: &str,
+    ) -> RusqliteResult<AgentTrigger> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let id = Uuid::new_v4().to_string();
+        let now = now_ms();
+        let enabled = input.enabled.unwrap_or(true);
+        let max_runs_per_hour = input.max_runs_per_hour.unwrap_or(DEFAULT_MAX_RUNS_PER_HOUR);
+        let config = if input.config.is_null() {
+            Value::Object(Default::default())
+        } else {
+            input.config.clone()
+        };
+
+        conn.execute(
+            "INSERT INTO agent_triggers (
+                id,
+                name,
+                source,
+                config,
+                prompt,
+                provider,
+                model,
+                custom_backend_id,
+                system_prompt,
+                conversation_id,
+                approval_policy,
+                enabled,
+                max_runs_per_hour,
+                created_at,
+                updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                id,
+                input.name,
+                input.source,
+                config.to_string(),
+                input.prompt,
+                input.provider,
+                input.model,
+                input.custom_backend_id,
+                input.system_prompt,
+                input.conversation_id,
+                approval_policy,
+                enabled,
+                max_runs_per_hour,
+                now,
+                now,
+            ],
+        )?;
+
+        Ok(AgentTrigger {
+            id,
+            name: input.name.clone(),
+            source: input.source.clone(),
+            config,
+            prompt: input.prompt.clone(),
+            provider: input.provider.clone(),
+            model: input.model.clone(),
+            custom_backend_id: input.custom_backend_id.clone(),
+            system_prompt: input.system_prompt.clone(),
+            conversation_id: input.conversation_id.clone(),
+            approval_policy: approval_policy.to_string(),
+            enabled,
+            max_runs_per_hour,
+            cursor: None,
+            last_fired_at: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    fn get_agent_triggers(&self) -> RusqliteResult<Vec<AgentTrigger>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TRIGGER_COLUMNS} FROM agent_triggers ORDER BY created_at DESC"
+        ))?;
+        let iter = stmt.query_map([], row_to_agent_trigger)?;
+        iter.collect()
+    }
+
+    fn get_agent_trigger_by_id(&self, id: &str) -> RusqliteResult<Option<AgentTrigger>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TRIGGER_COLUMNS} FROM agent_triggers WHERE id = ?1"
+        ))?;
+        match stmt.query_row(params![id], row_to_agent_trigger) {
+            Ok(trigger) => Ok(Some(trigger)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn update_agent_trigger(
+        &self,
+        input: &UpdateAgentTriggerInput,
+    ) -> RusqliteResult<Option<AgentTrigger>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let mut updates = Vec::new();
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(ref value) = input.name {
+            updates.push("name = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.config {
+            // New settings invalidate the polling baseline.
+            updates.push("config = ?");
+            params_vec.push(Box::new(value.to_string()));
+            updates.push("cursor = NULL");
+        }
+        if let Some(ref value) = input.prompt {
+            updates.push("prompt = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.provider {
+            updates.push("provider = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.model {
+            updates.push("model = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.custom_backend_id {
+            updates.push("custom_backend_id = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.system_prompt {
+            updates.push("system_prompt = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.conversation_id {
+            updates.push("conversation_id = ?");
+            params_vec.push(Box::new(Some(value.clone()).filter(|id| !id.is_empty())));
+        }
+        if let Some(ref value) = input.approval_policy {
+            updates.push("approval_policy = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(value) = input.enabled {
+            updates.push("enabled = ?");
+            params_vec.push(Box::new(value));
+        }
+        if let Some(value) = input.max_runs_per_hour {
+            updates.push("max_runs_per_hour = ?");
+            params_vec.push(Box::new(value));
+        }
+
+        if updates.is_empty() {
+            drop(conn);
+            return self.get_agent_trigger_by_id(&input.id);
+        }
+
+        updates.push("updated_at = ?");
+        params_vec.push(Box::new(now_ms()));
+        params_vec.push(Box::new(input.id.clone()));
+
+        let sql = format!(
+            "UPDATE agent_triggers SET {} WHERE id = ?",
+            updates.join(", ")
+        );
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params_vec.iter().map(|value| value.as_ref()).collect();
+        let rows_affected = conn.execute(&sql, params_refs.as_slice())?;
+        if rows_affected == 0 {
+            return Ok(None);
+        }
+
+        drop(conn);
+        self.get_agent_trigger_by_id(&input.id)
+    }
+
+    fn delete_agent_trigger(&self, id: &str) -> RusqliteResult<bool> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "DELETE FROM agent_trigger_events WHERE trigger_id = ?1",
+            params![id],
+        )?;
+        let rows_affected =
+            conn.execute("DELETE FROM agent_triggers WHERE id = ?1", params![id])?;
+        Ok(rows_affected > 0)
+    }
+
+    fn set_agent_trigger_cursor(&self, id: &str, cursor: Option<&Value>) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE agent_triggers SET cursor = ?1 WHERE id = ?2",
+            params![cursor.map(|value| value.to_string()), id],
+        )?;
+        Ok(())
+    }
+
+    fn set_agent_trigger_error(&self, id: &str, error: Option<&str>) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE agent_triggers SET last_error = ?1 WHERE id = ?2",
+            params![error, id],
+        )?;
+        Ok(())
+    }
+
+    fn has_agent_trigger_event(&self, trigger_id: &str, dedupe_key: &str) -> RusqliteResult<bool> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM agent_trigger_events WHERE trigger_id = ?1 AND dedupe_key = ?2",
+            params![trigger_id, dedupe_key],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Stores an event unless one with the same dedupe key exists. Returns whether it was new.
+    fn record_agent_trigger_event(
+        &self,
+        trigger_id: &str,
+        dedupe_key: &str,
+        summary: &str,
+        payload: &Value,
+        status: &str,
+    ) -> RusqliteResult<bool> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO agent_trigger_events (
+                id, trigger_id, dedupe_key, summary, payload, status, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                Uuid::new_v4().to_string(),
+                trigger_id,
+                dedupe_key,
+                summary,
+                payload.to_string(),
+                status,
+                now_ms(),
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    fn get_pending_agent_trigger_events(
+        &self,
+        trigger_id: &str,
+        limit: u32,
+    ) -> RusqliteResult<Vec<AgentTriggerEvent>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {EVENT_COLUMNS} FROM agent_trigger_events
+             WHERE trigger_id = ?1 AND status = 'pending'
+             ORDER BY created_at ASC
+             LIMIT ?2"
+        ))?;
+        let iter = stmt.query_map(params![trigger_id, limit], row_to_agent_trigger_event)?;
+        iter.collect()
+    }
+
+    fn get_agent_trigger_events(
+        &self,
+        trigger_id: &str,
+        limit: u32,
+    ) -> RusqliteResult<Vec<AgentTriggerEvent>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {EVENT_COLUMNS} FROM agent_trigger_events
+             WHERE trigger_id = ?1 AND status != 'baseline'
+             ORDER BY created_at DESC
+             LIMIT ?2"
+        ))?;
+        let iter = stmt.query_map(params![trigger_id, limit], row_to_agent_trigger_event)?;
+        iter.collect()
+    }
+
+    /// Number of runs the trigger started since `since_ms`.
+    fn count_agent_trigger_runs_since(
+        &self,
+        trigger_id: &str,
+        since_ms: i64,
+    ) -> RusqliteResult<u32> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(DISTINCT run_id) FROM agent_trigger_events
+             WHERE trigger_id = ?1 AND run_id IS NOT NULL AND dispatched_at >= ?2",
+            params![trigger_id, since_ms],
+            |row| row.get(0),
+        )
+    }
+
+    /// When the trigger's earliest run since `since_ms` was dispatched, if it ran at all.
+    fn first_agent_trigger_run_since(
+        &self,
+        trigger_id: &str,
+        since_ms: i64,
+    ) -> RusqliteResult<Option<i64>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.query_row(
+            "SELECT MIN(dispatched_at) FROM agent_trigger_events
+             WHERE trigger_id = ?1 AND run_id IS NOT NULL AND dispatched_at >= ?2",
+            params![trigger_id, since_ms],
+            |row| row.get(0),
+        )
+    }
+
+    fn set_agent_trigger_events_status(
+        &self,
+        event_ids: &[String],
+        status: &str,
+        error: Option<&str>,
+    ) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let now = now_ms();
+        for event_id in event_ids {
+            conn.execute(
+                "UPDATE agent_trigger_events SET status = ?1, error = ?2, finished_at = ?3 WHERE id = ?4",
+                params![status, error, now, event_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn dispatch_agent_trigger_events(
+        &self,
+        trigger_id: &str,
+        event_ids: &[String],
+        run_id: &str,
+    ) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let now = now_ms();
+        for event_id in event_ids {
+            conn.execute(
+                "UPDATE agent_trigger_events SET status = 'running', run_id = ?1, dispatched_at = ?2 WHERE id = ?3",
+                params![run_id, now, event_id],
+            )?;
+        }
+        conn.execute(
+            "UPDATE agent_triggers SET last_fired_at = ?1 WHERE id = ?2",
+            params![now, trigger_id],
+        )?;
+        Ok(())
+    }
+
+    fn finish_agent_trigger_run(
+        &self,
+        run_id: &str,
+        status: &str,
+        conversation_id: Option<&str>,
+        message_id: Option<&str>,
+        error: Option<&str>,
+    ) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE agent_trigger_events
+             SET status = ?1, conversation_id = ?2, message_id = ?3, error = ?4, finished_at = ?5
+             WHERE run_id = ?6",
+            params![status, conversation_id, message_id, error, now_ms(), run_id],
+        )?;
+        Ok(())
+    }
+
+    /// Marks events left in `running` by a previous app session as failed.
+    fn fail_interrupted_agent_trigger_events(&self) -> RusqliteResult<usize> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE agent_trigger_events
+             SET status = 'failed', error = 'Interrupted before the run finished', finished_at = ?1
+             WHERE status = 'running'",
+            params![now_ms()],
+        )
+    }
+}
+
+fn parse_json_column(raw: Option<String>) -> Option<Value> {
+    raw.and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+fn row_to_agent_trigger(row: &Row<'_>) -> RusqliteResult<AgentTrigger> {
+    Ok(AgentTrigger {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source: row.get(2)?,
+        config: parse_json_column(row.get(3)?).unwrap_or(Value::Object(Default::default())),
+        prompt: row.get(4)?,
+        provider: row.get(5)?,
+        model: row.get(6)?,
+        custom_backend_id: row.get(7)?,
+        system_prompt: row.get(8)?,
+        conversation_id: row.get(9)?,
+        approval_policy: row.get(10)?,
+        enabled: row.get(11)?,
+        max_runs_per_hour: row.get(12)?,
+        cursor: parse_json_column(row.get(13)?),
+        last_fired_at: row.get(14)?,
+        last_error: row.get(15)?,
+        created_at: row.get(16)?,
+        updated_at: row.get(17)?,
+    })
+}
+
+fn row_to_agent_trigger_event(row: &Row<'_>) -> RusqliteResult<AgentTriggerEvent> {
+    Ok(AgentTriggerEvent {
+        id: row.get(0)?,
+        trigger_id: row.get(1)?,
+        dedupe_key: row.get(2)?,
+        summary: row.get(3)?,
+        payload: parse_json_column(row.get(4)?).unwrap_or(Value::Null),
+        status: row.get(5)?,
+        run_id: row.get(6)?,
+        conversation_id: row.get(7)?,
+        message_id: row.get(8)?,
+        error: row.get(9)?,
+        created_at: row.get(10)?,
+        dispatched_at: row.get(11)?,
+        finished_at: row.get(12)?,
+    })
+}
@@ -0,0 +1,147 @@
+use super::DbOperations;
+use crate::db::models::{AppendAuditLogInput, AuditLogEntry, AuditLogQuery};
+use rusqlite::{params, Result as RusqliteResult, Row};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+const AUDIT_COLUMNS: &str = "id, created_at, execution_id, tool_name, integration_id, connection_id, account_label, args, decision, approved_by, approval_rule_id, outcome, error, error_kind, external_resource_id, conversation_id, message_id, duration_ms";
+const DEFAULT_QUERY_LIMIT: u32 = 500;
+
+/// Append-only record of side-effecting tool calls. There is deliberately no update or
+/// delete; the table's triggers reject both.
+pub trait AuditLogOperations: DbOperations {
+    fn append_audit_log_entry(&self, input: &AppendAuditLogInput) -> RusqliteResult<AuditLogEntry> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let id = Uuid::new_v4().to_string();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+
+        conn.execute(
+            &format!(
+                "INSERT INTO audit_log ({AUDIT_COLUMNS})
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)"
+            ),
+            params![
+                id,
+                now,
+                input.execution_id,
+                input.tool_name,
+                input.integration_id,
+                input.connection_id,
+                input.account_label,
+                input.args.to_string(),
+                input.decision,
+                input.approved_by,
+                input.approval_rule_id,
+                input.outcome,
+                input.error,
+                input.error_kind,
+                input.external_resource_id,
+                input.conversation_id,
+                input.message_id,
+                input.duration_ms,
+            ],
+        )?;
+
+        Ok(AuditLogEntry {
+            id,
+            created_at: now,
+            execution_id: input.execution_id.clone(),
+            tool_name: input.tool_name.clone(),
+            integration_id: input.integration_id.clone(),
+            connection_id: input.connection_id.clone(),
+            account_label: input.account_label.clone(),
+            args: input.args.clone(),
+            decision: input.decision.clone(),
+            approved_by: input.approved_by.clone(),
+            approval_rule_id: input.approval_rule_id.clone(),
+            outcome: input.outcome.clone(),
+            error: input.error.clone(),
+            error_kind: input.error_kind.clone(),
+            external_resource_id: input.external_resource_id.clone(),
+            conversation_id: input.conversation_id.clone(),
+            message_id: input.message_id.clone(),
+            duration_ms: input.duration_ms,
+        })
+    }
+
+    /// Entries matching `query`, newest first.
+    fn query_audit_log(&self, query: &AuditLogQuery) -> RusqliteResult<Vec<AuditLogEntry>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let mut sql = format!("SELECT {AUDIT_COLUMNS} FROM audit_log WHERE 1 = 1");
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        if let Some(tool_name) = query.tool_name.as_deref().filter(|v| !v.is_empty()) {
+            match tool_name.strip_suffix('*') {
+                Some(prefix) => {
+                    sql.push_str(" AND substr(tool_name, 1, ?) = ?");
+                    params_vec.push(Box::new(prefix.chars().count() as i64));
+                    params_vec.push(Box::new(prefix.to_string()));
+                }
+                None => {
+                    sql.push_str(" AND tool_name = ?");
+                    params_vec.push(Box::new(tool_name.to_string()));
+                }
+            }
+        }
+        let exact_filters = [
+            ("integration_id", &query.integration_id),
+            ("connection_id", &query.connection_id),
+            ("conversation_id", &query.conversation_id),
+            ("decision", &query.decision),
+            ("outcome", &query.outcome),
+        ];
+        for (column, value) in exact_filters {
+            if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+                sql.push_str(&format!(" AND {column} = ?"));
+                params_vec.push(Box::new(value.to_string()));
+            }
+        }
+        if let Some(since) = query.since {
+            sql.push_str(" AND created_at >= ?");
+            params_vec.push(Box::new(since));
+        }
+        if let Some(until) = query.until {
+            sql.push_str(" AND created_at < ?");
+            params_vec.push(Box::new(until));
+        }
+        sql.push_str(" ORDER BY created_at DESC, rowid DESC LIMIT ? OFFSET ?");
+        params_vec.push(Box::new(query.limit.unwrap_or(DEFAULT_QUERY_LIMIT)));
+        params_vec.push(Box::new(query.offset.unwrap_or(0)));
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params_vec.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare(&sql)?;
+        let iter = stmt.query_map(params_refs.as_slice(), row_to_audit_log_entry)?;
+        iter.collect()
+    }
+}
+
+fn row_to_audit_log_entry(row: &Row<'_>) -> RusqliteResult<AuditLogEntry> {
+    let args: String = row.get(7)?;
+    Ok(AuditLogEntry {
+        id: row.get(0)?,
+        created_at: row.get(1)?,
+        execution_id: row.get(2)?,
+        tool_name: row.get(3)?,
+        integration_id: row.get(4)?,
+        connection_id: row.get(5)?,
+        account_label: row.get(6)?,
+        args: serde_json::from_str(&args).unwrap_or(serde_json::Value::Null),
+        decision: row.get(8)?,
+        approved_by: row.get(9)?,
+        approval_rule_id: row.get(10)?,
+        outcome: row.get(11)?,
+        error: row.get(12)?,
+        error_kind: row.get(13)?,
+        external_resource_id: row.get(14)?,
+        conversation_id: row.get(15)?,
+        message_id: row.get(16)?,
+        duration_ms: row.get(17)?,
+    })
+}
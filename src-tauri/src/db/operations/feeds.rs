@@ -0,0 +1,300 @@
+use super::DbOperations;
+use crate::db::models::{
+    CreateFeedSubscriptionInput, FeedItem, FeedItemQuery, FeedPollUpdate, FeedSubscription,
+    NewFeedItem, UpdateFeedSubscriptionInput,
+};
+use rusqlite::{params, Result as RusqliteResult, Row};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+const SUBSCRIPTION_COLUMNS: &str = "s.id, s.url, s.title, s.site_url, s.poll_interval_minutes, s.enabled, s.etag, s.last_modified, s.last_polled_at, s.next_poll_at, s.last_error, s.created_at, s.updated_at, (SELECT COUNT(*) FROM feed_items i WHERE i.feed_id = s.id AND i.read_at IS NULL)";
+const ITEM_COLUMNS: &str = "i.id, i.feed_id, s.title, i.guid, i.title, i.url, i.summary, i.author, i.published_at, i.fetched_at, i.read_at";
+const DEFAULT_ITEM_LIMIT: u32 = 50;
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+pub trait FeedOperations: DbOperations {
+    fn create_feed_subscription(
+        &self,
+        input: &CreateFeedSubscriptionInput,
+        poll_interval_minutes: i64,
+    ) -> RusqliteResult<FeedSubscription> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let id = Uuid::new_v4().to_string();
+        let now = now_ms();
+        conn.execute(
+            "INSERT INTO feed_subscriptions (
+                id, url, title, poll_interval_minutes, enabled, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, 1, ?5, ?5)",
+            params![id, input.url, input.title, poll_interval_minutes, now],
+        )?;
+
+        Ok(FeedSubscription {
+            id,
+            url: input.url.clone(),
+            title: input.title.clone(),
+            site_url: None,
+            poll_interval_minutes,
+            enabled: true,
+            etag: None,
+            last_modified: None,
+            last_polled_at: None,
+            next_poll_at: None,
+            last_error: None,
+            unread_count: 0,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    fn get_feed_subscriptions(&self) -> RusqliteResult<Vec<FeedSubscription>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SUBSCRIPTION_COLUMNS} FROM feed_subscriptions s
+             ORDER BY COALESCE(s.title, s.url) COLLATE NOCASE"
+        ))?;
+        let iter = stmt.query_map([], row_to_feed_subscription)?;
+        iter.collect()
+    }
+
+    fn get_feed_subscription_by_id(&self, id: &str) -> RusqliteResult<Option<FeedSubscription>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SUBSCRIPTION_COLUMNS} FROM feed_subscriptions s WHERE s.id = ?1"
+        ))?;
+        match stmt.query_row(params![id], row_to_feed_subscription) {
+            Ok(feed) => Ok(Some(feed)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Enabled subscriptions never polled, or whose next poll is at or before `now_ms`.
+    fn get_due_feed_subscriptions(&self, now_ms: i64) -> RusqliteResult<Vec<FeedSubscription>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SUBSCRIPTION_COLUMNS} FROM feed_subscriptions s
+             WHERE s.enabled = 1 AND (s.next_poll_at IS NULL OR s.next_poll_at <= ?1)
+             ORDER BY COALESCE(s.next_poll_at, 0) ASC"
+        ))?;
+        let iter = stmt.query_map(params![now_ms], row_to_feed_subscription)?;
+        iter.collect()
+    }
+
+    fn update_feed_subscription(
+        &self,
+        input: &UpdateFeedSubscriptionInput,
+    ) -> RusqliteResult<Option<FeedSubscription>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let title = input
+            .title
+            .as_deref()
+            .map(str::trim)
+            .filter(|title| !title.is_empty());
+        let rows = conn.execute(
+            "UPDATE feed_subscriptions
+             SET title = CASE WHEN ?2 IS NULL THEN title ELSE ?3 END,
+                 poll_interval_minutes = COALESCE(?4, poll_interval_minutes),
+                 enabled = COALESCE(?5, enabled),
+                 next_poll_at = CASE WHEN ?4 IS NULL THEN next_poll_at ELSE NULL END,
+                 updated_at = ?6
+             WHERE id = ?1",
+            params![
+                input.id,
+                input.title,
+                title,
+                input.poll_interval_minutes,
+                input.enabled,
+                now_ms(),
+            ],
+        )?;
+        drop(conn);
+        if rows == 0 {
+            return Ok(None);
+        }
+        self.get_feed_subscription_by_id(&input.id)
+    }
+
+    /// Stores the poll result. The declared title only fills in a subscription without one.
+    fn record_feed_poll(&self, id: &str, update: &FeedPollUpdate) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE feed_subscriptions
+             SET title = COALESCE(title, ?2),
+                 site_url = COALESCE(?3, site_url),
+                 etag = CASE WHEN ?6 IS NULL THEN COALESCE(?4, etag) ELSE etag END,
+                 last_modified = CASE WHEN ?6 IS NULL THEN COALESCE(?5, last_modified) ELSE last_modified END,
+                 last_error = ?6,
+                 last_polled_at = ?7,
+                 next_poll_at = ?8
+             WHERE id = ?1",
+            params![
+                id,
+                update.feed_title,
+                update.site_url,
+                update.etag,
+                update.last_modified,
+                update.error,
+                update.polled_at,
+                update.next_poll_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Inserts entries not seen before and returns how many were new. Entries already stored,
+    /// read or not, are left alone.
+    fn insert_feed_items(&self, feed_id: &str, items: &[NewFeedItem]) -> RusqliteResult<usize> {
+        let binding = self.conn();
+        let mut conn = binding.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = now_ms();
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO feed_items (
+                    id, feed_id, guid, title, url, summary, author, published_at, fetched_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for item in items {
+                inserted += stmt.execute(params![
+                    Uuid::new_v4().to_string(),
+                    feed_id,
+                    item.guid,
+                    item.title,
+                    item.url,
+                    item.summary,
+                    item.author,
+                    item.published_at,
+                    now,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    fn get_feed_items(&self, query: &FeedItemQuery) -> RusqliteResult<Vec<FeedItem>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let mut sql = format!(
+            "SELECT {ITEM_COLUMNS} FROM feed_items i
+             JOIN feed_subscriptions s ON s.id = i.feed_id
+             WHERE 1 = 1"
+        );
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        if let Some(feed_id) = query.feed_id.as_deref().filter(|v| !v.is_empty()) {
+            sql.push_str(" AND i.feed_id = ?");
+            params_vec.push(Box::new(feed_id.to_string()));
+        }
+        if query.unread_only {
+            sql.push_str(" AND i.read_at IS NULL");
+        }
+        if let Some(since) = query.since {
+            sql.push_str(" AND COALESCE(i.published_at, i.fetched_at) >= ?");
+            params_vec.push(Box::new(since));
+        }
+        sql.push_str(" ORDER BY COALESCE(i.published_at, i.fetched_at) DESC, i.rowid DESC LIMIT ?");
+        params_vec.push(Box::new(query.limit.unwrap_or(DEFAULT_ITEM_LIMIT)));
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params_vec.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare(&sql)?;
+        let iter = stmt.query_map(params_refs.as_slice(), row_to_feed_item)?;
+        iter.collect()
+    }
+
+    fn count_unread_feed_items(&self, feed_id: Option<&str>) -> RusqliteResult<i64> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM feed_items
+             WHERE read_at IS NULL AND (?1 IS NULL OR feed_id = ?1)",
+            params![feed_id],
+            |row| row.get(0),
+        )
+    }
+
+    fn mark_feed_items_read(&self, ids: &[String]) -> RusqliteResult<usize> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let now = now_ms();
+        let mut stmt =
+            conn.prepare("UPDATE feed_items SET read_at = ?1 WHERE id = ?2 AND read_at IS NULL")?;
+        let mut updated = 0;
+        for id in ids {
+            updated += stmt.execute(params![now, id])?;
+        }
+        Ok(updated)
+    }
+
+    /// Marks every unread item, or those of one feed, published (or fetched) before `before`.
+    fn mark_feed_read(&self, feed_id: Option<&str>, before: Option<i64>) -> RusqliteResult<usize> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE feed_items SET read_at = ?1
+             WHERE read_at IS NULL
+               AND (?2 IS NULL OR feed_id = ?2)
+               AND (?3 IS NULL OR COALESCE(published_at, fetched_at) < ?3)",
+            params![now_ms(), feed_id, before],
+        )
+    }
+
+    fn delete_feed_subscription(&self, id: &str) -> RusqliteResult<bool> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute("DELETE FROM feed_items WHERE feed_id = ?1", params![id])?;
+        let rows_affected =
+            conn.execute("DELETE FROM feed_subscriptions WHERE id = ?1", params![id])?;
+        Ok(rows_affected > 0)
+    }
+}
+
+fn row_to_feed_subscription(row: &Row<'_>) -> RusqliteResult<FeedSubscription> {
+    Ok(FeedSubscription {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        title: row.get(2)?,
+        site_url: row.get(3)?,
+        poll_interval_minutes: row.get(4)?,
+        enabled: row.get(5)?,
+        etag: row.get(6)?,
+        last_modified: row.get(7)?,
+        last_polled_at: row.get(8)?,
+        next_poll_at: row.get(9)?,
+        last_error: row.get(10)?,
+        created_at: row.get(11)?,
+        updated_at: row.get(12)?,
+        unread_count: row.get(13)?,
+    })
+}
+
+fn row_to_feed_item(row: &Row<'_>) -> RusqliteResult<FeedItem> {
+    Ok(FeedItem {
+        id: row.get(0)?,
+        feed_id: row.get(1)?,
+        feed_title: row.get(2)?,
+        guid: row.get(3)?,
+        title: row.get(4)?,
+        url: row.get(5)?,
+        summary: row.get(6)?,
+        author: row.get(7)?,
+        published_at: row.get(8)?,
+        fetched_at: row.get(9)?,
+        read_at: row.get(10)?,
+    })
+}
@@ -0,0 +1,453 @@
+use super::DbOperations;
+use crate::db::models::{
+    CachedCalendarEvent, CachedGmailMessage, CachedTodoistTask, GmailCacheQuery,
+    IntegrationSyncState, SYNC_RESOURCE_CALENDAR_PREFIX, SYNC_RESOURCE_GMAIL,
+    SYNC_RESOURCE_TODOIST,
+};
+use rusqlite::{params, Result as RusqliteResult, Row};
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SYNC_STATE_COLUMNS: &str =
+    "connection_id, resource, cursor, status, last_error, item_count, last_synced_at, updated_at";
+const GMAIL_COLUMNS: &str =
+    "connection_id, id, thread_id, internal_date, from_addr, to_addr, subject, snippet, label_ids";
+const CALENDAR_COLUMNS: &str = "connection_id, calendar_id, id, summary, start_at, end_at, raw";
+const TODOIST_COLUMNS: &str = "connection_id, id, project_id, content, checked, raw";
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+/// Escapes `%`, `_` and `\` for use in a `LIKE ... ESCAPE '\'` pattern.
+fn like_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Local mirror of integration data kept up to date by the background sync engine.
+pub trait IntegrationCacheOperations: DbOperations {
+    fn get_integration_sync_state(
+        &self,
+        connection_id: &str,
+        resource: &str,
+    ) -> RusqliteResult<Option<IntegrationSyncState>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SYNC_STATE_COLUMNS} FROM integration_sync_state
+             WHERE connection_id = ?1 AND resource = ?2"
+        ))?;
+        let mut rows = stmt.query(params![connection_id, resource])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row_to_sync_state(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_integration_sync_states(
+        &self,
+        connection_id: Option<&str>,
+    ) -> RusqliteResult<Vec<IntegrationSyncState>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SYNC_STATE_COLUMNS} FROM integration_sync_state
+             WHERE ?1 IS NULL OR connection_id = ?1
+             ORDER BY connection_id, resource"
+        ))?;
+        let iter = stmt.query_map(params![connection_id], row_to_sync_state)?;
+        iter.collect()
+    }
+
+    /// Records a finished sync. On error the previous cursor and sync time are kept.
+    fn save_integration_sync_result(
+        &self,
+        connection_id: &str,
+        resource: &str,
+        result: Result<&str, &str>,
+    ) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let now = now_ms();
+        let item_count: u32 = match resource {
+            SYNC_RESOURCE_GMAIL => conn.query_row(
+                "SELECT COUNT(*) FROM cached_gmail_messages WHERE connection_id = ?1",
+                params![connection_id],
+                |row| row.get(0),
+            )?,
+            SYNC_RESOURCE_TODOIST => conn.query_row(
+                "SELECT COUNT(*) FROM cached_todoist_tasks WHERE connection_id = ?1 AND checked = 0",
+                params![connection_id],
+                |row| row.get(0),
+            )?,
+            _ => conn.query_row(
+                "SELECT COUNT(*) FROM cached_calendar_events WHERE connection_id = ?1 AND calendar_id = ?2",
+                params![
+                    connection_id,
+                    resource.trim_start_matches(SYNC_RESOURCE_CALENDAR_PREFIX)
+                ],
+                |row| row.get(0),
+            )?,
+        };
+
+        match result {
+            Ok(cursor) => conn.execute(
+                "INSERT INTO integration_sync_state
+                 (connection_id, resource, cursor, status, last_error, item_count, last_synced_at, updated_at)
+                 VALUES (?1, ?2, ?3, 'ok', NULL, ?4, ?5, ?5)
+                 ON CONFLICT(connection_id, resource) DO UPDATE SET
+                 cursor = excluded.cursor, status = 'ok', last_error = NULL,
+                 item_count = excluded.item_count, last_synced_at = excluded.last_synced_at,
+                 updated_at = excluded.updated_at",
+                params![connection_id, resource, cursor, item_count, now],
+            )?,
+            Err(error) => conn.execute(
+                "INSERT INTO integration_sync_state
+                 (connection_id, resource, status, last_error, item_count, updated_at)
+                 VALUES (?1, ?2, 'error', ?3, ?4, ?5)
+                 ON CONFLICT(connection_id, resource) DO UPDATE SET
+                 status = 'error', last_error = excluded.last_error,
+                 item_count = excluded.item_count, updated_at = excluded.updated_at",
+                params![connection_id, resource, error, item_count, now],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Flags cached resources as out of date after a write through the live API, so reads
+    /// bypass the cache until the next sync. `resource_prefix` matches e.g. `calendar:`.
+    fn mark_integration_sync_stale(
+        &self,
+        connection_id: &str,
+        resource_prefix: &str,
+    ) -> RusqliteResult<usize> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE integration_sync_state SET status = 'stale', updated_at = ?1
+             WHERE connection_id = ?2 AND status = 'ok' AND substr(resource, 1, length(?3)) = ?3",
+            params![now_ms(), connection_id, resource_prefix],
+        )
+    }
+
+    fn clear_integration_cache(&self, connection_id: &str) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let mut conn = binding.lock().unwrap();
+        let tx = conn.transaction()?;
+        for table in [
+            "integration_sync_state",
+            "cached_gmail_messages",
+            "cached_calendar_events",
+            "cached_todoist_tasks",
+        ] {
+            tx.execute(
+                &format!("DELETE FROM {table} WHERE connection_id = ?1"),
+                params![connection_id],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Applies one sync batch. With `replace_all` the existing rows are dropped first, which
+    /// is how full resyncs remove messages that disappeared while the cursor was invalid.
+    fn apply_cached_gmail_changes(
+        &self,
+        connection_id: &str,
+        upserts: &[CachedGmailMessage],
+        deleted_ids: &[String],
+        replace_all: bool,
+    ) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let mut conn = binding.lock().unwrap();
+        let tx = conn.transaction()?;
+        if replace_all {
+            tx.execute(
+                "DELETE FROM cached_gmail_messages WHERE connection_id = ?1",
+                params![connection_id],
+            )?;
+        }
+        for id in deleted_ids {
+            tx.execute(
+                "DELETE FROM cached_gmail_messages WHERE connection_id = ?1 AND id = ?2",
+                params![connection_id, id],
+            )?;
+        }
+        for message in upserts {
+            let label_ids = serde_json::to_string(&message.label_ids).unwrap_or_default();
+            tx.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO cached_gmail_messages ({GMAIL_COLUMNS})
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+                ),
+                params![
+                    connection_id,
+                    message.id,
+                    message.thread_id,
+                    message.internal_date,
+                    message.from,
+                    message.to,
+                    message.subject,
+                    message.snippet,
+                    label_ids,
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    fn search_cached_gmail_messages(
+        &self,
+        connection_id: &str,
+        query: &GmailCacheQuery,
+    ) -> RusqliteResult<Vec<CachedGmailMessage>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let mut sql =
+            format!("SELECT {GMAIL_COLUMNS} FROM cached_gmail_messages WHERE connection_id = ?");
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> =
+            vec![Box::new(connection_id.to_string())];
+        if let Some(from) = query.from.as_deref() {
+            sql.push_str(" AND from_addr LIKE ? ESCAPE '\\'");
+            params_vec.push(Box::new(like_pattern(from)));
+        }
+        if let Some(to) = query.to.as_deref() {
+            sql.push_str(" AND to_addr LIKE ? ESCAPE '\\'");
+            params_vec.push(Box::new(like_pattern(to)));
+        }
+        if let Some(text) = query.text.as_deref() {
+            sql.push_str(" AND (subject LIKE ? ESCAPE '\\' OR snippet LIKE ? ESCAPE '\\')");
+            params_vec.push(Box::new(like_pattern(text)));
+            params_vec.push(Box::new(like_pattern(text)));
+        }
+        if let Some(label_id) = query.label_id.as_deref() {
+            sql.push_str(" AND EXISTS (SELECT 1 FROM json_each(label_ids) WHERE value = ?)");
+            params_vec.push(Box::new(label_id.to_string()));
+        }
+        if let Some(after_ms) = query.after_ms {
+            sql.push_str(" AND internal_date >= ?");
+            params_vec.push(Box::new(after_ms));
+        }
+        if let Some(before_ms) = query.before_ms {
+            sql.push_str(" AND internal_date < ?");
+            params_vec.push(Box::new(before_ms));
+        }
+        sql.push_str(" ORDER BY internal_date DESC LIMIT ?");
+        params_vec.push(Box::new(query.limit));
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params_vec.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare(&sql)?;
+        let iter = stmt.query_map(params_refs.as_slice(), row_to_cached_gmail_message)?;
+        iter.collect()
+    }
+
+    fn apply_cached_calendar_changes(
+        &self,
+        connection_id: &str,
+        calendar_id: &str,
+        upserts: &[CachedCalendarEvent],
+        deleted_ids: &[String],
+        replace_all: bool,
+    ) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let mut conn = binding.lock().unwrap();
+        let tx = conn.transaction()?;
+        if replace_all {
+            tx.execute(
+                "DELETE FROM cached_calendar_events WHERE connection_id = ?1 AND calendar_id = ?2",
+                params![connection_id, calendar_id],
+            )?;
+        }
+        for id in deleted_ids {
+            tx.execute(
+                "DELETE FROM cached_calendar_events
+                 WHERE connection_id = ?1 AND calendar_id = ?2 AND id = ?3",
+                params![connection_id, calendar_id, id],
+            )?;
+        }
+        for event in upserts {
+            tx.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO cached_calendar_events ({CALENDAR_COLUMNS})
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+                ),
+                params![
+                    connection_id,
+                    calendar_id,
+                    event.id,
+                    event.summary,
+                    event.start_at,
+                    event.end_at,
+                    event.raw.to_string(),
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Events overlapping `[start_ms, end_ms)`, ordered by start time. `text` matches the
+    /// summary, description or location.
+    fn get_cached_calendar_events(
+        &self,
+        connection_id: &str,
+        calendar_id: &str,
+        start_ms: Option<i64>,
+        end_ms: Option<i64>,
+        text: Option<&str>,
+        limit: u32,
+    ) -> RusqliteResult<Vec<CachedCalendarEvent>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {CALENDAR_COLUMNS} FROM cached_calendar_events
+             WHERE connection_id = ?1 AND calendar_id = ?2
+               AND (?3 IS NULL OR end_at IS NULL OR end_at > ?3)
+               AND (?4 IS NULL OR start_at < ?4)
+               AND (?5 IS NULL
+                    OR summary LIKE ?5 ESCAPE '\\'
+                    OR json_extract(raw, '$.description') LIKE ?5 ESCAPE '\\'
+                    OR json_extract(raw, '$.location') LIKE ?5 ESCAPE '\\')
+             ORDER BY start_at
+             LIMIT ?6"
+        ))?;
+        let iter = stmt.query_map(
+            params![
+                connection_id,
+                calendar_id,
+                start_ms,
+                end_ms,
+                text.map(like_pattern),
+                limit
+            ],
+            row_to_cached_calendar_event,
+        )?;
+        iter.collect()
+    }
+
+    fn apply_cached_todoist_changes(
+        &self,
+        connection_id: &str,
+        upserts: &[CachedTodoistTask],
+        deleted_ids: &[String],
+        replace_all: bool,
+    ) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let mut conn = binding.lock().unwrap();
+        let tx = conn.transaction()?;
+        if replace_all {
+            tx.execute(
+                "DELETE FROM cached_todoist_tasks WHERE connection_id = ?1",
+                params![connection_id],
+            )?;
+        }
+        for id in deleted_ids {
+            tx.execute(
+                "DELETE FROM cached_todoist_tasks WHERE connection_id = ?1 AND id = ?2",
+                params![connection_id, id],
+            )?;
+        }
+        for task in upserts {
+            tx.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO cached_todoist_tasks ({TODOIST_COLUMNS})
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+                ),
+                params![
+                    connection_id,
+                    task.id,
+                    task.project_id,
+                    task.content,
+                    task.checked,
+                    task.raw.to_string(),
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Open tasks, optionally limited to one project.
+    fn get_cached_todoist_tasks(
+        &self,
+        connection_id: &str,
+        project_id: Option<&str>,
+    ) -> RusqliteResult<Vec<CachedTodoistTask>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TODOIST_COLUMNS} FROM cached_todoist_tasks
+             WHERE connection_id = ?1 AND checked = 0 AND (?2 IS NULL OR project_id = ?2)
+             ORDER BY rowid"
+        ))?;
+        let iter = stmt.query_map(
+            params![connection_id, project_id],
+            row_to_cached_todoist_task,
+        )?;
+        iter.collect()
+    }
+}
+
+fn parse_json_column(raw: Option<String>) -> Value {
+    raw.and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or(Value::Null)
+}
+
+fn row_to_sync_state(row: &Row<'_>) -> RusqliteResult<IntegrationSyncState> {
+    Ok(IntegrationSyncState {
+        connection_id: row.get(0)?,
+        resource: row.get(1)?,
+        cursor: row.get(2)?,
+        status: row.get(3)?,
+        last_error: row.get(4)?,
+        item_count: row.get(5)?,
+        last_synced_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+fn row_to_cached_gmail_message(row: &Row<'_>) -> RusqliteResult<CachedGmailMessage> {
+    let label_ids: Option<String> = row.get(8)?;
+    Ok(CachedGmailMessage {
+        connection_id: row.get(0)?,
+        id: row.get(1)?,
+        thread_id: row.get(2)?,
+        internal_date: row.get(3)?,
+        from: row.get(4)?,
+        to: row.get(5)?,
+        subject: row.get(6)?,
+        snippet: row.get(7)?,
+        label_ids: label_ids
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default(),
+    })
+}
+
+fn row_to_cached_calendar_event(row: &Row<'_>) -> RusqliteResult<CachedCalendarEvent> {
+    Ok(CachedCalendarEvent {
+        connection_id: row.get(0)?,
+        calendar_id: row.get(1)?,
+        id: row.get(2)?,
+        summary: row.get(3)?,
+        start_at: row.get(4)?,
+        end_at: row.get(5)?,
+        raw: parse_json_column(row.get(6)?),
+    })
+}
+
+fn row_to_cached_todoist_task(row: &Row<'_>) -> RusqliteResult<CachedTodoistTask> {
+    Ok(CachedTodoistTask {
+        connection_id: row.get(0)?,
+        id: row.get(1)?,
+        project_id: row.get(2)?,
+        content: row.get(3)?,
+        checked: row.get(4)?,
+        raw: parse_json_column(row.get(5)?),
+    })
+}
@@ -95,9 +95,9 @@ pub trait MessageOperations: DbOperations {
 
         conn.execute(
             "INSERT INTO message_tool_executions (
-                id, message_id, tool_name, parameters, result, success, duration, timestamp, error, iteration_number
+                id, message_id, tool_name, parameters, result, success, duration, timestamp, error, iteration_number, error_kind
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 input.id,
                 input.message_id,
@@ -109,6 +109,7 @@ pub trait MessageOperations: DbOperations {
                 input.timestamp_ms,
                 input.error,
                 input.iteration_number,
+                input.error_kind,
             ],
         )?;
 
@@ -122,6 +123,7 @@ pub trait MessageOperations: DbOperations {
             duration_ms: input.duration_ms,
             timestamp_ms: input.timestamp_ms,
             error: input.error,
+            error_kind: input.error_kind,
             iteration_number: input.iteration_number,
         })
     }
@@ -232,7 +234,7 @@ pub trait MessageOperations: DbOperations {
 
         let tool_executions_start = Instant::now();
         let mut tool_exec_stmt = conn.prepare(
-            "SELECT message_id, id, tool_name, parameters, result, success, duration, timestamp, error, iteration_number
+            "SELECT message_id, id, tool_name, parameters, result, success, duration, timestamp, error, iteration_number, error_kind
              FROM message_tool_executions
              WHERE message_id IN (SELECT id FROM messages WHERE conversation_id = ?1)"
         )?;
@@ -260,6 +262,7 @@ pub trait MessageOperations: DbOperations {
                     duration_ms: row.get(6)?,
                     timestamp_ms,
                     error: row.get(8)?,
+                    error_kind: row.get(10)?,
                     iteration_number: row.get(9)?,
                 },
             ))
@@ -2,28 +2,42 @@ use rusqlite::Connection;
 use std::sync::{Arc, Mutex};
 
 mod agent_sessions;
+mod agent_triggers;
+mod audit_log;
 mod branches;
 mod conversations;
 mod custom_backends;
+mod feeds;
+mod integration_cache;
 mod integration_connections;
 mod mcp_servers;
 mod messages;
 mod models;
 mod preferences;
+mod scheduled_jobs;
 mod system_prompts;
+mod tool_approval_rules;
 mod usage;
+mod web_auth_profiles;
 
 pub use agent_sessions::*;
+pub use agent_triggers::*;
+pub use audit_log::*;
 pub use branches::*;
 pub use conversations::*;
 pub use custom_backends::*;
+pub use feeds::*;
+pub use integration_cache::*;
 pub use integration_connections::*;
 pub use mcp_servers::*;
 pub use messages::*;
 pub use models::*;
 pub use preferences::*;
+pub use scheduled_jobs::*;
 pub use system_prompts::*;
+pub use tool_approval_rules::*;
 pub use usage::*;
+pub use web_auth_profiles::*;
 
 pub trait DbOperations {
     fn conn(&self) -> Arc<Mutex<Connection>>;
@@ -0,0 +1,348 @@
+use super::DbOperations;
+use crate::db::models::{
+    CreateScheduledJobInput, ScheduledJob, ScheduledJobRun, UpdateScheduledJobInput,
+};
+use rusqlite::{params, Result as RusqliteResult, Row};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+const JOB_COLUMNS: &str = "id, name, prompt, schedule, provider, model, custom_backend_id, system_prompt, conversation_id, approval_policy, enabled, next_run_at, last_run_at, last_status, created_at, updated_at";
+const RUN_COLUMNS: &str = "id, job_id, triggered_by, status, conversation_id, message_id, output_preview, error, started_at, finished_at";
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+pub trait ScheduledJobOperations: DbOperations {
+    fn create_scheduled_job(
+        &self,
+        input: &CreateScheduledJobInput,
+        approval_policy: &str,
+        next_run_at: Option<i64>,
+    ) -> RusqliteResult<ScheduledJob> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let id = Uuid::new_v4().to_string();
+        let now = now_ms();
+        let enabled = input.enabled.unwrap_or(true);
+
+        conn.execute(
+            "INSERT INTO scheduled_jobs (
+                id,
+                name,
+                prompt,
+                schedule,
+                provider,
+                model,
+                custom_backend_id,
+                system_prompt,
+                conversation_id,
+                approval_policy,
+                enabled,
+                next_run_at,
+                created_at,
+                updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                id,
+                input.name,
+                input.prompt,
+                input.schedule,
+                input.provider,
+                input.model,
+                input.custom_backend_id,
+                input.system_prompt,
+                input.conversation_id,
+                approval_policy,
+                enabled,
+                next_run_at,
+                now,
+                now,
+            ],
+        )?;
+
+        Ok(ScheduledJob {
+            id,
+            name: input.name.clone(),
+            prompt: input.prompt.clone(),
+            schedule: input.schedule.clone(),
+            provider: input.provider.clone(),
+            model: input.model.clone(),
+            custom_backend_id: input.custom_backend_id.clone(),
+            system_prompt: input.system_prompt.clone(),
+            conversation_id: input.conversation_id.clone(),
+            approval_policy: approval_policy.to_string(),
+            enabled,
+            next_run_at,
+            last_run_at: None,
+            last_status: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    fn get_scheduled_jobs(&self) -> RusqliteResult<Vec<ScheduledJob>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {JOB_COLUMNS} FROM scheduled_jobs ORDER BY created_at DESC"
+        ))?;
+        let iter = stmt.query_map([], row_to_scheduled_job)?;
+        iter.collect()
+    }
+
+    fn get_scheduled_job_by_id(&self, id: &str) -> RusqliteResult<Option<ScheduledJob>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {JOB_COLUMNS} FROM scheduled_jobs WHERE id = ?1"
+        ))?;
+        match stmt.query_row(params![id], row_to_scheduled_job) {
+            Ok(job) => Ok(Some(job)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Enabled jobs whose next run is at or before `now_ms`, oldest first.
+    fn get_due_scheduled_jobs(&self, now_ms: i64) -> RusqliteResult<Vec<ScheduledJob>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {JOB_COLUMNS} FROM scheduled_jobs
+             WHERE enabled = 1 AND next_run_at IS NOT NULL AND next_run_at <= ?1
+             ORDER BY next_run_at ASC"
+        ))?;
+        let iter = stmt.query_map(params![now_ms], row_to_scheduled_job)?;
+        iter.collect()
+    }
+
+    fn update_scheduled_job(
+        &self,
+        input: &UpdateScheduledJobInput,
+    ) -> RusqliteResult<Option<ScheduledJob>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let mut updates = Vec::new();
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(ref value) = input.name {
+            updates.push("name = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.prompt {
+            updates.push("prompt = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.schedule {
+            updates.push("schedule = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.provider {
+            updates.push("provider = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.model {
+            updates.push("model = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.custom_backend_id {
+            updates.push("custom_backend_id = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.system_prompt {
+            updates.push("system_prompt = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(ref value) = input.conversation_id {
+            // An empty id switches the job back to a new conversation per run.
+            updates.push("conversation_id = ?");
+            params_vec.push(Box::new(Some(value.clone()).filter(|id| !id.is_empty())));
+        }
+        if let Some(ref value) = input.approval_policy {
+            updates.push("approval_policy = ?");
+            params_vec.push(Box::new(value.clone()));
+        }
+        if let Some(value) = input.enabled {
+            updates.push("enabled = ?");
+            params_vec.push(Box::new(value));
+        }
+
+        if updates.is_empty() {
+            drop(conn);
+            return self.get_scheduled_job_by_id(&input.id);
+        }
+
+        updates.push("updated_at = ?");
+        params_vec.push(Box::new(now_ms()));
+        params_vec.push(Box::new(input.id.clone()));
+
+        let sql = format!(
+            "UPDATE scheduled_jobs SET {} WHERE id = ?",
+            updates.join(", ")
+        );
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params_vec.iter().map(|value| value.as_ref()).collect();
+        let rows_affected = conn.execute(&sql, params_refs.as_slice())?;
+        if rows_affected == 0 {
+            return Ok(None);
+        }
+
+        drop(conn);
+        self.get_scheduled_job_by_id(&input.id)
+    }
+
+    fn set_scheduled_job_next_run(&self, id: &str, next_run_at: Option<i64>) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE scheduled_jobs SET next_run_at = ?1 WHERE id = ?2",
+            params![next_run_at, id],
+        )?;
+        Ok(())
+    }
+
+    fn delete_scheduled_job(&self, id: &str) -> RusqliteResult<bool> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "DELETE FROM scheduled_job_runs WHERE job_id = ?1",
+            params![id],
+        )?;
+        let rows_affected =
+            conn.execute("DELETE FROM scheduled_jobs WHERE id = ?1", params![id])?;
+        Ok(rows_affected > 0)
+    }
+
+    fn start_scheduled_job_run(
+        &self,
+        job_id: &str,
+        trigger: &str,
+    ) -> RusqliteResult<ScheduledJobRun> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let id = Uuid::new_v4().to_string();
+        let now = now_ms();
+        conn.execute(
+            "INSERT INTO scheduled_job_runs (id, job_id, triggered_by, status, started_at)
+             VALUES (?1, ?2, ?3, 'running', ?4)",
+            params![id, job_id, trigger, now],
+        )?;
+        conn.execute(
+            "UPDATE scheduled_jobs SET last_run_at = ?1, last_status = 'running' WHERE id = ?2",
+            params![now, job_id],
+        )?;
+        Ok(ScheduledJobRun {
+            id,
+            job_id: job_id.to_string(),
+            triggered_by: trigger.to_string(),
+            status: "running".to_string(),
+            conversation_id: None,
+            message_id: None,
+            output_preview: None,
+            error: None,
+            started_at: now,
+            finished_at: None,
+        })
+    }
+
+    fn finish_scheduled_job_run(&self, run: &ScheduledJobRun) -> RusqliteResult<()> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "UPDATE scheduled_job_runs
+             SET status = ?1, conversation_id = ?2, message_id = ?3, output_preview = ?4, error = ?5, finished_at = ?6
+             WHERE id = ?7",
+            params![
+                run.status,
+                run.conversation_id,
+                run.message_id,
+                run.output_preview,
+                run.error,
+                run.finished_at,
+                run.id,
+            ],
+        )?;
+        conn.execute(
+            "UPDATE scheduled_jobs SET last_status = ?1 WHERE id = ?2",
+            params![run.status, run.job_id],
+        )?;
+        Ok(())
+    }
+
+    fn get_scheduled_job_runs(
+        &self,
+        job_id: &str,
+        limit: u32,
+    ) -> RusqliteResult<Vec<ScheduledJobRun>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RUN_COLUMNS} FROM scheduled_job_runs
+             WHERE job_id = ?1
+             ORDER BY started_at DESC
+             LIMIT ?2"
+        ))?;
+        let iter = stmt.query_map(params![job_id, limit], row_to_scheduled_job_run)?;
+        iter.collect()
+    }
+
+    /// Marks runs left in `running` by a previous app session as failed.
+    fn fail_interrupted_scheduled_job_runs(&self) -> RusqliteResult<usize> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let now = now_ms();
+        conn.execute(
+            "UPDATE scheduled_jobs SET last_status = 'failed'
+             WHERE id IN (SELECT job_id FROM scheduled_job_runs WHERE status = 'running')",
+            [],
+        )?;
+        conn.execute(
+            "UPDATE scheduled_job_runs
+             SET status = 'failed', error = 'Interrupted before the run finished', finished_at = ?1
+             WHERE status = 'running'",
+            params![now],
+        )
+    }
+}
+
+fn row_to_scheduled_job(row: &Row<'_>) -> RusqliteResult<ScheduledJob> {
+    Ok(ScheduledJob {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        prompt: row.get(2)?,
+        schedule: row.get(3)?,
+        provider: row.get(4)?,
+        model: row.get(5)?,
+        custom_backend_id: row.get(6)?,
+        system_prompt: row.get(7)?,
+        conversation_id: row.get(8)?,
+        approval_policy: row.get(9)?,
+        enabled: row.get(10)?,
+        next_run_at: row.get(11)?,
+        last_run_at: row.get(12)?,
+        last_status: row.get(13)?,
+        created_at: row.get(14)?,
+        updated_at: row.get(15)?,
+    })
+}
+
+fn row_to_scheduled_job_run(row: &Row<'_>) -> RusqliteResult<ScheduledJobRun> {
+    Ok(ScheduledJobRun {
+        id: row.get(0)?,
+        job_id: row.get(1)?,
+        triggered_by: row.get(2)?,
+        status: row.get(3)?,
+        conversation_id: row.get(4)?,
+        message_id: row.get(5)?,
+        output_preview: row.get(6)?,
+        error: row.get(7)?,
+        started_at: row.get(8)?,
+        finished_at: row.get(9)?,
+    })
+}
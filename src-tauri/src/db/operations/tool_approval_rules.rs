@@ -0,0 +1,118 @@
+use super::DbOperations;
+use crate::db::models::{CreateToolApprovalRuleInput, ToolApprovalArgCondition, ToolApprovalRule};
+use rusqlite::{params, Result as RusqliteResult, Row};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+pub trait ToolApprovalRuleOperations: DbOperations {
+    fn create_tool_approval_rule(
+        &self,
+        input: &CreateToolApprovalRuleInput,
+    ) -> RusqliteResult<ToolApprovalRule> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let id = Uuid::new_v4().to_string();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        let arg_conditions =
+            serde_json::to_string(&input.arg_conditions).unwrap_or_else(|_| "[]".to_string());
+
+        conn.execute(
+            "INSERT INTO tool_approval_rules (
+                id,
+                name,
+                tool_pattern,
+                arg_conditions,
+                action,
+                scope,
+                conversation_id,
+                session_id,
+                expires_at,
+                created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id,
+                input.name,
+                input.tool_pattern,
+                arg_conditions,
+                input.action,
+                input.scope,
+                input.conversation_id,
+                input.session_id,
+                input.expires_at,
+                now,
+            ],
+        )?;
+
+        Ok(ToolApprovalRule {
+            id,
+            name: input.name.clone(),
+            tool_pattern: input.tool_pattern.clone(),
+            arg_conditions: input.arg_conditions.clone(),
+            action: input.action.clone(),
+            scope: input.scope.clone(),
+            conversation_id: input.conversation_id.clone(),
+            session_id: input.session_id.clone(),
+            expires_at: input.expires_at,
+            created_at: now,
+        })
+    }
+
+    fn get_tool_approval_rules(&self) -> RusqliteResult<Vec<ToolApprovalRule>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, tool_pattern, arg_conditions, action, scope, conversation_id, session_id, expires_at, created_at
+             FROM tool_approval_rules
+             ORDER BY created_at DESC",
+        )?;
+        let iter = stmt.query_map([], row_to_tool_approval_rule)?;
+        iter.collect()
+    }
+
+    fn delete_tool_approval_rule(&self, id: &str) -> RusqliteResult<bool> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let rows_affected =
+            conn.execute("DELETE FROM tool_approval_rules WHERE id = ?1", params![id])?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Removes rules that expired before `now_ms` and session grants that belong to
+    /// a different app session than `current_session_id`.
+    fn delete_stale_tool_approval_rules(
+        &self,
+        now_ms: i64,
+        current_session_id: &str,
+    ) -> RusqliteResult<usize> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        conn.execute(
+            "DELETE FROM tool_approval_rules
+             WHERE (expires_at IS NOT NULL AND expires_at <= ?1)
+                OR (scope = 'session' AND (session_id IS NULL OR session_id != ?2))",
+            params![now_ms, current_session_id],
+        )
+    }
+}
+
+fn row_to_tool_approval_rule(row: &Row<'_>) -> RusqliteResult<ToolApprovalRule> {
+    let arg_conditions: String = row.get(3)?;
+    let arg_conditions =
+        serde_json::from_str::<Vec<ToolApprovalArgCondition>>(&arg_conditions).unwrap_or_default();
+    Ok(ToolApprovalRule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        tool_pattern: row.get(2)?,
+        arg_conditions,
+        action: row.get(4)?,
+        scope: row.get(5)?,
+        conversation_id: row.get(6)?,
+        session_id: row.get(7)?,
+        expires_at: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
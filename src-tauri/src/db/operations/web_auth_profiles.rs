@@ -0,0 +1,122 @@
+use super::DbOperations;
+use crate::db::models::{SaveWebAuthProfileInput, WebAuthProfile};
+use rusqlite::{params, Result as RusqliteResult, Row};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+const PROFILE_COLUMNS: &str =
+    "id, name, kind, host_patterns, header_name, username, secret, created_at, updated_at";
+
+pub trait WebAuthProfileOperations: DbOperations {
+    fn save_web_auth_profile(
+        &self,
+        input: &SaveWebAuthProfileInput,
+    ) -> RusqliteResult<WebAuthProfile> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        let host_patterns =
+            serde_json::to_string(&input.host_patterns).unwrap_or_else(|_| "[]".to_string());
+
+        let id = match &input.id {
+            Some(id) => {
+                let rows = conn.execute(
+                    "UPDATE web_auth_profiles
+                     SET name = ?2, kind = ?3, host_patterns = ?4, header_name = ?5,
+                         username = ?6, secret = COALESCE(?7, secret), updated_at = ?8
+                     WHERE id = ?1",
+                    params![
+                        id,
+                        input.name,
+                        input.kind,
+                        host_patterns,
+                        input.header_name,
+                        input.username,
+                        input.secret,
+                        now,
+                    ],
+                )?;
+                if rows == 0 {
+                    return Err(rusqlite::Error::QueryReturnedNoRows);
+                }
+                id.clone()
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                conn.execute(
+                    "INSERT INTO web_auth_profiles (
+                        id, name, kind, host_patterns, header_name, username, secret,
+                        created_at, updated_at
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+                    params![
+                        id,
+                        input.name,
+                        input.kind,
+                        host_patterns,
+                        input.header_name,
+                        input.username,
+                        input.secret.as_deref().unwrap_or(""),
+                        now,
+                    ],
+                )?;
+                id
+            }
+        };
+
+        conn.query_row(
+            &format!("SELECT {PROFILE_COLUMNS} FROM web_auth_profiles WHERE id = ?1"),
+            params![id],
+            row_to_web_auth_profile,
+        )
+    }
+
+    fn get_web_auth_profiles(&self) -> RusqliteResult<Vec<WebAuthProfile>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {PROFILE_COLUMNS} FROM web_auth_profiles ORDER BY name COLLATE NOCASE"
+        ))?;
+        let iter = stmt.query_map([], row_to_web_auth_profile)?;
+        iter.collect()
+    }
+
+    fn get_web_auth_profile_by_name(&self, name: &str) -> RusqliteResult<Option<WebAuthProfile>> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {PROFILE_COLUMNS} FROM web_auth_profiles WHERE name = ?1"
+        ))?;
+        match stmt.query_row(params![name], row_to_web_auth_profile) {
+            Ok(profile) => Ok(Some(profile)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn delete_web_auth_profile(&self, id: &str) -> RusqliteResult<bool> {
+        let binding = self.conn();
+        let conn = binding.lock().unwrap();
+        let rows_affected =
+            conn.execute("DELETE FROM web_auth_profiles WHERE id = ?1", params![id])?;
+        Ok(rows_affected > 0)
+    }
+}
+
+fn row_to_web_auth_profile(row: &Row<'_>) -> RusqliteResult<WebAuthProfile> {
+    let host_patterns: String = row.get(3)?;
+    Ok(WebAuthProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: row.get(2)?,
+        host_patterns: serde_json::from_str(&host_patterns).unwrap_or_default(),
+        header_name: row.get(4)?,
+        username: row.get(5)?,
+        secret: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
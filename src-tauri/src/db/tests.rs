@@ -1,8 +1,13 @@
 use super::{
-    BranchOperations, ConversationOperations, CreateIntegrationConnectionInput,
-    CreateMcpServerInput, Db, DbOperations, IncomingAttachment, IntegrationConnectionOperations,
-    McpServerOperations, MessageOperations, Model, ModelOperations, PreferenceOperations,
-    UpdateIntegrationConnectionInput, UpdateMcpServerInput,
+    AgentTriggerOperations, AppendAuditLogInput, AuditLogOperations, AuditLogQuery,
+    BranchOperations, CachedGmailMessage, ConversationOperations, CreateAgentTriggerInput,
+    CreateFeedSubscriptionInput, CreateIntegrationConnectionInput, CreateMcpServerInput,
+    CreateScheduledJobInput, Db, DbOperations, FeedItemQuery, FeedOperations, FeedPollUpdate,
+    GmailCacheQuery, IncomingAttachment, IntegrationCacheOperations,
+    IntegrationConnectionOperations, McpServerOperations, MessageOperations, Model,
+    ModelOperations, NewFeedItem, PreferenceOperations, SaveWebAuthProfileInput,
+    ScheduledJobOperations, UpdateIntegrationConnectionInput, UpdateMcpServerInput,
+    WebAuthProfileOperations, SYNC_RESOURCE_GMAIL, WEB_AUTH_BEARER,
 };
 use rusqlite::params;
 use uuid::Uuid;
@@ -334,3 +339,358 @@ fn repair_message_tree_adds_orphaned_messages() {
     assert_eq!(repaired_consistency.orphaned_count, 0);
     assert!(repaired_consistency.is_consistent);
 }
+
+#[test]
+fn scheduled_jobs_due_and_interrupted_runs() {
+    let db = setup_db();
+
+    let input = CreateScheduledJobInput {
+        name: "Daily summary".to_string(),
+        prompt: "Summarize unread mail".to_string(),
+        schedule: "0 8 * * 1-5".to_string(),
+        provider: "openai".to_string(),
+        model: "gpt-4o".to_string(),
+        custom_backend_id: None,
+        system_prompt: None,
+        conversation_id: None,
+        approval_policy: None,
+        enabled: None,
+    };
+    let job = db
+        .create_scheduled_job(&input, "deny", Some(1_000))
+        .unwrap();
+    assert!(job.enabled);
+
+    assert!(db.get_due_scheduled_jobs(999).unwrap().is_empty());
+    assert_eq!(db.get_due_scheduled_jobs(1_000).unwrap().len(), 1);
+
+    let run = db.start_scheduled_job_run(&job.id, "schedule").unwrap();
+    assert_eq!(db.fail_interrupted_scheduled_job_runs().unwrap(), 1);
+    let runs = db.get_scheduled_job_runs(&job.id, 10).unwrap();
+    assert_eq!(runs[0].id, run.id);
+    assert_eq!(runs[0].status, "failed");
+    let job = db.get_scheduled_job_by_id(&job.id).unwrap().unwrap();
+    assert_eq!(job.last_status.as_deref(), Some("failed"));
+
+    assert!(db.delete_scheduled_job(&job.id).unwrap());
+    assert!(db.get_scheduled_job_runs(&job.id, 10).unwrap().is_empty());
+}
+
+#[test]
+fn agent_trigger_events_dedupe_and_count_runs() {
+    let db = setup_db();
+
+    let input = CreateAgentTriggerInput {
+        name: "Inbox notes".to_string(),
+        source: "vault".to_string(),
+        config: serde_json::json!({ "path_prefix": "Inbox" }),
+        prompt: "File new notes".to_string(),
+        provider: "openai".to_string(),
+        model: "gpt-4o".to_string(),
+        custom_backend_id: None,
+        system_prompt: None,
+        conversation_id: None,
+        approval_policy: None,
+        enabled: None,
+        max_runs_per_hour: None,
+    };
+    let trigger = db.create_agent_trigger(&input, "deny").unwrap();
+    let payload = serde_json::json!({ "path": "Inbox/a.md" });
+
+    assert!(db
+        .record_agent_trigger_event(&trigger.id, "vault:created:a", "a", &payload, "pending")
+        .unwrap());
+    assert!(!db
+        .record_agent_trigger_event(&trigger.id, "vault:created:a", "a", &payload, "pending")
+        .unwrap());
+    assert!(db
+        .has_agent_trigger_event(&trigger.id, "vault:created:a")
+        .unwrap());
+
+    let pending = db
+        .get_pending_agent_trigger_events(&trigger.id, 10)
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    let ids: Vec<String> = pending.iter().map(|event| event.id.clone()).collect();
+    db.dispatch_agent_trigger_events(&trigger.id, &ids, "run-1")
+        .unwrap();
+    assert_eq!(
+        db.count_agent_trigger_runs_since(&trigger.id, 0).unwrap(),
+        1
+    );
+    let first_run = db
+        .first_agent_trigger_run_since(&trigger.id, 0)
+        .unwrap()
+        .expect("run was dispatched");
+    assert_eq!(
+        db.first_agent_trigger_run_since(&trigger.id, first_run + 1)
+            .unwrap(),
+        None
+    );
+    assert!(db
+        .get_pending_agent_trigger_events(&trigger.id, 10)
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(db.fail_interrupted_agent_trigger_events().unwrap(), 1);
+    let events = db.get_agent_trigger_events(&trigger.id, 10).unwrap();
+    assert_eq!(events[0].status, "failed");
+
+    assert!(db.delete_agent_trigger(&trigger.id).unwrap());
+    assert!(db
+        .get_agent_trigger_events(&trigger.id, 10)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn gmail_cache_search_and_sync_state() {
+    let db = setup_db();
+    let message = |id: &str, from: &str, date: i64, labels: &[&str]| CachedGmailMessage {
+        connection_id: "conn".to_string(),
+        id: id.to_string(),
+        thread_id: format!("t-{id}"),
+        internal_date: date,
+        from: from.to_string(),
+        to: "me@example.com".to_string(),
+        subject: format!("Subject {id}"),
+        snippet: "100% done".to_string(),
+        label_ids: labels.iter().map(|label| label.to_string()).collect(),
+    };
+    db.apply_cached_gmail_changes(
+        "conn",
+        &[
+            message("a", "Ada <ada@example.com>", 1_000, &["INBOX"]),
+            message("b", "Bob <bob@example.com>", 2_000, &["INBOX", "STARRED"]),
+            message("c", "Ada <ada@example.com>", 3_000, &["SENT"]),
+        ],
+        &[],
+        true,
+    )
+    .unwrap();
+    db.apply_cached_gmail_changes("conn", &[], &["c".to_string()], false)
+        .unwrap();
+
+    let search = |query: GmailCacheQuery| {
+        db.search_cached_gmail_messages("conn", &GmailCacheQuery { limit: 10, ..query })
+            .unwrap()
+            .into_iter()
+            .map(|message| message.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(search(GmailCacheQuery::default()), vec!["b", "a"]);
+    assert_eq!(
+        search(GmailCacheQuery {
+            from: Some("ada@".to_string()),
+            ..GmailCacheQuery::default()
+        }),
+        vec!["a"]
+    );
+    assert_eq!(
+        search(GmailCacheQuery {
+            label_id: Some("STARRED".to_string()),
+            ..GmailCacheQuery::default()
+        }),
+        vec!["b"]
+    );
+    assert_eq!(
+        search(GmailCacheQuery {
+            text: Some("done".to_string()),
+            after_ms: Some(1_500),
+            ..GmailCacheQuery::default()
+        }),
+        vec!["b"]
+    );
+    // LIKE wildcards in user input are matched literally.
+    assert!(search(GmailCacheQuery {
+        text: Some("1%".to_string()),
+        ..GmailCacheQuery::default()
+    })
+    .is_empty());
+
+    db.save_integration_sync_result("conn", SYNC_RESOURCE_GMAIL, Ok("42"))
+        .unwrap();
+    db.save_integration_sync_result("conn", SYNC_RESOURCE_GMAIL, Err("HTTP 500"))
+        .unwrap();
+    let state = db
+        .get_integration_sync_state("conn", SYNC_RESOURCE_GMAIL)
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.status, "error");
+    assert_eq!(state.cursor.as_deref(), Some("42"));
+    assert_eq!(state.item_count, 2);
+
+    db.save_integration_sync_result("conn", SYNC_RESOURCE_GMAIL, Ok("43"))
+        .unwrap();
+    assert_eq!(db.mark_integration_sync_stale("conn", "gmail").unwrap(), 1);
+    assert_eq!(
+        db.get_integration_sync_state("conn", SYNC_RESOURCE_GMAIL)
+            .unwrap()
+            .unwrap()
+            .status,
+        "stale"
+    );
+
+    db.clear_integration_cache("conn").unwrap();
+    assert!(search(GmailCacheQuery::default()).is_empty());
+    assert!(db.get_integration_sync_states(None).unwrap().is_empty());
+}
+
+#[test]
+fn audit_log_is_append_only_and_outlives_conversations() {
+    let db = setup_db();
+    let conversation_id = "conv-audit";
+    db.get_or_create_conversation(conversation_id).unwrap();
+
+    let entry = |tool_name: &str, outcome: &str| AppendAuditLogInput {
+        execution_id: Uuid::new_v4().to_string(),
+        tool_name: tool_name.to_string(),
+        integration_id: Some("gmail".to_string()),
+        connection_id: Some("conn-1".to_string()),
+        account_label: Some("ada@example.com".to_string()),
+        args: serde_json::json!({ "to": "bob@example.com" }),
+        decision: "approved".to_string(),
+        approved_by: Some("user".to_string()),
+        approval_rule_id: None,
+        outcome: outcome.to_string(),
+        error: None,
+        error_kind: None,
+        external_resource_id: Some("msg-1".to_string()),
+        conversation_id: Some(conversation_id.to_string()),
+        message_id: None,
+        duration_ms: 5,
+    };
+    db.append_audit_log_entry(&entry("gmail.send_message", "success"))
+        .unwrap();
+    db.append_audit_log_entry(&entry("gmail.create_draft", "error"))
+        .unwrap();
+    db.append_audit_log_entry(&entry("todoist.create_task", "success"))
+        .unwrap();
+
+    db.delete_conversation(conversation_id).unwrap();
+
+    let all = db.query_audit_log(&AuditLogQuery::default()).unwrap();
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[0].args["to"], "bob@example.com");
+
+    let gmail = db
+        .query_audit_log(&AuditLogQuery {
+            tool_name: Some("gmail.*".to_string()),
+            outcome: Some("success".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(gmail.len(), 1);
+    assert_eq!(gmail[0].tool_name, "gmail.send_message");
+
+    let binding = db.conn();
+    let conn = binding.lock().unwrap();
+    assert!(conn
+        .execute("UPDATE audit_log SET outcome = 'success'", [])
+        .is_err());
+    assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+}
+
+#[test]
+fn web_auth_profiles_keep_secrets_out_of_serialization() {
+    let db = setup_db();
+    let input = SaveWebAuthProfileInput {
+        id: None,
+        name: "github".to_string(),
+        kind: WEB_AUTH_BEARER.to_string(),
+        host_patterns: vec!["api.github.com".to_string()],
+        header_name: None,
+        username: None,
+        secret: Some("ghp_secret".to_string()),
+    };
+    let created = db.save_web_auth_profile(&input).unwrap();
+    assert_eq!(created.secret, "ghp_secret");
+    assert!(db.save_web_auth_profile(&input).is_err());
+
+    let updated = db
+        .save_web_auth_profile(&SaveWebAuthProfileInput {
+            id: Some(created.id.clone()),
+            host_patterns: vec!["*.github.com".to_string()],
+            secret: None,
+            ..input
+        })
+        .unwrap();
+    assert_eq!(updated.host_patterns, vec!["*.github.com"]);
+
+    let loaded = db.get_web_auth_profile_by_name("github").unwrap().unwrap();
+    assert_eq!(loaded.secret, "ghp_secret");
+    let serialized = serde_json::to_string(&loaded).unwrap();
+    assert!(!serialized.contains("ghp_secret"));
+    assert!(!serialized.contains("\"secret\""));
+
+    assert_eq!(db.get_web_auth_profiles().unwrap().len(), 1);
+    assert!(db.delete_web_auth_profile(&created.id).unwrap());
+    assert!(db.get_web_auth_profile_by_name("github").unwrap().is_none());
+}
+
+#[test]
+fn feed_items_are_deduplicated_and_track_read_state() {
+    let db = setup_db();
+    let feed = db
+        .create_feed_subscription(
+            &CreateFeedSubscriptionInput {
+                url: "https://example.com/feed.xml".to_string(),
+                title: None,
+                poll_interval_minutes: None,
+            },
+            60,
+        )
+        .unwrap();
+    let item = |guid: &str, published_at: i64| NewFeedItem {
+        guid: guid.to_string(),
+        title: format!("Post {guid}"),
+        url: Some(format!("https://example.com/{guid}")),
+        summary: None,
+        author: None,
+        published_at: Some(published_at),
+    };
+
+    let items = [item("a", 1_000), item("b", 2_000)];
+    assert_eq!(db.insert_feed_items(&feed.id, &items).unwrap(), 2);
+    assert_eq!(
+        db.insert_feed_items(&feed.id, &[item("b", 2_000), item("c", 3_000)])
+            .unwrap(),
+        1
+    );
+    assert_eq!(db.get_due_feed_subscriptions(0).unwrap().len(), 1);
+    db.record_feed_poll(
+        &feed.id,
+        &FeedPollUpdate {
+            feed_title: Some("Example".to_string()),
+            etag: Some("\"v1\"".to_string()),
+            polled_at: 10,
+            next_poll_at: 100,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(db.get_due_feed_subscriptions(50).unwrap().is_empty());
+
+    let unread = db
+        .get_feed_items(&FeedItemQuery {
+            unread_only: true,
+            ..Default::default()
+        })
+        .unwrap();
+    let guids: Vec<_> = unread.iter().map(|item| item.guid.as_str()).collect();
+    assert_eq!(guids, vec!["c", "b", "a"]);
+    assert_eq!(unread[0].feed_title.as_deref(), Some("Example"));
+
+    assert_eq!(db.mark_feed_items_read(&[unread[0].id.clone()]).unwrap(), 1);
+    assert_eq!(db.mark_feed_read(Some(&feed.id), Some(2_000)).unwrap(), 1);
+    assert_eq!(db.count_unread_feed_items(None).unwrap(), 1);
+    let loaded = db.get_feed_subscription_by_id(&feed.id).unwrap().unwrap();
+    assert_eq!(loaded.unread_count, 1);
+    assert_eq!(loaded.etag.as_deref(), Some("\"v1\""));
+
+    assert!(db.delete_feed_subscription(&feed.id).unwrap());
+    assert!(db
+        .get_feed_items(&FeedItemQuery::default())
+        .unwrap()
+        .is_empty());
+}
@@ -1,5 +1,5 @@
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::sync::{mpsc, Arc, Mutex};
 
 pub const EVENT_MESSAGE_SAVED: &str = "message.saved";
@@ -12,6 +12,7 @@ pub const EVENT_ASSISTANT_STREAM_CHUNK: &str = "assistant.stream.chunk";
 pub const EVENT_ASSISTANT_STREAM_COMPLETED: &str = "assistant.stream.completed";
 pub const EVENT_TOOL_EXECUTION_STARTED: &str = "tool.execution.started";
 pub const EVENT_TOOL_EXECUTION_COMPLETED: &str = "tool.execution.completed";
+pub const EVENT_TOOL_EXECUTION_PROGRESS: &str = "tool.execution.progress";
 pub const EVENT_TOOL_EXECUTION_PROPOSED: &str = "tool.execution.proposed";
 pub const EVENT_TOOL_EXECUTION_APPROVED: &str = "tool.execution.approved";
 pub const EVENT_TOOL_EXECUTION_DENIED: &str = "tool.execution.denied";
@@ -22,6 +23,12 @@ pub const EVENT_AGENT_STEP_PROPOSED: &str = "agent.step.proposed";
 pub const EVENT_AGENT_STEP_STARTED: &str = "agent.step.started";
 pub const EVENT_AGENT_STEP_COMPLETED: &str = "agent.step.completed";
 pub const EVENT_AGENT_COMPLETED: &str = "agent.completed";
+pub const EVENT_SCHEDULED_JOB_RUN_STARTED: &str = "scheduled_job.run.started";
+pub const EVENT_SCHEDULED_JOB_RUN_COMPLETED: &str = "scheduled_job.run.completed";
+pub const EVENT_AGENT_TRIGGER_RUN_STARTED: &str = "trigger.run.started";
+pub const EVENT_AGENT_TRIGGER_RUN_COMPLETED: &str = "trigger.run.completed";
+pub const EVENT_INTEGRATION_SYNC_COMPLETED: &str = "integration.sync.completed";
+pub const EVENT_FEED_ITEMS_ADDED: &str = "feed.items.added";
 
 #[derive(Clone, Debug, Serialize)]
 pub struct AgentEvent {
@@ -47,12 +54,25 @@ impl AgentEvent {
 #[derive(Clone)]
 pub struct EventBus {
     subscribers: Arc<Mutex<Vec<mpsc::Sender<AgentEvent>>>>,
+    scope: Map<String, Value>,
 }
 
 impl EventBus {
     pub fn new() -> Self {
         Self {
             subscribers: Arc::new(Mutex::new(Vec::new())),
+            scope: Map::new(),
+        }
+    }
+
+    /// Returns a bus sharing the same subscribers that stamps `fields` onto every object
+    /// payload it publishes, e.g. to nest a sub-agent's events under the parent step.
+    pub fn scoped(&self, fields: Map<String, Value>) -> Self {
+        let mut scope = self.scope.clone();
+        scope.extend(fields);
+        Self {
+            subscribers: self.subscribers.clone(),
+            scope,
         }
     }
 
@@ -63,7 +83,14 @@ impl EventBus {
         rx
     }
 
-    pub fn publish(&self, event: AgentEvent) {
+    pub fn publish(&self, mut event: AgentEvent) {
+        if !self.scope.is_empty() {
+            if let Value::Object(payload) = &mut event.payload {
+                for (key, value) in &self.scope {
+                    payload.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+        }
         let mut subscribers = self.subscribers.lock().unwrap();
         subscribers.retain(|sender| sender.send(event.clone()).is_ok());
     }
@@ -0,0 +1,304 @@
+//! Feed subscriptions: polls RSS, Atom and JSON Feed documents on each subscription's
+//! interval and stores entries not seen before, so the feeds tools can build digests from
+//! SQLite.
+
+mod parser;
+
+pub use parser::{parse_feed, ParsedFeed};
+
+use crate::db::{Db, FeedOperations, FeedPollUpdate, FeedSubscription, PreferenceOperations};
+use crate::events::{AgentEvent, EventBus, EVENT_FEED_ITEMS_ADDED};
+use crate::tools::{is_private_host, DEFAULT_USER_AGENT, PREF_USER_AGENT};
+use chrono::Utc;
+use reqwest::blocking::Client;
+use reqwest::header::{
+    HeaderName, ACCEPT, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, USER_AGENT,
+};
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+pub const DEFAULT_POLL_INTERVAL_MINUTES: i64 = 60;
+pub const MIN_POLL_INTERVAL_MINUTES: i64 = 5;
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_FEED_BYTES: u64 = 5 * 1024 * 1024;
+const FEED_ACCEPT: &str = "application/rss+xml, application/atom+xml, application/feed+json, application/json;q=0.9, application/xml;q=0.9, text/xml;q=0.8, */*;q=0.5";
+
+/// Checks a subscription URL and returns it normalized.
+pub fn validate_feed_url(input: &str) -> Result<String, String> {
+    let url = Url::parse(input.trim()).map_err(|_| "Feed URL is not a valid URL".to_string())?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err("Feed URL must use http or https".to_string());
+    }
+    if url.host_str().is_none() {
+        return Err("Feed URL must include a host".to_string());
+    }
+    if is_private_url(&url) {
+        return Err("Feed URL must not point to a private or local host".to_string());
+    }
+    Ok(url.to_string())
+}
+
+/// Feeds are fetched without the web access rules, so private and local hosts are never
+/// reachable through them.
+fn is_private_url(url: &Url) -> bool {
+    url.host_str()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+        .is_some_and(is_private_host)
+}
+
+pub fn validate_poll_interval(minutes: Option<i64>) -> Result<i64, String> {
+    let minutes = minutes.unwrap_or(DEFAULT_POLL_INTERVAL_MINUTES);
+    if minutes < MIN_POLL_INTERVAL_MINUTES {
+        return Err(format!(
+            "Poll interval must be at least {MIN_POLL_INTERVAL_MINUTES} minutes"
+        ));
+    }
+    Ok(minutes)
+}
+
+fn user_agent(db: &Db) -> String {
+    PreferenceOperations::get_preference(db, PREF_USER_AGENT)
+        .ok()
+        .flatten()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string())
+}
+
+/// Response of a conditional fetch; `None` body means the feed is unchanged.
+struct FeedFetch {
+    body: Option<Vec<u8>>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+fn fetch_feed(db: &Db, feed: &FeedSubscription) -> Result<FeedFetch, String> {
+    let url = Url::parse(&feed.url).map_err(|err| format!("Invalid feed URL: {err}"))?;
+    if is_private_url(&url) {
+        return Err("Feed URL points to a private or local host".to_string());
+    }
+    let redirects = Policy::custom(|attempt| {
+        if attempt.previous().len() >= 10 {
+            attempt.error("too many redirects")
+        } else if is_private_url(attempt.url()) {
+            attempt.error("redirect to a private or local host")
+        } else {
+            attempt.follow()
+        }
+    });
+    let client = Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .redirect(redirects)
+        .build()
+        .map_err(|err| format!("Failed to build HTTP client: {err}"))?;
+    let mut request = client
+        .get(url)
+        .header(USER_AGENT, user_agent(db))
+        .header(ACCEPT, FEED_ACCEPT);
+    if let Some(etag) = &feed.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &feed.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let response = request
+        .send()
+        .map_err(|err| format!("Failed to fetch feed: {err}"))?;
+    let status = response.status();
+    let header = |name: HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    if status == StatusCode::NOT_MODIFIED {
+        return Ok(FeedFetch {
+            body: None,
+            etag,
+            last_modified,
+        });
+    }
+    if !status.is_success() {
+        return Err(format!("Feed returned HTTP {status}"));
+    }
+
+    let mut body = Vec::new();
+    response
+        .take(MAX_FEED_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| format!("Failed to read feed: {err}"))?;
+    if body.len() as u64 > MAX_FEED_BYTES {
+        return Err(format!(
+            "Feed is larger than {} MB",
+            MAX_FEED_BYTES / 1024 / 1024
+        ));
+    }
+    Ok(FeedFetch {
+        body: Some(body),
+        etag,
+        last_modified,
+    })
+}
+
+/// Fetches the feed, stores new entries and schedules the next poll. Returns how many
+/// entries were new. Failures are recorded on the subscription as well as returned.
+pub fn poll_feed(db: &Db, feed: &FeedSubscription) -> Result<usize, String> {
+    let polled_at = Utc::now().timestamp_millis();
+    let next_poll_at =
+        polled_at + feed.poll_interval_minutes.max(MIN_POLL_INTERVAL_MINUTES) * 60_000;
+    let mut update = FeedPollUpdate {
+        polled_at,
+        next_poll_at,
+        ..Default::default()
+    };
+
+    let result = fetch_and_store(db, feed, &mut update);
+    if let Err(err) = &result {
+        update.error = Some(err.clone());
+    }
+    db.record_feed_poll(&feed.id, &update)
+        .map_err(|err| format!("Failed to save feed state: {err}"))?;
+    result
+}
+
+fn fetch_and_store(
+    db: &Db,
+    feed: &FeedSubscription,
+    update: &mut FeedPollUpdate,
+) -> Result<usize, String> {
+    let fetched = fetch_feed(db, feed)?;
+    update.etag = fetched.etag;
+    update.last_modified = fetched.last_modified;
+    let Some(body) = fetched.body else {
+        return Ok(0);
+    };
+
+    let feed_url = Url::parse(&feed.url).map_err(|err| format!("Invalid feed URL: {err}"))?;
+    let parsed = parse_feed(&body, &feed_url)?;
+    update.feed_title = parsed.title;
+    update.site_url = parsed.site_url;
+    db.insert_feed_items(&feed.id, &parsed.items)
+        .map_err(|err| format!("Failed to store feed items: {err}"))
+}
+
+/// Polls due subscriptions in the background.
+#[derive(Clone)]
+pub struct FeedPoller {
+    db: Db,
+    event_bus: EventBus,
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+impl FeedPoller {
+    pub fn new(db: Db, event_bus: EventBus) -> Self {
+        Self {
+            db,
+            event_bus,
+            running: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn start(&self) {
+        let poller = self.clone();
+        std::thread::spawn(move || loop {
+            poller.tick();
+            std::thread::sleep(TICK_INTERVAL);
+        });
+    }
+
+    fn tick(&self) {
+        let now = Utc::now().timestamp_millis();
+        let feeds = match self.db.get_due_feed_subscriptions(now) {
+            Ok(feeds) => feeds,
+            Err(err) => {
+                log::error!("[feeds] failed to load due feeds: {}", err);
+                return;
+            }
+        };
+        for feed in feeds {
+            let _ = self.poll(&feed);
+        }
+    }
+
+    /// Starts a poll of the subscription in the background. Returns false when one is
+    /// already running.
+    pub fn poll_now(&self, feed_id: &str) -> Result<bool, String> {
+        let feed = self
+            .db
+            .get_feed_subscription_by_id(feed_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Feed not found".to_string())?;
+        if self.running.lock().unwrap().contains(&feed.id) {
+            return Ok(false);
+        }
+        let poller = self.clone();
+        std::thread::spawn(move || {
+            let _ = poller.poll(&feed);
+        });
+        Ok(true)
+    }
+
+    /// Polls one subscription now. Returns `Ok(None)` when a poll of it is already running.
+    pub fn poll(&self, feed: &FeedSubscription) -> Result<Option<usize>, String> {
+        if !self.running.lock().unwrap().insert(feed.id.clone()) {
+            return Ok(None);
+        }
+        let result = poll_feed(&self.db, feed);
+        self.running.lock().unwrap().remove(&feed.id);
+
+        match &result {
+            Ok(added) => {
+                log::info!("[feeds] polled {} new={}", feed.url, added);
+                if *added > 0 {
+                    let now = Utc::now().timestamp_millis();
+                    self.event_bus.publish(AgentEvent::new_with_timestamp(
+                        EVENT_FEED_ITEMS_ADDED,
+                        json!({
+                            "feed_id": feed.id,
+                            "url": feed.url,
+                            "title": feed.title,
+                            "added": added,
+                            "timestamp_ms": now,
+                        }),
+                        now,
+                    ));
+                }
+            }
+            Err(err) => log::warn!("[feeds] poll of {} failed: {}", feed.url, err),
+        }
+        result.map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feed_urls_must_not_point_to_private_hosts() {
+        for url in [
+            "http://localhost/feed.xml",
+            "http://127.0.0.1:8080/rss",
+            "http://169.254.169.254/latest/meta-data",
+            "http://10.0.0.5/atom.xml",
+            "http://[::1]/feed",
+        ] {
+            assert!(validate_feed_url(url).is_err(), "{url} should be rejected");
+        }
+        assert_eq!(
+            validate_feed_url(" https://blog.example.com/feed.xml ").unwrap(),
+            "https://blog.example.com/feed.xml"
+        );
+    }
+}
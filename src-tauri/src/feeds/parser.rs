@@ -0,0 +1,360 @@
+//! Parses RSS 2.0 (and RSS 1.0), Atom and JSON Feed documents into one item shape.
+
+use crate::db::NewFeedItem;
+use chrono::DateTime;
+use roxmltree::{Document, Node};
+use scraper::Html;
+use serde_json::Value;
+use url::Url;
+
+const SUMMARY_MAX_CHARS: usize = 500;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedFeed {
+    pub title: Option<String>,
+    pub site_url: Option<String>,
+    pub items: Vec<NewFeedItem>,
+}
+
+pub fn parse_feed(body: &[u8], feed_url: &Url) -> Result<ParsedFeed, String> {
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with('{') {
+        return parse_json_feed(text, feed_url);
+    }
+    let document = Document::parse(text).map_err(|err| format!("Invalid feed XML: {err}"))?;
+    let root = document.root_element();
+    match root.tag_name().name() {
+        "rss" => {
+            let channel = child(root, "channel").ok_or("RSS feed has no channel")?;
+            Ok(parse_rss(channel, channel, feed_url))
+        }
+        // RSS 1.0 keeps items next to the channel instead of inside it.
+        "RDF" => {
+            let channel = child(root, "channel").unwrap_or(root);
+            Ok(parse_rss(channel, root, feed_url))
+        }
+        "feed" => Ok(parse_atom(root, feed_url)),
+        other => Err(format!("Unsupported feed format '<{other}>'")),
+    }
+}
+
+fn parse_rss(channel: Node, items_parent: Node, feed_url: &Url) -> ParsedFeed {
+    let items = items_parent
+        .children()
+        .filter(|node| is_named(*node, "item"))
+        .filter_map(|item| {
+            let url = child_text(item, "link").and_then(|link| resolve(feed_url, &link));
+            let guid = child_text(item, "guid").or_else(|| url.clone());
+            let title = child_text(item, "title").unwrap_or_default();
+            let published_at = child_text(item, "pubDate")
+                .or_else(|| child_text(item, "date"))
+                .and_then(|date| parse_date(&date));
+            let guid = guid.or_else(|| fallback_guid(&title, published_at))?;
+            Some(NewFeedItem {
+                guid,
+                title: plain_text(&title),
+                url,
+                summary: child_text(item, "description")
+                    .or_else(|| child_text(item, "encoded"))
+                    .map(|html| summarize(&html))
+                    .filter(|summary| !summary.is_empty()),
+                author: child_text(item, "creator").or_else(|| child_text(item, "author")),
+                published_at,
+            })
+        })
+        .collect();
+    ParsedFeed {
+        title: child_text(channel, "title").map(|title| plain_text(&title)),
+        site_url: child_text(channel, "link").and_then(|link| resolve(feed_url, &link)),
+        items,
+    }
+}
+
+fn parse_atom(feed: Node, feed_url: &Url) -> ParsedFeed {
+    let items = feed
+        .children()
+        .filter(|node| is_named(*node, "entry"))
+        .filter_map(|entry| {
+            let url = atom_link(entry).and_then(|href| resolve(feed_url, &href));
+            let title = child_text(entry, "title").unwrap_or_default();
+            let published_at = child_text(entry, "published")
+                .or_else(|| child_text(entry, "updated"))
+                .and_then(|date| parse_date(&date));
+            let guid = child_text(entry, "id")
+                .or_else(|| url.clone())
+                .or_else(|| fallback_guid(&title, published_at))?;
+            Some(NewFeedItem {
+                guid,
+                title: plain_text(&title),
+                url,
+                summary: child_text(entry, "summary")
+                    .or_else(|| child_text(entry, "content"))
+                    .map(|html| summarize(&html))
+                    .filter(|summary| !summary.is_empty()),
+                author: child(entry, "author").and_then(|author| child_text(author, "name")),
+                published_at,
+            })
+        })
+        .collect();
+    ParsedFeed {
+        title: child_text(feed, "title").map(|title| plain_text(&title)),
+        site_url: atom_link(feed).and_then(|href| resolve(feed_url, &href)),
+        items,
+    }
+}
+
+fn parse_json_feed(text: &str, feed_url: &Url) -> Result<ParsedFeed, String> {
+    let feed: Value =
+        serde_json::from_str(text).map_err(|err| format!("Invalid JSON Feed: {err}"))?;
+    let is_json_feed = feed
+        .get("version")
+        .and_then(|v| v.as_str())
+        .is_some_and(|version| version.contains("jsonfeed.org"));
+    if !is_json_feed {
+        return Err("JSON document is not a JSON Feed".to_string());
+    }
+    let string = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    // Version 1.1 uses an `authors` array, 1.0 a single `author`.
+    let author = |value: &Value| {
+        value
+            .get("authors")
+            .and_then(|authors| authors.get(0))
+            .or_else(|| value.get("author"))
+            .and_then(|author| string(author, "name"))
+    };
+    let items = feed
+        .get("items")
+        .and_then(|items| items.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let url = string(item, "url").and_then(|url| resolve(feed_url, &url));
+                    let title = string(item, "title").unwrap_or_default();
+                    let published_at = string(item, "date_published")
+                        .or_else(|| string(item, "date_modified"))
+                        .and_then(|date| parse_date(&date));
+                    let guid = item
+                        .get("id")
+                        .and_then(|id| match id {
+                            Value::String(id) => Some(id.clone()),
+                            Value::Number(id) => Some(id.to_string()),
+                            _ => None,
+                        })
+                        .or_else(|| url.clone())
+                        .or_else(|| fallback_guid(&title, published_at))?;
+                    Some(NewFeedItem {
+                        guid,
+                        title,
+                        url,
+                        summary: string(item, "summary")
+                            .or_else(|| string(item, "content_text"))
+                            .or_else(|| string(item, "content_html"))
+                            .map(|text| summarize(&text))
+                            .filter(|summary| !summary.is_empty()),
+                        author: author(item).or_else(|| author(&feed)),
+                        published_at,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(ParsedFeed {
+        title: string(&feed, "title"),
+        site_url: string(&feed, "home_page_url").and_then(|url| resolve(feed_url, &url)),
+        items,
+    })
+}
+
+/// Matches on the local name so namespaced elements (`dc:creator`, `content:encoded`, RSS 1.0
+/// items) are found without knowing their prefix.
+fn is_named(node: Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| is_named(*child, name))
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    let child = child(node, name)?;
+    let text = child
+        .descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect::<String>();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// The entry's `alternate` link, or its first link without a `rel`.
+fn atom_link(node: Node) -> Option<String> {
+    let links = node
+        .children()
+        .filter(|child| is_named(*child, "link"))
+        .collect::<Vec<_>>();
+    links
+        .iter()
+        .find(|link| link.attribute("rel") == Some("alternate"))
+        .or_else(|| links.iter().find(|link| link.attribute("rel").is_none()))
+        .and_then(|link| link.attribute("href"))
+        .map(|href| href.trim().to_string())
+}
+
+fn resolve(base: &Url, link: &str) -> Option<String> {
+    let url = base.join(link.trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+/// RFC 2822 (RSS) or RFC 3339 (Atom, JSON Feed, Dublin Core) dates, in unix ms.
+fn parse_date(input: &str) -> Option<i64> {
+    let input = input.trim();
+    DateTime::parse_from_rfc2822(input)
+        .or_else(|_| DateTime::parse_from_rfc3339(input))
+        .ok()
+        .map(|date| date.timestamp_millis())
+}
+
+fn fallback_guid(title: &str, published_at: Option<i64>) -> Option<String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return None;
+    }
+    Some(match published_at {
+        Some(published_at) => format!("{title}@{published_at}"),
+        None => title.to_string(),
+    })
+}
+
+fn plain_text(html: &str) -> String {
+    if !html.contains('<') && !html.contains('&') {
+        return html.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    let fragment = Html::parse_fragment(html);
+    fragment
+        .root_element()
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn summarize(html: &str) -> String {
+    let text = plain_text(html);
+    if text.chars().count() <= SUMMARY_MAX_CHARS {
+        return text;
+    }
+    let cut = text.chars().take(SUMMARY_MAX_CHARS).collect::<String>();
+    let cut = cut.rsplit_once(' ').map(|(head, _)| head).unwrap_or(&cut);
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://blog.example.com/feed.xml").unwrap()
+    }
+
+    #[test]
+    fn parses_rss_and_atom() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+              <channel>
+                <title>Example Blog</title>
+                <link>https://blog.example.com/</link>
+                <item>
+                  <title>Release 1.2</title>
+                  <link>/posts/release-1-2</link>
+                  <guid isPermaLink="false">post-12</guid>
+                  <description><![CDATA[<p>New <b>things</b> &amp; fixes.</p>]]></description>
+                  <dc:creator>Ada</dc:creator>
+                  <pubDate>Tue, 03 Jun 2025 10:00:00 +0000</pubDate>
+                </item>
+                <item>
+                  <title>No guid</title>
+                  <link>https://blog.example.com/posts/no-guid</link>
+                </item>
+              </channel>
+            </rss>"#;
+        let feed = parse_feed(rss.as_bytes(), &base()).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Example Blog"));
+        assert_eq!(feed.items.len(), 2);
+        let item = &feed.items[0];
+        assert_eq!(item.guid, "post-12");
+        assert_eq!(
+            item.url.as_deref(),
+            Some("https://blog.example.com/posts/release-1-2")
+        );
+        assert_eq!(item.summary.as_deref(), Some("New things & fixes."));
+        assert_eq!(item.author.as_deref(), Some("Ada"));
+        assert_eq!(item.published_at, Some(1_748_944_800_000));
+        assert_eq!(feed.items[1].guid, "https://blog.example.com/posts/no-guid");
+
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Changelog</title>
+              <link href="https://example.com/changelog"/>
+              <link rel="self" href="https://example.com/changelog.atom"/>
+              <entry>
+                <title type="html">v2 &amp;amp; more</title>
+                <id>tag:example.com,2025:v2</id>
+                <link rel="alternate" href="https://example.com/changelog/v2"/>
+                <updated>2025-06-03T10:00:00Z</updated>
+                <author><name>Grace</name></author>
+                <summary>Second version.</summary>
+              </entry>
+            </feed>"#;
+        let feed = parse_feed(atom.as_bytes(), &base()).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Changelog"));
+        assert_eq!(
+            feed.site_url.as_deref(),
+            Some("https://example.com/changelog")
+        );
+        let entry = &feed.items[0];
+        assert_eq!(entry.guid, "tag:example.com,2025:v2");
+        assert_eq!(entry.title, "v2 & more");
+        assert_eq!(entry.author.as_deref(), Some("Grace"));
+        assert_eq!(entry.published_at, Some(1_748_944_800_000));
+    }
+
+    #[test]
+    fn parses_json_feed_and_rejects_other_documents() {
+        let json = r#"{
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": "Notes",
+            "home_page_url": "https://notes.example.com/",
+            "authors": [{ "name": "Linus" }],
+            "items": [
+                { "id": 7, "url": "/7", "title": "Seven", "content_html": "<p>Hello</p>",
+                  "date_published": "2025-06-03T12:00:00+02:00" },
+                { "id": "8", "content_text": "Untitled note" }
+            ]
+        }"#;
+        let feed = parse_feed(json.as_bytes(), &base()).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Notes"));
+        assert_eq!(feed.items.len(), 2);
+        assert_eq!(feed.items[0].guid, "7");
+        assert_eq!(
+            feed.items[0].url.as_deref(),
+            Some("https://blog.example.com/7")
+        );
+        assert_eq!(feed.items[0].summary.as_deref(), Some("Hello"));
+        assert_eq!(feed.items[0].author.as_deref(), Some("Linus"));
+        assert_eq!(feed.items[0].published_at, Some(1_748_944_800_000));
+        assert_eq!(feed.items[1].title, "");
+
+        assert!(parse_feed(b"{\"hello\": 1}", &base()).is_err());
+        assert!(parse_feed(b"<html><body/></html>", &base()).is_err());
+    }
+}
@@ -0,0 +1,611 @@
+//! CalDAV (RFC 4791) calendars such as Nextcloud, Fastmail, or Radicale. The server URL
+//! lives in the connection settings and the password (or app password) in the
+//! connection's access token.
+
+use super::icalendar::{self, Component};
+use super::{
+    load_integration_settings, DiscoveredResource, Integration, IntegrationCapability,
+    IntegrationHealth, IntegrationMetadata,
+};
+use crate::db::{Db, IntegrationConnection};
+use crate::tools::{register_caldav_tools, ToolError, ToolRegistry};
+use chrono::{DateTime, Utc};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use url::Url;
+
+const DAV_NS: &str = "DAV:";
+const CALDAV_NS: &str = "urn:ietf:params:xml:ns:caldav";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct CalDavIntegration;
+
+impl Integration for CalDavIntegration {
+    fn manifest(&self) -> IntegrationMetadata {
+        IntegrationMetadata {
+            id: "caldav".to_string(),
+            name: "CalDAV".to_string(),
+            provider: "caldav".to_string(),
+            description:
+                "List and edit events on CalDAV calendars (Nextcloud, Fastmail, Radicale, iCloud)."
+                    .to_string(),
+            auth_type: "password".to_string(),
+            category: "calendar".to_string(),
+            capabilities: vec![
+                IntegrationCapability::ActionExecute,
+                IntegrationCapability::Discovery,
+                IntegrationCapability::HealthCheck,
+            ],
+            scopes: Vec::new(),
+            settings_schema: settings_schema(),
+        }
+    }
+
+    fn health_check(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<IntegrationHealth, String> {
+        let client = CalDavClient::load(db, connection)?;
+        let result = client.calendars();
+        Ok(IntegrationHealth {
+            ok: result.is_ok(),
+            status: None,
+            message: result.err().map(|err| err.message),
+        })
+    }
+
+    fn discovery(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<Vec<DiscoveredResource>, String> {
+        let client = CalDavClient::load(db, connection)?;
+        let calendars = client.calendars().map_err(|err| err.message)?;
+        Ok(calendars
+            .into_iter()
+            .map(|calendar| DiscoveredResource {
+                kind: "calendar".to_string(),
+                metadata: json!({
+                    "time_zone": calendar.time_zone,
+                    "color": calendar.color,
+                }),
+                name: calendar.name,
+                id: calendar.id,
+            })
+            .collect())
+    }
+
+    fn register_actions(&self, registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+        register_caldav_tools(registry, db)
+    }
+}
+
+fn settings_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "server_url": {
+                "type": "string",
+                "minLength": 1,
+                "description": "CalDAV URL, e.g. https://cloud.example.com/remote.php/dav or https://caldav.fastmail.com/dav/."
+            },
+            "username": {
+                "type": "string",
+                "description": "Login name, when it differs from the account label."
+            },
+            "calendar_ids": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Calendars used when a tool call names none. Defaults to every event calendar."
+            }
+        },
+        "required": ["server_url"],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CalDavSettings {
+    #[serde(default)]
+    pub server_url: String,
+    pub username: Option<String>,
+    #[serde(default)]
+    pub calendar_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarInfo {
+    /// Collection path on the server, which tools use as the calendar id.
+    pub id: String,
+    pub name: String,
+    pub time_zone: Option<String>,
+    pub color: Option<String>,
+}
+
+/// A calendar object resource: one `.ics` file holding an event and its overrides.
+#[derive(Debug, Clone)]
+pub struct CalendarObject {
+    pub href: String,
+    pub etag: Option<String>,
+    pub calendar: Component,
+}
+
+#[derive(Clone)]
+pub struct CalDavClient {
+    http: Client,
+    server_url: Url,
+    username: String,
+    password: String,
+    pub default_calendar_ids: Vec<String>,
+}
+
+impl CalDavClient {
+    pub fn load(db: &Db, connection: &IntegrationConnection) -> Result<Self, String> {
+        let settings = serde_json::from_value::<CalDavSettings>(load_integration_settings(
+            db,
+            &connection.integration_id,
+            &connection.id,
+        ))
+        .map_err(|err| format!("Invalid CalDAV settings: {err}"))?;
+        let server_url = Url::parse(settings.server_url.trim()).map_err(|_| {
+            "CalDAV connection is missing its server URL. Set it in the connection settings."
+                .to_string()
+        })?;
+        let username = settings
+            .username
+            .filter(|value| !value.trim().is_empty())
+            .or_else(|| connection.account_label.clone())
+            .unwrap_or_default();
+        if username.trim().is_empty() {
+            return Err("CalDAV connection has no username or account label.".to_string());
+        }
+        let password = connection
+            .access_token
+            .clone()
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| "CalDAV connection has no password.".to_string())?;
+        let http = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|err| format!("Failed to create HTTP client: {err}"))?;
+        Ok(Self {
+            http,
+            server_url,
+            username,
+            password,
+            default_calendar_ids: settings.calendar_ids,
+        })
+    }
+
+    fn url(&self, href: &str) -> Result<Url, ToolError> {
+        self.server_url
+            .join(href)
+            .map_err(|err| ToolError::validation(format!("Invalid calendar path '{href}': {err}")))
+    }
+
+    fn request(&self, method: &str, href: &str) -> Result<RequestBuilder, ToolError> {
+        let method = Method::from_bytes(method.as_bytes())
+            .map_err(|err| ToolError::new(format!("Invalid HTTP method: {err}")))?;
+        Ok(self
+            .http
+            .request(method, self.url(href)?)
+            .basic_auth(&self.username, Some(&self.password)))
+    }
+
+    fn send(&self, request: RequestBuilder) -> Result<Response, ToolError> {
+        let response = request
+            .send()
+            .map_err(|err| ToolError::upstream(format!("Failed to reach CalDAV server: {err}")))?;
+        let status = response.status();
+        if status.as_u16() == 412 {
+            return Err(
+                ToolError::new("The event changed on the server since it was read")
+                    .with_hint("List the events again and retry the change."),
+            );
+        }
+        if !status.is_success() {
+            return Err(ToolError::from_http_response(
+                &response,
+                format!("CalDAV server error: HTTP {status}"),
+            ));
+        }
+        Ok(response)
+    }
+
+    fn xml_request(
+        &self,
+        method: &str,
+        href: &str,
+        depth: &str,
+        body: String,
+    ) -> Result<Vec<DavResponse>, ToolError> {
+        let response = self.send(
+            self.request(method, href)?
+                .header("Depth", depth)
+                .header("Content-Type", "application/xml; charset=utf-8")
+                .body(body),
+        )?;
+        let text = response
+            .text()
+            .map_err(|err| ToolError::upstream(format!("Failed to read CalDAV response: {err}")))?;
+        parse_multistatus(&text).map_err(ToolError::upstream)
+    }
+
+    fn propfind(
+        &self,
+        href: &str,
+        depth: &str,
+        props: &str,
+    ) -> Result<Vec<DavResponse>, ToolError> {
+        self.xml_request(
+            "PROPFIND",
+            href,
+            depth,
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?><d:propfind xmlns:d="{DAV_NS}" xmlns:c="{CALDAV_NS}" xmlns:a="http://apple.com/ns/ical/"><d:prop>{props}</d:prop></d:propfind>"#
+            ),
+        )
+    }
+
+    /// First href of `prop` on `href`, following RFC 6764 discovery steps.
+    fn find_href(&self, href: &str, prop: &str, local_name: &str) -> Option<String> {
+        self.propfind(href, "0", prop)
+            .ok()?
+            .into_iter()
+            .find_map(|response| response.props.get(local_name)?.hrefs.first().cloned())
+    }
+
+    /// Event calendars in the user's calendar home.
+    pub fn calendars(&self) -> Result<Vec<CalendarInfo>, ToolError> {
+        let start = self.server_url.path().to_string();
+        let principal = self
+            .find_href(
+                &start,
+                "<d:current-user-principal/>",
+                "current-user-principal",
+            )
+            .or_else(|| {
+                self.find_href(
+                    "/.well-known/caldav",
+                    "<d:current-user-principal/>",
+                    "current-user-principal",
+                )
+            })
+            .unwrap_or_else(|| start.clone());
+        let home = self
+            .find_href(&principal, "<c:calendar-home-set/>", "calendar-home-set")
+            .unwrap_or(principal);
+        let responses = self.propfind(
+            &home,
+            "1",
+            "<d:resourcetype/><d:displayname/><c:supported-calendar-component-set/><c:calendar-timezone/><a:calendar-color/>",
+        )?;
+        Ok(responses
+            .into_iter()
+            .filter(|response| {
+                let is_calendar = response
+                    .props
+                    .get("resourcetype")
+                    .is_some_and(|prop| prop.children.iter().any(|name| name == "calendar"));
+                let supports_events = response
+                    .props
+                    .get("supported-calendar-component-set")
+                    .map(|prop| {
+                        prop.names.is_empty() || prop.names.iter().any(|name| name == "VEVENT")
+                    })
+                    .unwrap_or(true);
+                is_calendar && supports_events
+            })
+            .map(|response| {
+                let text = |name: &str| {
+                    response
+                        .props
+                        .get(name)
+                        .map(|prop| prop.text.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                };
+                CalendarInfo {
+                    name: text("displayname").unwrap_or_else(|| response.href.clone()),
+                    time_zone: text("calendar-timezone")
+                        .and_then(|value| calendar_time_zone(&value)),
+                    color: text("calendar-color"),
+                    id: response.href,
+                }
+            })
+            .collect())
+    }
+
+    /// Objects with an event in `range`, or every event object when `range` is `None`.
+    /// Recurring events match when any occurrence falls in the range.
+    pub fn events(
+        &self,
+        calendar_id: &str,
+        range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) -> Result<Vec<CalendarObject>, ToolError> {
+        let filter = match range {
+            Some((start, end)) => format!(
+                r#"<c:comp-filter name="VEVENT"><c:time-range start="{}" end="{}"/></c:comp-filter>"#,
+                start.format("%Y%m%dT%H%M%SZ"),
+                end.format("%Y%m%dT%H%M%SZ")
+            ),
+            None => r#"<c:comp-filter name="VEVENT"/>"#.to_string(),
+        };
+        self.calendar_query(calendar_id, &filter)
+    }
+
+    /// The object holding the event with `uid`, if any.
+    pub fn find_event(
+        &self,
+        calendar_id: &str,
+        uid: &str,
+    ) -> Result<Option<CalendarObject>, ToolError> {
+        let filter = format!(
+            r#"<c:comp-filter name="VEVENT"><c:prop-filter name="UID"><c:text-match collation="i;octet">{}</c:text-match></c:prop-filter></c:comp-filter>"#,
+            xml_escape(uid)
+        );
+        Ok(self
+            .calendar_query(calendar_id, &filter)?
+            .into_iter()
+            .find(|object| {
+                object
+                    .calendar
+                    .children("VEVENT")
+                    .any(|event| event.text("UID").as_deref() == Some(uid))
+            }))
+    }
+
+    fn calendar_query(
+        &self,
+        calendar_id: &str,
+        comp_filter: &str,
+    ) -> Result<Vec<CalendarObject>, ToolError> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><c:calendar-query xmlns:d="{DAV_NS}" xmlns:c="{CALDAV_NS}"><d:prop><d:getetag/><c:calendar-data/></d:prop><c:filter><c:comp-filter name="VCALENDAR">{comp_filter}</c:comp-filter></c:filter></c:calendar-query>"#
+        );
+        let responses = self.xml_request("REPORT", calendar_id, "1", body)?;
+        Ok(responses
+            .into_iter()
+            .filter_map(|response| {
+                let data = response.props.get("calendar-data")?;
+                let calendar = match icalendar::parse(&data.text) {
+                    Ok(calendar) => calendar,
+                    Err(err) => {
+                        log::warn!(
+                            "[caldav] skipping unreadable object {}: {}",
+                            response.href,
+                            err
+                        );
+                        return None;
+                    }
+                };
+                Some(CalendarObject {
+                    etag: response
+                        .props
+                        .get("getetag")
+                        .map(|prop| prop.text.trim().to_string())
+                        .filter(|value| !value.is_empty()),
+                    href: response.href,
+                    calendar,
+                })
+            })
+            .collect())
+    }
+
+    /// Creates (`etag` is `None`) or replaces an object, returning its new ETag when the
+    /// server sends one. Replacing fails if someone else changed the object meanwhile.
+    pub fn put(
+        &self,
+        href: &str,
+        calendar: &Component,
+        etag: Option<&str>,
+    ) -> Result<Option<String>, ToolError> {
+        let request = self
+            .request("PUT", href)?
+            .header("Content-Type", "text/calendar; charset=utf-8")
+            .body(calendar.to_ics());
+        let request = match etag {
+            Some(etag) => request.header("If-Match", etag),
+            None => request.header("If-None-Match", "*"),
+        };
+        let response = self.send(request)?;
+        Ok(response
+            .headers()
+            .get("ETag")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string))
+    }
+
+    pub fn delete(&self, href: &str, etag: Option<&str>) -> Result<(), ToolError> {
+        let mut request = self.request("DELETE", href)?;
+        if let Some(etag) = etag {
+            request = request.header("If-Match", etag);
+        }
+        self.send(request).map(|_| ())
+    }
+
+    /// Href for a new object in `calendar_id`.
+    pub fn new_object_href(calendar_id: &str, uid: &str) -> String {
+        let uid = uid
+            .chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || ch == '-' {
+                    ch
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        format!("{}/{uid}.ics", calendar_id.trim_end_matches('/'))
+    }
+}
+
+/// TZID of the VTIMEZONE in a `calendar-timezone` property.
+fn calendar_time_zone(value: &str) -> Option<String> {
+    let calendar = icalendar::parse(value).ok()?;
+    let zone = calendar.children("VTIMEZONE").next()?;
+    Some(zone.property("TZID")?.value.trim().to_string())
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// One `<d:response>` of a multistatus body.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DavResponse {
+    pub href: String,
+    /// Properties found with a 2xx status, by local name.
+    pub props: HashMap<String, DavProperty>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DavProperty {
+    pub text: String,
+    /// Nested `<d:href>` values, e.g. of `calendar-home-set`.
+    pub hrefs: Vec<String>,
+    /// Local names of child elements, e.g. `calendar` in `resourcetype`.
+    pub children: Vec<String>,
+    /// `name` attributes of child elements, e.g. the components a calendar supports.
+    pub names: Vec<String>,
+}
+
+pub fn parse_multistatus(xml: &str) -> Result<Vec<DavResponse>, String> {
+    let document =
+        roxmltree::Document::parse(xml).map_err(|err| format!("Invalid CalDAV response: {err}"))?;
+    let is = |node: &roxmltree::Node, name: &str| {
+        node.is_element()
+            && node.tag_name().name() == name
+            && node.tag_name().namespace() == Some(DAV_NS)
+    };
+    let mut responses = Vec::new();
+    for response in document.descendants().filter(|node| is(node, "response")) {
+        let Some(href) = response
+            .children()
+            .find(|node| is(node, "href"))
+            .and_then(|node| node.text())
+        else {
+            continue;
+        };
+        let mut props = HashMap::new();
+        for propstat in response.children().filter(|node| is(node, "propstat")) {
+            let ok = propstat
+                .children()
+                .find(|node| is(node, "status"))
+                .and_then(|node| node.text())
+                .map(|status| {
+                    status
+                        .split_whitespace()
+                        .nth(1)
+                        .is_some_and(|code| code.starts_with('2'))
+                })
+                .unwrap_or(true);
+            if !ok {
+                continue;
+            }
+            for prop in propstat
+                .children()
+                .filter(|node| is(node, "prop"))
+                .flat_map(|node| node.children())
+                .filter(|node| node.is_element())
+            {
+                let children = prop.children().filter(|node| node.is_element());
+                props.insert(
+                    prop.tag_name().name().to_string(),
+                    DavProperty {
+                        text: prop
+                            .descendants()
+                            .filter(|node| node.is_text())
+                            .filter_map(|node| node.text())
+                            .collect(),
+                        hrefs: prop
+                            .descendants()
+                            .filter(|node| is(node, "href"))
+                            .filter_map(|node| node.text())
+                            .map(|text| text.trim().to_string())
+                            .collect(),
+                        children: children
+                            .clone()
+                            .map(|node| node.tag_name().name().to_string())
+                            .collect(),
+                        names: children
+                            .filter_map(|node| node.attribute("name"))
+                            .map(str::to_string)
+                            .collect(),
+                    },
+                );
+            }
+        }
+        responses.push(DavResponse {
+            href: href.trim().to_string(),
+            props,
+        });
+    }
+    Ok(responses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_multistatus_properties() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav" xmlns:x1="http://apple.com/ns/ical/">
+  <d:response>
+    <d:href>/dav/calendars/ada/personal/</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype><d:collection/><cal:calendar/></d:resourcetype>
+        <d:displayname>Personal</d:displayname>
+        <cal:supported-calendar-component-set><cal:comp name="VEVENT"/><cal:comp name="VTODO"/></cal:supported-calendar-component-set>
+        <x1:calendar-color>#0082c9</x1:calendar-color>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+    <d:propstat>
+      <d:prop><cal:calendar-timezone/></d:prop>
+      <d:status>HTTP/1.1 404 Not Found</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/dav/principals/ada/</d:href>
+    <d:propstat>
+      <d:prop><cal:calendar-home-set><d:href>/dav/calendars/ada/</d:href></cal:calendar-home-set></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let responses = parse_multistatus(xml).expect("parsed");
+        assert_eq!(responses.len(), 2);
+        let calendar = &responses[0];
+        assert_eq!(calendar.href, "/dav/calendars/ada/personal/");
+        assert_eq!(
+            calendar.props["resourcetype"].children,
+            vec!["collection", "calendar"]
+        );
+        assert_eq!(calendar.props["displayname"].text, "Personal");
+        assert_eq!(
+            calendar.props["supported-calendar-component-set"].names,
+            vec!["VEVENT", "VTODO"]
+        );
+        assert_eq!(calendar.props["calendar-color"].text, "#0082c9");
+        assert!(!calendar.props.contains_key("calendar-timezone"));
+        assert_eq!(
+            responses[1].props["calendar-home-set"].hrefs,
+            vec!["/dav/calendars/ada/"]
+        );
+        assert_eq!(
+            CalDavClient::new_object_href("/dav/calendars/ada/personal/", "a1@b.c"),
+            "/dav/calendars/ada/personal/a1_b_c.ics"
+        );
+    }
+}
@@ -0,0 +1,381 @@
+//! Generic email over IMAP and SMTP. Server settings live in the connection settings and
+//! the password (or app password) in the connection's access token.
+
+use super::{
+    load_integration_settings, DiscoveredResource, Integration, IntegrationCapability,
+    IntegrationHealth, IntegrationMetadata,
+};
+use crate::db::{Db, IntegrationConnection};
+use crate::tools::{register_email_tools, ToolRegistry};
+use lettre::address::Envelope;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Address, SmtpTransport, Transport};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct EmailIntegration;
+
+impl Integration for EmailIntegration {
+    fn manifest(&self) -> IntegrationMetadata {
+        IntegrationMetadata {
+            id: "email".to_string(),
+            name: "Email (IMAP/SMTP)".to_string(),
+            provider: "imap".to_string(),
+            description: "Read, search, and send mail from any IMAP/SMTP account.".to_string(),
+            auth_type: "password".to_string(),
+            category: "email".to_string(),
+            capabilities: vec![
+                IntegrationCapability::ActionExecute,
+                IntegrationCapability::Discovery,
+                IntegrationCapability::HealthCheck,
+            ],
+            scopes: Vec::new(),
+            settings_schema: settings_schema(),
+        }
+    }
+
+    fn health_check(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<IntegrationHealth, String> {
+        let account = EmailAccount::load(db, connection)?;
+        let checks = [
+            (
+                "IMAP",
+                account.imap_session().map(|mut session| {
+                    let _ = session.logout();
+                }),
+            ),
+            ("SMTP", account.check_smtp()),
+        ];
+        let failures = checks
+            .into_iter()
+            .filter_map(|(service, result)| result.err().map(|err| format!("{service}: {err}")))
+            .collect::<Vec<_>>();
+        Ok(IntegrationHealth {
+            ok: failures.is_empty(),
+            status: None,
+            message: (!failures.is_empty()).then(|| failures.join("; ")),
+        })
+    }
+
+    fn discovery(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<Vec<DiscoveredResource>, String> {
+        let account = EmailAccount::load(db, connection)?;
+        let mut session = account.imap_session()?;
+        let mailboxes = list_mailboxes(&mut session);
+        let _ = session.logout();
+        Ok(mailboxes?
+            .into_iter()
+            .map(|name| DiscoveredResource {
+                kind: "mailbox".to_string(),
+                id: name.clone(),
+                metadata: json!({}),
+                name,
+            })
+            .collect())
+    }
+
+    fn register_actions(&self, registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+        register_email_tools(registry, db)
+    }
+}
+
+fn settings_schema() -> Value {
+    let security = json!({ "type": "string", "enum": ["tls", "starttls", "none"] });
+    json!({
+        "type": "object",
+        "properties": {
+            "imap_host": { "type": "string", "minLength": 1 },
+            "imap_port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+            "imap_security": security,
+            "smtp_host": { "type": "string", "minLength": 1 },
+            "smtp_port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+            "smtp_security": security,
+            "username": {
+                "type": "string",
+                "description": "Login name, when it differs from the account email address."
+            },
+            "from_address": {
+                "type": "string",
+                "description": "From header, e.g. 'Ada Lovelace <ada@example.com>'. Defaults to the account email address."
+            },
+            "sent_mailbox": {
+                "type": "string",
+                "description": "Mailbox that receives a copy of sent mail, e.g. 'Sent'. Leave empty when the SMTP server files sent mail itself."
+            }
+        },
+        "required": ["imap_host", "smtp_host"],
+        "additionalProperties": false
+    })
+}
+
+/// Transport security. `none` is meant for local test servers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Security {
+    #[default]
+    Tls,
+    Starttls,
+    None,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EmailSettings {
+    #[serde(default)]
+    pub imap_host: String,
+    pub imap_port: Option<u16>,
+    #[serde(default)]
+    pub imap_security: Security,
+    #[serde(default)]
+    pub smtp_host: String,
+    pub smtp_port: Option<u16>,
+    #[serde(default)]
+    pub smtp_security: Security,
+    pub username: Option<String>,
+    pub from_address: Option<String>,
+    pub sent_mailbox: Option<String>,
+}
+
+impl EmailSettings {
+    fn imap_port(&self) -> u16 {
+        self.imap_port.unwrap_or(match self.imap_security {
+            Security::Tls => 993,
+            Security::Starttls | Security::None => 143,
+        })
+    }
+
+    fn smtp_port(&self) -> u16 {
+        self.smtp_port.unwrap_or(match self.smtp_security {
+            Security::Tls => 465,
+            Security::Starttls => 587,
+            Security::None => 25,
+        })
+    }
+}
+
+pub trait ImapStream: Read + Write + Send {}
+impl<T: Read + Write + Send> ImapStream for T {}
+
+pub type ImapSession = imap::Session<Box<dyn ImapStream>>;
+
+/// Everything needed to talk to one account's IMAP and SMTP servers.
+#[derive(Clone)]
+pub struct EmailAccount {
+    pub settings: EmailSettings,
+    pub username: String,
+    password: String,
+    /// Address used in the From header and the SMTP envelope.
+    pub from_address: String,
+}
+
+impl EmailAccount {
+    pub fn load(db: &Db, connection: &IntegrationConnection) -> Result<Self, String> {
+        let settings = serde_json::from_value::<EmailSettings>(load_integration_settings(
+            db,
+            &connection.integration_id,
+            &connection.id,
+        ))
+        .map_err(|err| format!("Invalid email settings: {err}"))?;
+        if settings.imap_host.trim().is_empty() || settings.smtp_host.trim().is_empty() {
+            return Err(
+                "Email connection is missing its IMAP or SMTP server. Set them in the connection settings."
+                    .to_string(),
+            );
+        }
+        let account_label = connection.account_label.clone().unwrap_or_default();
+        let username = settings
+            .username
+            .clone()
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| account_label.clone());
+        if username.trim().is_empty() {
+            return Err("Email connection has no username or account address.".to_string());
+        }
+        let password = connection
+            .access_token
+            .clone()
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| "Email connection has no password.".to_string())?;
+        let from_address = settings
+            .from_address
+            .clone()
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| {
+                if account_label.contains('@') {
+                    account_label.clone()
+                } else {
+                    username.clone()
+                }
+            });
+        Ok(Self {
+            settings,
+            username,
+            password,
+            from_address,
+        })
+    }
+
+    /// Connects and logs in to the IMAP server.
+    pub fn imap_session(&self) -> Result<ImapSession, String> {
+        let host = self.settings.imap_host.trim();
+        let security = self.settings.imap_security;
+        let tcp = TcpStream::connect((host, self.settings.imap_port()))
+            .map_err(|err| format!("Failed to connect to {host}: {err}"))?;
+        let _ = tcp.set_read_timeout(Some(IO_TIMEOUT));
+        let _ = tcp.set_write_timeout(Some(IO_TIMEOUT));
+
+        let stream: Box<dyn ImapStream> = match security {
+            Security::None => Box::new(tcp),
+            Security::Tls | Security::Starttls => {
+                let tcp = if security == Security::Starttls {
+                    imap_starttls(tcp)?
+                } else {
+                    tcp
+                };
+                let connector = native_tls::TlsConnector::new()
+                    .map_err(|err| format!("TLS setup failed: {err}"))?;
+                Box::new(
+                    connector
+                        .connect(host, tcp)
+                        .map_err(|err| format!("TLS handshake with {host} failed: {err}"))?,
+                )
+            }
+        };
+
+        let mut client = imap::Client::new(stream);
+        // After STARTTLS the greeting has already been read on the plain connection.
+        if security != Security::Starttls {
+            client
+                .read_greeting()
+                .map_err(|err| format!("IMAP greeting failed: {err}"))?;
+        }
+        client
+            .login(&self.username, &self.password)
+            .map_err(|(err, _)| format!("IMAP login failed: {err}"))
+    }
+
+    fn smtp_transport(&self) -> Result<SmtpTransport, String> {
+        let host = self.settings.smtp_host.trim();
+        let builder = match self.settings.smtp_security {
+            Security::Tls => SmtpTransport::relay(host),
+            Security::Starttls => SmtpTransport::starttls_relay(host),
+            Security::None => Ok(SmtpTransport::builder_dangerous(host)),
+        }
+        .map_err(|err| format!("Invalid SMTP server {host}: {err}"))?;
+        Ok(builder
+            .port(self.settings.smtp_port())
+            .credentials(Credentials::new(
+                self.username.clone(),
+                self.password.clone(),
+            ))
+            .timeout(Some(IO_TIMEOUT))
+            .build())
+    }
+
+    fn check_smtp(&self) -> Result<(), String> {
+        match self.smtp_transport()?.test_connection() {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("server did not accept the connection".to_string()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    /// Sends a complete RFC 2822 message to `recipients` and, when a sent mailbox is
+    /// configured, files a copy there. Filing failures are returned as a warning.
+    pub fn send(&self, recipients: &[String], message: &str) -> Result<Option<String>, String> {
+        let from = parse_address(&self.from_address)?;
+        let to = recipients
+            .iter()
+            .map(|recipient| parse_address(recipient))
+            .collect::<Result<Vec<_>, _>>()?;
+        let envelope =
+            Envelope::new(Some(from), to).map_err(|err| format!("Invalid recipients: {err}"))?;
+        self.smtp_transport()?
+            .send_raw(&envelope, message.as_bytes())
+            .map_err(|err| format!("SMTP send failed: {err}"))?;
+
+        let Some(sent_mailbox) = self
+            .settings
+            .sent_mailbox
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+        else {
+            return Ok(None);
+        };
+        let filed = self.imap_session().and_then(|mut session| {
+            let result = session
+                .append_with_flags(sent_mailbox, message.as_bytes(), &[imap::types::Flag::Seen])
+                .map_err(|err| err.to_string());
+            let _ = session.logout();
+            result
+        });
+        Ok(filed
+            .err()
+            .map(|err| format!("Sent, but could not file a copy in '{sent_mailbox}': {err}")))
+    }
+}
+
+/// Bare address of `value`, which may be in `Name <user@example.com>` form.
+fn parse_address(value: &str) -> Result<Address, String> {
+    let value = value.trim();
+    let bare = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value,
+    };
+    bare.trim()
+        .parse::<Address>()
+        .map_err(|err| format!("Invalid email address '{value}': {err}"))
+}
+
+/// Upgrades a plain IMAP connection with STARTTLS, consuming the server greeting.
+fn imap_starttls(tcp: TcpStream) -> Result<TcpStream, String> {
+    let io_err = |err: std::io::Error| format!("STARTTLS failed: {err}");
+    let mut reader = BufReader::new(tcp.try_clone().map_err(io_err)?);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(io_err)?;
+    if !line.starts_with("* OK") {
+        return Err(format!("Unexpected IMAP greeting: {}", line.trim()));
+    }
+    let mut writer = tcp;
+    writer.write_all(b"a0 STARTTLS\r\n").map_err(io_err)?;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(io_err)? == 0 {
+            return Err("STARTTLS failed: connection closed".to_string());
+        }
+        if let Some(status) = line.strip_prefix("a0 ") {
+            if status.starts_with("OK") {
+                return Ok(writer);
+            }
+            return Err(format!("Server refused STARTTLS: {}", status.trim()));
+        }
+    }
+}
+
+/// Selectable mailboxes, by full name.
+pub fn list_mailboxes(session: &mut ImapSession) -> Result<Vec<String>, String> {
+    let names = session
+        .list(Some(""), Some("*"))
+        .map_err(|err| format!("Failed to list mailboxes: {err}"))?;
+    Ok(names
+        .iter()
+        .filter(|name| {
+            !name
+                .attributes()
+                .iter()
+                .any(|attribute| matches!(attribute, imap::types::NameAttribute::NoSelect))
+        })
+        .map(|name| name.name().to_string())
+        .collect())
+}
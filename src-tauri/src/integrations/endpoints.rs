@@ -0,0 +1,93 @@
+//! REST API base URLs of the built-in integrations. Each API resolves, in order, from the
+//! connection's settings, a `{NAME}_API_BASE_URL` environment variable covering the whole
+//! integration, and the production default. That lets connections go through self-hosted
+//! proxies or enterprise gateways, and tests point whole tool flows at a local stand-in.
+
+use super::load_integration_settings;
+use crate::db::{Db, IntegrationConnection};
+
+/// One REST API of an integration.
+#[derive(Debug, Clone, Copy)]
+pub struct ApiEndpoint {
+    pub integration_id: &'static str,
+    /// Connection setting that overrides the base URL.
+    pub setting: &'static str,
+    /// Environment variable that overrides the base URL for every connection.
+    pub env_var: &'static str,
+    pub default_url: &'static str,
+}
+
+pub const GMAIL_API: ApiEndpoint = ApiEndpoint {
+    integration_id: "gmail",
+    setting: "api_base_url",
+    env_var: "GMAIL_API_BASE_URL",
+    default_url: "https://gmail.googleapis.com/gmail/v1",
+};
+
+pub const GOOGLE_CALENDAR_API: ApiEndpoint = ApiEndpoint {
+    integration_id: "google_calendar",
+    setting: "api_base_url",
+    env_var: "GOOGLE_CALENDAR_API_BASE_URL",
+    default_url: "https://www.googleapis.com/calendar/v3",
+};
+
+pub const TODOIST_REST_API: ApiEndpoint = ApiEndpoint {
+    integration_id: "todoist",
+    setting: "api_base_url",
+    env_var: "TODOIST_API_BASE_URL",
+    default_url: "https://api.todoist.com/rest/v2",
+};
+
+pub const TODOIST_SYNC_API: ApiEndpoint = ApiEndpoint {
+    integration_id: "todoist",
+    setting: "sync_api_base_url",
+    env_var: "TODOIST_SYNC_API_BASE_URL",
+    default_url: "https://api.todoist.com/sync/v9",
+};
+
+pub const GITHUB_API: ApiEndpoint = ApiEndpoint {
+    integration_id: "github",
+    setting: "api_base_url",
+    env_var: "GITHUB_API_BASE_URL",
+    default_url: "https://api.github.com",
+};
+
+impl ApiEndpoint {
+    /// Base URL when no connection is known: the environment override or the default.
+    pub fn default_base_url(&self) -> String {
+        std::env::var(self.env_var)
+            .ok()
+            .and_then(|value| normalize(&value))
+            .unwrap_or_else(|| self.default_url.to_string())
+    }
+
+    /// Base URL for one connection, without a trailing slash.
+    pub fn base_url(&self, db: &Db, connection_id: &str) -> String {
+        load_integration_settings(db, self.integration_id, connection_id)
+            .get(self.setting)
+            .and_then(|value| value.as_str())
+            .and_then(normalize)
+            .unwrap_or_else(|| self.default_base_url())
+    }
+
+    /// `path` (starting with `/`) on the connection's base URL.
+    pub fn url(&self, db: &Db, connection: &IntegrationConnection, path: &str) -> String {
+        format!("{}{path}", self.base_url(db, &connection.id))
+    }
+
+    /// JSON schema of the connection setting, for settings schemas.
+    pub fn setting_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "string",
+            "description": format!(
+                "Optional. API base URL for a proxy, gateway or local stand-in. Defaults to {}.",
+                self.default_url
+            )
+        })
+    }
+}
+
+fn normalize(value: &str) -> Option<String> {
+    let value = value.trim().trim_end_matches('/');
+    (!value.is_empty()).then(|| value.to_string())
+}
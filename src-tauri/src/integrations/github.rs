@@ -0,0 +1,227 @@
+//! GitHub issues, pull requests and notifications. Connections hold either an OAuth token
+//! (device flow) or a personal access token pasted as the access token. The REST API base
+//! URL is a connection setting so GitHub Enterprise Server and local stubs work.
+
+use super::endpoints::GITHUB_API;
+use super::{
+    DiscoveredResource, Integration, IntegrationCapability, IntegrationHealth, IntegrationMetadata,
+    IntegrationScope,
+};
+use crate::db::{Db, IntegrationConnection};
+use crate::tools::{register_github_tools, ToolError, ToolRegistry};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const API_VERSION: &str = "2022-11-28";
+const USER_AGENT: &str = "ai-agent/1.0";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct GitHubIntegration;
+
+impl Integration for GitHubIntegration {
+    fn manifest(&self) -> IntegrationMetadata {
+        IntegrationMetadata {
+            id: "github".to_string(),
+            name: "GitHub".to_string(),
+            provider: "github".to_string(),
+            description: "Search issues and pull requests, read diffs and comments, file issues, and check notifications.".to_string(),
+            auth_type: "oauth2".to_string(),
+            category: "developer".to_string(),
+            capabilities: vec![
+                IntegrationCapability::ActionExecute,
+                IntegrationCapability::Discovery,
+                IntegrationCapability::HealthCheck,
+            ],
+            scopes: vec![
+                IntegrationScope {
+                    label: "Read and write issues and pull requests, including private repositories"
+                        .to_string(),
+                    scope: "repo".to_string(),
+                },
+                IntegrationScope {
+                    label: "Read notifications".to_string(),
+                    scope: "notifications".to_string(),
+                },
+            ],
+            settings_schema: settings_schema(),
+        }
+    }
+
+    fn health_check(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<IntegrationHealth, String> {
+        let client = GitHubClient::load(db, connection).map_err(|err| err.message)?;
+        let response = client
+            .request(reqwest::Method::GET, "/user")
+            .send()
+            .map_err(|err| format!("Test request failed: {err}"))?;
+        let status = response.status();
+        Ok(IntegrationHealth {
+            ok: status.is_success(),
+            status: Some(status.as_u16()),
+            message: (!status.is_success()).then(|| format!("HTTP status {}", status.as_u16())),
+        })
+    }
+
+    fn discovery(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<Vec<DiscoveredResource>, String> {
+        let client = GitHubClient::load(db, connection).map_err(|err| err.message)?;
+        let repos = client
+            .get_json(
+                "/user/repos",
+                &[
+                    ("per_page", "100".to_string()),
+                    ("sort", "updated".to_string()),
+                ],
+            )
+            .map_err(|err| err.message)?;
+        Ok(repos
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|repo| {
+                let full_name = repo.get("full_name")?.as_str()?.to_string();
+                Some(DiscoveredResource {
+                    kind: "repository".to_string(),
+                    name: full_name.clone(),
+                    metadata: json!({
+                        "private": repo.get("private"),
+                        "default_branch": repo.get("default_branch"),
+                    }),
+                    id: full_name,
+                })
+            })
+            .collect())
+    }
+
+    fn register_actions(&self, registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+        register_github_tools(registry, db)
+    }
+
+    fn fetch_account_label(&self, access_token: &str) -> Option<String> {
+        GitHubClient::new(&GITHUB_API.default_base_url(), access_token)
+            .ok()?
+            .get_json("/user", &[])
+            .ok()?
+            .get("login")
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string())
+    }
+}
+
+fn settings_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "api_base_url": {
+                "type": "string",
+                "description": "REST API root. Defaults to https://api.github.com (or GITHUB_API_BASE_URL); GitHub Enterprise Server uses https://HOST/api/v3."
+            }
+        },
+        "additionalProperties": false
+    })
+}
+
+/// Authenticated REST client for one connection.
+pub struct GitHubClient {
+    http: Client,
+    base_url: String,
+    token: String,
+}
+
+impl GitHubClient {
+    pub fn new(base_url: &str, token: &str) -> Result<Self, ToolError> {
+        let http = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(USER_AGENT)
+            .build()
+            .map_err(|err| ToolError::new(format!("Failed to create HTTP client: {err}")))?;
+        Ok(Self {
+            http,
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        })
+    }
+
+    pub fn load(db: &Db, connection: &IntegrationConnection) -> Result<Self, ToolError> {
+        let token = crate::tools::get_access_token(connection)?;
+        Self::new(&GITHUB_API.base_url(db, &connection.id), &token)
+    }
+
+    pub fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{path}", self.base_url))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", API_VERSION)
+    }
+
+    pub fn send(&self, request: RequestBuilder) -> Result<Response, ToolError> {
+        let response = request
+            .send()
+            .map_err(|err| ToolError::upstream(format!("Failed to call GitHub API: {err}")))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        // GitHub answers 403 with an exhausted quota instead of 429.
+        let rate_limited = response
+            .headers()
+            .get("x-ratelimit-remaining")
+            .and_then(|value| value.to_str().ok())
+            == Some("0");
+        if rate_limited {
+            let reset_ms = response
+                .headers()
+                .get("x-ratelimit-reset")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<i64>().ok())
+                .map(|reset| (reset * 1000 - chrono::Utc::now().timestamp_millis()).max(0) as u64);
+            let error = ToolError::rate_limited("GitHub API rate limit exceeded");
+            return Err(match reset_ms {
+                Some(ms) => error.with_retry_after_ms(ms),
+                None => error,
+            });
+        }
+        let body = response.text().unwrap_or_default();
+        let detail = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|json| json.get("message")?.as_str().map(str::to_string))
+            .map(|message| format!(": {message}"))
+            .unwrap_or_default();
+        Err(ToolError::from_http_status(
+            status.as_u16(),
+            format!("GitHub API error: HTTP {status}{detail}"),
+        ))
+    }
+
+    pub fn get_json(&self, path: &str, query: &[(&str, String)]) -> Result<Value, ToolError> {
+        self.parse(self.send(self.request(reqwest::Method::GET, path).query(query))?)
+    }
+
+    /// GETs `path` in a different media type, e.g. `application/vnd.github.diff`.
+    pub fn get_text(&self, path: &str, accept: &str) -> Result<String, ToolError> {
+        self.send(
+            self.request(reqwest::Method::GET, path)
+                .header("Accept", accept),
+        )?
+        .text()
+        .map_err(|err| ToolError::upstream(format!("Failed to read GitHub response: {err}")))
+    }
+
+    pub fn post_json(&self, path: &str, body: &Value) -> Result<Value, ToolError> {
+        self.parse(self.send(self.request(reqwest::Method::POST, path).json(body))?)
+    }
+
+    fn parse(&self, response: Response) -> Result<Value, ToolError> {
+        response
+            .json::<Value>()
+            .map_err(|err| ToolError::new(format!("Failed to parse GitHub response: {err}")))
+    }
+}
@@ -1,21 +1,147 @@
-use super::IntegrationMetadata;
+use super::endpoints::{GMAIL_API, GOOGLE_CALENDAR_API};
+use super::sync::{self, SyncProgress};
+use super::{
+    get_json, http_health_check, DiscoveredResource, Integration, IntegrationCapability,
+    IntegrationHealth, IntegrationMetadata, IntegrationScope,
+};
+use crate::db::{Db, IntegrationConnection, SYNC_RESOURCE_CALENDAR_PREFIX, SYNC_RESOURCE_GMAIL};
+use crate::oauth::google_oauth_env_configured;
+use crate::tools::{
+    get_google_access_token, preferred_calendar_ids, register_gmail_tools,
+    register_google_calendar_tools, ToolRegistry,
+};
+use serde_json::json;
 
-pub fn integrations() -> Vec<IntegrationMetadata> {
-    vec![
+pub struct GmailIntegration;
+
+impl Integration for GmailIntegration {
+    fn manifest(&self) -> IntegrationMetadata {
         IntegrationMetadata {
             id: "gmail".to_string(),
             name: "Gmail".to_string(),
             provider: "google".to_string(),
-            description: "Read and send email, manage labels.".to_string(),
+            description: "Read, draft, and send email, manage labels and attachments.".to_string(),
             auth_type: "oauth2".to_string(),
             category: "email".to_string(),
             capabilities: vec![
-                "sync".to_string(),
-                "webhook_ingest".to_string(),
-                "action_execute".to_string(),
-                "discovery".to_string(),
+                IntegrationCapability::Sync,
+                IntegrationCapability::ActionExecute,
+                IntegrationCapability::Discovery,
+                IntegrationCapability::HealthCheck,
+            ],
+            scopes: vec![
+                IntegrationScope {
+                    label: "Read email and labels".to_string(),
+                    scope: "https://www.googleapis.com/auth/gmail.readonly".to_string(),
+                },
+                IntegrationScope {
+                    label: "Send email".to_string(),
+                    scope: "https://www.googleapis.com/auth/gmail.send".to_string(),
+                },
+                IntegrationScope {
+                    label: "Create and send drafts".to_string(),
+                    scope: "https://www.googleapis.com/auth/gmail.compose".to_string(),
+                },
+                IntegrationScope {
+                    label: "Archive, star, and mark email read".to_string(),
+                    scope: "https://www.googleapis.com/auth/gmail.modify".to_string(),
+                },
             ],
-        },
+            settings_schema: json!({
+                "type": "object",
+                "properties": {
+                    "api_base_url": GMAIL_API.setting_schema()
+                },
+                "additionalProperties": false
+            }),
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        google_oauth_env_configured()
+    }
+
+    fn access_token(&self, db: &Db, connection: &IntegrationConnection) -> Result<String, String> {
+        get_google_access_token(db, connection).map_err(|err| err.message)
+    }
+
+    fn health_check(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<IntegrationHealth, String> {
+        let token = self.access_token(db, connection)?;
+        http_health_check(&GMAIL_API.url(db, connection, "/users/me/profile"), &token)
+    }
+
+    fn discovery(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<Vec<DiscoveredResource>, String> {
+        let token = self.access_token(db, connection)?;
+        let json = get_json(
+            &GMAIL_API.url(db, connection, "/users/me/labels"),
+            &token,
+            "Gmail labels",
+        )?;
+        Ok(json
+            .get("labels")
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|label| {
+                let id = label.get("id")?.as_str()?.to_string();
+                Some(DiscoveredResource {
+                    kind: "label".to_string(),
+                    name: label
+                        .get("name")
+                        .and_then(|value| value.as_str())
+                        .unwrap_or(&id)
+                        .to_string(),
+                    metadata: json!({ "type": label.get("type") }),
+                    id,
+                })
+            })
+            .collect())
+    }
+
+    fn sync_resources(&self, _db: &Db, _connection: &IntegrationConnection) -> Vec<String> {
+        vec![SYNC_RESOURCE_GMAIL.to_string()]
+    }
+
+    fn sync(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+        token: &str,
+        _resource: &str,
+        cursor: Option<&str>,
+    ) -> Result<SyncProgress, String> {
+        sync::gmail::sync(db, &connection.id, token, cursor)
+    }
+
+    fn register_actions(&self, registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+        register_gmail_tools(registry, db)
+    }
+
+    fn fetch_account_label(&self, access_token: &str) -> Option<String> {
+        get_json(
+            &format!("{}/users/me/profile", GMAIL_API.default_base_url()),
+            access_token,
+            "Gmail profile",
+        )
+        .ok()?
+        .get("emailAddress")
+        .and_then(|value| value.as_str())
+        .map(|value| value.to_string())
+    }
+}
+
+pub struct GoogleCalendarIntegration;
+
+impl Integration for GoogleCalendarIntegration {
+    fn manifest(&self) -> IntegrationMetadata {
         IntegrationMetadata {
             id: "google_calendar".to_string(),
             name: "Google Calendar".to_string(),
@@ -24,10 +150,129 @@ pub fn integrations() -> Vec<IntegrationMetadata> {
             auth_type: "oauth2".to_string(),
             category: "calendar".to_string(),
             capabilities: vec![
-                "sync".to_string(),
-                "action_execute".to_string(),
-                "discovery".to_string(),
+                IntegrationCapability::Sync,
+                IntegrationCapability::ActionExecute,
+                IntegrationCapability::Discovery,
+                IntegrationCapability::HealthCheck,
+            ],
+            scopes: vec![
+                IntegrationScope {
+                    label: "Read calendars".to_string(),
+                    scope: "https://www.googleapis.com/auth/calendar.readonly".to_string(),
+                },
+                IntegrationScope {
+                    label: "Create and edit events".to_string(),
+                    scope: "https://www.googleapis.com/auth/calendar.events".to_string(),
+                },
             ],
-        },
-    ]
+            settings_schema: json!({
+                "type": "object",
+                "properties": {
+                    "api_base_url": GOOGLE_CALENDAR_API.setting_schema(),
+                    "calendar_ids": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Calendars used by tools and sync. Defaults to the primary calendar."
+                    },
+                    "working_hours_start": {
+                        "type": "string",
+                        "pattern": "^\\d{2}:\\d{2}$",
+                        "description": "Start of the working day for gcal.find_free_time, e.g. 09:00."
+                    },
+                    "working_hours_end": {
+                        "type": "string",
+                        "pattern": "^\\d{2}:\\d{2}$",
+                        "description": "End of the working day, e.g. 17:00."
+                    },
+                    "working_days": {
+                        "type": "array",
+                        "items": { "type": "integer", "minimum": 1, "maximum": 7 },
+                        "description": "ISO weekdays (1 = Monday). Defaults to Monday to Friday."
+                    }
+                }
+            }),
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        google_oauth_env_configured()
+    }
+
+    fn access_token(&self, db: &Db, connection: &IntegrationConnection) -> Result<String, String> {
+        get_google_access_token(db, connection).map_err(|err| err.message)
+    }
+
+    fn health_check(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<IntegrationHealth, String> {
+        let token = self.access_token(db, connection)?;
+        http_health_check(
+            &GOOGLE_CALENDAR_API.url(db, connection, "/users/me/calendarList?maxResults=1"),
+            &token,
+        )
+    }
+
+    fn discovery(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<Vec<DiscoveredResource>, String> {
+        let token = self.access_token(db, connection)?;
+        let json = get_json(
+            &GOOGLE_CALENDAR_API.url(db, connection, "/users/me/calendarList"),
+            &token,
+            "Calendar list",
+        )?;
+        Ok(json
+            .get("items")
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                let id = item.get("id")?.as_str()?.to_string();
+                Some(DiscoveredResource {
+                    kind: "calendar".to_string(),
+                    name: item
+                        .get("summary")
+                        .and_then(|value| value.as_str())
+                        .unwrap_or(&id)
+                        .to_string(),
+                    metadata: json!({
+                        "primary": item.get("primary").and_then(|value| value.as_bool()).unwrap_or(false),
+                        "time_zone": item.get("timeZone"),
+                        "access_role": item.get("accessRole"),
+                    }),
+                    id,
+                })
+            })
+            .collect())
+    }
+
+    fn sync_resources(&self, db: &Db, connection: &IntegrationConnection) -> Vec<String> {
+        preferred_calendar_ids(db, &connection.id)
+            .unwrap_or_else(|| vec!["primary".to_string()])
+            .into_iter()
+            .map(|calendar_id| format!("{SYNC_RESOURCE_CALENDAR_PREFIX}{calendar_id}"))
+            .collect()
+    }
+
+    fn sync(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+        token: &str,
+        resource: &str,
+        cursor: Option<&str>,
+    ) -> Result<SyncProgress, String> {
+        let calendar_id = resource
+            .strip_prefix(SYNC_RESOURCE_CALENDAR_PREFIX)
+            .ok_or_else(|| format!("Unknown calendar sync resource '{resource}'"))?;
+        sync::calendar::sync(db, &connection.id, token, calendar_id, cursor)
+    }
+
+    fn register_actions(&self, registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+        register_google_calendar_tools(registry, db)
+    }
 }
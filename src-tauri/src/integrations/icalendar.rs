@@ -0,0 +1,1325 @@
+//! Minimal iCalendar (RFC 5545) support for CalDAV and ICS subscriptions: reading and
+//! writing components, `TZID` time zones, and expanding recurring events.
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+const PRODID: &str = "-//ai-agent//Calendar//EN";
+/// Bounds the work per series, e.g. a daily rule without an end is walked for ~130 years.
+const MAX_RECURRENCE_PERIODS: usize = 50_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    /// Uppercased property name.
+    pub name: String,
+    /// Parameters with uppercased names and unquoted values.
+    pub params: Vec<(String, String)>,
+    /// Raw value, still escaped for TEXT properties.
+    pub value: String,
+}
+
+impl Property {
+    pub fn new(name: &str, value: impl Into<String>) -> Self {
+        Self {
+            name: name.to_ascii_uppercase(),
+            params: Vec::new(),
+            value: value.into(),
+        }
+    }
+
+    /// A TEXT property, escaping `value`.
+    pub fn text(name: &str, value: &str) -> Self {
+        Self::new(name, escape_text(value))
+    }
+
+    pub fn with_param(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.params.push((name.to_ascii_uppercase(), value.into()));
+        self
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn text_value(&self) -> String {
+        unescape_text(&self.value)
+    }
+
+    /// Parses one unfolded content line, e.g. `DTSTART;TZID=Europe/Berlin:20260105T100000`.
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let mut in_quotes = false;
+        let colon = line.char_indices().find_map(|(index, ch)| match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                None
+            }
+            ':' if !in_quotes => Some(index),
+            _ => None,
+        })?;
+        let mut parts = split_unquoted(&line[..colon], ';').into_iter();
+        let name = parts.next()?.trim().to_ascii_uppercase();
+        if name.is_empty() {
+            return None;
+        }
+        let params = parts
+            .filter_map(|part| {
+                let (key, value) = part.split_once('=')?;
+                Some((
+                    key.trim().to_ascii_uppercase(),
+                    value.trim().trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+        Some(Self {
+            name,
+            params,
+            value: line[colon + 1..].to_string(),
+        })
+    }
+
+    /// The unfolded content line, e.g. `RRULE:FREQ=WEEKLY;BYDAY=MO`.
+    pub fn to_line(&self) -> String {
+        let mut line = self.name.clone();
+        for (key, value) in &self.params {
+            if value.contains([':', ';', ',']) {
+                line.push_str(&format!(";{key}=\"{value}\""));
+            } else {
+                line.push_str(&format!(";{key}={value}"));
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        line
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_ascii_uppercase(),
+            ..Self::default()
+        }
+    }
+
+    /// An empty VCALENDAR with the required version and product id.
+    pub fn calendar() -> Self {
+        let mut calendar = Self::new("VCALENDAR");
+        calendar.push(Property::new("VERSION", "2.0"));
+        calendar.push(Property::new("PRODID", PRODID));
+        calendar
+    }
+
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn properties<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties
+            .iter()
+            .filter(move |property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Unescaped value of a TEXT property.
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name).map(Property::text_value)
+    }
+
+    pub fn push(&mut self, property: Property) {
+        self.properties.push(property);
+    }
+
+    /// Replaces every property with the same name.
+    pub fn set(&mut self, property: Property) {
+        self.remove(&property.name);
+        self.properties.push(property);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.properties
+            .retain(|property| !property.name.eq_ignore_ascii_case(name));
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Component> {
+        self.components
+            .iter()
+            .filter(move |component| component.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Serializes with CRLF line endings, folding lines at 75 octets.
+    pub fn to_ics(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn write(&self, out: &mut String) {
+        write_folded(out, &format!("BEGIN:{}", self.name));
+        for property in &self.properties {
+            write_folded(out, &property.to_line());
+        }
+        for component in &self.components {
+            component.write(out);
+        }
+        write_folded(out, &format!("END:{}", self.name));
+    }
+}
+
+/// Parses an iCalendar stream and returns its first top-level component (the VCALENDAR).
+pub fn parse(text: &str) -> Result<Component, String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        if let Some(continuation) = raw.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(continuation);
+                continue;
+            }
+        }
+        if !raw.trim().is_empty() {
+            lines.push(raw.to_string());
+        }
+    }
+
+    let mut stack: Vec<Component> = Vec::new();
+    for line in lines {
+        let Some(property) = Property::parse_line(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component::new(property.value.trim())),
+            "END" => {
+                let component = stack
+                    .pop()
+                    .ok_or_else(|| format!("Unexpected END:{}", property.value.trim()))?;
+                if !component.name.eq_ignore_ascii_case(property.value.trim()) {
+                    return Err(format!(
+                        "END:{} does not close BEGIN:{}",
+                        property.value.trim(),
+                        component.name
+                    ));
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.components.push(component),
+                    None => return Ok(component),
+                }
+            }
+            _ => {
+                if let Some(current) = stack.last_mut() {
+                    current.properties.push(property);
+                }
+            }
+        }
+    }
+    Err("No complete iCalendar component found".to_string())
+}
+
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (index, ch) in value.char_indices() {
+        if ch == '"' {
+            in_quotes = !in_quotes;
+        } else if ch == separator && !in_quotes {
+            parts.push(&value[start..index]);
+            start = index + ch.len_utf8();
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+fn write_folded(out: &mut String, line: &str) {
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(ch);
+        width += ch.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+pub fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+pub fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// A DATE or DATE-TIME value. Zoned times keep their wall-clock time and `TZID`, so
+/// recurrences follow daylight saving changes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventTime {
+    Date(NaiveDate),
+    Utc(NaiveDateTime),
+    Floating(NaiveDateTime),
+    Zoned(NaiveDateTime, String),
+}
+
+impl EventTime {
+    pub fn from_property(property: &Property) -> Option<Self> {
+        Self::parse_value(
+            property.value.trim(),
+            property.param("TZID"),
+            property.param("VALUE") == Some("DATE"),
+        )
+    }
+
+    pub fn parse_value(value: &str, tzid: Option<&str>, is_date: bool) -> Option<Self> {
+        let value = value.trim();
+        if is_date || value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .map(EventTime::Date);
+        }
+        if let Some(utc) = value.strip_suffix('Z') {
+            return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+                .ok()
+                .map(EventTime::Utc);
+        }
+        let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        Some(match tzid.map(str::trim).filter(|tzid| !tzid.is_empty()) {
+            Some(tzid) => EventTime::Zoned(naive, tzid.to_string()),
+            None => EventTime::Floating(naive),
+        })
+    }
+
+    /// All values of a multi-valued property such as EXDATE or RDATE.
+    pub fn list_from_property(property: &Property) -> Vec<Self> {
+        let is_date = property.param("VALUE") == Some("DATE");
+        property
+            .value
+            .split(',')
+            .filter_map(|value| Self::parse_value(value, property.param("TZID"), is_date))
+            .collect()
+    }
+
+    pub fn is_date(&self) -> bool {
+        matches!(self, EventTime::Date(_))
+    }
+
+    /// Wall-clock time; dates start at midnight.
+    pub fn naive(&self) -> NaiveDateTime {
+        match self {
+            EventTime::Date(date) => date.and_hms_opt(0, 0, 0).unwrap_or_default(),
+            EventTime::Utc(naive) | EventTime::Floating(naive) | EventTime::Zoned(naive, _) => {
+                *naive
+            }
+        }
+    }
+
+    /// The same kind of value at another wall-clock time.
+    pub fn with_naive(&self, naive: NaiveDateTime) -> Self {
+        match self {
+            EventTime::Date(_) => EventTime::Date(naive.date()),
+            EventTime::Utc(_) => EventTime::Utc(naive),
+            EventTime::Floating(_) => EventTime::Floating(naive),
+            EventTime::Zoned(_, tzid) => EventTime::Zoned(naive, tzid.clone()),
+        }
+    }
+
+    pub fn tzid(&self) -> Option<&str> {
+        match self {
+            EventTime::Zoned(_, tzid) => Some(tzid),
+            _ => None,
+        }
+    }
+
+    pub fn format_value(&self) -> String {
+        match self {
+            EventTime::Date(date) => date.format("%Y%m%d").to_string(),
+            EventTime::Utc(naive) => naive.format("%Y%m%dT%H%M%SZ").to_string(),
+            EventTime::Floating(naive) | EventTime::Zoned(naive, _) => {
+                naive.format("%Y%m%dT%H%M%S").to_string()
+            }
+        }
+    }
+
+    pub fn to_property(&self, name: &str) -> Property {
+        let property = Property::new(name, self.format_value());
+        match self {
+            EventTime::Date(_) => property.with_param("VALUE", "DATE"),
+            EventTime::Zoned(_, tzid) => property.with_param("TZID", tzid.clone()),
+            _ => property,
+        }
+    }
+}
+
+/// Looks up an IANA zone, also accepting prefixed ids such as
+/// `/mozilla.org/20050126_1/Europe/Berlin`.
+pub fn iana_zone(tzid: &str) -> Option<Tz> {
+    let tzid = tzid.trim().trim_matches('"');
+    if let Ok(tz) = tzid.parse::<Tz>() {
+        return Some(tz);
+    }
+    tzid.match_indices('/')
+        .find_map(|(index, _)| tzid[index + 1..].parse::<Tz>().ok())
+}
+
+/// Resolves `TZID`s: IANA names through the tz database, anything else (e.g. Windows zone
+/// names) through the fixed offset of the calendar's own VTIMEZONE.
+#[derive(Debug, Clone, Default)]
+pub struct Zones {
+    custom: HashMap<String, FixedOffset>,
+}
+
+impl Zones {
+    pub fn from_calendar(calendar: &Component) -> Self {
+        let mut custom = HashMap::new();
+        for zone in calendar.children("VTIMEZONE") {
+            let Some(tzid) = zone.property("TZID").map(|p| p.value.trim().to_string()) else {
+                continue;
+            };
+            let offset = zone
+                .children("STANDARD")
+                .chain(zone.children("DAYLIGHT"))
+                .find_map(|rule| rule.property("TZOFFSETTO"))
+                .and_then(|property| parse_utc_offset(&property.value));
+            if let Some(offset) = offset {
+                custom.insert(tzid, offset);
+            }
+        }
+        Self { custom }
+    }
+
+    /// The instant a value stands for. Dates and floating times use the local time zone.
+    pub fn resolve(&self, time: &EventTime) -> DateTime<FixedOffset> {
+        let naive = time.naive();
+        match time {
+            EventTime::Utc(_) => Utc.from_utc_datetime(&naive).fixed_offset(),
+            EventTime::Date(_) | EventTime::Floating(_) => local_instant(&Local, naive),
+            EventTime::Zoned(_, tzid) => {
+                if let Some(tz) = iana_zone(tzid) {
+                    local_instant(&tz, naive)
+                } else if let Some(offset) = self.custom.get(tzid) {
+                    local_instant(offset, naive)
+                } else {
+                    local_instant(&Local, naive)
+                }
+            }
+        }
+    }
+
+    /// Wall-clock time of `instant` in the zone of `like`, for comparing against a series.
+    pub fn to_wall_time(&self, instant: DateTime<Utc>, like: &EventTime) -> NaiveDateTime {
+        match like {
+            EventTime::Utc(_) => instant.naive_utc(),
+            EventTime::Date(_) | EventTime::Floating(_) => {
+                instant.with_timezone(&Local).naive_local()
+            }
+            EventTime::Zoned(_, tzid) => {
+                if let Some(tz) = iana_zone(tzid) {
+                    instant.with_timezone(&tz).naive_local()
+                } else if let Some(offset) = self.custom.get(tzid) {
+                    instant.with_timezone(offset).naive_local()
+                } else {
+                    instant.with_timezone(&Local).naive_local()
+                }
+            }
+        }
+    }
+}
+
+/// Wall-clock time in `tz`. Times skipped by a daylight saving jump move forward an hour.
+fn local_instant<Z: TimeZone>(tz: &Z, naive: NaiveDateTime) -> DateTime<FixedOffset> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|instant| instant.fixed_offset())
+        .unwrap_or_else(|| Utc.from_utc_datetime(&naive).fixed_offset())
+}
+
+fn parse_utc_offset(value: &str) -> Option<FixedOffset> {
+    let value = value.trim();
+    let (sign, digits) = match value.chars().next()? {
+        '+' => (1, &value[1..]),
+        '-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    if digits.len() < 4 || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let hours = digits[0..2].parse::<i32>().ok()?;
+    let minutes = digits[2..4].parse::<i32>().ok()?;
+    let seconds = digits
+        .get(4..6)
+        .and_then(|s| s.parse::<i32>().ok())
+        .unwrap_or(0);
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+fn format_utc_offset(offset: FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    format!("{sign}{:02}{:02}", minutes / 60, minutes % 60)
+}
+
+/// A VTIMEZONE for an IANA zone, with yearly rules derived from its transitions in
+/// `year`. Servers and clients that don't know the zone name fall back to these rules.
+pub fn vtimezone(tzid: &str, year: i32) -> Option<Component> {
+    let tz = iana_zone(tzid)?;
+    let offset_at =
+        |instant: DateTime<Utc>| tz.offset_from_utc_datetime(&instant.naive_utc()).fix();
+    let year_start = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).single()?;
+    let year_end = Utc.with_ymd_and_hms(year + 1, 1, 1, 0, 0, 0).single()?;
+
+    let mut transitions = Vec::new();
+    let mut previous = offset_at(year_start);
+    let mut hour = year_start;
+    while hour < year_end {
+        let next_hour = hour + Duration::hours(1);
+        let offset = offset_at(next_hour);
+        if offset != previous {
+            let mut instant = hour;
+            while offset_at(instant) == previous {
+                instant += Duration::minutes(1);
+            }
+            transitions.push((instant, previous, offset));
+            previous = offset;
+        }
+        hour = next_hour;
+    }
+
+    let mut zone = Component::new("VTIMEZONE");
+    zone.push(Property::new("TZID", tzid));
+    if transitions.is_empty() {
+        let mut standard = Component::new("STANDARD");
+        standard.push(Property::new("DTSTART", "19700101T000000"));
+        standard.push(Property::new("TZOFFSETFROM", format_utc_offset(previous)));
+        standard.push(Property::new("TZOFFSETTO", format_utc_offset(previous)));
+        zone.components.push(standard);
+        return Some(zone);
+    }
+    for (instant, from, to) in transitions {
+        let kind = if to.local_minus_utc() > from.local_minus_utc() {
+            "DAYLIGHT"
+        } else {
+            "STANDARD"
+        };
+        let wall = instant.with_timezone(&from).naive_local();
+        let date = wall.date();
+        let last_day = days_in_month(date.year(), date.month());
+        let ordinal = if date.day() + 7 > last_day {
+            -1
+        } else {
+            ((date.day() - 1) / 7 + 1) as i32
+        };
+        let mut rule = Component::new(kind);
+        rule.push(Property::new(
+            "DTSTART",
+            wall.format("%Y%m%dT%H%M%S").to_string(),
+        ));
+        rule.push(Property::new("TZOFFSETFROM", format_utc_offset(from)));
+        rule.push(Property::new("TZOFFSETTO", format_utc_offset(to)));
+        rule.push(Property::new(
+            "RRULE",
+            format!(
+                "FREQ=YEARLY;BYMONTH={};BYDAY={ordinal}{}",
+                date.month(),
+                weekday_code(date.weekday())
+            ),
+        ));
+        zone.components.push(rule);
+    }
+    Some(zone)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The supported subset of RRULE: DAILY to YEARLY with INTERVAL, COUNT, UNTIL, BYDAY
+/// (with ordinals), BYMONTHDAY, BYMONTH and BYSETPOS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<EventTime>,
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+}
+
+impl RecurrenceRule {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut rule = RecurrenceRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+        };
+        let mut freq = None;
+        let number_list = |value: &str| {
+            value
+                .split(',')
+                .map(|item| item.trim().parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("Invalid RRULE list '{value}'"))
+        };
+        for part in value.trim().split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid RRULE part '{part}'"))?;
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.trim().to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("Unsupported RRULE frequency {other}")),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .trim()
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("Invalid RRULE interval '{value}'"))?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .trim()
+                            .parse::<u32>()
+                            .map_err(|_| format!("Invalid RRULE count '{value}'"))?,
+                    )
+                }
+                "UNTIL" => {
+                    rule.until = Some(
+                        EventTime::parse_value(value, None, false)
+                            .ok_or_else(|| format!("Invalid RRULE until '{value}'"))?,
+                    )
+                }
+                "BYDAY" => {
+                    for item in value.split(',') {
+                        let item = item.trim().to_ascii_uppercase();
+                        if item.len() < 2 {
+                            return Err(format!("Invalid RRULE day '{item}'"));
+                        }
+                        let (ordinal, code) = item.split_at(item.len() - 2);
+                        let weekday = parse_weekday(code)
+                            .ok_or_else(|| format!("Invalid RRULE day '{item}'"))?;
+                        let ordinal = match ordinal.trim_start_matches('+') {
+                            "" => None,
+                            ordinal => Some(
+                                ordinal
+                                    .parse::<i32>()
+                                    .map_err(|_| format!("Invalid RRULE day '{item}'"))?,
+                            ),
+                        };
+                        rule.by_day.push((ordinal, weekday));
+                    }
+                }
+                "BYMONTHDAY" => rule.by_month_day = number_list(value)?,
+                "BYMONTH" => {
+                    rule.by_month = number_list(value)?
+                        .into_iter()
+                        .map(|month| month as u32)
+                        .collect()
+                }
+                "BYSETPOS" => rule.by_set_pos = number_list(value)?,
+                "WKST" => {}
+                other => return Err(format!("Unsupported RRULE part {other}")),
+            }
+        }
+        rule.freq = freq.ok_or_else(|| "RRULE without FREQ".to_string())?;
+        Ok(rule)
+    }
+
+    /// Wall-clock start times of the series from `start`, ending at `until` or after
+    /// COUNT occurrences. Only those in `window` are returned, at most `limit`.
+    pub fn expand(
+        &self,
+        start: NaiveDateTime,
+        until: Option<NaiveDateTime>,
+        window: (NaiveDateTime, NaiveDateTime),
+        limit: usize,
+    ) -> Vec<NaiveDateTime> {
+        let mut found = Vec::new();
+        let mut produced = 0u32;
+        let start_date = start.date();
+        let step = self.interval as i64;
+        for period in 0..MAX_RECURRENCE_PERIODS as i64 {
+            let offset = period * step;
+            // Periods past the last representable date end the series.
+            let Some(mut dates) = self.period_dates(start_date, offset) else {
+                break;
+            };
+            if !self.by_month.is_empty() {
+                dates.retain(|date| self.by_month.contains(&date.month()));
+            }
+            if self.freq == Frequency::Daily {
+                dates.retain(|date| {
+                    (self.by_month_day.is_empty() || self.matches_month_day(*date))
+                        && (self.by_day.is_empty()
+                            || self.by_day.iter().any(|(_, day)| *day == date.weekday()))
+                });
+            }
+            dates.sort();
+            dates.dedup();
+            if !self.by_set_pos.is_empty() {
+                let len = dates.len() as i32;
+                let mut selected = self
+                    .by_set_pos
+                    .iter()
+                    .filter_map(|pos| {
+                        let index = if *pos > 0 { pos - 1 } else { len + pos };
+                        (0..len).contains(&index).then(|| dates[index as usize])
+                    })
+                    .collect::<Vec<_>>();
+                selected.sort();
+                selected.dedup();
+                dates = selected;
+            }
+
+            for date in dates {
+                let occurrence = date.and_time(start.time());
+                if occurrence < start {
+                    continue;
+                }
+                if until.is_some_and(|until| occurrence > until) || occurrence > window.1 {
+                    return found;
+                }
+                produced += 1;
+                if occurrence >= window.0 {
+                    found.push(occurrence);
+                    if found.len() >= limit {
+                        return found;
+                    }
+                }
+                if self.count.is_some_and(|count| produced >= count) {
+                    return found;
+                }
+            }
+        }
+        found
+    }
+
+    /// Candidate dates of the period `offset` frequency units after `start_date`, before the
+    /// BYxxx filters. `None` once the period lies outside the dates chrono can represent.
+    fn period_dates(&self, start_date: NaiveDate, offset: i64) -> Option<Vec<NaiveDate>> {
+        let offset = u64::try_from(offset).ok()?;
+        let dates = match self.freq {
+            Frequency::Daily => vec![start_date.checked_add_days(Days::new(offset))?],
+            Frequency::Weekly => {
+                let from_monday = start_date.weekday().num_days_from_monday() as u64;
+                let week = start_date
+                    .checked_sub_days(Days::new(from_monday))?
+                    .checked_add_days(Days::new(offset.checked_mul(7)?))?;
+                if self.by_day.is_empty() {
+                    vec![week.checked_add_days(Days::new(from_monday))?]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|(_, weekday)| {
+                            week.checked_add_days(Days::new(weekday.num_days_from_monday() as u64))
+                        })
+                        .collect::<Option<Vec<_>>>()?
+                }
+            }
+            Frequency::Monthly => {
+                let months = (start_date.year() as i64 * 12 + start_date.month0() as i64)
+                    .checked_add(i64::try_from(offset).ok()?)?;
+                let year = representable_year(months.div_euclid(12))?;
+                self.month_dates(year, months.rem_euclid(12) as u32 + 1, start_date.day())
+            }
+            Frequency::Yearly => {
+                let year = representable_year(
+                    (start_date.year() as i64).checked_add(i64::try_from(offset).ok()?)?,
+                )?;
+                let months = if self.by_month.is_empty() {
+                    vec![start_date.month()]
+                } else {
+                    self.by_month.clone()
+                };
+                months
+                    .into_iter()
+                    .flat_map(|month| self.month_dates(year, month, start_date.day()))
+                    .collect()
+            }
+        };
+        Some(dates)
+    }
+
+    fn matches_month_day(&self, date: NaiveDate) -> bool {
+        let last = days_in_month(date.year(), date.month()) as i32;
+        self.by_month_day.iter().any(|day| {
+            let day = if *day > 0 { *day } else { last + day + 1 };
+            day == date.day() as i32
+        })
+    }
+
+    fn month_dates(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let last = days_in_month(year, month);
+        let in_month = |day: u32| NaiveDate::from_ymd_opt(year, month, day);
+        if !self.by_month_day.is_empty() {
+            return (1..=last)
+                .filter_map(in_month)
+                .filter(|date| self.matches_month_day(*date))
+                .filter(|date| {
+                    self.by_day.is_empty()
+                        || self.by_day.iter().any(|(_, day)| *day == date.weekday())
+                })
+                .collect();
+        }
+        if !self.by_day.is_empty() {
+            let mut dates = Vec::new();
+            for (ordinal, weekday) in &self.by_day {
+                let matching = (1..=last)
+                    .filter_map(in_month)
+                    .filter(|date| date.weekday() == *weekday)
+                    .collect::<Vec<_>>();
+                match ordinal {
+                    None => dates.extend(matching),
+                    Some(n) => {
+                        let index = if *n > 0 {
+                            n - 1
+                        } else {
+                            matching.len() as i32 + n
+                        };
+                        if let Some(date) = usize::try_from(index)
+                            .ok()
+                            .and_then(|index| matching.get(index))
+                        {
+                            dates.push(*date);
+                        }
+                    }
+                }
+            }
+            return dates;
+        }
+        in_month(default_day).into_iter().collect()
+    }
+}
+
+fn representable_year(year: i64) -> Option<i32> {
+    (NaiveDate::MIN.year() as i64..=NaiveDate::MAX.year() as i64)
+        .contains(&year)
+        .then_some(year as i32)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|date| date.pred_opt())
+        .map(|date| date.day())
+        .unwrap_or(28)
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    Some(match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// RFC 5545 DURATION such as `PT1H30M`, `P1D` or `-P1W`.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for ch in value.chars() {
+        match ch {
+            '0'..='9' => number.push(ch),
+            'T' => {}
+            unit => {
+                let amount = number.parse::<i64>().ok()?;
+                number.clear();
+                let part = match unit {
+                    'W' => Duration::try_weeks(amount),
+                    'D' => Duration::try_days(amount),
+                    'H' => Duration::try_hours(amount),
+                    'M' => Duration::try_minutes(amount),
+                    'S' => Duration::try_seconds(amount),
+                    _ => return None,
+                }?;
+                total = total.checked_add(&part)?;
+            }
+        }
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// One occurrence of an event. `component` is the series master, or the override
+/// VEVENT for an occurrence that was edited on its own.
+#[derive(Debug, Clone)]
+pub struct Occurrence<'a> {
+    pub component: &'a Component,
+    pub uid: String,
+    pub start: EventTime,
+    pub end: EventTime,
+    /// Set for occurrences of a recurring series.
+    pub recurrence_id: Option<EventTime>,
+}
+
+pub fn event_start(event: &Component) -> Option<EventTime> {
+    event.property("DTSTART").and_then(EventTime::from_property)
+}
+
+/// DTEND, else DTSTART plus DURATION, else one day for all-day events.
+pub fn event_end(event: &Component, start: &EventTime) -> EventTime {
+    if let Some(end) = event.property("DTEND").and_then(EventTime::from_property) {
+        return end;
+    }
+    let duration = event
+        .property("DURATION")
+        .and_then(|property| parse_duration(&property.value))
+        .unwrap_or_else(|| {
+            if start.is_date() {
+                Duration::days(1)
+            } else {
+                Duration::zero()
+            }
+        });
+    start.with_naive(start.naive() + duration)
+}
+
+/// Occurrences of the calendar's events that overlap `range`, sorted by start. Recurring
+/// events are expanded, with EXDATEs removed and edited occurrences replaced.
+pub fn occurrences<'a>(
+    calendar: &'a Component,
+    zones: &Zones,
+    range: (DateTime<Utc>, DateTime<Utc>),
+    limit: usize,
+) -> Vec<Occurrence<'a>> {
+    let instant_ms = |time: &EventTime| zones.resolve(time).timestamp_millis();
+    let mut overrides: HashMap<(String, i64), &Component> = HashMap::new();
+    let mut masters = Vec::new();
+    for event in calendar.children("VEVENT") {
+        let uid = event.text("UID").unwrap_or_default();
+        match event
+            .property("RECURRENCE-ID")
+            .and_then(EventTime::from_property)
+        {
+            Some(recurrence_id) => {
+                overrides.insert((uid, instant_ms(&recurrence_id)), event);
+            }
+            None => masters.push((uid, event)),
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut used_overrides = Vec::new();
+    for (uid, event) in masters {
+        let Some(start) = event_start(event) else {
+            continue;
+        };
+        let end = event_end(event, &start);
+        let rule = event
+            .property("RRULE")
+            .and_then(|property| RecurrenceRule::parse(&property.value).ok());
+        let Some(rule) = rule else {
+            found.push(Occurrence {
+                component: event,
+                uid,
+                start,
+                end,
+                recurrence_id: None,
+            });
+            continue;
+        };
+
+        let duration = end.naive() - start.naive();
+        // Pad the window by the event length so occurrences starting before it still overlap.
+        let window = (
+            zones.to_wall_time(range.0, &start) - duration - Duration::days(1),
+            zones.to_wall_time(range.1, &start) + Duration::days(1),
+        );
+        let until = rule.until.as_ref().map(|until| match until {
+            EventTime::Utc(_) => {
+                zones.to_wall_time(zones.resolve(until).with_timezone(&Utc), &start)
+            }
+            // A date UNTIL includes that whole day.
+            EventTime::Date(date) => date.and_hms_opt(23, 59, 59).unwrap_or_default(),
+            other => other.naive(),
+        });
+        let mut starts = rule.expand(start.naive(), until, window, limit.saturating_mul(4));
+        for rdate in event
+            .properties("RDATE")
+            .flat_map(EventTime::list_from_property)
+        {
+            let naive = zones.to_wall_time(zones.resolve(&rdate).with_timezone(&Utc), &start);
+            if naive >= window.0 && naive <= window.1 && !starts.contains(&naive) {
+                starts.push(naive);
+            }
+        }
+        let excluded = event
+            .properties("EXDATE")
+            .flat_map(EventTime::list_from_property)
+            .map(|exdate| instant_ms(&exdate))
+            .collect::<Vec<_>>();
+
+        for naive in starts {
+            let recurrence_id = start.with_naive(naive);
+            let key_ms = instant_ms(&recurrence_id);
+            if excluded.contains(&key_ms) {
+                continue;
+            }
+            if let Some(edited) = overrides.get(&(uid.clone(), key_ms)) {
+                used_overrides.push((uid.clone(), key_ms));
+                let Some(edited_start) = event_start(edited) else {
+                    continue;
+                };
+                found.push(Occurrence {
+                    component: edited,
+                    uid: uid.clone(),
+                    end: event_end(edited, &edited_start),
+                    start: edited_start,
+                    recurrence_id: Some(recurrence_id),
+                });
+                continue;
+            }
+            found.push(Occurrence {
+                component: event,
+                uid: uid.clone(),
+                end: recurrence_id.with_naive(naive + duration),
+                start: recurrence_id.clone(),
+                recurrence_id: Some(recurrence_id),
+            });
+        }
+    }
+
+    // Edited occurrences moved into the range from outside it.
+    for ((uid, key_ms), edited) in &overrides {
+        if used_overrides.contains(&(uid.clone(), *key_ms)) {
+            continue;
+        }
+        let (Some(start), Some(recurrence_id)) = (
+            event_start(edited),
+            edited
+                .property("RECURRENCE-ID")
+                .and_then(EventTime::from_property),
+        ) else {
+            continue;
+        };
+        found.push(Occurrence {
+            component: edited,
+            uid: uid.clone(),
+            end: event_end(edited, &start),
+            start,
+            recurrence_id: Some(recurrence_id),
+        });
+    }
+
+    let range_ms = (range.0.timestamp_millis(), range.1.timestamp_millis());
+    let mut found = found
+        .into_iter()
+        .filter(|occurrence| {
+            let start = instant_ms(&occurrence.start);
+            let end = instant_ms(&occurrence.end).max(start + 1);
+            start < range_ms.1 && end > range_ms.0
+        })
+        .collect::<Vec<_>>();
+    found.sort_by_key(|occurrence| instant_ms(&occurrence.start));
+    found.truncate(limit);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .expect("timestamp")
+            .with_timezone(&Utc)
+    }
+
+    fn naive(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M").expect("naive")
+    }
+
+    #[test]
+    fn parses_and_writes_folded_escaped_properties() {
+        let text = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:a@example.com\r\nSUMMARY:Plan\\, review\\; ship\\nnext\r\nDESCRIPTION:This line is long enough that it is folded onto a second line by the\r\n  writer\r\nATTENDEE;CN=\"Lovelace, Ada\";PARTSTAT=ACCEPTED:mailto:ada@example.com\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = parse(text).expect("parsed");
+        let event = calendar.children("VEVENT").next().expect("event");
+        assert_eq!(
+            event.text("SUMMARY").as_deref(),
+            Some("Plan, review; ship\nnext")
+        );
+        assert!(event
+            .text("DESCRIPTION")
+            .unwrap()
+            .ends_with("by the writer"));
+        let attendee = event.property("ATTENDEE").expect("attendee");
+        assert_eq!(attendee.param("CN"), Some("Lovelace, Ada"));
+        assert_eq!(attendee.value, "mailto:ada@example.com");
+
+        let written = calendar.to_ics();
+        assert!(written.lines().all(|line| line.len() <= 75));
+        assert_eq!(parse(&written).expect("round trip"), calendar);
+        assert!(parse("BEGIN:VCALENDAR\r\nEND:VEVENT\r\n").is_err());
+    }
+
+    #[test]
+    fn rules_expand_weekly_monthly_and_yearly_series() {
+        let window = (naive("2026-01-01T00:00"), naive("2027-12-31T00:00"));
+        let weekly = RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=MO,WE;COUNT=4").unwrap();
+        assert_eq!(
+            weekly.expand(naive("2026-01-05T10:00"), None, window, 10),
+            vec![
+                naive("2026-01-05T10:00"),
+                naive("2026-01-07T10:00"),
+                naive("2026-01-12T10:00"),
+                naive("2026-01-14T10:00"),
+            ]
+        );
+
+        let last_friday = RecurrenceRule::parse("FREQ=MONTHLY;BYDAY=-1FR").unwrap();
+        assert_eq!(
+            last_friday.expand(
+                naive("2026-01-30T09:00"),
+                Some(naive("2026-03-31T00:00")),
+                window,
+                10
+            ),
+            vec![
+                naive("2026-01-30T09:00"),
+                naive("2026-02-27T09:00"),
+                naive("2026-03-27T09:00"),
+            ]
+        );
+
+        let last_workday = RecurrenceRule::parse(
+            "FREQ=MONTHLY;INTERVAL=2;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1;COUNT=2",
+        )
+        .unwrap();
+        assert_eq!(
+            last_workday.expand(naive("2026-01-30T09:00"), None, window, 10),
+            vec![naive("2026-01-30T09:00"), naive("2026-03-31T09:00")]
+        );
+
+        let leap_day = RecurrenceRule::parse("FREQ=YEARLY").unwrap();
+        assert_eq!(
+            leap_day.expand(
+                naive("2024-02-29T00:00"),
+                None,
+                (naive("2024-01-01T00:00"), naive("2029-01-01T00:00")),
+                10
+            ),
+            vec![naive("2024-02-29T00:00"), naive("2028-02-29T00:00")]
+        );
+        assert!(RecurrenceRule::parse("FREQ=HOURLY").is_err());
+    }
+
+    #[test]
+    fn huge_intervals_end_the_series_instead_of_overflowing() {
+        let start = naive("2026-01-05T10:00");
+        let window = (naive("2026-01-01T00:00"), naive("9999-12-31T00:00"));
+        for freq in ["DAILY", "WEEKLY", "MONTHLY", "YEARLY"] {
+            let rule = RecurrenceRule::parse(&format!("FREQ={freq};INTERVAL=4294967295")).unwrap();
+            assert_eq!(rule.expand(start, None, window, 10), vec![start], "{freq}");
+        }
+        let daily = RecurrenceRule::parse("FREQ=DAILY;INTERVAL=100000000").unwrap();
+        assert_eq!(daily.expand(start, None, window, 10), vec![start]);
+
+        assert_eq!(
+            parse_duration("P1W2DT3H"),
+            Some(Duration::hours(9 * 24 + 3))
+        );
+        assert!(parse_duration("P99999999999999999W").is_none());
+    }
+
+    #[test]
+    fn occurrences_follow_time_zones_exdates_and_overrides() {
+        let text = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:standup\r\n",
+            "SUMMARY:Standup\r\n",
+            "DTSTART;TZID=Europe/Berlin:20260323T090000\r\n",
+            "DTEND;TZID=Europe/Berlin:20260323T091500\r\n",
+            "RRULE:FREQ=DAILY;COUNT=10\r\n",
+            "EXDATE;TZID=Europe/Berlin:20260325T090000\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:standup\r\n",
+            "SUMMARY:Standup (moved)\r\n",
+            "RECURRENCE-ID;TZID=Europe/Berlin:20260326T090000\r\n",
+            "DTSTART;TZID=Europe/Berlin:20260326T110000\r\n",
+            "DTEND;TZID=Europe/Berlin:20260326T111500\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:review\r\n",
+            "DTSTART;VALUE=DATE:20260327\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+        let calendar = parse(text).unwrap();
+        let zones = Zones::from_calendar(&calendar);
+        let found = occurrences(
+            &calendar,
+            &zones,
+            (utc("2026-03-27T00:00:00Z"), utc("2026-03-30T00:00:00Z")),
+            50,
+        );
+        let starts = found
+            .iter()
+            .map(|occurrence| {
+                (
+                    occurrence.uid.as_str(),
+                    zones.resolve(&occurrence.start).to_rfc3339(),
+                )
+            })
+            .collect::<Vec<_>>();
+        // Berlin switches to summer time on 29 March, so the UTC time moves an hour earlier.
+        assert!(starts.contains(&("standup", "2026-03-27T09:00:00+01:00".to_string())));
+        assert!(starts.contains(&("standup", "2026-03-29T09:00:00+02:00".to_string())));
+        assert!(found.iter().any(|occurrence| occurrence.uid == "review"));
+
+        let week = occurrences(
+            &calendar,
+            &zones,
+            (utc("2026-03-23T00:00:00Z"), utc("2026-03-26T11:00:00Z")),
+            50,
+        );
+        let summaries = week
+            .iter()
+            .map(|occurrence| occurrence.component.text("SUMMARY").unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(summaries, vec!["Standup", "Standup", "Standup (moved)"]);
+        assert_eq!(
+            week[2].recurrence_id,
+            Some(EventTime::Zoned(
+                naive("2026-03-26T09:00"),
+                "Europe/Berlin".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn time_values_durations_and_vtimezones() {
+        let custom = parse(concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VTIMEZONE\r\n",
+            "TZID:W. Europe Standard Time\r\n",
+            "BEGIN:STANDARD\r\n",
+            "DTSTART:16010101T030000\r\n",
+            "TZOFFSETFROM:+0200\r\n",
+            "TZOFFSETTO:+0100\r\n",
+            "END:STANDARD\r\n",
+            "END:VTIMEZONE\r\n",
+            "END:VCALENDAR\r\n",
+        ))
+        .unwrap();
+        let zones = Zones::from_calendar(&custom);
+        let time =
+            EventTime::parse_value("20260105T100000", Some("W. Europe Standard Time"), false)
+                .unwrap();
+        assert_eq!(
+            zones.resolve(&time).to_rfc3339(),
+            "2026-01-05T10:00:00+01:00"
+        );
+        assert_eq!(
+            iana_zone("/mozilla.org/20050126_1/America/New_York"),
+            Some(chrono_tz::America::New_York)
+        );
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("-P1W"), Some(Duration::weeks(-1)));
+
+        let zone = vtimezone("Europe/Berlin", 2026).expect("zone");
+        let rules = zone
+            .components
+            .iter()
+            .map(|rule| {
+                (
+                    rule.name.as_str(),
+                    rule.property("RRULE").unwrap().value.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            vec![
+                ("DAYLIGHT", "FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU"),
+                ("STANDARD", "FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU"),
+            ]
+        );
+        assert_eq!(
+            zone.components[0].property("DTSTART").unwrap().value,
+            "20260329T020000"
+        );
+    }
+}
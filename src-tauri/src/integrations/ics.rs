@@ -0,0 +1,90 @@
+//! Read-only ICS calendar subscriptions (webcal feeds, published calendars). The feed URL
+//! is stored as the connection's access token since it usually embeds a secret.
+
+use super::icalendar::{self, Component};
+use super::{Integration, IntegrationCapability, IntegrationHealth, IntegrationMetadata};
+use crate::db::{Db, IntegrationConnection};
+use crate::tools::{register_ics_tools, ToolError, ToolRegistry};
+use reqwest::blocking::Client;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct IcsIntegration;
+
+impl Integration for IcsIntegration {
+    fn manifest(&self) -> IntegrationMetadata {
+        IntegrationMetadata {
+            id: "ics".to_string(),
+            name: "ICS subscription".to_string(),
+            provider: "ics".to_string(),
+            description: "Read events from a published calendar feed (webcal or .ics URL)."
+                .to_string(),
+            auth_type: "url".to_string(),
+            category: "calendar".to_string(),
+            capabilities: vec![
+                IntegrationCapability::ActionExecute,
+                IntegrationCapability::HealthCheck,
+            ],
+            scopes: Vec::new(),
+            settings_schema: super::empty_settings_schema(),
+        }
+    }
+
+    fn health_check(
+        &self,
+        _db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<IntegrationHealth, String> {
+        let result = fetch_calendar(connection);
+        Ok(IntegrationHealth {
+            ok: result.is_ok(),
+            status: None,
+            message: result.err().map(|err| err.message),
+        })
+    }
+
+    fn register_actions(&self, registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+        register_ics_tools(registry, db)
+    }
+}
+
+/// Feed URL of a subscription, with `webcal://` mapped to `https://`.
+pub fn feed_url(connection: &IntegrationConnection) -> Result<String, ToolError> {
+    let url = connection
+        .access_token
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| ToolError::new("ICS subscription has no calendar URL"))?;
+    Ok(match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{rest}"),
+        None => url.to_string(),
+    })
+}
+
+/// Downloads and parses the subscribed calendar.
+pub fn fetch_calendar(connection: &IntegrationConnection) -> Result<Component, ToolError> {
+    let url = feed_url(connection)?;
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|err| ToolError::new(format!("Failed to create HTTP client: {err}")))?;
+    let response = client
+        .get(&url)
+        .header("Accept", "text/calendar")
+        .send()
+        .map_err(|err| ToolError::upstream(format!("Failed to fetch calendar feed: {err}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ToolError::from_http_response(
+            &response,
+            format!("Calendar feed error: HTTP {status}"),
+        ));
+    }
+    let text = response
+        .text()
+        .map_err(|err| ToolError::upstream(format!("Failed to read calendar feed: {err}")))?;
+    icalendar::parse(&text)
+        .map_err(|err| ToolError::upstream(format!("Calendar feed is not valid iCalendar: {err}")))
+}
@@ -1,13 +1,21 @@
-use super::IntegrationMetadata;
+use super::{Integration, IntegrationMetadata};
 
-pub fn integrations() -> Vec<IntegrationMetadata> {
-    vec![IntegrationMetadata {
-        id: "mcp".to_string(),
-        name: "MCP Servers".to_string(),
-        provider: "mcp".to_string(),
-        description: "Configure local or remote MCP servers.".to_string(),
-        auth_type: "api_key".to_string(),
-        category: "mcp".to_string(),
-        capabilities: vec!["discovery".to_string(), "health_check".to_string()],
-    }]
+/// MCP servers have no integration connection; they are configured and health-checked
+/// through the `*_mcp_server` commands, so this plugin only provides the manifest.
+pub struct McpIntegration;
+
+impl Integration for McpIntegration {
+    fn manifest(&self) -> IntegrationMetadata {
+        IntegrationMetadata {
+            id: "mcp".to_string(),
+            name: "MCP Servers".to_string(),
+            provider: "mcp".to_string(),
+            description: "Configure local or remote MCP servers.".to_string(),
+            auth_type: "api_key".to_string(),
+            category: "mcp".to_string(),
+            capabilities: Vec::new(),
+            scopes: Vec::new(),
+            settings_schema: super::empty_settings_schema(),
+        }
+    }
 }
@@ -0,0 +1,213 @@
+//! Local stand-in for integration REST APIs in tests. Routes answer canned JSON, every
+//! request is recorded, and `connect` creates a connection whose API base URLs point here,
+//! so whole tool flows run offline.
+
+use super::settings_preference_key;
+use crate::db::{
+    CreateIntegrationConnectionInput, Db, IntegrationConnection, IntegrationConnectionOperations,
+    PreferenceOperations,
+};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path including the query string.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or(Value::Null)
+    }
+
+    /// Decoded query parameter, or form field for form-encoded bodies.
+    pub fn param(&self, name: &str) -> Option<String> {
+        let query = self.path.split_once('?').map(|(_, query)| query);
+        query
+            .into_iter()
+            .chain(Some(self.body.as_str()))
+            .flat_map(|pairs| url::form_urlencoded::parse(pairs.as_bytes()))
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+}
+
+struct Route {
+    method: String,
+    path: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Value,
+}
+
+#[derive(Default)]
+struct State {
+    routes: Vec<Route>,
+    requests: Vec<RecordedRequest>,
+}
+
+pub struct MockServer {
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("mock server address")
+        );
+        let state = Arc::new(Mutex::new(State::default()));
+        let server_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle(stream, &server_state);
+            }
+        });
+        Self { url, state }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Answers `method path` (query string ignored) with `status` and `body`. Later routes
+    /// for the same request win, so tests can override a default.
+    pub fn route(&self, method: &str, path: &str, status: u16, body: Value) -> &Self {
+        self.route_with_headers(method, path, status, &[], body)
+    }
+
+    /// Like [`MockServer::route`], with extra response headers.
+    pub fn route_with_headers(
+        &self,
+        method: &str,
+        path: &str,
+        status: u16,
+        headers: &[(&str, &str)],
+        body: Value,
+    ) -> &Self {
+        self.state.lock().unwrap().routes.push(Route {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            headers: headers
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            body,
+        });
+        self
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Creates a connected `integration_id` connection whose API base URLs are this server.
+    pub fn connect(&self, db: &Db, integration_id: &str) -> IntegrationConnection {
+        let connection = db
+            .create_integration_connection(&CreateIntegrationConnectionInput {
+                integration_id: integration_id.to_string(),
+                account_label: Some("mock@example.com".to_string()),
+                auth_type: "oauth2".to_string(),
+                access_token: Some("mock-token".to_string()),
+                refresh_token: None,
+                scopes: None,
+                expires_at: None,
+            })
+            .expect("create mock connection");
+        let settings = json!({ "api_base_url": self.url, "sync_api_base_url": self.url });
+        db.set_preference(
+            &settings_preference_key(integration_id, &connection.id),
+            &settings.to_string(),
+        )
+        .expect("save mock settings");
+        connection
+    }
+}
+
+fn handle(mut stream: TcpStream, state: &Mutex<State>) {
+    let Some(request) = read_request(&stream) else {
+        return;
+    };
+    let (status, headers, body) = {
+        let mut state = state.lock().unwrap();
+        let path = request.path.split('?').next().unwrap_or("");
+        let response = state
+            .routes
+            .iter()
+            .rev()
+            .find(|route| route.method == request.method && route.path == path)
+            .map(|route| (route.status, route.headers.clone(), route.body.clone()))
+            .unwrap_or_else(|| {
+                let error = json!({ "error": format!("no mock route for {path}") });
+                (404, Vec::new(), error)
+            });
+        state.requests.push(request);
+        response
+    };
+    let body = if body.is_null() {
+        String::new()
+    } else {
+        body.to_string()
+    };
+    let extra_headers = headers
+        .iter()
+        .map(|(key, value)| format!("{key}: {value}\r\n"))
+        .collect::<String>();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\n{extra_headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+fn read_request(stream: &TcpStream) -> Option<RecordedRequest> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
@@ -1,10 +1,67 @@
+//! Integration plugins. Each plugin describes itself with a manifest and implements the
+//! [`Integration`] trait for the capabilities it declares; [`IntegrationRegistry`] routes
+//! requests to the right plugin.
+
+use crate::db::{Db, IntegrationConnection, PreferenceOperations};
+use crate::tools::ToolRegistry;
+use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use specta::Type;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
+pub mod caldav;
+pub mod email;
+pub mod endpoints;
+pub mod github;
 pub mod google;
+pub mod icalendar;
+pub mod ics;
 pub mod mcp;
+#[cfg(test)]
+pub(crate) mod mock_server;
+pub mod sync;
 pub mod todoist;
 
+use sync::SyncProgress;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrationCapability {
+    /// Mirror remote data into the local cache on a schedule.
+    Sync,
+    /// Receive and process external events.
+    WebhookIngest,
+    /// Perform user-initiated actions through tools.
+    ActionExecute,
+    /// List resources the user can pick in settings (labels, calendars, projects).
+    Discovery,
+    /// Validate connectivity and auth state.
+    HealthCheck,
+}
+
+impl IntegrationCapability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntegrationCapability::Sync => "sync",
+            IntegrationCapability::WebhookIngest => "webhook_ingest",
+            IntegrationCapability::ActionExecute => "action_execute",
+            IntegrationCapability::Discovery => "discovery",
+            IntegrationCapability::HealthCheck => "health_check",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct IntegrationScope {
+    /// Human-readable description shown before connecting.
+    pub label: String,
+    /// Provider scope string requested during OAuth.
+    pub scope: String,
+}
+
+/// Plugin manifest, used by the UI and for routing.
 #[derive(Debug, Serialize, Deserialize, Clone, Type)]
 pub struct IntegrationMetadata {
     pub id: String,
@@ -13,20 +70,256 @@ pub struct IntegrationMetadata {
     pub description: String,
     pub auth_type: String,
     pub category: String,
-    pub capabilities: Vec<String>,
+    pub capabilities: Vec<IntegrationCapability>,
+    pub scopes: Vec<IntegrationScope>,
+    /// JSON Schema of the per-connection settings object.
+    pub settings_schema: Value,
+}
+
+impl IntegrationMetadata {
+    pub fn supports(&self, capability: IntegrationCapability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct IntegrationHealth {
+    pub ok: bool,
+    /// HTTP status of the probe request, when one was made.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Type)]
+pub struct DiscoveredResource {
+    /// Resource type, e.g. `label`, `calendar` or `project`.
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+    /// Provider-specific details such as `primary` for calendars.
+    pub metadata: Value,
+}
+
+/// An integration plugin. Default implementations report the capability as unsupported, so
+/// plugins only implement what their manifest declares.
+pub trait Integration: Send + Sync {
+    fn manifest(&self) -> IntegrationMetadata;
+
+    /// Whether the plugin can be used in this build, e.g. OAuth client credentials are set.
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    /// Returns a usable access token, refreshing it when the auth flow supports that.
+    fn access_token(&self, _db: &Db, connection: &IntegrationConnection) -> Result<String, String> {
+        crate::tools::get_access_token(connection).map_err(|err| err.message)
+    }
+
+    fn health_check(
+        &self,
+        _db: &Db,
+        _connection: &IntegrationConnection,
+    ) -> Result<IntegrationHealth, String> {
+        Err(unsupported(
+            &self.manifest().id,
+            IntegrationCapability::HealthCheck,
+        ))
+    }
+
+    fn discovery(
+        &self,
+        _db: &Db,
+        _connection: &IntegrationConnection,
+    ) -> Result<Vec<DiscoveredResource>, String> {
+        Err(unsupported(
+            &self.manifest().id,
+            IntegrationCapability::Discovery,
+        ))
+    }
+
+    /// Resources mirrored by [`Integration::sync`], used as sync state keys.
+    fn sync_resources(&self, _db: &Db, _connection: &IntegrationConnection) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Syncs one resource, resuming from `cursor` when the provider supports it.
+    fn sync(
+        &self,
+        _db: &Db,
+        _connection: &IntegrationConnection,
+        _token: &str,
+        _resource: &str,
+        _cursor: Option<&str>,
+    ) -> Result<SyncProgress, String> {
+        Err(unsupported(
+            &self.manifest().id,
+            IntegrationCapability::Sync,
+        ))
+    }
+
+    /// Registers the tools that carry out this plugin's actions.
+    fn register_actions(&self, _registry: &mut ToolRegistry, _db: Db) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Label for a newly authorized account, such as its email address.
+    fn fetch_account_label(&self, _access_token: &str) -> Option<String> {
+        None
+    }
+}
+
+fn unsupported(integration_id: &str, capability: IntegrationCapability) -> String {
+    format!(
+        "Integration '{integration_id}' does not support {}",
+        capability.as_str()
+    )
+}
+
+pub struct IntegrationRegistry {
+    plugins: Vec<Arc<dyn Integration>>,
+}
+
+impl IntegrationRegistry {
+    pub fn new(plugins: Vec<Arc<dyn Integration>>) -> Self {
+        Self { plugins }
+    }
+
+    /// Manifests of the plugins usable in this build.
+    pub fn manifests(&self) -> Vec<IntegrationMetadata> {
+        self.available().map(|plugin| plugin.manifest()).collect()
+    }
+
+    pub fn get(&self, integration_id: &str) -> Option<Arc<dyn Integration>> {
+        self.available()
+            .find(|plugin| plugin.manifest().id == integration_id)
+            .cloned()
+    }
+
+    /// Looks up the plugin and checks that its manifest declares `capability`.
+    pub fn require(
+        &self,
+        integration_id: &str,
+        capability: IntegrationCapability,
+    ) -> Result<Arc<dyn Integration>, String> {
+        let plugin = self
+            .get(integration_id)
+            .ok_or_else(|| format!("Unknown or unavailable integration '{integration_id}'"))?;
+        if !plugin.manifest().supports(capability) {
+            return Err(unsupported(integration_id, capability));
+        }
+        Ok(plugin)
+    }
+
+    /// Ids of the available plugins that declare `capability`.
+    pub fn ids_with(&self, capability: IntegrationCapability) -> Vec<String> {
+        self.available()
+            .map(|plugin| plugin.manifest())
+            .filter(|manifest| manifest.supports(capability))
+            .map(|manifest| manifest.id)
+            .collect()
+    }
+
+    pub fn register_tools(&self, registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+        for plugin in self.available() {
+            if plugin
+                .manifest()
+                .supports(IntegrationCapability::ActionExecute)
+            {
+                plugin.register_actions(registry, db.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn available(&self) -> impl Iterator<Item = &Arc<dyn Integration>> {
+        self.plugins.iter().filter(|plugin| plugin.is_available())
+    }
+}
+
+static REGISTRY: OnceLock<IntegrationRegistry> = OnceLock::new();
+
+/// The built-in plugins.
+pub fn integration_registry() -> &'static IntegrationRegistry {
+    REGISTRY.get_or_init(|| {
+        IntegrationRegistry::new(vec![
+            Arc::new(google::GmailIntegration),
+            Arc::new(google::GoogleCalendarIntegration),
+            Arc::new(todoist::TodoistIntegration),
+            Arc::new(email::EmailIntegration),
+            Arc::new(caldav::CalDavIntegration),
+            Arc::new(ics::IcsIntegration),
+            Arc::new(github::GitHubIntegration),
+            Arc::new(mcp::McpIntegration),
+        ])
+    })
 }
 
 pub fn default_integrations() -> Vec<IntegrationMetadata> {
-    let mut integrations = Vec::new();
-    integrations.extend(google::integrations());
-    integrations.extend(todoist::integrations());
-    integrations.extend(mcp::integrations());
-    integrations
+    integration_registry().manifests()
+}
+
+/// Preference holding a connection's settings object.
+pub fn settings_preference_key(integration_id: &str, connection_id: &str) -> String {
+    format!("integration_settings.{integration_id}.{connection_id}")
+}
+
+pub fn load_integration_settings(db: &Db, integration_id: &str, connection_id: &str) -> Value {
+    PreferenceOperations::get_preference(
+        db,
+        &settings_preference_key(integration_id, connection_id),
+    )
+    .ok()
+    .flatten()
+    .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+    .filter(|value| value.is_object())
+    .unwrap_or_else(|| json!({}))
+}
+
+/// Settings schema for plugins without settings.
+pub(crate) fn empty_settings_schema() -> Value {
+    json!({ "type": "object", "properties": {}, "additionalProperties": false })
+}
+
+/// Probes `url` with the connection's token; any 2xx counts as healthy.
+pub(crate) fn http_health_check(url: &str, token: &str) -> Result<IntegrationHealth, String> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_else(|_| Client::new());
+    let response = client
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .map_err(|e| format!("Test request failed: {e}"))?;
+    let status = response.status();
+    Ok(IntegrationHealth {
+        ok: status.is_success(),
+        status: Some(status.as_u16()),
+        message: (!status.is_success()).then(|| format!("HTTP status {}", status.as_u16())),
+    })
+}
+
+/// GETs `url` and parses the JSON body, for discovery requests.
+pub(crate) fn get_json(url: &str, token: &str, service: &str) -> Result<Value, String> {
+    let response = Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .map_err(|e| format!("{service} request failed: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{service} request failed: HTTP {status}"));
+    }
+    response
+        .json::<Value>()
+        .map_err(|e| format!("Failed to parse {service} response: {e}"))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::default_integrations;
+    use super::*;
 
     #[test]
     fn default_integrations_include_core_providers() {
@@ -35,9 +328,55 @@ mod tests {
             .iter()
             .map(|item| item.id.as_str())
             .collect::<Vec<_>>();
-        assert!(ids.contains(&"gmail"));
-        assert!(ids.contains(&"google_calendar"));
         assert!(ids.contains(&"todoist"));
         assert!(ids.contains(&"mcp"));
+        if crate::oauth::google_oauth_env_configured() {
+            assert!(ids.contains(&"gmail"));
+            assert!(ids.contains(&"google_calendar"));
+        }
+    }
+
+    #[test]
+    fn registry_routes_only_declared_capabilities() {
+        let registry = integration_registry();
+        assert!(registry
+            .require("todoist", IntegrationCapability::Sync)
+            .is_ok());
+        let err = registry
+            .require("mcp", IntegrationCapability::Sync)
+            .err()
+            .expect("mcp does not sync");
+        assert!(err.contains("does not support sync"));
+        assert!(registry
+            .require("unknown", IntegrationCapability::HealthCheck)
+            .is_err());
+        assert!(registry
+            .ids_with(IntegrationCapability::Sync)
+            .contains(&"todoist".to_string()));
+    }
+
+    #[test]
+    fn manifests_have_unique_ids_and_object_settings() {
+        let plugins: Vec<IntegrationMetadata> = vec![
+            google::GmailIntegration.manifest(),
+            google::GoogleCalendarIntegration.manifest(),
+            todoist::TodoistIntegration.manifest(),
+            email::EmailIntegration.manifest(),
+            caldav::CalDavIntegration.manifest(),
+            ics::IcsIntegration.manifest(),
+            github::GitHubIntegration.manifest(),
+            mcp::McpIntegration.manifest(),
+        ];
+        let mut ids = plugins
+            .iter()
+            .map(|item| item.id.clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), plugins.len());
+        for manifest in plugins {
+            assert_eq!(manifest.settings_schema["type"], "object");
+            assert!(jsonschema::JSONSchema::compile(&manifest.settings_schema).is_ok());
+        }
     }
 }
@@ -0,0 +1,171 @@
+use super::{
+    fetch_json, http_client, sync_with_fallback, SyncError, SyncProgress, CALENDAR_SYNC_WINDOW_DAYS,
+};
+use crate::db::{CachedCalendarEvent, Db, IntegrationCacheOperations};
+use crate::integrations::endpoints::GOOGLE_CALENDAR_API;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+const PAGE_SIZE: u32 = 2_500;
+const MAX_PAGES: usize = 20;
+
+/// Mirrors one calendar's events, with recurring events expanded into instances.
+pub fn sync(
+    db: &Db,
+    connection_id: &str,
+    token: &str,
+    calendar_id: &str,
+    cursor: Option<&str>,
+) -> Result<SyncProgress, String> {
+    let client = http_client();
+    let label = format!("Google Calendar {calendar_id}");
+    let api = GOOGLE_CALENDAR_API.base_url(db, connection_id);
+    sync_with_fallback(
+        &label,
+        cursor,
+        |sync_token| {
+            let (items, next_token) =
+                fetch_events(&client, &api, token, calendar_id, Some(sync_token))?;
+            apply_events(db, connection_id, calendar_id, items, next_token, false)
+        },
+        || {
+            let (items, next_token) = fetch_events(&client, &api, token, calendar_id, None)?;
+            apply_events(db, connection_id, calendar_id, items, next_token, true)
+        },
+    )
+}
+
+fn fetch_events(
+    client: &Client,
+    api: &str,
+    token: &str,
+    calendar_id: &str,
+    sync_token: Option<&str>,
+) -> Result<(Vec<Value>, String), SyncError> {
+    let encoded_id: String = url::form_urlencoded::byte_serialize(calendar_id.as_bytes()).collect();
+    let url = format!("{api}/calendars/{encoded_id}/events");
+    let time_min = (Utc::now() - Duration::days(CALENDAR_SYNC_WINDOW_DAYS)).to_rfc3339();
+
+    let mut items = Vec::new();
+    let mut page_token: Option<String> = None;
+    for _ in 0..MAX_PAGES {
+        let mut request = client
+            .get(&url)
+            .query(&[("singleEvents", "true")])
+            .query(&[("maxResults", PAGE_SIZE.to_string())]);
+        request = match sync_token {
+            Some(sync_token) => request.query(&[("syncToken", sync_token)]),
+            None => request.query(&[("timeMin", time_min.as_str())]),
+        };
+        if let Some(page_token) = page_token.as_deref() {
+            request = request.query(&[("pageToken", page_token)]);
+        }
+        // 410 Gone means the sync token was invalidated and a full sync is required.
+        let page = fetch_json(
+            request.bearer_auth(token),
+            "Google Calendar",
+            &[StatusCode::GONE],
+        )?;
+        if let Some(page_items) = page.get("items").and_then(|value| value.as_array()) {
+            items.extend(page_items.iter().cloned());
+        }
+        if let Some(next_sync_token) = page.get("nextSyncToken").and_then(|value| value.as_str()) {
+            return Ok((items, next_sync_token.to_string()));
+        }
+        page_token = page
+            .get("nextPageToken")
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+        if page_token.is_none() {
+            break;
+        }
+    }
+    Err(SyncError::Failed(format!(
+        "Google Calendar did not return a sync token for {calendar_id}"
+    )))
+}
+
+fn apply_events(
+    db: &Db,
+    connection_id: &str,
+    calendar_id: &str,
+    items: Vec<Value>,
+    next_sync_token: String,
+    replace_all: bool,
+) -> Result<SyncProgress, SyncError> {
+    let mut upserts = Vec::new();
+    let mut deleted_ids = Vec::new();
+    for item in items {
+        let Some(id) = item.get("id").and_then(|value| value.as_str()) else {
+            continue;
+        };
+        if item.get("status").and_then(|value| value.as_str()) == Some("cancelled") {
+            deleted_ids.push(id.to_string());
+            continue;
+        }
+        upserts.push(CachedCalendarEvent {
+            connection_id: connection_id.to_string(),
+            calendar_id: calendar_id.to_string(),
+            id: id.to_string(),
+            summary: item
+                .get("summary")
+                .and_then(|value| value.as_str())
+                .unwrap_or("")
+                .to_string(),
+            start_at: item.get("start").and_then(event_time_ms),
+            end_at: item.get("end").and_then(event_time_ms),
+            raw: item,
+        });
+    }
+    db.apply_cached_calendar_changes(
+        connection_id,
+        calendar_id,
+        &upserts,
+        &deleted_ids,
+        replace_all,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(SyncProgress {
+        cursor: next_sync_token,
+        changed: upserts.len() + deleted_ids.len(),
+    })
+}
+
+/// Converts an event `start`/`end` object to epoch ms. All-day dates use local midnight.
+fn event_time_ms(value: &Value) -> Option<i64> {
+    if let Some(date_time) = value.get("dateTime").and_then(|value| value.as_str()) {
+        return DateTime::parse_from_rfc3339(date_time)
+            .ok()
+            .map(|parsed| parsed.timestamp_millis());
+    }
+    let date = value.get("date").and_then(|value| value.as_str())?;
+    let midnight = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(0, 0, 0)?;
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|parsed| parsed.timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn event_times_accept_timestamps_and_dates() {
+        assert_eq!(
+            event_time_ms(&json!({ "dateTime": "2024-05-01T10:00:00Z" })),
+            Some(1_714_557_600_000)
+        );
+        let expected = Local
+            .with_ymd_and_hms(2024, 5, 1, 0, 0, 0)
+            .earliest()
+            .map(|parsed| parsed.timestamp_millis());
+        assert_eq!(event_time_ms(&json!({ "date": "2024-05-01" })), expected);
+        assert_eq!(event_time_ms(&json!({})), None);
+    }
+}
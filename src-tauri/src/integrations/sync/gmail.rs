@@ -0,0 +1,300 @@
+use super::{fetch_json, http_client, sync_with_fallback, SyncError, SyncProgress};
+use crate::db::{CachedGmailMessage, Db, IntegrationCacheOperations};
+use crate::integrations::endpoints::GMAIL_API;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// How far back the first sync reaches.
+const INITIAL_SYNC_DAYS: u32 = 180;
+const INITIAL_SYNC_MAX_MESSAGES: usize = 2_000;
+const PAGE_SIZE: u32 = 500;
+const HISTORY_TYPES: [&str; 4] = [
+    "messageAdded",
+    "messageDeleted",
+    "labelAdded",
+    "labelRemoved",
+];
+
+/// Mirrors message metadata (headers, snippet, labels). Bodies stay on the server and are
+/// fetched by `gmail.get_thread` when needed.
+pub fn sync(
+    db: &Db,
+    connection_id: &str,
+    token: &str,
+    cursor: Option<&str>,
+) -> Result<SyncProgress, String> {
+    let client = http_client();
+    let api = format!("{}/users/me", GMAIL_API.base_url(db, connection_id));
+    sync_with_fallback(
+        "Gmail",
+        cursor,
+        |history_id| incremental_sync(db, &client, &api, connection_id, token, history_id),
+        || full_sync(db, &client, &api, connection_id, token),
+    )
+}
+
+fn full_sync(
+    db: &Db,
+    client: &Client,
+    api: &str,
+    connection_id: &str,
+    token: &str,
+) -> Result<SyncProgress, SyncError> {
+    // Read the history id before listing so changes made during the listing are replayed
+    // by the next incremental sync instead of being lost.
+    let profile = fetch_json(
+        client.get(format!("{api}/profile")).bearer_auth(token),
+        "Gmail",
+        &[],
+    )?;
+    let history_id = history_id_of(&profile)
+        .ok_or_else(|| "Gmail profile did not include a historyId".to_string())?;
+
+    let query = format!("newer_than:{INITIAL_SYNC_DAYS}d");
+    let mut ids = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut request = client
+            .get(format!("{api}/messages"))
+            .query(&[("q", query.as_str())])
+            .query(&[("maxResults", PAGE_SIZE.to_string())]);
+        if let Some(page_token) = page_token.as_deref() {
+            request = request.query(&[("pageToken", page_token)]);
+        }
+        let page = fetch_json(request.bearer_auth(token), "Gmail", &[])?;
+        ids.extend(
+            page.get("messages")
+                .and_then(|value| value.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|message| message.get("id").and_then(|value| value.as_str()))
+                .map(str::to_string),
+        );
+        page_token = page
+            .get("nextPageToken")
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+        if page_token.is_none() || ids.len() >= INITIAL_SYNC_MAX_MESSAGES {
+            break;
+        }
+    }
+    ids.truncate(INITIAL_SYNC_MAX_MESSAGES);
+
+    let mut messages = Vec::with_capacity(ids.len());
+    for id in &ids {
+        if let Some(message) = fetch_message(client, api, connection_id, token, id)? {
+            messages.push(message);
+        }
+    }
+    db.apply_cached_gmail_changes(connection_id, &messages, &[], true)
+        .map_err(|e| e.to_string())?;
+    Ok(SyncProgress {
+        cursor: history_id,
+        changed: messages.len(),
+    })
+}
+
+fn incremental_sync(
+    db: &Db,
+    client: &Client,
+    api: &str,
+    connection_id: &str,
+    token: &str,
+    start_history_id: &str,
+) -> Result<SyncProgress, SyncError> {
+    let mut changed_ids = HashSet::new();
+    let mut deleted_ids = HashSet::new();
+    let mut latest_history_id = start_history_id.to_string();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut request = client
+            .get(format!("{api}/history"))
+            .query(&[("startHistoryId", start_history_id)])
+            .query(&[("maxResults", PAGE_SIZE.to_string())]);
+        for history_type in HISTORY_TYPES {
+            request = request.query(&[("historyTypes", history_type)]);
+        }
+        if let Some(page_token) = page_token.as_deref() {
+            request = request.query(&[("pageToken", page_token)]);
+        }
+        // Gmail answers 404 when the start id is older than the history it keeps.
+        let page = fetch_json(
+            request.bearer_auth(token),
+            "Gmail",
+            &[StatusCode::NOT_FOUND],
+        )?;
+
+        for record in page
+            .get("history")
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+        {
+            for key in ["messagesAdded", "labelsAdded", "labelsRemoved"] {
+                changed_ids.extend(history_message_ids(record, key));
+            }
+            deleted_ids.extend(history_message_ids(record, "messagesDeleted"));
+        }
+        if let Some(history_id) = history_id_of(&page) {
+            latest_history_id = history_id;
+        }
+        page_token = page
+            .get("nextPageToken")
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    let mut upserts = Vec::new();
+    for id in changed_ids.difference(&deleted_ids) {
+        match fetch_message(client, api, connection_id, token, id)? {
+            Some(message) => upserts.push(message),
+            None => {
+                deleted_ids.insert(id.clone());
+            }
+        }
+    }
+    let deleted_ids: Vec<String> = deleted_ids.into_iter().collect();
+    db.apply_cached_gmail_changes(connection_id, &upserts, &deleted_ids, false)
+        .map_err(|e| e.to_string())?;
+    Ok(SyncProgress {
+        cursor: latest_history_id,
+        changed: upserts.len() + deleted_ids.len(),
+    })
+}
+
+/// Fetches one message's metadata. Returns `None` when it was deleted in the meantime.
+fn fetch_message(
+    client: &Client,
+    api: &str,
+    connection_id: &str,
+    token: &str,
+    id: &str,
+) -> Result<Option<CachedGmailMessage>, SyncError> {
+    let request = client
+        .get(format!("{api}/messages/{id}"))
+        .query(&[
+            ("format", "metadata"),
+            ("metadataHeaders", "From"),
+            ("metadataHeaders", "To"),
+            ("metadataHeaders", "Cc"),
+            ("metadataHeaders", "Subject"),
+        ])
+        .bearer_auth(token);
+    let raw = match fetch_json(request, "Gmail", &[StatusCode::NOT_FOUND]) {
+        Ok(raw) => raw,
+        Err(SyncError::CursorExpired) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    Ok(Some(parse_message(connection_id, &raw)))
+}
+
+fn parse_message(connection_id: &str, raw: &Value) -> CachedGmailMessage {
+    let header = |name: &str| {
+        raw.pointer("/payload/headers")
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|header| {
+                header
+                    .get("name")
+                    .and_then(|value| value.as_str())
+                    .is_some_and(|value| value.eq_ignore_ascii_case(name))
+            })
+            .filter_map(|header| header.get("value").and_then(|value| value.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let to = [header("To"), header("Cc")]
+        .into_iter()
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let str_field = |key: &str| {
+        raw.get(key)
+            .and_then(|value| value.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    CachedGmailMessage {
+        connection_id: connection_id.to_string(),
+        id: str_field("id"),
+        thread_id: str_field("threadId"),
+        internal_date: str_field("internalDate").parse().unwrap_or(0),
+        from: header("From"),
+        to,
+        subject: header("Subject"),
+        snippet: str_field("snippet"),
+        label_ids: raw
+            .get("labelIds")
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.as_str())
+            .map(str::to_string)
+            .collect(),
+    }
+}
+
+fn history_id_of(value: &Value) -> Option<String> {
+    match value.get("historyId")? {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn history_message_ids<'a>(record: &'a Value, key: &str) -> impl Iterator<Item = String> + 'a {
+    record
+        .get(key)
+        .and_then(|value| value.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            entry
+                .pointer("/message/id")
+                .and_then(|value| value.as_str())
+        })
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn message_metadata_is_flattened_for_the_cache() {
+        let raw = json!({
+            "id": "m1",
+            "threadId": "t1",
+            "internalDate": "1700000000000",
+            "snippet": "Lunch on Friday?",
+            "labelIds": ["INBOX", "UNREAD"],
+            "payload": {
+                "headers": [
+                    { "name": "From", "value": "Ada <ada@example.com>" },
+                    { "name": "To", "value": "me@example.com" },
+                    { "name": "Cc", "value": "bob@example.com" },
+                    { "name": "Subject", "value": "Lunch" }
+                ]
+            }
+        });
+        let message = parse_message("conn", &raw);
+        assert_eq!(message.thread_id, "t1");
+        assert_eq!(message.internal_date, 1_700_000_000_000);
+        assert_eq!(message.from, "Ada <ada@example.com>");
+        assert_eq!(message.to, "me@example.com, bob@example.com");
+        assert_eq!(message.label_ids, vec!["INBOX", "UNREAD"]);
+
+        let record = json!({ "messagesDeleted": [{ "message": { "id": "m2" } }] });
+        assert_eq!(
+            history_message_ids(&record, "messagesDeleted").collect::<Vec<_>>(),
+            vec!["m2"]
+        );
+    }
+}
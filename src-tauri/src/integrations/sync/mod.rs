@@ -0,0 +1,325 @@
+//! Background sync that mirrors Gmail, Google Calendar and Todoist into local tables so
+//! tools can answer from SQLite instead of calling the live API every time.
+
+pub(crate) mod calendar;
+pub(crate) mod gmail;
+pub(crate) mod todoist;
+
+use super::{integration_registry, IntegrationCapability};
+use crate::db::{
+    Db, IntegrationCacheOperations, IntegrationConnection, IntegrationConnectionOperations,
+    PreferenceOperations, UpdateIntegrationConnectionInput,
+};
+use crate::events::{AgentEvent, EventBus, EVENT_INTEGRATION_SYNC_COMPLETED};
+use chrono::Utc;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Minutes between automatic syncs of a connection. `0` turns automatic sync off.
+pub const PREF_SYNC_INTERVAL_MINUTES: &str = "integrations.sync_interval_minutes";
+const DEFAULT_SYNC_INTERVAL_MINUTES: i64 = 10;
+const MIN_SYNC_INTERVAL_MINUTES: i64 = 5;
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+/// How far back calendar sync reaches. Reads for earlier ranges go to the live API.
+pub const CALENDAR_SYNC_WINDOW_DAYS: i64 = 365;
+/// Cached data older than this is not served to tools unless they ask for the cache.
+pub const CACHE_MAX_AGE_MS: i64 = 30 * 60 * 1000;
+
+/// Outcome of syncing one resource.
+pub struct SyncProgress {
+    /// Cursor to resume from on the next run.
+    pub cursor: String,
+    /// Rows written or removed.
+    pub changed: usize,
+}
+
+pub(crate) enum SyncError {
+    /// The provider no longer accepts the stored cursor; a full resync is needed.
+    CursorExpired,
+    Failed(String),
+}
+
+impl From<String> for SyncError {
+    fn from(message: String) -> Self {
+        SyncError::Failed(message)
+    }
+}
+
+pub(crate) fn http_client() -> Client {
+    Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+/// Sends the request and parses JSON. `expired` lists the statuses the provider uses to
+/// reject an outdated cursor.
+pub(crate) fn fetch_json(
+    request: RequestBuilder,
+    service: &str,
+    expired: &[StatusCode],
+) -> Result<Value, SyncError> {
+    let response = request
+        .send()
+        .map_err(|err| SyncError::Failed(format!("Failed to call {service} API: {err}")))?;
+    let status = response.status();
+    if expired.contains(&status) {
+        return Err(SyncError::CursorExpired);
+    }
+    if !status.is_success() {
+        return Err(SyncError::Failed(format!(
+            "{service} API error: HTTP {status}"
+        )));
+    }
+    response
+        .json::<Value>()
+        .map_err(|err| SyncError::Failed(format!("Failed to parse {service} response: {err}")))
+}
+
+/// Sync time of the cached copy when it is recent enough to answer reads.
+pub fn fresh_cache_synced_at(db: &Db, connection_id: &str, resource: &str) -> Option<i64> {
+    let state = db
+        .get_integration_sync_state(connection_id, resource)
+        .ok()
+        .flatten()?;
+    let synced_at = state.last_synced_at?;
+    let fresh =
+        state.status == "ok" && synced_at >= Utc::now().timestamp_millis() - CACHE_MAX_AGE_MS;
+    fresh.then_some(synced_at)
+}
+
+/// Keeps the local cache of each connected account up to date.
+#[derive(Clone)]
+pub struct IntegrationSyncEngine {
+    db: Db,
+    event_bus: EventBus,
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+impl IntegrationSyncEngine {
+    pub fn new(db: Db, event_bus: EventBus) -> Self {
+        Self {
+            db,
+            event_bus,
+            running: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn start(&self) {
+        let engine = self.clone();
+        std::thread::spawn(move || loop {
+            engine.tick();
+            std::thread::sleep(TICK_INTERVAL);
+        });
+    }
+
+    fn sync_interval_ms(&self) -> Option<i64> {
+        let minutes = PreferenceOperations::get_preference(&self.db, PREF_SYNC_INTERVAL_MINUTES)
+            .ok()
+            .flatten()
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .unwrap_or(DEFAULT_SYNC_INTERVAL_MINUTES);
+        if minutes <= 0 {
+            return None;
+        }
+        Some(minutes.max(MIN_SYNC_INTERVAL_MINUTES) * 60 * 1000)
+    }
+
+    fn tick(&self) {
+        let Some(interval_ms) = self.sync_interval_ms() else {
+            return;
+        };
+        let connections = match self.db.get_integration_connections() {
+            Ok(connections) => connections,
+            Err(err) => {
+                log::error!("[sync] failed to load connections: {}", err);
+                return;
+            }
+        };
+        let now = Utc::now().timestamp_millis();
+        let synced_integrations = integration_registry().ids_with(IntegrationCapability::Sync);
+        for connection in connections {
+            if connection.status != "connected"
+                || !synced_integrations.contains(&connection.integration_id)
+            {
+                continue;
+            }
+            let stale = self
+                .db
+                .get_integration_sync_states(Some(&connection.id))
+                .map(|states| states.iter().any(|state| state.status == "stale"))
+                .unwrap_or(false);
+            let due = connection
+                .last_sync_at
+                .map(|last| now - last >= interval_ms)
+                .unwrap_or(true);
+            if due || stale {
+                self.sync_connection(&connection);
+            }
+        }
+    }
+
+    /// Starts a sync in the background. Returns false when one is already running.
+    pub fn sync_now(&self, connection_id: &str) -> Result<bool, String> {
+        let connection = self
+            .db
+            .get_integration_connection_by_id(connection_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Integration connection not found".to_string())?;
+        integration_registry().require(&connection.integration_id, IntegrationCapability::Sync)?;
+        if self.running.lock().unwrap().contains(&connection.id) {
+            return Ok(false);
+        }
+        let engine = self.clone();
+        std::thread::spawn(move || engine.sync_connection(&connection));
+        Ok(true)
+    }
+
+    fn sync_connection(&self, connection: &IntegrationConnection) {
+        if !self.running.lock().unwrap().insert(connection.id.clone()) {
+            return;
+        }
+
+        let results = self.sync_resources(connection);
+        let changed: usize = results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok())
+            .sum();
+        let errors: Vec<String> = results
+            .iter()
+            .filter_map(|(resource, result)| {
+                result
+                    .as_ref()
+                    .err()
+                    .map(|err| format!("{resource}: {err}"))
+            })
+            .collect();
+        let now = Utc::now().timestamp_millis();
+
+        let update = UpdateIntegrationConnectionInput {
+            id: connection.id.clone(),
+            account_label: None,
+            status: None,
+            auth_type: None,
+            access_token: None,
+            refresh_token: None,
+            scopes: None,
+            expires_at: None,
+            last_error: Some(errors.join("; ")),
+            last_sync_at: Some(now),
+        };
+        if let Err(err) = self.db.update_integration_connection(&update) {
+            log::error!(
+                "[sync] failed to update connection {}: {}",
+                connection.id,
+                err
+            );
+        }
+        if errors.is_empty() {
+            log::info!(
+                "[sync] synced {} ({}) changed={}",
+                connection.id,
+                connection.integration_id,
+                changed
+            );
+        } else {
+            log::warn!(
+                "[sync] sync of {} ({}) had errors: {}",
+                connection.id,
+                connection.integration_id,
+                errors.join("; ")
+            );
+        }
+
+        self.event_bus.publish(AgentEvent::new_with_timestamp(
+            EVENT_INTEGRATION_SYNC_COMPLETED,
+            json!({
+                "connection_id": connection.id,
+                "integration_id": connection.integration_id,
+                "status": if errors.is_empty() { "ok" } else { "error" },
+                "changed": changed,
+                "errors": errors,
+                "timestamp_ms": now,
+            }),
+            now,
+        ));
+        self.running.lock().unwrap().remove(&connection.id);
+    }
+
+    /// Syncs every resource of the connection and records each result. Returns
+    /// `(resource, changed rows or error)` pairs.
+    fn sync_resources(
+        &self,
+        connection: &IntegrationConnection,
+    ) -> Vec<(String, Result<usize, String>)> {
+        let plugin = match integration_registry()
+            .require(&connection.integration_id, IntegrationCapability::Sync)
+        {
+            Ok(plugin) => plugin,
+            Err(err) => return vec![("sync".to_string(), Err(err))],
+        };
+        let token = match plugin.access_token(&self.db, connection) {
+            Ok(token) => token,
+            Err(err) => return vec![("auth".to_string(), Err(err))],
+        };
+
+        plugin
+            .sync_resources(&self.db, connection)
+            .into_iter()
+            .map(|resource| {
+                let cursor = self
+                    .db
+                    .get_integration_sync_state(&connection.id, &resource)
+                    .ok()
+                    .flatten()
+                    .and_then(|state| state.cursor);
+                let result =
+                    plugin.sync(&self.db, connection, &token, &resource, cursor.as_deref());
+                let saved = match &result {
+                    Ok(progress) => self.db.save_integration_sync_result(
+                        &connection.id,
+                        &resource,
+                        Ok(&progress.cursor),
+                    ),
+                    Err(err) => {
+                        self.db
+                            .save_integration_sync_result(&connection.id, &resource, Err(err))
+                    }
+                };
+                if let Err(err) = saved {
+                    log::error!("[sync] failed to save state for {}: {}", resource, err);
+                }
+                (resource, result.map(|progress| progress.changed))
+            })
+            .collect()
+    }
+}
+
+/// Runs an incremental sync when a cursor is stored and falls back to a full sync when
+/// there is none or the provider rejected it.
+pub(crate) fn sync_with_fallback(
+    label: &str,
+    cursor: Option<&str>,
+    incremental: impl FnOnce(&str) -> Result<SyncProgress, SyncError>,
+    full: impl FnOnce() -> Result<SyncProgress, SyncError>,
+) -> Result<SyncProgress, String> {
+    if let Some(cursor) = cursor.filter(|cursor| !cursor.is_empty()) {
+        match incremental(cursor) {
+            Err(SyncError::CursorExpired) => {
+                log::info!("[sync] {} cursor expired, running a full sync", label);
+            }
+            Err(SyncError::Failed(err)) => return Err(err),
+            Ok(progress) => return Ok(progress),
+        }
+    }
+    match full() {
+        Ok(progress) => Ok(progress),
+        Err(SyncError::CursorExpired) => Err(format!("{label} rejected the full sync request")),
+        Err(SyncError::Failed(err)) => Err(err),
+    }
+}
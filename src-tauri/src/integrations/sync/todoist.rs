@@ -0,0 +1,174 @@
+use super::{fetch_json, http_client, sync_with_fallback, SyncError, SyncProgress};
+use crate::db::{CachedTodoistTask, Db, IntegrationCacheOperations};
+use crate::integrations::endpoints::TODOIST_SYNC_API;
+use serde_json::{json, Value};
+
+/// Mirrors tasks through the Sync API, which returns only what changed since `sync_token`.
+pub fn sync(
+    db: &Db,
+    connection_id: &str,
+    token: &str,
+    cursor: Option<&str>,
+) -> Result<SyncProgress, String> {
+    sync_with_fallback(
+        "Todoist",
+        cursor,
+        |sync_token| sync_items(db, connection_id, token, sync_token),
+        || sync_items(db, connection_id, token, "*"),
+    )
+}
+
+fn sync_items(
+    db: &Db,
+    connection_id: &str,
+    token: &str,
+    sync_token: &str,
+) -> Result<SyncProgress, SyncError> {
+    let url = format!("{}/sync", TODOIST_SYNC_API.base_url(db, connection_id));
+    let response = fetch_json(
+        http_client().post(url).bearer_auth(token).form(&[
+            ("sync_token", sync_token),
+            ("resource_types", "[\"items\"]"),
+        ]),
+        "Todoist",
+        &[],
+    )?;
+    let next_token = response
+        .get("sync_token")
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| "Todoist sync response did not include a sync_token".to_string())?
+        .to_string();
+    // The server may answer an incremental request with a full snapshot.
+    let full_sync = response
+        .get("full_sync")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(sync_token == "*");
+
+    let mut upserts = Vec::new();
+    let mut deleted_ids = Vec::new();
+    for item in response
+        .get("items")
+        .and_then(|value| value.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let Some(id) = id_string(item.get("id")) else {
+            continue;
+        };
+        if item.get("is_deleted").and_then(|value| value.as_bool()) == Some(true) {
+            deleted_ids.push(id);
+            continue;
+        }
+        upserts.push(to_cached_task(connection_id, &id, item));
+    }
+
+    db.apply_cached_todoist_changes(connection_id, &upserts, &deleted_ids, full_sync)
+        .map_err(|e| e.to_string())?;
+    Ok(SyncProgress {
+        cursor: next_token,
+        changed: upserts.len() + deleted_ids.len(),
+    })
+}
+
+fn id_string(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Converts a Sync API item into the REST task shape returned by `todoist.list_tasks`.
+fn to_cached_task(connection_id: &str, id: &str, item: &Value) -> CachedTodoistTask {
+    let checked = item
+        .get("checked")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    let content = item
+        .get("content")
+        .and_then(|value| value.as_str())
+        .unwrap_or("")
+        .to_string();
+    let project_id = id_string(item.get("project_id"));
+    CachedTodoistTask {
+        connection_id: connection_id.to_string(),
+        id: id.to_string(),
+        project_id: project_id.clone(),
+        content: content.clone(),
+        checked,
+        raw: json!({
+            "id": id,
+            "content": content,
+            "description": item.get("description"),
+            "project_id": project_id,
+            "section_id": id_string(item.get("section_id")),
+            "parent_id": id_string(item.get("parent_id")),
+            "labels": item.get("labels"),
+            "priority": item.get("priority"),
+            "due": item.get("due"),
+            "order": item.get("child_order"),
+            "is_completed": checked,
+            "created_at": item.get("added_at"),
+            "url": format!("https://app.todoist.com/app/task/{id}"),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::mock_server::MockServer;
+
+    #[test]
+    fn sync_items_map_to_rest_task_shape() {
+        let item = json!({
+            "id": "123",
+            "content": "Buy milk",
+            "project_id": "9",
+            "checked": false,
+            "child_order": 3,
+            "labels": ["errand"],
+            "priority": 2
+        });
+        let task = to_cached_task("conn", "123", &item);
+        assert_eq!(task.project_id.as_deref(), Some("9"));
+        assert!(!task.checked);
+        assert_eq!(task.raw["order"], 3);
+        assert_eq!(task.raw["is_completed"], false);
+        assert_eq!(task.raw["labels"], json!(["errand"]));
+    }
+
+    #[test]
+    fn sync_runs_against_the_configured_sync_api() {
+        let db_path =
+            std::env::temp_dir().join(format!("todoist-sync-{}.db", uuid::Uuid::new_v4()));
+        let mut db = Db::new(db_path.to_str().unwrap()).expect("db init failed");
+        db.run_migrations().expect("db migrations failed");
+        let server = MockServer::start();
+        server.route(
+            "POST",
+            "/sync",
+            200,
+            json!({
+                "sync_token": "token-2",
+                "full_sync": true,
+                "items": [{ "id": "1", "content": "Buy milk", "project_id": "9" }]
+            }),
+        );
+        let connection = server.connect(&db, "todoist");
+
+        let progress = sync(&db, &connection.id, "mock-token", None).expect("sync failed");
+        assert_eq!(progress.cursor, "token-2");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].param("sync_token").as_deref(), Some("*"));
+        assert_eq!(
+            requests[0].header("authorization"),
+            Some("Bearer mock-token")
+        );
+        let tasks = db
+            .get_cached_todoist_tasks(&connection.id, None)
+            .expect("cached tasks");
+        assert_eq!(tasks.len(), 1);
+    }
+}
@@ -1,17 +1,110 @@
-use super::IntegrationMetadata;
+use super::endpoints::{TODOIST_REST_API, TODOIST_SYNC_API};
+use super::sync::{self, SyncProgress};
+use super::{
+    get_json, http_health_check, DiscoveredResource, Integration, IntegrationCapability,
+    IntegrationHealth, IntegrationMetadata, IntegrationScope,
+};
+use crate::db::{Db, IntegrationConnection, SYNC_RESOURCE_TODOIST};
+use crate::tools::{register_todoist_tools, ToolRegistry};
+use serde_json::json;
 
-pub fn integrations() -> Vec<IntegrationMetadata> {
-    vec![IntegrationMetadata {
-        id: "todoist".to_string(),
-        name: "Todoist".to_string(),
-        provider: "todoist".to_string(),
-        description: "Create and complete personal tasks.".to_string(),
-        auth_type: "oauth2".to_string(),
-        category: "tasks".to_string(),
-        capabilities: vec![
-            "sync".to_string(),
-            "action_execute".to_string(),
-            "discovery".to_string(),
-        ],
-    }]
+pub struct TodoistIntegration;
+
+impl Integration for TodoistIntegration {
+    fn manifest(&self) -> IntegrationMetadata {
+        IntegrationMetadata {
+            id: "todoist".to_string(),
+            name: "Todoist".to_string(),
+            provider: "todoist".to_string(),
+            description: "Manage tasks, projects, labels, and comments.".to_string(),
+            auth_type: "oauth2".to_string(),
+            category: "tasks".to_string(),
+            capabilities: vec![
+                IntegrationCapability::Sync,
+                IntegrationCapability::ActionExecute,
+                IntegrationCapability::Discovery,
+                IntegrationCapability::HealthCheck,
+            ],
+            scopes: vec![
+                IntegrationScope {
+                    label: "Read and edit tasks and projects".to_string(),
+                    scope: "data:read_write".to_string(),
+                },
+                IntegrationScope {
+                    label: "Delete tasks and labels".to_string(),
+                    scope: "data:delete".to_string(),
+                },
+            ],
+            settings_schema: json!({
+                "type": "object",
+                "properties": {
+                    "api_base_url": TODOIST_REST_API.setting_schema(),
+                    "sync_api_base_url": TODOIST_SYNC_API.setting_schema()
+                },
+                "additionalProperties": false
+            }),
+        }
+    }
+
+    fn health_check(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<IntegrationHealth, String> {
+        let token = self.access_token(db, connection)?;
+        http_health_check(&TODOIST_REST_API.url(db, connection, "/projects"), &token)
+    }
+
+    fn discovery(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+    ) -> Result<Vec<DiscoveredResource>, String> {
+        let token = self.access_token(db, connection)?;
+        let json = get_json(
+            &TODOIST_REST_API.url(db, connection, "/projects"),
+            &token,
+            "Todoist projects",
+        )?;
+        Ok(json
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|project| {
+                let id = project.get("id")?.as_str()?.to_string();
+                Some(DiscoveredResource {
+                    kind: "project".to_string(),
+                    name: project
+                        .get("name")
+                        .and_then(|value| value.as_str())
+                        .unwrap_or(&id)
+                        .to_string(),
+                    metadata: json!({
+                        "parent_id": project.get("parent_id"),
+                        "is_inbox_project": project.get("is_inbox_project"),
+                    }),
+                    id,
+                })
+            })
+            .collect())
+    }
+
+    fn sync_resources(&self, _db: &Db, _connection: &IntegrationConnection) -> Vec<String> {
+        vec![SYNC_RESOURCE_TODOIST.to_string()]
+    }
+
+    fn sync(
+        &self,
+        db: &Db,
+        connection: &IntegrationConnection,
+        token: &str,
+        _resource: &str,
+        cursor: Option<&str>,
+    ) -> Result<SyncProgress, String> {
+        sync::todoist::sync(db, &connection.id, token, cursor)
+    }
+
+    fn register_actions(&self, registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+        register_todoist_tools(registry, db)
+    }
 }
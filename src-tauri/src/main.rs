@@ -4,13 +4,16 @@ mod agent;
 mod commands;
 mod db;
 mod events;
+mod feeds;
 mod files;
 mod integrations;
 mod llm;
 mod oauth;
+mod scheduler;
 mod setup_default_values;
 mod tool_outputs;
 mod tools;
+mod triggers;
 
 use db::Db;
 use events::EventBus;
@@ -41,6 +44,9 @@ fn main() {
                 .expect("Failed to run database migrations");
 
             setup_default_values::initialize(&mut db).expect("Failed to initialize default values");
+            if let Err(err) = tools::purge_stale_tool_approval_rules(&db) {
+                log::warn!("[tools] {}", err);
+            }
 
             // Initialize the file manager
             let file_manager = FileManager::new().expect("Failed to create file manager");
@@ -68,12 +74,32 @@ fn main() {
                 .expect("Failed to register integration tools");
             tools::register_tool_output_tools(&mut tool_registry, db.clone())
                 .expect("Failed to register tool output tools");
+            tools::register_agent_tools(&mut tool_registry, db.clone())
+                .expect("Failed to register agent tools");
+            tools::register_feed_tools(&mut tool_registry, db.clone())
+                .expect("Failed to register feed tools");
             log::info!(
                 "[tools] registered {} tools",
                 tool_registry.list_metadata().len()
             );
             let approval_store = tools::ApprovalStore::new();
             let oauth_store = oauth::OAuthSessionStore::new();
+            let unattended_runner = scheduler::UnattendedRunner::new(
+                db.clone(),
+                event_bus.clone(),
+                tool_registry.clone(),
+                approval_store.clone(),
+            );
+            let scheduler = scheduler::Scheduler::new(unattended_runner.clone());
+            scheduler.start();
+            let trigger_engine = triggers::TriggerEngine::new(unattended_runner);
+            trigger_engine.start();
+            let sync_engine =
+                integrations::sync::IntegrationSyncEngine::new(db.clone(), event_bus.clone());
+            sync_engine.start();
+            oauth::TokenRefresher::new(db.clone()).start();
+            let feed_poller = feeds::FeedPoller::new(db.clone(), event_bus.clone());
+            feed_poller.start();
 
             app.manage(db);
             app.manage(file_manager);
@@ -81,6 +107,10 @@ fn main() {
             app.manage(tool_registry);
             app.manage(approval_store);
             app.manage(oauth_store);
+            app.manage(scheduler);
+            app.manage(trigger_engine);
+            app.manage(sync_engine);
+            app.manage(feed_poller);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -159,10 +189,18 @@ fn main() {
             commands::update_integration_connection,
             commands::delete_integration_connection,
             commands::test_integration_connection,
-            commands::start_google_oauth,
+            commands::start_integration_oauth,
+            commands::start_integration_device_oauth,
+            commands::upgrade_integration_scopes,
             commands::get_oauth_session,
             commands::cancel_oauth_session,
             commands::list_google_calendars,
+            commands::list_integration_sync_states,
+            commands::sync_integration_now,
+            commands::clear_integration_cache,
+            commands::discover_integration_resources,
+            commands::get_integration_settings,
+            commands::save_integration_settings,
             // MCP server commands
             commands::get_mcp_servers,
             commands::get_mcp_server,
@@ -183,6 +221,41 @@ fn main() {
             commands::list_pending_tool_approvals,
             commands::list_tools,
             commands::set_tool_approval_override,
+            commands::list_tool_approval_rules,
+            commands::create_tool_approval_rule,
+            commands::delete_tool_approval_rule,
+            commands::list_audit_log,
+            commands::export_audit_log,
+            commands::list_web_access_rules,
+            commands::save_web_access_rule,
+            commands::revoke_web_access_rule,
+            commands::list_web_auth_profiles,
+            commands::save_web_auth_profile,
+            commands::delete_web_auth_profile,
+            commands::list_web_crawls,
+            commands::cancel_web_crawl,
+            // Feed commands
+            commands::list_feed_subscriptions,
+            commands::create_feed_subscription,
+            commands::update_feed_subscription,
+            commands::delete_feed_subscription,
+            commands::refresh_feed,
+            commands::list_feed_items,
+            commands::mark_feed_items_read,
+            // Scheduled job commands
+            commands::list_scheduled_jobs,
+            commands::create_scheduled_job,
+            commands::update_scheduled_job,
+            commands::delete_scheduled_job,
+            commands::run_scheduled_job_now,
+            commands::list_scheduled_job_runs,
+            // Trigger commands
+            commands::list_agent_triggers,
+            commands::create_agent_trigger,
+            commands::update_agent_trigger,
+            commands::delete_agent_trigger,
+            commands::list_agent_trigger_events,
+            commands::poll_agent_trigger_now,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,359 @@
+use super::{
+    build_auth_url, exchange_code, generate_pkce, parse_scopes, poll_device_token,
+    request_device_authorization, DeviceOAuthStartResponse, DevicePoll, OAuthProvider,
+    OAuthSessionStore, OAuthStartResponse, TokenResponse,
+};
+use crate::db::{
+    CreateIntegrationConnectionInput, Db, IntegrationConnectionOperations,
+    UpdateIntegrationConnectionInput,
+};
+use crate::integrations::integration_registry;
+use chrono::Utc;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+use url::Url;
+
+const LOOPBACK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// What to authorize: the integration, its provider, the scopes to request and, for
+/// reconnects and scope upgrades, the connection to update.
+#[derive(Clone, Debug)]
+pub struct AuthorizationRequest {
+    pub integration_id: String,
+    pub provider: OAuthProvider,
+    pub scopes: Vec<String>,
+    pub connection_id: Option<String>,
+}
+
+impl AuthorizationRequest {
+    /// Requests the manifest scopes plus `extra_scopes`. With `connection_id`, scopes the
+    /// connection already holds are kept so an upgrade never narrows access.
+    pub fn new(
+        db: &Db,
+        integration_id: &str,
+        connection_id: Option<&str>,
+        extra_scopes: &[String],
+    ) -> Result<Self, String> {
+        let manifest = integration_registry()
+            .get(integration_id)
+            .map(|plugin| plugin.manifest())
+            .ok_or_else(|| format!("Unknown or unavailable integration '{integration_id}'."))?;
+        if manifest.auth_type != "oauth2" {
+            return Err(format!("{} does not use OAuth.", manifest.name));
+        }
+        let provider = super::oauth_provider(&manifest.provider)?;
+
+        let granted = match connection_id {
+            Some(connection_id) => {
+                let connection = db
+                    .get_integration_connection_by_id(connection_id)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| "Integration connection not found".to_string())?;
+                if connection.integration_id != integration_id {
+                    return Err(format!(
+                        "Connection {connection_id} is not a {integration_id} integration."
+                    ));
+                }
+                parse_scopes(connection.scopes.as_deref().unwrap_or(""))
+            }
+            None => Vec::new(),
+        };
+
+        let mut scopes: Vec<String> = Vec::new();
+        let candidates = manifest
+            .scopes
+            .into_iter()
+            .map(|scope| scope.scope)
+            .chain(granted)
+            .chain(extra_scopes.iter().cloned());
+        for scope in candidates {
+            if !scope.trim().is_empty() && !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+
+        Ok(Self {
+            integration_id: integration_id.to_string(),
+            provider,
+            scopes,
+            connection_id: connection_id.map(str::to_string),
+        })
+    }
+}
+
+/// Authorization code flow with PKCE and a one-shot loopback redirect. The returned URL is
+/// opened by the UI; the session completes when the browser hits the redirect.
+pub fn start_loopback_flow(
+    db: Db,
+    store: OAuthSessionStore,
+    request: AuthorizationRequest,
+) -> Result<OAuthStartResponse, String> {
+    let (code_verifier, code_challenge) = generate_pkce();
+    let state_token = uuid::Uuid::new_v4().to_string();
+
+    let listener = TcpListener::bind(("127.0.0.1", request.provider.redirect_port))
+        .map_err(|err| err.to_string())?;
+    let port = listener.local_addr().map_err(|err| err.to_string())?.port();
+    let redirect_uri = format!("http://127.0.0.1:{port}/oauth/{}", request.provider.id);
+    let auth_url = build_auth_url(
+        &request.provider,
+        &redirect_uri,
+        &request.scopes,
+        &state_token,
+        &code_challenge,
+    )?;
+
+    let session_id = store.create_session();
+    let session_id_for_thread = session_id.clone();
+    std::thread::spawn(move || {
+        let _ = listener.set_nonblocking(true);
+        let deadline = Instant::now() + LOOPBACK_TIMEOUT;
+
+        loop {
+            if store.is_cancelled(&session_id_for_thread) {
+                break;
+            }
+            if Instant::now() > deadline {
+                store.set_error(&session_id_for_thread, "OAuth flow timed out.".to_string());
+                break;
+            }
+
+            match listener.accept() {
+                Ok((mut stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    let callback = LoopbackCallback {
+                        redirect_uri: &redirect_uri,
+                        code_verifier: &code_verifier,
+                        state_token: &state_token,
+                    };
+                    let result = callback.handle(&mut stream, &request);
+                    let page = match result {
+                        Ok(token) => match save_authorized_connection(&db, &request, token) {
+                            Ok(connection_id) => {
+                                store.set_completed(&session_id_for_thread, connection_id);
+                                "Authorization complete. You can return to the app."
+                            }
+                            Err(err) => {
+                                store.set_error(&session_id_for_thread, err);
+                                "Authorization failed. You can close this window."
+                            }
+                        },
+                        Err(err) => {
+                            store.set_error(&session_id_for_thread, err);
+                            "Authorization failed. You can close this window."
+                        }
+                    };
+                    let _ = respond_html(&mut stream, page);
+                    break;
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(200));
+                }
+                Err(err) => {
+                    store.set_error(
+                        &session_id_for_thread,
+                        format!("OAuth listener error: {err}"),
+                    );
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(OAuthStartResponse {
+        session_id,
+        auth_url,
+    })
+}
+
+struct LoopbackCallback<'a> {
+    redirect_uri: &'a str,
+    code_verifier: &'a str,
+    state_token: &'a str,
+}
+
+impl LoopbackCallback<'_> {
+    fn handle(
+        &self,
+        stream: &mut TcpStream,
+        request: &AuthorizationRequest,
+    ) -> Result<TokenResponse, String> {
+        let mut buffer = [0u8; 4096];
+        let size = stream
+            .read(&mut buffer)
+            .map_err(|err| format!("OAuth read error: {err}"))?;
+        let raw = String::from_utf8_lossy(&buffer[..size]);
+        let path = raw
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or("/");
+        let parsed = Url::parse(&format!("http://localhost{path}"))
+            .map_err(|err| format!("OAuth parse error: {err}"))?;
+
+        let mut code: Option<String> = None;
+        let mut state: Option<String> = None;
+        let mut error: Option<String> = None;
+        let mut error_description: Option<String> = None;
+        for (key, value) in parsed.query_pairs() {
+            match key.as_ref() {
+                "code" => code = Some(value.to_string()),
+                "state" => state = Some(value.to_string()),
+                "error" => error = Some(value.to_string()),
+                "error_description" => error_description = Some(value.to_string()),
+                _ => {}
+            }
+        }
+
+        if let Some(error) = error {
+            return Err(match error_description {
+                Some(description) => format!("OAuth error: {error} - {description}"),
+                None => format!("OAuth error: {error}"),
+            });
+        }
+        if state.as_deref() != Some(self.state_token) {
+            return Err("OAuth state mismatch.".to_string());
+        }
+        let code = code.ok_or_else(|| "Missing authorization code.".to_string())?;
+        exchange_code(
+            &request.provider,
+            &code,
+            self.code_verifier,
+            self.redirect_uri,
+        )
+    }
+}
+
+/// Device authorization grant: the user enters a short code on another device. The
+/// returned code is shown by the UI while a background thread polls for the token.
+pub fn start_device_flow(
+    db: Db,
+    store: OAuthSessionStore,
+    request: AuthorizationRequest,
+) -> Result<DeviceOAuthStartResponse, String> {
+    let authorization = request_device_authorization(&request.provider, &request.scopes)?;
+    let session_id = store.create_session();
+    let session_id_for_thread = session_id.clone();
+    let device_code = authorization.device_code.clone();
+    let mut interval = Duration::from_secs(authorization.interval.max(1));
+    let deadline = Instant::now() + Duration::from_secs(authorization.expires_in);
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        if store.is_cancelled(&session_id_for_thread) {
+            break;
+        }
+        if Instant::now() > deadline {
+            store.set_error(
+                &session_id_for_thread,
+                "Device code expired before it was approved.".to_string(),
+            );
+            break;
+        }
+        match poll_device_token(&request.provider, &device_code) {
+            Ok(DevicePoll::Pending) => {}
+            Ok(DevicePoll::SlowDown) => interval += Duration::from_secs(5),
+            Ok(DevicePoll::Complete(token)) => {
+                match save_authorized_connection(&db, &request, token) {
+                    Ok(connection_id) => store.set_completed(&session_id_for_thread, connection_id),
+                    Err(err) => store.set_error(&session_id_for_thread, err),
+                }
+                break;
+            }
+            Err(err) => {
+                store.set_error(&session_id_for_thread, err);
+                break;
+            }
+        }
+    });
+
+    Ok(DeviceOAuthStartResponse {
+        session_id,
+        user_code: authorization.user_code,
+        verification_uri: authorization.verification_uri,
+        verification_uri_complete: authorization.verification_uri_complete,
+        expires_in: authorization.expires_in,
+    })
+}
+
+/// Stores the new tokens on the requested connection, the integration's existing
+/// connection, or a new one. Returns the connection id.
+fn save_authorized_connection(
+    db: &Db,
+    request: &AuthorizationRequest,
+    token: TokenResponse,
+) -> Result<String, String> {
+    let now = Utc::now().timestamp_millis();
+    let expires_at = token.expires_in.map(|seconds| now + seconds * 1000);
+    let account_label = integration_registry()
+        .get(&request.integration_id)
+        .and_then(|plugin| plugin.fetch_account_label(&token.access_token));
+
+    let existing = match request.connection_id.as_deref() {
+        Some(connection_id) => db
+            .get_integration_connection_by_id(connection_id)
+            .map_err(|e| e.to_string())?,
+        None => db
+            .get_integration_connections()
+            .ok()
+            .and_then(|connections| {
+                connections
+                    .into_iter()
+                    .find(|item| item.integration_id == request.integration_id)
+            }),
+    };
+
+    let refresh_token = token.refresh_token.clone().or_else(|| {
+        existing
+            .as_ref()
+            .and_then(|item| item.refresh_token.clone())
+    });
+    let scopes = match token.scope.as_deref() {
+        Some(scope) if !scope.trim().is_empty() => parse_scopes(scope).join(" "),
+        _ => request.scopes.join(" "),
+    };
+
+    if let Some(existing) = existing {
+        let update = UpdateIntegrationConnectionInput {
+            id: existing.id.clone(),
+            account_label,
+            status: Some("connected".to_string()),
+            auth_type: Some("oauth2".to_string()),
+            access_token: Some(token.access_token),
+            refresh_token,
+            scopes: Some(scopes),
+            expires_at,
+            last_error: Some(String::new()),
+            last_sync_at: None,
+        };
+        return match db.update_integration_connection(&update) {
+            Ok(Some(updated)) => Ok(updated.id),
+            Ok(None) => Ok(existing.id),
+            Err(err) => Err(format!("Failed to save connection: {err}")),
+        };
+    }
+
+    let input = CreateIntegrationConnectionInput {
+        integration_id: request.integration_id.clone(),
+        account_label,
+        auth_type: "oauth2".to_string(),
+        access_token: Some(token.access_token),
+        refresh_token,
+        scopes: Some(scopes),
+        expires_at,
+    };
+    db.create_integration_connection(&input)
+        .map(|created| created.id)
+        .map_err(|err| format!("Failed to save connection: {err}"))
+}
+
+fn respond_html(stream: &mut TcpStream, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
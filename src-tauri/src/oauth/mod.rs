@@ -0,0 +1,821 @@
+//! Provider-agnostic OAuth 2.0: endpoint config per provider, authorization code with PKCE,
+//! device authorization, refresh and revocation. Flows that talk to the user live in
+//! [`flow`]; token refresh, including the background refresher, lives in [`refresh`].
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use url::Url;
+use uuid::Uuid;
+
+pub mod flow;
+pub mod refresh;
+
+pub use flow::{start_device_flow, start_loopback_flow, AuthorizationRequest};
+pub use refresh::{ensure_access_token, TokenRefresher};
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Endpoints and client credentials of one OAuth provider.
+#[derive(Clone, Debug)]
+pub struct OAuthProvider {
+    pub id: String,
+    pub display_name: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub auth_url: String,
+    pub token_url: String,
+    pub device_authorization_url: Option<String>,
+    pub revocation_url: Option<String>,
+    /// Form field that carries the token in revocation requests.
+    pub revocation_token_param: &'static str,
+    pub scope_separator: &'static str,
+    pub extra_auth_params: Vec<(&'static str, &'static str)>,
+    /// The device flow works with the client id alone, so no client secret is required.
+    pub public_client: bool,
+    /// Loopback port for providers that only accept a registered redirect URI; 0 picks a
+    /// free port.
+    pub redirect_port: u16,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum TokenError {
+    /// The grant is gone (revoked, expired or missing); the user has to connect again.
+    Reauthorize(String),
+    Failed(String),
+}
+
+impl TokenError {
+    pub fn message(&self) -> &str {
+        match self {
+            TokenError::Reauthorize(message) | TokenError::Failed(message) => message,
+        }
+    }
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthErrorResponse {
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    error_description: Option<String>,
+    #[serde(default)]
+    error_uri: Option<String>,
+}
+
+/// Provider config with client credentials from `{PROVIDER}_OAUTH_CLIENT_ID` and
+/// `{PROVIDER}_OAUTH_CLIENT_SECRET`, read at runtime or baked in at build time.
+pub fn oauth_provider(provider_id: &str) -> Result<OAuthProvider, String> {
+    let client_id = oauth_env_value(provider_id, "CLIENT_ID");
+    let client_secret = oauth_env_value(provider_id, "CLIENT_SECRET");
+    let redirect_port = oauth_env_value(provider_id, "REDIRECT_PORT")
+        .trim()
+        .parse::<u16>()
+        .unwrap_or(0);
+    let mut provider = provider_endpoints(provider_id, client_id, Some(client_secret))
+        .ok_or_else(|| format!("No OAuth configuration for provider '{provider_id}'."))?;
+    provider.redirect_port = redirect_port;
+    apply_endpoint_overrides(&mut provider);
+
+    let has_secret = provider
+        .client_secret
+        .as_ref()
+        .map(|secret| !secret.trim().is_empty())
+        .unwrap_or(false);
+    let prefix = provider_id.to_uppercase();
+    if provider.client_id.trim().is_empty() && provider.public_client {
+        return Err(format!(
+            "{} OAuth is disabled. Set {prefix}_OAUTH_CLIENT_ID.",
+            provider.display_name
+        ));
+    }
+    if provider.client_id.trim().is_empty() || (!has_secret && !provider.public_client) {
+        return Err(format!(
+            "{} OAuth is disabled. Set {prefix}_OAUTH_CLIENT_ID and {prefix}_OAUTH_CLIENT_SECRET.",
+            provider.display_name
+        ));
+    }
+    Ok(provider)
+}
+
+pub fn oauth_provider_configured(provider_id: &str) -> bool {
+    oauth_provider(provider_id).is_ok()
+}
+
+pub fn google_oauth_env_configured() -> bool {
+    oauth_provider_configured("google")
+}
+
+fn provider_endpoints(
+    provider_id: &str,
+    client_id: String,
+    client_secret: Option<String>,
+) -> Option<OAuthProvider> {
+    let provider = match provider_id {
+        "google" => OAuthProvider {
+            id: provider_id.to_string(),
+            display_name: "Google".to_string(),
+            client_id,
+            client_secret,
+            auth_url: "https://accounts.google.com/o/oauth2/v2/auth".to_string(),
+            token_url: "https://oauth2.googleapis.com/token".to_string(),
+            device_authorization_url: Some("https://oauth2.googleapis.com/device/code".to_string()),
+            revocation_url: Some("https://oauth2.googleapis.com/revoke".to_string()),
+            revocation_token_param: "token",
+            scope_separator: " ",
+            // `include_granted_scopes` keeps earlier grants when upgrading scopes.
+            extra_auth_params: vec![
+                ("access_type", "offline"),
+                ("prompt", "consent"),
+                ("include_granted_scopes", "true"),
+            ],
+            public_client: false,
+            redirect_port: 0,
+        },
+        "todoist" => OAuthProvider {
+            id: provider_id.to_string(),
+            display_name: "Todoist".to_string(),
+            client_id,
+            client_secret,
+            auth_url: "https://todoist.com/oauth/authorize".to_string(),
+            token_url: "https://todoist.com/oauth/access_token".to_string(),
+            device_authorization_url: None,
+            revocation_url: Some(
+                "https://api.todoist.com/sync/v9/access_tokens/revoke".to_string(),
+            ),
+            revocation_token_param: "access_token",
+            scope_separator: ",",
+            extra_auth_params: Vec::new(),
+            public_client: false,
+            redirect_port: 0,
+        },
+        // Revoking a GitHub grant needs the client secret as basic auth on a JSON DELETE,
+        // which the form-based revocation does not cover; users revoke it on GitHub.
+        "github" => OAuthProvider {
+            id: provider_id.to_string(),
+            display_name: "GitHub".to_string(),
+            client_id,
+            client_secret,
+            auth_url: "https://github.com/login/oauth/authorize".to_string(),
+            token_url: "https://github.com/login/oauth/access_token".to_string(),
+            device_authorization_url: Some("https://github.com/login/device/code".to_string()),
+            revocation_url: None,
+            revocation_token_param: "access_token",
+            scope_separator: " ",
+            extra_auth_params: Vec::new(),
+            public_client: true,
+            redirect_port: 0,
+        },
+        _ => return None,
+    };
+    Some(provider)
+}
+
+/// Points a provider at a gateway or local stand-in through `{PROVIDER}_OAUTH_AUTH_URL`,
+/// `_TOKEN_URL`, `_DEVICE_URL` and `_REVOCATION_URL`.
+fn apply_endpoint_overrides(provider: &mut OAuthProvider) {
+    let value = |key: &str| {
+        let value = oauth_env_value(&provider.id, key);
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    };
+    let auth_url = value("AUTH_URL");
+    let token_url = value("TOKEN_URL");
+    let device_url = value("DEVICE_URL");
+    let revocation_url = value("REVOCATION_URL");
+    if let Some(url) = auth_url {
+        provider.auth_url = url;
+    }
+    if let Some(url) = token_url {
+        provider.token_url = url;
+    }
+    if device_url.is_some() {
+        provider.device_authorization_url = device_url;
+    }
+    if revocation_url.is_some() {
+        provider.revocation_url = revocation_url;
+    }
+}
+
+fn oauth_env_value(provider_id: &str, key: &str) -> String {
+    let name = format!("{}_OAUTH_{key}", provider_id.to_uppercase());
+    std::env::var(&name)
+        .ok()
+        .or_else(|| build_time_env_value(&name).map(|value| value.to_string()))
+        .unwrap_or_default()
+}
+
+/// Credentials compiled into release builds. `option_env!` needs literal names.
+fn build_time_env_value(name: &str) -> Option<&'static str> {
+    match name {
+        "GOOGLE_OAUTH_CLIENT_ID" => option_env!("GOOGLE_OAUTH_CLIENT_ID"),
+        "GOOGLE_OAUTH_CLIENT_SECRET" => option_env!("GOOGLE_OAUTH_CLIENT_SECRET"),
+        "TODOIST_OAUTH_CLIENT_ID" => option_env!("TODOIST_OAUTH_CLIENT_ID"),
+        "TODOIST_OAUTH_CLIENT_SECRET" => option_env!("TODOIST_OAUTH_CLIENT_SECRET"),
+        "GITHUB_OAUTH_CLIENT_ID" => option_env!("GITHUB_OAUTH_CLIENT_ID"),
+        "GITHUB_OAUTH_CLIENT_SECRET" => option_env!("GITHUB_OAUTH_CLIENT_SECRET"),
+        _ => None,
+    }
+}
+
+pub fn generate_pkce() -> (String, String) {
+    let verifier: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(64)
+        .map(char::from)
+        .collect();
+    let mut hasher = Sha256::new();
+    hasher.update(verifier.as_bytes());
+    let challenge = URL_SAFE_NO_PAD.encode(hasher.finalize());
+    (verifier, challenge)
+}
+
+pub fn build_auth_url(
+    provider: &OAuthProvider,
+    redirect_uri: &str,
+    scopes: &[String],
+    state: &str,
+    code_challenge: &str,
+) -> Result<String, String> {
+    let mut url =
+        Url::parse(&provider.auth_url).map_err(|err| format!("Failed to build auth URL: {err}"))?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("client_id", &provider.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("response_type", "code")
+            .append_pair("scope", &scopes.join(provider.scope_separator))
+            .append_pair("state", state)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        for (key, value) in &provider.extra_auth_params {
+            query.append_pair(key, value);
+        }
+    }
+    Ok(url.to_string())
+}
+
+fn with_client_credentials(
+    provider: &OAuthProvider,
+    mut params: Vec<(&'static str, String)>,
+) -> Vec<(&'static str, String)> {
+    params.push(("client_id", provider.client_id.clone()));
+    if let Some(secret) = provider
+        .client_secret
+        .as_ref()
+        .filter(|secret| !secret.trim().is_empty())
+    {
+        params.push(("client_secret", secret.clone()));
+    }
+    params
+}
+
+/// Posts a form to the provider and returns the status and body. Providers default to
+/// form-encoded responses unless JSON is asked for.
+fn post_form(
+    request: RequestBuilder,
+    params: &[(&'static str, String)],
+    action: &str,
+) -> Result<(reqwest::StatusCode, String), String> {
+    let response = request
+        .header("Accept", "application/json")
+        .form(params)
+        .send()
+        .map_err(|err| format!("{action} failed: {err}"))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .map_err(|err| format!("Failed to read {action} response: {err}"))?;
+    Ok((status, body))
+}
+
+fn request_token(
+    provider: &OAuthProvider,
+    params: Vec<(&'static str, String)>,
+    action: &str,
+) -> Result<TokenResponse, TokenError> {
+    let params = with_client_credentials(provider, params);
+    let (status, body) = post_form(Client::new().post(&provider.token_url), &params, action)
+        .map_err(TokenError::Failed)?;
+    if !status.is_success() {
+        let message = format!(
+            "{action} error: HTTP {status}{} [client_id={}]",
+            format_oauth_error(&body),
+            provider.client_id
+        );
+        // RFC 6749 uses 400 `invalid_grant` for revoked or expired grants; some providers
+        // answer 401.
+        return Err(
+            if status == reqwest::StatusCode::BAD_REQUEST
+                || status == reqwest::StatusCode::UNAUTHORIZED
+            {
+                TokenError::Reauthorize(message)
+            } else {
+                TokenError::Failed(message)
+            },
+        );
+    }
+    serde_json::from_str::<TokenResponse>(&body)
+        .map_err(|err| TokenError::Failed(format!("Failed to parse {action} response: {err}")))
+}
+
+pub fn exchange_code(
+    provider: &OAuthProvider,
+    code: &str,
+    code_verifier: &str,
+    redirect_uri: &str,
+) -> Result<TokenResponse, String> {
+    request_token(
+        provider,
+        vec![
+            ("code", code.to_string()),
+            ("code_verifier", code_verifier.to_string()),
+            ("redirect_uri", redirect_uri.to_string()),
+            ("grant_type", "authorization_code".to_string()),
+        ],
+        "Token exchange",
+    )
+    .map_err(|err| err.to_string())
+}
+
+pub fn refresh_access_token(
+    provider: &OAuthProvider,
+    refresh_token: &str,
+) -> Result<TokenResponse, TokenError> {
+    request_token(
+        provider,
+        vec![
+            ("refresh_token", refresh_token.to_string()),
+            ("grant_type", "refresh_token".to_string()),
+        ],
+        "Token refresh",
+    )
+}
+
+/// Revokes a token at the provider. Returns false when the provider has no revocation
+/// endpoint.
+pub fn revoke_token(provider: &OAuthProvider, token: &str) -> Result<bool, String> {
+    let Some(url) = provider.revocation_url.as_deref() else {
+        return Ok(false);
+    };
+    let params = with_client_credentials(
+        provider,
+        vec![(provider.revocation_token_param, token.to_string())],
+    );
+    let (status, body) = post_form(Client::new().post(url), &params, "Token revocation")?;
+    if !status.is_success() {
+        return Err(format!(
+            "Token revocation error: HTTP {status}{}",
+            format_oauth_error(&body)
+        ));
+    }
+    Ok(true)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    /// Google calls this `verification_url`.
+    #[serde(alias = "verification_url")]
+    pub verification_uri: String,
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    #[serde(default = "default_device_poll_interval")]
+    pub interval: u64,
+}
+
+fn default_device_poll_interval() -> u64 {
+    5
+}
+
+pub fn request_device_authorization(
+    provider: &OAuthProvider,
+    scopes: &[String],
+) -> Result<DeviceAuthorization, String> {
+    let url = provider
+        .device_authorization_url
+        .as_deref()
+        .ok_or_else(|| {
+            format!(
+                "{} does not support the device authorization flow.",
+                provider.display_name
+            )
+        })?;
+    let params = with_client_credentials(
+        provider,
+        vec![("scope", scopes.join(provider.scope_separator))],
+    );
+    let (status, body) = post_form(Client::new().post(url), &params, "Device authorization")?;
+    if !status.is_success() {
+        return Err(format!(
+            "Device authorization error: HTTP {status}{}",
+            format_oauth_error(&body)
+        ));
+    }
+    serde_json::from_str::<DeviceAuthorization>(&body)
+        .map_err(|err| format!("Failed to parse device authorization response: {err}"))
+}
+
+#[derive(Debug)]
+pub enum DevicePoll {
+    Pending,
+    /// The provider asked to poll less often.
+    SlowDown,
+    Complete(TokenResponse),
+}
+
+pub fn poll_device_token(
+    provider: &OAuthProvider,
+    device_code: &str,
+) -> Result<DevicePoll, String> {
+    let params = with_client_credentials(
+        provider,
+        vec![
+            ("device_code", device_code.to_string()),
+            ("grant_type", DEVICE_CODE_GRANT.to_string()),
+        ],
+    );
+    let (status, body) = post_form(
+        Client::new().post(&provider.token_url),
+        &params,
+        "Device token request",
+    )?;
+    // Some providers (GitHub) report pending states with HTTP 200 and an `error` field.
+    let error = serde_json::from_str::<OAuthErrorResponse>(&body)
+        .ok()
+        .and_then(|parsed| parsed.error);
+    match error.as_deref() {
+        Some("authorization_pending") => return Ok(DevicePoll::Pending),
+        Some("slow_down") => return Ok(DevicePoll::SlowDown),
+        Some(_) => {
+            return Err(format!(
+                "Device authorization failed{}",
+                format_oauth_error(&body)
+            ))
+        }
+        None => {}
+    }
+    if !status.is_success() {
+        return Err(format!(
+            "Device token error: HTTP {status}{}",
+            format_oauth_error(&body)
+        ));
+    }
+    serde_json::from_str::<TokenResponse>(&body)
+        .map(DevicePoll::Complete)
+        .map_err(|err| format!("Failed to parse device token response: {err}"))
+}
+
+/// Splits a stored or returned scope string. Providers use spaces or commas.
+pub fn parse_scopes(raw: &str) -> Vec<String> {
+    raw.split(|c: char| c.is_whitespace() || c == ',')
+        .map(str::trim)
+        .filter(|scope| !scope.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Broader scopes that include narrower ones, as `(granted, also covers)`.
+const SCOPE_IMPLICATIONS: [(&str, &str); 9] = [
+    (
+        "https://mail.google.com/",
+        "https://www.googleapis.com/auth/gmail.",
+    ),
+    (
+        "https://www.googleapis.com/auth/gmail.modify",
+        "https://www.googleapis.com/auth/gmail.readonly",
+    ),
+    (
+        "https://www.googleapis.com/auth/gmail.modify",
+        "https://www.googleapis.com/auth/gmail.labels",
+    ),
+    (
+        "https://www.googleapis.com/auth/gmail.modify",
+        "https://www.googleapis.com/auth/gmail.compose",
+    ),
+    (
+        "https://www.googleapis.com/auth/gmail.modify",
+        "https://www.googleapis.com/auth/gmail.send",
+    ),
+    (
+        "https://www.googleapis.com/auth/gmail.compose",
+        "https://www.googleapis.com/auth/gmail.send",
+    ),
+    (
+        "https://www.googleapis.com/auth/calendar",
+        "https://www.googleapis.com/auth/calendar.",
+    ),
+    (
+        "https://www.googleapis.com/auth/calendar.events",
+        "https://www.googleapis.com/auth/calendar.events.readonly",
+    ),
+    ("data:read_write", "data:read"),
+];
+
+fn scope_granted(granted: &[String], required: &str) -> bool {
+    granted.iter().any(|scope| {
+        scope == required
+            || SCOPE_IMPLICATIONS
+                .iter()
+                .any(|(broad, covered)| scope == broad && required.starts_with(covered))
+    })
+}
+
+/// Required scopes the connection has not granted. Connections without recorded scopes
+/// (e.g. pasted tokens) are not checked.
+pub fn missing_scopes(granted: Option<&str>, required: &[&str]) -> Vec<String> {
+    let granted = parse_scopes(granted.unwrap_or(""));
+    if granted.is_empty() {
+        return Vec::new();
+    }
+    required
+        .iter()
+        .filter(|scope| !scope_granted(&granted, scope))
+        .map(|scope| scope.to_string())
+        .collect()
+}
+
+fn format_oauth_error(body: &str) -> String {
+    let trimmed = body.trim();
+    if trimmed.is_empty() {
+        return String::new();
+    }
+
+    if let Ok(parsed) = serde_json::from_str::<OAuthErrorResponse>(trimmed) {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(error) = parsed.error {
+            if !error.trim().is_empty() {
+                parts.push(error.trim().to_string());
+            }
+        }
+        if let Some(description) = parsed.error_description {
+            if !description.trim().is_empty() {
+                parts.push(description.trim().to_string());
+            }
+        }
+        if let Some(uri) = parsed.error_uri {
+            if !uri.trim().is_empty() {
+                parts.push(uri.trim().to_string());
+            }
+        }
+        if !parts.is_empty() {
+            return format!(" ({})", parts.join(" - "));
+        }
+    } else if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+        if let Some(error) = value.get("error").and_then(|v| v.as_str()) {
+            let mut parts: Vec<String> = Vec::new();
+            if !error.trim().is_empty() {
+                parts.push(error.trim().to_string());
+            }
+            if let Some(description) = value.get("error_description").and_then(|v| v.as_str()) {
+                if !description.trim().is_empty() {
+                    parts.push(description.trim().to_string());
+                }
+            }
+            if let Some(uri) = value.get("error_uri").and_then(|v| v.as_str()) {
+                if !uri.trim().is_empty() {
+                    parts.push(uri.trim().to_string());
+                }
+            }
+            if !parts.is_empty() {
+                return format!(" ({})", parts.join(" - "));
+            }
+        }
+    }
+
+    let truncated: String = trimmed.chars().take(300).collect();
+    if trimmed.len() > truncated.len() {
+        format!(" ({}...)", truncated)
+    } else {
+        format!(" ({truncated})")
+    }
+}
+
+#[derive(Clone, Debug)]
+struct OAuthSession {
+    status: OAuthStatus,
+}
+
+#[derive(Clone, Debug)]
+enum OAuthStatus {
+    Pending,
+    Completed { connection_id: String },
+    Error { message: String },
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct OAuthSessionStatus {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct OAuthSessionStore {
+    sessions: Arc<Mutex<HashMap<String, OAuthSession>>>,
+}
+
+impl OAuthSessionStore {
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn create_session(&self) -> String {
+        let id = Uuid::new_v4().to_string();
+        let session = OAuthSession {
+            status: OAuthStatus::Pending,
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.insert(id.clone(), session);
+        id
+    }
+
+    pub fn set_completed(&self, id: &str, connection_id: String) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.get_mut(id) {
+            session.status = OAuthStatus::Completed { connection_id };
+        }
+    }
+
+    pub fn set_error(&self, id: &str, message: String) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.get_mut(id) {
+            session.status = OAuthStatus::Error { message };
+        }
+    }
+
+    pub fn set_cancelled(&self, id: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.get_mut(id) {
+            session.status = OAuthStatus::Cancelled;
+        }
+    }
+
+    pub fn is_cancelled(&self, id: &str) -> bool {
+        let sessions = self.sessions.lock().unwrap();
+        matches!(
+            sessions.get(id).map(|session| &session.status),
+            Some(OAuthStatus::Cancelled)
+        )
+    }
+
+    pub fn get_status(&self, id: &str) -> Option<OAuthSessionStatus> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions.get(id).map(|session| match &session.status {
+            OAuthStatus::Pending => OAuthSessionStatus {
+                status: "pending".to_string(),
+                connection_id: None,
+                error: None,
+            },
+            OAuthStatus::Completed { connection_id } => OAuthSessionStatus {
+                status: "completed".to_string(),
+                connection_id: Some(connection_id.clone()),
+                error: None,
+            },
+            OAuthStatus::Error { message } => OAuthSessionStatus {
+                status: "error".to_string(),
+                connection_id: None,
+                error: Some(message.clone()),
+            },
+            OAuthStatus::Cancelled => OAuthSessionStatus {
+                status: "cancelled".to_string(),
+                connection_id: None,
+                error: None,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct OAuthStartResponse {
+    pub session_id: String,
+    pub auth_url: String,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct DeviceOAuthStartResponse {
+    pub session_id: String,
+    /// Code the user enters at `verification_uri`.
+    pub user_code: String,
+    pub verification_uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_provider() -> OAuthProvider {
+        provider_endpoints("google", "client-id".to_string(), None).expect("google provider")
+    }
+
+    #[test]
+    fn pkce_generation_is_deterministic_length() {
+        let (verifier, challenge) = generate_pkce();
+        assert!(verifier.len() >= 43);
+        assert!(!challenge.contains('='));
+    }
+
+    #[test]
+    fn build_auth_url_contains_params() {
+        let scopes = vec!["scope-a".to_string(), "scope-b".to_string()];
+        let url = build_auth_url(
+            &test_provider(),
+            "http://127.0.0.1:8000/callback",
+            &scopes,
+            "state",
+            "challenge",
+        )
+        .expect("url");
+        let parsed = url::Url::parse(&url).expect("parse url");
+        let params: std::collections::HashMap<_, _> = parsed.query_pairs().into_owned().collect();
+        assert_eq!(params.get("client_id"), Some(&"client-id".to_string()));
+        assert_eq!(
+            params.get("redirect_uri"),
+            Some(&"http://127.0.0.1:8000/callback".to_string())
+        );
+        assert_eq!(params.get("scope"), Some(&"scope-a scope-b".to_string()));
+        assert_eq!(params.get("code_challenge"), Some(&"challenge".to_string()));
+        assert_eq!(params.get("access_type"), Some(&"offline".to_string()));
+    }
+
+    #[test]
+    fn todoist_scopes_are_comma_separated() {
+        let provider =
+            provider_endpoints("todoist", "client-id".to_string(), None).expect("todoist");
+        let scopes = vec!["data:read_write".to_string(), "data:delete".to_string()];
+        let url =
+            build_auth_url(&provider, "http://127.0.0.1:1/cb", &scopes, "s", "c").expect("url");
+        let parsed = url::Url::parse(&url).expect("parse url");
+        let scope = parsed
+            .query_pairs()
+            .find(|(key, _)| key == "scope")
+            .map(|(_, value)| value.into_owned());
+        assert_eq!(scope.as_deref(), Some("data:read_write,data:delete"));
+    }
+
+    #[test]
+    fn github_is_a_public_device_flow_client() {
+        let provider = provider_endpoints("github", "client-id".to_string(), None).expect("github");
+        assert!(provider.public_client);
+        assert!(provider.device_authorization_url.is_some());
+        assert!(provider.revocation_url.is_none());
+    }
+
+    #[test]
+    fn missing_scopes_respects_broader_grants() {
+        let granted = "https://www.googleapis.com/auth/gmail.modify https://www.googleapis.com/auth/gmail.send";
+        assert!(missing_scopes(
+            Some(granted),
+            &["https://www.googleapis.com/auth/gmail.readonly"]
+        )
+        .is_empty());
+        assert_eq!(
+            missing_scopes(
+                Some("https://www.googleapis.com/auth/gmail.readonly"),
+                &["https://www.googleapis.com/auth/gmail.send"]
+            ),
+            vec!["https://www.googleapis.com/auth/gmail.send".to_string()]
+        );
+        assert!(missing_scopes(None, &["anything"]).is_empty());
+        assert!(missing_scopes(
+            Some("https://mail.google.com/"),
+            &["https://www.googleapis.com/auth/gmail.send"]
+        )
+        .is_empty());
+    }
+}
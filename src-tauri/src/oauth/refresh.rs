@@ -0,0 +1,268 @@
+use super::{oauth_provider, refresh_access_token, TokenError};
+use crate::db::{
+    Db, IntegrationConnection, IntegrationConnectionOperations, UpdateIntegrationConnectionInput,
+};
+use crate::integrations::integration_registry;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Tokens expiring within this window are refreshed before use.
+const USE_MARGIN_MS: i64 = 60_000;
+/// The background refresher renews tokens this far ahead of expiry, so tools and syncs
+/// rarely wait on a refresh.
+const BACKGROUND_MARGIN_MS: i64 = 5 * 60_000;
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One lock per connection id. Refreshes of a connection are serialized so concurrent
+/// callers don't each spend its refresh token, while a slow provider never holds up other
+/// connections.
+static REFRESH_LOCKS: OnceLock<Mutex<HashMap<String, Arc<Mutex<()>>>>> = OnceLock::new();
+
+fn refresh_lock(connection_id: &str) -> Arc<Mutex<()>> {
+    REFRESH_LOCKS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry(connection_id.to_string())
+        .or_default()
+        .clone()
+}
+
+fn now_ms() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+fn needs_refresh(connection: &IntegrationConnection, now: i64, margin_ms: i64) -> bool {
+    let token_missing = connection
+        .access_token
+        .as_deref()
+        .map(|token| token.trim().is_empty())
+        .unwrap_or(true);
+    let expiring = connection
+        .expires_at
+        .map(|expires_at| expires_at > 0 && expires_at <= now + margin_ms)
+        .unwrap_or(false);
+    token_missing || expiring
+}
+
+/// Returns the connection's access token, refreshing it first when it is missing or about
+/// to expire.
+pub fn ensure_access_token(
+    db: &Db,
+    connection: &IntegrationConnection,
+    provider_id: &str,
+) -> Result<String, TokenError> {
+    if !needs_refresh(connection, now_ms(), USE_MARGIN_MS) {
+        log::debug!(
+            "[oauth] using cached access token: connection_id={} integration_id={} expires_at={}",
+            connection.id,
+            connection.integration_id,
+            connection.expires_at.unwrap_or(0)
+        );
+        return Ok(connection.access_token.clone().unwrap_or_default());
+    }
+    refresh_connection(db, &connection.id, provider_id, USE_MARGIN_MS)
+}
+
+fn refresh_connection(
+    db: &Db,
+    connection_id: &str,
+    provider_id: &str,
+    margin_ms: i64,
+) -> Result<String, TokenError> {
+    let lock = refresh_lock(connection_id);
+    let _guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Re-read under the lock: another caller may have refreshed while this one waited.
+    let connection = db
+        .get_integration_connection_by_id(connection_id)
+        .map_err(|err| TokenError::Failed(err.to_string()))?
+        .ok_or_else(|| TokenError::Failed("Integration connection not found".to_string()))?;
+    let now = now_ms();
+    if !needs_refresh(&connection, now, margin_ms) {
+        return Ok(connection.access_token.clone().unwrap_or_default());
+    }
+
+    let refresh_token = connection
+        .refresh_token
+        .clone()
+        .filter(|token| !token.trim().is_empty())
+        .ok_or_else(|| {
+            TokenError::Reauthorize(format!(
+                "Missing refresh token for {} integration",
+                connection.integration_id
+            ))
+        })?;
+    let provider = oauth_provider(provider_id).map_err(TokenError::Failed)?;
+
+    log::info!(
+        "[oauth] refreshing access token: connection_id={} integration_id={} expires_at={} now={}",
+        connection.id,
+        connection.integration_id,
+        connection.expires_at.unwrap_or(0),
+        now
+    );
+    let refreshed = match refresh_access_token(&provider, &refresh_token) {
+        Ok(refreshed) => refreshed,
+        Err(err) => {
+            log::warn!(
+                "[oauth] refresh failed: connection_id={} integration_id={} error={}",
+                connection.id,
+                connection.integration_id,
+                err
+            );
+            if let TokenError::Reauthorize(message) = &err {
+                let _ = db.update_integration_connection(&UpdateIntegrationConnectionInput {
+                    id: connection.id.clone(),
+                    account_label: None,
+                    status: Some("error".to_string()),
+                    auth_type: None,
+                    access_token: None,
+                    refresh_token: None,
+                    scopes: None,
+                    expires_at: None,
+                    last_error: Some(message.clone()),
+                    last_sync_at: None,
+                });
+            }
+            return Err(err);
+        }
+    };
+
+    let expires_at = refreshed.expires_in.map(|seconds| now + seconds * 1000);
+    let _ = db.update_integration_connection(&UpdateIntegrationConnectionInput {
+        id: connection.id.clone(),
+        account_label: None,
+        status: Some("connected".to_string()),
+        auth_type: None,
+        access_token: Some(refreshed.access_token.clone()),
+        refresh_token: Some(refreshed.refresh_token.unwrap_or(refresh_token)),
+        scopes: None,
+        expires_at,
+        last_error: Some(String::new()),
+        last_sync_at: None,
+    });
+    log::info!(
+        "[oauth] refresh succeeded: connection_id={} integration_id={} expires_at={}",
+        connection.id,
+        connection.integration_id,
+        expires_at.unwrap_or(0)
+    );
+    Ok(refreshed.access_token)
+}
+
+/// Renews OAuth tokens shortly before they expire.
+#[derive(Clone)]
+pub struct TokenRefresher {
+    db: Db,
+}
+
+impl TokenRefresher {
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+
+    pub fn start(&self) {
+        let refresher = self.clone();
+        std::thread::spawn(move || loop {
+            refresher.tick();
+            std::thread::sleep(TICK_INTERVAL);
+        });
+    }
+
+    fn tick(&self) {
+        let connections = match self.db.get_integration_connections() {
+            Ok(connections) => connections,
+            Err(err) => {
+                log::error!("[oauth] failed to load connections: {}", err);
+                return;
+            }
+        };
+        let now = now_ms();
+        for connection in connections {
+            let refreshable = connection.auth_type == "oauth2"
+                && connection.status == "connected"
+                && connection.expires_at.unwrap_or(0) > 0
+                && connection
+                    .refresh_token
+                    .as_deref()
+                    .map(|token| !token.trim().is_empty())
+                    .unwrap_or(false);
+            if !refreshable || !needs_refresh(&connection, now, BACKGROUND_MARGIN_MS) {
+                continue;
+            }
+            let Some(plugin) = integration_registry().get(&connection.integration_id) else {
+                continue;
+            };
+            let provider_id = plugin.manifest().provider;
+            if let Err(err) =
+                refresh_connection(&self.db, &connection.id, &provider_id, BACKGROUND_MARGIN_MS)
+            {
+                log::warn!(
+                    "[oauth] background refresh failed for {}: {}",
+                    connection.id,
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(access_token: Option<&str>, expires_at: Option<i64>) -> IntegrationConnection {
+        IntegrationConnection {
+            id: "conn".to_string(),
+            integration_id: "gmail".to_string(),
+            account_label: None,
+            status: "connected".to_string(),
+            auth_type: "oauth2".to_string(),
+            access_token: access_token.map(str::to_string),
+            refresh_token: Some("refresh".to_string()),
+            scopes: None,
+            expires_at,
+            last_error: None,
+            last_sync_at: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn refresh_is_needed_for_missing_or_expiring_tokens() {
+        let now = 1_000_000;
+        assert!(needs_refresh(&connection(None, None), now, USE_MARGIN_MS));
+        assert!(needs_refresh(
+            &connection(Some("token"), Some(now + 30_000)),
+            now,
+            USE_MARGIN_MS
+        ));
+        assert!(!needs_refresh(
+            &connection(Some("token"), Some(now + 120_000)),
+            now,
+            USE_MARGIN_MS
+        ));
+        assert!(needs_refresh(
+            &connection(Some("token"), Some(now + 120_000)),
+            now,
+            BACKGROUND_MARGIN_MS
+        ));
+        assert!(!needs_refresh(
+            &connection(Some("token"), None),
+            now,
+            BACKGROUND_MARGIN_MS
+        ));
+    }
+
+    #[test]
+    fn refresh_locks_are_per_connection() {
+        let first = refresh_lock("conn-a");
+        let _guard = first.lock().unwrap();
+        assert!(Arc::ptr_eq(&first, &refresh_lock("conn-a")));
+        let other = refresh_lock("conn-b");
+        assert!(other.try_lock().is_ok());
+    }
+}
@@ -0,0 +1,239 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike};
+
+/// How far ahead `next_after` searches before giving up on a schedule that never fires
+/// (e.g. "0 0 31 2 *").
+const MAX_LOOKAHEAD_DAYS: u32 = 366 * 5;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A standard five-field cron expression: minute, hour, day of month, month, day of week.
+/// Supports `*`, lists, ranges, steps, month and weekday names, and the `@hourly`,
+/// `@daily`, `@weekly`, `@monthly` and `@yearly` shorthands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let expanded = match expression.to_ascii_lowercase().as_str() {
+            "@hourly" => "0 * * * *".to_string(),
+            "@daily" | "@midnight" => "0 0 * * *".to_string(),
+            "@weekly" => "0 0 * * 0".to_string(),
+            "@monthly" => "0 0 1 * *".to_string(),
+            "@yearly" | "@annually" => "0 0 1 1 *".to_string(),
+            _ => expression.to_string(),
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "Invalid cron expression '{expression}': expected 5 fields (minute hour day month weekday)"
+            ));
+        }
+
+        let minutes = parse_field(fields[0], 0, 59, None)?;
+        let hours = parse_field(fields[1], 0, 23, None)?;
+        let days_of_month = parse_field(fields[2], 1, 31, None)?;
+        let months = parse_field(fields[3], 1, 12, Some((&MONTH_NAMES, 1)))?;
+        let mut days_of_week = parse_field(fields[4], 0, 7, Some((&WEEKDAY_NAMES, 0)))?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            day_of_month_restricted: !fields[2].starts_with('*'),
+            day_of_week_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    /// Returns the first matching minute strictly after `after`, in the same time zone.
+    /// Local times skipped by a DST change are passed over.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let start = after
+            .naive_local()
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(Duration::minutes(1))?;
+        let timezone = after.timezone();
+
+        let mut date = start.date();
+        for _ in 0..MAX_LOOKAHEAD_DAYS {
+            if self.matches_date(date) {
+                let is_start_day = date == start.date();
+                let first_hour = if is_start_day { start.hour() } else { 0 };
+                for hour in first_hour..24 {
+                    if !has_bit(self.hours, hour) {
+                        continue;
+                    }
+                    let first_minute = if is_start_day && hour == start.hour() {
+                        start.minute()
+                    } else {
+                        0
+                    };
+                    for minute in first_minute..60 {
+                        if !has_bit(self.minutes, minute) {
+                            continue;
+                        }
+                        let naive = date.and_hms_opt(hour, minute, 0)?;
+                        if let Some(candidate) = timezone.from_local_datetime(&naive).earliest() {
+                            return Some(candidate);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !has_bit(self.months, date.month()) {
+            return false;
+        }
+        let day_of_month = has_bit(self.days_of_month, date.day());
+        let day_of_week = has_bit(self.days_of_week, date.weekday().num_days_from_sunday());
+        // Classic cron semantics: when both day fields are restricted, either may match.
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+fn has_bit(mask: u64, value: u32) -> bool {
+    mask & (1u64 << value) != 0
+}
+
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: Option<(&[&str], u32)>,
+) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("Invalid step in cron field '{field}'"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, field, names)?,
+                parse_value(end, field, names)?,
+            )
+        } else {
+            let value = parse_value(range, field, names)?;
+            // "5/15" means every 15 starting at 5.
+            if part.contains('/') {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "Cron field '{field}' is out of range ({min}-{max})"
+            ));
+        }
+
+        let mut value = start;
+        while value <= end {
+            mask |= 1u64 << value;
+            value += step;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, field: &str, names: Option<(&[&str], u32)>) -> Result<u32, String> {
+    if let Ok(number) = value.parse::<u32>() {
+        return Ok(number);
+    }
+    let lowered = value.to_ascii_lowercase();
+    names
+        .and_then(|(names, offset)| {
+            names
+                .iter()
+                .position(|name| *name == lowered)
+                .map(|index| index as u32 + offset)
+        })
+        .ok_or_else(|| format!("Invalid value '{value}' in cron field '{field}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn weekday_morning_schedule_skips_weekends() {
+        let schedule = CronSchedule::parse("0 8 * * mon-fri").unwrap();
+        // Friday 2024-03-01 09:00 -> Monday 2024-03-04 08:00
+        let next = schedule.next_after(&at("2024-03-01T09:00:00Z")).unwrap();
+        assert_eq!(next, at("2024-03-04T08:00:00Z"));
+        let next = schedule.next_after(&at("2024-03-04T07:59:30Z")).unwrap();
+        assert_eq!(next, at("2024-03-04T08:00:00Z"));
+    }
+
+    #[test]
+    fn steps_lists_and_shorthands_parse() {
+        let schedule = CronSchedule::parse("*/15 9-17 * * *").unwrap();
+        let next = schedule.next_after(&at("2024-03-01T09:14:00Z")).unwrap();
+        assert_eq!(next, at("2024-03-01T09:15:00Z"));
+        let next = schedule.next_after(&at("2024-03-01T17:45:00Z")).unwrap();
+        assert_eq!(next, at("2024-03-02T09:00:00Z"));
+
+        let schedule = CronSchedule::parse("@monthly").unwrap();
+        let next = schedule.next_after(&at("2024-01-15T00:00:00Z")).unwrap();
+        assert_eq!(next, at("2024-02-01T00:00:00Z"));
+
+        let sundays = CronSchedule::parse("30 6 * * 7").unwrap();
+        let next = sundays.next_after(&at("2024-03-01T00:00:00Z")).unwrap();
+        assert_eq!(next, at("2024-03-03T06:30:00Z"));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        assert!(CronSchedule::parse("0 8 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 8 * * funday").is_err());
+        assert!(CronSchedule::parse("0 0 31 2 *")
+            .unwrap()
+            .next_after(&at("2024-01-01T00:00:00Z"))
+            .is_none());
+    }
+}
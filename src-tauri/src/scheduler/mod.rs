@@ -0,0 +1,224 @@
+mod cron;
+mod runner;
+
+pub use cron::CronSchedule;
+pub use runner::{UnattendedRunRequest, UnattendedRunner};
+
+use crate::agent::UnattendedApprovalPolicy;
+use crate::db::{ScheduledJob, ScheduledJobOperations, ScheduledJobRun};
+use crate::events::{
+    AgentEvent, EVENT_SCHEDULED_JOB_RUN_COMPLETED, EVENT_SCHEDULED_JOB_RUN_STARTED,
+};
+use chrono::{Local, TimeZone, Utc};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const APPROVAL_POLICY_DENY: &str = "deny";
+pub const APPROVAL_POLICY_ALLOW: &str = "allow";
+pub const APPROVAL_POLICY_ASK: &str = "ask";
+pub const RUN_TRIGGER_SCHEDULE: &str = "schedule";
+pub const RUN_TRIGGER_MANUAL: &str = "manual";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Maps a stored approval policy to the controller setting. `ask` keeps the normal
+/// approval prompt, which times out if nobody answers.
+pub fn parse_approval_policy(value: &str) -> Result<Option<UnattendedApprovalPolicy>, String> {
+    match value {
+        APPROVAL_POLICY_DENY => Ok(Some(UnattendedApprovalPolicy::Deny)),
+        APPROVAL_POLICY_ALLOW => Ok(Some(UnattendedApprovalPolicy::Allow)),
+        APPROVAL_POLICY_ASK => Ok(None),
+        other => Err(format!(
+            "Invalid approval policy '{other}': expected deny, allow or ask"
+        )),
+    }
+}
+
+/// Next fire time in ms after `after_ms`, evaluated in the local time zone.
+pub fn next_run_at(schedule: &str, after_ms: i64) -> Result<Option<i64>, String> {
+    let schedule = CronSchedule::parse(schedule)?;
+    let after = Local
+        .timestamp_millis_opt(after_ms)
+        .single()
+        .ok_or_else(|| "Invalid timestamp".to_string())?;
+    Ok(schedule
+        .next_after(&after)
+        .map(|next| next.timestamp_millis()))
+}
+
+/// Runs scheduled jobs on a background thread. Jobs missed while the app was closed run
+/// once on the first poll, then continue on their schedule.
+#[derive(Clone)]
+pub struct Scheduler {
+    runner: UnattendedRunner,
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Scheduler {
+    pub fn new(runner: UnattendedRunner) -> Self {
+        Self {
+            runner,
+            running: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn start(&self) {
+        match self.runner.db().fail_interrupted_scheduled_job_runs() {
+            Ok(count) if count > 0 => {
+                log::warn!("[scheduler] marked {} interrupted runs as failed", count);
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("[scheduler] failed to clean up interrupted runs: {}", err),
+        }
+
+        let scheduler = self.clone();
+        std::thread::spawn(move || loop {
+            scheduler.tick();
+            std::thread::sleep(POLL_INTERVAL);
+        });
+    }
+
+    fn tick(&self) {
+        let now_ms = Utc::now().timestamp_millis();
+        if let Ok(jobs) = self.runner.db().get_scheduled_jobs() {
+            for job in jobs
+                .iter()
+                .filter(|job| job.enabled && job.next_run_at.is_none())
+            {
+                self.reschedule(job, now_ms);
+            }
+        }
+
+        let due = match self.runner.db().get_due_scheduled_jobs(now_ms) {
+            Ok(due) => due,
+            Err(err) => {
+                log::error!("[scheduler] failed to load due jobs: {}", err);
+                return;
+            }
+        };
+
+        for job in due {
+            // Advance first so a slow or failing run never fires twice for the same slot.
+            self.reschedule(&job, now_ms);
+            if let Err(err) = self.spawn_run(job.clone(), RUN_TRIGGER_SCHEDULE) {
+                log::warn!("[scheduler] skipped job {} ({}): {}", job.id, job.name, err);
+            }
+        }
+    }
+
+    fn reschedule(&self, job: &ScheduledJob, now_ms: i64) {
+        let next = next_run_at(&job.schedule, now_ms).unwrap_or_else(|err| {
+            log::error!(
+                "[scheduler] job {} has an invalid schedule: {}",
+                job.id,
+                err
+            );
+            None
+        });
+        if let Err(err) = self.runner.db().set_scheduled_job_next_run(&job.id, next) {
+            log::error!(
+                "[scheduler] failed to store next run for job {}: {}",
+                job.id,
+                err
+            );
+        }
+    }
+
+    pub fn run_now(&self, job_id: &str) -> Result<ScheduledJobRun, String> {
+        let job = self
+            .runner
+            .db()
+            .get_scheduled_job_by_id(job_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Scheduled job not found: {job_id}"))?;
+        self.spawn_run(job, RUN_TRIGGER_MANUAL)
+    }
+
+    fn spawn_run(&self, job: ScheduledJob, trigger: &str) -> Result<ScheduledJobRun, String> {
+        if !self.running.lock().unwrap().insert(job.id.clone()) {
+            return Err("A run of this job is still in progress".to_string());
+        }
+
+        let run = match self.runner.db().start_scheduled_job_run(&job.id, trigger) {
+            Ok(run) => run,
+            Err(err) => {
+                self.running.lock().unwrap().remove(&job.id);
+                return Err(err.to_string());
+            }
+        };
+        self.publish_run(EVENT_SCHEDULED_JOB_RUN_STARTED, &job, &run);
+
+        let scheduler = self.clone();
+        let started = run.clone();
+        std::thread::spawn(move || {
+            scheduler.execute_run(&job, run);
+            scheduler.running.lock().unwrap().remove(&job.id);
+        });
+        Ok(started)
+    }
+
+    fn execute_run(&self, job: &ScheduledJob, mut run: ScheduledJobRun) {
+        log::info!(
+            "[scheduler] running job {} ({}) trigger={}",
+            job.id,
+            job.name,
+            run.triggered_by
+        );
+
+        let request = UnattendedRunRequest {
+            conversation_id: job.conversation_id.clone(),
+            conversation_name: job.name.clone(),
+            prompt: job.prompt.clone(),
+            provider: job.provider.clone(),
+            model: job.model.clone(),
+            custom_backend_id: job.custom_backend_id.clone(),
+            system_prompt: job.system_prompt.clone(),
+            approval_policy: job.approval_policy.clone(),
+        };
+        match self.runner.run(request) {
+            Ok(result) => {
+                run.status = result.status().to_string();
+                run.output_preview = result.output_preview();
+                run.conversation_id = Some(result.conversation_id);
+                run.message_id = Some(result.message_id);
+                run.error = result.outcome.error;
+            }
+            Err(err) => {
+                run.status = "failed".to_string();
+                run.error = Some(err);
+            }
+        }
+        run.finished_at = Some(Utc::now().timestamp_millis());
+
+        if let Some(error) = run.error.as_deref() {
+            log::warn!(
+                "[scheduler] job {} ({}) failed: {}",
+                job.id,
+                job.name,
+                error
+            );
+        }
+        if let Err(err) = self.runner.db().finish_scheduled_job_run(&run) {
+            log::error!("[scheduler] failed to record run {}: {}", run.id, err);
+        }
+        self.publish_run(EVENT_SCHEDULED_JOB_RUN_COMPLETED, job, &run);
+    }
+
+    fn publish_run(&self, event_type: &str, job: &ScheduledJob, run: &ScheduledJobRun) {
+        let timestamp_ms = Utc::now().timestamp_millis();
+        self.runner
+            .event_bus()
+            .publish(AgentEvent::new_with_timestamp(
+                event_type,
+                json!({
+                    "job_id": job.id,
+                    "job_name": job.name,
+                    "run": run,
+                    "timestamp_ms": timestamp_ms
+                }),
+                timestamp_ms,
+            ));
+    }
+}
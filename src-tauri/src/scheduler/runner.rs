@@ -0,0 +1,154 @@
+use super::parse_approval_policy;
+use crate::commands::{start_agent_run, AgentRunOptions, AgentRunOutcome, AgentSendMessagePayload};
+use crate::db::{ConversationOperations, Db};
+use crate::events::{AgentEvent, EventBus, EVENT_CONVERSATION_UPDATED};
+use crate::tools::{ApprovalStore, ToolRegistry};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+const OUTPUT_PREVIEW_MAX_CHARS: usize = 500;
+
+/// An agent run nobody started from the chat UI, e.g. a scheduled job or a trigger.
+#[derive(Debug, Clone)]
+pub struct UnattendedRunRequest {
+    /// Conversation to post into; a new one named `conversation_name` is created when unset.
+    pub conversation_id: Option<String>,
+    pub conversation_name: String,
+    pub prompt: String,
+    pub provider: String,
+    pub model: String,
+    pub custom_backend_id: Option<String>,
+    pub system_prompt: Option<String>,
+    pub approval_policy: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnattendedRunResult {
+    pub conversation_id: String,
+    pub message_id: String,
+    pub outcome: AgentRunOutcome,
+}
+
+impl UnattendedRunResult {
+    pub fn status(&self) -> &'static str {
+        if self.outcome.error.is_some() {
+            "failed"
+        } else if self.outcome.cancelled {
+            "cancelled"
+        } else {
+            "succeeded"
+        }
+    }
+
+    pub fn output_preview(&self) -> Option<String> {
+        let trimmed = self.outcome.response.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+        if trimmed.chars().count() <= OUTPUT_PREVIEW_MAX_CHARS {
+            return Some(trimmed.to_string());
+        }
+        let mut preview: String = trimmed.chars().take(OUTPUT_PREVIEW_MAX_CHARS).collect();
+        preview.push('…');
+        Some(preview)
+    }
+}
+
+/// Starts agent runs through the same path as the chat command and blocks until they finish.
+#[derive(Clone)]
+pub struct UnattendedRunner {
+    db: Db,
+    event_bus: EventBus,
+    tool_registry: ToolRegistry,
+    approvals: ApprovalStore,
+}
+
+impl UnattendedRunner {
+    pub fn new(
+        db: Db,
+        event_bus: EventBus,
+        tool_registry: ToolRegistry,
+        approvals: ApprovalStore,
+    ) -> Self {
+        Self {
+            db,
+            event_bus,
+            tool_registry,
+            approvals,
+        }
+    }
+
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    pub fn event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    /// Errors only when the run could not start; failures inside the run are reported
+    /// through the outcome.
+    pub fn run(&self, request: UnattendedRunRequest) -> Result<UnattendedRunResult, String> {
+        let unattended_approval = parse_approval_policy(&request.approval_policy)?;
+        let conversation_id = self.prepare_conversation(&request)?;
+        let payload = AgentSendMessagePayload {
+            conversation_id: Some(conversation_id),
+            model: request.model,
+            provider: request.provider,
+            system_prompt: request.system_prompt,
+            content: request.prompt,
+            attachments: Vec::new(),
+            user_message_id: None,
+            assistant_message_id: None,
+            custom_backend_id: request.custom_backend_id,
+            stream: None,
+        };
+        let (started, worker) = start_agent_run(
+            &self.db,
+            &self.event_bus,
+            &self.tool_registry,
+            &self.approvals,
+            payload,
+            AgentRunOptions {
+                unattended_approval,
+            },
+        )?;
+        let outcome = worker.join().unwrap_or_else(|_| AgentRunOutcome {
+            error: Some("Agent worker panicked".to_string()),
+            ..AgentRunOutcome::default()
+        });
+        Ok(UnattendedRunResult {
+            conversation_id: started.conversation_id,
+            message_id: started.assistant_message_id,
+            outcome,
+        })
+    }
+
+    fn prepare_conversation(&self, request: &UnattendedRunRequest) -> Result<String, String> {
+        if let Some(conversation_id) = request.conversation_id.clone() {
+            return Ok(conversation_id);
+        }
+
+        let conversation_id = Uuid::new_v4().to_string();
+        ConversationOperations::get_or_create_conversation(&self.db, &conversation_id)
+            .map_err(|e| e.to_string())?;
+        ConversationOperations::update_conversation_name(
+            &self.db,
+            &conversation_id,
+            &request.conversation_name,
+        )
+        .map_err(|e| e.to_string())?;
+        let timestamp_ms = Utc::now().timestamp_millis();
+        self.event_bus.publish(AgentEvent::new_with_timestamp(
+            EVENT_CONVERSATION_UPDATED,
+            json!({
+                "conversation_id": conversation_id,
+                "name": request.conversation_name,
+                "timestamp_ms": timestamp_ms
+            }),
+            timestamp_ms,
+        ));
+        Ok(conversation_id)
+    }
+}
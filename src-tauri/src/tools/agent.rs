@@ -0,0 +1,68 @@
+use crate::db::Db;
+use crate::tools::{
+    ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub const DELEGATE_TOOL_NAME: &str = "agent.delegate";
+
+pub fn register_agent_tools(registry: &mut ToolRegistry, _db: Db) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: DELEGATE_TOOL_NAME.to_string(),
+        description: "Hand an isolated subtask to a sub-agent with its own turn budget, e.g. \"summarize each of these threads\". The sub-agent only sees the task text and context you pass, can only use the listed tools, and returns a compact summary.".to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {
+                "task": {
+                    "type": "string",
+                    "description": "What the sub-agent should do and what it should return."
+                },
+                "context": {
+                    "type": "string",
+                    "description": "Facts from this conversation the sub-agent needs (ids, paths, constraints)."
+                },
+                "tools": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Tool names or globs the sub-agent may use, e.g. [\"gmail.*\"]. Defaults to none."
+                },
+                "max_turns": { "type": "integer", "minimum": 1, "maximum": 15 },
+                "max_tool_calls_per_step": { "type": "integer", "minimum": 1, "maximum": 10 },
+                "max_result_chars": { "type": "integer", "minimum": 200, "maximum": 8000 }
+            },
+            "required": ["task"],
+            "additionalProperties": false
+        }),
+        result_schema: json!({
+            "type": "object",
+            "properties": {
+                "session_id": { "type": "string" },
+                "success": { "type": "boolean" },
+                "result": { "type": "string" },
+                "truncated": { "type": "boolean" },
+                "needs_input": { "type": "boolean" },
+                "tool_calls": { "type": "integer" },
+                "tools": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["session_id", "success", "result"],
+            "additionalProperties": false
+        }),
+        requires_approval: false,
+        result_mode: ToolResultMode::Inline,
+    };
+
+    // The controller runs delegation itself because it owns the LLM client; this handler only
+    // fires if the tool is invoked outside an agent run.
+    let handler = Arc::new(move |_args: Value, _ctx: ToolExecutionContext| {
+        Err(ToolError::new(
+            "agent.delegate can only run inside an agent conversation",
+        ))
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: None,
+    })
+}
@@ -0,0 +1,446 @@
+use crate::db::{CreateToolApprovalRuleInput, ToolApprovalArgCondition, ToolApprovalRule};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use uuid::Uuid;
+
+pub const RULE_ACTION_ALLOW: &str = "allow";
+pub const RULE_ACTION_ASK: &str = "ask";
+pub const RULE_ACTION_DENY: &str = "deny";
+
+pub const RULE_SCOPE_GLOBAL: &str = "global";
+pub const RULE_SCOPE_CONVERSATION: &str = "conversation";
+pub const RULE_SCOPE_SESSION: &str = "session";
+
+const CONDITION_OPS: [&str; 6] = [
+    "equals",
+    "not_equals",
+    "glob",
+    "not_glob",
+    "regex",
+    "not_regex",
+];
+
+/// Compiled patterns are kept until the cache grows past this, then rebuilt.
+const MAX_CACHED_PATTERNS: usize = 512;
+
+static APP_SESSION_ID: OnceLock<String> = OnceLock::new();
+static PATTERN_CACHE: OnceLock<Mutex<HashMap<String, Option<Regex>>>> = OnceLock::new();
+
+/// Identifier of the current app run. Session-scoped grants only apply while it matches.
+pub fn app_session_id() -> &'static str {
+    APP_SESSION_ID.get_or_init(|| Uuid::new_v4().to_string())
+}
+
+pub fn validate_rule_input(input: &CreateToolApprovalRuleInput) -> Result<(), String> {
+    if input.tool_pattern.trim().is_empty() {
+        return Err("tool_pattern is required".to_string());
+    }
+    match input.action.as_str() {
+        RULE_ACTION_ALLOW | RULE_ACTION_ASK | RULE_ACTION_DENY => {}
+        other => {
+            return Err(format!(
+                "Invalid rule action: {other}. Expected allow, ask, or deny."
+            ))
+        }
+    }
+    match input.scope.as_str() {
+        RULE_SCOPE_GLOBAL | RULE_SCOPE_SESSION => {}
+        RULE_SCOPE_CONVERSATION => {
+            if input
+                .conversation_id
+                .as_deref()
+                .map(str::trim)
+                .unwrap_or("")
+                .is_empty()
+            {
+                return Err("Conversation-scoped rules require conversation_id".to_string());
+            }
+        }
+        other => {
+            return Err(format!(
+                "Invalid rule scope: {other}. Expected global, conversation, or session."
+            ))
+        }
+    }
+    for condition in &input.arg_conditions {
+        if condition.arg.trim().is_empty() {
+            return Err("Rule conditions require an arg name".to_string());
+        }
+        if !CONDITION_OPS.contains(&condition.op.as_str()) {
+            return Err(format!(
+                "Invalid condition op: {}. Expected one of {}.",
+                condition.op,
+                CONDITION_OPS.join(", ")
+            ));
+        }
+        if condition.op.ends_with("regex") {
+            Regex::new(&condition.value)
+                .map_err(|err| format!("Invalid regex for '{}': {err}", condition.arg))?;
+        }
+    }
+    Ok(())
+}
+
+/// Picks the rule that decides a tool call. Deny beats ask beats allow, so a narrow
+/// "always ask" rule can carve an exception out of a broad grant; ties go to the newest rule.
+/// Path arguments are compared relative to `vault_root`, the way the file tools resolve them.
+pub fn find_matching_rule<'a>(
+    rules: &'a [ToolApprovalRule],
+    tool_name: &str,
+    args: &Value,
+    conversation_id: Option<&str>,
+    now_ms: i64,
+    vault_root: Option<&Path>,
+) -> Option<&'a ToolApprovalRule> {
+    rules
+        .iter()
+        .filter(|rule| rule_is_active(rule, conversation_id, now_ms))
+        .filter(|rule| glob_matches(&rule.tool_pattern, tool_name))
+        .filter(|rule| {
+            rule.arg_conditions
+                .iter()
+                .all(|condition| condition_matches(condition, args, vault_root))
+        })
+        .max_by_key(|rule| (action_rank(&rule.action), rule.created_at))
+}
+
+fn rule_is_active(rule: &ToolApprovalRule, conversation_id: Option<&str>, now_ms: i64) -> bool {
+    if rule
+        .expires_at
+        .map(|value| value <= now_ms)
+        .unwrap_or(false)
+    {
+        return false;
+    }
+    match rule.scope.as_str() {
+        RULE_SCOPE_GLOBAL => true,
+        RULE_SCOPE_CONVERSATION => {
+            conversation_id.is_some() && rule.conversation_id.as_deref() == conversation_id
+        }
+        RULE_SCOPE_SESSION => rule.session_id.as_deref() == Some(app_session_id()),
+        _ => false,
+    }
+}
+
+fn action_rank(action: &str) -> u8 {
+    match action {
+        RULE_ACTION_DENY => 3,
+        RULE_ACTION_ASK => 2,
+        RULE_ACTION_ALLOW => 1,
+        _ => 0,
+    }
+}
+
+fn condition_matches(
+    condition: &ToolApprovalArgCondition,
+    args: &Value,
+    vault_root: Option<&Path>,
+) -> bool {
+    let Some(value) = lookup_arg(args, &condition.arg) else {
+        return false;
+    };
+    let candidates = match value {
+        Value::Array(items) => items.iter().filter_map(value_as_text).collect::<Vec<_>>(),
+        other => value_as_text(other).into_iter().collect(),
+    };
+    // A path that cannot be normalized matches nothing, so it never satisfies an allow rule.
+    let candidates = if is_path_arg(&condition.arg) {
+        let normalized = candidates
+            .iter()
+            .map(|candidate| normalize_path_candidate(candidate, vault_root))
+            .collect::<Option<Vec<_>>>();
+        let Some(normalized) = normalized else {
+            return false;
+        };
+        normalized
+    } else {
+        candidates
+    };
+
+    candidates
+        .iter()
+        .any(|candidate| match condition.op.as_str() {
+            "equals" => candidate.eq_ignore_ascii_case(&condition.value),
+            "not_equals" => !candidate.eq_ignore_ascii_case(&condition.value),
+            "glob" => glob_matches(&condition.value, candidate),
+            "not_glob" => !glob_matches(&condition.value, candidate),
+            "regex" => regex_matches(&condition.value, candidate),
+            "not_regex" => !regex_matches(&condition.value, candidate),
+            _ => false,
+        })
+}
+
+fn lookup_arg<'a>(args: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(args, |current, segment| current.get(segment))
+}
+
+fn value_as_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+fn is_path_arg(arg: &str) -> bool {
+    let name = arg.rsplit('.').next().unwrap_or(arg);
+    matches!(name, "path" | "paths" | "folder")
+        || name.ends_with("_path")
+        || name.ends_with("_paths")
+        || name.ends_with("_folder")
+}
+
+/// Rewrites a path argument to the vault-relative form globs are written against: drops
+/// `./` and empty segments and strips the vault root from absolute paths. Returns `None`
+/// for `..` segments and absolute paths outside the vault, which the file tools refuse.
+fn normalize_path_candidate(candidate: &str, vault_root: Option<&Path>) -> Option<String> {
+    let candidate = candidate.trim();
+    let relative = if Path::new(candidate).is_absolute() {
+        let relative = Path::new(candidate).strip_prefix(vault_root?).ok()?;
+        relative.to_str()?.to_string()
+    } else {
+        candidate.to_string()
+    };
+    let mut segments = Vec::new();
+    for segment in relative.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            other => segments.push(other),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+/// Compiles `source` once and reuses it; invalid patterns are cached as `None`.
+fn cached_regex(source: &str) -> Option<Regex> {
+    let cache = PATTERN_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap();
+    if let Some(regex) = cache.get(source) {
+        return regex.clone();
+    }
+    if cache.len() >= MAX_CACHED_PATTERNS {
+        cache.clear();
+    }
+    let regex = Regex::new(source).ok();
+    cache.insert(source.to_string(), regex.clone());
+    regex
+}
+
+fn regex_matches(pattern: &str, candidate: &str) -> bool {
+    cached_regex(pattern)
+        .map(|regex| regex.is_match(candidate))
+        .unwrap_or(false)
+}
+
+/// Glob matching where `*` stays within one path segment, `**` crosses segments and
+/// `?` matches a single character.
+pub fn glob_matches(pattern: &str, candidate: &str) -> bool {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                } else {
+                    regex.push_str("[^/]*");
+                }
+            }
+            '?' => regex.push_str("[^/]"),
+            other => regex.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    regex.push('$');
+    cached_regex(&regex)
+        .map(|regex| regex.is_match(candidate))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{app_session_id, find_matching_rule, glob_matches};
+    use crate::db::{ToolApprovalArgCondition, ToolApprovalRule};
+    use serde_json::json;
+    use std::path::Path;
+
+    fn rule(
+        id: &str,
+        tool_pattern: &str,
+        action: &str,
+        conditions: Vec<(&str, &str, &str)>,
+    ) -> ToolApprovalRule {
+        ToolApprovalRule {
+            id: id.to_string(),
+            name: None,
+            tool_pattern: tool_pattern.to_string(),
+            arg_conditions: conditions
+                .into_iter()
+                .map(|(arg, op, value)| ToolApprovalArgCondition {
+                    arg: arg.to_string(),
+                    op: op.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+            action: action.to_string(),
+            scope: "global".to_string(),
+            conversation_id: None,
+            session_id: None,
+            expires_at: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn glob_segments() {
+        assert!(glob_matches("Drafts/**", "Drafts/a/b.md"));
+        assert!(glob_matches("gmail.*", "gmail.send_message"));
+        assert!(!glob_matches("Drafts/*", "Drafts/a/b.md"));
+        assert!(!glob_matches("Drafts/**", "Notes/Drafts/a.md"));
+    }
+
+    #[test]
+    fn deny_and_ask_rules_take_precedence_over_allow() {
+        let rules = vec![
+            rule(
+                "drafts",
+                "files.write",
+                "allow",
+                vec![("path", "glob", "Drafts/**")],
+            ),
+            rule("gmail-all", "gmail.*", "allow", vec![]),
+            rule(
+                "gmail-external",
+                "gmail.send_message",
+                "ask",
+                vec![("to", "not_glob", "*@example.com")],
+            ),
+            rule(
+                "no-delete",
+                "web.request",
+                "deny",
+                vec![("method", "equals", "DELETE")],
+            ),
+        ];
+
+        let matched = find_matching_rule(
+            &rules,
+            "files.write",
+            &json!({ "path": "Drafts/today.md" }),
+            None,
+            1,
+            None,
+        );
+        assert_eq!(matched.map(|rule| rule.id.as_str()), Some("drafts"));
+        assert!(find_matching_rule(
+            &rules,
+            "files.write",
+            &json!({ "path": "Notes/today.md" }),
+            None,
+            1,
+            None
+        )
+        .is_none());
+
+        let internal = find_matching_rule(
+            &rules,
+            "gmail.send_message",
+            &json!({ "to": ["a@example.com"] }),
+            None,
+            1,
+            None,
+        );
+        assert_eq!(internal.map(|rule| rule.id.as_str()), Some("gmail-all"));
+        let external = find_matching_rule(
+            &rules,
+            "gmail.send_message",
+            &json!({ "to": ["a@example.com", "b@other.org"] }),
+            None,
+            1,
+            None,
+        );
+        assert_eq!(
+            external.map(|rule| rule.id.as_str()),
+            Some("gmail-external")
+        );
+
+        let delete = find_matching_rule(
+            &rules,
+            "web.request",
+            &json!({ "url": "https://api.example.com", "method": "delete" }),
+            None,
+            1,
+            None,
+        );
+        assert_eq!(delete.map(|rule| rule.id.as_str()), Some("no-delete"));
+    }
+
+    #[test]
+    fn expired_and_foreign_session_rules_are_ignored() {
+        let mut expired = rule("expired", "files.*", "allow", vec![]);
+        expired.expires_at = Some(10);
+        let mut other_session = rule("other", "files.*", "allow", vec![]);
+        other_session.scope = "session".to_string();
+        other_session.session_id = Some("previous-run".to_string());
+        let mut current_session = rule("current", "files.*", "allow", vec![]);
+        current_session.scope = "session".to_string();
+        current_session.session_id = Some(app_session_id().to_string());
+
+        let rules = vec![expired, other_session];
+        assert!(find_matching_rule(&rules, "files.write", &json!({}), None, 20, None).is_none());
+
+        let rules = vec![current_session];
+        let matched = find_matching_rule(&rules, "files.write", &json!({}), None, 20, None);
+        assert_eq!(matched.map(|rule| rule.id.as_str()), Some("current"));
+    }
+
+    #[test]
+    fn path_conditions_see_normalized_vault_paths() {
+        let rules = vec![
+            rule(
+                "outside-private",
+                "files.*",
+                "allow",
+                vec![("path", "not_glob", "Private/**")],
+            ),
+            rule(
+                "private",
+                "files.*",
+                "deny",
+                vec![("path", "glob", "Private/**")],
+            ),
+        ];
+        let root = Path::new("/vault");
+        let decide = |path: &str| {
+            find_matching_rule(
+                &rules,
+                "files.read",
+                &json!({ "path": path }),
+                None,
+                1,
+                Some(root),
+            )
+            .map(|rule| rule.id.as_str())
+        };
+
+        assert_eq!(decide("Notes/a.md"), Some("outside-private"));
+        assert_eq!(decide("./Private/secret.md"), Some("private"));
+        assert_eq!(decide("Private//secret.md"), Some("private"));
+        assert_eq!(decide("/vault/Private/secret.md"), Some("private"));
+        assert_eq!(decide("/vault/./Notes/a.md"), Some("outside-private"));
+        assert_eq!(decide("Notes/../Private/secret.md"), None);
+        assert_eq!(decide("/elsewhere/Private/secret.md"), None);
+    }
+}
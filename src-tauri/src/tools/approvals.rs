@@ -1,5 +1,12 @@
-use crate::db::{Db, PreferenceOperations};
+use crate::db::{Db, PreferenceOperations, ToolApprovalRuleOperations};
+use crate::tools::approval_rules::{
+    app_session_id, find_matching_rule, RULE_ACTION_ALLOW, RULE_ACTION_DENY,
+};
+use crate::tools::get_vault_root;
+use serde::Serialize;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub const PREF_TOOL_APPROVAL_OVERRIDES: &str = "plugins.tools.approval_overrides";
 pub const PREF_TOOL_CONVERSATION_APPROVAL_OVERRIDES: &str =
@@ -126,3 +133,153 @@ pub fn set_conversation_tool_approval_override(
     .map_err(|err| format!("Failed to save conversation tool approval overrides: {err}"))?;
     Ok(())
 }
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolApprovalResolution {
+    pub requires_approval: bool,
+    pub denied: bool,
+    pub source: &'static str,
+    pub rule_id: Option<String>,
+}
+
+/// The tool's default, adjusted for tools whose arguments decide whether the call has side
+/// effects.
+fn default_approval_for_call(tool_name: &str, args: &Value, default: bool) -> bool {
+    let flag = |key: &str| args.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    match tool_name {
+        // Saving a draft sends nothing; sending it later goes through gmail.send_draft.
+        "gmail.reply" | "gmail.forward" => default && !flag("draft"),
+        // Clipping writes a note into the vault.
+        "web.fetch" => default || flag("save_to_vault"),
+        _ => default,
+    }
+}
+
+/// Decides how a concrete tool call is approved. Rules are consulted first, then the
+/// conversation override, then the global override, then the tool's own default.
+pub fn resolve_tool_approval(
+    db: &Db,
+    conversation_id: Option<&str>,
+    tool_name: &str,
+    args: &Value,
+    default_requires_approval: bool,
+) -> ToolApprovalResolution {
+    match ToolApprovalRuleOperations::get_tool_approval_rules(db) {
+        Ok(rules) => {
+            let vault_root = get_vault_root(db).ok();
+            if let Some(rule) = find_matching_rule(
+                &rules,
+                tool_name,
+                args,
+                conversation_id,
+                now_ms(),
+                vault_root.as_deref(),
+            ) {
+                return ToolApprovalResolution {
+                    requires_approval: rule.action != RULE_ACTION_ALLOW,
+                    denied: rule.action == RULE_ACTION_DENY,
+                    source: "rule",
+                    rule_id: Some(rule.id.clone()),
+                };
+            }
+        }
+        Err(err) => {
+            log::warn!(
+                "Failed to load tool approval rules for {}: {}",
+                tool_name,
+                err
+            );
+        }
+    }
+
+    if let Some(conversation_id) = conversation_id {
+        match get_conversation_tool_approval_override(db, conversation_id, tool_name) {
+            Ok(Some(value)) => {
+                return ToolApprovalResolution {
+                    requires_approval: value,
+                    denied: false,
+                    source: "conversation_override",
+                    rule_id: None,
+                }
+            }
+            Ok(None) => {}
+            Err(err) => log::warn!(
+                "Failed to load conversation tool approval override for {}: {}",
+                tool_name,
+                err
+            ),
+        }
+    }
+
+    match get_tool_approval_override(db, tool_name) {
+        Ok(Some(value)) => {
+            return ToolApprovalResolution {
+                requires_approval: value,
+                denied: false,
+                source: "global_override",
+                rule_id: None,
+            }
+        }
+        Ok(None) => {}
+        Err(err) => log::warn!(
+            "Failed to load global tool approval override for {}: {}",
+            tool_name,
+            err
+        ),
+    }
+
+    ToolApprovalResolution {
+        requires_approval: default_approval_for_call(tool_name, args, default_requires_approval),
+        denied: false,
+        source: "default",
+        rule_id: None,
+    }
+}
+
+pub fn purge_stale_tool_approval_rules(db: &Db) -> Result<usize, String> {
+    ToolApprovalRuleOperations::delete_stale_tool_approval_rules(db, now_ms(), app_session_id())
+        .map_err(|err| format!("Failed to purge tool approval rules: {err}"))
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|value| value.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_tool_approval;
+    use crate::db::Db;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[test]
+    fn call_arguments_adjust_the_default_approval() {
+        let db_path = std::env::temp_dir().join(format!("approvals-{}.db", Uuid::new_v4()));
+        let mut db = Db::new(db_path.to_str().unwrap()).expect("db init failed");
+        db.run_migrations().expect("db migrations failed");
+
+        for tool in ["gmail.reply", "gmail.forward"] {
+            let resolve = |args| resolve_tool_approval(&db, None, tool, &args, true);
+            assert!(resolve(json!({ "message_id": "m1" })).requires_approval);
+            assert!(resolve(json!({ "message_id": "m1", "draft": false })).requires_approval);
+            assert!(!resolve(json!({ "message_id": "m1", "draft": true })).requires_approval);
+        }
+        let fetch = |args| resolve_tool_approval(&db, None, "web.fetch", &args, false);
+        assert!(!fetch(json!({ "url": "https://example.com/" })).requires_approval);
+        assert!(
+            fetch(json!({ "url": "https://example.com/", "save_to_vault": true }))
+                .requires_approval
+        );
+        let send = resolve_tool_approval(
+            &db,
+            None,
+            "gmail.send_draft",
+            &json!({ "draft": true }),
+            true,
+        );
+        assert!(send.requires_approval);
+    }
+}
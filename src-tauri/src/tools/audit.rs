@@ -0,0 +1,302 @@
+//! Audit trail of side-effecting tool calls. A tool counts as side-effecting when its
+//! metadata requires approval by default, whatever rules or overrides later decide.
+
+use serde_json::{json, Map, Value};
+
+use super::integrations::get_connection;
+use crate::db::{AppendAuditLogInput, AuditLogEntry, AuditLogOperations, Db};
+use crate::integrations::integration_registry;
+
+pub const AUDIT_DECISION_APPROVED: &str = "approved";
+pub const AUDIT_DECISION_MODIFIED: &str = "modified";
+pub const AUDIT_DECISION_AUTO_APPROVED: &str = "auto_approved";
+pub const AUDIT_DECISION_DENIED: &str = "denied";
+pub const AUDIT_DECISION_TIMED_OUT: &str = "timed_out";
+pub const AUDIT_DECISION_CANCELLED: &str = "cancelled";
+
+pub const AUDIT_OUTCOME_SUCCESS: &str = "success";
+pub const AUDIT_OUTCOME_ERROR: &str = "error";
+pub const AUDIT_OUTCOME_NOT_EXECUTED: &str = "not_executed";
+
+const REDACTED: &str = "[redacted]";
+/// Argument keys whose values are never written to the log.
+const SECRET_KEY_PARTS: [&str; 6] = [
+    "password",
+    "secret",
+    "token",
+    "authorization",
+    "api_key",
+    "cookie",
+];
+const CSV_COLUMNS: [&str; 18] = [
+    "id",
+    "created_at",
+    "execution_id",
+    "tool_name",
+    "integration_id",
+    "connection_id",
+    "account_label",
+    "args",
+    "decision",
+    "approved_by",
+    "approval_rule_id",
+    "outcome",
+    "error",
+    "error_kind",
+    "external_resource_id",
+    "conversation_id",
+    "message_id",
+    "duration_ms",
+];
+/// Result fields that identify what a call created or changed, most specific first.
+const RESOURCE_ID_KEYS: [&str; 9] = [
+    "id",
+    "message_id",
+    "event_id",
+    "task_id",
+    "draft_id",
+    "thread_id",
+    "html_url",
+    "url",
+    "path",
+];
+
+/// One tool call as seen by the orchestrator, before connection lookup and redaction.
+pub struct ToolAuditRecord<'a> {
+    pub execution_id: &'a str,
+    pub tool_name: &'a str,
+    pub args: &'a Value,
+    pub decision: &'a str,
+    pub approved_by: Option<&'a str>,
+    pub approval_rule_id: Option<&'a str>,
+    pub outcome: &'a str,
+    pub error: Option<&'a str>,
+    pub error_kind: Option<&'a str>,
+    pub external_resource_id: Option<String>,
+    pub conversation_id: Option<&'a str>,
+    pub message_id: Option<&'a str>,
+    pub duration_ms: i64,
+}
+
+/// Appends `record` to the audit log. Failures are logged and never fail the tool call.
+pub fn record_tool_audit(db: &Db, record: ToolAuditRecord<'_>) -> Option<AuditLogEntry> {
+    let integration_id = integration_for_tool(record.tool_name);
+    let connection = integration_id.as_deref().and_then(|integration_id| {
+        let connection_id = record
+            .args
+            .get("connection_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        get_connection(db, connection_id, integration_id).ok()
+    });
+    let input = AppendAuditLogInput {
+        execution_id: record.execution_id.to_string(),
+        tool_name: record.tool_name.to_string(),
+        integration_id,
+        connection_id: connection.as_ref().map(|c| c.id.clone()),
+        account_label: connection.and_then(|c| c.account_label),
+        args: redact_args(record.args),
+        decision: record.decision.to_string(),
+        approved_by: record.approved_by.map(str::to_string),
+        approval_rule_id: record.approval_rule_id.map(str::to_string),
+        outcome: record.outcome.to_string(),
+        error: record.error.map(str::to_string),
+        error_kind: record.error_kind.map(str::to_string),
+        external_resource_id: record.external_resource_id,
+        conversation_id: record.conversation_id.map(str::to_string),
+        message_id: record.message_id.map(str::to_string),
+        duration_ms: record.duration_ms,
+    };
+    match AuditLogOperations::append_audit_log_entry(db, &input) {
+        Ok(entry) => Some(entry),
+        Err(err) => {
+            log::error!(
+                "[audit] failed to record tool call: tool={} execution_id={} error={}",
+                record.tool_name,
+                record.execution_id,
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Serializes entries for export as `jsonl` (one JSON object per line) or `csv`.
+pub fn export_audit_entries(entries: &[AuditLogEntry], format: &str) -> Result<String, String> {
+    match format {
+        "jsonl" => entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).map_err(|err| err.to_string()))
+            .map(|line| line.map(|line| line + "\n"))
+            .collect(),
+        "csv" => {
+            let mut out = CSV_COLUMNS.join(",") + "\r\n";
+            for entry in entries {
+                let fields = [
+                    entry.id.clone(),
+                    entry.created_at.to_string(),
+                    entry.execution_id.clone(),
+                    entry.tool_name.clone(),
+                    entry.integration_id.clone().unwrap_or_default(),
+                    entry.connection_id.clone().unwrap_or_default(),
+                    entry.account_label.clone().unwrap_or_default(),
+                    entry.args.to_string(),
+                    entry.decision.clone(),
+                    entry.approved_by.clone().unwrap_or_default(),
+                    entry.approval_rule_id.clone().unwrap_or_default(),
+                    entry.outcome.clone(),
+                    entry.error.clone().unwrap_or_default(),
+                    entry.error_kind.clone().unwrap_or_default(),
+                    entry.external_resource_id.clone().unwrap_or_default(),
+                    entry.conversation_id.clone().unwrap_or_default(),
+                    entry.message_id.clone().unwrap_or_default(),
+                    entry.duration_ms.to_string(),
+                ];
+                let row = fields
+                    .iter()
+                    .map(String::as_str)
+                    .map(csv_field)
+                    .collect::<Vec<_>>();
+                out.push_str(&row.join(","));
+                out.push_str("\r\n");
+            }
+            Ok(out)
+        }
+        other => Err(format!(
+            "Unsupported audit log export format '{other}'. Use 'jsonl' or 'csv'."
+        )),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Integration whose tools use the `{prefix}.` namespace of `tool_name`.
+fn integration_for_tool(tool_name: &str) -> Option<String> {
+    let (prefix, _) = tool_name.split_once('.')?;
+    let integration_id = match prefix {
+        "gcal" => "google_calendar",
+        other => other,
+    };
+    integration_registry()
+        .get(integration_id)
+        .map(|_| integration_id.to_string())
+}
+
+/// Copy of `args` with the values of secret-looking keys replaced.
+pub fn redact_args(args: &Value) -> Value {
+    match args {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let lower = key.to_lowercase();
+                    let value = if SECRET_KEY_PARTS.iter().any(|part| lower.contains(part)) {
+                        json!(REDACTED)
+                    } else {
+                        redact_args(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_args).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Best guess at the id of the resource a tool result describes: a top-level id field, or
+/// one of the wrapped object (`{"issue": {...}}`, `{"event": {...}}`).
+pub fn external_resource_id(output: &Value) -> Option<String> {
+    let object = output.as_object()?;
+    let direct = |object: &Map<String, Value>| {
+        RESOURCE_ID_KEYS
+            .iter()
+            .find_map(|key| match object.get(*key) {
+                Some(Value::String(value)) if !value.is_empty() => Some(value.clone()),
+                Some(Value::Number(value)) => Some(value.to_string()),
+                _ => None,
+            })
+    };
+    direct(object).or_else(|| {
+        object
+            .values()
+            .filter_map(|value| value.as_object())
+            .find_map(direct)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_secret_values_recursively() {
+        let args = json!({
+            "url": "https://example.com",
+            "headers": { "Authorization": "Bearer abc", "Accept": "text/html" },
+            "accounts": [{ "password": "hunter2", "user": "ada" }]
+        });
+        let redacted = redact_args(&args);
+        assert_eq!(redacted["url"], "https://example.com");
+        assert_eq!(redacted["headers"]["Authorization"], REDACTED);
+        assert_eq!(redacted["headers"]["Accept"], "text/html");
+        assert_eq!(redacted["accounts"][0]["password"], REDACTED);
+        assert_eq!(redacted["accounts"][0]["user"], "ada");
+    }
+
+    #[test]
+    fn finds_resource_ids_in_common_result_shapes() {
+        assert_eq!(
+            external_resource_id(&json!({ "id": "msg-1", "threadId": "t" })).as_deref(),
+            Some("msg-1")
+        );
+        assert_eq!(
+            external_resource_id(&json!({ "issue": { "number": 7, "url": "https://x/7" } }))
+                .as_deref(),
+            Some("https://x/7")
+        );
+        assert_eq!(
+            external_resource_id(&json!({ "path": "notes/a.md", "bytes": 3 })).as_deref(),
+            Some("notes/a.md")
+        );
+        assert_eq!(external_resource_id(&json!({ "ok": true })), None);
+    }
+
+    #[test]
+    fn csv_export_quotes_fields() {
+        let entry = AuditLogEntry {
+            id: "a1".to_string(),
+            created_at: 1,
+            execution_id: "e1".to_string(),
+            tool_name: "gmail.send".to_string(),
+            integration_id: Some("gmail".to_string()),
+            connection_id: None,
+            account_label: None,
+            args: json!({ "to": "ada@example.com", "subject": "Hi, \"you\"" }),
+            decision: AUDIT_DECISION_APPROVED.to_string(),
+            approved_by: Some("user".to_string()),
+            approval_rule_id: None,
+            outcome: AUDIT_OUTCOME_SUCCESS.to_string(),
+            error: None,
+            error_kind: None,
+            external_resource_id: Some("msg-1".to_string()),
+            conversation_id: None,
+            message_id: None,
+            duration_ms: 12,
+        };
+        let csv = export_audit_entries(std::slice::from_ref(&entry), "csv").unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("a1,1,e1,gmail.send,gmail,,,\"{"));
+        assert!(row.contains(r#"""subject"":""Hi, \""you\"""""#));
+        assert!(row.ends_with(",approved,user,,success,,,msg-1,,,12"));
+
+        let jsonl = export_audit_entries(&[entry.clone(), entry], "jsonl").unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+        assert!(export_audit_entries(&[], "xml").is_err());
+    }
+}
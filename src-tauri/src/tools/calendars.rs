@@ -0,0 +1,1084 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::integrations::{
+    applies_to_series, apply_to_arg_schema, get_connection, parse_time_arg, recurrence_arg_schema,
+    required_str_arg, string_list_arg,
+};
+use super::{
+    ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
+};
+use crate::db::{Db, IntegrationConnection, IntegrationConnectionOperations};
+use crate::integrations::caldav::{CalDavClient, CalendarObject};
+use crate::integrations::icalendar::{
+    self, event_end, event_start, iana_zone, Component, EventTime, Property, RecurrenceRule, Zones,
+};
+use crate::integrations::ics::fetch_calendar;
+
+const DEFAULT_MAX_RESULTS: usize = 250;
+const DEFAULT_RANGE_DAYS: i64 = 30;
+/// Occurrences expanded per calendar before `query` and `max_results` are applied.
+const MAX_OCCURRENCES: usize = 2500;
+
+pub fn register_caldav_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let db_for_calendars = db.clone();
+    let db_for_list = db.clone();
+    let db_for_create = db.clone();
+    let db_for_update = db.clone();
+    let db_for_delete = db.clone();
+
+    let list_calendars = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "caldav.list_calendars".to_string(),
+            description: "List the event calendars of a CalDAV account. 'selected' marks the calendars used when a tool call names none.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema()
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendars": { "type": "array" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = caldav_client(&db_for_calendars, &args)?;
+            let calendars = client
+                .calendars()?
+                .into_iter()
+                .map(|calendar| {
+                    let selected = client.default_calendar_ids.is_empty()
+                        || client.default_calendar_ids.contains(&calendar.id);
+                    json!({
+                        "id": calendar.id,
+                        "summary": calendar.name,
+                        "time_zone": calendar.time_zone,
+                        "color": calendar.color,
+                        "selected": selected
+                    })
+                })
+                .collect::<Vec<_>>();
+            Ok(json!({ "calendars": calendars }))
+        }),
+        preview: None,
+    };
+
+    let list_events = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "caldav.list_events".to_string(),
+            description: "List or search CalDAV events, grouped by calendar. If calendar_ids is omitted, uses the calendars selected in the connection settings (all event calendars if none). Defaults to the next 30 days.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "calendar_id": { "type": "string" },
+                    "calendar_ids": { "type": "array", "items": { "type": "string" } },
+                    "time_min": { "type": "string" },
+                    "time_max": { "type": "string" },
+                    "query": { "type": "string", "description": "Optional. Text to find in the title, description, or location." },
+                    "max_results": { "type": "integer", "minimum": 1, "maximum": 2500 },
+                    "expand_recurring": {
+                        "type": "boolean",
+                        "description": "Optional, default true. List each occurrence of recurring events (with its own id) instead of the series."
+                    }
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendars": { "type": "array" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = caldav_client(&db_for_list, &args)?;
+            let range = time_range_arg(&args)?;
+            let expand = args
+                .get("expand_recurring")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+            let query = query_arg(&args);
+            let max_results = max_results_arg(&args);
+
+            let calendar_ids = match calendar_ids_arg(&args) {
+                Some(ids) => ids,
+                None => default_calendar_ids(&client)?,
+            };
+            let mut grouped = Vec::new();
+            for calendar_id in calendar_ids {
+                let objects = client.events(&calendar_id, Some(range))?;
+                let mut events = objects
+                    .iter()
+                    .flat_map(|object| {
+                        calendar_events(&object.calendar, range, expand, query.as_deref())
+                    })
+                    .collect::<Vec<_>>();
+                events.sort_by_key(|(start_ms, _)| *start_ms);
+                events.truncate(max_results);
+                grouped.push(json!({
+                    "calendar_id": calendar_id,
+                    "events": events.into_iter().map(|(_, event)| event).collect::<Vec<_>>()
+                }));
+            }
+            Ok(json!({ "calendars": grouped }))
+        }),
+        preview: None,
+    };
+
+    let create_event = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "caldav.create_event".to_string(),
+            description: "Create a CalDAV event. Use YYYY-MM-DD for start and end of an all-day event (end is exclusive).".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "calendar_id": {
+                        "type": "string",
+                        "description": "Optional. Calendar id from caldav.list_calendars; defaults to the first selected calendar."
+                    },
+                    "summary": { "type": "string" },
+                    "description": { "type": "string" },
+                    "location": { "type": "string" },
+                    "start": { "type": "string", "description": "RFC 3339, YYYY-MM-DDTHH:MM in time_zone, or YYYY-MM-DD." },
+                    "end": { "type": "string" },
+                    "time_zone": time_zone_schema(),
+                    "attendees": { "type": "array", "items": { "type": "string" } },
+                    "recurrence": recurrence_arg_schema()
+                },
+                "required": ["summary", "start", "end"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = caldav_client(&db_for_create, &args)?;
+            let calendar_id = match optional_str_arg(&args, "calendar_id") {
+                Some(id) => id.to_string(),
+                None => default_calendar_ids(&client)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| ToolError::not_found("The CalDAV account has no event calendars"))?,
+            };
+            let recurrence = recurrence_properties(&args)?;
+            let time_zone = match optional_str_arg(&args, "time_zone") {
+                Some(time_zone) => Some(time_zone.to_string()),
+                // Recurring events keep their wall-clock time across daylight saving changes
+                // only when they carry a time zone.
+                None if !recurrence.is_empty() => client
+                    .calendars()?
+                    .into_iter()
+                    .find(|calendar| calendar.id == calendar_id)
+                    .and_then(|calendar| calendar.time_zone),
+                None => None,
+            };
+
+            let uid = Uuid::new_v4().to_string();
+            let mut event = Component::new("VEVENT");
+            event.push(Property::text("UID", &uid));
+            event.push(Property::new("CREATED", utc_stamp(Utc::now())));
+            let changed = apply_event_fields(&mut event, &args, time_zone.as_deref(), recurrence)?;
+            if !changed.start || !changed.end {
+                return Err(ToolError::validation("Provide both 'start' and 'end'"));
+            }
+            touch_event(&mut event);
+
+            let mut calendar = Component::calendar();
+            calendar.components.push(event);
+            add_time_zones(&mut calendar);
+            client.put(
+                &CalDavClient::new_object_href(&calendar_id, &uid),
+                &calendar,
+                None,
+            )?;
+            let event = &calendar.components[calendar.components.len() - 1];
+            Ok(event_json(event, &uid, None, &Zones::from_calendar(&calendar), true))
+        }),
+        preview: None,
+    };
+
+    let update_event = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "caldav.update_event".to_string(),
+            description: "Update fields on an existing CalDAV event. For an occurrence of a recurring event, apply_to='series' edits the whole series instead of that occurrence.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "event_id": { "type": "string" },
+                    "calendar_id": { "type": "string" },
+                    "summary": { "type": "string" },
+                    "description": { "type": "string" },
+                    "location": { "type": "string" },
+                    "start": { "type": "string" },
+                    "end": { "type": "string" },
+                    "time_zone": time_zone_schema(),
+                    "attendees": { "type": "array", "items": { "type": "string" } },
+                    "recurrence": recurrence_arg_schema(),
+                    "apply_to": apply_to_arg_schema()
+                },
+                "required": ["event_id"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = caldav_client(&db_for_update, &args)?;
+            let (uid, instance) = parse_event_id(required_str_arg(&args, "event_id")?);
+            let instance = instance.filter(|_| !applies_to_series(&args));
+            let recurrence = recurrence_properties(&args)?;
+            if instance.is_some() && !recurrence.is_empty() {
+                return Err(ToolError::validation(
+                    "Recurrence rules belong to the series",
+                )
+                .with_hint("Retry with apply_to 'series'."));
+            }
+            let object = locate_event(&client, &args, &uid)?;
+            let mut calendar = object.calendar.clone();
+            let zones = Zones::from_calendar(&calendar);
+
+            let index = match &instance {
+                None => master_index(&calendar, &uid).ok_or_else(|| event_not_found(&uid))?,
+                Some(instance) => match override_index(&calendar, &uid, instance, &zones) {
+                    Some(index) => index,
+                    None => {
+                        let edited = new_override(&calendar, &uid, instance, &zones)?;
+                        calendar.components.push(edited);
+                        calendar.components.len() - 1
+                    }
+                },
+            };
+            let event = &mut calendar.components[index];
+            let time_zone = optional_str_arg(&args, "time_zone")
+                .map(str::to_string)
+                .or_else(|| event_start(event).and_then(|start| start.tzid().map(str::to_string)));
+            let changed = apply_event_fields(event, &args, time_zone.as_deref(), recurrence)?;
+            if !changed.any {
+                return Err(ToolError::validation(
+                    "Provide at least one field to update (for example summary, start, or end)",
+                ));
+            }
+            touch_event(event);
+            add_time_zones(&mut calendar);
+            client.put(&object.href, &calendar, object.etag.as_deref())?;
+
+            // Added time zones shift the event's position.
+            let zones = Zones::from_calendar(&calendar);
+            let index = match &instance {
+                None => master_index(&calendar, &uid),
+                Some(instance) => override_index(&calendar, &uid, instance, &zones),
+            }
+            .ok_or_else(|| event_not_found(&uid))?;
+            let event = &calendar.components[index];
+            Ok(match &instance {
+                Some(instance) => event_json(
+                    event,
+                    &format!("{uid}_{}", instance_stamp(instance, &zones)),
+                    Some(&uid),
+                    &zones,
+                    false,
+                ),
+                None => event_json(event, &uid, None, &zones, true),
+            })
+        }),
+        preview: None,
+    };
+
+    let delete_event = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "caldav.delete_event".to_string(),
+            description: "Delete a CalDAV event. For an occurrence of a recurring event, apply_to='series' deletes the whole series instead of that occurrence.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "event_id": { "type": "string" },
+                    "calendar_id": { "type": "string" },
+                    "apply_to": apply_to_arg_schema()
+                },
+                "required": ["event_id"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "deleted": { "type": "boolean" },
+                    "event_id": { "type": "string" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = caldav_client(&db_for_delete, &args)?;
+            let event_id = required_str_arg(&args, "event_id")?;
+            let (uid, instance) = parse_event_id(event_id);
+            let object = locate_event(&client, &args, &uid)?;
+            let Some(instance) = instance.filter(|_| !applies_to_series(&args)) else {
+                client.delete(&object.href, object.etag.as_deref())?;
+                return Ok(json!({ "deleted": true, "event_id": uid }));
+            };
+
+            let mut calendar = object.calendar.clone();
+            let zones = Zones::from_calendar(&calendar);
+            if let Some(index) = override_index(&calendar, &uid, &instance, &zones) {
+                calendar.components.remove(index);
+            }
+            match master_index(&calendar, &uid) {
+                Some(index) => {
+                    let master = &mut calendar.components[index];
+                    let start = event_start(master)
+                        .ok_or_else(|| ToolError::new("The event has no start time"))?;
+                    let excluded = recurrence_id_for(&instance, &start, &zones);
+                    master.push(excluded.to_property("EXDATE"));
+                    touch_event(master);
+                    client.put(&object.href, &calendar, object.etag.as_deref())?;
+                }
+                None if calendar.children("VEVENT").next().is_none() => {
+                    client.delete(&object.href, object.etag.as_deref())?;
+                }
+                None => {
+                    client.put(&object.href, &calendar, object.etag.as_deref())?;
+                }
+            }
+            Ok(json!({ "deleted": true, "event_id": event_id }))
+        }),
+        preview: None,
+    };
+
+    registry.register(list_calendars)?;
+    registry.register(list_events)?;
+    registry.register(create_event)?;
+    registry.register(update_event)?;
+    registry.register(delete_event)?;
+    Ok(())
+}
+
+pub fn register_ics_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let db_for_list = db.clone();
+
+    let list_events = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "ics.list_events".to_string(),
+            description: "List or search events from subscribed ICS calendar feeds (read-only), grouped by subscription. Recurring events are expanded into occurrences. Defaults to the next 30 days.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to read every ICS subscription."
+                    },
+                    "time_min": { "type": "string" },
+                    "time_max": { "type": "string" },
+                    "query": { "type": "string", "description": "Optional. Text to find in the title, description, or location." },
+                    "max_results": { "type": "integer", "minimum": 1, "maximum": 2500 }
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendars": { "type": "array" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let range = time_range_arg(&args)?;
+            let query = query_arg(&args);
+            let max_results = max_results_arg(&args);
+            let connections = match optional_str_arg(&args, "connection_id") {
+                Some(connection_id) => vec![get_connection(&db_for_list, connection_id, "ics")?],
+                None => ics_connections(&db_for_list)?,
+            };
+
+            let mut grouped = Vec::new();
+            for connection in connections {
+                let calendar = match fetch_calendar(&connection) {
+                    Ok(calendar) => calendar,
+                    Err(err) => {
+                        grouped.push(json!({
+                            "calendar_id": connection.id,
+                            "summary": connection.account_label,
+                            "error": err.message
+                        }));
+                        continue;
+                    }
+                };
+                let mut events = calendar_events(&calendar, range, true, query.as_deref());
+                events.sort_by_key(|(start_ms, _)| *start_ms);
+                events.truncate(max_results);
+                grouped.push(json!({
+                    "calendar_id": connection.id,
+                    "summary": calendar.text("X-WR-CALNAME").or(connection.account_label),
+                    "events": events.into_iter().map(|(_, event)| event).collect::<Vec<_>>()
+                }));
+            }
+            Ok(json!({ "calendars": grouped }))
+        }),
+        preview: None,
+    };
+
+    registry.register(list_events)?;
+    Ok(())
+}
+
+fn connection_id_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Optional. Omit to use the default connected CalDAV account."
+    })
+}
+
+fn time_zone_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Optional. IANA time zone such as 'Europe/Berlin'. Recurring events default to the calendar's time zone."
+    })
+}
+
+fn caldav_client(db: &Db, args: &Value) -> Result<CalDavClient, ToolError> {
+    let connection_id = args
+        .get("connection_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let connection = get_connection(db, connection_id, "caldav")?;
+    CalDavClient::load(db, &connection).map_err(|err| {
+        ToolError::new(err)
+            .with_hint("Ask the user to check the CalDAV connection in Settings > Integrations.")
+    })
+}
+
+fn ics_connections(db: &Db) -> Result<Vec<IntegrationConnection>, ToolError> {
+    let connections = IntegrationConnectionOperations::get_integration_connections(db)
+        .map_err(|err| ToolError::new(format!("Failed to load integration connections: {err}")))?
+        .into_iter()
+        .filter(|connection| connection.integration_id == "ics")
+        .collect::<Vec<_>>();
+    if connections.is_empty() {
+        return Err(ToolError::not_found("No ICS subscriptions are set up")
+            .with_hint("Ask the user to add the calendar URL in Settings > Integrations."));
+    }
+    Ok(connections)
+}
+
+fn optional_str_arg<'a>(args: &'a Value, key: &str) -> Option<&'a str> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn query_arg(args: &Value) -> Option<String> {
+    optional_str_arg(args, "query").map(str::to_lowercase)
+}
+
+fn max_results_arg(args: &Value) -> usize {
+    args.get("max_results")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize)
+        .unwrap_or(DEFAULT_MAX_RESULTS)
+}
+
+fn calendar_ids_arg(args: &Value) -> Option<Vec<String>> {
+    let ids = string_list_arg(args, "calendar_ids");
+    if !ids.is_empty() {
+        return Some(ids);
+    }
+    optional_str_arg(args, "calendar_id").map(|id| vec![id.to_string()])
+}
+
+fn default_calendar_ids(client: &CalDavClient) -> Result<Vec<String>, ToolError> {
+    if !client.default_calendar_ids.is_empty() {
+        return Ok(client.default_calendar_ids.clone());
+    }
+    Ok(client
+        .calendars()?
+        .into_iter()
+        .map(|calendar| calendar.id)
+        .collect())
+}
+
+/// `time_min` to `time_max`, defaulting to the next 30 days.
+fn time_range_arg(args: &Value) -> Result<(DateTime<Utc>, DateTime<Utc>), ToolError> {
+    let bound = |key: &str| -> Result<Option<DateTime<Utc>>, ToolError> {
+        let Some(value) = optional_str_arg(args, key) else {
+            return Ok(None);
+        };
+        parse_time_arg(value)
+            .and_then(DateTime::from_timestamp_millis)
+            .map(Some)
+            .ok_or_else(|| {
+                ToolError::validation(format!(
+                    "Invalid '{key}'. Use an RFC 3339 timestamp or YYYY-MM-DD."
+                ))
+            })
+    };
+    let start = bound("time_min")?.unwrap_or_else(Utc::now);
+    let end = bound("time_max")?.unwrap_or(start + Duration::days(DEFAULT_RANGE_DAYS));
+    if end <= start {
+        return Err(ToolError::validation("'time_max' must be after 'time_min'"));
+    }
+    Ok((start, end))
+}
+
+/// Events of one calendar as Google Calendar shaped JSON, keyed by start time for sorting.
+fn calendar_events(
+    calendar: &Component,
+    range: (DateTime<Utc>, DateTime<Utc>),
+    expand: bool,
+    query: Option<&str>,
+) -> Vec<(i64, Value)> {
+    let zones = Zones::from_calendar(calendar);
+    let matches = |event: &Component| {
+        let Some(query) = query else {
+            return true;
+        };
+        ["SUMMARY", "DESCRIPTION", "LOCATION"].iter().any(|name| {
+            event
+                .text(name)
+                .is_some_and(|text| text.to_lowercase().contains(query))
+        })
+    };
+
+    if expand {
+        return icalendar::occurrences(calendar, &zones, range, MAX_OCCURRENCES)
+            .into_iter()
+            .filter(|occurrence| matches(occurrence.component))
+            .map(|occurrence| {
+                let start_ms = zones.resolve(&occurrence.start).timestamp_millis();
+                let mut event = match &occurrence.recurrence_id {
+                    Some(recurrence_id) => event_json(
+                        occurrence.component,
+                        &format!(
+                            "{}_{}",
+                            occurrence.uid,
+                            instance_stamp(recurrence_id, &zones)
+                        ),
+                        Some(&occurrence.uid),
+                        &zones,
+                        false,
+                    ),
+                    None => event_json(occurrence.component, &occurrence.uid, None, &zones, false),
+                };
+                event["start"] = time_json(&occurrence.start, &zones);
+                event["end"] = time_json(&occurrence.end, &zones);
+                (start_ms, event)
+            })
+            .collect();
+    }
+
+    calendar
+        .children("VEVENT")
+        .filter(|event| matches(*event))
+        .filter_map(|event| {
+            let uid = event.text("UID")?;
+            let start_ms = zones.resolve(&event_start(event)?).timestamp_millis();
+            let value = match event
+                .property("RECURRENCE-ID")
+                .and_then(EventTime::from_property)
+            {
+                Some(recurrence_id) => event_json(
+                    event,
+                    &format!("{uid}_{}", instance_stamp(&recurrence_id, &zones)),
+                    Some(&uid),
+                    &zones,
+                    false,
+                ),
+                None => event_json(event, &uid, None, &zones, true),
+            };
+            Some((start_ms, value))
+        })
+        .collect()
+}
+
+fn event_json(
+    event: &Component,
+    id: &str,
+    recurring_event_id: Option<&str>,
+    zones: &Zones,
+    include_recurrence: bool,
+) -> Value {
+    let mut value = serde_json::Map::new();
+    value.insert("id".to_string(), json!(id));
+    value.insert("iCalUID".to_string(), json!(event.text("UID")));
+    if let Some(recurring_event_id) = recurring_event_id {
+        value.insert("recurringEventId".to_string(), json!(recurring_event_id));
+    }
+    for (key, name) in [
+        ("summary", "SUMMARY"),
+        ("description", "DESCRIPTION"),
+        ("location", "LOCATION"),
+    ] {
+        if let Some(text) = event.text(name) {
+            value.insert(key.to_string(), json!(text));
+        }
+    }
+    value.insert(
+        "status".to_string(),
+        json!(event
+            .text("STATUS")
+            .map(|status| status.to_lowercase())
+            .unwrap_or_else(|| "confirmed".to_string())),
+    );
+    if let Some(start) = event_start(event) {
+        value.insert("start".to_string(), time_json(&start, zones));
+        value.insert(
+            "end".to_string(),
+            time_json(&event_end(event, &start), zones),
+        );
+    }
+    if include_recurrence {
+        let recurrence = ["RRULE", "RDATE", "EXDATE"]
+            .iter()
+            .flat_map(|name| event.properties(name))
+            .map(Property::to_line)
+            .collect::<Vec<_>>();
+        if !recurrence.is_empty() {
+            value.insert("recurrence".to_string(), json!(recurrence));
+        }
+    }
+    let attendees = event
+        .properties("ATTENDEE")
+        .map(|attendee| {
+            let mut person = person_json(attendee);
+            person["responseStatus"] = json!(match attendee.param("PARTSTAT") {
+                Some("ACCEPTED") => "accepted",
+                Some("DECLINED") => "declined",
+                Some("TENTATIVE") => "tentative",
+                _ => "needsAction",
+            });
+            person
+        })
+        .collect::<Vec<_>>();
+    if !attendees.is_empty() {
+        value.insert("attendees".to_string(), json!(attendees));
+    }
+    if let Some(organizer) = event.property("ORGANIZER") {
+        value.insert("organizer".to_string(), person_json(organizer));
+    }
+    Value::Object(value)
+}
+
+fn person_json(property: &Property) -> Value {
+    let value = property.value.trim();
+    let email = value
+        .get(..7)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("mailto:"))
+        .map_or(value, |_| &value[7..]);
+    let mut person = json!({ "email": email });
+    if let Some(name) = property.param("CN") {
+        person["displayName"] = json!(name);
+    }
+    person
+}
+
+fn time_json(time: &EventTime, zones: &Zones) -> Value {
+    match time {
+        EventTime::Date(date) => json!({ "date": date.format("%Y-%m-%d").to_string() }),
+        EventTime::Zoned(_, tzid) => json!({
+            "dateTime": zones.resolve(time).to_rfc3339(),
+            "timeZone": tzid
+        }),
+        EventTime::Utc(_) | EventTime::Floating(_) => {
+            json!({ "dateTime": zones.resolve(time).to_rfc3339() })
+        }
+    }
+}
+
+fn utc_stamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Suffix of an occurrence id: the date of all-day events, else the UTC start.
+fn instance_stamp(recurrence_id: &EventTime, zones: &Zones) -> String {
+    match recurrence_id {
+        EventTime::Date(date) => date.format("%Y%m%d").to_string(),
+        other => utc_stamp(zones.resolve(other).with_timezone(&Utc)),
+    }
+}
+
+/// Splits an occurrence id (`{uid}_{stamp}`) into the event UID and the occurrence start.
+fn parse_event_id(event_id: &str) -> (String, Option<EventTime>) {
+    let event_id = event_id.trim();
+    if let Some((uid, stamp)) = event_id.rsplit_once('_') {
+        let instance = EventTime::parse_value(stamp, None, false)
+            .filter(|time| matches!(time, EventTime::Date(_) | EventTime::Utc(_)));
+        if instance.is_some() && !uid.is_empty() {
+            return (uid.to_string(), instance);
+        }
+    }
+    (event_id.to_string(), None)
+}
+
+/// The RECURRENCE-ID of an occurrence, in the same form as the series start.
+fn recurrence_id_for(instance: &EventTime, series_start: &EventTime, zones: &Zones) -> EventTime {
+    match instance {
+        EventTime::Date(date) => {
+            series_start.with_naive(date.and_time(series_start.naive().time()))
+        }
+        other => {
+            let instant = zones.resolve(other).with_timezone(&Utc);
+            series_start.with_naive(zones.to_wall_time(instant, series_start))
+        }
+    }
+}
+
+fn event_not_found(uid: &str) -> ToolError {
+    ToolError::not_found(format!("Event {uid} not found"))
+        .with_hint("Use caldav.list_events to find the event id and its calendar_id.")
+}
+
+fn locate_event(
+    client: &CalDavClient,
+    args: &Value,
+    uid: &str,
+) -> Result<CalendarObject, ToolError> {
+    let calendar_ids = match optional_str_arg(args, "calendar_id") {
+        Some(id) => vec![id.to_string()],
+        None => default_calendar_ids(client)?,
+    };
+    for calendar_id in calendar_ids {
+        if let Some(object) = client.find_event(&calendar_id, uid)? {
+            return Ok(object);
+        }
+    }
+    Err(event_not_found(uid))
+}
+
+fn is_event(component: &Component, uid: &str) -> bool {
+    component.name == "VEVENT" && component.text("UID").as_deref() == Some(uid)
+}
+
+fn master_index(calendar: &Component, uid: &str) -> Option<usize> {
+    calendar.components.iter().position(|component| {
+        is_event(component, uid) && component.property("RECURRENCE-ID").is_none()
+    })
+}
+
+fn override_index(
+    calendar: &Component,
+    uid: &str,
+    instance: &EventTime,
+    zones: &Zones,
+) -> Option<usize> {
+    let stamp = instance_stamp(instance, zones);
+    calendar.components.iter().position(|component| {
+        is_event(component, uid)
+            && component
+                .property("RECURRENCE-ID")
+                .and_then(EventTime::from_property)
+                .is_some_and(|recurrence_id| instance_stamp(&recurrence_id, zones) == stamp)
+    })
+}
+
+/// A copy of the series master for one occurrence, to be edited on its own.
+fn new_override(
+    calendar: &Component,
+    uid: &str,
+    instance: &EventTime,
+    zones: &Zones,
+) -> Result<Component, ToolError> {
+    let master = master_index(calendar, uid)
+        .map(|index| &calendar.components[index])
+        .ok_or_else(|| event_not_found(uid))?;
+    let start = event_start(master).ok_or_else(|| ToolError::new("The event has no start time"))?;
+    let duration = event_end(master, &start).naive() - start.naive();
+    let recurrence_id = recurrence_id_for(instance, &start, zones);
+
+    let mut edited = master.clone();
+    for name in ["RRULE", "RDATE", "EXDATE", "DURATION"] {
+        edited.remove(name);
+    }
+    edited.set(recurrence_id.to_property("RECURRENCE-ID"));
+    edited.set(recurrence_id.to_property("DTSTART"));
+    edited.set(
+        recurrence_id
+            .with_naive(recurrence_id.naive() + duration)
+            .to_property("DTEND"),
+    );
+    Ok(edited)
+}
+
+/// Which fields [`apply_event_fields`] changed.
+struct ChangedFields {
+    any: bool,
+    start: bool,
+    end: bool,
+}
+
+fn apply_event_fields(
+    event: &mut Component,
+    args: &Value,
+    time_zone: Option<&str>,
+    recurrence: Vec<Property>,
+) -> Result<ChangedFields, ToolError> {
+    let mut any = false;
+    for (key, name) in [
+        ("summary", "SUMMARY"),
+        ("description", "DESCRIPTION"),
+        ("location", "LOCATION"),
+    ] {
+        if let Some(text) = args.get(key).and_then(|v| v.as_str()) {
+            event.set(Property::text(name, text));
+            any = true;
+        }
+    }
+
+    let recurring = !recurrence.is_empty() || event.property("RRULE").is_some();
+    let start = optional_str_arg(args, "start")
+        .map(|value| event_time_arg(value, time_zone, recurring))
+        .transpose()?;
+    let end = optional_str_arg(args, "end")
+        .map(|value| event_time_arg(value, time_zone, recurring))
+        .transpose()?;
+    let new_start = start.clone().or_else(|| event_start(event));
+    if let (Some(start), Some(end)) = (&new_start, &end) {
+        if start.is_date() != end.is_date() {
+            return Err(ToolError::validation(
+                "'start' and 'end' must both be dates or both be date-times",
+            ));
+        }
+    }
+    if let Some(start) = &start {
+        event.set(start.to_property("DTSTART"));
+        any = true;
+    }
+    if let Some(end) = &end {
+        event.remove("DURATION");
+        event.set(end.to_property("DTEND"));
+        any = true;
+    }
+
+    if let Some(attendees) = args.get("attendees").and_then(|v| v.as_array()) {
+        event.remove("ATTENDEE");
+        for email in attendees.iter().filter_map(|item| item.as_str()) {
+            event.push(
+                Property::new("ATTENDEE", format!("mailto:{}", email.trim()))
+                    .with_param("PARTSTAT", "NEEDS-ACTION")
+                    .with_param("RSVP", "TRUE"),
+            );
+        }
+        any = true;
+    }
+    if !recurrence.is_empty() {
+        for name in ["RRULE", "RDATE", "EXDATE"] {
+            event.remove(name);
+        }
+        for property in recurrence {
+            event.push(property);
+        }
+        any = true;
+    }
+    Ok(ChangedFields {
+        any,
+        start: start.is_some(),
+        end: end.is_some(),
+    })
+}
+
+/// Parses `recurrence` lines, checking that every RRULE is one this app can expand.
+fn recurrence_properties(args: &Value) -> Result<Vec<Property>, ToolError> {
+    string_list_arg(args, "recurrence")
+        .iter()
+        .map(|line| {
+            let property = Property::parse_line(line)
+                .filter(|property| matches!(property.name.as_str(), "RRULE" | "RDATE" | "EXDATE"))
+                .ok_or_else(|| {
+                    ToolError::validation(format!(
+                        "Invalid recurrence line '{line}'. Use RRULE:, RDATE:, or EXDATE: lines."
+                    ))
+                })?;
+            if property.name == "RRULE" {
+                RecurrenceRule::parse(&property.value).map_err(ToolError::validation)?;
+            }
+            Ok(property)
+        })
+        .collect()
+}
+
+/// Accepts `YYYY-MM-DD` (all-day), RFC 3339, or a wall-clock `YYYY-MM-DDTHH:MM[:SS]`
+/// interpreted in `time_zone`. Without a time zone, recurring events get floating local
+/// times so they keep their hour across daylight saving changes.
+fn event_time_arg(
+    value: &str,
+    time_zone: Option<&str>,
+    recurring: bool,
+) -> Result<EventTime, ToolError> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(EventTime::Date(date));
+    }
+    let zone = match time_zone {
+        Some(time_zone) => Some((
+            time_zone.to_string(),
+            iana_zone(time_zone).ok_or_else(|| {
+                ToolError::validation(format!("Unknown time zone '{time_zone}'"))
+                    .with_hint("Use an IANA name such as 'Europe/Berlin'.")
+            })?,
+        )),
+        None => None,
+    };
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Ok(match zone {
+            Some((tzid, tz)) => EventTime::Zoned(parsed.with_timezone(&tz).naive_local(), tzid),
+            None if recurring => EventTime::Floating(parsed.with_timezone(&Local).naive_local()),
+            None => EventTime::Utc(parsed.naive_utc()),
+        });
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(match zone {
+                Some((tzid, _)) => EventTime::Zoned(naive, tzid),
+                None => EventTime::Floating(naive),
+            });
+        }
+    }
+    Err(ToolError::validation(format!(
+        "Invalid time '{value}'. Use RFC 3339, YYYY-MM-DDTHH:MM with time_zone, or YYYY-MM-DD for all-day events."
+    )))
+}
+
+/// Bumps SEQUENCE and the modification stamps so clients and attendees pick up a change.
+fn touch_event(event: &mut Component) {
+    let now = utc_stamp(Utc::now());
+    let sequence = event
+        .property("SEQUENCE")
+        .and_then(|property| property.value.trim().parse::<u32>().ok())
+        .map_or(0, |sequence| sequence + 1);
+    event.set(Property::new("SEQUENCE", sequence.to_string()));
+    event.set(Property::new("DTSTAMP", now.clone()));
+    event.set(Property::new("LAST-MODIFIED", now));
+}
+
+/// Adds a VTIMEZONE for every IANA `TZID` the events use that the calendar doesn't define.
+fn add_time_zones(calendar: &mut Component) {
+    let defined = calendar
+        .children("VTIMEZONE")
+        .filter_map(|zone| {
+            zone.property("TZID")
+                .map(|property| property.value.trim().to_string())
+        })
+        .collect::<Vec<_>>();
+    let mut missing: Vec<(String, i32)> = Vec::new();
+    for event in calendar.children("VEVENT") {
+        for property in &event.properties {
+            let Some(tzid) = property.param("TZID") else {
+                continue;
+            };
+            if defined.iter().any(|id| id == tzid) || missing.iter().any(|(id, _)| id == tzid) {
+                continue;
+            }
+            let year = event_start(event)
+                .map(|start| chrono::Datelike::year(&start.naive()))
+                .unwrap_or_else(|| chrono::Datelike::year(&Utc::now()));
+            missing.push((tzid.to_string(), year));
+        }
+    }
+    for (tzid, year) in missing {
+        if let Some(zone) = icalendar::vtimezone(&tzid, year) {
+            calendar.components.insert(0, zone);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_times_and_ids_round_trip() {
+        assert_eq!(
+            event_time_arg("2026-05-01", None, false).unwrap(),
+            EventTime::Date(NaiveDate::from_ymd_opt(2026, 5, 1).unwrap())
+        );
+        let berlin = event_time_arg("2026-05-01T08:00:00Z", Some("Europe/Berlin"), false).unwrap();
+        assert_eq!(berlin.format_value(), "20260501T100000");
+        assert_eq!(berlin.tzid(), Some("Europe/Berlin"));
+        assert_eq!(
+            event_time_arg("2026-05-01T08:00:00+02:00", None, false)
+                .unwrap()
+                .format_value(),
+            "20260501T060000Z"
+        );
+        assert!(event_time_arg("2026-05-01T08:00", Some("Mars/Olympus"), false).is_err());
+
+        let zones = Zones::default();
+        let (uid, instance) = parse_event_id("abc_def_20260501T060000Z");
+        assert_eq!(uid, "abc_def");
+        let instance = instance.expect("instance");
+        assert_eq!(instance_stamp(&instance, &zones), "20260501T060000Z");
+        assert_eq!(
+            recurrence_id_for(&instance, &berlin, &zones),
+            EventTime::Zoned(
+                NaiveDate::from_ymd_opt(2026, 5, 1)
+                    .unwrap()
+                    .and_hms_opt(8, 0, 0)
+                    .unwrap(),
+                "Europe/Berlin".to_string()
+            )
+        );
+        assert_eq!(parse_event_id("plain-uid"), ("plain-uid".to_string(), None));
+    }
+
+    #[test]
+    fn editing_an_occurrence_adds_an_override_and_time_zone() {
+        let mut calendar = Component::calendar();
+        let mut master = Component::new("VEVENT");
+        master.push(Property::text("UID", "weekly"));
+        master.push(Property::text("SUMMARY", "Sync"));
+        master.push(Property::new("RRULE", "FREQ=WEEKLY"));
+        let args = json!({
+            "start": "2026-05-04T09:00",
+            "end": "2026-05-04T09:30",
+            "attendees": ["ada@example.com"]
+        });
+        apply_event_fields(&mut master, &args, Some("Europe/Berlin"), Vec::new()).unwrap();
+        calendar.components.push(master);
+        add_time_zones(&mut calendar);
+        assert_eq!(calendar.components[0].name, "VTIMEZONE");
+
+        let zones = Zones::from_calendar(&calendar);
+        let (_, instance) = parse_event_id("weekly_20260511T070000Z");
+        let instance = instance.unwrap();
+        assert!(override_index(&calendar, "weekly", &instance, &zones).is_none());
+        let mut edited = new_override(&calendar, "weekly", &instance, &zones).unwrap();
+        apply_event_fields(
+            &mut edited,
+            &json!({ "summary": "Sync (moved)" }),
+            None,
+            Vec::new(),
+        )
+        .unwrap();
+        calendar.components.push(edited);
+        assert_eq!(
+            override_index(&calendar, "weekly", &instance, &zones),
+            Some(2)
+        );
+
+        let json = event_json(
+            &calendar.components[2],
+            "weekly_20260511T070000Z",
+            Some("weekly"),
+            &zones,
+            false,
+        );
+        assert_eq!(json["summary"], "Sync (moved)");
+        assert_eq!(json["start"]["dateTime"], "2026-05-11T09:00:00+02:00");
+        assert_eq!(json["end"]["timeZone"], "Europe/Berlin");
+        assert_eq!(json["attendees"][0]["email"], "ada@example.com");
+        assert_eq!(json["attendees"][0]["responseStatus"], "needsAction");
+        assert!(calendar.components[2].property("RRULE").is_none());
+
+        assert!(recurrence_properties(&json!({ "recurrence": ["RRULE:FREQ=SECONDLY"] })).is_err());
+        assert!(recurrence_properties(&json!({ "recurrence": ["DTSTART:20260101"] })).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use super::ToolError;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Cancellation flag shared between the controller and a running tool. A token trips when
+/// either its own flag or the parent run's flag is set, so timeouts can stop a single tool
+/// without cancelling the whole run.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    parent: Option<Arc<AtomicBool>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_parent(parent: Arc<AtomicBool>) -> Self {
+        Self {
+            flag: Arc::new(AtomicBool::new(false)),
+            parent: Some(parent),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+            || self
+                .parent
+                .as_ref()
+                .map(|parent| parent.load(Ordering::SeqCst))
+                .unwrap_or(false)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolProgress {
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+pub type ToolProgressSink = dyn Fn(ToolProgress) + Send + Sync;
+
+#[derive(Clone, Default)]
+pub struct ToolExecutionContext {
+    pub conversation_id: Option<String>,
+    pub message_id: Option<String>,
+    pub session_id: Option<String>,
+    pub execution_id: Option<String>,
+    pub cancellation: CancellationToken,
+    progress: Option<Arc<ToolProgressSink>>,
+}
+
+impl fmt::Debug for ToolExecutionContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolExecutionContext")
+            .field("conversation_id", &self.conversation_id)
+            .field("message_id", &self.message_id)
+            .field("session_id", &self.session_id)
+            .field("execution_id", &self.execution_id)
+            .field("cancelled", &self.cancellation.is_cancelled())
+            .finish()
+    }
+}
+
+impl ToolExecutionContext {
+    pub fn new(cancellation: CancellationToken) -> Self {
+        Self {
+            cancellation,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_progress(mut self, sink: Arc<ToolProgressSink>) -> Self {
+        self.progress = Some(sink);
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Returns an error once the run was cancelled or the tool timed out. Long-running tools
+    /// call this between units of work.
+    pub fn check_cancelled(&self) -> Result<(), ToolError> {
+        if self.is_cancelled() {
+            return Err(ToolError::cancelled("Tool execution cancelled"));
+        }
+        Ok(())
+    }
+
+    pub fn report_progress(
+        &self,
+        message: impl Into<String>,
+        current: Option<u64>,
+        total: Option<u64>,
+    ) {
+        self.report(ToolProgress {
+            message: message.into(),
+            current,
+            total,
+            data: None,
+        });
+    }
+
+    pub fn report(&self, progress: ToolProgress) {
+        if let Some(sink) = &self.progress {
+            (sink)(progress);
+        }
+    }
+}
@@ -0,0 +1,968 @@
+use chrono::NaiveDate;
+use mailparse::{DispositionType, MailHeader, MailHeaderMap, ParsedMail};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::integrations::{
+    address_email, address_list_arg, get_connection, split_addresses, OriginalMessage, OutgoingMail,
+};
+use super::{
+    ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
+};
+use crate::db::Db;
+use crate::integrations::email::{list_mailboxes, EmailAccount, ImapSession};
+
+const DEFAULT_MAILBOX: &str = "INBOX";
+/// Messages scanned per requested thread, since one thread spans several messages.
+const MESSAGES_PER_THREAD: usize = 5;
+const MAX_SCANNED_MESSAGES: usize = 500;
+
+pub fn register_email_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let db_for_list = db.clone();
+    let db_for_get = db.clone();
+    let db_for_mailboxes = db.clone();
+    let db_for_send = db.clone();
+    let db_for_reply = db.clone();
+    let db_for_forward = db.clone();
+
+    let list_threads = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "email.list_threads".to_string(),
+            description: "List recent threads in an IMAP mailbox, newest first. Threads are grouped by the Message-ID of their first message.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "query": { "type": "string", "description": "Text to find in headers or body (IMAP TEXT search)." },
+                    "mailbox": mailbox_schema(),
+                    "from": { "type": "string" },
+                    "to": { "type": "string" },
+                    "subject": { "type": "string" },
+                    "after": { "type": "string", "description": "YYYY-MM-DD. Messages on or after this date." },
+                    "before": { "type": "string", "description": "YYYY-MM-DD. Messages before this date." },
+                    "unread": { "type": "boolean", "description": "Only threads with unread messages." },
+                    "max_results": { "type": "integer", "minimum": 1, "maximum": 100 }
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "mailbox": { "type": "string" },
+                    "threads": { "type": "array" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let account = email_account(&db_for_list, &args)?;
+            let mailbox = mailbox_arg(&args);
+            let criteria = imap_search_query(&args)?;
+            let max_results = args
+                .get("max_results")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(20) as usize;
+
+            let headers = with_imap(&account, |session| {
+                examine(session, &mailbox)?;
+                let mut uids = search_uids(session, &criteria)?;
+                uids.truncate((max_results * MESSAGES_PER_THREAD).min(MAX_SCANNED_MESSAGES));
+                fetch_headers(session, &uids)
+            })?;
+            let threads = group_threads(headers)
+                .into_iter()
+                .take(max_results)
+                .collect::<Vec<_>>();
+            Ok(json!({ "mailbox": mailbox, "threads": threads }))
+        }),
+        preview: None,
+    };
+
+    let get_thread = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "email.get_thread".to_string(),
+            description: "Get an email thread with minimal fields (title, body, date, attachments). Looks in the given mailbox and the configured sent mailbox.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "thread_id": { "type": "string", "description": "Thread id from email.list_threads." },
+                    "mailbox": mailbox_schema(),
+                    "mode": { "type": "string", "enum": ["latest", "all"] },
+                    "max_messages": { "type": "integer", "minimum": 1, "maximum": 50 }
+                },
+                "required": ["thread_id"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let thread_id = args.get("thread_id").and_then(|v| v.as_str()).unwrap_or("");
+            if thread_id.trim().is_empty() {
+                return Err(ToolError::validation("Missing 'thread_id'"));
+            }
+            let thread_id = thread_id.trim();
+            let account = email_account(&db_for_get, &args)?;
+            let mailbox = mailbox_arg(&args);
+
+            let messages = with_imap(&account, |session| {
+                if let Some(uid) = thread_id.strip_prefix("uid:") {
+                    let uid = uid
+                        .parse::<u32>()
+                        .map_err(|_| ToolError::validation(format!("Invalid thread id {thread_id}")))?;
+                    examine(session, &mailbox)?;
+                    return fetch_messages(session, &mailbox, &[uid]);
+                }
+                let criteria = thread_search_query(thread_id);
+                let mut messages = Vec::new();
+                for mailbox in search_mailboxes(&account, &mailbox) {
+                    examine(session, &mailbox)?;
+                    let uids = search_uids(session, &criteria)?;
+                    messages.extend(fetch_messages(session, &mailbox, &uids)?);
+                }
+                Ok(messages)
+            })?;
+
+            let mode = args
+                .get("mode")
+                .and_then(|v| v.as_str())
+                .unwrap_or("latest");
+            let max_messages = args
+                .get("max_messages")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize);
+            Ok(thread_result(thread_id, messages, mode, max_messages))
+        }),
+        preview: None,
+    };
+
+    let list_mailboxes_tool = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "email.list_mailboxes".to_string(),
+            description: "List the IMAP mailboxes (folders) of the connected email account."
+                .to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema()
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "mailboxes": { "type": "array", "items": { "type": "string" } }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let account = email_account(&db_for_mailboxes, &args)?;
+            let mailboxes = with_imap(&account, |session| {
+                list_mailboxes(session).map_err(ToolError::upstream)
+            })?;
+            Ok(json!({ "mailboxes": mailboxes }))
+        }),
+        preview: None,
+    };
+
+    let send_message = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "email.send_message".to_string(),
+            description: "Send an email over SMTP from the connected account.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "to": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+                    "cc": { "type": "array", "items": { "type": "string" } },
+                    "bcc": { "type": "array", "items": { "type": "string" } },
+                    "subject": { "type": "string" },
+                    "body": { "type": "string" }
+                },
+                "required": ["to", "subject", "body"]
+            }),
+            result_schema: sent_result_schema(),
+            requires_approval: false,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let account = email_account(&db_for_send, &args)?;
+            let mail = OutgoingMail::from_args(&args);
+            if mail.to.is_empty() {
+                return Err(ToolError::validation("Missing 'to'"));
+            }
+            deliver_email(&account, &mail)
+        }),
+        preview: None,
+    };
+
+    let reply = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "email.reply".to_string(),
+            description: "Reply to an email, quoting it and setting In-Reply-To/References so it stays in the thread.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "message_id": { "type": "string", "description": "Message-ID of the message to reply to, as returned by email.get_thread." },
+                    "mailbox": mailbox_schema(),
+                    "body": { "type": "string" },
+                    "reply_all": { "type": "boolean", "description": "Also reply to the original To and Cc recipients." },
+                    "cc": { "type": "array", "items": { "type": "string" } },
+                    "bcc": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["message_id", "body"]
+            }),
+            result_schema: sent_result_schema(),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let message_id = args.get("message_id").and_then(|v| v.as_str()).unwrap_or("");
+            if message_id.trim().is_empty() {
+                return Err(ToolError::validation("Missing 'message_id'"));
+            }
+            let account = email_account(&db_for_reply, &args)?;
+            let original = find_original(&account, &mailbox_arg(&args), message_id)?;
+            let body = args.get("body").and_then(|v| v.as_str()).unwrap_or("");
+            let reply_all = args
+                .get("reply_all")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let mut mail = original.reply(body, reply_all, Some(&account.from_address));
+            if let Some(cc) = address_list_arg(&args, "cc").filter(|cc| !cc.is_empty()) {
+                mail.cc = [mail.cc.as_str(), cc.as_str()]
+                    .into_iter()
+                    .filter(|value| !value.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+            }
+            if let Some(bcc) = address_list_arg(&args, "bcc") {
+                mail.bcc = bcc;
+            }
+            if mail.to.is_empty() {
+                return Err(ToolError::validation(
+                    "Could not determine who to reply to from the original message",
+                ));
+            }
+            deliver_email(&account, &mail)
+        }),
+        preview: None,
+    };
+
+    let forward = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "email.forward".to_string(),
+            description:
+                "Forward an email's text with an optional note. Attachments are not re-attached."
+                    .to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "message_id": { "type": "string", "description": "Message-ID of the message to forward, as returned by email.get_thread." },
+                    "mailbox": mailbox_schema(),
+                    "to": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+                    "cc": { "type": "array", "items": { "type": "string" } },
+                    "bcc": { "type": "array", "items": { "type": "string" } },
+                    "body": { "type": "string", "description": "Note placed above the forwarded message." }
+                },
+                "required": ["message_id", "to"]
+            }),
+            result_schema: sent_result_schema(),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let message_id = args
+                .get("message_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            if message_id.trim().is_empty() {
+                return Err(ToolError::validation("Missing 'message_id'"));
+            }
+            let to = address_list_arg(&args, "to").unwrap_or_default();
+            if to.is_empty() {
+                return Err(ToolError::validation("Missing 'to'"));
+            }
+            let account = email_account(&db_for_forward, &args)?;
+            let original = find_original(&account, &mailbox_arg(&args), message_id)?;
+            let note = args.get("body").and_then(|v| v.as_str()).unwrap_or("");
+            let mut mail = original.forward(to, note);
+            mail.cc = address_list_arg(&args, "cc").unwrap_or_default();
+            mail.bcc = address_list_arg(&args, "bcc").unwrap_or_default();
+            deliver_email(&account, &mail)
+        }),
+        preview: None,
+    };
+
+    registry.register(list_threads)?;
+    registry.register(get_thread)?;
+    registry.register(list_mailboxes_tool)?;
+    registry.register(send_message)?;
+    registry.register(reply)?;
+    registry.register(forward)?;
+    Ok(())
+}
+
+fn connection_id_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Optional. Omit to use the default connected email account."
+    })
+}
+
+fn mailbox_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Optional. IMAP mailbox, default INBOX. See email.list_mailboxes."
+    })
+}
+
+fn sent_result_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "message_id": { "type": "string" },
+            "thread_id": { "type": "string" },
+            "warning": { "type": "string" }
+        }
+    })
+}
+
+fn email_account(db: &Db, args: &Value) -> Result<EmailAccount, ToolError> {
+    let connection_id = args
+        .get("connection_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let connection = get_connection(db, connection_id, "email")?;
+    EmailAccount::load(db, &connection).map_err(|err| {
+        ToolError::new(err)
+            .with_hint("Ask the user to check the email connection in Settings > Integrations.")
+    })
+}
+
+fn mailbox_arg(args: &Value) -> String {
+    args.get("mailbox")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or(DEFAULT_MAILBOX)
+        .to_string()
+}
+
+/// The requested mailbox, then the sent mailbox so threads include the account's replies.
+fn search_mailboxes(account: &EmailAccount, mailbox: &str) -> Vec<String> {
+    let mut mailboxes = vec![mailbox.to_string()];
+    if let Some(sent) = account
+        .settings
+        .sent_mailbox
+        .as_deref()
+        .map(str::trim)
+        .filter(|sent| !sent.is_empty() && *sent != mailbox)
+    {
+        mailboxes.push(sent.to_string());
+    }
+    mailboxes
+}
+
+/// Runs `f` in a logged-in IMAP session and logs out afterwards.
+fn with_imap<T>(
+    account: &EmailAccount,
+    f: impl FnOnce(&mut ImapSession) -> Result<T, ToolError>,
+) -> Result<T, ToolError> {
+    let mut session = account.imap_session().map_err(ToolError::upstream)?;
+    let result = f(&mut session);
+    let _ = session.logout();
+    result
+}
+
+fn examine(session: &mut ImapSession, mailbox: &str) -> Result<(), ToolError> {
+    session.examine(mailbox).map(|_| ()).map_err(|err| {
+        ToolError::not_found(format!("Could not open mailbox '{mailbox}': {err}"))
+            .with_hint("Use email.list_mailboxes to see the available mailboxes.")
+    })
+}
+
+/// Matching UIDs, newest first.
+fn search_uids(session: &mut ImapSession, criteria: &str) -> Result<Vec<u32>, ToolError> {
+    let mut uids = session
+        .uid_search(criteria)
+        .map_err(|err| ToolError::upstream(format!("IMAP search failed: {err}")))?
+        .into_iter()
+        .collect::<Vec<_>>();
+    uids.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(uids)
+}
+
+fn uid_set(uids: &[u32]) -> String {
+    uids.iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Quoted IMAP string. Line breaks cannot appear in a quoted string, so they become spaces.
+fn imap_quote(value: &str) -> String {
+    let escaped = value
+        .replace(['\r', '\n'], " ")
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
+
+fn imap_date(value: &str, key: &str) -> Result<String, ToolError> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map(|date| date.format("%-d-%b-%Y").to_string())
+        .map_err(|_| ToolError::validation(format!("Invalid '{key}', expected YYYY-MM-DD")))
+}
+
+/// IMAP SEARCH criteria for the list arguments. All criteria must match.
+fn imap_search_query(args: &Value) -> Result<String, ToolError> {
+    let text_arg = |key: &str| {
+        args.get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let mut criteria = Vec::new();
+    for (key, keyword) in [
+        ("query", "TEXT"),
+        ("from", "FROM"),
+        ("to", "TO"),
+        ("subject", "SUBJECT"),
+    ] {
+        if let Some(value) = text_arg(key) {
+            criteria.push(format!("{keyword} {}", imap_quote(value)));
+        }
+    }
+    if let Some(after) = text_arg("after") {
+        criteria.push(format!("SINCE {}", imap_date(after, "after")?));
+    }
+    if let Some(before) = text_arg("before") {
+        criteria.push(format!("BEFORE {}", imap_date(before, "before")?));
+    }
+    if args.get("unread").and_then(|v| v.as_bool()) == Some(true) {
+        criteria.push("UNSEEN".to_string());
+    }
+    if criteria.is_empty() {
+        return Ok("ALL".to_string());
+    }
+    let query = criteria.join(" ");
+    if query.is_ascii() {
+        Ok(query)
+    } else {
+        Ok(format!("CHARSET UTF-8 {query}"))
+    }
+}
+
+/// Messages whose own id or references include the thread's root Message-ID.
+fn thread_search_query(thread_id: &str) -> String {
+    let id = imap_quote(thread_id);
+    format!("OR OR HEADER Message-ID {id} HEADER References {id} HEADER In-Reply-To {id}")
+}
+
+/// Message ids (`<...>`) in a Message-ID, In-Reply-To or References header.
+fn message_ids(value: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let id = &rest[start..start + len + 1];
+        if id.len() > 2 {
+            ids.push(id.to_string());
+        }
+        rest = &rest[start + len + 1..];
+    }
+    ids
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct EmailHeaders {
+    message_id: Option<String>,
+    in_reply_to: Option<String>,
+    references: Option<String>,
+    subject: String,
+    from: String,
+    reply_to: Option<String>,
+    to: String,
+    cc: String,
+    date: Option<String>,
+}
+
+impl EmailHeaders {
+    fn from_headers(headers: &[MailHeader]) -> Self {
+        let optional = |name: &str| {
+            headers
+                .get_first_value(name)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            message_id: optional("Message-ID"),
+            in_reply_to: optional("In-Reply-To"),
+            references: optional("References"),
+            subject: optional("Subject").unwrap_or_default(),
+            from: optional("From").unwrap_or_default(),
+            reply_to: optional("Reply-To"),
+            to: optional("To").unwrap_or_default(),
+            cc: optional("Cc").unwrap_or_default(),
+            date: optional("Date"),
+        }
+    }
+
+    /// Root Message-ID of the thread: the first reference, else what this message replies
+    /// to, else its own id.
+    fn thread_id(&self) -> Option<String> {
+        [&self.references, &self.in_reply_to, &self.message_id]
+            .into_iter()
+            .flatten()
+            .find_map(|value| message_ids(value).into_iter().next())
+    }
+
+    /// Milliseconds since the epoch from the Date header.
+    fn date_ms(&self) -> Option<i64> {
+        self.date
+            .as_deref()
+            .and_then(|date| mailparse::dateparse(date).ok())
+            .map(|seconds| seconds * 1000)
+    }
+}
+
+/// Header-only view of a message, for listing threads.
+#[derive(Debug, Clone)]
+struct EmailHeaderEntry {
+    uid: u32,
+    headers: EmailHeaders,
+    internal_date_ms: i64,
+    seen: bool,
+}
+
+impl EmailHeaderEntry {
+    fn thread_id(&self) -> String {
+        self.headers
+            .thread_id()
+            .unwrap_or_else(|| format!("uid:{}", self.uid))
+    }
+}
+
+fn fetch_headers(
+    session: &mut ImapSession,
+    uids: &[u32],
+) -> Result<Vec<EmailHeaderEntry>, ToolError> {
+    if uids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let fetches = session
+        .uid_fetch(uid_set(uids), "(UID FLAGS INTERNALDATE RFC822.HEADER)")
+        .map_err(|err| ToolError::upstream(format!("IMAP fetch failed: {err}")))?;
+    Ok(fetches
+        .iter()
+        .filter_map(|fetch| {
+            let uid = fetch.uid?;
+            let (headers, _) = mailparse::parse_headers(fetch.header()?).ok()?;
+            let headers = EmailHeaders::from_headers(&headers);
+            let internal_date_ms = fetch
+                .internal_date()
+                .map(|date| date.timestamp_millis())
+                .or_else(|| headers.date_ms())
+                .unwrap_or(0);
+            Some(EmailHeaderEntry {
+                uid,
+                headers,
+                internal_date_ms,
+                seen: fetch
+                    .flags()
+                    .iter()
+                    .any(|flag| matches!(flag, imap::types::Flag::Seen)),
+            })
+        })
+        .collect())
+}
+
+/// Groups messages into threads, most recently active first.
+fn group_threads(entries: Vec<EmailHeaderEntry>) -> Vec<Value> {
+    let mut threads: Vec<(String, Vec<EmailHeaderEntry>)> = Vec::new();
+    for entry in entries {
+        let thread_id = entry.thread_id();
+        match threads.iter_mut().find(|(id, _)| *id == thread_id) {
+            Some((_, messages)) => messages.push(entry),
+            None => threads.push((thread_id, vec![entry])),
+        }
+    }
+    for (_, messages) in threads.iter_mut() {
+        messages.sort_by_key(|entry| entry.internal_date_ms);
+    }
+    threads.sort_by_key(|(_, messages)| {
+        std::cmp::Reverse(messages.last().map(|entry| entry.internal_date_ms))
+    });
+    threads
+        .into_iter()
+        .filter_map(|(id, messages)| {
+            let first = messages.first()?;
+            let latest = messages.last()?;
+            Some(json!({
+                "id": id,
+                "subject": first.headers.subject,
+                "from": latest.headers.from,
+                "date_header": latest.headers.date,
+                "internal_date_ms": latest.internal_date_ms,
+                "message_count": messages.len(),
+                "unread": messages.iter().any(|entry| !entry.seen),
+                "latest_message_id": latest.headers.message_id,
+            }))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct EmailMessageSummary {
+    thread_id: String,
+    message_id: String,
+    mailbox: String,
+    uid: u32,
+    title: String,
+    from: String,
+    to: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    cc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_header: Option<String>,
+    internal_date_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_html: Option<String>,
+    attachments: Vec<EmailAttachmentSummary>,
+    #[serde(skip)]
+    headers: EmailHeaders,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct EmailAttachmentSummary {
+    filename: String,
+    mime_type: String,
+    size: usize,
+}
+
+fn parse_email_message(
+    mailbox: &str,
+    uid: u32,
+    raw: &[u8],
+    internal_date_ms: Option<i64>,
+) -> Option<EmailMessageSummary> {
+    let parsed = mailparse::parse_mail(raw).ok()?;
+    let headers = EmailHeaders::from_headers(&parsed.headers);
+    let mut body_text = None;
+    let mut body_html = None;
+    let mut attachments = Vec::new();
+    collect_mail_parts(&parsed, &mut body_text, &mut body_html, &mut attachments);
+    Some(EmailMessageSummary {
+        thread_id: headers.thread_id().unwrap_or_else(|| format!("uid:{uid}")),
+        message_id: headers
+            .message_id
+            .clone()
+            .unwrap_or_else(|| format!("uid:{uid}")),
+        mailbox: mailbox.to_string(),
+        uid,
+        title: headers.subject.clone(),
+        from: headers.from.clone(),
+        to: headers.to.clone(),
+        cc: headers.cc.clone(),
+        date_header: headers.date.clone(),
+        internal_date_ms: internal_date_ms.or_else(|| headers.date_ms()).unwrap_or(0),
+        body_text,
+        body_html,
+        attachments,
+        headers,
+    })
+}
+
+fn collect_mail_parts(
+    part: &ParsedMail,
+    body_text: &mut Option<String>,
+    body_html: &mut Option<String>,
+    attachments: &mut Vec<EmailAttachmentSummary>,
+) {
+    if !part.subparts.is_empty() {
+        for subpart in &part.subparts {
+            collect_mail_parts(subpart, body_text, body_html, attachments);
+        }
+        return;
+    }
+    let disposition = part.get_content_disposition();
+    let filename = disposition
+        .params
+        .get("filename")
+        .or_else(|| part.ctype.params.get("name"))
+        .cloned();
+    if disposition.disposition == DispositionType::Attachment || filename.is_some() {
+        attachments.push(EmailAttachmentSummary {
+            filename: filename.unwrap_or_default(),
+            mime_type: part.ctype.mimetype.clone(),
+            size: part.get_body_raw().map(|body| body.len()).unwrap_or(0),
+        });
+        return;
+    }
+    let target = match part.ctype.mimetype.as_str() {
+        "text/plain" => body_text,
+        "text/html" => body_html,
+        _ => return,
+    };
+    if target.is_none() {
+        *target = part.get_body().ok();
+    }
+}
+
+fn fetch_messages(
+    session: &mut ImapSession,
+    mailbox: &str,
+    uids: &[u32],
+) -> Result<Vec<EmailMessageSummary>, ToolError> {
+    if uids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let fetches = session
+        .uid_fetch(uid_set(uids), "(UID INTERNALDATE BODY.PEEK[])")
+        .map_err(|err| ToolError::upstream(format!("IMAP fetch failed: {err}")))?;
+    Ok(fetches
+        .iter()
+        .filter_map(|fetch| {
+            parse_email_message(
+                mailbox,
+                fetch.uid?,
+                fetch.body()?,
+                fetch.internal_date().map(|date| date.timestamp_millis()),
+            )
+        })
+        .collect())
+}
+
+/// Same shape as `gmail.get_thread`. Copies of one message found in several mailboxes
+/// are listed once.
+fn thread_result(
+    thread_id: &str,
+    messages: Vec<EmailMessageSummary>,
+    mode: &str,
+    max_messages: Option<usize>,
+) -> Value {
+    let mut seen = HashSet::new();
+    let mut messages = messages
+        .into_iter()
+        .filter(|message| seen.insert(message.message_id.clone()))
+        .collect::<Vec<_>>();
+    messages.sort_by_key(|message| message.internal_date_ms);
+    if mode == "latest" {
+        return json!({
+            "thread_id": thread_id,
+            "mode": "latest",
+            "message": messages.pop(),
+        });
+    }
+    if let Some(max) = max_messages {
+        if messages.len() > max {
+            messages.drain(..messages.len() - max);
+        }
+    }
+    json!({
+        "thread_id": thread_id,
+        "mode": "all",
+        "messages": messages,
+    })
+}
+
+/// Looks up a message by Message-ID in `mailbox`, then in the sent mailbox.
+fn find_original(
+    account: &EmailAccount,
+    mailbox: &str,
+    message_id: &str,
+) -> Result<OriginalMessage, ToolError> {
+    let criteria = format!("HEADER Message-ID {}", imap_quote(message_id.trim()));
+    let message = with_imap(account, |session| {
+        for mailbox in search_mailboxes(account, mailbox) {
+            examine(session, &mailbox)?;
+            let uids = search_uids(session, &criteria)?;
+            if let Some(message) = fetch_messages(session, &mailbox, &uids[..uids.len().min(1)])?
+                .into_iter()
+                .next()
+            {
+                return Ok(message);
+            }
+        }
+        Err(
+            ToolError::not_found(format!("Message {message_id} not found"))
+                .with_hint("Pass the mailbox the message is in."),
+        )
+    })?;
+    Ok(original_message(message))
+}
+
+fn original_message(message: EmailMessageSummary) -> OriginalMessage {
+    let headers = message.headers;
+    OriginalMessage {
+        thread_id: message.thread_id,
+        message_id_header: headers.message_id,
+        references: headers.references,
+        subject: headers.subject,
+        from: headers.from,
+        reply_to: headers.reply_to,
+        to: headers.to,
+        cc: headers.cc,
+        date: headers.date,
+        body: message.body_text.or(message.body_html).unwrap_or_default(),
+    }
+}
+
+/// Sends `mail` with fresh `From`, `Date` and `Message-ID` headers.
+fn deliver_email(account: &EmailAccount, mail: &OutgoingMail) -> Result<Value, ToolError> {
+    let recipients = [&mail.to, &mail.cc, &mail.bcc]
+        .into_iter()
+        .flat_map(|header| split_addresses(header))
+        .collect::<Vec<_>>();
+    let from_email = address_email(&account.from_address);
+    let domain = from_email
+        .rsplit_once('@')
+        .map(|(_, domain)| domain)
+        .filter(|domain| !domain.is_empty())
+        .unwrap_or("localhost");
+    let message_id = format!("<{}@{domain}>", Uuid::new_v4());
+    let raw = mail.to_rfc822(
+        &[
+            ("From", account.from_address.clone()),
+            ("Date", chrono::Local::now().to_rfc2822()),
+            ("Message-ID", message_id.clone()),
+        ],
+        false,
+    );
+    let warning = account
+        .send(&recipients, &raw)
+        .map_err(ToolError::upstream)?;
+
+    let thread_id = [&mail.references, &mail.in_reply_to]
+        .into_iter()
+        .flatten()
+        .find_map(|value| message_ids(value).into_iter().next())
+        .unwrap_or_else(|| message_id.clone());
+    let mut result = json!({ "message_id": message_id, "thread_id": thread_id });
+    if let Some(warning) = warning {
+        result["warning"] = json!(warning);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(uid: u32, raw_headers: &str, internal_date_ms: i64, seen: bool) -> EmailHeaderEntry {
+        let (headers, _) = mailparse::parse_headers(raw_headers.as_bytes()).expect("headers");
+        EmailHeaderEntry {
+            uid,
+            headers: EmailHeaders::from_headers(&headers),
+            internal_date_ms,
+            seen,
+        }
+    }
+
+    #[test]
+    fn search_query_combines_criteria_and_escapes_strings() {
+        assert_eq!(imap_search_query(&json!({})).unwrap(), "ALL");
+        assert_eq!(
+            imap_search_query(&json!({
+                "query": "say \"hi\"\r\nnow",
+                "from": "ada@example.com",
+                "after": "2026-01-05",
+                "unread": true
+            }))
+            .unwrap(),
+            "TEXT \"say \\\"hi\\\"  now\" FROM \"ada@example.com\" SINCE 5-Jan-2026 UNSEEN"
+        );
+        assert!(imap_search_query(&json!({ "subject": "café" }))
+            .unwrap()
+            .starts_with("CHARSET UTF-8 SUBJECT"));
+        assert!(imap_search_query(&json!({ "before": "January 5" })).is_err());
+    }
+
+    #[test]
+    fn messages_group_into_threads_by_root_message_id() {
+        let entries = vec![
+            entry(
+                1,
+                "Message-ID: <root@example.com>\r\nSubject: Plan\r\nFrom: Ada <ada@example.com>\r\n\r\n",
+                1_000,
+                true,
+            ),
+            entry(
+                2,
+                "Message-ID: <other@example.com>\r\nSubject: Lunch\r\nFrom: bob@example.com\r\n\r\n",
+                2_000,
+                true,
+            ),
+            entry(
+                3,
+                "Message-ID: <reply2@example.com>\r\nIn-Reply-To: <reply1@example.com>\r\nReferences: <root@example.com>\r\n <reply1@example.com>\r\nSubject: Re: Plan\r\nFrom: Bob <bob@example.com>\r\n\r\n",
+                3_000,
+                false,
+            ),
+            entry(4, "Subject: No id\r\n\r\n", 500, true),
+        ];
+        let threads = group_threads(entries);
+        assert_eq!(threads.len(), 3);
+        assert_eq!(threads[0]["id"], "<root@example.com>");
+        assert_eq!(threads[0]["subject"], "Plan");
+        assert_eq!(threads[0]["from"], "Bob <bob@example.com>");
+        assert_eq!(threads[0]["message_count"], 2);
+        assert_eq!(threads[0]["unread"], true);
+        assert_eq!(threads[0]["latest_message_id"], "<reply2@example.com>");
+        assert_eq!(threads[1]["id"], "<other@example.com>");
+        assert_eq!(threads[2]["id"], "uid:4");
+    }
+
+    #[test]
+    fn parsed_messages_reply_in_thread() {
+        let raw = concat!(
+            "Message-ID: <root@example.com>\r\n",
+            "Subject: =?UTF-8?B?Q2Fmw6k=?=\r\n",
+            "From: Ada <ada@example.com>\r\n",
+            "To: me@example.com, Bob <bob@example.com>\r\n",
+            "Date: Mon, 5 Jan 2026 10:00:00 +0000\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/mixed; boundary=\"b\"\r\n",
+            "\r\n",
+            "--b\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "\r\n",
+            "See attached.\r\n",
+            "--b\r\n",
+            "Content-Type: application/pdf; name=\"plan.pdf\"\r\n",
+            "Content-Disposition: attachment; filename=\"plan.pdf\"\r\n",
+            "\r\n",
+            "PDF\r\n",
+            "--b--\r\n",
+        );
+        let message = parse_email_message("INBOX", 7, raw.as_bytes(), None).expect("parsed");
+        assert_eq!(message.title, "Café");
+        assert_eq!(message.thread_id, "<root@example.com>");
+        assert_eq!(message.internal_date_ms, 1_767_607_200_000);
+        assert_eq!(
+            message.body_text.as_deref().map(str::trim),
+            Some("See attached.")
+        );
+        assert_eq!(message.attachments.len(), 1);
+        assert_eq!(message.attachments[0].filename, "plan.pdf");
+
+        let reply = original_message(message).reply("Thanks", true, Some("me@example.com"));
+        assert_eq!(reply.to, "Ada <ada@example.com>");
+        assert_eq!(reply.cc, "Bob <bob@example.com>");
+        assert_eq!(reply.in_reply_to.as_deref(), Some("<root@example.com>"));
+
+        let raw = reply.to_rfc822(&[("From", "me@example.com".to_string())], false);
+        assert!(raw.starts_with("From: me@example.com\r\n"));
+        assert!(!raw.contains("Bcc:"));
+    }
+}
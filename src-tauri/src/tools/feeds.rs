@@ -0,0 +1,282 @@
+//! `feeds.*` tools over the stored feed subscriptions, for building digests of new posts.
+
+use crate::db::{Db, FeedItem, FeedItemQuery, FeedOperations};
+use crate::feeds::poll_feed;
+use crate::tools::{
+    ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DEFAULT_UNREAD_LIMIT: u64 = 50;
+const MAX_UNREAD_LIMIT: u64 = 200;
+
+pub fn register_feed_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    register_list_tool(registry, db.clone())?;
+    register_unread_tool(registry, db.clone())?;
+    register_mark_read_tool(registry, db)
+}
+
+fn register_list_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: "feeds.list".to_string(),
+        description:
+            "List RSS/Atom/JSON Feed subscriptions with their unread counts and last poll status."
+                .to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {},
+            "additionalProperties": false
+        }),
+        result_schema: json!({
+            "type": "object",
+            "properties": {
+                "feeds": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "string" },
+                            "url": { "type": "string" },
+                            "title": { "type": ["string", "null"] },
+                            "site_url": { "type": ["string", "null"] },
+                            "enabled": { "type": "boolean" },
+                            "unread_count": { "type": "integer" },
+                            "last_polled": { "type": ["string", "null"] },
+                            "last_error": { "type": ["string", "null"] }
+                        },
+                        "required": ["id", "url", "enabled", "unread_count"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["feeds"],
+            "additionalProperties": false
+        }),
+        requires_approval: false,
+        result_mode: ToolResultMode::Inline,
+    };
+
+    let handler = Arc::new(move |_args: Value, _ctx: ToolExecutionContext| {
+        let feeds = db
+            .get_feed_subscriptions()
+            .map_err(|err| ToolError::new(format!("Failed to load feeds: {err}")))?;
+        let feeds = feeds
+            .into_iter()
+            .map(|feed| {
+                json!({
+                    "id": feed.id,
+                    "url": feed.url,
+                    "title": feed.title,
+                    "site_url": feed.site_url,
+                    "enabled": feed.enabled,
+                    "unread_count": feed.unread_count,
+                    "last_polled": feed.last_polled_at.and_then(to_rfc3339),
+                    "last_error": feed.last_error
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({ "feeds": feeds }))
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: None,
+    })
+}
+
+fn register_unread_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: "feeds.unread".to_string(),
+        description: "Get unread feed items, newest first, across all feeds or one feed. Set 'refresh' to poll the feeds before reading. Items stay unread until feeds.mark_read is called.".to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {
+                "feed_id": { "type": "string" },
+                "limit": { "type": "integer", "minimum": 1, "maximum": MAX_UNREAD_LIMIT },
+                "refresh": { "type": "boolean", "default": false }
+            },
+            "additionalProperties": false
+        }),
+        result_schema: json!({
+            "type": "object",
+            "properties": {
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "string" },
+                            "feed_id": { "type": "string" },
+                            "feed_title": { "type": ["string", "null"] },
+                            "title": { "type": "string" },
+                            "url": { "type": ["string", "null"] },
+                            "summary": { "type": ["string", "null"] },
+                            "author": { "type": ["string", "null"] },
+                            "published": { "type": ["string", "null"] }
+                        },
+                        "required": ["id", "feed_id", "title"],
+                        "additionalProperties": false
+                    }
+                },
+                "total_unread": { "type": "integer" },
+                "refresh_errors": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["items", "total_unread"],
+            "additionalProperties": false
+        }),
+        requires_approval: false,
+        result_mode: ToolResultMode::Auto,
+    };
+
+    let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+        let feed_id = args
+            .get("feed_id")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
+        let limit = args
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_UNREAD_LIMIT)
+            .clamp(1, MAX_UNREAD_LIMIT);
+        let refresh = args
+            .get("refresh")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let feeds = db
+            .get_feed_subscriptions()
+            .map_err(|err| ToolError::new(format!("Failed to load feeds: {err}")))?;
+        if let Some(feed_id) = &feed_id {
+            if !feeds.iter().any(|feed| &feed.id == feed_id) {
+                return Err(ToolError::not_found(format!("Unknown feed '{feed_id}'"))
+                    .with_hint("Use feeds.list to find feed ids."));
+            }
+        }
+
+        let mut refresh_errors = Vec::new();
+        if refresh {
+            for feed in feeds.iter().filter(|feed| {
+                feed.enabled && (feed_id.is_none() || feed_id.as_deref() == Some(feed.id.as_str()))
+            }) {
+                if let Err(err) = poll_feed(&db, feed) {
+                    refresh_errors.push(format!("{}: {err}", feed.url));
+                }
+            }
+        }
+
+        let query = FeedItemQuery {
+            feed_id: feed_id.clone(),
+            unread_only: true,
+            since: None,
+            limit: Some(limit as u32),
+        };
+        let items = db
+            .get_feed_items(&query)
+            .map_err(|err| ToolError::new(format!("Failed to load feed items: {err}")))?;
+        let total_unread = db
+            .count_unread_feed_items(feed_id.as_deref())
+            .map_err(|err| ToolError::new(format!("Failed to count feed items: {err}")))?;
+
+        let mut result = json!({
+            "items": items.iter().map(item_to_json).collect::<Vec<_>>(),
+            "total_unread": total_unread
+        });
+        if !refresh_errors.is_empty() {
+            result["refresh_errors"] = json!(refresh_errors);
+        }
+        Ok(result)
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: None,
+    })
+}
+
+fn register_mark_read_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: "feeds.mark_read".to_string(),
+        description: "Mark feed items as read: specific 'item_ids', every unread item of 'feed_id', or 'all'. 'before' (Unix ms) limits the feed or all form to older items.".to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {
+                "item_ids": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+                "feed_id": { "type": "string" },
+                "all": { "type": "boolean" },
+                "before": { "type": "integer" }
+            },
+            "additionalProperties": false
+        }),
+        result_schema: json!({
+            "type": "object",
+            "properties": {
+                "marked": { "type": "integer" }
+            },
+            "required": ["marked"],
+            "additionalProperties": false
+        }),
+        requires_approval: false,
+        result_mode: ToolResultMode::Inline,
+    };
+
+    let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+        let item_ids = args.get("item_ids").and_then(|v| v.as_array()).map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        });
+        let feed_id = args
+            .get("feed_id")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let all = args.get("all").and_then(|v| v.as_bool()).unwrap_or(false);
+        let before = args.get("before").and_then(|v| v.as_i64());
+
+        let selectors = [item_ids.is_some(), feed_id.is_some(), all]
+            .iter()
+            .filter(|selected| **selected)
+            .count();
+        if selectors != 1 {
+            return Err(ToolError::validation(
+                "Provide exactly one of 'item_ids', 'feed_id' or 'all'",
+            ));
+        }
+
+        let marked = match item_ids {
+            Some(ids) => db.mark_feed_items_read(&ids),
+            None => db.mark_feed_read(feed_id, before),
+        }
+        .map_err(|err| ToolError::new(format!("Failed to mark feed items read: {err}")))?;
+        Ok(json!({ "marked": marked }))
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: None,
+    })
+}
+
+fn item_to_json(item: &FeedItem) -> Value {
+    json!({
+        "id": item.id,
+        "feed_id": item.feed_id,
+        "feed_title": item.feed_title,
+        "title": item.title,
+        "url": item.url,
+        "summary": item.summary,
+        "author": item.author,
+        "published": to_rfc3339(item.published_at.unwrap_or(item.fetched_at))
+    })
+}
+
+fn to_rfc3339(ms: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp_millis(ms).map(|parsed| parsed.to_rfc3339())
+}
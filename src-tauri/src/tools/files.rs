@@ -164,7 +164,7 @@ fn register_read_tool(
         let path = require_string_arg(&args, "path")?;
         let vault_path = resolve_vault_path(&db, &path)?;
         let content = fs::read_to_string(&vault_path.full_path)
-            .map_err(|err| ToolError::new(format!("Failed to read file: {err}")))?;
+            .map_err(|err| ToolError::from_io("Failed to read file", err))?;
         Ok(json!({
             "path": vault_path.display_path,
             "content": content
@@ -216,7 +216,7 @@ fn register_read_range_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), S
         let path = require_string_arg(&args, "path")?;
         let start_line = args.get("start_line").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
         if start_line == 0 {
-            return Err(ToolError::new("Invalid 'start_line'"));
+            return Err(ToolError::validation("Invalid 'start_line'"));
         }
         let end_line = args
             .get("end_line")
@@ -233,14 +233,14 @@ fn register_read_range_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), S
 
         if let Some(end_line) = end_line {
             if end_line < start_line {
-                return Err(ToolError::new("Invalid line range"));
+                return Err(ToolError::validation("Invalid line range"));
             }
         }
         if max_lines == 0 {
-            return Err(ToolError::new("Invalid 'max_lines'"));
+            return Err(ToolError::validation("Invalid 'max_lines'"));
         }
         if max_chars == 0 {
-            return Err(ToolError::new("Invalid 'max_chars'"));
+            return Err(ToolError::validation("Invalid 'max_chars'"));
         }
 
         let requested_end_line =
@@ -248,7 +248,7 @@ fn register_read_range_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), S
 
         let vault_path = resolve_vault_path(&db, &path)?;
         let file = fs::File::open(&vault_path.full_path)
-            .map_err(|err| ToolError::new(format!("Failed to read file: {err}")))?;
+            .map_err(|err| ToolError::from_io("Failed to read file", err))?;
         let mut reader = BufReader::new(file);
 
         let mut line_no = 0usize;
@@ -261,7 +261,7 @@ fn register_read_range_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), S
             buf.clear();
             let bytes = reader
                 .read_line(&mut buf)
-                .map_err(|err| ToolError::new(format!("Failed to read file: {err}")))?;
+                .map_err(|err| ToolError::from_io("Failed to read file", err))?;
             if bytes == 0 {
                 break;
             }
@@ -350,7 +350,7 @@ fn register_search_replace_tool(registry: &mut ToolRegistry, db: Db) -> Result<(
         let edit = parse_search_replace_args(&args)?;
         let vault_path = resolve_vault_path(&handler_db, &edit.path)?;
         let original = fs::read_to_string(&vault_path.full_path)
-            .map_err(|err| ToolError::new(format!("Failed to read file: {err}")))?;
+            .map_err(|err| ToolError::from_io("Failed to read file", err))?;
         let (updated, replacements) = apply_search_replace(&original, &edit)?;
         if replacements > 0 {
             fs::write(&vault_path.full_path, updated.as_bytes())
@@ -368,7 +368,7 @@ fn register_search_replace_tool(registry: &mut ToolRegistry, db: Db) -> Result<(
         let edit = parse_search_replace_args(&args)?;
         let vault_path = resolve_vault_path(&preview_db, &edit.path)?;
         let original = fs::read_to_string(&vault_path.full_path)
-            .map_err(|err| ToolError::new(format!("Failed to read file: {err}")))?;
+            .map_err(|err| ToolError::from_io("Failed to read file", err))?;
         let (updated, replacements) = apply_search_replace(&original, &edit)?;
         let mut preview = build_diff_preview(&vault_path.display_path, &original, &updated);
         if let Some(obj) = preview.as_object_mut() {
@@ -602,7 +602,7 @@ fn register_edit_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String>
         let edit = parse_edit_args(&args)?;
         let vault_path = resolve_vault_path(&handler_db, &edit.path)?;
         let original = fs::read_to_string(&vault_path.full_path)
-            .map_err(|err| ToolError::new(format!("Failed to read file: {err}")))?;
+            .map_err(|err| ToolError::from_io("Failed to read file", err))?;
         let updated = apply_line_edit(&original, edit.start_line, edit.end_line, &edit.content)?;
         fs::write(&vault_path.full_path, updated.as_bytes())
             .map_err(|err| ToolError::new(format!("Failed to edit file: {err}")))?;
@@ -617,7 +617,7 @@ fn register_edit_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String>
         let edit = parse_edit_args(&args)?;
         let vault_path = resolve_vault_path(&preview_db, &edit.path)?;
         let original = fs::read_to_string(&vault_path.full_path)
-            .map_err(|err| ToolError::new(format!("Failed to read file: {err}")))?;
+            .map_err(|err| ToolError::from_io("Failed to read file", err))?;
         let updated = apply_line_edit(&original, edit.start_line, edit.end_line, &edit.content)?;
         Ok(build_diff_preview(
             &vault_path.display_path,
@@ -653,7 +653,7 @@ fn require_string_arg(args: &Value, key: &str) -> Result<String, ToolError> {
     args.get(key)
         .and_then(|value| value.as_str())
         .map(|value| value.to_string())
-        .ok_or_else(|| ToolError::new(format!("Missing or invalid '{key}'")))
+        .ok_or_else(|| ToolError::validation(format!("Missing or invalid '{key}'")))
 }
 
 fn optional_string_arg(args: &Value, key: &str) -> Option<String> {
@@ -670,14 +670,16 @@ fn parse_path_content_args(args: &Value) -> Result<(String, String), ToolError>
 
 fn parse_edit_args(args: &Value) -> Result<EditArgs, ToolError> {
     let path = require_string_arg(args, "path")?;
-    let start_line =
-        args.get("start_line")
-            .and_then(|value| value.as_u64())
-            .ok_or_else(|| ToolError::new("Missing or invalid 'start_line'"))? as usize;
-    let end_line =
-        args.get("end_line")
-            .and_then(|value| value.as_u64())
-            .ok_or_else(|| ToolError::new("Missing or invalid 'end_line'"))? as usize;
+    let start_line = args
+        .get("start_line")
+        .and_then(|value| value.as_u64())
+        .ok_or_else(|| ToolError::validation("Missing or invalid 'start_line'"))?
+        as usize;
+    let end_line = args
+        .get("end_line")
+        .and_then(|value| value.as_u64())
+        .ok_or_else(|| ToolError::validation("Missing or invalid 'end_line'"))?
+        as usize;
     let content = require_string_arg(args, "content")?;
     Ok(EditArgs {
         path,
@@ -725,7 +727,7 @@ fn apply_line_edit(
     replacement: &str,
 ) -> Result<String, ToolError> {
     if start_line == 0 || end_line == 0 || end_line < start_line {
-        return Err(ToolError::new("Invalid line range"));
+        return Err(ToolError::validation("Invalid line range"));
     }
 
     let has_trailing_newline = original.ends_with('\n');
@@ -762,7 +764,7 @@ fn build_search_replace_regex(edit: &SearchReplaceArgs) -> Result<Regex, ToolErr
     builder.case_insensitive(!edit.case_sensitive);
     builder
         .build()
-        .map_err(|err| ToolError::new(format!("Invalid search pattern: {err}")))
+        .map_err(|err| ToolError::validation(format!("Invalid search pattern: {err}")))
 }
 
 fn apply_search_replace(
@@ -799,7 +801,7 @@ fn read_optional_file(path: &Path) -> Result<String, ToolError> {
     if !path.exists() {
         return Ok(String::new());
     }
-    fs::read_to_string(path).map_err(|err| ToolError::new(format!("Failed to read file: {err}")))
+    fs::read_to_string(path).map_err(|err| ToolError::from_io("Failed to read file", err))
 }
 
 fn build_diff_preview(path: &str, before: &str, after: &str) -> Value {
@@ -0,0 +1,664 @@
+use serde_json::{json, Value};
+
+use super::integrations::{get_connection, required_str_arg, string_list_arg};
+use super::{
+    ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
+};
+use crate::db::Db;
+use crate::integrations::github::GitHubClient;
+
+const DEFAULT_MAX_RESULTS: u64 = 30;
+const MAX_RESULTS_LIMIT: u64 = 100;
+const DEFAULT_MAX_DIFF_CHARS: usize = 50_000;
+const MAX_BODY_CHARS: usize = 4_000;
+
+pub fn register_github_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let db_for_search = db.clone();
+    let db_for_issues = db.clone();
+    let db_for_pulls = db.clone();
+    let db_for_issue = db.clone();
+    let db_for_pull = db.clone();
+    let db_for_create = db.clone();
+    let db_for_comment = db.clone();
+    let db_for_notifications = db;
+
+    let search_issues = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "github.search_issues".to_string(),
+            description: "Search GitHub issues and pull requests. 'query' accepts GitHub search syntax (e.g. 'label:bug author:octocat'); repo, type and state are added as qualifiers.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "query": { "type": "string" },
+                    "repo": repo_schema(),
+                    "type": { "type": "string", "enum": ["issue", "pr"] },
+                    "state": { "type": "string", "enum": ["open", "closed"] },
+                    "max_results": max_results_schema()
+                },
+                "required": ["query"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "total_count": { "type": "integer" },
+                    "items": { "type": "array" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = github_client(&db_for_search, &args)?;
+            let query = search_query(&args)?;
+            let result = client.get_json(
+                "/search/issues",
+                &[
+                    ("q", query),
+                    ("per_page", max_results_arg(&args).to_string()),
+                ],
+            )?;
+            Ok(json!({
+                "total_count": result.get("total_count").cloned().unwrap_or(json!(0)),
+                "items": summaries(&result["items"])
+            }))
+        }),
+        preview: None,
+    };
+
+    let list_issues = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "github.list_issues".to_string(),
+            description: "List issues of a repository, most recently updated first. Pull requests are excluded.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "repo": repo_schema(),
+                    "state": state_schema(),
+                    "labels": { "type": "array", "items": { "type": "string" } },
+                    "assignee": { "type": "string", "description": "Optional. A login, 'none' or '*'." },
+                    "max_results": max_results_schema()
+                },
+                "required": ["repo"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "issues": { "type": "array" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = github_client(&db_for_issues, &args)?;
+            let repo = repo_arg(&args)?;
+            let mut query = vec![
+                ("state", optional_str_arg(&args, "state").unwrap_or("open").to_string()),
+                ("sort", "updated".to_string()),
+                ("per_page", max_results_arg(&args).to_string()),
+            ];
+            let labels = string_list_arg(&args, "labels");
+            if !labels.is_empty() {
+                query.push(("labels", labels.join(",")));
+            }
+            if let Some(assignee) = optional_str_arg(&args, "assignee") {
+                query.push(("assignee", assignee.to_string()));
+            }
+            let result = client.get_json(&format!("/repos/{repo}/issues"), &query)?;
+            let issues = result
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|item| item.get("pull_request").is_none())
+                .map(issue_summary)
+                .collect::<Vec<_>>();
+            Ok(json!({ "issues": issues }))
+        }),
+        preview: None,
+    };
+
+    let list_pull_requests = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "github.list_pull_requests".to_string(),
+            description: "List pull requests of a repository, most recently updated first."
+                .to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "repo": repo_schema(),
+                    "state": state_schema(),
+                    "base": { "type": "string", "description": "Optional. Base branch name." },
+                    "head": { "type": "string", "description": "Optional. 'user:branch'." },
+                    "max_results": max_results_schema()
+                },
+                "required": ["repo"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pull_requests": { "type": "array" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = github_client(&db_for_pulls, &args)?;
+            let repo = repo_arg(&args)?;
+            let mut query = vec![
+                (
+                    "state",
+                    optional_str_arg(&args, "state")
+                        .unwrap_or("open")
+                        .to_string(),
+                ),
+                ("sort", "updated".to_string()),
+                ("direction", "desc".to_string()),
+                ("per_page", max_results_arg(&args).to_string()),
+            ];
+            for key in ["base", "head"] {
+                if let Some(value) = optional_str_arg(&args, key) {
+                    query.push((key, value.to_string()));
+                }
+            }
+            let result = client.get_json(&format!("/repos/{repo}/pulls"), &query)?;
+            Ok(json!({ "pull_requests": summaries(&result) }))
+        }),
+        preview: None,
+    };
+
+    let get_issue = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "github.get_issue".to_string(),
+            description: "Read an issue with its body and comments.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "repo": repo_schema(),
+                    "number": { "type": "integer", "minimum": 1 }
+                },
+                "required": ["repo", "number"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "issue": { "type": "object" },
+                    "comments": { "type": "array" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = github_client(&db_for_issue, &args)?;
+            let repo = repo_arg(&args)?;
+            let number = number_arg(&args)?;
+            let issue = client.get_json(&format!("/repos/{repo}/issues/{number}"), &[])?;
+            let comments = issue_comments(&client, &repo, number)?;
+            let mut summary = issue_summary(&issue);
+            summary["body"] = json!(truncate(
+                issue["body"].as_str().unwrap_or(""),
+                MAX_BODY_CHARS
+            ));
+            Ok(json!({ "issue": summary, "comments": comments }))
+        }),
+        preview: None,
+    };
+
+    let get_pull_request = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "github.get_pull_request".to_string(),
+            description: "Read a pull request with its description, conversation and review comments, and optionally its unified diff.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "repo": repo_schema(),
+                    "number": { "type": "integer", "minimum": 1 },
+                    "include_diff": { "type": "boolean", "description": "Optional, default true." },
+                    "max_diff_chars": { "type": "integer", "minimum": 1 }
+                },
+                "required": ["repo", "number"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pull_request": { "type": "object" },
+                    "comments": { "type": "array" },
+                    "review_comments": { "type": "array" },
+                    "diff": { "type": "string" },
+                    "diff_truncated": { "type": "boolean" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = github_client(&db_for_pull, &args)?;
+            let repo = repo_arg(&args)?;
+            let number = number_arg(&args)?;
+            let path = format!("/repos/{repo}/pulls/{number}");
+            let pull = client.get_json(&path, &[])?;
+            let mut summary = issue_summary(&pull);
+            summary["body"] = json!(truncate(pull["body"].as_str().unwrap_or(""), MAX_BODY_CHARS));
+            for key in ["mergeable", "additions", "deletions", "changed_files"] {
+                summary[key] = pull.get(key).cloned().unwrap_or(Value::Null);
+            }
+            let comments = issue_comments(&client, &repo, number)?;
+            let review_comments = client
+                .get_json(
+                    &format!("{path}/comments"),
+                    &[("per_page", MAX_RESULTS_LIMIT.to_string())],
+                )?
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|comment| {
+                    let mut summary = comment_summary(comment);
+                    summary["path"] = comment.get("path").cloned().unwrap_or(Value::Null);
+                    summary["line"] = comment.get("line").cloned().unwrap_or(Value::Null);
+                    summary
+                })
+                .collect::<Vec<_>>();
+            let mut result = json!({
+                "pull_request": summary,
+                "comments": comments,
+                "review_comments": review_comments
+            });
+            if args.get("include_diff").and_then(|v| v.as_bool()).unwrap_or(true) {
+                let max_chars = args
+                    .get("max_diff_chars")
+                    .and_then(|v| v.as_u64())
+                    .map(|v| v as usize)
+                    .unwrap_or(DEFAULT_MAX_DIFF_CHARS);
+                let diff = client.get_text(&path, "application/vnd.github.diff")?;
+                result["diff_truncated"] = json!(diff.chars().count() > max_chars);
+                result["diff"] = json!(truncate(&diff, max_chars));
+            }
+            Ok(result)
+        }),
+        preview: None,
+    };
+
+    let create_issue = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "github.create_issue".to_string(),
+            description: "Open a new issue in a repository.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "repo": repo_schema(),
+                    "title": { "type": "string" },
+                    "body": { "type": "string", "description": "Optional. Markdown." },
+                    "labels": { "type": "array", "items": { "type": "string" } },
+                    "assignees": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["repo", "title"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "issue": { "type": "object" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = github_client(&db_for_create, &args)?;
+            let repo = repo_arg(&args)?;
+            let mut body = json!({ "title": required_str_arg(&args, "title")? });
+            if let Some(text) = args.get("body").and_then(|v| v.as_str()) {
+                body["body"] = json!(text);
+            }
+            for key in ["labels", "assignees"] {
+                let values = string_list_arg(&args, key);
+                if !values.is_empty() {
+                    body[key] = json!(values);
+                }
+            }
+            let issue = client.post_json(&format!("/repos/{repo}/issues"), &body)?;
+            Ok(json!({ "issue": issue_summary(&issue) }))
+        }),
+        preview: None,
+    };
+
+    let add_comment = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "github.add_comment".to_string(),
+            description: "Comment on an issue or pull request conversation.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "repo": repo_schema(),
+                    "number": { "type": "integer", "minimum": 1 },
+                    "body": { "type": "string", "description": "Markdown." }
+                },
+                "required": ["repo", "number", "body"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "comment": { "type": "object" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = github_client(&db_for_comment, &args)?;
+            let repo = repo_arg(&args)?;
+            let number = number_arg(&args)?;
+            let body = required_str_arg(&args, "body")?;
+            let comment = client.post_json(
+                &format!("/repos/{repo}/issues/{number}/comments"),
+                &json!({ "body": body }),
+            )?;
+            Ok(json!({ "comment": comment_summary(&comment) }))
+        }),
+        preview: None,
+    };
+
+    let list_notifications = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "github.list_notifications".to_string(),
+            description: "List the user's GitHub notifications, unread only unless 'all' is set."
+                .to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": connection_id_schema(),
+                    "all": { "type": "boolean" },
+                    "participating": { "type": "boolean", "description": "Optional. Only threads the user is directly involved in." },
+                    "since": { "type": "string", "description": "Optional. RFC 3339 timestamp." },
+                    "max_results": max_results_schema()
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "notifications": { "type": "array" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let client = github_client(&db_for_notifications, &args)?;
+            let mut query = vec![("per_page", max_results_arg(&args).to_string())];
+            for key in ["all", "participating"] {
+                if args.get(key).and_then(|v| v.as_bool()).unwrap_or(false) {
+                    query.push((key, "true".to_string()));
+                }
+            }
+            if let Some(since) = optional_str_arg(&args, "since") {
+                query.push(("since", since.to_string()));
+            }
+            let result = client.get_json("/notifications", &query)?;
+            let notifications = result
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(notification_summary)
+                .collect::<Vec<_>>();
+            Ok(json!({ "notifications": notifications }))
+        }),
+        preview: None,
+    };
+
+    registry.register(search_issues)?;
+    registry.register(list_issues)?;
+    registry.register(list_pull_requests)?;
+    registry.register(get_issue)?;
+    registry.register(get_pull_request)?;
+    registry.register(create_issue)?;
+    registry.register(add_comment)?;
+    registry.register(list_notifications)?;
+    Ok(())
+}
+
+fn connection_id_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Optional. Omit to use the default connected GitHub account."
+    })
+}
+
+fn repo_schema() -> Value {
+    json!({ "type": "string", "description": "Repository as 'owner/name'." })
+}
+
+fn state_schema() -> Value {
+    json!({ "type": "string", "enum": ["open", "closed", "all"] })
+}
+
+fn max_results_schema() -> Value {
+    json!({ "type": "integer", "minimum": 1, "maximum": MAX_RESULTS_LIMIT })
+}
+
+fn github_client(db: &Db, args: &Value) -> Result<GitHubClient, ToolError> {
+    let connection_id = args
+        .get("connection_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let connection = get_connection(db, connection_id, "github")?;
+    GitHubClient::load(db, &connection).map_err(|err| {
+        err.with_hint("Ask the user to check the GitHub connection in Settings > Integrations.")
+    })
+}
+
+fn optional_str_arg<'a>(args: &'a Value, key: &str) -> Option<&'a str> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn max_results_arg(args: &Value) -> u64 {
+    args.get("max_results")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_MAX_RESULTS)
+        .clamp(1, MAX_RESULTS_LIMIT)
+}
+
+fn repo_arg(args: &Value) -> Result<String, ToolError> {
+    let repo = required_str_arg(args, "repo")?.trim().trim_matches('/');
+    let valid = repo.split('/').count() == 2 && repo.split('/').all(|part| !part.is_empty());
+    if !valid {
+        return Err(ToolError::validation(
+            "'repo' must be a repository in the form 'owner/name'",
+        ));
+    }
+    Ok(repo.to_string())
+}
+
+fn number_arg(args: &Value) -> Result<u64, ToolError> {
+    args.get("number")
+        .and_then(|v| v.as_u64())
+        .filter(|number| *number > 0)
+        .ok_or_else(|| ToolError::validation("'number' must be a positive issue number"))
+}
+
+/// Builds the `q` parameter of `/search/issues` from the free-text query and filters.
+fn search_query(args: &Value) -> Result<String, ToolError> {
+    let mut parts = vec![required_str_arg(args, "query")?.trim().to_string()];
+    if optional_str_arg(args, "repo").is_some() {
+        parts.push(format!("repo:{}", repo_arg(args)?));
+    }
+    if let Some(kind) = optional_str_arg(args, "type") {
+        parts.push(format!("is:{kind}"));
+    }
+    if let Some(state) = optional_str_arg(args, "state") {
+        parts.push(format!("is:{state}"));
+    }
+    Ok(parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+fn issue_comments(client: &GitHubClient, repo: &str, number: u64) -> Result<Vec<Value>, ToolError> {
+    let comments = client.get_json(
+        &format!("/repos/{repo}/issues/{number}/comments"),
+        &[("per_page", MAX_RESULTS_LIMIT.to_string())],
+    )?;
+    Ok(comments
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(comment_summary)
+        .collect())
+}
+
+fn summaries(items: &Value) -> Vec<Value> {
+    items
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(issue_summary)
+        .collect()
+}
+
+/// Compact view of an issue or pull request; the raw API objects are mostly URLs.
+fn issue_summary(item: &Value) -> Value {
+    let is_pull_request = item.get("pull_request").is_some() || item.get("merged_at").is_some();
+    let labels = item["labels"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|label| label.get("name").and_then(|v| v.as_str()))
+        .collect::<Vec<_>>();
+    let assignees = item["assignees"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|user| user.get("login").and_then(|v| v.as_str()))
+        .collect::<Vec<_>>();
+    let mut summary = json!({
+        "number": item.get("number"),
+        "title": item.get("title"),
+        "type": if is_pull_request { "pr" } else { "issue" },
+        "state": item.get("state"),
+        "author": item["user"].get("login"),
+        "labels": labels,
+        "assignees": assignees,
+        "comments": item.get("comments"),
+        "created_at": item.get("created_at"),
+        "updated_at": item.get("updated_at"),
+        "url": item.get("html_url")
+    });
+    if let Some(repo) = repository_name(item) {
+        summary["repo"] = json!(repo);
+    }
+    if is_pull_request {
+        summary["draft"] = item.get("draft").cloned().unwrap_or(Value::Null);
+        summary["merged_at"] = item
+            .get("merged_at")
+            .or_else(|| item["pull_request"].get("merged_at"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        if let Some(head) = item["head"].get("ref") {
+            summary["head"] = head.clone();
+            summary["base"] = item["base"].get("ref").cloned().unwrap_or(Value::Null);
+        }
+    }
+    summary
+}
+
+/// `owner/name` from the `repository_url` that issue payloads carry.
+fn repository_name(item: &Value) -> Option<String> {
+    let url = item.get("repository_url")?.as_str()?;
+    let (_, repo) = url.split_once("/repos/")?;
+    Some(repo.to_string())
+}
+
+fn comment_summary(comment: &Value) -> Value {
+    json!({
+        "id": comment.get("id"),
+        "author": comment["user"].get("login"),
+        "body": truncate(comment["body"].as_str().unwrap_or(""), MAX_BODY_CHARS),
+        "created_at": comment.get("created_at"),
+        "url": comment.get("html_url")
+    })
+}
+
+fn notification_summary(thread: &Value) -> Value {
+    json!({
+        "id": thread.get("id"),
+        "repo": thread["repository"].get("full_name"),
+        "title": thread["subject"].get("title"),
+        "type": thread["subject"].get("type"),
+        "reason": thread.get("reason"),
+        "unread": thread.get("unread"),
+        "updated_at": thread.get("updated_at"),
+        "number": thread["subject"]["url"]
+            .as_str()
+            .and_then(|url| url.rsplit('/').next())
+            .and_then(|number| number.parse::<u64>().ok())
+    })
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_query_adds_qualifiers() {
+        let args = json!({
+            "query": "label:bug crash",
+            "repo": "octo/widgets/",
+            "type": "pr",
+            "state": "open"
+        });
+        assert_eq!(
+            search_query(&args).unwrap(),
+            "label:bug crash repo:octo/widgets is:pr is:open"
+        );
+        assert!(search_query(&json!({ "query": "x", "repo": "widgets" })).is_err());
+    }
+
+    #[test]
+    fn summaries_distinguish_issues_and_pull_requests() {
+        let issue = json!({
+            "number": 7,
+            "title": "Crash on start",
+            "state": "open",
+            "user": { "login": "octocat" },
+            "labels": [{ "name": "bug" }],
+            "assignees": [],
+            "repository_url": "https://api.github.com/repos/octo/widgets"
+        });
+        let summary = issue_summary(&issue);
+        assert_eq!(summary["type"], "issue");
+        assert_eq!(summary["repo"], "octo/widgets");
+        assert_eq!(summary["labels"], json!(["bug"]));
+        assert!(summary.get("draft").is_none());
+
+        let pull = json!({
+            "number": 8,
+            "draft": true,
+            "merged_at": null,
+            "head": { "ref": "fix-crash" },
+            "base": { "ref": "main" }
+        });
+        let summary = issue_summary(&pull);
+        assert_eq!(summary["type"], "pr");
+        assert_eq!(summary["head"], "fix-crash");
+        assert_eq!(summary["base"], "main");
+        assert_eq!(truncate("abcdef", 3), "abc…");
+    }
+}
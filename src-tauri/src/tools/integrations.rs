@@ -1,28 +1,40 @@
-use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
 use base64::Engine as _;
-use reqwest::blocking::Client;
+use chrono::{Datelike, NaiveTime, TimeZone};
+use reqwest::blocking::{Client, RequestBuilder};
 use serde_json::{json, Value};
-use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+use uuid::Uuid;
 
+use super::vault::{ensure_parent_dirs, resolve_vault_path};
 use super::{
     ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
 };
 use crate::db::{
-    Db, IntegrationConnection, IntegrationConnectionOperations, PreferenceOperations,
-    UpdateIntegrationConnectionInput,
+    Db, GmailCacheQuery, IntegrationCacheOperations, IntegrationConnection,
+    IntegrationConnectionOperations, SYNC_RESOURCE_CALENDAR_PREFIX, SYNC_RESOURCE_GMAIL,
+    SYNC_RESOURCE_TODOIST,
 };
-use crate::oauth::{google_oauth_config, google_oauth_env_configured, refresh_google_token};
+use crate::integrations::endpoints::{
+    GMAIL_API, GOOGLE_CALENDAR_API, TODOIST_REST_API, TODOIST_SYNC_API,
+};
+use crate::integrations::sync::{fresh_cache_synced_at, CALENDAR_SYNC_WINDOW_DAYS};
+use crate::integrations::{integration_registry, load_integration_settings};
+use crate::oauth::{ensure_access_token, missing_scopes, TokenError};
+
+const GMAIL_READONLY_SCOPE: &str = "https://www.googleapis.com/auth/gmail.readonly";
+const GMAIL_SEND_SCOPE: &str = "https://www.googleapis.com/auth/gmail.send";
+const GMAIL_COMPOSE_SCOPE: &str = "https://www.googleapis.com/auth/gmail.compose";
+const GMAIL_MODIFY_SCOPE: &str = "https://www.googleapis.com/auth/gmail.modify";
+const CALENDAR_EVENTS_SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+const TODOIST_DELETE_SCOPE: &str = "data:delete";
 
+/// Registers the action tools of every available integration plugin.
 pub fn register_integration_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
-    if google_oauth_env_configured() {
-        register_gmail_tools(registry, db.clone())?;
-        register_google_calendar_tools(registry, db.clone())?;
-    }
-    register_todoist_tools(registry, db)?;
-    Ok(())
+    integration_registry().register_tools(registry, db)
 }
 
-fn get_connection(
+pub fn get_connection(
     db: &Db,
     connection_id: &str,
     expected_integration: &str,
@@ -56,7 +68,7 @@ fn get_connection(
             return Ok(connection);
         }
 
-        return Err(ToolError::new("Integration connection not found"));
+        return Err(ToolError::not_found("Integration connection not found"));
     }
 
     if let Some(connection) = connections.iter().find(|item| item.id == connection_id) {
@@ -102,10 +114,10 @@ fn get_connection(
         return Ok(connection);
     }
 
-    Err(ToolError::new("Integration connection not found"))
+    Err(ToolError::not_found("Integration connection not found"))
 }
 
-fn get_access_token(connection: &IntegrationConnection) -> Result<String, ToolError> {
+pub fn get_access_token(connection: &IntegrationConnection) -> Result<String, ToolError> {
     let token = connection.access_token.clone().unwrap_or_default();
     if token.is_empty() {
         return Err(ToolError::new(
@@ -115,102 +127,301 @@ fn get_access_token(connection: &IntegrationConnection) -> Result<String, ToolEr
     Ok(token)
 }
 
-fn get_google_access_token(
+pub fn get_google_access_token(
     db: &Db,
     connection: &IntegrationConnection,
 ) -> Result<String, ToolError> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|err| ToolError::new(format!("Time error: {err}")))?
-        .as_millis() as i64;
+    ensure_access_token(db, connection, "google").map_err(|err| match err {
+        TokenError::Reauthorize(message) => ToolError::permission(message)
+            .with_hint("Ask the user to reconnect the Google integration."),
+        TokenError::Failed(message) => ToolError::upstream(message),
+    })
+}
 
-    let token = connection.access_token.clone().unwrap_or_default();
-    let expires_at = connection.expires_at.unwrap_or(0);
-    let has_refresh = connection
-        .refresh_token
-        .as_ref()
-        .map(|token| !token.trim().is_empty())
-        .unwrap_or(false);
-
-    let needs_refresh = token.trim().is_empty() || (expires_at > 0 && expires_at <= now + 60_000);
-
-    if needs_refresh {
-        log::info!(
-            "[oauth] refreshing Google access token: connection_id={} integration_id={} expires_at={} now={} has_refresh={}",
-            connection.id,
-            connection.integration_id,
-            expires_at,
-            now,
-            has_refresh
-        );
-        let refresh_token = connection
-            .refresh_token
-            .clone()
-            .ok_or_else(|| ToolError::new("Missing refresh token for Google integration"))?;
-        let config = google_oauth_config().map_err(ToolError::new)?;
-        let refreshed = refresh_google_token(&config, &refresh_token).map_err(|err| {
-            log::warn!(
-                "[oauth] refresh failed: connection_id={} integration_id={} error={}",
-                connection.id,
-                connection.integration_id,
-                err
-            );
-            ToolError::new(err)
-        })?;
-        let new_access_token = refreshed.access_token.clone();
-        let new_refresh_token = refreshed.refresh_token.clone().unwrap_or(refresh_token);
-        let new_expires_at = refreshed.expires_in.map(|seconds| now + seconds * 1000);
-
-        let _ = IntegrationConnectionOperations::update_integration_connection(
-            db,
-            &UpdateIntegrationConnectionInput {
-                id: connection.id.clone(),
-                account_label: None,
-                status: Some("connected".to_string()),
-                auth_type: None,
-                access_token: Some(new_access_token.clone()),
-                refresh_token: Some(new_refresh_token),
-                scopes: None,
-                expires_at: new_expires_at,
-                last_error: Some(String::new()),
-                last_sync_at: None,
-            },
-        );
+/// Fails with a permission error naming the scopes the connection still needs, so the
+/// user can upgrade access instead of the API call failing with a bare 403.
+fn require_scopes(connection: &IntegrationConnection, required: &[&str]) -> Result<(), ToolError> {
+    let missing = missing_scopes(connection.scopes.as_deref(), required);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(ToolError::permission(format!(
+        "The {} connection has not granted: {}",
+        connection.integration_id,
+        missing.join(", ")
+    ))
+    .with_hint(
+        "Ask the user to grant the additional access from Settings > Integrations (upgrade permissions), then retry.",
+    ))
+}
 
-        log::info!(
-            "[oauth] refresh succeeded: connection_id={} integration_id={} expires_at={}",
-            connection.id,
-            connection.integration_id,
-            new_expires_at.unwrap_or(0)
-        );
-        return Ok(new_access_token);
+fn source_arg_schema() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["auto", "cache", "live"],
+        "description": "Optional. 'auto' (default) reads the local sync cache when it is fresh, 'cache' always reads it (works offline), 'live' always calls the API."
+    })
+}
+
+fn read_source(args: &Value) -> &str {
+    args.get("source")
+        .and_then(|v| v.as_str())
+        .unwrap_or("auto")
+}
+
+/// Sync time of the cached copy to answer from, or `None` to call the live API.
+fn cache_synced_at(
+    db: &Db,
+    source: &str,
+    connection_id: &str,
+    resource: &str,
+) -> Result<Option<i64>, ToolError> {
+    match source {
+        "live" => Ok(None),
+        "cache" => db
+            .get_integration_sync_state(connection_id, resource)
+            .map_err(|err| ToolError::new(format!("Failed to load sync state: {err}")))?
+            .and_then(|state| state.last_synced_at)
+            .map(Some)
+            .ok_or_else(|| {
+                ToolError::not_found("Nothing has been synced for this account yet").with_hint(
+                    "Retry with source 'live', or wait for the background sync to finish.",
+                )
+            }),
+        _ => Ok(fresh_cache_synced_at(db, connection_id, resource)),
     }
+}
 
-    if token.trim().is_empty() && !has_refresh {
+fn mark_cache_stale(db: &Db, connection_id: &str, resource_prefix: &str) {
+    if let Err(err) = db.mark_integration_sync_stale(connection_id, resource_prefix) {
         log::warn!(
-            "[oauth] missing access and refresh tokens: connection_id={} integration_id={}",
-            connection.id,
-            connection.integration_id
+            "[sync] failed to mark {} cache stale for {}: {}",
+            resource_prefix,
+            connection_id,
+            err
         );
-        return Err(ToolError::new(
-            "Integration connection is missing access and refresh tokens",
-        ));
     }
+}
 
-    log::debug!(
-        "[oauth] using cached access token: connection_id={} integration_id={} expires_at={}",
-        connection.id,
-        connection.integration_id,
-        expires_at
-    );
-    Ok(token)
+/// Accepts RFC 3339 timestamps or `YYYY-MM-DD` dates (local midnight).
+pub(super) fn parse_time_arg(value: &str) -> Option<i64> {
+    if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.timestamp_millis());
+    }
+    let midnight = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(0, 0, 0)?;
+    chrono::TimeZone::from_local_datetime(&chrono::Local, &midnight)
+        .earliest()
+        .map(|parsed| parsed.timestamp_millis())
+}
+
+fn format_time_ms(value: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp_millis(value).map(|parsed| parsed.to_rfc3339())
+}
+
+/// Thread listing from the cache. Search queries need Gmail's own parser, so requests with
+/// `query` or `page_token` always go to the live API.
+fn cached_gmail_threads(
+    db: &Db,
+    args: &Value,
+    connection_id: &str,
+) -> Result<Option<Value>, ToolError> {
+    let source = read_source(args);
+    let needs_live = args.get("query").and_then(|v| v.as_str()).is_some()
+        || args.get("page_token").and_then(|v| v.as_str()).is_some();
+    if needs_live {
+        if source == "cache" {
+            return Err(ToolError::validation(
+                "Gmail search queries and paging are not supported on the local cache",
+            )
+            .with_hint("Use gmail.search_cache for offline search, or source 'live'."));
+        }
+        return Ok(None);
+    }
+    let Some(synced_at) = cache_synced_at(db, source, connection_id, SYNC_RESOURCE_GMAIL)? else {
+        return Ok(None);
+    };
+
+    let label_ids: Vec<String> = args
+        .get("label_ids")
+        .and_then(|v| v.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let max_results = args
+        .get("max_results")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(100) as usize;
+    let query = GmailCacheQuery {
+        label_id: label_ids.first().cloned(),
+        limit: (max_results * 10).min(5_000) as u32,
+        ..GmailCacheQuery::default()
+    };
+    let messages = db
+        .search_cached_gmail_messages(connection_id, &query)
+        .map_err(|err| ToolError::new(format!("Failed to read Gmail cache: {err}")))?;
+
+    let mut seen = std::collections::HashSet::new();
+    let mut threads = Vec::new();
+    for message in messages {
+        if !label_ids
+            .iter()
+            .all(|label| message.label_ids.contains(label))
+        {
+            continue;
+        }
+        // Like the API, hide spam and trash unless a label asks for them.
+        if label_ids.is_empty()
+            && message
+                .label_ids
+                .iter()
+                .any(|label| label == "SPAM" || label == "TRASH")
+        {
+            continue;
+        }
+        if !seen.insert(message.thread_id.clone()) {
+            continue;
+        }
+        threads.push(json!({
+            "id": message.thread_id,
+            "snippet": message.snippet,
+            "subject": message.subject,
+            "from": message.from,
+            "date": format_time_ms(message.internal_date),
+        }));
+        if threads.len() >= max_results {
+            break;
+        }
+    }
+    Ok(Some(json!({
+        "threads": threads,
+        "source": "cache",
+        "synced_at": format_time_ms(synced_at),
+    })))
+}
+
+/// Events from the cache when every requested calendar is synced and the range starts
+/// inside the synced window.
+fn cached_calendar_events(
+    db: &Db,
+    args: &Value,
+    connection_id: &str,
+    calendar_ids: &[String],
+) -> Result<Option<Value>, ToolError> {
+    let source = read_source(args);
+    if source == "live" {
+        return Ok(None);
+    }
+    let time_arg = |key: &str| -> Result<Option<i64>, ToolError> {
+        match args.get(key).and_then(|v| v.as_str()) {
+            None => Ok(None),
+            Some(value) => parse_time_arg(value)
+                .map(Some)
+                .ok_or_else(|| ToolError::validation(format!("Invalid '{key}': {value}"))),
+        }
+    };
+    let (time_min, time_max) = match (time_arg("time_min"), time_arg("time_max")) {
+        (Ok(time_min), Ok(time_max)) => (time_min, time_max),
+        (Err(err), _) | (_, Err(err)) => {
+            if source == "cache" {
+                return Err(err);
+            }
+            return Ok(None);
+        }
+    };
+    if source == "auto" {
+        let window_start =
+            chrono::Utc::now().timestamp_millis() - CALENDAR_SYNC_WINDOW_DAYS * 24 * 60 * 60 * 1000;
+        if time_min
+            .map(|time_min| time_min < window_start)
+            .unwrap_or(true)
+        {
+            return Ok(None);
+        }
+    }
+    let text = args.get("query").and_then(|v| v.as_str());
+    let max_results = args
+        .get("max_results")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(250) as u32;
+
+    let mut grouped = Vec::new();
+    for calendar_id in calendar_ids {
+        let resource = format!("{SYNC_RESOURCE_CALENDAR_PREFIX}{calendar_id}");
+        let Some(synced_at) = cache_synced_at(db, source, connection_id, &resource)? else {
+            return Ok(None);
+        };
+        let events = db
+            .get_cached_calendar_events(
+                connection_id,
+                calendar_id,
+                time_min,
+                time_max,
+                text,
+                max_results,
+            )
+            .map_err(|err| ToolError::new(format!("Failed to read Calendar cache: {err}")))?;
+        grouped.push(json!({
+            "calendar_id": calendar_id,
+            "events": events.into_iter().map(|event| event.raw).collect::<Vec<_>>(),
+            "source": "cache",
+            "synced_at": format_time_ms(synced_at),
+        }));
+    }
+    Ok(Some(json!({ "calendars": grouped })))
+}
+
+/// Open tasks from the cache. Filter expressions and the section, label and id filters are
+/// only evaluated by the API.
+fn cached_todoist_tasks(
+    db: &Db,
+    args: &Value,
+    connection_id: &str,
+) -> Result<Option<Value>, ToolError> {
+    let source = read_source(args);
+    let needs_live = ["filter", "section_id", "label", "ids"]
+        .iter()
+        .any(|key| args.get(*key).is_some_and(|value| !value.is_null()));
+    if needs_live {
+        if source == "cache" {
+            return Err(ToolError::validation(
+                "Todoist filters are not supported on the local cache",
+            ));
+        }
+        return Ok(None);
+    }
+    if cache_synced_at(db, source, connection_id, SYNC_RESOURCE_TODOIST)?.is_none() {
+        return Ok(None);
+    }
+    let project_id = args.get("project_id").and_then(|v| v.as_str());
+    let tasks = db
+        .get_cached_todoist_tasks(connection_id, project_id)
+        .map_err(|err| ToolError::new(format!("Failed to read Todoist cache: {err}")))?;
+    Ok(Some(Value::Array(
+        tasks.into_iter().map(|task| task.raw).collect(),
+    )))
 }
 
-fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+pub fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
     let db_for_list = db.clone();
     let db_for_get = db.clone();
     let db_for_labels = db.clone();
     let db_for_send = db.clone();
+    let db_for_search = db.clone();
+    let db_for_drafts = db.clone();
+    let db_for_create_draft = db.clone();
+    let db_for_update_draft = db.clone();
+    let db_for_send_draft = db.clone();
+    let db_for_reply = db.clone();
+    let db_for_forward = db.clone();
+    let db_for_modify = db.clone();
+    let db_for_attachment = db.clone();
     let list_threads = ToolDefinition {
         metadata: ToolMetadata {
             name: "gmail.list_threads".to_string(),
@@ -225,7 +436,8 @@ fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Strin
                     "query": { "type": "string" },
                     "label_ids": { "type": "array", "items": { "type": "string" } },
                     "max_results": { "type": "integer", "minimum": 1, "maximum": 500 },
-                    "page_token": { "type": "string" }
+                    "page_token": { "type": "string" },
+                    "source": source_arg_schema()
                 }
             }),
             result_schema: json!({
@@ -244,10 +456,14 @@ fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Strin
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
             let connection = get_connection(&db_for_list, connection_id, "gmail")?;
+            if let Some(cached) = cached_gmail_threads(&db_for_list, &args, &connection.id)? {
+                return Ok(cached);
+            }
             let token = get_google_access_token(&db_for_list, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_list, &connection.id);
 
             let client = Client::new();
-            let mut request = client.get("https://gmail.googleapis.com/gmail/v1/users/me/threads");
+            let mut request = client.get(format!("{api}/users/me/threads"));
             if let Some(query) = args.get("query").and_then(|v| v.as_str()) {
                 request = request.query(&[("q", query)]);
             }
@@ -268,10 +484,13 @@ fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Strin
             let response = request
                 .bearer_auth(token)
                 .send()
-                .map_err(|err| ToolError::new(format!("Failed to call Gmail API: {err}")))?;
+                .map_err(|err| ToolError::upstream(format!("Failed to call Gmail API: {err}")))?;
             let status = response.status();
             if !status.is_success() {
-                return Err(ToolError::new(format!("Gmail API error: HTTP {status}")));
+                return Err(ToolError::from_http_response(
+                    &response,
+                    format!("Gmail API error: HTTP {status}"),
+                ));
             }
 
             response
@@ -310,12 +529,13 @@ fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Strin
                 .unwrap_or("");
             let thread_id = args.get("thread_id").and_then(|v| v.as_str()).unwrap_or("");
             if thread_id.trim().is_empty() {
-                return Err(ToolError::new("Missing 'thread_id'"));
+                return Err(ToolError::validation("Missing 'thread_id'"));
             }
             let connection = get_connection(&db_for_get, connection_id, "gmail")?;
             let token = get_google_access_token(&db_for_get, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_get, &connection.id);
 
-            let url = format!("https://gmail.googleapis.com/gmail/v1/users/me/threads/{thread_id}");
+            let url = format!("{api}/users/me/threads/{thread_id}");
             let client = Client::new();
             let mut request = client.get(url);
 
@@ -325,10 +545,13 @@ fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Strin
             let response = request
                 .bearer_auth(token)
                 .send()
-                .map_err(|err| ToolError::new(format!("Failed to call Gmail API: {err}")))?;
+                .map_err(|err| ToolError::upstream(format!("Failed to call Gmail API: {err}")))?;
             let status = response.status();
             if !status.is_success() {
-                return Err(ToolError::new(format!("Gmail API error: HTTP {status}")));
+                return Err(ToolError::from_http_response(
+                    &response,
+                    format!("Gmail API error: HTTP {status}"),
+                ));
             }
 
             let raw = response
@@ -372,17 +595,21 @@ fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Strin
                 .unwrap_or("");
             let connection = get_connection(&db_for_labels, connection_id, "gmail")?;
             let token = get_google_access_token(&db_for_labels, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_labels, &connection.id);
 
             let client = Client::new();
             let response = client
-                .get("https://gmail.googleapis.com/gmail/v1/users/me/labels")
+                .get(format!("{api}/users/me/labels"))
                 .bearer_auth(token)
                 .send()
-                .map_err(|err| ToolError::new(format!("Failed to call Gmail API: {err}")))?;
+                .map_err(|err| ToolError::upstream(format!("Failed to call Gmail API: {err}")))?;
 
             let status = response.status();
             if !status.is_success() {
-                return Err(ToolError::new(format!("Gmail API error: HTTP {status}")));
+                return Err(ToolError::from_http_response(
+                    &response,
+                    format!("Gmail API error: HTTP {status}"),
+                ));
             }
 
             response
@@ -427,630 +654,2982 @@ fn register_gmail_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Strin
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
             let connection = get_connection(&db_for_send, connection_id, "gmail")?;
+            require_scopes(&connection, &[GMAIL_SEND_SCOPE])?;
             let token = get_google_access_token(&db_for_send, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_send, &connection.id);
 
-            let to = args
-                .get("to")
-                .and_then(|v| v.as_array())
-                .ok_or_else(|| ToolError::new("Missing 'to'"))?;
-            let to_list = to
-                .iter()
-                .filter_map(|v| v.as_str())
-                .collect::<Vec<_>>()
-                .join(", ");
-            let cc_list = args
-                .get("cc")
-                .and_then(|v| v.as_array())
-                .map(|list| {
-                    list.iter()
-                        .filter_map(|v| v.as_str())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                })
-                .unwrap_or_default();
-            let bcc_list = args
-                .get("bcc")
-                .and_then(|v| v.as_array())
-                .map(|list| {
-                    list.iter()
-                        .filter_map(|v| v.as_str())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                })
-                .unwrap_or_default();
-            let subject = args.get("subject").and_then(|v| v.as_str()).unwrap_or("");
-            let body = args.get("body").and_then(|v| v.as_str()).unwrap_or("");
-
-            let mut headers = Vec::new();
-            headers.push(format!("To: {to_list}"));
-            if !cc_list.is_empty() {
-                headers.push(format!("Cc: {cc_list}"));
-            }
-            if !bcc_list.is_empty() {
-                headers.push(format!("Bcc: {bcc_list}"));
+            let mail = OutgoingMail::from_args(&args);
+            if mail.to.is_empty() {
+                return Err(ToolError::validation("Missing 'to'"));
             }
-            headers.push(format!("Subject: {subject}"));
-            headers.push("MIME-Version: 1.0".to_string());
-            headers.push("Content-Type: text/plain; charset=\"UTF-8\"".to_string());
-
-            let raw_email = format!("{}\r\n\r\n{}", headers.join("\r\n"), body);
-            let encoded = URL_SAFE_NO_PAD.encode(raw_email.as_bytes());
+            let sent = deliver_gmail(&api, &token, &mail, None, false)?;
+            mark_cache_stale(&db_for_send, &connection.id, SYNC_RESOURCE_GMAIL);
+            Ok(sent)
+        }),
+        preview: None,
+    };
 
-            let client = Client::new();
-            let response = client
-                .post("https://gmail.googleapis.com/gmail/v1/users/me/messages/send")
-                .bearer_auth(token)
-                .json(&json!({ "raw": encoded }))
-                .send()
-                .map_err(|err| ToolError::new(format!("Failed to call Gmail API: {err}")))?;
+    let search_cache = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.search_cache".to_string(),
+            description: "Search the locally synced copy of Gmail message metadata (sender, recipients, subject, snippet, labels). Works offline; bodies are not cached, use gmail.get_thread for those.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "from": { "type": "string", "description": "Substring of the From header." },
+                    "to": { "type": "string", "description": "Substring of the To or Cc headers." },
+                    "text": { "type": "string", "description": "Substring of the subject or snippet." },
+                    "label_id": { "type": "string" },
+                    "after": { "type": "string", "description": "RFC 3339 timestamp or YYYY-MM-DD." },
+                    "before": { "type": "string", "description": "RFC 3339 timestamp or YYYY-MM-DD." },
+                    "max_results": { "type": "integer", "minimum": 1, "maximum": 500 }
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "messages": { "type": "array" },
+                    "source": { "type": "string" },
+                    "synced_at": { "type": "string" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_search, connection_id, "gmail")?;
+            let synced_at = db_for_search
+                .get_integration_sync_state(&connection.id, SYNC_RESOURCE_GMAIL)
+                .map_err(|err| ToolError::new(format!("Failed to load sync state: {err}")))?
+                .and_then(|state| state.last_synced_at)
+                .ok_or_else(|| {
+                    ToolError::not_found("Gmail has not been synced for this account yet")
+                        .with_hint("Use gmail.list_threads with a query to search the live mailbox.")
+                })?;
 
-            let status = response.status();
-            if !status.is_success() {
-                return Err(ToolError::new(format!("Gmail API error: HTTP {status}")));
-            }
+            let str_arg = |key: &str| {
+                args.get(key)
+                    .and_then(|v| v.as_str())
+                    .filter(|value| !value.trim().is_empty())
+                    .map(str::to_string)
+            };
+            let time_arg = |key: &str| -> Result<Option<i64>, ToolError> {
+                match str_arg(key) {
+                    None => Ok(None),
+                    Some(value) => parse_time_arg(&value)
+                        .map(Some)
+                        .ok_or_else(|| ToolError::validation(format!("Invalid '{key}': {value}"))),
+                }
+            };
+            let query = GmailCacheQuery {
+                from: str_arg("from"),
+                to: str_arg("to"),
+                text: str_arg("text"),
+                label_id: str_arg("label_id"),
+                after_ms: time_arg("after")?,
+                before_ms: time_arg("before")?,
+                limit: args
+                    .get("max_results")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(50)
+                    .min(500) as u32,
+            };
+            let messages = db_for_search
+                .search_cached_gmail_messages(&connection.id, &query)
+                .map_err(|err| ToolError::new(format!("Failed to read Gmail cache: {err}")))?;
 
-            response
-                .json::<Value>()
-                .map_err(|err| ToolError::new(format!("Failed to parse Gmail response: {err}")))
+            Ok(json!({
+                "messages": messages
+                    .into_iter()
+                    .map(|message| json!({
+                        "id": message.id,
+                        "thread_id": message.thread_id,
+                        "date": format_time_ms(message.internal_date),
+                        "from": message.from,
+                        "to": message.to,
+                        "subject": message.subject,
+                        "snippet": message.snippet,
+                        "label_ids": message.label_ids,
+                    }))
+                    .collect::<Vec<_>>(),
+                "source": "cache",
+                "synced_at": format_time_ms(synced_at),
+            }))
         }),
         preview: None,
     };
 
-    registry.register(list_threads)?;
-    registry.register(get_thread)?;
-    registry.register(list_labels)?;
-    registry.register(send_message)?;
-    Ok(())
-}
-
-fn minify_gmail_thread(raw: Value, mode: &str, max_messages: Option<usize>) -> Value {
-    let thread_id = raw
-        .get("id")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let messages = raw
-        .get("messages")
-        .and_then(|v| v.as_array())
-        .cloned()
-        .unwrap_or_default();
-
-    let mut parsed = messages
-        .into_iter()
-        .filter_map(|message| parse_gmail_message(&thread_id, message))
-        .collect::<Vec<_>>();
+    let list_drafts = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.list_drafts".to_string(),
+            description: "List Gmail drafts with their recipients and subject.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "max_results": { "type": "integer", "minimum": 1, "maximum": 50 }
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "drafts": { "type": "array" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_drafts, connection_id, "gmail")?;
+            require_scopes(&connection, &[GMAIL_COMPOSE_SCOPE])?;
+            let token = get_google_access_token(&db_for_drafts, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_drafts, &connection.id);
+            let max_results = args
+                .get("max_results")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(20)
+                .min(50);
 
-    parsed.sort_by(|a, b| a.internal_date_ms.cmp(&b.internal_date_ms));
-    if mode == "latest" {
-        if let Some(last) = parsed.pop() {
-            return json!({
-                "thread_id": thread_id,
-                "mode": "latest",
-                "message": last
-            });
-        }
-        return json!({
-            "thread_id": thread_id,
-            "mode": "latest",
-            "message": null
-        });
-    }
+            let client = Client::new();
+            let listed = gmail_json(
+                client
+                    .get(format!("{api}/users/me/drafts"))
+                    .query(&[("maxResults", max_results.to_string())])
+                    .bearer_auth(&token)
+                    .send(),
+            )?;
+            let mut drafts = Vec::new();
+            for item in listed
+                .get("drafts")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+            {
+                let Some(draft_id) = item.get("id").and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                let draft = gmail_json(
+                    client
+                        .get(format!("{api}/users/me/drafts/{draft_id}"))
+                        .query(&[("format", "metadata")])
+                        .bearer_auth(&token)
+                        .send(),
+                )?;
+                let message = draft.get("message").cloned().unwrap_or_else(|| json!({}));
+                let payload = message.get("payload").cloned().unwrap_or_else(|| json!({}));
+                let headers = extract_headers(&payload);
+                drafts.push(json!({
+                    "draft_id": draft_id,
+                    "message_id": message.get("id"),
+                    "thread_id": message.get("threadId"),
+                    "to": headers.get("to"),
+                    "cc": headers.get("cc"),
+                    "subject": headers.get("subject"),
+                    "snippet": message.get("snippet"),
+                }));
+            }
+            Ok(json!({ "drafts": drafts }))
+        }),
+        preview: None,
+    };
 
-    if let Some(max) = max_messages {
-        if parsed.len() > max {
-            parsed = parsed.into_iter().rev().take(max).collect::<Vec<_>>();
-            parsed.sort_by(|a, b| a.internal_date_ms.cmp(&b.internal_date_ms));
-        }
-    }
+    let create_draft = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.create_draft".to_string(),
+            description: "Save a new Gmail draft for the user to review and send. Use gmail.reply or gmail.forward with draft=true to draft inside an existing thread.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "to": { "type": "array", "items": { "type": "string" } },
+                    "cc": { "type": "array", "items": { "type": "string" } },
+                    "bcc": { "type": "array", "items": { "type": "string" } },
+                    "subject": { "type": "string" },
+                    "body": { "type": "string" }
+                },
+                "required": ["body"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "message": { "type": "object" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_create_draft, connection_id, "gmail")?;
+            require_scopes(&connection, &[GMAIL_COMPOSE_SCOPE])?;
+            let token = get_google_access_token(&db_for_create_draft, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_create_draft, &connection.id);
 
-    json!({
-        "thread_id": thread_id,
-        "mode": "all",
-        "messages": parsed
-    })
-}
+            let draft = deliver_gmail(&api, &token, &OutgoingMail::from_args(&args), None, true)?;
+            mark_cache_stale(&db_for_create_draft, &connection.id, SYNC_RESOURCE_GMAIL);
+            Ok(draft)
+        }),
+        preview: None,
+    };
 
-fn parse_gmail_message(thread_id: &str, message: Value) -> Option<GmailMessageSummary> {
-    let message_id = message.get("id").and_then(|v| v.as_str())?.to_string();
-    let internal_date_ms = message
-        .get("internalDate")
+    let update_draft = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.update_draft".to_string(),
+            description:
+                "Edit a Gmail draft. Only the given fields change; the draft stays in its thread."
+                    .to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "draft_id": { "type": "string" },
+                    "to": { "type": "array", "items": { "type": "string" } },
+                    "cc": { "type": "array", "items": { "type": "string" } },
+                    "bcc": { "type": "array", "items": { "type": "string" } },
+                    "subject": { "type": "string" },
+                    "body": { "type": "string" }
+                },
+                "required": ["draft_id"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "message": { "type": "object" }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let draft_id = args.get("draft_id").and_then(|v| v.as_str()).unwrap_or("");
+            if draft_id.trim().is_empty() {
+                return Err(ToolError::validation("Missing 'draft_id'"));
+            }
+            let connection = get_connection(&db_for_update_draft, connection_id, "gmail")?;
+            require_scopes(&connection, &[GMAIL_COMPOSE_SCOPE])?;
+            let token = get_google_access_token(&db_for_update_draft, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_update_draft, &connection.id);
+
+            let url = gmail_url(&api, &["drafts", draft_id])?;
+            let client = Client::new();
+            let existing = gmail_json(
+                client
+                    .get(url.clone())
+                    .query(&[("format", "full")])
+                    .bearer_auth(&token)
+                    .send(),
+            )?;
+            let message = existing
+                .get("message")
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            let payload = message.get("payload").cloned().unwrap_or_else(|| json!({}));
+            let mut mail = OutgoingMail::from_payload(&payload);
+            mail.apply_args(&args);
+
+            let mut updated_message = json!({ "raw": mail.to_raw() });
+            if let Some(thread_id) = message.get("threadId").and_then(|v| v.as_str()) {
+                updated_message["threadId"] = json!(thread_id);
+            }
+            let updated = gmail_json(
+                client
+                    .put(url)
+                    .bearer_auth(&token)
+                    .json(&json!({ "id": draft_id, "message": updated_message }))
+                    .send(),
+            )?;
+            mark_cache_stale(&db_for_update_draft, &connection.id, SYNC_RESOURCE_GMAIL);
+            Ok(updated)
+        }),
+        preview: None,
+    };
+
+    let send_draft = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.send_draft".to_string(),
+            description: "Send an existing Gmail draft.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "draft_id": { "type": "string" }
+                },
+                "required": ["draft_id"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "threadId": { "type": "string" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let draft_id = args.get("draft_id").and_then(|v| v.as_str()).unwrap_or("");
+            if draft_id.trim().is_empty() {
+                return Err(ToolError::validation("Missing 'draft_id'"));
+            }
+            let connection = get_connection(&db_for_send_draft, connection_id, "gmail")?;
+            require_scopes(&connection, &[GMAIL_COMPOSE_SCOPE])?;
+            let token = get_google_access_token(&db_for_send_draft, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_send_draft, &connection.id);
+
+            let sent = gmail_json(
+                Client::new()
+                    .post(format!("{api}/users/me/drafts/send"))
+                    .bearer_auth(token)
+                    .json(&json!({ "id": draft_id }))
+                    .send(),
+            )?;
+            mark_cache_stale(&db_for_send_draft, &connection.id, SYNC_RESOURCE_GMAIL);
+            Ok(sent)
+        }),
+        preview: None,
+    };
+
+    let reply = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.reply".to_string(),
+            description: "Reply to a Gmail message in its thread, quoting it and setting In-Reply-To/References. Sending requires approval; set draft=true to save the reply as a draft instead.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "message_id": { "type": "string", "description": "Gmail id of the message to reply to." },
+                    "body": { "type": "string" },
+                    "reply_all": { "type": "boolean", "description": "Also reply to the original To and Cc recipients." },
+                    "cc": { "type": "array", "items": { "type": "string" } },
+                    "bcc": { "type": "array", "items": { "type": "string" } },
+                    "draft": { "type": "boolean" }
+                },
+                "required": ["message_id", "body"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let message_id = args.get("message_id").and_then(|v| v.as_str()).unwrap_or("");
+            if message_id.trim().is_empty() {
+                return Err(ToolError::validation("Missing 'message_id'"));
+            }
+            let as_draft = args.get("draft").and_then(|v| v.as_bool()).unwrap_or(false);
+            let connection = get_connection(&db_for_reply, connection_id, "gmail")?;
+            let write_scope = if as_draft {
+                GMAIL_COMPOSE_SCOPE
+            } else {
+                GMAIL_SEND_SCOPE
+            };
+            require_scopes(&connection, &[GMAIL_READONLY_SCOPE, write_scope])?;
+            let token = get_google_access_token(&db_for_reply, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_reply, &connection.id);
+
+            let original = OriginalMessage::fetch(&api, &token, message_id)?;
+            let body = args.get("body").and_then(|v| v.as_str()).unwrap_or("");
+            let reply_all = args
+                .get("reply_all")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let mut mail = original.reply(body, reply_all, connection.account_label.as_deref());
+            if let Some(cc) = address_list_arg(&args, "cc").filter(|cc| !cc.is_empty()) {
+                mail.cc = [mail.cc.as_str(), cc.as_str()]
+                    .into_iter()
+                    .filter(|value| !value.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+            }
+            if let Some(bcc) = address_list_arg(&args, "bcc") {
+                mail.bcc = bcc;
+            }
+            if mail.to.is_empty() {
+                return Err(ToolError::validation(
+                    "Could not determine who to reply to from the original message",
+                ));
+            }
+
+            let result = deliver_gmail(&api, &token, &mail, Some(original.thread_id.as_str()), as_draft)?;
+            mark_cache_stale(&db_for_reply, &connection.id, SYNC_RESOURCE_GMAIL);
+            Ok(result)
+        }),
+        preview: None,
+    };
+
+    let forward = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.forward".to_string(),
+            description: "Forward a Gmail message's text with an optional note, keeping it in the original thread. Attachments are not re-attached. Sending requires approval; set draft=true to save it as a draft instead.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "message_id": { "type": "string", "description": "Gmail id of the message to forward." },
+                    "to": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+                    "cc": { "type": "array", "items": { "type": "string" } },
+                    "bcc": { "type": "array", "items": { "type": "string" } },
+                    "body": { "type": "string", "description": "Note placed above the forwarded message." },
+                    "draft": { "type": "boolean" }
+                },
+                "required": ["message_id", "to"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let message_id = args.get("message_id").and_then(|v| v.as_str()).unwrap_or("");
+            if message_id.trim().is_empty() {
+                return Err(ToolError::validation("Missing 'message_id'"));
+            }
+            let to = address_list_arg(&args, "to")
+                .filter(|to| !to.is_empty())
+                .ok_or_else(|| ToolError::validation("Missing 'to'"))?;
+            let as_draft = args.get("draft").and_then(|v| v.as_bool()).unwrap_or(false);
+            let connection = get_connection(&db_for_forward, connection_id, "gmail")?;
+            let write_scope = if as_draft {
+                GMAIL_COMPOSE_SCOPE
+            } else {
+                GMAIL_SEND_SCOPE
+            };
+            require_scopes(&connection, &[GMAIL_READONLY_SCOPE, write_scope])?;
+            let token = get_google_access_token(&db_for_forward, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_forward, &connection.id);
+
+            let original = OriginalMessage::fetch(&api, &token, message_id)?;
+            let note = args.get("body").and_then(|v| v.as_str()).unwrap_or("");
+            let mut mail = original.forward(to, note);
+            mail.cc = address_list_arg(&args, "cc").unwrap_or_default();
+            mail.bcc = address_list_arg(&args, "bcc").unwrap_or_default();
+
+            let result = deliver_gmail(&api, &token, &mail, Some(original.thread_id.as_str()), as_draft)?;
+            mark_cache_stale(&db_for_forward, &connection.id, SYNC_RESOURCE_GMAIL);
+            Ok(result)
+        }),
+        preview: None,
+    };
+
+    let modify_labels = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.modify_labels".to_string(),
+            description: "Add or remove labels on a Gmail message or whole thread. Shortcuts: archive=true removes INBOX, star=true adds STARRED, read=true removes UNREAD (false reverses each).".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "message_id": { "type": "string" },
+                    "thread_id": { "type": "string" },
+                    "add_label_ids": { "type": "array", "items": { "type": "string" } },
+                    "remove_label_ids": { "type": "array", "items": { "type": "string" } },
+                    "archive": { "type": "boolean" },
+                    "star": { "type": "boolean" },
+                    "read": { "type": "boolean" }
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "labelIds": { "type": "array", "items": { "type": "string" } }
+                }
+            }),
+            requires_approval: false,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let str_arg = |key: &str| {
+                args.get(key)
+                    .and_then(|v| v.as_str())
+                    .filter(|value| !value.trim().is_empty())
+            };
+            let (kind, id) = match (str_arg("message_id"), str_arg("thread_id")) {
+                (Some(message_id), None) => ("messages", message_id),
+                (None, Some(thread_id)) => ("threads", thread_id),
+                _ => {
+                    return Err(ToolError::validation(
+                        "Provide exactly one of 'message_id' or 'thread_id'",
+                    ))
+                }
+            };
+            let (add, remove) = label_changes(&args);
+            if add.is_empty() && remove.is_empty() {
+                return Err(ToolError::validation("No label changes requested"));
+            }
+            let connection = get_connection(&db_for_modify, connection_id, "gmail")?;
+            require_scopes(&connection, &[GMAIL_MODIFY_SCOPE])?;
+            let token = get_google_access_token(&db_for_modify, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_modify, &connection.id);
+            let url = gmail_url(&api, &[kind, id, "modify"])?;
+
+            let modified = gmail_json(
+                Client::new()
+                    .post(url)
+                    .bearer_auth(token)
+                    .json(&json!({ "addLabelIds": add, "removeLabelIds": remove }))
+                    .send(),
+            )?;
+            mark_cache_stale(&db_for_modify, &connection.id, SYNC_RESOURCE_GMAIL);
+            Ok(modified)
+        }),
+        preview: None,
+    };
+
+    let download_attachment = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gmail.download_attachment".to_string(),
+            description: "Save a Gmail attachment into the vault. Get attachment ids from gmail.get_thread. Path is relative to the vault root and must not exist yet.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Gmail account."
+                    },
+                    "message_id": { "type": "string" },
+                    "attachment_id": { "type": "string" },
+                    "path": { "type": "string", "description": "Vault-relative destination, e.g. attachments/invoice.pdf." }
+                },
+                "required": ["message_id", "attachment_id", "path"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "size_bytes": { "type": "integer" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let message_id = args.get("message_id").and_then(|v| v.as_str()).unwrap_or("");
+            let attachment_id = args
+                .get("attachment_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            if message_id.trim().is_empty() || attachment_id.trim().is_empty() {
+                return Err(ToolError::validation(
+                    "Missing 'message_id' or 'attachment_id'",
+                ));
+            }
+            let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+            let vault_path = resolve_vault_path(&db_for_attachment, path)?;
+            if vault_path.full_path.exists() {
+                return Err(ToolError::validation("File already exists")
+                    .with_hint("Choose a different vault path."));
+            }
+            let connection = get_connection(&db_for_attachment, connection_id, "gmail")?;
+            require_scopes(&connection, &[GMAIL_READONLY_SCOPE])?;
+            let token = get_google_access_token(&db_for_attachment, &connection)?;
+            let api = GMAIL_API.base_url(&db_for_attachment, &connection.id);
+
+            let attachment = gmail_json(
+                Client::new()
+                    .get(gmail_url(
+                        &api,
+                        &["messages", message_id, "attachments", attachment_id],
+                    )?)
+                    .bearer_auth(token)
+                    .send(),
+            )?;
+            let data = attachment
+                .get("data")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::upstream("Gmail returned no attachment data"))?;
+            let bytes = URL_SAFE_NO_PAD
+                .decode(data.trim_end_matches('=').as_bytes())
+                .map_err(|err| ToolError::new(format!("Failed to decode attachment: {err}")))?;
+
+            ensure_parent_dirs(&vault_path.full_path)?;
+            std::fs::write(&vault_path.full_path, &bytes)
+                .map_err(|err| ToolError::new(format!("Failed to write attachment: {err}")))?;
+            Ok(json!({
+                "path": vault_path.display_path,
+                "size_bytes": bytes.len(),
+            }))
+        }),
+        preview: None,
+    };
+
+    registry.register(list_threads)?;
+    registry.register(get_thread)?;
+    registry.register(search_cache)?;
+    registry.register(list_labels)?;
+    registry.register(send_message)?;
+    registry.register(list_drafts)?;
+    registry.register(create_draft)?;
+    registry.register(update_draft)?;
+    registry.register(send_draft)?;
+    registry.register(reply)?;
+    registry.register(forward)?;
+    registry.register(modify_labels)?;
+    registry.register(download_attachment)?;
+    Ok(())
+}
+
+fn minify_gmail_thread(raw: Value, mode: &str, max_messages: Option<usize>) -> Value {
+    let thread_id = raw
+        .get("id")
         .and_then(|v| v.as_str())
-        .and_then(|v| v.parse::<i64>().ok())
-        .unwrap_or(0);
+        .unwrap_or("")
+        .to_string();
+    let messages = raw
+        .get("messages")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut parsed = messages
+        .into_iter()
+        .filter_map(|message| parse_gmail_message(&thread_id, message))
+        .collect::<Vec<_>>();
+
+    parsed.sort_by(|a, b| a.internal_date_ms.cmp(&b.internal_date_ms));
+    if mode == "latest" {
+        if let Some(last) = parsed.pop() {
+            return json!({
+                "thread_id": thread_id,
+                "mode": "latest",
+                "message": last
+            });
+        }
+        return json!({
+            "thread_id": thread_id,
+            "mode": "latest",
+            "message": null
+        });
+    }
+
+    if let Some(max) = max_messages {
+        if parsed.len() > max {
+            parsed = parsed.into_iter().rev().take(max).collect::<Vec<_>>();
+            parsed.sort_by(|a, b| a.internal_date_ms.cmp(&b.internal_date_ms));
+        }
+    }
+
+    json!({
+        "thread_id": thread_id,
+        "mode": "all",
+        "messages": parsed
+    })
+}
+
+fn parse_gmail_message(thread_id: &str, message: Value) -> Option<GmailMessageSummary> {
+    let message_id = message.get("id").and_then(|v| v.as_str())?.to_string();
+    let internal_date_ms = message
+        .get("internalDate")
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+
+    let payload = message.get("payload").cloned().unwrap_or_else(|| json!({}));
+    let headers = extract_headers(&payload);
+    let subject = headers.get("subject").cloned().unwrap_or_default();
+    let date_header = headers.get("date").cloned();
+
+    let mut body_text: Option<String> = None;
+    let mut body_html: Option<String> = None;
+    let mut attachments: Vec<GmailAttachmentSummary> = Vec::new();
+    collect_parts(&payload, &mut body_text, &mut body_html, &mut attachments);
+
+    Some(GmailMessageSummary {
+        thread_id: thread_id.to_string(),
+        message_id,
+        title: subject,
+        date_header,
+        internal_date_ms,
+        body_text,
+        body_html,
+        attachments,
+    })
+}
+
+fn extract_headers(payload: &Value) -> std::collections::HashMap<String, String> {
+    let mut headers = std::collections::HashMap::new();
+    if let Some(items) = payload.get("headers").and_then(|v| v.as_array()) {
+        for item in items {
+            let name = item.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            let value = item.get("value").and_then(|v| v.as_str()).unwrap_or("");
+            if !name.is_empty() {
+                headers.insert(name.to_lowercase(), value.to_string());
+            }
+        }
+    }
+    headers
+}
+
+fn collect_parts(
+    payload: &Value,
+    body_text: &mut Option<String>,
+    body_html: &mut Option<String>,
+    attachments: &mut Vec<GmailAttachmentSummary>,
+) {
+    let filename = payload
+        .get("filename")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let mime_type = payload
+        .get("mimeType")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let body = payload.get("body").cloned().unwrap_or_else(|| json!({}));
+    let attachment_id = body
+        .get("attachmentId")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let size = body.get("size").and_then(|v| v.as_i64()).unwrap_or(0);
+
+    if !filename.is_empty() && !attachment_id.is_empty() {
+        attachments.push(GmailAttachmentSummary {
+            filename: filename.to_string(),
+            mime_type: mime_type.to_string(),
+            attachment_id: attachment_id.to_string(),
+            size,
+        });
+    }
+
+    if let Some(data) = body.get("data").and_then(|v| v.as_str()) {
+        if mime_type == "text/plain" && body_text.is_none() {
+            *body_text = decode_gmail_body(data);
+        } else if mime_type == "text/html" && body_html.is_none() {
+            *body_html = decode_gmail_body(data);
+        }
+    }
+
+    if let Some(parts) = payload.get("parts").and_then(|v| v.as_array()) {
+        for part in parts {
+            collect_parts(part, body_text, body_html, attachments);
+        }
+    }
+}
+
+fn decode_gmail_body(data: &str) -> Option<String> {
+    if data.trim().is_empty() {
+        return None;
+    }
+    let decoded = URL_SAFE_NO_PAD.decode(data.as_bytes()).ok()?;
+    String::from_utf8(decoded).ok()
+}
+
+/// `{api}/users/me/` followed by `segments`. Ids come from the model, so each is
+/// percent-encoded as its own path segment and cannot change the endpoint.
+fn gmail_url(api: &str, segments: &[&str]) -> Result<Url, ToolError> {
+    let mut url = Url::parse(&format!("{api}/users/me"))
+        .map_err(|err| ToolError::new(format!("Invalid Gmail API URL: {err}")))?;
+    url.path_segments_mut()
+        .map_err(|_| ToolError::new("Invalid Gmail API URL"))?
+        .extend(segments);
+    Ok(url)
+}
+
+/// Checks the status of a Gmail API call and parses its JSON body.
+fn gmail_json(result: reqwest::Result<reqwest::blocking::Response>) -> Result<Value, ToolError> {
+    let response =
+        result.map_err(|err| ToolError::upstream(format!("Failed to call Gmail API: {err}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ToolError::from_http_response(
+            &response,
+            format!("Gmail API error: HTTP {status}"),
+        ));
+    }
+    response
+        .json::<Value>()
+        .map_err(|err| ToolError::new(format!("Failed to parse Gmail response: {err}")))
+}
+
+pub(super) fn address_list_arg(args: &Value, key: &str) -> Option<String> {
+    args.get(key).and_then(|v| v.as_array()).map(|list| {
+        list.iter()
+            .filter_map(|v| v.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+}
+
+/// A plain-text message to send or save as a draft. `in_reply_to` and `references` keep
+/// replies threaded in the recipients' mail clients.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct OutgoingMail {
+    pub(super) to: String,
+    pub(super) cc: String,
+    pub(super) bcc: String,
+    pub(super) subject: String,
+    pub(super) body: String,
+    pub(super) in_reply_to: Option<String>,
+    pub(super) references: Option<String>,
+}
+
+impl OutgoingMail {
+    pub(super) fn from_args(args: &Value) -> Self {
+        let mut mail = Self::default();
+        mail.apply_args(args);
+        mail
+    }
+
+    /// Overwrites the fields present in `args`, leaving the others unchanged.
+    fn apply_args(&mut self, args: &Value) {
+        if let Some(to) = address_list_arg(args, "to") {
+            self.to = to;
+        }
+        if let Some(cc) = address_list_arg(args, "cc") {
+            self.cc = cc;
+        }
+        if let Some(bcc) = address_list_arg(args, "bcc") {
+            self.bcc = bcc;
+        }
+        if let Some(subject) = args.get("subject").and_then(|v| v.as_str()) {
+            self.subject = subject.to_string();
+        }
+        if let Some(body) = args.get("body").and_then(|v| v.as_str()) {
+            self.body = body.to_string();
+        }
+    }
+
+    /// Reads back a message payload, e.g. an existing draft.
+    fn from_payload(payload: &Value) -> Self {
+        let headers = extract_headers(payload);
+        let header = |name: &str| headers.get(name).cloned().unwrap_or_default();
+        let mut body_text: Option<String> = None;
+        let mut body_html: Option<String> = None;
+        let mut attachments: Vec<GmailAttachmentSummary> = Vec::new();
+        collect_parts(payload, &mut body_text, &mut body_html, &mut attachments);
+        Self {
+            to: header("to"),
+            cc: header("cc"),
+            bcc: header("bcc"),
+            subject: header("subject"),
+            body: body_text.or(body_html).unwrap_or_default(),
+            in_reply_to: headers.get("in-reply-to").cloned(),
+            references: headers.get("references").cloned(),
+        }
+    }
+
+    /// RFC 2822 message, base64url-encoded for the Gmail `raw` field.
+    fn to_raw(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.to_rfc822(&[], true).as_bytes())
+    }
+
+    /// RFC 2822 message text. `extra_headers` come first, e.g. `From` and `Message-ID`
+    /// when sending over SMTP, where `Bcc` must also be left out of the message itself.
+    pub(super) fn to_rfc822(&self, extra_headers: &[(&str, String)], include_bcc: bool) -> String {
+        let mut headers = extra_headers
+            .iter()
+            .map(|(name, value)| format!("{name}: {}", sanitize_header(value)))
+            .collect::<Vec<_>>();
+        let optional = [
+            ("To", Some(&self.to)),
+            ("Cc", Some(&self.cc)),
+            ("Bcc", include_bcc.then_some(&self.bcc)),
+            ("In-Reply-To", self.in_reply_to.as_ref()),
+            ("References", self.references.as_ref()),
+        ];
+        for (name, value) in optional {
+            let value = value
+                .map(String::as_str)
+                .map(sanitize_header)
+                .unwrap_or_default();
+            if !value.is_empty() {
+                headers.push(format!("{name}: {value}"));
+            }
+        }
+        headers.push(format!(
+            "Subject: {}",
+            encode_header_value(&sanitize_header(&self.subject))
+        ));
+        headers.push("MIME-Version: 1.0".to_string());
+        headers.push("Content-Type: text/plain; charset=\"UTF-8\"".to_string());
+
+        format!("{}\r\n\r\n{}", headers.join("\r\n"), self.body)
+    }
+}
+
+/// Header values must stay on one line; a newline would start a new header.
+fn sanitize_header(value: &str) -> String {
+    value.replace(['\r', '\n'], " ").trim().to_string()
+}
+
+/// RFC 2047 encoding for non-ASCII header values such as subjects.
+fn encode_header_value(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_string();
+    }
+    format!("=?UTF-8?B?{}?=", STANDARD.encode(value.as_bytes()))
+}
+
+/// Sends `mail`, or saves it as a draft, in `thread_id` when given.
+fn deliver_gmail(
+    api: &str,
+    token: &str,
+    mail: &OutgoingMail,
+    thread_id: Option<&str>,
+    as_draft: bool,
+) -> Result<Value, ToolError> {
+    let mut message = json!({ "raw": mail.to_raw() });
+    if let Some(thread_id) = thread_id.filter(|id| !id.is_empty()) {
+        message["threadId"] = json!(thread_id);
+    }
+    let client = Client::new();
+    let request = if as_draft {
+        client
+            .post(format!("{api}/users/me/drafts"))
+            .json(&json!({ "message": message }))
+    } else {
+        client
+            .post(format!("{api}/users/me/messages/send"))
+            .json(&message)
+    };
+    gmail_json(request.bearer_auth(token).send())
+}
+
+/// The message a reply or forward is based on.
+#[derive(Debug, Clone, Default)]
+pub(super) struct OriginalMessage {
+    pub(super) thread_id: String,
+    pub(super) message_id_header: Option<String>,
+    pub(super) references: Option<String>,
+    pub(super) subject: String,
+    pub(super) from: String,
+    pub(super) reply_to: Option<String>,
+    pub(super) to: String,
+    pub(super) cc: String,
+    pub(super) date: Option<String>,
+    pub(super) body: String,
+}
+
+impl OriginalMessage {
+    fn fetch(api: &str, token: &str, message_id: &str) -> Result<Self, ToolError> {
+        let raw = gmail_json(
+            Client::new()
+                .get(gmail_url(api, &["messages", message_id])?)
+                .query(&[("format", "full")])
+                .bearer_auth(token)
+                .send(),
+        )?;
+        let payload = raw.get("payload").cloned().unwrap_or_else(|| json!({}));
+        let headers = extract_headers(&payload);
+        let header = |name: &str| headers.get(name).cloned().unwrap_or_default();
+        let content = OutgoingMail::from_payload(&payload);
+        Ok(Self {
+            thread_id: raw
+                .get("threadId")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            message_id_header: headers.get("message-id").cloned(),
+            references: headers.get("references").cloned(),
+            subject: header("subject"),
+            from: header("from"),
+            reply_to: headers
+                .get("reply-to")
+                .filter(|value| !value.trim().is_empty())
+                .cloned(),
+            to: header("to"),
+            cc: header("cc"),
+            date: headers.get("date").cloned(),
+            body: content.body,
+        })
+    }
+
+    /// `References` for a reply: the original's references followed by its Message-ID.
+    fn reply_references(&self) -> Option<String> {
+        let chain = [
+            self.references.as_deref(),
+            self.message_id_header.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+        (!chain.is_empty()).then(|| chain.join(" "))
+    }
+
+    /// Reply quoting the original. `own_address` keeps the account itself out of the
+    /// recipients of a reply-all.
+    pub(super) fn reply(
+        &self,
+        body: &str,
+        reply_all: bool,
+        own_address: Option<&str>,
+    ) -> OutgoingMail {
+        let is_own = |address: &str| {
+            own_address
+                .map(|own| address_email(address) == address_email(own))
+                .unwrap_or(false)
+        };
+        // Replying to a message the account sent goes back to its recipients.
+        let sender = self.reply_to.clone().unwrap_or_else(|| self.from.clone());
+        let to = if is_own(&sender) {
+            split_addresses(&self.to)
+        } else {
+            split_addresses(&sender)
+        };
+        let mut cc = Vec::new();
+        if reply_all {
+            for address in split_addresses(&self.to)
+                .into_iter()
+                .chain(split_addresses(&self.cc))
+            {
+                let email = address_email(&address);
+                let seen = to
+                    .iter()
+                    .chain(cc.iter())
+                    .any(|existing| address_email(existing) == email);
+                if !seen && !is_own(&address) {
+                    cc.push(address);
+                }
+            }
+        }
+
+        OutgoingMail {
+            to: to.join(", "),
+            cc: cc.join(", "),
+            bcc: String::new(),
+            subject: prefixed_subject(&self.subject, "Re:", &["re:"]),
+            body: format!("{body}\r\n\r\n{}", self.quoted()),
+            in_reply_to: self.message_id_header.clone(),
+            references: self.reply_references(),
+        }
+    }
+
+    pub(super) fn forward(&self, to: String, note: &str) -> OutgoingMail {
+        let mut header_block = vec![
+            "---------- Forwarded message ---------".to_string(),
+            format!("From: {}", self.from),
+        ];
+        if let Some(date) = &self.date {
+            header_block.push(format!("Date: {date}"));
+        }
+        header_block.push(format!("Subject: {}", self.subject));
+        header_block.push(format!("To: {}", self.to));
+        if !self.cc.is_empty() {
+            header_block.push(format!("Cc: {}", self.cc));
+        }
+        OutgoingMail {
+            to,
+            subject: prefixed_subject(&self.subject, "Fwd:", &["fwd:", "fw:"]),
+            body: format!(
+                "{note}\r\n\r\n{}\r\n\r\n{}",
+                header_block.join("\r\n"),
+                self.body
+            ),
+            in_reply_to: self.message_id_header.clone(),
+            references: self.reply_references(),
+            ..OutgoingMail::default()
+        }
+    }
+
+    fn quoted(&self) -> String {
+        let attribution = match &self.date {
+            Some(date) => format!("On {date}, {} wrote:", self.from),
+            None => format!("{} wrote:", self.from),
+        };
+        let quoted = self
+            .body
+            .lines()
+            .map(|line| {
+                if line.is_empty() {
+                    ">".to_string()
+                } else {
+                    format!("> {line}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        format!("{attribution}\r\n{quoted}")
+    }
+}
+
+fn prefixed_subject(subject: &str, prefix: &str, existing: &[&str]) -> String {
+    let lower = subject.trim().to_lowercase();
+    if existing.iter().any(|marker| lower.starts_with(marker)) {
+        return subject.trim().to_string();
+    }
+    format!("{prefix} {}", subject.trim())
+}
+
+/// Splits an address header on commas outside quoted names and angle brackets.
+pub(super) fn split_addresses(header: &str) -> Vec<String> {
+    let mut addresses = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut in_angle = false;
+    for ch in header.chars() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_angle = true,
+            '>' if !in_quotes => in_angle = false,
+            ',' if !in_quotes && !in_angle => {
+                if !current.trim().is_empty() {
+                    addresses.push(current.trim().to_string());
+                }
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(ch);
+    }
+    if !current.trim().is_empty() {
+        addresses.push(current.trim().to_string());
+    }
+    addresses
+}
+
+/// Lowercased bare email of an address such as `"Name" <user@example.com>`.
+pub(super) fn address_email(address: &str) -> String {
+    let address = address.trim();
+    let bare = match (address.rfind('<'), address.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &address[start + 1..end],
+        _ => address,
+    };
+    bare.trim().to_lowercase()
+}
+
+/// Label ids to add and remove, from explicit lists plus the archive/star/read shortcuts.
+fn label_changes(args: &Value) -> (Vec<String>, Vec<String>) {
+    let ids = |key: &str| {
+        args.get(key)
+            .and_then(|v| v.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+    let mut add = ids("add_label_ids");
+    let mut remove = ids("remove_label_ids");
+    let shortcuts = [
+        ("archive", "INBOX", false),
+        ("star", "STARRED", true),
+        ("read", "UNREAD", false),
+    ];
+    for (key, label, adds_when_true) in shortcuts {
+        if let Some(flag) = args.get(key).and_then(|v| v.as_bool()) {
+            let target = if flag == adds_when_true {
+                &mut add
+            } else {
+                &mut remove
+            };
+            if !target.iter().any(|existing| existing == label) {
+                target.push(label.to_string());
+            }
+        }
+    }
+    (add, remove)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct GmailMessageSummary {
+    thread_id: String,
+    message_id: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_header: Option<String>,
+    internal_date_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_html: Option<String>,
+    attachments: Vec<GmailAttachmentSummary>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct GmailAttachmentSummary {
+    filename: String,
+    mime_type: String,
+    attachment_id: String,
+    size: i64,
+}
+
+pub(super) fn string_list_arg(args: &Value, key: &str) -> Vec<String> {
+    args.get(key)
+        .and_then(|v| v.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(super) fn recurrence_arg_schema() -> Value {
+    json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "Optional. RFC 5545 lines such as 'RRULE:FREQ=WEEKLY;BYDAY=MO,WE' or 'EXDATE:20260105T100000Z'. Recurring events need a time zone; the calendar's is used when time_zone is omitted."
+    })
+}
+
+pub(super) fn apply_to_arg_schema() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["instance", "series"],
+        "description": "Optional. For an occurrence of a recurring event: 'instance' (default) changes only that occurrence, 'series' changes every occurrence."
+    })
+}
+
+fn send_updates_arg_schema() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["all", "externalOnly", "none"],
+        "description": "Optional. Who gets email notifications about the change. Defaults to none."
+    })
+}
+
+pub(super) fn applies_to_series(args: &Value) -> bool {
+    args.get("apply_to").and_then(|v| v.as_str()) == Some("series")
+}
+
+fn with_send_updates(request: RequestBuilder, args: &Value) -> RequestBuilder {
+    match args.get("send_updates").and_then(|v| v.as_str()) {
+        Some(send_updates) => request.query(&[("sendUpdates", send_updates)]),
+        None => request,
+    }
+}
+
+/// Events URL of a calendar, or of one event. Calendar ids may contain `#` and `@`, so
+/// they are percent-encoded as path segments.
+fn calendar_events_url(
+    api: &str,
+    calendar_id: &str,
+    event_id: Option<&str>,
+) -> Result<Url, ToolError> {
+    let mut url = Url::parse(&format!("{api}/calendars"))
+        .map_err(|err| ToolError::new(format!("Invalid Calendar API URL: {err}")))?;
+    {
+        let mut segments = url
+            .path_segments_mut()
+            .map_err(|_| ToolError::new("Invalid Calendar API URL"))?;
+        segments.push(calendar_id).push("events");
+        if let Some(event_id) = event_id {
+            segments.push(event_id);
+        }
+    }
+    Ok(url)
+}
+
+fn calendar_response(
+    result: reqwest::Result<reqwest::blocking::Response>,
+) -> Result<reqwest::blocking::Response, ToolError> {
+    let response = result
+        .map_err(|err| ToolError::upstream(format!("Failed to call Google Calendar API: {err}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ToolError::from_http_response(
+            &response,
+            format!("Google Calendar API error: HTTP {status}"),
+        ));
+    }
+    Ok(response)
+}
+
+fn calendar_json(result: reqwest::Result<reqwest::blocking::Response>) -> Result<Value, ToolError> {
+    calendar_response(result)?
+        .json::<Value>()
+        .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))
+}
+
+/// The event to change: `event_id` itself, or with `series` the recurring event that the
+/// occurrence belongs to.
+fn target_event_id(
+    api: &str,
+    token: &str,
+    calendar_id: &str,
+    event_id: &str,
+    series: bool,
+) -> Result<String, ToolError> {
+    if !series {
+        return Ok(event_id.to_string());
+    }
+    let event = calendar_json(
+        Client::new()
+            .get(calendar_events_url(api, calendar_id, Some(event_id))?)
+            .bearer_auth(token)
+            .send(),
+    )?;
+    Ok(event
+        .get("recurringEventId")
+        .and_then(|v| v.as_str())
+        .unwrap_or(event_id)
+        .to_string())
+}
+
+fn calendar_time_zone(api: &str, token: &str, calendar_id: &str) -> Result<String, ToolError> {
+    let mut url = Url::parse(&format!("{api}/calendars"))
+        .map_err(|err| ToolError::new(format!("Invalid Calendar API URL: {err}")))?;
+    url.path_segments_mut()
+        .map_err(|_| ToolError::new("Invalid Calendar API URL"))?
+        .push(calendar_id);
+    let calendar = calendar_json(Client::new().get(url).bearer_auth(token).send())?;
+    calendar
+        .get("timeZone")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            ToolError::validation("Recurring events need a time zone")
+                .with_hint("Pass time_zone, e.g. 'Europe/Berlin'.")
+        })
+}
+
+/// The event's attendee list with the account's own response updated, or `None` when the
+/// account is not an attendee.
+fn set_own_response(event: &Value, response_status: &str, comment: Option<&str>) -> Option<Value> {
+    let mut attendees = event.get("attendees")?.as_array()?.clone();
+    let own = attendees
+        .iter_mut()
+        .find(|attendee| attendee.get("self").and_then(|v| v.as_bool()) == Some(true))?;
+    own["responseStatus"] = json!(response_status);
+    if let Some(comment) = comment {
+        own["comment"] = json!(comment);
+    }
+    Some(Value::Array(attendees))
+}
+
+/// Daily window in which free time is offered.
+#[derive(Debug, Clone, PartialEq)]
+struct WorkingHours {
+    start: NaiveTime,
+    end: NaiveTime,
+    /// ISO weekdays, 1 = Monday.
+    days: Vec<u32>,
+}
+
+impl WorkingHours {
+    /// Tool arguments override the connection's settings, which override 09:00-17:00 on
+    /// weekdays.
+    fn resolve(args: &Value, settings: &Value) -> Result<Self, ToolError> {
+        let time = |key: &str, default: &str| -> Result<NaiveTime, ToolError> {
+            let value = args
+                .get(key)
+                .or_else(|| settings.get(key))
+                .and_then(|v| v.as_str())
+                .unwrap_or(default);
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .map_err(|_| ToolError::validation(format!("Invalid '{key}': {value}")))
+        };
+        let start = time("working_hours_start", "09:00")?;
+        let end = time("working_hours_end", "17:00")?;
+        if end <= start {
+            return Err(ToolError::validation(
+                "Working hours must end after they start",
+            ));
+        }
+        let days = args
+            .get("working_days")
+            .or_else(|| settings.get("working_days"))
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_u64())
+                    .filter(|day| (1..=7).contains(day))
+                    .map(|day| day as u32)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|days| !days.is_empty())
+            .unwrap_or_else(|| vec![1, 2, 3, 4, 5]);
+        Ok(Self { start, end, days })
+    }
+}
+
+/// Gaps of at least `min_duration_ms` between `busy` periods, inside working hours and
+/// the `range` (epoch ms), earliest first.
+fn free_slots<Tz: TimeZone>(
+    tz: &Tz,
+    range: (i64, i64),
+    busy: &[(i64, i64)],
+    hours: &WorkingHours,
+    min_duration_ms: i64,
+    limit: usize,
+) -> Vec<(i64, i64)> {
+    let mut merged = busy.to_vec();
+    merged.sort();
+    let mut busy: Vec<(i64, i64)> = Vec::new();
+    for (start, end) in merged {
+        match busy.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => busy.push((start, end)),
+        }
+    }
+
+    let mut slots = Vec::new();
+    let (Some(first), Some(last)) = (
+        tz.timestamp_millis_opt(range.0).earliest(),
+        tz.timestamp_millis_opt(range.1).earliest(),
+    ) else {
+        return slots;
+    };
+    let mut day = first.date_naive();
+    while day <= last.date_naive() && slots.len() < limit {
+        let window = hours
+            .days
+            .contains(&day.weekday().number_from_monday())
+            .then(|| {
+                let open = tz
+                    .from_local_datetime(&day.and_time(hours.start))
+                    .earliest()?;
+                let close = tz
+                    .from_local_datetime(&day.and_time(hours.end))
+                    .earliest()?;
+                Some((open.timestamp_millis(), close.timestamp_millis()))
+            })
+            .flatten();
+        if let Some((open, close)) = window {
+            let mut cursor = open.max(range.0);
+            let close = close.min(range.1);
+            for &(busy_start, busy_end) in &busy {
+                if busy_end <= cursor {
+                    continue;
+                }
+                if busy_start >= close {
+                    break;
+                }
+                if busy_start - cursor >= min_duration_ms {
+                    slots.push((cursor, busy_start));
+                }
+                cursor = busy_end;
+            }
+            if close - cursor >= min_duration_ms {
+                slots.push((cursor, close));
+            }
+        }
+        match day.succ_opt() {
+            Some(next) => day = next,
+            None => break,
+        }
+    }
+    slots.truncate(limit);
+    slots
+}
+
+/// Calendars the user picked in the integration settings, if any.
+pub fn preferred_calendar_ids(db: &Db, connection_id: &str) -> Option<Vec<String>> {
+    load_integration_settings(db, "google_calendar", connection_id)
+        .get("calendar_ids")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|values| !values.is_empty())
+}
+
+pub fn register_google_calendar_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let db_for_list = db.clone();
+    let db_for_list_calendars = db.clone();
+    let db_for_create = db.clone();
+    let db_for_update = db.clone();
+    let db_for_delete = db.clone();
+    let db_for_respond = db.clone();
+    let db_for_free_time = db.clone();
+    let list_calendars = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gcal.list_calendars".to_string(),
+            description: "List Google Calendar calendars for the connected account. By default, returns only the user's selected calendars from integration settings (falls back to primary if none).".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                    },
+                    "max_results": { "type": "integer", "minimum": 1, "maximum": 250 }
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendars": { "type": "array" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args.get("connection_id").and_then(|v| v.as_str()).unwrap_or("");
+            let connection = get_connection(&db_for_list_calendars, connection_id, "google_calendar")?;
+            let token = get_google_access_token(&db_for_list_calendars, &connection)?;
+            let api = GOOGLE_CALENDAR_API.base_url(&db_for_list_calendars, &connection.id);
+
+            let client = Client::new();
+            let mut request = client.get(format!("{api}/users/me/calendarList"));
+            if let Some(max_results) = args.get("max_results").and_then(|v| v.as_u64()) {
+                request = request.query(&[("maxResults", max_results.to_string())]);
+            }
+
+            let response = request
+                .bearer_auth(token)
+                .send()
+                .map_err(|err| ToolError::upstream(format!("Failed to call Google Calendar API: {err}")))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(ToolError::from_http_response(&response, format!("Google Calendar API error: HTTP {status}")));
+            }
+
+            let json = response
+                .json::<Value>()
+                .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))?;
+
+            let items = json
+                .get("items")
+                .and_then(|value| value.as_array())
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|item| {
+                            let id = item.get("id")?.as_str()?.to_string();
+                            let summary = item
+                                .get("summary")
+                                .and_then(|value| value.as_str())
+                                .unwrap_or(&id)
+                                .to_string();
+                            let primary = item
+                                .get("primary")
+                                .and_then(|value| value.as_bool())
+                                .unwrap_or(false);
+                            let time_zone = item
+                                .get("timeZone")
+                                .and_then(|value| value.as_str())
+                                .map(|value| value.to_string());
+                            let access_role = item
+                                .get("accessRole")
+                                .and_then(|value| value.as_str())
+                                .map(|value| value.to_string());
+
+                            Some(json!({
+                                "id": id,
+                                "summary": summary,
+                                "primary": primary,
+                                "time_zone": time_zone,
+                                "access_role": access_role
+                            }))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let preferred_ids = preferred_calendar_ids(&db_for_list_calendars, &connection.id);
+
+            let filtered = if let Some(ids) = preferred_ids {
+                let allowed: std::collections::HashSet<_> = ids.into_iter().collect();
+                items
+                    .into_iter()
+                    .filter(|item| {
+                        item.get("id")
+                            .and_then(|value| value.as_str())
+                            .map(|id| allowed.contains(id))
+                            .unwrap_or(false)
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                let primary = items
+                    .iter()
+                    .find(|item| item.get("primary").and_then(|value| value.as_bool()) == Some(true))
+                    .cloned()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                primary
+            };
+
+            Ok(json!({ "calendars": filtered }))
+        }),
+        preview: None,
+    };
+
+    let list_events = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gcal.list_events".to_string(),
+            description: "List or search Google Calendar events, grouped by calendar. If calendar_ids is omitted, uses the user's selected calendars (integration settings); falls back to primary if none."
+                .to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                    },
+                    "calendar_id": { "type": "string" },
+                    "calendar_ids": { "type": "array", "items": { "type": "string" } },
+                    "time_min": { "type": "string" },
+                    "time_max": { "type": "string" },
+                    "query": { "type": "string" },
+                    "max_results": { "type": "integer", "minimum": 1, "maximum": 2500 },
+                    "expand_recurring": {
+                        "type": "boolean",
+                        "description": "Optional. List each occurrence of recurring events (with its own id) instead of the series. The local cache is always expanded."
+                    },
+                    "source": source_arg_schema()
+                }
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendars": { "type": "array" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args.get("connection_id").and_then(|v| v.as_str()).unwrap_or("");
+            let connection = get_connection(&db_for_list, connection_id, "google_calendar")?;
+
+            let explicit_calendar_ids = args
+                .get("calendar_ids")
+                .and_then(|v| v.as_array())
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|item| item.as_str())
+                        .map(|value| value.trim())
+                        .filter(|value| !value.is_empty())
+                        .map(|value| value.to_string())
+                        .collect::<Vec<_>>()
+                })
+                .filter(|values| !values.is_empty());
+
+            let explicit_calendar_id = args
+                .get("calendar_id")
+                .and_then(|v| v.as_str())
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string());
+
+            let calendar_ids = if let Some(ids) = explicit_calendar_ids {
+                ids
+            } else if let Some(id) = explicit_calendar_id {
+                vec![id]
+            } else {
+                preferred_calendar_ids(&db_for_list, &connection.id)
+                    .unwrap_or_else(|| vec!["primary".to_string()])
+            };
+            if let Some(cached) =
+                cached_calendar_events(&db_for_list, &args, &connection.id, &calendar_ids)?
+            {
+                return Ok(cached);
+            }
+            let token = get_google_access_token(&db_for_list, &connection)?;
+            let api = GOOGLE_CALENDAR_API.base_url(&db_for_list, &connection.id);
+            let client = Client::new();
+
+            let mut grouped: Vec<Value> = Vec::new();
+            for calendar_id in calendar_ids {
+                let url = format!("{api}/calendars/{calendar_id}/events");
+                let mut request = client.get(url);
+                if let Some(time_min) = args.get("time_min").and_then(|v| v.as_str()) {
+                    request = request.query(&[("timeMin", time_min)]);
+                }
+                if let Some(time_max) = args.get("time_max").and_then(|v| v.as_str()) {
+                    request = request.query(&[("timeMax", time_max)]);
+                }
+                if let Some(query) = args.get("query").and_then(|v| v.as_str()) {
+                    request = request.query(&[("q", query)]);
+                }
+                if let Some(max_results) = args.get("max_results").and_then(|v| v.as_u64()) {
+                    request = request.query(&[("maxResults", max_results.to_string())]);
+                }
+                if args.get("expand_recurring").and_then(|v| v.as_bool()) == Some(true) {
+                    request = request.query(&[("singleEvents", "true"), ("orderBy", "startTime")]);
+                }
+
+                let response = request
+                    .bearer_auth(&token)
+                    .send()
+                    .map_err(|err| ToolError::upstream(format!("Failed to call Google Calendar API: {err}")))?;
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(ToolError::from_http_response(&response, format!("Google Calendar API error: HTTP {status}")));
+                }
+
+                let json = response
+                    .json::<Value>()
+                    .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))?;
+
+                let events = json.get("items").cloned().unwrap_or_else(|| json!([]));
+                let mut entry = serde_json::Map::new();
+                entry.insert("calendar_id".to_string(), json!(calendar_id));
+                entry.insert("events".to_string(), events);
+                if let Some(token) = json.get("nextPageToken").and_then(|value| value.as_str()) {
+                    entry.insert("next_page_token".to_string(), json!(token));
+                }
+                if let Some(summary) = json.get("summary").and_then(|value| value.as_str()) {
+                    entry.insert("summary".to_string(), json!(summary));
+                }
+                if let Some(time_zone) = json.get("timeZone").and_then(|value| value.as_str()) {
+                    entry.insert("time_zone".to_string(), json!(time_zone));
+                }
+                grouped.push(Value::Object(entry));
+            }
+
+            Ok(json!({ "calendars": grouped }))
+        }),
+        preview: None,
+    };
+
+    let create_event = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gcal.create_event".to_string(),
+            description: "Create a Google Calendar event.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                    },
+                    "calendar_id": { "type": "string" },
+                    "summary": { "type": "string" },
+                    "description": { "type": "string" },
+                    "start": { "type": "string" },
+                    "end": { "type": "string" },
+                    "time_zone": { "type": "string" },
+                    "attendees": { "type": "array", "items": { "type": "string" } },
+                    "recurrence": recurrence_arg_schema(),
+                    "send_updates": send_updates_arg_schema()
+                },
+                "required": ["summary", "start", "end"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_create, connection_id, "google_calendar")?;
+            require_scopes(&connection, &[CALENDAR_EVENTS_SCOPE])?;
+            let token = get_google_access_token(&db_for_create, &connection)?;
+            let api = GOOGLE_CALENDAR_API.base_url(&db_for_create, &connection.id);
+
+            let calendar_id = args
+                .get("calendar_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("primary");
+            let url = format!("{api}/calendars/{calendar_id}/events");
+            let summary = args.get("summary").and_then(|v| v.as_str()).unwrap_or("");
+            let description = args.get("description").and_then(|v| v.as_str());
+            let start = args.get("start").and_then(|v| v.as_str()).unwrap_or("");
+            let end = args.get("end").and_then(|v| v.as_str()).unwrap_or("");
+            let recurrence = string_list_arg(&args, "recurrence");
+            let time_zone = match args.get("time_zone").and_then(|v| v.as_str()) {
+                Some(time_zone) => Some(time_zone.to_string()),
+                // Google rejects recurring events without an explicit time zone.
+                None if !recurrence.is_empty() => {
+                    Some(calendar_time_zone(&api, &token, calendar_id)?)
+                }
+                None => None,
+            };
+            let attendees = args
+                .get("attendees")
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_str())
+                        .map(|email| json!({ "email": email }))
+                        .collect::<Vec<_>>()
+                });
+
+            let mut event = json!({
+                "summary": summary,
+                "description": description,
+                "start": {
+                    "dateTime": start,
+                    "timeZone": time_zone
+                },
+                "end": {
+                    "dateTime": end,
+                    "timeZone": time_zone
+                },
+                "attendees": attendees
+            });
+            if !recurrence.is_empty() {
+                event["recurrence"] = json!(recurrence);
+            }
+
+            let client = Client::new();
+            let response = with_send_updates(client.post(url), &args)
+                .bearer_auth(token)
+                .json(&event)
+                .send()
+                .map_err(|err| {
+                    ToolError::upstream(format!("Failed to call Google Calendar API: {err}"))
+                })?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(ToolError::from_http_response(
+                    &response,
+                    format!("Google Calendar API error: HTTP {status}"),
+                ));
+            }
+            mark_cache_stale(
+                &db_for_create,
+                &connection.id,
+                SYNC_RESOURCE_CALENDAR_PREFIX,
+            );
+
+            response
+                .json::<Value>()
+                .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))
+        }),
+        preview: None,
+    };
+
+    let update_event = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gcal.update_event".to_string(),
+            description: "Update fields on an existing Google Calendar event. For an instance of a recurring event, apply_to='series' edits the whole series instead of that occurrence.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                    },
+                    "event_id": { "type": "string" },
+                    "calendar_id": { "type": "string" },
+                    "summary": { "type": "string" },
+                    "description": { "type": "string" },
+                    "location": { "type": "string" },
+                    "start": { "type": "string" },
+                    "end": { "type": "string" },
+                    "time_zone": { "type": "string" },
+                    "attendees": { "type": "array", "items": { "type": "string" } },
+                    "recurrence": recurrence_arg_schema(),
+                    "apply_to": apply_to_arg_schema(),
+                    "send_updates": send_updates_arg_schema()
+                },
+                "required": ["event_id"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_update, connection_id, "google_calendar")?;
+            require_scopes(&connection, &[CALENDAR_EVENTS_SCOPE])?;
+            let token = get_google_access_token(&db_for_update, &connection)?;
+            let api = GOOGLE_CALENDAR_API.base_url(&db_for_update, &connection.id);
+
+            let event_id = args
+                .get("event_id")
+                .and_then(|v| v.as_str())
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| ToolError::new("event_id is required"))?;
+            let calendar_id = args
+                .get("calendar_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("primary");
+
+            let mut event = serde_json::Map::new();
+
+            if let Some(summary) = args.get("summary").and_then(|v| v.as_str()) {
+                event.insert("summary".to_string(), json!(summary));
+            }
+            if let Some(description) = args.get("description").and_then(|v| v.as_str()) {
+                event.insert("description".to_string(), json!(description));
+            }
+            if let Some(location) = args.get("location").and_then(|v| v.as_str()) {
+                event.insert("location".to_string(), json!(location));
+            }
+
+            let time_zone = args.get("time_zone").and_then(|v| v.as_str());
+            if let Some(start) = args.get("start").and_then(|v| v.as_str()) {
+                event.insert(
+                    "start".to_string(),
+                    json!({
+                        "dateTime": start,
+                        "timeZone": time_zone
+                    }),
+                );
+            }
+            if let Some(end) = args.get("end").and_then(|v| v.as_str()) {
+                event.insert(
+                    "end".to_string(),
+                    json!({
+                        "dateTime": end,
+                        "timeZone": time_zone
+                    }),
+                );
+            }
+
+            if let Some(attendees) = args.get("attendees").and_then(|v| v.as_array()) {
+                let values = attendees
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .map(|email| json!({ "email": email }))
+                    .collect::<Vec<_>>();
+                event.insert("attendees".to_string(), json!(values));
+            }
+            let recurrence = string_list_arg(&args, "recurrence");
+            if !recurrence.is_empty() {
+                event.insert("recurrence".to_string(), json!(recurrence));
+            }
+
+            if event.is_empty() {
+                return Err(ToolError::new(
+                    "Provide at least one field to update (for example summary, start, or end)",
+                ));
+            }
+
+            let target_id = target_event_id(&api, &token, calendar_id, event_id, applies_to_series(&args))?;
+            let url = calendar_events_url(&api, calendar_id, Some(&target_id))?;
+            let client = Client::new();
+            let response = with_send_updates(client.patch(url), &args)
+                .bearer_auth(token)
+                .json(&Value::Object(event))
+                .send()
+                .map_err(|err| {
+                    ToolError::upstream(format!("Failed to call Google Calendar API: {err}"))
+                })?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(ToolError::from_http_response(
+                    &response,
+                    format!("Google Calendar API error: HTTP {status}"),
+                ));
+            }
+            mark_cache_stale(
+                &db_for_update,
+                &connection.id,
+                SYNC_RESOURCE_CALENDAR_PREFIX,
+            );
+
+            response
+                .json::<Value>()
+                .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))
+        }),
+        preview: None,
+    };
+
+    let delete_event = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gcal.delete_event".to_string(),
+            description: "Delete a Google Calendar event. For an instance of a recurring event, apply_to='series' deletes the whole series instead of that occurrence.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                    },
+                    "event_id": { "type": "string" },
+                    "calendar_id": { "type": "string" },
+                    "apply_to": apply_to_arg_schema(),
+                    "send_updates": send_updates_arg_schema()
+                },
+                "required": ["event_id"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "deleted": { "type": "boolean" },
+                    "event_id": { "type": "string" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let event_id = args
+                .get("event_id")
+                .and_then(|v| v.as_str())
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| ToolError::validation("Missing 'event_id'"))?;
+            let calendar_id = args
+                .get("calendar_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("primary");
+            let connection = get_connection(&db_for_delete, connection_id, "google_calendar")?;
+            require_scopes(&connection, &[CALENDAR_EVENTS_SCOPE])?;
+            let token = get_google_access_token(&db_for_delete, &connection)?;
+            let api = GOOGLE_CALENDAR_API.base_url(&db_for_delete, &connection.id);
+
+            let target_id =
+                target_event_id(&api, &token, calendar_id, event_id, applies_to_series(&args))?;
+            let url = calendar_events_url(&api, calendar_id, Some(&target_id))?;
+            calendar_response(
+                with_send_updates(Client::new().delete(url), &args)
+                    .bearer_auth(token)
+                    .send(),
+            )?;
+            mark_cache_stale(
+                &db_for_delete,
+                &connection.id,
+                SYNC_RESOURCE_CALENDAR_PREFIX,
+            );
+            Ok(json!({ "deleted": true, "event_id": target_id }))
+        }),
+        preview: None,
+    };
+
+    let respond = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gcal.respond".to_string(),
+            description: "Accept, decline, or tentatively accept a Google Calendar invitation as the connected account, optionally with a comment to the organizer.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                    },
+                    "event_id": { "type": "string" },
+                    "calendar_id": { "type": "string" },
+                    "response": { "type": "string", "enum": ["accepted", "declined", "tentative"] },
+                    "comment": { "type": "string" },
+                    "apply_to": apply_to_arg_schema(),
+                    "send_updates": send_updates_arg_schema()
+                },
+                "required": ["event_id", "response"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let event_id = args
+                .get("event_id")
+                .and_then(|v| v.as_str())
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| ToolError::validation("Missing 'event_id'"))?;
+            let response_status = args
+                .get("response")
+                .and_then(|v| v.as_str())
+                .filter(|value| matches!(*value, "accepted" | "declined" | "tentative"))
+                .ok_or_else(|| {
+                    ToolError::validation("'response' must be accepted, declined or tentative")
+                })?;
+            let calendar_id = args
+                .get("calendar_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("primary");
+            let connection = get_connection(&db_for_respond, connection_id, "google_calendar")?;
+            require_scopes(&connection, &[CALENDAR_EVENTS_SCOPE])?;
+            let token = get_google_access_token(&db_for_respond, &connection)?;
+            let api = GOOGLE_CALENDAR_API.base_url(&db_for_respond, &connection.id);
+
+            let target_id =
+                target_event_id(&api, &token, calendar_id, event_id, applies_to_series(&args))?;
+            let url = calendar_events_url(&api, calendar_id, Some(&target_id))?;
+            let client = Client::new();
+            let event = calendar_json(client.get(url.clone()).bearer_auth(&token).send())?;
+            let comment = args.get("comment").and_then(|v| v.as_str());
+            let attendees = set_own_response(&event, response_status, comment).ok_or_else(|| {
+                ToolError::validation("The connected account is not an attendee of this event")
+            })?;
+
+            let updated = calendar_json(
+                with_send_updates(client.patch(url), &args)
+                    .bearer_auth(&token)
+                    .json(&json!({ "attendees": attendees }))
+                    .send(),
+            )?;
+            mark_cache_stale(
+                &db_for_respond,
+                &connection.id,
+                SYNC_RESOURCE_CALENDAR_PREFIX,
+            );
+            Ok(updated)
+        }),
+        preview: None,
+    };
+
+    let find_free_time = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "gcal.find_free_time".to_string(),
+            description: "Find free time slots within working hours using the Google Calendar freeBusy API. Checks the selected calendars plus any attendee calendars visible to the account. Working hours default to the integration settings (09:00-17:00, Monday-Friday) in the computer's local time zone.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                    },
+                    "duration_minutes": { "type": "integer", "minimum": 5, "maximum": 1440 },
+                    "time_min": { "type": "string", "description": "RFC 3339 timestamp or YYYY-MM-DD. Defaults to now." },
+                    "time_max": { "type": "string", "description": "RFC 3339 timestamp or YYYY-MM-DD. Defaults to 7 days after time_min." },
+                    "calendar_ids": { "type": "array", "items": { "type": "string" } },
+                    "attendees": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Email addresses whose calendars must also be free."
+                    },
+                    "working_hours_start": { "type": "string", "pattern": "^\\d{2}:\\d{2}$" },
+                    "working_hours_end": { "type": "string", "pattern": "^\\d{2}:\\d{2}$" },
+                    "working_days": {
+                        "type": "array",
+                        "items": { "type": "integer", "minimum": 1, "maximum": 7 },
+                        "description": "ISO weekdays, 1 = Monday."
+                    },
+                    "max_results": { "type": "integer", "minimum": 1, "maximum": 50 }
+                },
+                "required": ["duration_minutes"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "slots": { "type": "array" },
+                    "unavailable_calendars": { "type": "array" }
+                }
+            }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_free_time, connection_id, "google_calendar")?;
+
+            let duration_minutes = args
+                .get("duration_minutes")
+                .and_then(|v| v.as_i64())
+                .filter(|value| *value > 0)
+                .ok_or_else(|| ToolError::validation("Missing 'duration_minutes'"))?;
+            let time_arg = |key: &str| -> Result<Option<i64>, ToolError> {
+                match args.get(key).and_then(|v| v.as_str()) {
+                    None => Ok(None),
+                    Some(value) => parse_time_arg(value)
+                        .map(Some)
+                        .ok_or_else(|| ToolError::validation(format!("Invalid '{key}': {value}"))),
+                }
+            };
+            let time_min = time_arg("time_min")?.unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+            let time_max = time_arg("time_max")?.unwrap_or(time_min + 7 * 24 * 60 * 60 * 1000);
+            if time_max <= time_min {
+                return Err(ToolError::validation("'time_max' must be after 'time_min'"));
+            }
+            let settings = load_integration_settings(&db_for_free_time, "google_calendar", &connection.id);
+            let hours = WorkingHours::resolve(&args, &settings)?;
+
+            let mut calendar_ids = string_list_arg(&args, "calendar_ids");
+            if calendar_ids.is_empty() {
+                calendar_ids = preferred_calendar_ids(&db_for_free_time, &connection.id)
+                    .unwrap_or_else(|| vec!["primary".to_string()]);
+            }
+            for attendee in string_list_arg(&args, "attendees") {
+                if !calendar_ids.contains(&attendee) {
+                    calendar_ids.push(attendee);
+                }
+            }
 
-    let payload = message.get("payload").cloned().unwrap_or_else(|| json!({}));
-    let headers = extract_headers(&payload);
-    let subject = headers.get("subject").cloned().unwrap_or_default();
-    let date_header = headers.get("date").cloned();
+            let token = get_google_access_token(&db_for_free_time, &connection)?;
+            let api = GOOGLE_CALENDAR_API.base_url(&db_for_free_time, &connection.id);
+            let (Some(time_min_text), Some(time_max_text)) = (format_time_ms(time_min), format_time_ms(time_max)) else {
+                return Err(ToolError::validation("Time range is out of bounds"));
+            };
+            let body = json!({
+                "timeMin": time_min_text,
+                "timeMax": time_max_text,
+                "items": calendar_ids.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+            });
+            let response = calendar_json(
+                Client::new()
+                    .post(format!("{api}/freeBusy"))
+                    .bearer_auth(token)
+                    .json(&body)
+                    .send(),
+            )?;
 
-    let mut body_text: Option<String> = None;
-    let mut body_html: Option<String> = None;
-    let mut attachments: Vec<GmailAttachmentSummary> = Vec::new();
-    collect_parts(&payload, &mut body_text, &mut body_html, &mut attachments);
+            let mut busy = Vec::new();
+            let mut unavailable = Vec::new();
+            if let Some(calendars) = response.get("calendars").and_then(|v| v.as_object()) {
+                for (calendar_id, calendar) in calendars {
+                    if calendar.get("errors").and_then(|v| v.as_array()).is_some_and(|errors| !errors.is_empty()) {
+                        unavailable.push(calendar_id.clone());
+                        continue;
+                    }
+                    for period in calendar.get("busy").and_then(|v| v.as_array()).into_iter().flatten() {
+                        let start = period.get("start").and_then(|v| v.as_str()).and_then(parse_time_arg);
+                        let end = period.get("end").and_then(|v| v.as_str()).and_then(parse_time_arg);
+                        if let (Some(start), Some(end)) = (start, end) {
+                            busy.push((start, end));
+                        }
+                    }
+                }
+            }
 
-    Some(GmailMessageSummary {
-        thread_id: thread_id.to_string(),
-        message_id,
-        title: subject,
-        date_header,
-        internal_date_ms,
-        body_text,
-        body_html,
-        attachments,
-    })
+            let max_results = args.get("max_results").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
+            let slots = free_slots(
+                &chrono::Local,
+                (time_min, time_max),
+                &busy,
+                &hours,
+                duration_minutes * 60 * 1000,
+                max_results,
+            );
+            let format_local = |value: i64| {
+                chrono::Local
+                    .timestamp_millis_opt(value)
+                    .earliest()
+                    .map(|parsed| parsed.to_rfc3339())
+            };
+            Ok(json!({
+                "slots": slots
+                    .into_iter()
+                    .map(|(start, end)| json!({
+                        "start": format_local(start),
+                        "end": format_local(end),
+                        "minutes": (end - start) / 60_000,
+                    }))
+                    .collect::<Vec<_>>(),
+                "unavailable_calendars": unavailable,
+            }))
+        }),
+        preview: None,
+    };
+
+    registry.register(list_calendars)?;
+    registry.register(list_events)?;
+    registry.register(create_event)?;
+    registry.register(update_event)?;
+    registry.register(delete_event)?;
+    registry.register(respond)?;
+    registry.register(find_free_time)?;
+    Ok(())
 }
 
-fn extract_headers(payload: &Value) -> std::collections::HashMap<String, String> {
-    let mut headers = std::collections::HashMap::new();
-    if let Some(items) = payload.get("headers").and_then(|v| v.as_array()) {
-        for item in items {
-            let name = item.get("name").and_then(|v| v.as_str()).unwrap_or("");
-            let value = item.get("value").and_then(|v| v.as_str()).unwrap_or("");
-            if !name.is_empty() {
-                headers.insert(name.to_lowercase(), value.to_string());
-            }
-        }
+/// `{api}/` followed by `segments`, each percent-encoded as its own path segment so a
+/// task or label id cannot change the endpoint.
+fn todoist_url(api: &str, segments: &[&str]) -> Result<Url, ToolError> {
+    let mut url =
+        Url::parse(api).map_err(|err| ToolError::new(format!("Invalid Todoist API URL: {err}")))?;
+    url.path_segments_mut()
+        .map_err(|_| ToolError::new("Invalid Todoist API URL"))?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+/// Checks the status of a Todoist API call and parses its JSON body. Empty bodies
+/// (`204 No Content`) become `null`.
+fn todoist_json(result: reqwest::Result<reqwest::blocking::Response>) -> Result<Value, ToolError> {
+    let response =
+        result.map_err(|err| ToolError::upstream(format!("Failed to call Todoist API: {err}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ToolError::from_http_response(
+            &response,
+            format!("Todoist API error: HTTP {status}"),
+        ));
     }
-    headers
+    let body = response
+        .text()
+        .map_err(|err| ToolError::new(format!("Failed to read Todoist response: {err}")))?;
+    if body.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&body)
+        .map_err(|err| ToolError::new(format!("Failed to parse Todoist response: {err}")))
 }
 
-fn collect_parts(
-    payload: &Value,
-    body_text: &mut Option<String>,
-    body_html: &mut Option<String>,
-    attachments: &mut Vec<GmailAttachmentSummary>,
-) {
-    let filename = payload
-        .get("filename")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let mime_type = payload
-        .get("mimeType")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let body = payload.get("body").cloned().unwrap_or_else(|| json!({}));
-    let attachment_id = body
-        .get("attachmentId")
+pub(super) fn required_str_arg<'a>(args: &'a Value, key: &str) -> Result<&'a str, ToolError> {
+    args.get(key)
         .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let size = body.get("size").and_then(|v| v.as_i64()).unwrap_or(0);
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| ToolError::validation(format!("Missing '{key}'")))
+}
 
-    if !filename.is_empty() && !attachment_id.is_empty() {
-        attachments.push(GmailAttachmentSummary {
-            filename: filename.to_string(),
-            mime_type: mime_type.to_string(),
-            attachment_id: attachment_id.to_string(),
-            size,
-        });
+/// Schema properties shared by task create and update.
+fn todoist_task_properties() -> serde_json::Map<String, Value> {
+    let properties = json!({
+        "content": { "type": "string" },
+        "description": { "type": "string" },
+        "labels": { "type": "array", "items": { "type": "string" }, "description": "Label names. Replaces the task's labels." },
+        "priority": { "type": "integer", "minimum": 1, "maximum": 4, "description": "4 is the highest (p1 in the app)." },
+        "due_string": { "type": "string", "description": "Natural language such as 'next monday 9am' or 'every friday'; 'no date' clears the due date." },
+        "due_date": { "type": "string" },
+        "due_datetime": { "type": "string" }
+    });
+    match properties {
+        Value::Object(map) => map,
+        _ => serde_json::Map::new(),
     }
+}
 
-    if let Some(data) = body.get("data").and_then(|v| v.as_str()) {
-        if mime_type == "text/plain" && body_text.is_none() {
-            *body_text = decode_gmail_body(data);
-        } else if mime_type == "text/html" && body_html.is_none() {
-            *body_html = decode_gmail_body(data);
+/// Task fields present in `args`, in the REST API's shape.
+fn todoist_task_fields(args: &Value) -> serde_json::Map<String, Value> {
+    let mut payload = serde_json::Map::new();
+    for key in [
+        "content",
+        "description",
+        "due_string",
+        "due_date",
+        "due_datetime",
+    ] {
+        if let Some(value) = args.get(key).and_then(|v| v.as_str()) {
+            payload.insert(key.to_string(), json!(value));
         }
     }
+    if let Some(labels) = args.get("labels").and_then(|v| v.as_array()) {
+        let labels = labels.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>();
+        payload.insert("labels".to_string(), json!(labels));
+    }
+    if let Some(priority) = args.get("priority").and_then(|v| v.as_i64()) {
+        payload.insert("priority".to_string(), json!(priority));
+    }
+    payload
+}
 
-    if let Some(parts) = payload.get("parts").and_then(|v| v.as_array()) {
-        for part in parts {
-            collect_parts(part, body_text, body_html, attachments);
+/// Sync API commands accepted per request.
+const TODOIST_SYNC_BATCH: usize = 100;
+
+/// Converts one bulk operation into a Sync API command.
+fn todoist_sync_command(operation: &Value) -> Result<Value, ToolError> {
+    let action = required_str_arg(operation, "action")?;
+    let task_id = required_str_arg(operation, "task_id")?;
+    let mut command_args = serde_json::Map::new();
+    command_args.insert("id".to_string(), json!(task_id));
+    let command_type = match action {
+        "update" => {
+            let fields = todoist_task_fields(operation);
+            for (key, value) in fields {
+                match key.as_str() {
+                    // The Sync API takes due dates as an object instead of REST's fields.
+                    "due_string" => {
+                        command_args.insert("due".to_string(), json!({ "string": value }));
+                    }
+                    "due_date" | "due_datetime" => {
+                        command_args.insert("due".to_string(), json!({ "date": value }));
+                    }
+                    other => {
+                        command_args.insert(other.to_string(), value);
+                    }
+                }
+            }
+            if command_args.len() == 1 {
+                return Err(ToolError::validation(format!(
+                    "Update of task {task_id} has no fields to change"
+                )));
+            }
+            "item_update"
         }
-    }
+        "move" => {
+            let (key, value) = todoist_move_target(operation)?;
+            command_args.insert(key.to_string(), json!(value));
+            "item_move"
+        }
+        "complete" => "item_close",
+        "reopen" => "item_uncomplete",
+        "delete" => "item_delete",
+        other => {
+            return Err(ToolError::validation(format!(
+                "Unknown bulk action '{other}'"
+            )))
+        }
+    };
+    Ok(json!({
+        "type": command_type,
+        "uuid": Uuid::new_v4().to_string(),
+        "args": Value::Object(command_args),
+    }))
 }
 
-fn decode_gmail_body(data: &str) -> Option<String> {
-    if data.trim().is_empty() {
-        return None;
+/// Destination of a move: exactly one of `project_id`, `section_id` or `parent_id`.
+fn todoist_move_target(args: &Value) -> Result<(&'static str, String), ToolError> {
+    let targets = ["project_id", "section_id", "parent_id"]
+        .into_iter()
+        .filter_map(|key| {
+            args.get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| (key, value.to_string()))
+        })
+        .collect::<Vec<_>>();
+    match <[_; 1]>::try_from(targets) {
+        Ok([target]) => Ok(target),
+        Err(_) => Err(ToolError::validation(
+            "Provide exactly one of 'project_id', 'section_id' or 'parent_id'",
+        )),
     }
-    let decoded = URL_SAFE_NO_PAD.decode(data.as_bytes()).ok()?;
-    String::from_utf8(decoded).ok()
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
-struct GmailMessageSummary {
-    thread_id: String,
-    message_id: String,
-    title: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    date_header: Option<String>,
-    internal_date_ms: i64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    body_text: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    body_html: Option<String>,
-    attachments: Vec<GmailAttachmentSummary>,
+/// Runs Sync API commands in batches and reports each command's outcome in order.
+fn run_todoist_commands(
+    api: &str,
+    token: &str,
+    commands: Vec<Value>,
+) -> Result<Vec<Value>, ToolError> {
+    let client = Client::new();
+    let mut results = Vec::new();
+    for batch in commands.chunks(TODOIST_SYNC_BATCH) {
+        let response = todoist_json(
+            client
+                .post(format!("{api}/sync"))
+                .bearer_auth(token)
+                .json(&json!({ "commands": batch }))
+                .send(),
+        )?;
+        let statuses = response
+            .get("sync_status")
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+        for command in batch {
+            let uuid = command.get("uuid").and_then(|v| v.as_str()).unwrap_or("");
+            let task_id = command.pointer("/args/id").cloned().unwrap_or(Value::Null);
+            results.push(match statuses.get(uuid) {
+                Some(Value::String(status)) if status == "ok" => {
+                    json!({ "task_id": task_id, "ok": true })
+                }
+                Some(error) => json!({
+                    "task_id": task_id,
+                    "ok": false,
+                    "error": error.get("error").cloned().unwrap_or_else(|| error.clone()),
+                }),
+                None => json!({
+                    "task_id": task_id,
+                    "ok": false,
+                    "error": "No status returned for this command",
+                }),
+            });
+        }
+    }
+    Ok(results)
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
-struct GmailAttachmentSummary {
-    filename: String,
-    mime_type: String,
-    attachment_id: String,
-    size: i64,
+/// Comment target: exactly one of `task_id` or `project_id`.
+fn todoist_comment_target(args: &Value) -> Result<(&'static str, String), ToolError> {
+    let task_id = args.get("task_id").and_then(|v| v.as_str());
+    let project_id = args.get("project_id").and_then(|v| v.as_str());
+    match (task_id, project_id) {
+        (Some(task_id), None) => Ok(("task_id", task_id.to_string())),
+        (None, Some(project_id)) => Ok(("project_id", project_id.to_string())),
+        _ => Err(ToolError::validation(
+            "Provide exactly one of 'task_id' or 'project_id'",
+        )),
+    }
 }
 
-fn register_google_calendar_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
-    let db_for_list = db.clone();
-    let db_for_list_calendars = db.clone();
-    let db_for_create = db.clone();
-    let db_for_update = db.clone();
-    let list_calendars = ToolDefinition {
+pub fn register_todoist_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let db_for_list = db.clone();
+    let db_for_create = db.clone();
+    let db_for_complete = db.clone();
+    let db_for_update = db.clone();
+    let db_for_reopen = db.clone();
+    let db_for_delete = db.clone();
+    let db_for_move = db.clone();
+    let db_for_bulk = db.clone();
+    let db_for_projects = db.clone();
+    let db_for_sections = db.clone();
+    let db_for_labels = db.clone();
+    let db_for_save_label = db.clone();
+    let db_for_delete_label = db.clone();
+    let db_for_comments = db.clone();
+    let db_for_add_comment = db.clone();
+    let list_tasks = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "todoist.list_tasks".to_string(),
+            description: "List active Todoist tasks, optionally narrowed by project, section, label, ids or a Todoist filter query.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Todoist account."
+                    },
+                    "project_id": { "type": "string" },
+                    "section_id": { "type": "string" },
+                    "label": { "type": "string", "description": "Label name." },
+                    "ids": { "type": "array", "items": { "type": "string" } },
+                    "filter": {
+                        "type": "string",
+                        "description": "Todoist filter syntax, e.g. 'today | overdue', '#Work & p1', '@waiting & due before: next week'."
+                    },
+                    "source": source_arg_schema()
+                }
+            }),
+            result_schema: json!({ "type": "array" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_list, connection_id, "todoist")?;
+            if let Some(cached) = cached_todoist_tasks(&db_for_list, &args, &connection.id)? {
+                return Ok(cached);
+            }
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_list, &connection.id);
+
+            let client = Client::new();
+            let mut request = client.get(format!("{api}/tasks"));
+            if let Some(project_id) = args.get("project_id").and_then(|v| v.as_str()) {
+                request = request.query(&[("project_id", project_id)]);
+            }
+            if let Some(section_id) = args.get("section_id").and_then(|v| v.as_str()) {
+                request = request.query(&[("section_id", section_id)]);
+            }
+            if let Some(label) = args.get("label").and_then(|v| v.as_str()) {
+                request = request.query(&[("label", label)]);
+            }
+            let ids = string_list_arg(&args, "ids");
+            if !ids.is_empty() {
+                request = request.query(&[("ids", ids.join(","))]);
+            }
+            if let Some(filter) = args.get("filter").and_then(|v| v.as_str()) {
+                request = request.query(&[("filter", filter)]);
+            }
+
+            let response = request
+                .bearer_auth(token)
+                .send()
+                .map_err(|err| ToolError::upstream(format!("Failed to call Todoist API: {err}")))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(ToolError::from_http_response(
+                    &response,
+                    format!("Todoist API error: HTTP {status}"),
+                ));
+            }
+
+            response
+                .json::<Value>()
+                .map_err(|err| ToolError::new(format!("Failed to parse Todoist response: {err}")))
+        }),
+        preview: None,
+    };
+
+    let create_task = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "todoist.create_task".to_string(),
+            description: "Create a Todoist task.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Todoist account."
+                    },
+                    "content": { "type": "string" },
+                    "description": { "type": "string" },
+                    "project_id": { "type": "string" },
+                    "section_id": { "type": "string" },
+                    "parent_id": { "type": "string" },
+                    "labels": { "type": "array", "items": { "type": "string" } },
+                    "priority": { "type": "integer", "minimum": 1, "maximum": 4 },
+                    "due_string": { "type": "string" },
+                    "due_date": { "type": "string" },
+                    "due_datetime": { "type": "string" }
+                },
+                "required": ["content"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_create, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_create, &connection.id);
+
+            let mut payload = todoist_task_fields(&args);
+            for key in ["project_id", "section_id", "parent_id"] {
+                if let Some(value) = args.get(key).and_then(|v| v.as_str()) {
+                    payload.insert(key.to_string(), json!(value));
+                }
+            }
+
+            let client = Client::new();
+            let response = client
+                .post(format!("{api}/tasks"))
+                .bearer_auth(token)
+                .json(&Value::Object(payload))
+                .send()
+                .map_err(|err| ToolError::upstream(format!("Failed to call Todoist API: {err}")))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(ToolError::from_http_response(
+                    &response,
+                    format!("Todoist API error: HTTP {status}"),
+                ));
+            }
+            mark_cache_stale(&db_for_create, &connection.id, SYNC_RESOURCE_TODOIST);
+
+            response
+                .json::<Value>()
+                .map_err(|err| ToolError::new(format!("Failed to parse Todoist response: {err}")))
+        }),
+        preview: None,
+    };
+
+    let complete_task = ToolDefinition {
         metadata: ToolMetadata {
-            name: "gcal.list_calendars".to_string(),
-            description: "List Google Calendar calendars for the connected account. By default, returns only the user's selected calendars from integration settings (falls back to primary if none).".to_string(),
+            name: "todoist.complete_task".to_string(),
+            description: "Mark a Todoist task as complete.".to_string(),
             args_schema: json!({
                 "type": "object",
                 "properties": {
                     "connection_id": {
                         "type": "string",
-                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                        "description": "Optional. Omit to use the default connected Todoist account."
                     },
-                    "max_results": { "type": "integer", "minimum": 1, "maximum": 250 }
-                }
-            }),
-            result_schema: json!({
-                "type": "object",
-                "properties": {
-                    "calendars": { "type": "array" }
-                }
+                    "task_id": { "type": "string" }
+                },
+                "required": ["task_id"]
             }),
+            result_schema: json!({ "type": "object" }),
             requires_approval: true,
-            result_mode: ToolResultMode::Auto,
+            result_mode: ToolResultMode::Inline,
         },
         handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
-            let connection_id = args.get("connection_id").and_then(|v| v.as_str()).unwrap_or("");
-            let connection = get_connection(&db_for_list_calendars, connection_id, "google_calendar")?;
-            let token = get_google_access_token(&db_for_list_calendars, &connection)?;
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_complete, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_complete, &connection.id);
 
-            let client = Client::new();
-            let mut request = client.get("https://www.googleapis.com/calendar/v3/users/me/calendarList");
-            if let Some(max_results) = args.get("max_results").and_then(|v| v.as_u64()) {
-                request = request.query(&[("maxResults", max_results.to_string())]);
+            let task_id = args.get("task_id").and_then(|v| v.as_str()).unwrap_or("");
+            if task_id.is_empty() {
+                return Err(ToolError::validation("Missing task_id"));
             }
 
-            let response = request
-                .bearer_auth(token)
-                .send()
-                .map_err(|err| ToolError::new(format!("Failed to call Google Calendar API: {err}")))?;
+            let client = Client::new();
+            let url = todoist_url(&api, &["tasks", task_id, "close"])?;
+            let response =
+                client.post(url).bearer_auth(token).send().map_err(|err| {
+                    ToolError::upstream(format!("Failed to call Todoist API: {err}"))
+                })?;
             let status = response.status();
             if !status.is_success() {
-                return Err(ToolError::new(format!("Google Calendar API error: HTTP {status}")));
+                return Err(ToolError::from_http_response(
+                    &response,
+                    format!("Todoist API error: HTTP {status}"),
+                ));
             }
+            mark_cache_stale(&db_for_complete, &connection.id, SYNC_RESOURCE_TODOIST);
 
-            let json = response
-                .json::<Value>()
-                .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))?;
-
-            let items = json
-                .get("items")
-                .and_then(|value| value.as_array())
-                .map(|values| {
-                    values
-                        .iter()
-                        .filter_map(|item| {
-                            let id = item.get("id")?.as_str()?.to_string();
-                            let summary = item
-                                .get("summary")
-                                .and_then(|value| value.as_str())
-                                .unwrap_or(&id)
-                                .to_string();
-                            let primary = item
-                                .get("primary")
-                                .and_then(|value| value.as_bool())
-                                .unwrap_or(false);
-                            let time_zone = item
-                                .get("timeZone")
-                                .and_then(|value| value.as_str())
-                                .map(|value| value.to_string());
-                            let access_role = item
-                                .get("accessRole")
-                                .and_then(|value| value.as_str())
-                                .map(|value| value.to_string());
-
-                            Some(json!({
-                                "id": id,
-                                "summary": summary,
-                                "primary": primary,
-                                "time_zone": time_zone,
-                                "access_role": access_role
-                            }))
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .unwrap_or_default();
-
-            let pref_key = format!("integration_settings.google_calendar.{}", connection.id);
-            let preferred_ids = PreferenceOperations::get_preference(&db_for_list_calendars, &pref_key)
-                .ok()
-                .flatten()
-                .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
-                .and_then(|value| value.get("calendar_ids").and_then(|v| v.as_array()).cloned())
-                .map(|values| {
-                    values
-                        .iter()
-                        .filter_map(|item| item.as_str())
-                        .map(|value| value.trim())
-                        .filter(|value| !value.is_empty())
-                        .map(|value| value.to_string())
-                        .collect::<Vec<_>>()
-                })
-                .filter(|values| !values.is_empty());
+            Ok(json!({ "ok": true }))
+        }),
+        preview: None,
+    };
 
-            let filtered = if let Some(ids) = preferred_ids {
-                let allowed: std::collections::HashSet<_> = ids.into_iter().collect();
-                items
-                    .into_iter()
-                    .filter(|item| {
-                        item.get("id")
-                            .and_then(|value| value.as_str())
-                            .map(|id| allowed.contains(id))
-                            .unwrap_or(false)
-                    })
-                    .collect::<Vec<_>>()
-            } else {
-                let primary = items
-                    .iter()
-                    .find(|item| item.get("primary").and_then(|value| value.as_bool()) == Some(true))
-                    .cloned()
-                    .into_iter()
-                    .collect::<Vec<_>>();
-                primary
-            };
+    let mut update_properties = todoist_task_properties();
+    update_properties.insert(
+        "connection_id".to_string(),
+        json!({
+            "type": "string",
+            "description": "Optional. Omit to use the default connected Todoist account."
+        }),
+    );
+    update_properties.insert("task_id".to_string(), json!({ "type": "string" }));
+    let update_task = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "todoist.update_task".to_string(),
+            description: "Update a Todoist task's content, description, labels, priority or due date. Use todoist.move_task to change its project or section.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": update_properties,
+                "required": ["task_id"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let task_id = required_str_arg(&args, "task_id")?;
+            let fields = todoist_task_fields(&args);
+            if fields.is_empty() {
+                return Err(ToolError::validation(
+                    "Provide at least one field to update (for example content or due_string)",
+                ));
+            }
+            let connection = get_connection(&db_for_update, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_update, &connection.id);
+            let url = todoist_url(&api, &["tasks", task_id])?;
 
-            Ok(json!({ "calendars": filtered }))
+            let updated = todoist_json(
+                Client::new()
+                    .post(url)
+                    .bearer_auth(token)
+                    .json(&Value::Object(fields))
+                    .send(),
+            )?;
+            mark_cache_stale(&db_for_update, &connection.id, SYNC_RESOURCE_TODOIST);
+            Ok(updated)
         }),
         preview: None,
     };
 
-    let list_events = ToolDefinition {
+    let reopen_task = ToolDefinition {
         metadata: ToolMetadata {
-            name: "gcal.list_events".to_string(),
-            description: "List or search Google Calendar events, grouped by calendar. If calendar_ids is omitted, uses the user's selected calendars (integration settings); falls back to primary if none."
-                .to_string(),
+            name: "todoist.reopen_task".to_string(),
+            description: "Reopen a completed Todoist task.".to_string(),
             args_schema: json!({
                 "type": "object",
                 "properties": {
                     "connection_id": {
                         "type": "string",
-                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                        "description": "Optional. Omit to use the default connected Todoist account."
                     },
-                    "calendar_id": { "type": "string" },
-                    "calendar_ids": { "type": "array", "items": { "type": "string" } },
-                    "time_min": { "type": "string" },
-                    "time_max": { "type": "string" },
-                    "query": { "type": "string" },
-                    "max_results": { "type": "integer", "minimum": 1, "maximum": 2500 }
-                }
-            }),
-            result_schema: json!({
-                "type": "object",
-                "properties": {
-                    "calendars": { "type": "array" }
-                }
+                    "task_id": { "type": "string" }
+                },
+                "required": ["task_id"]
             }),
+            result_schema: json!({ "type": "object" }),
             requires_approval: true,
-            result_mode: ToolResultMode::Auto,
+            result_mode: ToolResultMode::Inline,
         },
         handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
-            let connection_id = args.get("connection_id").and_then(|v| v.as_str()).unwrap_or("");
-            let connection = get_connection(&db_for_list, connection_id, "google_calendar")?;
-            let token = get_google_access_token(&db_for_list, &connection)?;
-
-            let client = Client::new();
-            let explicit_calendar_ids = args
-                .get("calendar_ids")
-                .and_then(|v| v.as_array())
-                .map(|values| {
-                    values
-                        .iter()
-                        .filter_map(|item| item.as_str())
-                        .map(|value| value.trim())
-                        .filter(|value| !value.is_empty())
-                        .map(|value| value.to_string())
-                        .collect::<Vec<_>>()
-                })
-                .filter(|values| !values.is_empty());
-
-            let explicit_calendar_id = args
-                .get("calendar_id")
+            let connection_id = args
+                .get("connection_id")
                 .and_then(|v| v.as_str())
-                .map(|value| value.trim())
-                .filter(|value| !value.is_empty())
-                .map(|value| value.to_string());
-
-            let calendar_ids = if let Some(ids) = explicit_calendar_ids {
-                ids
-            } else if let Some(id) = explicit_calendar_id {
-                vec![id]
-            } else {
-                let pref_key = format!("integration_settings.google_calendar.{}", connection.id);
-                let preferred_ids = PreferenceOperations::get_preference(&db_for_list, &pref_key)
-                    .ok()
-                    .flatten()
-                    .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
-                    .and_then(|value| value.get("calendar_ids").and_then(|v| v.as_array()).cloned())
-                    .map(|values| {
-                        values
-                            .iter()
-                            .filter_map(|item| item.as_str())
-                            .map(|value| value.trim())
-                            .filter(|value| !value.is_empty())
-                            .map(|value| value.to_string())
-                            .collect::<Vec<_>>()
-                    })
-                    .filter(|values| !values.is_empty());
-
-                preferred_ids.unwrap_or_else(|| vec!["primary".to_string()])
-            };
-
-            let mut grouped: Vec<Value> = Vec::new();
-            for calendar_id in calendar_ids {
-                let url = format!("https://www.googleapis.com/calendar/v3/calendars/{calendar_id}/events");
-                let mut request = client.get(url);
-                if let Some(time_min) = args.get("time_min").and_then(|v| v.as_str()) {
-                    request = request.query(&[("timeMin", time_min)]);
-                }
-                if let Some(time_max) = args.get("time_max").and_then(|v| v.as_str()) {
-                    request = request.query(&[("timeMax", time_max)]);
-                }
-                if let Some(query) = args.get("query").and_then(|v| v.as_str()) {
-                    request = request.query(&[("q", query)]);
-                }
-                if let Some(max_results) = args.get("max_results").and_then(|v| v.as_u64()) {
-                    request = request.query(&[("maxResults", max_results.to_string())]);
-                }
-
-                let response = request
-                    .bearer_auth(&token)
-                    .send()
-                    .map_err(|err| ToolError::new(format!("Failed to call Google Calendar API: {err}")))?;
-                let status = response.status();
-                if !status.is_success() {
-                    return Err(ToolError::new(format!("Google Calendar API error: HTTP {status}")));
-                }
+                .unwrap_or("");
+            let task_id = required_str_arg(&args, "task_id")?;
+            let connection = get_connection(&db_for_reopen, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_reopen, &connection.id);
+            let url = todoist_url(&api, &["tasks", task_id, "reopen"])?;
 
-                let json = response
-                    .json::<Value>()
-                    .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))?;
+            todoist_json(Client::new().post(url).bearer_auth(token).send())?;
+            mark_cache_stale(&db_for_reopen, &connection.id, SYNC_RESOURCE_TODOIST);
+            Ok(json!({ "ok": true }))
+        }),
+        preview: None,
+    };
 
-                let events = json.get("items").cloned().unwrap_or_else(|| json!([]));
-                let mut entry = serde_json::Map::new();
-                entry.insert("calendar_id".to_string(), json!(calendar_id));
-                entry.insert("events".to_string(), events);
-                if let Some(token) = json.get("nextPageToken").and_then(|value| value.as_str()) {
-                    entry.insert("next_page_token".to_string(), json!(token));
-                }
-                if let Some(summary) = json.get("summary").and_then(|value| value.as_str()) {
-                    entry.insert("summary".to_string(), json!(summary));
-                }
-                if let Some(time_zone) = json.get("timeZone").and_then(|value| value.as_str()) {
-                    entry.insert("time_zone".to_string(), json!(time_zone));
-                }
-                grouped.push(Value::Object(entry));
-            }
+    let delete_task = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "todoist.delete_task".to_string(),
+            description: "Permanently delete a Todoist task and its subtasks.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Todoist account."
+                    },
+                    "task_id": { "type": "string" }
+                },
+                "required": ["task_id"]
+            }),
+            result_schema: json!({ "type": "object" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Inline,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let task_id = required_str_arg(&args, "task_id")?;
+            let connection = get_connection(&db_for_delete, connection_id, "todoist")?;
+            require_scopes(&connection, &[TODOIST_DELETE_SCOPE])?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_delete, &connection.id);
+            let url = todoist_url(&api, &["tasks", task_id])?;
 
-            Ok(json!({ "calendars": grouped }))
+            todoist_json(Client::new().delete(url).bearer_auth(token).send())?;
+            mark_cache_stale(&db_for_delete, &connection.id, SYNC_RESOURCE_TODOIST);
+            Ok(json!({ "ok": true }))
         }),
         preview: None,
     };
 
-    let create_event = ToolDefinition {
+    let move_task = ToolDefinition {
         metadata: ToolMetadata {
-            name: "gcal.create_event".to_string(),
-            description: "Create a Google Calendar event.".to_string(),
+            name: "todoist.move_task".to_string(),
+            description: "Move a Todoist task to another project, a section, or under a parent task. Subtasks move with it.".to_string(),
             args_schema: json!({
                 "type": "object",
                 "properties": {
                     "connection_id": {
                         "type": "string",
-                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                        "description": "Optional. Omit to use the default connected Todoist account."
                     },
-                    "calendar_id": { "type": "string" },
-                    "summary": { "type": "string" },
-                    "description": { "type": "string" },
-                    "start": { "type": "string" },
-                    "end": { "type": "string" },
-                    "time_zone": { "type": "string" },
-                    "attendees": { "type": "array", "items": { "type": "string" } }
+                    "task_id": { "type": "string" },
+                    "project_id": { "type": "string" },
+                    "section_id": { "type": "string" },
+                    "parent_id": { "type": "string" }
                 },
-                "required": ["summary", "start", "end"]
+                "required": ["task_id"]
             }),
             result_schema: json!({ "type": "object" }),
             requires_approval: true,
-            result_mode: ToolResultMode::Auto,
+            result_mode: ToolResultMode::Inline,
         },
         handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
             let connection_id = args
                 .get("connection_id")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            let connection = get_connection(&db_for_create, connection_id, "google_calendar")?;
-            let token = get_google_access_token(&db_for_create, &connection)?;
-
-            let calendar_id = args
-                .get("calendar_id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("primary");
-            let url =
-                format!("https://www.googleapis.com/calendar/v3/calendars/{calendar_id}/events");
-            let summary = args.get("summary").and_then(|v| v.as_str()).unwrap_or("");
-            let description = args.get("description").and_then(|v| v.as_str());
-            let start = args.get("start").and_then(|v| v.as_str()).unwrap_or("");
-            let end = args.get("end").and_then(|v| v.as_str()).unwrap_or("");
-            let time_zone = args.get("time_zone").and_then(|v| v.as_str());
-            let attendees = args
-                .get("attendees")
-                .and_then(|v| v.as_array())
-                .map(|items| {
-                    items
-                        .iter()
-                        .filter_map(|item| item.as_str())
-                        .map(|email| json!({ "email": email }))
-                        .collect::<Vec<_>>()
-                });
-
-            let event = json!({
-                "summary": summary,
-                "description": description,
-                "start": {
-                    "dateTime": start,
-                    "timeZone": time_zone
-                },
-                "end": {
-                    "dateTime": end,
-                    "timeZone": time_zone
-                },
-                "attendees": attendees
-            });
+            let mut operation = args.clone();
+            operation["action"] = json!("move");
+            let command = todoist_sync_command(&operation)?;
+            let connection = get_connection(&db_for_move, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_SYNC_API.base_url(&db_for_move, &connection.id);
 
-            let client = Client::new();
-            let response = client
-                .post(url)
-                .bearer_auth(token)
-                .json(&event)
-                .send()
-                .map_err(|err| {
-                    ToolError::new(format!("Failed to call Google Calendar API: {err}"))
-                })?;
-            let status = response.status();
-            if !status.is_success() {
-                return Err(ToolError::new(format!(
-                    "Google Calendar API error: HTTP {status}"
+            let result = run_todoist_commands(&api, &token, vec![command])?
+                .pop()
+                .unwrap_or(Value::Null);
+            mark_cache_stale(&db_for_move, &connection.id, SYNC_RESOURCE_TODOIST);
+            if result.get("ok").and_then(|v| v.as_bool()) != Some(true) {
+                return Err(ToolError::upstream(format!(
+                    "Todoist rejected the move: {}",
+                    result.get("error").cloned().unwrap_or(Value::Null)
                 )));
             }
-
-            response
-                .json::<Value>()
-                .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))
+            Ok(result)
         }),
         preview: None,
     };
 
-    let update_event = ToolDefinition {
+    let bulk_update = ToolDefinition {
         metadata: ToolMetadata {
-            name: "gcal.update_event".to_string(),
-            description: "Update fields on an existing Google Calendar event.".to_string(),
+            name: "todoist.bulk_update".to_string(),
+            description: "Apply many task changes at once through the Todoist Sync API, e.g. rescheduling a week's tasks. Each operation updates, moves, completes, reopens or deletes one task; results are reported per operation.".to_string(),
             args_schema: json!({
                 "type": "object",
                 "properties": {
                     "connection_id": {
                         "type": "string",
-                        "description": "Optional. Omit to use the default connected Google Calendar account."
+                        "description": "Optional. Omit to use the default connected Todoist account."
                     },
-                    "event_id": { "type": "string" },
-                    "calendar_id": { "type": "string" },
-                    "summary": { "type": "string" },
-                    "description": { "type": "string" },
-                    "location": { "type": "string" },
-                    "start": { "type": "string" },
-                    "end": { "type": "string" },
-                    "time_zone": { "type": "string" },
-                    "attendees": { "type": "array", "items": { "type": "string" } }
+                    "operations": {
+                        "type": "array",
+                        "minItems": 1,
+                        "maxItems": 500,
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "action": { "type": "string", "enum": ["update", "move", "complete", "reopen", "delete"] },
+                                "task_id": { "type": "string" },
+                                "content": { "type": "string" },
+                                "description": { "type": "string" },
+                                "labels": { "type": "array", "items": { "type": "string" } },
+                                "priority": { "type": "integer", "minimum": 1, "maximum": 4 },
+                                "due_string": { "type": "string" },
+                                "due_date": { "type": "string" },
+                                "due_datetime": { "type": "string" },
+                                "project_id": { "type": "string" },
+                                "section_id": { "type": "string" },
+                                "parent_id": { "type": "string" }
+                            },
+                            "required": ["action", "task_id"]
+                        }
+                    }
                 },
-                "required": ["event_id"]
+                "required": ["operations"]
+            }),
+            result_schema: json!({
+                "type": "object",
+                "properties": {
+                    "results": { "type": "array" },
+                    "succeeded": { "type": "integer" },
+                    "failed": { "type": "integer" }
+                }
             }),
-            result_schema: json!({ "type": "object" }),
             requires_approval: true,
             result_mode: ToolResultMode::Auto,
         },
@@ -1059,108 +3638,156 @@ fn register_google_calendar_tools(registry: &mut ToolRegistry, db: Db) -> Result
                 .get("connection_id")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            let connection = get_connection(&db_for_update, connection_id, "google_calendar")?;
-            let token = get_google_access_token(&db_for_update, &connection)?;
-
-            let event_id = args
-                .get("event_id")
-                .and_then(|v| v.as_str())
-                .map(|value| value.trim())
-                .filter(|value| !value.is_empty())
-                .ok_or_else(|| ToolError::new("event_id is required"))?;
-            let calendar_id = args
-                .get("calendar_id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("primary");
-            let url = format!(
-                "https://www.googleapis.com/calendar/v3/calendars/{calendar_id}/events/{event_id}"
-            );
-
-            let mut event = serde_json::Map::new();
-
-            if let Some(summary) = args.get("summary").and_then(|v| v.as_str()) {
-                event.insert("summary".to_string(), json!(summary));
-            }
-            if let Some(description) = args.get("description").and_then(|v| v.as_str()) {
-                event.insert("description".to_string(), json!(description));
-            }
-            if let Some(location) = args.get("location").and_then(|v| v.as_str()) {
-                event.insert("location".to_string(), json!(location));
-            }
-
-            let time_zone = args.get("time_zone").and_then(|v| v.as_str());
-            if let Some(start) = args.get("start").and_then(|v| v.as_str()) {
-                event.insert(
-                    "start".to_string(),
-                    json!({
-                        "dateTime": start,
-                        "timeZone": time_zone
-                    }),
-                );
-            }
-            if let Some(end) = args.get("end").and_then(|v| v.as_str()) {
-                event.insert(
-                    "end".to_string(),
-                    json!({
-                        "dateTime": end,
-                        "timeZone": time_zone
-                    }),
-                );
+            let operations = args
+                .get("operations")
+                .and_then(|v| v.as_array())
+                .filter(|operations| !operations.is_empty())
+                .ok_or_else(|| ToolError::validation("Missing 'operations'"))?;
+            // Validate everything first so a bad entry doesn't leave a half-applied batch.
+            let commands = operations
+                .iter()
+                .enumerate()
+                .map(|(index, operation)| {
+                    todoist_sync_command(operation).map_err(|err| {
+                        ToolError::validation(format!("Operation {index}: {}", err.message))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let connection = get_connection(&db_for_bulk, connection_id, "todoist")?;
+            let deletes = operations
+                .iter()
+                .any(|operation| operation.get("action").and_then(|v| v.as_str()) == Some("delete"));
+            if deletes {
+                require_scopes(&connection, &[TODOIST_DELETE_SCOPE])?;
             }
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_SYNC_API.base_url(&db_for_bulk, &connection.id);
 
-            if let Some(attendees) = args.get("attendees").and_then(|v| v.as_array()) {
-                let values = attendees
-                    .iter()
-                    .filter_map(|item| item.as_str())
-                    .map(|email| json!({ "email": email }))
-                    .collect::<Vec<_>>();
-                event.insert("attendees".to_string(), json!(values));
-            }
+            let results = run_todoist_commands(&api, &token, commands)?;
+            mark_cache_stale(&db_for_bulk, &connection.id, SYNC_RESOURCE_TODOIST);
+            let succeeded = results
+                .iter()
+                .filter(|result| result.get("ok").and_then(|v| v.as_bool()) == Some(true))
+                .count();
+            Ok(json!({
+                "failed": results.len() - succeeded,
+                "succeeded": succeeded,
+                "results": results,
+            }))
+        }),
+        preview: None,
+    };
 
-            if event.is_empty() {
-                return Err(ToolError::new(
-                    "Provide at least one field to update (for example summary, start, or end)",
-                ));
-            }
+    let list_projects = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "todoist.list_projects".to_string(),
+            description: "List Todoist projects.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Todoist account."
+                    }
+                }
+            }),
+            result_schema: json!({ "type": "array" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_projects, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_projects, &connection.id);
+            todoist_json(
+                Client::new()
+                    .get(format!("{api}/projects"))
+                    .bearer_auth(token)
+                    .send(),
+            )
+        }),
+        preview: None,
+    };
 
-            let client = Client::new();
-            let response = client
-                .patch(url)
-                .bearer_auth(token)
-                .json(&Value::Object(event))
-                .send()
-                .map_err(|err| {
-                    ToolError::new(format!("Failed to call Google Calendar API: {err}"))
-                })?;
-            let status = response.status();
-            if !status.is_success() {
-                return Err(ToolError::new(format!(
-                    "Google Calendar API error: HTTP {status}"
-                )));
+    let list_sections = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "todoist.list_sections".to_string(),
+            description: "List Todoist sections, optionally for one project.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Todoist account."
+                    },
+                    "project_id": { "type": "string" }
+                }
+            }),
+            result_schema: json!({ "type": "array" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_sections, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_sections, &connection.id);
+            let mut request = Client::new().get(format!("{api}/sections"));
+            if let Some(project_id) = args.get("project_id").and_then(|v| v.as_str()) {
+                request = request.query(&[("project_id", project_id)]);
             }
-
-            response
-                .json::<Value>()
-                .map_err(|err| ToolError::new(format!("Failed to parse Calendar response: {err}")))
+            todoist_json(request.bearer_auth(token).send())
         }),
         preview: None,
     };
 
-    registry.register(list_calendars)?;
-    registry.register(list_events)?;
-    registry.register(create_event)?;
-    registry.register(update_event)?;
-    Ok(())
-}
+    let list_labels = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "todoist.list_labels".to_string(),
+            description: "List the Todoist account's personal labels.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Todoist account."
+                    }
+                }
+            }),
+            result_schema: json!({ "type": "array" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let connection = get_connection(&db_for_labels, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_labels, &connection.id);
+            todoist_json(
+                Client::new()
+                    .get(format!("{api}/labels"))
+                    .bearer_auth(token)
+                    .send(),
+            )
+        }),
+        preview: None,
+    };
 
-fn register_todoist_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
-    let db_for_list = db.clone();
-    let db_for_create = db.clone();
-    let db_for_complete = db.clone();
-    let list_tasks = ToolDefinition {
+    let save_label = ToolDefinition {
         metadata: ToolMetadata {
-            name: "todoist.list_tasks".to_string(),
-            description: "List Todoist tasks.".to_string(),
+            name: "todoist.save_label".to_string(),
+            description: "Create a Todoist label, or rename/recolor one when label_id is given."
+                .to_string(),
             args_schema: json!({
                 "type": "object",
                 "properties": {
@@ -1168,51 +3795,66 @@ fn register_todoist_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                         "type": "string",
                         "description": "Optional. Omit to use the default connected Todoist account."
                     },
-                    "project_id": { "type": "string" },
-                    "filter": { "type": "string" }
+                    "label_id": { "type": "string", "description": "Omit to create a new label." },
+                    "name": { "type": "string" },
+                    "color": { "type": "string", "description": "Todoist color name, e.g. 'berry_red'." },
+                    "is_favorite": { "type": "boolean" }
                 }
             }),
-            result_schema: json!({ "type": "array" }),
+            result_schema: json!({ "type": "object" }),
             requires_approval: true,
-            result_mode: ToolResultMode::Auto,
+            result_mode: ToolResultMode::Inline,
         },
         handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
             let connection_id = args
                 .get("connection_id")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            let connection = get_connection(&db_for_list, connection_id, "todoist")?;
-            let token = get_access_token(&connection)?;
-
-            let client = Client::new();
-            let mut request = client.get("https://api.todoist.com/rest/v2/tasks");
-            if let Some(project_id) = args.get("project_id").and_then(|v| v.as_str()) {
-                request = request.query(&[("project_id", project_id)]);
+            let label_id = args
+                .get("label_id")
+                .and_then(|v| v.as_str())
+                .filter(|value| !value.trim().is_empty());
+            let mut payload = serde_json::Map::new();
+            for key in ["name", "color"] {
+                if let Some(value) = args.get(key).and_then(|v| v.as_str()) {
+                    payload.insert(key.to_string(), json!(value));
+                }
             }
-            if let Some(filter) = args.get("filter").and_then(|v| v.as_str()) {
-                request = request.query(&[("filter", filter)]);
+            if let Some(is_favorite) = args.get("is_favorite").and_then(|v| v.as_bool()) {
+                payload.insert("is_favorite".to_string(), json!(is_favorite));
             }
-
-            let response = request
-                .bearer_auth(token)
-                .send()
-                .map_err(|err| ToolError::new(format!("Failed to call Todoist API: {err}")))?;
-            let status = response.status();
-            if !status.is_success() {
-                return Err(ToolError::new(format!("Todoist API error: HTTP {status}")));
+            if label_id.is_none() && !payload.contains_key("name") {
+                return Err(ToolError::validation("Missing 'name' for the new label"));
+            }
+            if payload.is_empty() {
+                return Err(ToolError::validation("No label changes requested"));
             }
+            let connection = get_connection(&db_for_save_label, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_save_label, &connection.id);
 
-            response
-                .json::<Value>()
-                .map_err(|err| ToolError::new(format!("Failed to parse Todoist response: {err}")))
+            let url = match label_id {
+                Some(label_id) => todoist_url(&api, &["labels", label_id])?,
+                None => todoist_url(&api, &["labels"])?,
+            };
+            let saved = todoist_json(
+                Client::new()
+                    .post(url)
+                    .bearer_auth(token)
+                    .json(&Value::Object(payload))
+                    .send(),
+            )?;
+            // Renaming a label renames it on every task.
+            mark_cache_stale(&db_for_save_label, &connection.id, SYNC_RESOURCE_TODOIST);
+            Ok(saved)
         }),
         preview: None,
     };
 
-    let create_task = ToolDefinition {
+    let delete_label = ToolDefinition {
         metadata: ToolMetadata {
-            name: "todoist.create_task".to_string(),
-            description: "Create a Todoist task.".to_string(),
+            name: "todoist.delete_label".to_string(),
+            description: "Delete a Todoist label and remove it from all tasks.".to_string(),
             args_schema: json!({
                 "type": "object",
                 "properties": {
@@ -1220,79 +3862,77 @@ fn register_todoist_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                         "type": "string",
                         "description": "Optional. Omit to use the default connected Todoist account."
                     },
-                    "content": { "type": "string" },
-                    "description": { "type": "string" },
-                    "project_id": { "type": "string" },
-                    "labels": { "type": "array", "items": { "type": "string" } },
-                    "priority": { "type": "integer", "minimum": 1, "maximum": 4 },
-                    "due_string": { "type": "string" },
-                    "due_date": { "type": "string" },
-                    "due_datetime": { "type": "string" }
+                    "label_id": { "type": "string" }
                 },
-                "required": ["content"]
+                "required": ["label_id"]
             }),
             result_schema: json!({ "type": "object" }),
             requires_approval: true,
-            result_mode: ToolResultMode::Auto,
+            result_mode: ToolResultMode::Inline,
         },
         handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
             let connection_id = args
                 .get("connection_id")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            let connection = get_connection(&db_for_create, connection_id, "todoist")?;
+            let label_id = required_str_arg(&args, "label_id")?;
+            let connection = get_connection(&db_for_delete_label, connection_id, "todoist")?;
+            require_scopes(&connection, &[TODOIST_DELETE_SCOPE])?;
             let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_delete_label, &connection.id);
+            let url = todoist_url(&api, &["labels", label_id])?;
 
-            let mut payload = serde_json::Map::new();
-            if let Some(content) = args.get("content").and_then(|v| v.as_str()) {
-                payload.insert("content".to_string(), json!(content));
-            }
-            if let Some(description) = args.get("description").and_then(|v| v.as_str()) {
-                payload.insert("description".to_string(), json!(description));
-            }
-            if let Some(project_id) = args.get("project_id").and_then(|v| v.as_str()) {
-                payload.insert("project_id".to_string(), json!(project_id));
-            }
-            if let Some(labels) = args.get("labels").and_then(|v| v.as_array()) {
-                let labels = labels.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>();
-                payload.insert("labels".to_string(), json!(labels));
-            }
-            if let Some(priority) = args.get("priority").and_then(|v| v.as_i64()) {
-                payload.insert("priority".to_string(), json!(priority));
-            }
-            if let Some(due_string) = args.get("due_string").and_then(|v| v.as_str()) {
-                payload.insert("due_string".to_string(), json!(due_string));
-            }
-            if let Some(due_date) = args.get("due_date").and_then(|v| v.as_str()) {
-                payload.insert("due_date".to_string(), json!(due_date));
-            }
-            if let Some(due_datetime) = args.get("due_datetime").and_then(|v| v.as_str()) {
-                payload.insert("due_datetime".to_string(), json!(due_datetime));
-            }
-
-            let client = Client::new();
-            let response = client
-                .post("https://api.todoist.com/rest/v2/tasks")
-                .bearer_auth(token)
-                .json(&Value::Object(payload))
-                .send()
-                .map_err(|err| ToolError::new(format!("Failed to call Todoist API: {err}")))?;
-            let status = response.status();
-            if !status.is_success() {
-                return Err(ToolError::new(format!("Todoist API error: HTTP {status}")));
-            }
+            todoist_json(Client::new().delete(url).bearer_auth(token).send())?;
+            mark_cache_stale(&db_for_delete_label, &connection.id, SYNC_RESOURCE_TODOIST);
+            Ok(json!({ "ok": true }))
+        }),
+        preview: None,
+    };
 
-            response
-                .json::<Value>()
-                .map_err(|err| ToolError::new(format!("Failed to parse Todoist response: {err}")))
+    let list_comments = ToolDefinition {
+        metadata: ToolMetadata {
+            name: "todoist.list_comments".to_string(),
+            description: "List the comments on a Todoist task or project.".to_string(),
+            args_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": {
+                        "type": "string",
+                        "description": "Optional. Omit to use the default connected Todoist account."
+                    },
+                    "task_id": { "type": "string" },
+                    "project_id": { "type": "string" }
+                }
+            }),
+            result_schema: json!({ "type": "array" }),
+            requires_approval: true,
+            result_mode: ToolResultMode::Auto,
+        },
+        handler: std::sync::Arc::new(move |args, _ctx: ToolExecutionContext| {
+            let connection_id = args
+                .get("connection_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let (target_key, target_id) = todoist_comment_target(&args)?;
+            let connection = get_connection(&db_for_comments, connection_id, "todoist")?;
+            let token = get_access_token(&connection)?;
+            let api = TODOIST_REST_API.base_url(&db_for_comments, &connection.id);
+            todoist_json(
+                Client::new()
+                    .get(format!("{api}/comments"))
+                    .query(&[(target_key, target_id)])
+                    .bearer_auth(token)
+                    .send(),
+            )
         }),
         preview: None,
     };
 
-    let complete_task = ToolDefinition {
+    let add_comment = ToolDefinition {
         metadata: ToolMetadata {
-            name: "todoist.complete_task".to_string(),
-            description: "Mark a Todoist task as complete.".to_string(),
+            name: "todoist.add_comment".to_string(),
+            description: "Add a comment to a Todoist task or project. Markdown is supported."
+                .to_string(),
             args_schema: json!({
                 "type": "object",
                 "properties": {
@@ -1300,9 +3940,11 @@ fn register_todoist_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                         "type": "string",
                         "description": "Optional. Omit to use the default connected Todoist account."
                     },
-                    "task_id": { "type": "string" }
+                    "task_id": { "type": "string" },
+                    "project_id": { "type": "string" },
+                    "content": { "type": "string" }
                 },
-                "required": ["task_id"]
+                "required": ["content"]
             }),
             result_schema: json!({ "type": "object" }),
             requires_approval: true,
@@ -1313,27 +3955,20 @@ fn register_todoist_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                 .get("connection_id")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            let connection = get_connection(&db_for_complete, connection_id, "todoist")?;
+            let (target_key, target_id) = todoist_comment_target(&args)?;
+            let content = required_str_arg(&args, "content")?;
+            let mut payload = json!({ "content": content });
+            payload[target_key] = json!(target_id);
+            let connection = get_connection(&db_for_add_comment, connection_id, "todoist")?;
             let token = get_access_token(&connection)?;
-
-            let task_id = args.get("task_id").and_then(|v| v.as_str()).unwrap_or("");
-            if task_id.is_empty() {
-                return Err(ToolError::new("Missing task_id"));
-            }
-
-            let client = Client::new();
-            let url = format!("https://api.todoist.com/rest/v2/tasks/{task_id}/close");
-            let response = client
-                .post(url)
-                .bearer_auth(token)
-                .send()
-                .map_err(|err| ToolError::new(format!("Failed to call Todoist API: {err}")))?;
-            let status = response.status();
-            if !status.is_success() {
-                return Err(ToolError::new(format!("Todoist API error: HTTP {status}")));
-            }
-
-            Ok(json!({ "ok": true }))
+            let api = TODOIST_REST_API.base_url(&db_for_add_comment, &connection.id);
+            todoist_json(
+                Client::new()
+                    .post(format!("{api}/comments"))
+                    .bearer_auth(token)
+                    .json(&payload)
+                    .send(),
+            )
         }),
         preview: None,
     };
@@ -1341,14 +3976,34 @@ fn register_todoist_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
     registry.register(list_tasks)?;
     registry.register(create_task)?;
     registry.register(complete_task)?;
+    registry.register(update_task)?;
+    registry.register(reopen_task)?;
+    registry.register(delete_task)?;
+    registry.register(move_task)?;
+    registry.register(bulk_update)?;
+    registry.register(list_projects)?;
+    registry.register(list_sections)?;
+    registry.register(list_labels)?;
+    registry.register(save_label)?;
+    registry.register(delete_label)?;
+    registry.register(list_comments)?;
+    registry.register(add_comment)?;
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{register_gmail_tools, register_google_calendar_tools, register_todoist_tools};
+    use super::{
+        calendar_events_url, free_slots, gmail_url, label_changes, register_gmail_tools,
+        register_google_calendar_tools, register_todoist_tools, set_own_response, split_addresses,
+        todoist_sync_command, todoist_url, OriginalMessage, OutgoingMail, WorkingHours,
+        GOOGLE_CALENDAR_API, TODOIST_REST_API,
+    };
     use crate::db::Db;
-    use crate::tools::ToolRegistry;
+    use crate::integrations::mock_server::MockServer;
+    use crate::tools::{ToolErrorKind, ToolExecutionContext, ToolRegistry};
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine as _;
     use serde_json::{json, Value};
     use uuid::Uuid;
 
@@ -1381,20 +4036,73 @@ mod tests {
         register_gmail_tools(&mut registry, db.clone()).expect("gmail tools registration failed");
         register_google_calendar_tools(&mut registry, db.clone())
             .expect("gcal tools registration failed");
-        register_todoist_tools(&mut registry, db).expect("todoist tools registration failed");
+        register_todoist_tools(&mut registry, db.clone())
+            .expect("todoist tools registration failed");
+        crate::tools::register_email_tools(&mut registry, db.clone())
+            .expect("email tools registration failed");
+        crate::tools::register_caldav_tools(&mut registry, db.clone())
+            .expect("caldav tools registration failed");
+        crate::tools::register_ics_tools(&mut registry, db.clone())
+            .expect("ics tools registration failed");
+        crate::tools::register_github_tools(&mut registry, db)
+            .expect("github tools registration failed");
 
         let tool_names = [
             "gmail.list_threads",
             "gmail.get_thread",
+            "gmail.search_cache",
             "gmail.list_labels",
             "gmail.send_message",
+            "gmail.list_drafts",
+            "gmail.create_draft",
+            "gmail.update_draft",
+            "gmail.send_draft",
+            "gmail.reply",
+            "gmail.forward",
+            "gmail.modify_labels",
+            "gmail.download_attachment",
             "gcal.list_calendars",
             "gcal.list_events",
             "gcal.create_event",
             "gcal.update_event",
+            "gcal.delete_event",
+            "gcal.respond",
+            "gcal.find_free_time",
             "todoist.list_tasks",
             "todoist.create_task",
             "todoist.complete_task",
+            "todoist.update_task",
+            "todoist.reopen_task",
+            "todoist.delete_task",
+            "todoist.move_task",
+            "todoist.bulk_update",
+            "todoist.list_projects",
+            "todoist.list_sections",
+            "todoist.list_labels",
+            "todoist.save_label",
+            "todoist.delete_label",
+            "todoist.list_comments",
+            "todoist.add_comment",
+            "email.list_threads",
+            "email.get_thread",
+            "email.list_mailboxes",
+            "email.send_message",
+            "email.reply",
+            "email.forward",
+            "caldav.list_calendars",
+            "caldav.list_events",
+            "caldav.create_event",
+            "caldav.update_event",
+            "caldav.delete_event",
+            "ics.list_events",
+            "github.search_issues",
+            "github.list_issues",
+            "github.list_pull_requests",
+            "github.get_issue",
+            "github.get_pull_request",
+            "github.create_issue",
+            "github.add_comment",
+            "github.list_notifications",
         ];
 
         for tool_name in tool_names {
@@ -1415,7 +4123,16 @@ mod tests {
         register_gmail_tools(&mut registry, db.clone()).expect("gmail tools registration failed");
         register_google_calendar_tools(&mut registry, db.clone())
             .expect("gcal tools registration failed");
-        register_todoist_tools(&mut registry, db).expect("todoist tools registration failed");
+        register_todoist_tools(&mut registry, db.clone())
+            .expect("todoist tools registration failed");
+        crate::tools::register_email_tools(&mut registry, db.clone())
+            .expect("email tools registration failed");
+        crate::tools::register_caldav_tools(&mut registry, db.clone())
+            .expect("caldav tools registration failed");
+        crate::tools::register_ics_tools(&mut registry, db.clone())
+            .expect("ics tools registration failed");
+        crate::tools::register_github_tools(&mut registry, db)
+            .expect("github tools registration failed");
 
         let cases = [
             ("gmail.list_threads", json!({})),
@@ -1428,6 +4145,29 @@ mod tests {
                     "body": "Body"
                 }),
             ),
+            ("gmail.create_draft", json!({ "body": "Draft body" })),
+            ("gmail.update_draft", json!({ "draft_id": "draft-123" })),
+            ("gmail.send_draft", json!({ "draft_id": "draft-123" })),
+            (
+                "gmail.reply",
+                json!({ "message_id": "message-123", "body": "Thanks!" }),
+            ),
+            (
+                "gmail.forward",
+                json!({ "message_id": "message-123", "to": ["user@example.com"] }),
+            ),
+            (
+                "gmail.modify_labels",
+                json!({ "thread_id": "thread-123", "archive": true }),
+            ),
+            (
+                "gmail.download_attachment",
+                json!({
+                    "message_id": "message-123",
+                    "attachment_id": "attachment-123",
+                    "path": "attachments/file.pdf"
+                }),
+            ),
             ("gcal.list_events", json!({})),
             (
                 "gcal.create_event",
@@ -1438,9 +4178,113 @@ mod tests {
                 }),
             ),
             ("gcal.update_event", json!({ "event_id": "event-123" })),
+            ("gcal.delete_event", json!({ "event_id": "event-123" })),
+            (
+                "gcal.respond",
+                json!({ "event_id": "event-123", "response": "accepted" }),
+            ),
+            ("gcal.find_free_time", json!({ "duration_minutes": 30 })),
             ("todoist.list_tasks", json!({})),
             ("todoist.create_task", json!({ "content": "Ship fix" })),
             ("todoist.complete_task", json!({ "task_id": "task-123" })),
+            (
+                "todoist.update_task",
+                json!({ "task_id": "task-123", "due_string": "next monday" }),
+            ),
+            ("todoist.reopen_task", json!({ "task_id": "task-123" })),
+            ("todoist.delete_task", json!({ "task_id": "task-123" })),
+            (
+                "todoist.move_task",
+                json!({ "task_id": "task-123", "project_id": "project-1" }),
+            ),
+            (
+                "todoist.bulk_update",
+                json!({ "operations": [{ "action": "complete", "task_id": "task-123" }] }),
+            ),
+            ("todoist.list_projects", json!({})),
+            ("todoist.list_sections", json!({})),
+            ("todoist.list_labels", json!({})),
+            ("todoist.save_label", json!({ "name": "waiting" })),
+            ("todoist.delete_label", json!({ "label_id": "label-1" })),
+            ("todoist.list_comments", json!({ "task_id": "task-123" })),
+            (
+                "todoist.add_comment",
+                json!({ "task_id": "task-123", "content": "Done" }),
+            ),
+            ("email.list_threads", json!({ "unread": true })),
+            (
+                "email.get_thread",
+                json!({ "thread_id": "<root@example.com>" }),
+            ),
+            (
+                "email.send_message",
+                json!({
+                    "to": ["user@example.com"],
+                    "subject": "Subject",
+                    "body": "Body"
+                }),
+            ),
+            (
+                "email.reply",
+                json!({ "message_id": "<root@example.com>", "body": "Thanks!" }),
+            ),
+            (
+                "email.forward",
+                json!({ "message_id": "<root@example.com>", "to": ["user@example.com"] }),
+            ),
+            ("caldav.list_calendars", json!({})),
+            ("caldav.list_events", json!({ "expand_recurring": false })),
+            (
+                "caldav.create_event",
+                json!({
+                    "summary": "Standup",
+                    "start": "2026-01-05T09:00",
+                    "end": "2026-01-05T09:15",
+                    "time_zone": "Europe/Berlin",
+                    "recurrence": ["RRULE:FREQ=WEEKLY;BYDAY=MO"]
+                }),
+            ),
+            (
+                "caldav.update_event",
+                json!({ "event_id": "uid-1_20260112T080000Z", "summary": "Standup" }),
+            ),
+            (
+                "caldav.delete_event",
+                json!({ "event_id": "uid-1", "apply_to": "series" }),
+            ),
+            ("ics.list_events", json!({ "query": "holiday" })),
+            (
+                "github.search_issues",
+                json!({ "query": "crash", "repo": "octo/widgets", "type": "issue" }),
+            ),
+            (
+                "github.list_issues",
+                json!({ "repo": "octo/widgets", "labels": ["bug"] }),
+            ),
+            (
+                "github.list_pull_requests",
+                json!({ "repo": "octo/widgets", "state": "all" }),
+            ),
+            (
+                "github.get_issue",
+                json!({ "repo": "octo/widgets", "number": 7 }),
+            ),
+            (
+                "github.get_pull_request",
+                json!({ "repo": "octo/widgets", "number": 8, "include_diff": false }),
+            ),
+            (
+                "github.create_issue",
+                json!({ "repo": "octo/widgets", "title": "Crash on start" }),
+            ),
+            (
+                "github.add_comment",
+                json!({ "repo": "octo/widgets", "number": 7, "body": "Thanks!" }),
+            ),
+            (
+                "github.list_notifications",
+                json!({ "participating": true }),
+            ),
         ];
 
         for (tool_name, args) in cases {
@@ -1455,4 +4299,321 @@ mod tests {
                 });
         }
     }
+
+    fn original_message() -> OriginalMessage {
+        OriginalMessage {
+            thread_id: "thread-1".to_string(),
+            message_id_header: Some("<b@example.com>".to_string()),
+            references: Some("<a@example.com>".to_string()),
+            subject: "Quarterly plan".to_string(),
+            from: "\"Doe, Jane\" <jane@example.com>".to_string(),
+            reply_to: None,
+            to: "me@example.com, Bob <bob@example.com>".to_string(),
+            cc: "carol@example.com".to_string(),
+            date: Some("Mon, 5 Jan 2026 10:00:00 +0000".to_string()),
+            body: "Line one\n\nLine two".to_string(),
+        }
+    }
+
+    #[test]
+    fn replies_keep_thread_headers_and_skip_own_address() {
+        let original = original_message();
+        let reply = original.reply("Sounds good", true, Some("Me@Example.com"));
+        assert_eq!(reply.to, "\"Doe, Jane\" <jane@example.com>");
+        assert_eq!(reply.cc, "Bob <bob@example.com>, carol@example.com");
+        assert_eq!(reply.subject, "Re: Quarterly plan");
+        assert_eq!(reply.in_reply_to.as_deref(), Some("<b@example.com>"));
+        assert_eq!(
+            reply.references.as_deref(),
+            Some("<a@example.com> <b@example.com>")
+        );
+        assert!(reply.body.contains("> Line one\r\n>\r\n> Line two"));
+
+        let direct = original.reply("Thanks", false, None);
+        assert!(direct.cc.is_empty());
+
+        let forward = original.forward("dan@example.com".to_string(), "FYI");
+        assert_eq!(forward.subject, "Fwd: Quarterly plan");
+        assert!(forward.body.starts_with("FYI"));
+        assert!(forward
+            .body
+            .contains("From: \"Doe, Jane\" <jane@example.com>"));
+    }
+
+    #[test]
+    fn outgoing_mail_encodes_headers_safely() {
+        let mail = OutgoingMail {
+            to: "a@example.com".to_string(),
+            subject: "Grüße\r\nBcc: evil@example.com".to_string(),
+            body: "Hello".to_string(),
+            in_reply_to: Some("<b@example.com>".to_string()),
+            ..OutgoingMail::default()
+        };
+        let raw = String::from_utf8(URL_SAFE_NO_PAD.decode(mail.to_raw()).expect("base64"))
+            .expect("utf8");
+        assert!(raw.contains("In-Reply-To: <b@example.com>\r\n"));
+        assert!(raw.contains("Subject: =?UTF-8?B?"));
+        assert!(!raw.contains("\r\nBcc:"));
+        assert!(raw.ends_with("\r\n\r\nHello"));
+    }
+
+    #[test]
+    fn address_and_label_helpers() {
+        assert_eq!(
+            split_addresses("\"Doe, Jane\" <jane@example.com>, bob@example.com"),
+            vec!["\"Doe, Jane\" <jane@example.com>", "bob@example.com"]
+        );
+        let (add, remove) = label_changes(&json!({
+            "add_label_ids": ["Label_1"],
+            "archive": true,
+            "star": true,
+            "read": false
+        }));
+        assert_eq!(add, vec!["Label_1", "STARRED", "UNREAD"]);
+        assert_eq!(remove, vec!["INBOX"]);
+    }
+
+    #[test]
+    fn free_slots_respect_working_hours_and_busy_periods() {
+        let hour = 60 * 60 * 1000;
+        // Monday 2026-01-05 00:00 UTC.
+        let monday = 1_767_571_200_000;
+        let hours = WorkingHours::resolve(&json!({}), &json!({ "working_days": [1] }))
+            .expect("valid working hours");
+        let busy = [
+            (monday + 10 * hour, monday + 11 * hour),
+            (monday + 10 * hour + hour / 2, monday + 12 * hour),
+            (monday + 16 * hour + hour / 2, monday + 20 * hour),
+        ];
+        let slots = free_slots(
+            &chrono::Utc,
+            (monday, monday + 7 * 24 * hour),
+            &busy,
+            &hours,
+            hour,
+            10,
+        );
+        assert_eq!(
+            slots,
+            vec![
+                (monday + 9 * hour, monday + 10 * hour),
+                (monday + 12 * hour, monday + 16 * hour + hour / 2),
+            ]
+        );
+
+        let invalid = WorkingHours::resolve(&json!({ "working_hours_start": "18:00" }), &json!({}));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn gmail_url_keeps_ids_inside_their_segment() {
+        let url = gmail_url(
+            "https://gmail.googleapis.com/gmail/v1",
+            &["messages", "m1/../../drafts?x=1", "attachments", "a#1"],
+        )
+        .expect("valid url");
+        assert_eq!(
+            url.as_str(),
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages/m1%2F..%2F..%2Fdrafts%3Fx=1/attachments/a%231"
+        );
+    }
+
+    #[test]
+    fn calendar_helpers_encode_ids_and_update_own_response() {
+        let url = calendar_events_url(
+            GOOGLE_CALENDAR_API.default_url,
+            "en.usa#holiday@group.v.calendar.google.com",
+            Some("abc"),
+        )
+        .expect("valid url");
+        assert_eq!(
+            url.as_str(),
+            "https://www.googleapis.com/calendar/v3/calendars/en.usa%23holiday@group.v.calendar.google.com/events/abc"
+        );
+
+        let event = json!({
+            "attendees": [
+                { "email": "organizer@example.com", "organizer": true, "responseStatus": "accepted" },
+                { "email": "me@example.com", "self": true, "responseStatus": "needsAction" }
+            ]
+        });
+        let attendees = set_own_response(&event, "declined", Some("Out of office"))
+            .expect("account is an attendee");
+        assert_eq!(attendees[1]["responseStatus"], "declined");
+        assert_eq!(attendees[1]["comment"], "Out of office");
+        assert_eq!(attendees[0]["responseStatus"], "accepted");
+        assert!(set_own_response(&json!({}), "accepted", None).is_none());
+    }
+
+    #[test]
+    fn todoist_bulk_operations_map_to_sync_commands() {
+        let update = todoist_sync_command(&json!({
+            "action": "update",
+            "task_id": "1",
+            "due_string": "next monday",
+            "priority": 4
+        }))
+        .expect("valid update");
+        assert_eq!(update["type"], "item_update");
+        assert_eq!(update["args"]["id"], "1");
+        assert_eq!(update["args"]["due"], json!({ "string": "next monday" }));
+        assert_eq!(update["args"]["priority"], 4);
+        assert!(update["uuid"].as_str().is_some_and(|uuid| !uuid.is_empty()));
+
+        let moved = todoist_sync_command(&json!({
+            "action": "move",
+            "task_id": "1",
+            "section_id": "s1"
+        }))
+        .expect("valid move");
+        assert_eq!(moved["type"], "item_move");
+        assert_eq!(moved["args"]["section_id"], "s1");
+
+        let reopen = todoist_sync_command(&json!({ "action": "reopen", "task_id": "1" }))
+            .expect("valid reopen");
+        assert_eq!(reopen["type"], "item_uncomplete");
+
+        assert!(todoist_sync_command(&json!({ "action": "update", "task_id": "1" })).is_err());
+        assert!(todoist_sync_command(&json!({
+            "action": "move",
+            "task_id": "1",
+            "project_id": "p1",
+            "section_id": "s1"
+        }))
+        .is_err());
+        assert!(todoist_sync_command(&json!({ "action": "archive", "task_id": "1" })).is_err());
+    }
+
+    #[test]
+    fn tool_flows_run_against_configured_api_base_urls() {
+        let db = setup_db();
+        let server = MockServer::start();
+        server
+            .route(
+                "GET",
+                "/users/me/threads",
+                200,
+                json!({ "threads": [{ "id": "t1" }] }),
+            )
+            .route("GET", "/users/me/calendarList", 200, json!({ "items": [] }))
+            .route(
+                "POST",
+                "/tasks",
+                200,
+                json!({ "id": "task-1", "content": "Ship fix" }),
+            );
+        server.connect(&db, "gmail");
+        server.connect(&db, "google_calendar");
+        server.connect(&db, "todoist");
+        let mut registry = ToolRegistry::new();
+        register_gmail_tools(&mut registry, db.clone()).expect("gmail tools registration failed");
+        register_google_calendar_tools(&mut registry, db.clone())
+            .expect("gcal tools registration failed");
+        register_todoist_tools(&mut registry, db).expect("todoist tools registration failed");
+        let call = |name: &str, args: Value| {
+            let tool = registry.get(name).expect("missing tool");
+            (tool.handler)(args, ToolExecutionContext::default())
+        };
+
+        let threads = call(
+            "gmail.list_threads",
+            json!({ "source": "live", "query": "is:unread" }),
+        )
+        .expect("gmail.list_threads failed");
+        assert_eq!(threads["threads"][0]["id"], "t1");
+        call("gcal.list_calendars", json!({})).expect("gcal.list_calendars failed");
+        let task = call("todoist.create_task", json!({ "content": "Ship fix" }))
+            .expect("todoist.create_task failed");
+        assert_eq!(task["id"], "task-1");
+
+        let requests = server.requests();
+        let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths[0], "/users/me/threads?q=is%3Aunread");
+        assert_eq!(paths[1], "/users/me/calendarList");
+        assert_eq!(paths[2], "/tasks");
+        assert!(requests
+            .iter()
+            .all(|r| r.header("authorization") == Some("Bearer mock-token")));
+        assert_eq!(requests[2].json()["content"], "Ship fix");
+
+        server.route("POST", "/tasks", 429, json!({ "error": "slow down" }));
+        let err = call("todoist.create_task", json!({ "content": "Again" }))
+            .expect_err("rate limit should fail the call");
+        assert_eq!(err.kind, ToolErrorKind::RateLimited);
+    }
+
+    #[test]
+    fn todoist_task_and_label_ids_stay_inside_their_segment() {
+        let db = setup_db();
+        let server = MockServer::start();
+        server
+            .route(
+                "DELETE",
+                "/tasks/1%2F..%2F..%2Flabels%2F7",
+                204,
+                Value::Null,
+            )
+            .route(
+                "POST",
+                "/labels/7%3Fx=1%23y",
+                200,
+                json!({ "id": "7", "name": "urgent" }),
+            );
+        server.connect(&db, "todoist");
+        let mut registry = ToolRegistry::new();
+        register_todoist_tools(&mut registry, db).expect("todoist tools registration failed");
+        let call = |name: &str, args: Value| {
+            let tool = registry.get(name).expect("missing tool");
+            (tool.handler)(args, ToolExecutionContext::default())
+        };
+
+        call(
+            "todoist.delete_task",
+            json!({ "task_id": "1/../../labels/7" }),
+        )
+        .expect("todoist.delete_task failed");
+        let label = call(
+            "todoist.save_label",
+            json!({ "label_id": "7?x=1#y", "name": "urgent" }),
+        )
+        .expect("todoist.save_label failed");
+        assert_eq!(label["name"], "urgent");
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/tasks/1%2F..%2F..%2Flabels%2F7");
+        assert_eq!(requests[1].path, "/labels/7%3Fx=1%23y");
+        assert_eq!(
+            todoist_url(TODOIST_REST_API.default_url, &["tasks", "a/b", "reopen"])
+                .expect("valid url")
+                .as_str(),
+            "https://api.todoist.com/rest/v2/tasks/a%2Fb/reopen"
+        );
+    }
+
+    #[test]
+    fn gmail_modify_labels_encodes_the_message_id() {
+        let db = setup_db();
+        let server = MockServer::start();
+        server.route(
+            "POST",
+            "/users/me/messages/m1%2F..%2Fdrafts%3Fx=1/modify",
+            200,
+            json!({ "id": "m1", "labelIds": ["STARRED"] }),
+        );
+        server.connect(&db, "gmail");
+        let mut registry = ToolRegistry::new();
+        register_gmail_tools(&mut registry, db).expect("gmail tools registration failed");
+        let tool = registry.get("gmail.modify_labels").expect("missing tool");
+
+        let modified = (tool.handler)(
+            json!({ "message_id": "m1/../drafts?x=1", "star": true }),
+            ToolExecutionContext::default(),
+        )
+        .expect("gmail.modify_labels failed");
+        assert_eq!(modified["labelIds"][0], "STARRED");
+        assert_eq!(
+            server.requests()[0].path,
+            "/users/me/messages/m1%2F..%2Fdrafts%3Fx=1/modify"
+        );
+    }
 }
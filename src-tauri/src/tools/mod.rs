@@ -1,30 +1,73 @@
 use jsonschema::JSONSchema;
-use serde::Serialize;
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::sync::{mpsc, Arc, Mutex};
 use uuid::Uuid;
 
+mod agent;
+mod approval_rules;
 mod approvals;
+mod audit;
+mod calendars;
+mod context;
+mod email;
+mod feeds;
 mod files;
+mod github;
 mod integrations;
 mod prefs;
+mod readability;
 mod search;
 mod tool_outputs;
 mod vault;
 mod web;
-
+mod web_allowlist;
+mod web_auth;
+mod web_crawl;
+mod web_policy;
+mod web_search;
+
+pub use agent::{register_agent_tools, DELEGATE_TOOL_NAME};
+pub use approval_rules::{
+    app_session_id, validate_rule_input as validate_tool_approval_rule_input, RULE_ACTION_ALLOW,
+    RULE_SCOPE_CONVERSATION, RULE_SCOPE_GLOBAL, RULE_SCOPE_SESSION,
+};
 pub use approvals::{
-    get_conversation_tool_approval_override, get_tool_approval_override,
     load_conversation_tool_approval_overrides, load_tool_approval_overrides,
-    set_conversation_tool_approval_override, set_tool_approval_override,
+    purge_stale_tool_approval_rules, resolve_tool_approval,
+    set_conversation_tool_approval_override, set_tool_approval_override, ToolApprovalResolution,
+};
+pub use audit::{
+    export_audit_entries, external_resource_id, record_tool_audit, ToolAuditRecord,
+    AUDIT_DECISION_APPROVED, AUDIT_DECISION_AUTO_APPROVED, AUDIT_DECISION_CANCELLED,
+    AUDIT_DECISION_DENIED, AUDIT_DECISION_MODIFIED, AUDIT_DECISION_TIMED_OUT, AUDIT_OUTCOME_ERROR,
+    AUDIT_OUTCOME_NOT_EXECUTED, AUDIT_OUTCOME_SUCCESS,
 };
+pub use calendars::{register_caldav_tools, register_ics_tools};
+pub use context::{CancellationToken, ToolExecutionContext, ToolProgress, ToolProgressSink};
+pub use email::register_email_tools;
+pub use feeds::register_feed_tools;
 pub use files::register_file_tools;
-pub use integrations::register_integration_tools;
+pub use github::register_github_tools;
+pub use integrations::{
+    get_access_token, get_connection, get_google_access_token, preferred_calendar_ids,
+    register_gmail_tools, register_google_calendar_tools, register_integration_tools,
+    register_todoist_tools,
+};
 pub use prefs::register_pref_tools;
 pub use search::register_search_tool;
 pub use tool_outputs::register_tool_output_tools;
+pub use vault::{get_vault_root, normalize_relative_path, to_display_path};
 pub use web::register_web_tools;
+pub(crate) use web::{is_private_host, DEFAULT_USER_AGENT};
+pub use web_allowlist::{
+    load_web_access_rules, revoke_web_access_rule, upsert_web_access_rule, WebAccessRule,
+    WebAccessRuleInput,
+};
+pub use web_auth::normalize_web_auth_profile_input;
+pub use web_crawl::{cancel_web_crawl_job, list_web_crawl_jobs, WebCrawlJob};
+pub(crate) use web_policy::PREF_USER_AGENT;
 
 #[derive(Clone, Debug, Serialize)]
 pub struct ToolMetadata {
@@ -63,20 +106,179 @@ pub type ToolHandler =
 pub type ToolPreviewHandler =
     dyn Fn(Value, ToolExecutionContext) -> Result<Value, ToolError> + Send + Sync;
 
-#[derive(Clone, Debug, Default)]
-pub struct ToolExecutionContext;
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolErrorKind {
+    Validation,
+    NotFound,
+    Permission,
+    RateLimited,
+    Timeout,
+    Upstream,
+    Denied,
+    Cancelled,
+    Internal,
+}
+
+impl ToolErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Validation => "validation",
+            Self::NotFound => "not_found",
+            Self::Permission => "permission",
+            Self::RateLimited => "rate_limited",
+            Self::Timeout => "timeout",
+            Self::Upstream => "upstream",
+            Self::Denied => "denied",
+            Self::Cancelled => "cancelled",
+            Self::Internal => "internal",
+        }
+    }
+
+    fn default_retryable(&self) -> bool {
+        matches!(self, Self::RateLimited | Self::Timeout | Self::Upstream)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct ToolError {
+    pub kind: ToolErrorKind,
     pub message: String,
+    pub retryable: bool,
+    pub retry_after_ms: Option<u64>,
+    /// Guidance for the model on how to recover, e.g. which tool to call first.
+    pub hint: Option<String>,
 }
 
 impl ToolError {
     pub fn new(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::Internal, message)
+    }
+
+    pub fn with_kind(kind: ToolErrorKind, message: impl Into<String>) -> Self {
         Self {
+            kind,
             message: message.into(),
+            retryable: kind.default_retryable(),
+            retry_after_ms: None,
+            hint: None,
         }
     }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::Validation, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::NotFound, message)
+    }
+
+    pub fn permission(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::Permission, message)
+    }
+
+    pub fn rate_limited(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::RateLimited, message)
+    }
+
+    pub fn timeout(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::Timeout, message)
+    }
+
+    pub fn upstream(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::Upstream, message)
+    }
+
+    pub fn denied(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::Denied, message)
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        Self::with_kind(ToolErrorKind::Cancelled, message)
+    }
+
+    /// Classifies a non-success HTTP response from an upstream API.
+    pub fn from_http_response(
+        response: &reqwest::blocking::Response,
+        message: impl Into<String>,
+    ) -> Self {
+        let retry_after_ms = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(|seconds| seconds.saturating_mul(1_000));
+        let mut error = Self::from_http_status(response.status().as_u16(), message);
+        if retry_after_ms.is_some() {
+            error.retry_after_ms = retry_after_ms;
+        }
+        error
+    }
+
+    pub fn from_http_status(status: u16, message: impl Into<String>) -> Self {
+        match status {
+            400 | 422 => Self::validation(message),
+            401 => Self::permission(message).with_hint(
+                "The integration credentials were rejected; ask the user to reconnect it.",
+            ),
+            403 => Self::permission(message),
+            404 | 410 => Self::not_found(message),
+            408 => Self::timeout(message),
+            429 => Self::rate_limited(message),
+            500..=599 => Self::upstream(message),
+            _ => Self::new(message),
+        }
+    }
+
+    pub fn from_io(context: &str, err: std::io::Error) -> Self {
+        let message = format!("{context}: {err}");
+        match err.kind() {
+            std::io::ErrorKind::NotFound => Self::not_found(message),
+            std::io::ErrorKind::PermissionDenied => Self::permission(message),
+            std::io::ErrorKind::TimedOut => Self::timeout(message),
+            _ => Self::new(message),
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn with_retry_after_ms(mut self, retry_after_ms: u64) -> Self {
+        self.retry_after_ms = Some(retry_after_ms);
+        self
+    }
+
+    pub fn retryable(mut self, retryable: bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// The payload the model sees in place of a tool result.
+    pub fn to_value(&self) -> Value {
+        let mut value = json!({
+            "message": self.message,
+            "success": false,
+            "error_kind": self.kind.as_str(),
+            "retryable": self.retryable,
+        });
+        if let Some(obj) = value.as_object_mut() {
+            if let Some(retry_after_ms) = self.retry_after_ms {
+                obj.insert("retry_after_ms".to_string(), json!(retry_after_ms));
+            }
+            if let Some(hint) = &self.hint {
+                obj.insert("hint".to_string(), json!(hint));
+            }
+        }
+        value
+    }
+}
+
+impl std::fmt::Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -104,6 +306,26 @@ impl ToolRegistry {
         self.tools.get(name)
     }
 
+    pub fn remove(&mut self, name: &str) -> Option<ToolDefinition> {
+        self.tools.remove(name)
+    }
+
+    /// A copy of the registry containing only tools whose names match one of `patterns`
+    /// (globs such as `gmail.*` are allowed).
+    pub fn restricted(&self, patterns: &[String]) -> ToolRegistry {
+        let tools = self
+            .tools
+            .iter()
+            .filter(|(name, _)| {
+                patterns
+                    .iter()
+                    .any(|pattern| approval_rules::glob_matches(pattern.trim(), name))
+            })
+            .map(|(name, definition)| (name.clone(), definition.clone()))
+            .collect();
+        ToolRegistry { tools }
+    }
+
     pub fn list_metadata(&self) -> Vec<ToolMetadata> {
         let mut metadata = self
             .tools
@@ -119,7 +341,7 @@ impl ToolRegistry {
             .map_err(|err| ToolError::new(format!("Invalid args schema: {err}")))?;
         if let Err(errors) = schema.validate(args) {
             let messages = errors.map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
-            return Err(ToolError::new(format!(
+            return Err(ToolError::validation(format!(
                 "Invalid args for tool {}: {messages}",
                 metadata.name
             )));
@@ -135,8 +357,27 @@ pub struct ApprovalStore {
 
 #[derive(Clone, Debug)]
 pub enum ToolApprovalDecision {
-    Approved,
-    Denied,
+    Approved {
+        feedback: Option<String>,
+    },
+    Modified {
+        args: Value,
+        feedback: Option<String>,
+    },
+    Denied {
+        feedback: Option<String>,
+    },
+}
+
+impl ToolApprovalDecision {
+    pub fn feedback(&self) -> Option<&str> {
+        let feedback = match self {
+            ToolApprovalDecision::Approved { feedback }
+            | ToolApprovalDecision::Modified { feedback, .. }
+            | ToolApprovalDecision::Denied { feedback } => feedback.as_deref(),
+        };
+        feedback.map(str::trim).filter(|value| !value.is_empty())
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -204,18 +445,13 @@ impl ApprovalStore {
         (approval_id, rx)
     }
 
-    pub fn resolve(&self, approval_id: &str, approved: bool) -> Result<(), String> {
+    pub fn resolve(&self, approval_id: &str, decision: ToolApprovalDecision) -> Result<(), String> {
         let sender = {
             let mut pending = self.pending.lock().unwrap();
             pending.remove(approval_id).map(|entry| entry.sender)
         };
 
         let sender = sender.ok_or_else(|| format!("Unknown approval id: {approval_id}"))?;
-        let decision = if approved {
-            ToolApprovalDecision::Approved
-        } else {
-            ToolApprovalDecision::Denied
-        };
         sender
             .send(decision)
             .map_err(|_| "Failed to deliver approval decision".to_string())
@@ -250,8 +486,9 @@ impl ApprovalStore {
 #[cfg(test)]
 mod tests {
     use super::{
-        register_file_tools, register_search_tool, ToolDefinition, ToolError, ToolExecutionContext,
-        ToolMetadata, ToolRegistry, ToolResultMode,
+        register_file_tools, register_search_tool, ApprovalStore, CancellationToken,
+        PendingToolApprovalInput, ToolApprovalDecision, ToolDefinition, ToolError, ToolErrorKind,
+        ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
     };
     use crate::db::{Db, PreferenceOperations};
     use serde_json::json;
@@ -274,7 +511,7 @@ mod tests {
         args: serde_json::Value,
     ) -> serde_json::Value {
         let tool = registry.get(name).expect("missing tool");
-        let ctx = ToolExecutionContext;
+        let ctx = ToolExecutionContext::default();
         (tool.handler)(args, ctx).expect("tool execution failed")
     }
 
@@ -286,6 +523,28 @@ mod tests {
             .unwrap_or(false)
     }
 
+    #[test]
+    fn tool_errors_classify_http_statuses() {
+        let rate_limited = ToolError::from_http_status(429, "slow down").with_retry_after_ms(2_000);
+        assert_eq!(rate_limited.kind, ToolErrorKind::RateLimited);
+        assert!(rate_limited.retryable);
+        let value = rate_limited.to_value();
+        assert_eq!(value["error_kind"], "rate_limited");
+        assert_eq!(value["retry_after_ms"], 2_000);
+
+        let upstream = ToolError::from_http_status(503, "unavailable");
+        assert_eq!(upstream.kind, ToolErrorKind::Upstream);
+        assert!(upstream.retryable);
+
+        let not_found = ToolError::from_http_status(404, "missing");
+        assert_eq!(not_found.kind, ToolErrorKind::NotFound);
+        assert!(!not_found.retryable);
+
+        let auth = ToolError::from_http_status(401, "unauthorized");
+        assert_eq!(auth.kind, ToolErrorKind::Permission);
+        assert!(auth.hint.is_some());
+    }
+
     #[test]
     fn list_metadata_is_sorted_by_tool_name() {
         let mut registry = ToolRegistry::new();
@@ -328,6 +587,75 @@ mod tests {
         assert_eq!(names, vec!["a.tool".to_string(), "z.tool".to_string()]);
     }
 
+    #[test]
+    fn restricted_registry_keeps_matching_tools() {
+        let mut registry = ToolRegistry::new();
+        for name in ["gmail.list_threads", "gmail.send_message", "files.read"] {
+            registry
+                .register(ToolDefinition {
+                    metadata: ToolMetadata {
+                        name: name.to_string(),
+                        description: name.to_string(),
+                        args_schema: json!({ "type": "object" }),
+                        result_schema: json!({ "type": "object" }),
+                        requires_approval: false,
+                        result_mode: ToolResultMode::Auto,
+                    },
+                    handler: std::sync::Arc::new(|_, _| Ok(json!({}))),
+                    preview: None,
+                })
+                .expect("register tool");
+        }
+
+        let restricted =
+            registry.restricted(&["gmail.list_*".to_string(), "files.read".to_string()]);
+        let names = restricted
+            .list_metadata()
+            .into_iter()
+            .map(|metadata| metadata.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["files.read".to_string(), "gmail.list_threads".to_string()]
+        );
+        assert!(registry.restricted(&[]).list_metadata().is_empty());
+    }
+
+    #[test]
+    fn approval_store_delivers_modified_args_and_feedback() {
+        let store = ApprovalStore::new();
+        let (approval_id, rx) = store.create_request(PendingToolApprovalInput {
+            execution_id: "exec-1".to_string(),
+            tool_name: "gmail.send_message".to_string(),
+            args: json!({ "to": "wrong@example.com" }),
+            preview: None,
+            iteration: 1,
+            conversation_id: Some("conv-1".to_string()),
+            message_id: Some("msg-1".to_string()),
+            timestamp_ms: 0,
+        });
+
+        store
+            .resolve(
+                &approval_id,
+                ToolApprovalDecision::Modified {
+                    args: json!({ "to": "right@example.com" }),
+                    feedback: Some("  use the work address  ".to_string()),
+                },
+            )
+            .expect("resolve approval");
+
+        let decision = rx.recv().expect("decision delivered");
+        assert_eq!(decision.feedback(), Some("use the work address"));
+        match decision {
+            ToolApprovalDecision::Modified { args, .. } => {
+                assert_eq!(args, json!({ "to": "right@example.com" }));
+            }
+            other => panic!("unexpected decision: {other:?}"),
+        }
+        assert!(store.get_pending(&approval_id).is_none());
+    }
+
     #[test]
     fn vault_file_tools_and_search_smoke() {
         let vault_root = std::env::temp_dir().join(format!("vault-root-{}", Uuid::new_v4()));
@@ -411,6 +739,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn search_rg_stops_when_cancelled() {
+        if !rg_available() {
+            return;
+        }
+        let vault_root = std::env::temp_dir().join(format!("vault-root-{}", Uuid::new_v4()));
+        fs::create_dir_all(&vault_root).expect("vault root create failed");
+        fs::write(vault_root.join("note.md"), "Universe").expect("note write failed");
+
+        let db = setup_db(vault_root.to_str().unwrap());
+        let mut registry = ToolRegistry::new();
+        register_search_tool(&mut registry, db).expect("search tool registration failed");
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let tool = registry.get("search.rg").expect("missing tool");
+        let err = (tool.handler)(
+            json!({ "query": "Universe" }),
+            ToolExecutionContext::new(cancellation),
+        )
+        .expect_err("cancelled search should fail");
+        assert!(err.message.contains("cancelled"));
+    }
+
     #[test]
     fn read_range_defaults_and_limits() {
         let vault_root = std::env::temp_dir().join(format!("vault-root-{}", Uuid::new_v4()));
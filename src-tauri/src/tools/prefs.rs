@@ -119,5 +119,5 @@ fn require_string_arg(args: &Value, key: &str) -> Result<String, ToolError> {
     args.get(key)
         .and_then(|value| value.as_str())
         .map(|value| value.to_string())
-        .ok_or_else(|| ToolError::new(format!("Missing or invalid '{key}'")))
+        .ok_or_else(|| ToolError::validation(format!("Missing or invalid '{key}'")))
 }
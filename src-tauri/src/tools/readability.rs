@@ -0,0 +1,712 @@
+//! Readability-style main-content extraction for `web.fetch`. Paragraph-like nodes score
+//! their ancestors by text length and comma count, discounted by link density and by
+//! class/id names that look like chrome (navigation, footers, cookie banners). The best
+//! scoring node is rendered as Markdown with reference-style links.
+
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use url::Url;
+
+/// Nodes whose text feeds the scores of their ancestors.
+const SCORED_TAGS: [&str; 4] = ["p", "pre", "td", "blockquote"];
+const MIN_PARAGRAPH_CHARS: usize = 25;
+/// Below this much text the chosen node is probably wrong and the whole body is used.
+const MIN_ARTICLE_CHARS: usize = 200;
+const POSITIVE_NAMES: [&str; 10] = [
+    "article", "body", "content", "entry", "main", "page", "post", "text", "blog", "story",
+];
+const NEGATIVE_NAMES: [&str; 22] = [
+    "banner",
+    "breadcrumb",
+    "comment",
+    "consent",
+    "cookie",
+    "footer",
+    "footnote",
+    "masthead",
+    "menu",
+    "modal",
+    "nav",
+    "newsletter",
+    "popup",
+    "promo",
+    "related",
+    "share",
+    "sidebar",
+    "social",
+    "sponsor",
+    "subscribe",
+    "widget",
+    "advert",
+];
+/// Never part of the readable content.
+const SKIPPED_TAGS: [&str; 16] = [
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "form", "button",
+    "input", "select", "textarea", "nav", "footer", "aside", "dialog",
+];
+const BLOCK_TAGS: [&str; 29] = [
+    "address",
+    "article",
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+    "tr",
+    "body",
+];
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct PageMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReadablePage {
+    pub metadata: PageMetadata,
+    /// Main content as Markdown, ending with the link reference definitions.
+    pub markdown: String,
+}
+
+pub fn extract_readable(document: &Html, base_url: &Url) -> ReadablePage {
+    let metadata = extract_metadata(document, base_url);
+    let markdown = main_content(document)
+        .map(|content| {
+            let mut renderer = Renderer {
+                base_url,
+                links: Vec::new(),
+                link_index: HashMap::new(),
+            };
+            let body = renderer.blocks(content);
+            renderer.finish(body)
+        })
+        .unwrap_or_default();
+    ReadablePage { metadata, markdown }
+}
+
+/// The highest scoring content node, or `<body>` when nothing scores well enough.
+fn main_content(document: &Html) -> Option<ElementRef<'_>> {
+    let body = select_first(document, "body")?;
+    let mut scores: HashMap<ego_tree::NodeId, f64> = HashMap::new();
+    let mut candidates = Vec::new();
+
+    for element in body.descendants().filter_map(ElementRef::wrap) {
+        if !SCORED_TAGS.contains(&element.value().name()) || is_excluded(element) {
+            continue;
+        }
+        let text = collapse_whitespace(&element.text().collect::<String>());
+        let length = text.chars().count();
+        if length < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+        let score = 1.0 + text.matches(',').count() as f64 + (length as f64 / 100.0).min(3.0);
+        let ancestors = element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .take(2)
+            .collect::<Vec<_>>();
+        for (level, ancestor) in ancestors.into_iter().enumerate() {
+            let entry = scores.entry(ancestor.id()).or_insert_with(|| {
+                candidates.push(ancestor);
+                initial_score(ancestor)
+            });
+            *entry += if level == 0 { score } else { score / 2.0 };
+        }
+    }
+
+    let best = candidates
+        .into_iter()
+        .map(|candidate| {
+            let score = scores.get(&candidate.id()).copied().unwrap_or(0.0);
+            (candidate, score * (1.0 - link_density(candidate)))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(candidate, _)| candidate);
+
+    match best {
+        Some(best) if text_length(best) >= MIN_ARTICLE_CHARS => Some(best),
+        _ => select_first(document, "article")
+            .or_else(|| select_first(document, "main"))
+            .filter(|element| text_length(*element) >= MIN_ARTICLE_CHARS)
+            .or(Some(body)),
+    }
+}
+
+fn initial_score(element: ElementRef<'_>) -> f64 {
+    let tag_score = match element.value().name() {
+        "article" | "main" => 10.0,
+        "div" | "section" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    tag_score + class_weight(element)
+}
+
+fn class_weight(element: ElementRef<'_>) -> f64 {
+    let names = format!(
+        "{} {}",
+        element.value().attr("class").unwrap_or(""),
+        element.value().attr("id").unwrap_or("")
+    )
+    .to_ascii_lowercase();
+    let mut weight = 0.0;
+    if NEGATIVE_NAMES.iter().any(|name| names.contains(name)) {
+        weight -= 25.0;
+    }
+    if POSITIVE_NAMES.iter().any(|name| names.contains(name)) {
+        weight += 25.0;
+    }
+    weight
+}
+
+/// Skipped by both scoring and rendering: chrome tags, hidden nodes, and nodes whose
+/// class/id only matches chrome names.
+fn is_skipped(element: ElementRef<'_>) -> bool {
+    let value = element.value();
+    SKIPPED_TAGS.contains(&value.name())
+        || value.attr("hidden").is_some()
+        || value.attr("aria-hidden") == Some("true")
+        || value
+            .attr("style")
+            .map(|style| style.replace(' ', "").contains("display:none"))
+            .unwrap_or(false)
+        || (!matches!(value.name(), "body" | "article" | "main") && class_weight(element) < 0.0)
+}
+
+fn is_excluded(element: ElementRef<'_>) -> bool {
+    is_skipped(element)
+        || element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(is_skipped)
+}
+
+fn link_density(element: ElementRef<'_>) -> f64 {
+    let total = text_length(element);
+    if total == 0 {
+        return 0.0;
+    }
+    let linked: usize = element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|el| el.value().name() == "a")
+        .map(text_length)
+        .sum();
+    (linked as f64 / total as f64).min(1.0)
+}
+
+fn text_length(element: ElementRef<'_>) -> usize {
+    collapse_whitespace(&element.text().collect::<String>())
+        .chars()
+        .count()
+}
+
+struct Renderer<'a> {
+    base_url: &'a Url,
+    links: Vec<String>,
+    link_index: HashMap<String, usize>,
+}
+
+impl Renderer<'_> {
+    /// Children of `element` as Markdown blocks separated by blank lines.
+    fn blocks(&mut self, element: ElementRef<'_>) -> String {
+        let mut blocks: Vec<String> = Vec::new();
+        let mut inline = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_str(&text.replace(char::is_whitespace, " ")),
+                Node::Element(_) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    if is_skipped(child) {
+                        continue;
+                    }
+                    if BLOCK_TAGS.contains(&child.value().name()) {
+                        push_paragraph(&mut blocks, &mut inline);
+                        let block = self.block(child);
+                        if !block.trim().is_empty() {
+                            blocks.push(block);
+                        }
+                    } else {
+                        inline.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        push_paragraph(&mut blocks, &mut inline);
+        blocks.join("\n\n")
+    }
+
+    fn block(&mut self, element: ElementRef<'_>) -> String {
+        let name = element.value().name();
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = tidy_inline(&self.inline_children(element));
+                if text.is_empty() {
+                    String::new()
+                } else {
+                    format!("{} {text}", "#".repeat(level))
+                }
+            }
+            "p" | "dt" | "summary" | "figcaption" => tidy_inline(&self.inline_children(element)),
+            "pre" => code_block(element),
+            "ul" | "ol" => self.list(element, name == "ol"),
+            "blockquote" => prefix_lines(&self.blocks(element), "> ", ">"),
+            "table" => self.table(element),
+            "hr" => "---".to_string(),
+            _ => self.blocks(element),
+        }
+    }
+
+    fn inline(&mut self, element: ElementRef<'_>) -> String {
+        let value = element.value();
+        match value.name() {
+            "a" => {
+                let text = tidy_inline(&self.inline_children(element));
+                let href = value.attr("href").map(str::trim).unwrap_or("");
+                match self.resolve(href) {
+                    Some(url) if !text.is_empty() && !href.starts_with('#') => {
+                        let index = self.link_reference(url);
+                        format!("[{text}][{index}]")
+                    }
+                    _ => text,
+                }
+            }
+            "strong" | "b" => wrap_inline(&self.inline_children(element), "**"),
+            "em" | "i" => wrap_inline(&self.inline_children(element), "*"),
+            "del" | "s" | "strike" => wrap_inline(&self.inline_children(element), "~~"),
+            "code" | "kbd" | "samp" => {
+                let text = element.text().collect::<String>();
+                let fence = if text.contains('`') { "``" } else { "`" };
+                format!("{fence}{}{fence}", text.trim())
+            }
+            "br" => "\n".to_string(),
+            "img" => {
+                let alt = value.attr("alt").unwrap_or("").trim();
+                match value.attr("src").and_then(|src| self.resolve(src)) {
+                    Some(src) => format!("![{alt}]({src})"),
+                    None => alt.to_string(),
+                }
+            }
+            name if BLOCK_TAGS.contains(&name) => {
+                format!(" {} ", self.inline_children(element))
+            }
+            _ => self.inline_children(element),
+        }
+    }
+
+    fn inline_children(&mut self, element: ElementRef<'_>) -> String {
+        let mut out = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(&text.replace(char::is_whitespace, " ")),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child).filter(|c| !is_skipped(*c)) {
+                        out.push_str(&self.inline(child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+
+    fn list(&mut self, element: ElementRef<'_>, ordered: bool) -> String {
+        let mut number = element
+            .value()
+            .attr("start")
+            .and_then(|start| start.parse::<usize>().ok())
+            .unwrap_or(1);
+        let mut items = Vec::new();
+        for item in element.children().filter_map(ElementRef::wrap) {
+            if item.value().name() != "li" || is_skipped(item) {
+                continue;
+            }
+            let marker = if ordered {
+                format!("{number}.")
+            } else {
+                "-".to_string()
+            };
+            number += 1;
+            // Items stay tight even when they wrap their text in paragraphs.
+            let content = self.blocks(item).replace("\n\n", "\n");
+            let indent = " ".repeat(marker.len() + 1);
+            let mut lines = content.lines();
+            let first = lines.next().unwrap_or("");
+            let mut rendered = format!("{marker} {first}");
+            for line in lines {
+                rendered.push('\n');
+                if !line.is_empty() {
+                    rendered.push_str(&indent);
+                    rendered.push_str(line);
+                }
+            }
+            items.push(rendered);
+        }
+        items.join("\n")
+    }
+
+    fn table(&mut self, element: ElementRef<'_>) -> String {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for row in element.descendants().filter_map(ElementRef::wrap) {
+            if row.value().name() != "tr" {
+                continue;
+            }
+            let cells = row
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .map(|cell| {
+                    tidy_inline(&self.inline_children(cell))
+                        .replace('\n', " ")
+                        .replace('|', "\\|")
+                })
+                .collect::<Vec<_>>();
+            if !cells.is_empty() {
+                rows.push(cells);
+            }
+        }
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+        let line = |cells: &[String]| {
+            let mut padded = cells.to_vec();
+            padded.resize(columns, String::new());
+            format!("| {} |", padded.join(" | "))
+        };
+        let mut out = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+        out.extend(rows[1..].iter().map(|row| line(row)));
+        out.join("\n")
+    }
+
+    fn resolve(&self, href: &str) -> Option<String> {
+        if href.is_empty() {
+            return None;
+        }
+        let url = self.base_url.join(href).ok()?;
+        matches!(url.scheme(), "http" | "https" | "mailto").then(|| url.to_string())
+    }
+
+    fn link_reference(&mut self, url: String) -> usize {
+        if let Some(index) = self.link_index.get(&url) {
+            return *index;
+        }
+        self.links.push(url.clone());
+        let index = self.links.len();
+        self.link_index.insert(url, index);
+        index
+    }
+
+    fn finish(self, body: String) -> String {
+        let mut out = collapse_blank_lines(&body);
+        if !self.links.is_empty() {
+            out.push_str("\n\n");
+            for (index, url) in self.links.iter().enumerate() {
+                out.push_str(&format!("[{}]: {url}\n", index + 1));
+            }
+        }
+        out.trim_end().to_string()
+    }
+}
+
+fn push_paragraph(blocks: &mut Vec<String>, inline: &mut String) {
+    let paragraph = tidy_inline(inline);
+    if !paragraph.is_empty() {
+        blocks.push(paragraph);
+    }
+    inline.clear();
+}
+
+/// Collapses runs of spaces while keeping the explicit line breaks from `<br>`.
+fn tidy_inline(text: &str) -> String {
+    text.split('\n')
+        .map(collapse_whitespace)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn wrap_inline(text: &str, marker: &str) -> String {
+    let text = tidy_inline(text);
+    if text.is_empty() {
+        String::new()
+    } else {
+        format!("{marker}{text}{marker}")
+    }
+}
+
+fn code_block(element: ElementRef<'_>) -> String {
+    let language = element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter_map(|el| el.value().attr("class"))
+        .flat_map(str::split_whitespace)
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+        })
+        .unwrap_or("");
+    let code = element.text().collect::<String>();
+    let code = code.trim_matches('\n').trim_end();
+    let fence = if code.contains("```") { "~~~" } else { "```" };
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+fn prefix_lines(text: &str, prefix: &str, empty_prefix: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                empty_prefix.to_string()
+            } else {
+                format!("{prefix}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn collapse_blank_lines(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank = 0;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            blank += 1;
+            if blank > 1 {
+                continue;
+            }
+        } else {
+            blank = 0;
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+fn select_first<'a>(document: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(selector).ok()?;
+    document.select(&selector).next()
+}
+
+fn meta_content(document: &Html, selectors: &[&str]) -> Option<String> {
+    selectors.iter().find_map(|selector| {
+        select_first(document, selector)
+            .and_then(|el| el.value().attr("content"))
+            .map(collapse_whitespace)
+            .filter(|value| !value.is_empty())
+    })
+}
+
+fn element_text(document: &Html, selector: &str) -> Option<String> {
+    select_first(document, selector)
+        .map(|el| collapse_whitespace(&el.text().collect::<String>()))
+        .filter(|value| !value.is_empty())
+}
+
+fn extract_metadata(document: &Html, base_url: &Url) -> PageMetadata {
+    let json_ld = json_ld_article(document);
+    let json_ld_str = |key: &str| {
+        json_ld
+            .as_ref()
+            .and_then(|value| value.get(key))
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    };
+    let json_ld_author = json_ld
+        .as_ref()
+        .and_then(|value| value.get("author"))
+        .and_then(|author| match author {
+            Value::Array(authors) => authors.first().cloned(),
+            other => Some(other.clone()),
+        })
+        .and_then(|author| match author {
+            Value::String(name) => Some(name),
+            other => other
+                .get("name")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        });
+
+    PageMetadata {
+        title: meta_content(document, &["meta[property='og:title']"])
+            .or_else(|| json_ld_str("headline"))
+            .or_else(|| element_text(document, "title"))
+            .or_else(|| element_text(document, "h1")),
+        author: meta_content(
+            document,
+            &["meta[name='author']", "meta[name='parsely-author']"],
+        )
+        .or(json_ld_author)
+        .or_else(|| {
+            meta_content(document, &["meta[property='article:author']"])
+                .filter(|author| !author.starts_with("http"))
+        })
+        .or_else(|| element_text(document, "[rel='author']"))
+        .or_else(|| element_text(document, "[itemprop='author']")),
+        published: meta_content(
+            document,
+            &[
+                "meta[property='article:published_time']",
+                "meta[itemprop='datePublished']",
+                "meta[name='date']",
+                "meta[name='publish-date']",
+                "meta[name='parsely-pub-date']",
+            ],
+        )
+        .or_else(|| json_ld_str("datePublished"))
+        .or_else(|| {
+            select_first(document, "time[datetime]")
+                .and_then(|el| el.value().attr("datetime"))
+                .map(str::to_string)
+        }),
+        canonical_url: select_first(document, "link[rel='canonical']")
+            .and_then(|el| el.value().attr("href"))
+            .map(str::to_string)
+            .or_else(|| meta_content(document, &["meta[property='og:url']"]))
+            .and_then(|href| base_url.join(href.trim()).ok())
+            .map(|url| url.to_string()),
+        site_name: meta_content(document, &["meta[property='og:site_name']"]),
+        description: meta_content(
+            document,
+            &[
+                "meta[name='description']",
+                "meta[property='og:description']",
+            ],
+        ),
+        language: select_first(document, "html")
+            .and_then(|el| el.value().attr("lang"))
+            .map(str::to_string)
+            .filter(|lang| !lang.is_empty()),
+    }
+}
+
+/// First JSON-LD object that describes an article (it has a publication date or author).
+fn json_ld_article(document: &Html) -> Option<Value> {
+    let selector = Selector::parse("script[type='application/ld+json']").ok()?;
+    document
+        .select(&selector)
+        .filter_map(|script| serde_json::from_str::<Value>(&script.text().collect::<String>()).ok())
+        .flat_map(|value| match value {
+            Value::Array(items) => items,
+            Value::Object(ref object) if object.contains_key("@graph") => {
+                object["@graph"].as_array().cloned().unwrap_or_default()
+            }
+            other => vec![other],
+        })
+        .find(|item| item.get("datePublished").is_some() || item.get("author").is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <title>Fallback title</title>
+  <meta property="og:title" content="Parsing HTML in Rust">
+  <meta name="author" content="Ada Lovelace">
+  <meta property="article:published_time" content="2024-03-01T10:00:00Z">
+  <link rel="canonical" href="/posts/parsing">
+</head>
+<body>
+  <nav class="site-nav"><a href="/">Home</a> <a href="/about">About</a></nav>
+  <div id="cookie-banner"><p>We use cookies to improve your experience, please accept them.</p></div>
+  <div class="post-content">
+    <h2>Why parse</h2>
+    <p>Parsing HTML is harder than it looks, because real pages are messy, nested, and often invalid. See <a href="https://html.spec.whatwg.org/">the spec</a> for details.</p>
+    <p>Browsers recover from errors in well-defined ways, and a good parser follows the same rules, so scraped text matches what users see.</p>
+    <ul><li>Tokenize</li><li>Build the <em>tree</em><ul><li>Adoption agency</li></ul></li></ul>
+    <pre><code class="language-rust">let doc = Html::parse_document(html);</code></pre>
+    <table><tr><th>Crate</th><th>Speed</th></tr><tr><td>scraper</td><td>fast | safe</td></tr></table>
+  </div>
+  <footer><p>Copyright 2024, Example Corp, all rights reserved, forever and ever.</p></footer>
+</body>
+</html>"#;
+
+    #[test]
+    fn extracts_the_article_as_markdown() {
+        let base = Url::parse("https://example.com/posts/parsing?ref=feed").unwrap();
+        let page = extract_readable(&Html::parse_document(ARTICLE), &base);
+        let markdown = page.markdown;
+
+        assert!(markdown.starts_with("## Why parse\n\nParsing HTML is harder"));
+        assert!(markdown.contains("See [the spec][1] for details."));
+        assert!(markdown.contains("- Tokenize\n- Build the *tree*\n  - Adoption agency"));
+        assert!(markdown.contains("```rust\nlet doc = Html::parse_document(html);\n```"));
+        assert!(markdown.contains("| Crate | Speed |\n| --- | --- |\n| scraper | fast \\| safe |"));
+        assert!(markdown.ends_with("[1]: https://html.spec.whatwg.org/"));
+        assert!(!markdown.contains("cookies"));
+        assert!(!markdown.contains("Home"));
+        assert!(!markdown.contains("Copyright"));
+    }
+
+    #[test]
+    fn extracts_page_metadata() {
+        let base = Url::parse("https://example.com/posts/parsing?ref=feed").unwrap();
+        let metadata = extract_metadata(&Html::parse_document(ARTICLE), &base);
+        assert_eq!(metadata.title.as_deref(), Some("Parsing HTML in Rust"));
+        assert_eq!(metadata.author.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(metadata.published.as_deref(), Some("2024-03-01T10:00:00Z"));
+        assert_eq!(
+            metadata.canonical_url.as_deref(),
+            Some("https://example.com/posts/parsing")
+        );
+        assert_eq!(metadata.language.as_deref(), Some("en"));
+
+        let json_ld = Html::parse_document(
+            r#"<html><head><script type="application/ld+json">
+            {"@graph": [{"@type": "WebSite"}, {"@type": "NewsArticle", "datePublished": "2024-01-02", "author": [{"name": "Grace Hopper"}]}]}
+            </script></head><body></body></html>"#,
+        );
+        let metadata = extract_metadata(&json_ld, &base);
+        assert_eq!(metadata.author.as_deref(), Some("Grace Hopper"));
+        assert_eq!(metadata.published.as_deref(), Some("2024-01-02"));
+    }
+}
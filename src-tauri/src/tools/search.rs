@@ -8,7 +8,11 @@ use crate::tools::{
 use serde_json::{json, Value};
 use std::io::{BufRead, BufReader};
 use std::process::{Command, Stdio};
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const PROGRESS_EVERY_MATCHES: usize = 50;
 
 const VAULT_PATH_NOTE: &str =
     "Paths are relative to the vault root (use \".\" for root; no absolute paths).";
@@ -54,7 +58,7 @@ pub fn register_search_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), S
         result_mode: ToolResultMode::Auto,
     };
 
-    let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+    let handler = Arc::new(move |args: Value, ctx: ToolExecutionContext| {
         let query = require_string_arg(&args, "query")?;
         let literal = args
             .get("literal")
@@ -116,13 +120,31 @@ pub fn register_search_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), S
             .stdout
             .take()
             .ok_or_else(|| ToolError::new("Failed to read rg output"))?;
-        let reader = BufReader::new(stdout);
+        // Read on a separate thread so a cancelled run can kill rg even while it is silent.
+        let (line_tx, line_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if line_tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
 
         let mut results: Vec<Value> = Vec::new();
         let mut reached_limit = false;
-        for line in reader.lines() {
-            let line =
-                line.map_err(|err| ToolError::new(format!("Failed to read rg output: {err}")))?;
+        loop {
+            if ctx.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(ToolError::cancelled("Search cancelled"));
+            }
+            let line = match line_rx.recv_timeout(CANCEL_POLL_INTERVAL) {
+                Ok(line) => {
+                    line.map_err(|err| ToolError::new(format!("Failed to read rg output: {err}")))?
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
             let value: Value = match serde_json::from_str(&line) {
                 Ok(value) => value,
                 Err(_) => continue,
@@ -169,6 +191,13 @@ pub fn register_search_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), S
                 }
 
                 results.push(entry);
+                if results.len() % PROGRESS_EVERY_MATCHES == 0 {
+                    ctx.report_progress(
+                        format!("Found {} matches", results.len()),
+                        Some(results.len() as u64),
+                        Some(max_results as u64),
+                    );
+                }
                 if results.len() >= max_results {
                     reached_limit = true;
                     break;
@@ -203,5 +232,5 @@ fn require_string_arg(args: &Value, key: &str) -> Result<String, ToolError> {
     args.get(key)
         .and_then(|value| value.as_str())
         .map(|value| value.to_string())
-        .ok_or_else(|| ToolError::new(format!("Missing or invalid '{key}'")))
+        .ok_or_else(|| ToolError::validation(format!("Missing or invalid '{key}'")))
 }
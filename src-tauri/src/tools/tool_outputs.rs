@@ -29,7 +29,7 @@ pub fn register_tool_output_tools(registry: &mut ToolRegistry, _db: Db) -> Resul
     let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
         let id = args.get("id").and_then(|v| v.as_str()).unwrap_or("").trim();
         if id.is_empty() {
-            return Err(ToolError::new("Missing 'id'"));
+            return Err(ToolError::validation("Missing 'id'"));
         }
 
         let record = read_tool_output(id).map_err(ToolError::new)?;
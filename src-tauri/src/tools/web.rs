@@ -1,52 +1,63 @@
-use crate::db::{Db, PreferenceOperations};
+use crate::db::Db;
+use crate::tools::readability::{extract_readable, PageMetadata};
 use crate::tools::vault::resolve_vault_path;
+use crate::tools::web_allowlist::{
+    ensure_url_allowed, is_denied, load_web_access_rules, upsert_web_access_rule, WebAccessRule,
+    WebAccessRuleInput,
+};
+use crate::tools::web_auth::{
+    auth_header_names, auth_headers, load_auth_profile, profile_secrets, redact_secrets,
+    register_auth_profiles_tool,
+};
+use crate::tools::web_crawl::register_web_crawl_tools;
+use crate::tools::web_policy::{PolicyRequest, WebPolicy};
+use crate::tools::web_search::register_web_search_tool;
 use crate::tools::{
     ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
 };
 use reqwest::blocking::Client;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
 use reqwest::redirect::Policy;
-use reqwest::Method;
+use reqwest::{Method, StatusCode};
 use scraper::{Html, Selector};
-use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::net::IpAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
-const PREF_ALLOWED_HOSTS: &str = "plugins.web.allowed_hosts";
-const DEFAULT_MAX_BYTES: usize = 200_000;
+pub(super) const DEFAULT_MAX_BYTES: usize = 200_000;
 const DEFAULT_MAX_DOWNLOAD_BYTES: usize = 10_485_760; // 10 MB
-const DEFAULT_TIMEOUT_MS: u64 = 15_000;
-const DEFAULT_USER_AGENT: &str = "ai-agent/1.0";
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct AllowedHost {
-    host: String,
-    allow_private: bool,
-    approved_at: i64,
-}
+pub(super) const DEFAULT_TIMEOUT_MS: u64 = 15_000;
+const WEB_CLIPS_DIR: &str = "Web Clips";
+pub(crate) const DEFAULT_USER_AGENT: &str = "ai-agent/1.0";
 
 pub fn register_web_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
     register_approve_tool(registry, db.clone())?;
     register_fetch_tool(registry, db.clone())?;
     register_request_tool(registry, db.clone())?;
-    register_download_tool(registry, db)?;
+    register_auth_profiles_tool(registry, db.clone())?;
+    register_download_tool(registry, db.clone())?;
+    register_web_crawl_tools(registry, db.clone())?;
+    register_web_search_tool(registry, db)?;
     Ok(())
 }
 
 fn register_approve_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
     let metadata = ToolMetadata {
         name: "web.approve_domain".to_string(),
-        description: "Approve a website host for automated access (exact host).".to_string(),
+        description: "Approve a website host for automated access. Defaults to the exact host, every path and every method; narrow it with path_prefix, methods and expires_in_minutes.".to_string(),
         args_schema: json!({
             "type": "object",
             "properties": {
                 "url": { "type": "string" },
-                "allow_private": { "type": "boolean" }
+                "allow_private": { "type": "boolean" },
+                "include_subdomains": { "type": "boolean" },
+                "path_prefix": { "type": "string" },
+                "methods": { "type": "array", "items": { "type": "string" } },
+                "expires_in_minutes": { "type": "integer", "minimum": 1 }
             },
             "required": ["url"],
             "additionalProperties": false
@@ -54,11 +65,15 @@ fn register_approve_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
         result_schema: json!({
             "type": "object",
             "properties": {
+                "id": { "type": "string" },
                 "host": { "type": "string" },
+                "path_prefix": { "type": "string" },
+                "methods": { "type": "array", "items": { "type": "string" } },
                 "allow_private": { "type": "boolean" },
+                "expires_at": { "type": "integer" },
                 "saved": { "type": "boolean" }
             },
-            "required": ["host", "allow_private", "saved"],
+            "required": ["id", "host", "allow_private", "saved"],
             "additionalProperties": false
         }),
         requires_approval: false,
@@ -67,60 +82,57 @@ fn register_approve_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
 
     let handler_db = db.clone();
     let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
-        let url = require_string_arg(&args, "url")?;
-        let allow_private = args
-            .get("allow_private")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let host = normalize_host_from_input(&url)?;
-
-        if is_private_host(&host) && !allow_private {
-            return Err(ToolError::new(
-                "Private/local hosts require allow_private=true",
-            ));
+        let (input, path) = approval_input(&args)?;
+        let rules = load_web_access_rules(&handler_db)?;
+        let host = input.host.trim_start_matches("*.").to_string();
+        if is_denied(&rules, &host, &path) {
+            return Err(ToolError::permission(format!(
+                "Access to {host}{path} is denied by a web rule"
+            ))
+            .with_hint("The user blocked this URL; do not retry or approve it."));
         }
+        let rule = upsert_web_access_rule(&handler_db, input)?;
 
-        let mut allowed = load_allowlist(&handler_db)?;
-        let now = chrono::Utc::now().timestamp();
-        if let Some(entry) = allowed.iter_mut().find(|entry| entry.host == host) {
-            entry.allow_private = allow_private;
-            entry.approved_at = now;
-        } else {
-            allowed.push(AllowedHost {
-                host: host.clone(),
-                allow_private,
-                approved_at: now,
-            });
-        }
-        save_allowlist(&handler_db, &allowed)?;
-
-        Ok(json!({
-            "host": host,
-            "allow_private": allow_private,
+        let mut result = json!({
+            "id": rule.id,
+            "host": rule.host,
+            "allow_private": rule.allow_private,
             "saved": true
-        }))
+        });
+        if let Some(obj) = result.as_object_mut() {
+            if let Some(path_prefix) = rule.path_prefix {
+                obj.insert("path_prefix".to_string(), json!(path_prefix));
+            }
+            if !rule.methods.is_empty() {
+                obj.insert("methods".to_string(), json!(rule.methods));
+            }
+            if let Some(expires_at) = rule.expires_at {
+                obj.insert("expires_at".to_string(), json!(expires_at));
+            }
+        }
+        Ok(result)
     });
 
     let preview_db = db;
     let preview = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
-        let url = require_string_arg(&args, "url")?;
-        let allow_private = args
-            .get("allow_private")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let host = normalize_host_from_input(&url)?;
-        let host_for_preview = host.clone();
+        let (input, _) = approval_input(&args)?;
+        let host = input.host.trim_start_matches("*.").to_string();
         let mut preview = json!({
-            "host": host_for_preview,
-            "allow_private": allow_private
+            "host": input.host,
+            "allow_private": input.allow_private,
+            "path_prefix": input.path_prefix,
+            "methods": input.methods,
+            "expires_at": input.expires_at
         });
-        if let Ok(existing) = load_allowlist(&preview_db) {
-            if let Some(entry) = existing.iter().find(|entry| entry.host == host) {
+        if let Ok(existing) = load_web_access_rules(&preview_db) {
+            let existing = existing
+                .iter()
+                .filter(|rule| rule.host.trim_start_matches("*.") == host)
+                .map(|rule| json!(rule))
+                .collect::<Vec<_>>();
+            if !existing.is_empty() {
                 if let Some(obj) = preview.as_object_mut() {
-                    obj.insert(
-                        "existing".to_string(),
-                        json!({ "allow_private": entry.allow_private, "approved_at": entry.approved_at }),
-                    );
+                    obj.insert("existing".to_string(), json!(existing));
                 }
             }
         }
@@ -134,10 +146,62 @@ fn register_approve_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
     })
 }
 
+/// The rule `web.approve_domain` args describe, plus the URL's path for the deny check.
+fn approval_input(args: &Value) -> Result<(WebAccessRuleInput, String), ToolError> {
+    let url = parse_url(&require_string_arg(args, "url")?)?;
+    let host = normalize_host(
+        url.host_str()
+            .ok_or_else(|| ToolError::new("URL missing host"))?,
+    )?;
+    let include_subdomains = args
+        .get("include_subdomains")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let methods = match args.get("methods") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(value) => value
+            .as_array()
+            .ok_or_else(|| ToolError::validation("Invalid 'methods' (expected array)"))?
+            .iter()
+            .map(|method| {
+                method
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| ToolError::validation("Invalid 'methods' entry"))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    let expires_at = args
+        .get("expires_in_minutes")
+        .and_then(|v| v.as_i64())
+        .map(|minutes| chrono::Utc::now().timestamp() + minutes.max(1) * 60);
+    let input = WebAccessRuleInput {
+        id: None,
+        host: if include_subdomains {
+            format!("*.{host}")
+        } else {
+            host
+        },
+        path_prefix: args
+            .get("path_prefix")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        methods,
+        allow_private: args
+            .get("allow_private")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        deny: false,
+        expires_at,
+        note: None,
+    };
+    Ok((input, url.path().to_string()))
+}
+
 fn register_fetch_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
     let metadata = ToolMetadata {
         name: "web.fetch".to_string(),
-        description: "Fetch a web page and extract text plus links (host must be approved)."
+        description: "Fetch a web page and extract its main content as Markdown plus links (host must be approved). Use mode 'full' for all page text; save_to_vault clips the page into a note, which requires approval."
             .to_string(),
         args_schema: json!({
             "type": "object",
@@ -146,10 +210,14 @@ fn register_fetch_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String
                 "max_bytes": { "type": "integer", "minimum": 1 },
                 "timeout_ms": { "type": "integer", "minimum": 1 },
                 "user_agent": { "type": "string" },
+                "cache": { "type": "boolean" },
                 "same_host_only": { "type": "boolean" },
                 "extract_links": { "type": "boolean" },
                 "include_html": { "type": "boolean" },
-                "max_links": { "type": "integer", "minimum": 1 }
+                "max_links": { "type": "integer", "minimum": 1 },
+                "mode": { "type": "string", "enum": ["readable", "full"] },
+                "save_to_vault": { "type": "boolean" },
+                "note_path": { "type": "string" }
             },
             "required": ["url"],
             "additionalProperties": false
@@ -162,18 +230,35 @@ fn register_fetch_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String
                 "content_type": { "type": "string" },
                 "title": { "type": "string" },
                 "text": { "type": "string" },
+                "format": { "type": "string", "enum": ["markdown", "text"] },
+                "metadata": {
+                    "type": "object",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "author": { "type": "string" },
+                        "published": { "type": "string" },
+                        "canonical_url": { "type": "string" },
+                        "site_name": { "type": "string" },
+                        "description": { "type": "string" },
+                        "language": { "type": "string" }
+                    },
+                    "additionalProperties": false
+                },
+                "saved_path": { "type": "string" },
                 "html": { "type": "string" },
                 "links": { "type": "array", "items": { "type": "string" } },
                 "truncated": { "type": "boolean" },
-                "bytes": { "type": "integer" }
+                "bytes": { "type": "integer" },
+                "cached": { "type": "boolean" }
             },
-            "required": ["url", "status", "content_type", "text", "links", "truncated", "bytes"],
+            "required": ["url", "status", "content_type", "text", "format", "links", "truncated", "bytes"],
             "additionalProperties": false
         }),
         requires_approval: false,
         result_mode: ToolResultMode::Persist,
     };
 
+    let preview_db = db.clone();
     let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
         let url = require_string_arg(&args, "url")?;
         let max_bytes = args
@@ -184,10 +269,9 @@ fn register_fetch_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String
             .get("timeout_ms")
             .and_then(|v| v.as_u64())
             .unwrap_or(DEFAULT_TIMEOUT_MS);
-        let user_agent = args
-            .get("user_agent")
-            .and_then(|v| v.as_str())
-            .unwrap_or(DEFAULT_USER_AGENT);
+        let policy =
+            WebPolicy::load(&db)?.with_user_agent(args.get("user_agent").and_then(|v| v.as_str()));
+        let use_cache = args.get("cache").and_then(|v| v.as_bool()).unwrap_or(true);
         let same_host_only = args
             .get("same_host_only")
             .and_then(|v| v.as_bool())
@@ -204,6 +288,25 @@ fn register_fetch_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String
             .get("max_links")
             .and_then(|v| v.as_u64())
             .unwrap_or(200) as usize;
+        let readable = match args.get("mode").and_then(|v| v.as_str()) {
+            None | Some("readable") => true,
+            Some("full") => false,
+            Some(other) => {
+                return Err(ToolError::validation(format!(
+                    "Unknown mode '{other}' (expected 'readable' or 'full')"
+                )))
+            }
+        };
+        let save_to_vault = args
+            .get("save_to_vault")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let note_path = args
+            .get("note_path")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
 
         let parsed = parse_url(&url)?;
         let original_host = normalize_host(
@@ -212,61 +315,77 @@ fn register_fetch_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String
                 .ok_or_else(|| ToolError::new("URL missing host"))?,
         )?;
 
-        let allowlist = load_allowlist(&db)?;
-        ensure_host_allowed(&allowlist, &original_host)?;
+        let rules = load_web_access_rules(&db)?;
+        ensure_url_allowed(&rules, &parsed, &Method::GET)?;
 
-        let allowed_map = build_allowlist_map(&allowlist);
         let client = build_client(
             timeout_ms,
-            user_agent,
-            &allowed_map,
+            &policy.user_agent,
+            &rules,
+            &Method::GET,
             Some(&original_host),
             same_host_only,
         )?;
 
-        let response = client
-            .get(parsed.as_str())
-            .send()
-            .map_err(|err| ToolError::new(format!("Request failed: {err}")))?;
+        let response = policy.send(
+            &client,
+            PolicyRequest {
+                method: Method::GET,
+                url: &parsed,
+                use_cache,
+                max_bytes,
+            },
+            |request| request,
+            |response| read_limited_body(response, max_bytes),
+        )?;
 
-        if response.status().is_redirection() {
+        if response.status.is_redirection() {
             return Err(ToolError::new("Redirect blocked by host policy"));
         }
 
-        let base_url = response.url().clone();
-        let final_url = response.url().to_string();
+        let base_url = response.url.clone();
+        let final_url = response.url.to_string();
         let final_host = response
-            .url()
+            .url
             .host_str()
             .map(|host| normalize_host(host))
             .transpose()?
             .unwrap_or_else(|| original_host.clone());
 
-        ensure_host_allowed(&allowlist, &final_host)?;
+        ensure_url_allowed(&rules, &response.url, &Method::GET)?;
         if same_host_only && final_host != original_host {
             return Err(ToolError::new("Redirected to a different host"));
         }
 
-        let status = response.status().as_u16() as i64;
+        let status = response.status.as_u16() as i64;
         let content_type = response
-            .headers()
+            .headers
             .get(CONTENT_TYPE)
             .and_then(|v| v.to_str().ok())
             .unwrap_or("")
             .to_string();
 
-        let (body, truncated) = read_limited_body(response, max_bytes)?;
+        let (body, truncated) = (response.body, response.truncated);
         let body_text = String::from_utf8_lossy(&body).to_string();
         let bytes = body.len() as i64;
 
         let mut title = String::new();
         let mut text = String::new();
+        let mut format = "text";
+        let mut metadata = PageMetadata::default();
         let mut links: Vec<String> = Vec::new();
 
         if is_html_content(&content_type, &body_text) {
             let document = Html::parse_document(&body_text);
             title = extract_title(&document);
-            text = extract_text(&document);
+            if readable {
+                let page = extract_readable(&document, &base_url);
+                metadata = page.metadata;
+                text = page.markdown;
+                format = "markdown";
+            } else {
+                text = extract_text(&document);
+            }
             if extract_links {
                 links =
                     extract_links_from_document(&document, &base_url, same_host_only, max_links);
@@ -275,19 +394,49 @@ fn register_fetch_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String
             text = body_text.clone();
         }
 
+        let saved_path = if save_to_vault {
+            if text.trim().is_empty() {
+                return Err(ToolError::new(
+                    "Nothing to save: the page has no readable text",
+                ));
+            }
+            let note_title = metadata
+                .title
+                .clone()
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| title.clone());
+            let note = build_clip_note(&note_title, &final_url, &metadata, &text);
+            Some(save_clip_note(
+                &db,
+                note_path.as_deref(),
+                &note_title,
+                &note,
+            )?)
+        } else {
+            None
+        };
+
         let mut result = json!({
             "url": final_url,
             "status": status,
             "content_type": content_type,
             "title": title,
             "text": text,
+            "format": format,
             "links": links,
             "truncated": truncated,
-            "bytes": bytes
+            "bytes": bytes,
+            "cached": response.from_cache
         });
 
-        if include_html {
-            if let Some(obj) = result.as_object_mut() {
+        if let Some(obj) = result.as_object_mut() {
+            if format == "markdown" {
+                obj.insert("metadata".to_string(), json!(metadata));
+            }
+            if let Some(saved_path) = saved_path {
+                obj.insert("saved_path".to_string(), json!(saved_path));
+            }
+            if include_html {
                 obj.insert("html".to_string(), json!(body_text));
             }
         }
@@ -295,18 +444,38 @@ fn register_fetch_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String
         Ok(result)
     });
 
+    // Only shown when `save_to_vault` makes the call need approval.
+    let preview = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+        let url = parse_url(&require_string_arg(&args, "url")?)?;
+        let note_path = match args
+            .get("note_path")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        {
+            Some(path) => {
+                resolve_vault_path(&preview_db, &clip_note_path(Some(path), ""))?.display_path
+            }
+            None => format!("{WEB_CLIPS_DIR}/<page title>.md"),
+        };
+        Ok(json!({
+            "url": url.to_string(),
+            "save_to_vault": args.get("save_to_vault").and_then(|v| v.as_bool()).unwrap_or(false),
+            "note_path": note_path
+        }))
+    });
+
     registry.register(ToolDefinition {
         metadata,
         handler,
-        preview: None,
+        preview: Some(preview),
     })
 }
 
 fn register_request_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
     let metadata = ToolMetadata {
         name: "web.request".to_string(),
-        description: "Send an HTTP request and return the response (host must be approved)."
-            .to_string(),
+        description: "Send an HTTP request and return the response (host must be approved). Use 'auth_profile' to sign in with stored credentials from web.list_auth_profiles; never put secrets in headers yourself.".to_string(),
         args_schema: json!({
             "type": "object",
             "properties": {
@@ -321,7 +490,9 @@ fn register_request_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
                 "max_bytes": { "type": "integer", "minimum": 1 },
                 "timeout_ms": { "type": "integer", "minimum": 1 },
                 "user_agent": { "type": "string" },
-                "same_host_only": { "type": "boolean" }
+                "cache": { "type": "boolean" },
+                "same_host_only": { "type": "boolean" },
+                "auth_profile": { "type": "string" }
             },
             "required": ["url"],
             "additionalProperties": false
@@ -340,7 +511,9 @@ fn register_request_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
                     "additionalProperties": { "type": "string" }
                 },
                 "truncated": { "type": "boolean" },
-                "bytes": { "type": "integer" }
+                "bytes": { "type": "integer" },
+                "cached": { "type": "boolean" },
+                "auth_profile": { "type": "string" }
             },
             "required": ["url", "method", "status", "content_type", "text", "truncated", "bytes"],
             "additionalProperties": false
@@ -349,6 +522,7 @@ fn register_request_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
         result_mode: ToolResultMode::Auto,
     };
 
+    let handler_db = db.clone();
     let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
         let url = require_string_arg(&args, "url")?;
         let method_raw = args.get("method").and_then(|v| v.as_str()).unwrap_or("GET");
@@ -371,74 +545,107 @@ fn register_request_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
             .get("timeout_ms")
             .and_then(|v| v.as_u64())
             .unwrap_or(DEFAULT_TIMEOUT_MS);
-        let user_agent = args
-            .get("user_agent")
-            .and_then(|v| v.as_str())
-            .unwrap_or(DEFAULT_USER_AGENT);
+        let policy = WebPolicy::load(&handler_db)?
+            .with_user_agent(args.get("user_agent").and_then(|v| v.as_str()));
+        let use_cache = args.get("cache").and_then(|v| v.as_bool()).unwrap_or(false);
         let same_host_only = args
             .get("same_host_only")
             .and_then(|v| v.as_bool())
             .unwrap_or(true);
 
         let parsed = parse_url(&url)?;
+        let profile = args
+            .get("auth_profile")
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.trim().is_empty())
+            .map(|name| load_auth_profile(&handler_db, name.trim(), &parsed))
+            .transpose()?;
+        // Credentials never follow a redirect to another host, and signed responses are not
+        // served from or written to the shared cache.
+        let same_host_only = same_host_only || profile.is_some();
         let original_host = normalize_host(
             parsed
                 .host_str()
                 .ok_or_else(|| ToolError::new("URL missing host"))?,
         )?;
 
-        let allowlist = load_allowlist(&db)?;
-        ensure_host_allowed(&allowlist, &original_host)?;
+        let rules = load_web_access_rules(&handler_db)?;
+        ensure_url_allowed(&rules, &parsed, &method)?;
 
-        let allowed_map = build_allowlist_map(&allowlist);
         let client = build_client(
             timeout_ms,
-            user_agent,
-            &allowed_map,
+            &policy.user_agent,
+            &rules,
+            &method,
             Some(&original_host),
             same_host_only,
         )?;
 
-        let mut request = client.request(method.clone(), parsed.as_str());
-        if !headers.is_empty() {
-            request = request.headers(headers);
-        }
-        if let Some(json_body) = json_body {
-            request = request.json(&json_body);
-        } else if let Some(body) = body {
-            request = request.body(body);
-        }
-
-        let response = request
-            .send()
-            .map_err(|err| ToolError::new(format!("Request failed: {err}")))?;
+        let use_cache = use_cache && body.is_none() && json_body.is_none() && profile.is_none();
+        let auth = match &profile {
+            Some(profile) => {
+                let signed_body = match (&json_body, &body) {
+                    (Some(json_body), _) => serde_json::to_vec(json_body).map_err(|err| {
+                        ToolError::new(format!("Failed to serialize JSON body: {err}"))
+                    })?,
+                    (None, Some(body)) => body.as_bytes().to_vec(),
+                    (None, None) => Vec::new(),
+                };
+                Some(auth_headers(profile, &method, &parsed, &signed_body)?)
+            }
+            None => None,
+        };
+        let response = policy.send(
+            &client,
+            PolicyRequest {
+                method: method.clone(),
+                url: &parsed,
+                use_cache,
+                max_bytes,
+            },
+            |mut request| {
+                if !headers.is_empty() {
+                    request = request.headers(headers);
+                }
+                if let Some(auth) = auth {
+                    request = request.headers(auth);
+                }
+                if let Some(json_body) = json_body {
+                    request = request.json(&json_body);
+                } else if let Some(body) = body {
+                    request = request.body(body);
+                }
+                request
+            },
+            |response| read_limited_body(response, max_bytes),
+        )?;
 
-        if response.status().is_redirection() {
+        if response.status.is_redirection() {
             return Err(ToolError::new("Redirect blocked by host policy"));
         }
 
-        let final_url = response.url().to_string();
+        let final_url = response.url.to_string();
         let final_host = response
-            .url()
+            .url
             .host_str()
             .map(|host| normalize_host(host))
             .transpose()?
             .unwrap_or_else(|| original_host.clone());
-        ensure_host_allowed(&allowlist, &final_host)?;
+        ensure_url_allowed(&rules, &response.url, &method)?;
         if same_host_only && final_host != original_host {
             return Err(ToolError::new("Redirected to a different host"));
         }
 
-        let status = response.status().as_u16() as i64;
+        let status = response.status.as_u16() as i64;
         let content_type = response
-            .headers()
+            .headers
             .get(CONTENT_TYPE)
             .and_then(|v| v.to_str().ok())
             .unwrap_or("")
             .to_string();
-        let headers_json = headers_to_json(response.headers());
+        let headers_json = headers_to_json(&response.headers);
 
-        let (body_bytes, truncated) = read_limited_body(response, max_bytes)?;
+        let (body_bytes, truncated) = (response.body, response.truncated);
         let bytes = body_bytes.len() as i64;
         let text = if is_text_content(&content_type) {
             String::from_utf8_lossy(&body_bytes).to_string()
@@ -454,7 +661,8 @@ fn register_request_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
             "text": text,
             "headers": headers_json,
             "truncated": truncated,
-            "bytes": bytes
+            "bytes": bytes,
+            "cached": response.from_cache
         });
 
         if is_text_content(&content_type) && content_type.to_ascii_lowercase().contains("json") {
@@ -465,13 +673,47 @@ fn register_request_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Stri
             }
         }
 
+        if let Some(profile) = profile {
+            result = redact_secrets(&result, &profile_secrets(&profile));
+            if let Some(obj) = result.as_object_mut() {
+                obj.insert("auth_profile".to_string(), json!(profile.name));
+            }
+        }
+
         Ok(result)
     });
 
+    let preview_db = db;
+    let preview = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+        let url = parse_url(&require_string_arg(&args, "url")?)?;
+        let method = parse_method(args.get("method").and_then(|v| v.as_str()).unwrap_or("GET"))?;
+        let mut preview = json!({
+            "method": method.as_str(),
+            "url": url.to_string()
+        });
+        if let Some(name) = args
+            .get("auth_profile")
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.trim().is_empty())
+        {
+            let profile = load_auth_profile(&preview_db, name.trim(), &url)?;
+            let headers = auth_header_names(&profile)
+                .into_iter()
+                .map(|name| (name, json!("[redacted]")))
+                .collect::<serde_json::Map<_, _>>();
+            if let Some(obj) = preview.as_object_mut() {
+                obj.insert("auth_profile".to_string(), json!(profile.name));
+                obj.insert("auth_kind".to_string(), json!(profile.kind));
+                obj.insert("auth_headers".to_string(), Value::Object(headers));
+            }
+        }
+        Ok(preview)
+    });
+
     registry.register(ToolDefinition {
         metadata,
         handler,
-        preview: None,
+        preview: Some(preview),
     })
 }
 
@@ -490,6 +732,7 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                 "max_bytes_per_file": { "type": "integer", "minimum": 1 },
                 "timeout_ms": { "type": "integer", "minimum": 1 },
                 "user_agent": { "type": "string" },
+                "cache": { "type": "boolean" },
                 "same_host_only": { "type": "boolean" },
                 "flatten": { "type": "boolean" },
                 "rename_strategy": { "type": "string", "enum": ["safe", "overwrite"] }
@@ -512,6 +755,7 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                             "content_type": { "type": "string" },
                             "bytes_written": { "type": "integer" },
                             "path": { "type": "string" },
+                            "cached": { "type": "boolean" },
                             "error": { "type": "string" }
                         },
                         "required": ["url", "success"],
@@ -526,11 +770,11 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
         result_mode: ToolResultMode::Auto,
     };
 
-    let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+    let handler = Arc::new(move |args: Value, ctx: ToolExecutionContext| {
         let urls = args
             .get("urls")
             .and_then(|v| v.as_array())
-            .ok_or_else(|| ToolError::new("Missing or invalid 'urls'"))?;
+            .ok_or_else(|| ToolError::validation("Missing or invalid 'urls'"))?;
         let base_url = args.get("base_url").and_then(|v| v.as_str());
         let vault_path = args
             .get("vault_path")
@@ -544,10 +788,9 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
             .get("timeout_ms")
             .and_then(|v| v.as_u64())
             .unwrap_or(DEFAULT_TIMEOUT_MS);
-        let user_agent = args
-            .get("user_agent")
-            .and_then(|v| v.as_str())
-            .unwrap_or(DEFAULT_USER_AGENT);
+        let policy =
+            WebPolicy::load(&db)?.with_user_agent(args.get("user_agent").and_then(|v| v.as_str()));
+        let use_cache = args.get("cache").and_then(|v| v.as_bool()).unwrap_or(true);
         let same_host_only = args
             .get("same_host_only")
             .and_then(|v| v.as_bool())
@@ -561,8 +804,7 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
             .and_then(|v| v.as_str())
             .unwrap_or("safe");
 
-        let allowlist = load_allowlist(&db)?;
-        let allowed_map = build_allowlist_map(&allowlist);
+        let rules = load_web_access_rules(&db)?;
 
         let base_url_parsed = match base_url {
             Some(url) => Some(parse_url(url)?),
@@ -570,6 +812,7 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
         };
         let base_host = if same_host_only {
             if let Some(base) = &base_url_parsed {
+                ensure_url_allowed(&rules, base, &Method::GET)?;
                 normalize_host(
                     base.host_str()
                         .ok_or_else(|| ToolError::new("base_url missing host"))?,
@@ -580,6 +823,7 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| ToolError::new("urls must be non-empty strings"))?;
                 let parsed = parse_url(first)?;
+                ensure_url_allowed(&rules, &parsed, &Method::GET)?;
                 normalize_host(
                     parsed
                         .host_str()
@@ -590,10 +834,6 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
             String::new()
         };
 
-        if same_host_only {
-            ensure_host_allowed(&allowlist, &base_host)?;
-        }
-
         let base_dir = resolve_vault_path(&db, vault_path)?;
         if base_dir.full_path.exists() && !base_dir.full_path.is_dir() {
             return Err(ToolError::new("vault_path must be a directory"));
@@ -603,14 +843,22 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
 
         let client = build_client(
             timeout_ms,
-            user_agent,
-            &allowed_map,
+            &policy.user_agent,
+            &rules,
+            &Method::GET,
             Some(&base_host),
             same_host_only,
         )?;
 
+        let total = urls.len() as u64;
         let mut results = Vec::new();
-        for url_value in urls {
+        for (index, url_value) in urls.iter().enumerate() {
+            ctx.check_cancelled()?;
+            ctx.report_progress(
+                format!("Downloading {} of {total}", index + 1),
+                Some(index as u64),
+                Some(total),
+            );
             let url_str = match url_value.as_str() {
                 Some(value) => value,
                 None => {
@@ -666,7 +914,7 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                 continue;
             }
 
-            if let Err(err) = ensure_host_allowed(&allowlist, &host) {
+            if let Err(err) = ensure_url_allowed(&rules, &resolved, &Method::GET) {
                 results.push(json!({
                     "url": url_str,
                     "success": false,
@@ -675,19 +923,30 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                 continue;
             }
 
-            let response = match client.get(resolved.as_str()).send() {
+            let response = match policy.send(
+                &client,
+                PolicyRequest {
+                    method: Method::GET,
+                    url: &resolved,
+                    use_cache,
+                    max_bytes,
+                },
+                |request| request,
+                |response| read_cancellable_body(response, max_bytes, &ctx),
+            ) {
                 Ok(response) => response,
                 Err(err) => {
+                    ctx.check_cancelled()?;
                     results.push(json!({
                         "url": url_str,
                         "success": false,
-                        "error": format!("Request failed: {err}")
+                        "error": err.message
                     }));
                     continue;
                 }
             };
 
-            if response.status().is_redirection() {
+            if response.status.is_redirection() {
                 results.push(json!({
                     "url": url_str,
                     "success": false,
@@ -696,27 +955,14 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                 continue;
             }
 
-            let status = response.status().as_u16() as i64;
+            let status = response.status.as_u16() as i64;
             let content_type = response
-                .headers()
+                .headers
                 .get(CONTENT_TYPE)
                 .and_then(|v| v.to_str().ok())
                 .unwrap_or("")
                 .to_string();
-
-            let (body, truncated) = match read_limited_body(response, max_bytes) {
-                Ok(result) => result,
-                Err(err) => {
-                    results.push(json!({
-                        "url": url_str,
-                        "success": false,
-                        "status": status,
-                        "content_type": content_type,
-                        "error": err.message
-                    }));
-                    continue;
-                }
-            };
+            let (body, truncated) = (response.body, response.truncated);
 
             if truncated {
                 results.push(json!({
@@ -767,7 +1013,8 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
                 "status": status,
                 "content_type": content_type,
                 "bytes_written": body.len() as i64,
-                "path": display_path
+                "path": display_path,
+                "cached": response.from_cache
             }));
         }
 
@@ -790,38 +1037,13 @@ fn register_download_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), Str
     })
 }
 
-fn load_allowlist(db: &Db) -> Result<Vec<AllowedHost>, ToolError> {
-    let raw = PreferenceOperations::get_preference(db, PREF_ALLOWED_HOSTS)
-        .map_err(|err| ToolError::new(format!("Failed to load web allowlist: {err}")))?;
-    let Some(raw) = raw else {
-        return Ok(Vec::new());
-    };
-    serde_json::from_str(&raw).map_err(|err| ToolError::new(format!("Invalid allowlist: {err}")))
-}
-
-fn save_allowlist(db: &Db, list: &[AllowedHost]) -> Result<(), ToolError> {
-    let value = serde_json::to_string(list)
-        .map_err(|err| ToolError::new(format!("Failed to serialize allowlist: {err}")))?;
-    PreferenceOperations::set_preference(db, PREF_ALLOWED_HOSTS, &value)
-        .map_err(|err| ToolError::new(format!("Failed to save web allowlist: {err}")))?;
-    Ok(())
-}
-
-fn build_allowlist_map(list: &[AllowedHost]) -> HashMap<String, bool> {
-    let mut map = HashMap::new();
-    for entry in list {
-        map.insert(entry.host.clone(), entry.allow_private);
-    }
-    map
-}
-
 fn parse_method(input: &str) -> Result<Method, ToolError> {
     let normalized = input.trim().to_ascii_uppercase();
     if normalized.is_empty() {
         return Err(ToolError::new("Method cannot be empty"));
     }
     Method::from_bytes(normalized.as_bytes())
-        .map_err(|_| ToolError::new(format!("Invalid method '{input}'")))
+        .map_err(|_| ToolError::validation(format!("Invalid method '{input}'")))
 }
 
 fn parse_headers(args: &Value) -> Result<HeaderMap, ToolError> {
@@ -833,16 +1055,16 @@ fn parse_headers(args: &Value) -> Result<HeaderMap, ToolError> {
     }
     let obj = value
         .as_object()
-        .ok_or_else(|| ToolError::new("Invalid 'headers' (expected object)"))?;
+        .ok_or_else(|| ToolError::validation("Invalid 'headers' (expected object)"))?;
     let mut headers = HeaderMap::new();
     for (key, raw_value) in obj {
         let value = raw_value
             .as_str()
-            .ok_or_else(|| ToolError::new(format!("Invalid header value for '{key}'")))?;
+            .ok_or_else(|| ToolError::validation(format!("Invalid header value for '{key}'")))?;
         let name = HeaderName::from_bytes(key.as_bytes())
-            .map_err(|_| ToolError::new(format!("Invalid header name '{key}'")))?;
+            .map_err(|_| ToolError::validation(format!("Invalid header name '{key}'")))?;
         let value = HeaderValue::from_str(value)
-            .map_err(|_| ToolError::new(format!("Invalid header value for '{key}'")))?;
+            .map_err(|_| ToolError::validation(format!("Invalid header value for '{key}'")))?;
         headers.insert(name, value);
     }
     Ok(headers)
@@ -858,15 +1080,7 @@ fn headers_to_json(headers: &HeaderMap) -> Value {
     Value::Object(map)
 }
 
-fn normalize_host_from_input(input: &str) -> Result<String, ToolError> {
-    let url = parse_url(input)?;
-    let host = url
-        .host_str()
-        .ok_or_else(|| ToolError::new("URL missing host"))?;
-    normalize_host(host)
-}
-
-fn normalize_host(host: &str) -> Result<String, ToolError> {
+pub(super) fn normalize_host(host: &str) -> Result<String, ToolError> {
     let normalized = host.trim().trim_end_matches('.').to_ascii_lowercase();
     if normalized.is_empty() {
         return Err(ToolError::new("Host is empty"));
@@ -874,32 +1088,18 @@ fn normalize_host(host: &str) -> Result<String, ToolError> {
     Ok(normalized)
 }
 
-fn parse_url(input: &str) -> Result<Url, ToolError> {
+pub(super) fn parse_url(input: &str) -> Result<Url, ToolError> {
     match Url::parse(input) {
         Ok(url) => Ok(url),
         Err(_) => {
             let with_scheme = format!("https://{input}");
-            Url::parse(&with_scheme).map_err(|err| ToolError::new(format!("Invalid URL: {err}")))
+            Url::parse(&with_scheme)
+                .map_err(|err| ToolError::validation(format!("Invalid URL: {err}")))
         }
     }
 }
 
-fn ensure_host_allowed(list: &[AllowedHost], host: &str) -> Result<(), ToolError> {
-    let entry = list.iter().find(|entry| entry.host == host);
-    let Some(entry) = entry else {
-        return Err(ToolError::new(format!(
-            "Host not approved: {host}. Use web.approve_domain first."
-        )));
-    };
-    if is_private_host(host) && !entry.allow_private {
-        return Err(ToolError::new(
-            "Private/local host blocked. Re-approve with allow_private=true.",
-        ));
-    }
-    Ok(())
-}
-
-fn is_private_host(host: &str) -> bool {
+pub(crate) fn is_private_host(host: &str) -> bool {
     if host.eq_ignore_ascii_case("localhost") {
         return true;
     }
@@ -929,14 +1129,18 @@ fn is_private_ip(ip: IpAddr) -> bool {
     }
 }
 
-fn build_client(
+/// Client whose redirect policy re-checks every hop against the web access rules, for the
+/// method the hop will actually use.
+pub(super) fn build_client(
     timeout_ms: u64,
     user_agent: &str,
-    allowlist: &HashMap<String, bool>,
+    rules: &[WebAccessRule],
+    method: &Method,
     base_host: Option<&str>,
     same_host_only: bool,
 ) -> Result<Client, ToolError> {
-    let allowlist = allowlist.clone();
+    let rules = rules.to_vec();
+    let method = method.clone();
     let base_host = base_host.map(|host| host.to_string());
     let policy = Policy::custom(move |attempt| {
         if attempt.previous().len() >= 10 {
@@ -954,15 +1158,17 @@ fn build_client(
                 }
             }
         }
-        match allowlist.get(&host) {
-            Some(allow_private) => {
-                if is_private_host(&host) && !*allow_private {
-                    attempt.stop()
-                } else {
-                    attempt.follow()
-                }
+        // 303, and 301/302 after a POST, are followed with GET.
+        let hop_method = match attempt.status() {
+            StatusCode::SEE_OTHER => Method::GET,
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND if method == Method::POST => {
+                Method::GET
             }
-            None => attempt.stop(),
+            _ => method.clone(),
+        };
+        match ensure_url_allowed(&rules, attempt.url(), &hop_method) {
+            Ok(()) => attempt.follow(),
+            Err(_) => attempt.stop(),
         }
     });
 
@@ -974,7 +1180,7 @@ fn build_client(
         .map_err(|err| ToolError::new(format!("Failed to build client: {err}")))
 }
 
-fn read_limited_body(
+pub(super) fn read_limited_body(
     response: reqwest::blocking::Response,
     max_bytes: usize,
 ) -> Result<(Vec<u8>, bool), ToolError> {
@@ -991,7 +1197,32 @@ fn read_limited_body(
     Ok((body, truncated))
 }
 
-fn is_html_content(content_type: &str, body: &str) -> bool {
+/// Like `read_limited_body`, but reads in chunks so a cancelled download stops mid-transfer.
+fn read_cancellable_body(
+    mut response: reqwest::blocking::Response,
+    max_bytes: usize,
+    ctx: &ToolExecutionContext,
+) -> Result<(Vec<u8>, bool), ToolError> {
+    use std::io::Read;
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 64 * 1024];
+    loop {
+        ctx.check_cancelled()?;
+        let read = response
+            .read(&mut chunk)
+            .map_err(|err| ToolError::new(format!("Failed to read response: {err}")))?;
+        if read == 0 {
+            return Ok((body, false));
+        }
+        body.extend_from_slice(&chunk[..read]);
+        if body.len() > max_bytes {
+            body.truncate(max_bytes);
+            return Ok((body, true));
+        }
+    }
+}
+
+pub(super) fn is_html_content(content_type: &str, body: &str) -> bool {
     content_type.to_ascii_lowercase().contains("text/html") || body.contains("<html")
 }
 
@@ -1000,7 +1231,7 @@ fn is_text_content(content_type: &str) -> bool {
     lower.starts_with("text/") || lower.contains("json") || lower.contains("xml")
 }
 
-fn extract_title(document: &Html) -> String {
+pub(super) fn extract_title(document: &Html) -> String {
     let selector = match Selector::parse("title") {
         Ok(selector) => selector,
         Err(_) => return String::new(),
@@ -1038,7 +1269,75 @@ fn extract_text(document: &Html) -> String {
     result
 }
 
-fn extract_links_from_document(
+/// Markdown note with YAML front matter describing where and when the page was clipped.
+pub(super) fn build_clip_note(
+    title: &str,
+    url: &str,
+    metadata: &PageMetadata,
+    markdown: &str,
+) -> String {
+    let mut note = String::from("---\n");
+    let mut field = |key: &str, value: &str| {
+        note.push_str(&format!("{key}: {}\n", yaml_string(value)));
+    };
+    field("title", title);
+    field("source", metadata.canonical_url.as_deref().unwrap_or(url));
+    if let Some(author) = &metadata.author {
+        field("author", author);
+    }
+    if let Some(published) = &metadata.published {
+        field("published", published);
+    }
+    field("clipped", &chrono::Utc::now().to_rfc3339());
+    note.push_str("---\n\n");
+    if !title.is_empty() {
+        note.push_str(&format!("# {title}\n\n"));
+    }
+    note.push_str(markdown.trim());
+    note.push('\n');
+    note
+}
+
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// Vault-relative path of a clip: `note_path`, or `Web Clips/<title>.md`.
+fn clip_note_path(note_path: Option<&str>, title: &str) -> String {
+    match note_path {
+        Some(path) if path.ends_with(".md") => path.to_string(),
+        Some(path) => format!("{path}.md"),
+        None => {
+            let name = sanitize_segment(title);
+            let name = if name.is_empty() {
+                "clip".to_string()
+            } else {
+                name
+            };
+            format!("{WEB_CLIPS_DIR}/{name}.md")
+        }
+    }
+}
+
+/// Writes the note to `note_path`, or to `Web Clips/<title>.md`, without overwriting.
+fn save_clip_note(
+    db: &Db,
+    note_path: Option<&str>,
+    title: &str,
+    note: &str,
+) -> Result<String, ToolError> {
+    let relative = clip_note_path(note_path, title);
+    let resolved = ensure_unique_path(resolve_vault_path(db, &relative)?)?;
+    if let Some(parent) = resolved.full_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|err| ToolError::new(format!("Failed to create directories: {err}")))?;
+    }
+    std::fs::write(&resolved.full_path, note)
+        .map_err(|err| ToolError::new(format!("Failed to write note: {err}")))?;
+    Ok(resolved.display_path)
+}
+
+pub(super) fn extract_links_from_document(
     document: &Html,
     base_url: &Url,
     same_host_only: bool,
@@ -1091,7 +1390,7 @@ fn resolve_download_url(input: &str, base_url: Option<&Url>) -> Result<Url, Tool
                 return Err(ToolError::new("Relative URL requires base_url"));
             };
             base.join(input)
-                .map_err(|err| ToolError::new(format!("Invalid URL: {err}")))
+                .map_err(|err| ToolError::validation(format!("Invalid URL: {err}")))
         }
     }
 }
@@ -1237,7 +1536,7 @@ fn split_extension(file_name: &str) -> (String, String) {
     (stem, ext)
 }
 
-fn sanitize_segment(segment: &str) -> String {
+pub(super) fn sanitize_segment(segment: &str) -> String {
     let mut sanitized = String::new();
     for ch in segment.chars() {
         if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.' {
@@ -1249,9 +1548,9 @@ fn sanitize_segment(segment: &str) -> String {
     sanitized.trim_matches('_').to_string()
 }
 
-fn require_string_arg(args: &Value, key: &str) -> Result<String, ToolError> {
+pub(super) fn require_string_arg(args: &Value, key: &str) -> Result<String, ToolError> {
     args.get(key)
         .and_then(|value| value.as_str())
         .map(|value| value.to_string())
-        .ok_or_else(|| ToolError::new(format!("Missing or invalid '{key}'")))
+        .ok_or_else(|| ToolError::validation(format!("Missing or invalid '{key}'")))
 }
@@ -0,0 +1,412 @@
+//! Rules deciding which URLs the web tools may reach. They are stored as JSON in the
+//! `plugins.web.allowed_hosts` preference; entries saved before rules had ids, paths or
+//! methods load as host-wide allow rules.
+
+use super::web::{is_private_host, normalize_host};
+use crate::db::{Db, PreferenceOperations};
+use crate::tools::ToolError;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const PREF_ALLOWED_HOSTS: &str = "plugins.web.allowed_hosts";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebAccessRule {
+    #[serde(default)]
+    pub id: String,
+    /// Exact host, or `*.example.com` for `example.com` and all of its subdomains.
+    pub host: String,
+    /// Only paths equal to this prefix or below it (`/docs` covers `/docs/a`, not `/docsx`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+    /// Upper-case HTTP methods the rule covers; empty covers every method.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    #[serde(default)]
+    pub allow_private: bool,
+    /// Deny rules win over every allow rule that also matches.
+    #[serde(default)]
+    pub deny: bool,
+    /// Unix seconds after which the rule no longer applies, like `approved_at`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub approved_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A new rule, or an edit of the rule with `id`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebAccessRuleInput {
+    pub id: Option<String>,
+    pub host: String,
+    pub path_prefix: Option<String>,
+    #[serde(default)]
+    pub methods: Vec<String>,
+    #[serde(default)]
+    pub allow_private: bool,
+    #[serde(default)]
+    pub deny: bool,
+    pub expires_at: Option<i64>,
+    pub note: Option<String>,
+}
+
+impl WebAccessRule {
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        host_matches(&self.host, host)
+    }
+
+    fn matches_path(&self, path: &str) -> bool {
+        let Some(prefix) = self.path_prefix.as_deref() else {
+            return true;
+        };
+        let prefix = prefix.trim_end_matches('/');
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    fn matches_method(&self, method: &Method) -> bool {
+        self.methods.is_empty() || self.methods.iter().any(|m| m == method.as_str())
+    }
+
+    /// Same host pattern, path and methods: approving again updates the rule in place.
+    fn same_scope(&self, other: &WebAccessRule) -> bool {
+        self.host == other.host
+            && self.path_prefix == other.path_prefix
+            && self.methods == other.methods
+            && self.deny == other.deny
+    }
+}
+
+/// Whether `host` is `pattern`, or lies under it when `pattern` is `*.domain`.
+pub(super) fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => {
+            host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.ends_with('.'))
+        }
+        None => pattern == host,
+    }
+}
+
+pub fn load_web_access_rules(db: &Db) -> Result<Vec<WebAccessRule>, ToolError> {
+    let raw = PreferenceOperations::get_preference(db, PREF_ALLOWED_HOSTS)
+        .map_err(|err| ToolError::new(format!("Failed to load web allowlist: {err}")))?;
+    let Some(raw) = raw else {
+        return Ok(Vec::new());
+    };
+    let mut rules: Vec<WebAccessRule> = serde_json::from_str(&raw)
+        .map_err(|err| ToolError::new(format!("Invalid allowlist: {err}")))?;
+    // Entries from before rules had ids get one now, so commands can address them.
+    if rules.iter().any(|rule| rule.id.is_empty()) {
+        for rule in rules.iter_mut().filter(|rule| rule.id.is_empty()) {
+            rule.id = uuid::Uuid::new_v4().to_string();
+        }
+        save_web_access_rules(db, &rules)?;
+    }
+    Ok(rules)
+}
+
+fn save_web_access_rules(db: &Db, rules: &[WebAccessRule]) -> Result<(), ToolError> {
+    let value = serde_json::to_string(rules)
+        .map_err(|err| ToolError::new(format!("Failed to serialize allowlist: {err}")))?;
+    PreferenceOperations::set_preference(db, PREF_ALLOWED_HOSTS, &value)
+        .map_err(|err| ToolError::new(format!("Failed to save web allowlist: {err}")))?;
+    Ok(())
+}
+
+/// Validates `input` and saves it, replacing the rule with the same id, or else an
+/// existing rule with the same scope.
+pub fn upsert_web_access_rule(
+    db: &Db,
+    input: WebAccessRuleInput,
+) -> Result<WebAccessRule, ToolError> {
+    let mut rule = rule_from_input(input)?;
+    let mut rules = load_web_access_rules(db)?;
+    let existing = if rule.id.is_empty() {
+        rules.iter().position(|existing| existing.same_scope(&rule))
+    } else {
+        let position = rules.iter().position(|existing| existing.id == rule.id);
+        if position.is_none() {
+            return Err(ToolError::not_found(format!(
+                "Web access rule not found: {}",
+                rule.id
+            )));
+        }
+        position
+    };
+    match existing {
+        Some(index) => {
+            rule.id = rules[index].id.clone();
+            rules[index] = rule.clone();
+        }
+        None => {
+            rule.id = uuid::Uuid::new_v4().to_string();
+            rules.push(rule.clone());
+        }
+    }
+    save_web_access_rules(db, &rules)?;
+    Ok(rule)
+}
+
+pub fn revoke_web_access_rule(db: &Db, id: &str) -> Result<bool, ToolError> {
+    let mut rules = load_web_access_rules(db)?;
+    let before = rules.len();
+    rules.retain(|rule| rule.id != id);
+    if rules.len() == before {
+        return Ok(false);
+    }
+    save_web_access_rules(db, &rules)?;
+    Ok(true)
+}
+
+fn rule_from_input(input: WebAccessRuleInput) -> Result<WebAccessRule, ToolError> {
+    let host = normalize_host_pattern(&input.host)?;
+    let path_prefix = match input.path_prefix.as_deref().map(str::trim) {
+        None | Some("") | Some("/") => None,
+        Some(path) if path.starts_with('/') => Some(path.to_string()),
+        Some(path) => {
+            return Err(ToolError::validation(format!(
+                "path_prefix must start with '/': {path}"
+            )))
+        }
+    };
+    let mut methods = Vec::new();
+    for method in &input.methods {
+        let method = method.trim().to_ascii_uppercase();
+        Method::from_bytes(method.as_bytes())
+            .map_err(|_| ToolError::validation(format!("Invalid method '{method}'")))?;
+        if !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+    methods.sort();
+    if !input.deny && is_private_host(host.trim_start_matches("*.")) && !input.allow_private {
+        return Err(ToolError::validation(
+            "Private/local hosts require allow_private=true",
+        ));
+    }
+    Ok(WebAccessRule {
+        id: input.id.unwrap_or_default(),
+        host,
+        path_prefix,
+        methods,
+        allow_private: input.allow_private,
+        deny: input.deny,
+        expires_at: input.expires_at,
+        approved_at: chrono::Utc::now().timestamp(),
+        note: input
+            .note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty()),
+    })
+}
+
+/// Lower-cased exact host or `*.domain` pattern, from a bare host or a pasted URL.
+pub(super) fn normalize_host_pattern(raw: &str) -> Result<String, ToolError> {
+    let raw = raw.trim();
+    let host = match raw.strip_prefix("*.") {
+        Some(domain) => format!("*.{}", normalize_host(&host_of(domain)?)?),
+        None => normalize_host(&host_of(raw)?)?,
+    };
+    if host.strip_prefix("*.").unwrap_or(&host).contains('*') {
+        return Err(ToolError::validation(
+            "Wildcards are only supported as a leading '*.' in host",
+        ));
+    }
+    Ok(host)
+}
+
+/// Accepts a bare host or a URL, so a rule can be created from a pasted link.
+fn host_of(input: &str) -> Result<String, ToolError> {
+    if !input.contains("://") && !input.contains('/') {
+        return Ok(input.to_string());
+    }
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{input}")
+    };
+    Url::parse(&with_scheme)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .ok_or_else(|| ToolError::validation(format!("Invalid host: {input}")))
+}
+
+/// Whether an active deny rule covers `url` for any method, so approving it is pointless.
+pub fn is_denied(rules: &[WebAccessRule], host: &str, path: &str) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    rules.iter().any(|rule| {
+        rule.deny && !rule.is_expired(now) && rule.matches_host(host) && rule.matches_path(path)
+    })
+}
+
+/// Checks `method url` against the rules: a matching deny rule blocks it, otherwise an
+/// unexpired allow rule matching host, path and method must admit it.
+pub fn ensure_url_allowed(
+    rules: &[WebAccessRule],
+    url: &Url,
+    method: &Method,
+) -> Result<(), ToolError> {
+    let host = normalize_host(
+        url.host_str()
+            .ok_or_else(|| ToolError::new("URL missing host"))?,
+    )?;
+    let path = url.path();
+    let now = chrono::Utc::now().timestamp();
+    let host_rules = rules
+        .iter()
+        .filter(|rule| rule.matches_host(&host))
+        .collect::<Vec<_>>();
+    let matching = host_rules
+        .iter()
+        .copied()
+        .filter(|rule| {
+            !rule.is_expired(now) && rule.matches_path(path) && rule.matches_method(method)
+        })
+        .collect::<Vec<_>>();
+
+    if matching.iter().any(|rule| rule.deny) {
+        return Err(ToolError::permission(format!(
+            "Access to {host}{path} is denied by a web rule"
+        ))
+        .with_hint("The user blocked this URL; do not retry or approve it."));
+    }
+    if matching.is_empty() {
+        let allow_rules = host_rules
+            .into_iter()
+            .filter(|rule| !rule.deny)
+            .collect::<Vec<_>>();
+        return Err(if allow_rules.is_empty() {
+            ToolError::permission(format!("Host not approved: {host}"))
+                .with_hint("Call web.approve_domain for this host first.")
+        } else if allow_rules.iter().all(|rule| rule.is_expired(now)) {
+            ToolError::permission(format!("Approval for {host} has expired"))
+                .with_hint("Call web.approve_domain for this host again.")
+        } else {
+            ToolError::permission(format!(
+                "Approved rules for {host} do not cover {method} {path}"
+            ))
+            .with_hint("Call web.approve_domain with a path_prefix and methods covering it.")
+        });
+    }
+    if is_private_host(&host) && !matching.iter().any(|rule| rule.allow_private) {
+        return Err(ToolError::permission("Private/local host blocked")
+            .with_hint("Re-approve the host with allow_private=true."));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(host: &str) -> WebAccessRule {
+        WebAccessRule {
+            id: host.to_string(),
+            host: host.to_string(),
+            path_prefix: None,
+            methods: Vec::new(),
+            allow_private: false,
+            deny: false,
+            expires_at: None,
+            approved_at: 0,
+            note: None,
+        }
+    }
+
+    fn check(rules: &[WebAccessRule], method: Method, url: &str) -> Result<(), String> {
+        ensure_url_allowed(rules, &Url::parse(url).unwrap(), &method).map_err(|err| err.message)
+    }
+
+    #[test]
+    fn rules_scope_hosts_paths_methods_and_expiry() {
+        let rules = vec![
+            rule("*.example.com"),
+            WebAccessRule {
+                path_prefix: Some("/v1/".to_string()),
+                methods: vec!["GET".to_string(), "POST".to_string()],
+                ..rule("api.test.dev")
+            },
+            WebAccessRule {
+                path_prefix: Some("/v1/admin".to_string()),
+                deny: true,
+                ..rule("api.test.dev")
+            },
+            WebAccessRule {
+                expires_at: Some(chrono::Utc::now().timestamp() - 10),
+                ..rule("old.dev")
+            },
+        ];
+
+        assert!(check(&rules, Method::GET, "https://docs.example.com/a").is_ok());
+        assert!(check(&rules, Method::GET, "https://a.b.example.com/").is_ok());
+        assert!(check(&rules, Method::GET, "https://example.com/").is_ok());
+        assert!(check(&rules, Method::GET, "https://badexample.com/")
+            .unwrap_err()
+            .starts_with("Host not approved"));
+
+        assert!(check(&rules, Method::POST, "https://api.test.dev/v1/items").is_ok());
+        assert!(check(&rules, Method::GET, "https://api.test.dev/v1").is_ok());
+        assert!(
+            check(&rules, Method::DELETE, "https://api.test.dev/v1/items")
+                .unwrap_err()
+                .contains("do not cover DELETE /v1/items")
+        );
+        assert!(check(&rules, Method::GET, "https://api.test.dev/v2/items").is_err());
+        assert!(
+            check(&rules, Method::GET, "https://api.test.dev/v1/admin/users")
+                .unwrap_err()
+                .contains("denied")
+        );
+        assert!(check(
+            &rules,
+            Method::GET,
+            "https://api.test.dev/v1/administrators"
+        )
+        .is_ok());
+
+        assert!(check(&rules, Method::GET, "https://old.dev/")
+            .unwrap_err()
+            .contains("expired"));
+    }
+
+    #[test]
+    fn legacy_entries_load_as_host_rules() {
+        let legacy: Vec<WebAccessRule> = serde_json::from_str(
+            r#"[{"host": "localhost", "allow_private": true, "approved_at": 1700000000}]"#,
+        )
+        .unwrap();
+        assert_eq!(legacy[0].path_prefix, None);
+        assert!(!legacy[0].deny);
+        assert!(check(&legacy, Method::PUT, "http://localhost:8080/x").is_ok());
+
+        let input = WebAccessRuleInput {
+            host: "*.Example.COM.".to_string(),
+            path_prefix: Some("docs".to_string()),
+            ..Default::default()
+        };
+        assert!(rule_from_input(input).is_err());
+        let input = WebAccessRuleInput {
+            host: "api.*.example.com".to_string(),
+            ..Default::default()
+        };
+        assert!(rule_from_input(input).is_err());
+        let input = WebAccessRuleInput {
+            host: "*.Example.COM.".to_string(),
+            methods: vec!["get".to_string(), "GET".to_string()],
+            ..Default::default()
+        };
+        let rule = rule_from_input(input).unwrap();
+        assert_eq!(rule.host, "*.example.com");
+        assert_eq!(rule.methods, vec!["GET".to_string()]);
+    }
+}
@@ -0,0 +1,435 @@
+//! Named credential profiles for `web.request`. The model only ever names a profile; the
+//! headers are computed here, just before the request goes out, and any secret material the
+//! server echoes back is scrubbed from the result.
+
+use super::web_allowlist::{host_matches, normalize_host_pattern};
+use crate::db::{
+    Db, SaveWebAuthProfileInput, WebAuthProfile, WebAuthProfileOperations, WEB_AUTH_API_KEY,
+    WEB_AUTH_BASIC, WEB_AUTH_BEARER, WEB_AUTH_HMAC,
+};
+use crate::tools::{
+    ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
+};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::Method;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use url::Url;
+
+const AUTH_KINDS: [&str; 4] = [
+    WEB_AUTH_BEARER,
+    WEB_AUTH_BASIC,
+    WEB_AUTH_API_KEY,
+    WEB_AUTH_HMAC,
+];
+const DEFAULT_SIGNATURE_HEADER: &str = "X-Signature";
+const TIMESTAMP_HEADER: &str = "X-Timestamp";
+const REDACTED: &str = "[redacted]";
+
+/// Checks a profile before it is saved and normalizes its host patterns.
+pub fn normalize_web_auth_profile_input(
+    mut input: SaveWebAuthProfileInput,
+) -> Result<SaveWebAuthProfileInput, String> {
+    input.name = input.name.trim().to_string();
+    if input.name.is_empty() {
+        return Err("Profile name is required".to_string());
+    }
+    if !AUTH_KINDS.contains(&input.kind.as_str()) {
+        return Err(format!(
+            "Unknown auth kind '{}'. Use one of: {}",
+            input.kind,
+            AUTH_KINDS.join(", ")
+        ));
+    }
+    input.host_patterns = input
+        .host_patterns
+        .iter()
+        .filter(|pattern| !pattern.trim().is_empty())
+        .map(|pattern| normalize_host_pattern(pattern).map_err(|err| err.message))
+        .collect::<Result<Vec<_>, _>>()?;
+    if input.host_patterns.is_empty() {
+        return Err("At least one host pattern is required".to_string());
+    }
+    let trimmed = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    input.header_name = trimmed(input.header_name);
+    input.username = trimmed(input.username);
+    input.secret = input.secret.filter(|secret| !secret.is_empty());
+    if let Some(header) = input.header_name.as_deref() {
+        HeaderName::from_bytes(header.as_bytes())
+            .map_err(|_| format!("Invalid header name '{header}'"))?;
+    }
+    if input.kind == WEB_AUTH_API_KEY && input.header_name.is_none() {
+        return Err("API key profiles need a header name".to_string());
+    }
+    if input.kind == WEB_AUTH_BASIC && input.username.is_none() {
+        return Err("Basic auth profiles need a username".to_string());
+    }
+    if input.id.is_none() && input.secret.is_none() {
+        return Err("A secret is required for new profiles".to_string());
+    }
+    Ok(input)
+}
+
+/// Loads the profile `name` and checks that it may be sent to `url`.
+pub(super) fn load_auth_profile(
+    db: &Db,
+    name: &str,
+    url: &Url,
+) -> Result<WebAuthProfile, ToolError> {
+    let profile = db
+        .get_web_auth_profile_by_name(name)
+        .map_err(|err| ToolError::new(format!("Failed to load auth profile: {err}")))?;
+    let Some(profile) = profile else {
+        let names = db
+            .get_web_auth_profiles()
+            .map(|profiles| {
+                profiles
+                    .into_iter()
+                    .map(|profile| profile.name)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let hint = if names.is_empty() {
+            "No auth profiles are configured; add one in Settings".to_string()
+        } else {
+            format!("Available profiles: {}", names.join(", "))
+        };
+        return Err(ToolError::not_found(format!("Unknown auth profile '{name}'")).with_hint(hint));
+    };
+
+    let host = url
+        .host_str()
+        .map(|host| host.trim_end_matches('.').to_ascii_lowercase())
+        .unwrap_or_default();
+    if !profile
+        .host_patterns
+        .iter()
+        .any(|pattern| host_matches(pattern, &host))
+    {
+        return Err(ToolError::permission(format!(
+            "Auth profile '{}' is not allowed for host '{host}'",
+            profile.name
+        ))
+        .with_hint(format!(
+            "The profile covers: {}",
+            profile.host_patterns.join(", ")
+        )));
+    }
+    Ok(profile)
+}
+
+/// Headers carrying the profile's credentials for one request. `body` must be the exact bytes
+/// sent, since `hmac` profiles sign them.
+pub(super) fn auth_headers(
+    profile: &WebAuthProfile,
+    method: &Method,
+    url: &Url,
+    body: &[u8],
+) -> Result<HeaderMap, ToolError> {
+    let mut headers = HeaderMap::new();
+    match profile.kind.as_str() {
+        WEB_AUTH_BEARER => {
+            headers.insert(
+                AUTHORIZATION,
+                header_value(&format!("Bearer {}", profile.secret))?,
+            );
+        }
+        WEB_AUTH_BASIC => {
+            headers.insert(
+                AUTHORIZATION,
+                header_value(&format!("Basic {}", basic_token(profile)))?,
+            );
+        }
+        WEB_AUTH_API_KEY => {
+            headers.insert(
+                header_name(profile.header_name.as_deref())?,
+                header_value(&profile.secret)?,
+            );
+        }
+        WEB_AUTH_HMAC => {
+            let timestamp = chrono::Utc::now().timestamp().to_string();
+            let signature = hmac_signature(&profile.secret, &timestamp, method, url, body)?;
+            let name = profile
+                .header_name
+                .as_deref()
+                .unwrap_or(DEFAULT_SIGNATURE_HEADER);
+            headers.insert(header_name(Some(name))?, header_value(&signature)?);
+            headers.insert(
+                HeaderName::from_static("x-timestamp"),
+                header_value(&timestamp)?,
+            );
+        }
+        other => {
+            return Err(ToolError::new(format!(
+                "Unsupported auth profile kind '{other}'"
+            )));
+        }
+    }
+    Ok(headers)
+}
+
+/// Names of the headers `auth_headers` sets, for previews.
+pub(super) fn auth_header_names(profile: &WebAuthProfile) -> Vec<String> {
+    match profile.kind.as_str() {
+        WEB_AUTH_API_KEY => profile.header_name.iter().cloned().collect(),
+        WEB_AUTH_HMAC => vec![
+            profile
+                .header_name
+                .clone()
+                .unwrap_or_else(|| DEFAULT_SIGNATURE_HEADER.to_string()),
+            TIMESTAMP_HEADER.to_string(),
+        ],
+        _ => vec!["Authorization".to_string()],
+    }
+}
+
+/// Strings that must never appear in a tool result: the secret and its encodings.
+pub(super) fn profile_secrets(profile: &WebAuthProfile) -> Vec<String> {
+    let mut secrets = vec![profile.secret.clone()];
+    if profile.kind == WEB_AUTH_BASIC {
+        secrets.push(basic_token(profile));
+    }
+    secrets.retain(|secret| !secret.is_empty());
+    secrets
+}
+
+/// Replaces every occurrence of `secrets` in the strings and keys of `value`.
+pub(super) fn redact_secrets(value: &Value, secrets: &[String]) -> Value {
+    let scrub = |text: &str| {
+        secrets.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret.as_str(), REDACTED)
+        })
+    };
+    match value {
+        Value::String(text) => Value::String(scrub(text)),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| redact_secrets(item, secrets))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (scrub(key), redact_secrets(item, secrets)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+pub(super) fn register_auth_profiles_tool(
+    registry: &mut ToolRegistry,
+    db: Db,
+) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: "web.list_auth_profiles".to_string(),
+        description: "List the credential profiles web.request can use via 'auth_profile'. Secrets are never returned.".to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {},
+            "additionalProperties": false
+        }),
+        result_schema: json!({
+            "type": "object",
+            "properties": {
+                "profiles": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "kind": { "type": "string" },
+                            "host_patterns": { "type": "array", "items": { "type": "string" } }
+                        },
+                        "required": ["name", "kind", "host_patterns"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["profiles"],
+            "additionalProperties": false
+        }),
+        requires_approval: false,
+        result_mode: ToolResultMode::Inline,
+    };
+
+    let handler = Arc::new(move |_args: Value, _ctx: ToolExecutionContext| {
+        let profiles = db
+            .get_web_auth_profiles()
+            .map_err(|err| ToolError::new(format!("Failed to load auth profiles: {err}")))?;
+        let profiles = profiles
+            .into_iter()
+            .map(|profile| {
+                json!({
+                    "name": profile.name,
+                    "kind": profile.kind,
+                    "host_patterns": profile.host_patterns
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({ "profiles": profiles }))
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: None,
+    })
+}
+
+fn basic_token(profile: &WebAuthProfile) -> String {
+    let username = profile.username.as_deref().unwrap_or("");
+    base64::engine::general_purpose::STANDARD.encode(format!("{username}:{}", profile.secret))
+}
+
+/// Hex HMAC-SHA256 over `timestamp \n METHOD \n path?query \n hex(sha256(body))`.
+fn hmac_signature(
+    secret: &str,
+    timestamp: &str,
+    method: &Method,
+    url: &Url,
+    body: &[u8],
+) -> Result<String, ToolError> {
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+    let body_hash = to_hex(&Sha256::digest(body));
+    let payload = format!("{timestamp}\n{}\n{target}\n{body_hash}", method.as_str());
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|err| ToolError::new(format!("Invalid HMAC key: {err}")))?;
+    mac.update(payload.as_bytes());
+    Ok(to_hex(&mac.finalize().into_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn header_name(name: Option<&str>) -> Result<HeaderName, ToolError> {
+    let name = name
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| ToolError::new("Auth profile is missing a header name"))?;
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| ToolError::new(format!("Invalid header name '{name}' in auth profile")))
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, ToolError> {
+    let mut value = HeaderValue::from_str(value)
+        .map_err(|_| ToolError::new("Auth profile secret is not a valid header value"))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(kind: &str) -> WebAuthProfile {
+        WebAuthProfile {
+            id: "p1".to_string(),
+            name: "api".to_string(),
+            kind: kind.to_string(),
+            host_patterns: vec!["*.example.com".to_string()],
+            header_name: None,
+            username: None,
+            secret: "s3cret".to_string(),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn header(headers: &HeaderMap, name: &str) -> String {
+        headers.get(name).unwrap().to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn computes_headers_for_each_kind() {
+        let url = Url::parse("https://api.example.com/v1/items?page=2").unwrap();
+
+        let headers = auth_headers(&profile(WEB_AUTH_BEARER), &Method::GET, &url, b"").unwrap();
+        assert_eq!(header(&headers, "authorization"), "Bearer s3cret");
+
+        let basic = WebAuthProfile {
+            username: Some("bob".to_string()),
+            ..profile(WEB_AUTH_BASIC)
+        };
+        let headers = auth_headers(&basic, &Method::GET, &url, b"").unwrap();
+        assert_eq!(header(&headers, "authorization"), "Basic Ym9iOnMzY3JldA==");
+
+        let api_key = WebAuthProfile {
+            header_name: Some("X-Api-Key".to_string()),
+            ..profile(WEB_AUTH_API_KEY)
+        };
+        let headers = auth_headers(&api_key, &Method::GET, &url, b"").unwrap();
+        assert_eq!(header(&headers, "x-api-key"), "s3cret");
+
+        let headers = auth_headers(&profile(WEB_AUTH_HMAC), &Method::POST, &url, b"{}").unwrap();
+        let timestamp = header(&headers, "x-timestamp");
+        let expected = hmac_signature("s3cret", &timestamp, &Method::POST, &url, b"{}").unwrap();
+        assert_eq!(header(&headers, "x-signature"), expected);
+        assert_eq!(expected.len(), 64);
+        assert_ne!(
+            expected,
+            hmac_signature("s3cret", &timestamp, &Method::POST, &url, b"{ }").unwrap()
+        );
+    }
+
+    #[test]
+    fn redacts_secrets_and_validates_input() {
+        let basic = WebAuthProfile {
+            username: Some("bob".to_string()),
+            ..profile(WEB_AUTH_BASIC)
+        };
+        let value = json!({
+            "text": "echo: Basic Ym9iOnMzY3JldA== and s3cret",
+            "headers": { "s3cret": ["s3cret", 1] }
+        });
+        let redacted = redact_secrets(&value, &profile_secrets(&basic));
+        assert_eq!(
+            redacted,
+            json!({
+                "text": "echo: Basic [redacted] and [redacted]",
+                "headers": { "[redacted]": ["[redacted]", 1] }
+            })
+        );
+
+        let input = SaveWebAuthProfileInput {
+            id: None,
+            name: " api ".to_string(),
+            kind: WEB_AUTH_API_KEY.to_string(),
+            host_patterns: vec!["https://API.Example.com/x".to_string()],
+            header_name: None,
+            username: None,
+            secret: Some("key".to_string()),
+        };
+        assert!(normalize_web_auth_profile_input(input.clone()).is_err());
+        let normalized = normalize_web_auth_profile_input(SaveWebAuthProfileInput {
+            header_name: Some("X-Api-Key".to_string()),
+            ..input.clone()
+        })
+        .unwrap();
+        assert_eq!(normalized.name, "api");
+        assert_eq!(normalized.host_patterns, vec!["api.example.com"]);
+        let update = SaveWebAuthProfileInput {
+            header_name: Some("X-Api-Key".to_string()),
+            secret: None,
+            ..input
+        };
+        assert!(normalize_web_auth_profile_input(update.clone()).is_err());
+        assert!(normalize_web_auth_profile_input(SaveWebAuthProfileInput {
+            id: Some("p1".to_string()),
+            ..update
+        })
+        .is_ok());
+    }
+}
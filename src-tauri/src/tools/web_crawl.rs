@@ -0,0 +1,937 @@
+//! `web.crawl`: a bounded breadth-first crawl of one host that snapshots each HTML page into
+//! the vault as Markdown. Crawls run on a background thread so they outlive the tool-call
+//! timeout; `web.crawl_status` reports progress and the manifest, `web.crawl_cancel` stops
+//! one early.
+
+use super::approval_rules::glob_matches;
+use super::readability::extract_readable;
+use super::web::{
+    build_client, build_clip_note, extract_links_from_document, extract_title, is_html_content,
+    normalize_host, parse_url, read_limited_body, require_string_arg, sanitize_segment,
+    DEFAULT_MAX_BYTES, DEFAULT_TIMEOUT_MS,
+};
+use super::web_allowlist::{ensure_url_allowed, load_web_access_rules};
+use super::web_policy::{PolicyRequest, WebPolicy};
+use crate::db::Db;
+use crate::tools::vault::resolve_vault_path;
+use crate::tools::{
+    CancellationToken, ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolProgress,
+    ToolRegistry, ToolResultMode,
+};
+use regex::Regex;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use scraper::Html;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use url::Url;
+use uuid::Uuid;
+
+const WEB_CRAWLS_DIR: &str = "Web Crawls";
+const DEFAULT_MAX_DEPTH: u64 = 2;
+const MAX_DEPTH: u64 = 5;
+const DEFAULT_MAX_PAGES: u64 = 25;
+const MAX_PAGES: u64 = 200;
+/// Finished jobs kept for `web.crawl_status`; older ones are dropped first.
+const MAX_FINISHED_JOBS: usize = 20;
+const PAGE_EXTENSIONS: [&str; 6] = ["html", "htm", "xhtml", "php", "asp", "aspx"];
+
+pub const CRAWL_RUNNING: &str = "running";
+pub const CRAWL_COMPLETED: &str = "completed";
+pub const CRAWL_CANCELLED: &str = "cancelled";
+pub const CRAWL_FAILED: &str = "failed";
+
+/// One page written to the vault.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrawledPage {
+    pub url: String,
+    pub path: String,
+    pub title: String,
+    pub depth: u64,
+    pub bytes: u64,
+}
+
+/// A URL that was reached but not saved, with the reason.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedPage {
+    pub url: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebCrawlJob {
+    pub id: String,
+    pub start_url: String,
+    pub folder: String,
+    /// `running`, `completed`, `cancelled` or `failed`.
+    pub status: String,
+    pub max_depth: u64,
+    pub max_pages: u64,
+    /// URLs discovered but not fetched yet.
+    pub queued: u64,
+    pub pages: Vec<CrawledPage>,
+    pub skipped: Vec<SkippedPage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix milliseconds.
+    pub started_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+    #[serde(skip)]
+    cancellation: CancellationToken,
+}
+
+struct CrawlConfig {
+    start: Url,
+    host: String,
+    folder: String,
+    max_depth: u64,
+    max_pages: u64,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    max_bytes: usize,
+    timeout_ms: u64,
+    use_cache: bool,
+    policy: WebPolicy,
+}
+
+static JOBS: OnceLock<Mutex<HashMap<String, WebCrawlJob>>> = OnceLock::new();
+
+fn jobs() -> &'static Mutex<HashMap<String, WebCrawlJob>> {
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn update_job(id: &str, update: impl FnOnce(&mut WebCrawlJob)) {
+    if let Some(job) = jobs().lock().unwrap().get_mut(id) {
+        update(job);
+    }
+}
+
+/// Running jobs first, then the most recent.
+pub fn list_web_crawl_jobs() -> Vec<WebCrawlJob> {
+    let mut list = jobs().lock().unwrap().values().cloned().collect::<Vec<_>>();
+    list.sort_by_key(|job| (job.status != CRAWL_RUNNING, -job.started_at));
+    list
+}
+
+pub fn get_web_crawl_job(id: &str) -> Option<WebCrawlJob> {
+    jobs().lock().unwrap().get(id).cloned()
+}
+
+/// Asks a running crawl to stop after the page in flight. Returns false for unknown jobs.
+pub fn cancel_web_crawl_job(id: &str) -> bool {
+    match jobs().lock().unwrap().get(id) {
+        Some(job) => {
+            job.cancellation.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+pub(super) fn register_web_crawl_tools(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    register_crawl_tool(registry, db)?;
+    register_status_tool(registry)?;
+    register_cancel_tool(registry)?;
+    Ok(())
+}
+
+fn job_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "id": { "type": "string" },
+            "start_url": { "type": "string" },
+            "folder": { "type": "string" },
+            "status": { "type": "string", "enum": [CRAWL_RUNNING, CRAWL_COMPLETED, CRAWL_CANCELLED, CRAWL_FAILED] },
+            "max_depth": { "type": "integer" },
+            "max_pages": { "type": "integer" },
+            "queued": { "type": "integer" },
+            "pages": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "url": { "type": "string" },
+                        "path": { "type": "string" },
+                        "title": { "type": "string" },
+                        "depth": { "type": "integer" },
+                        "bytes": { "type": "integer" }
+                    },
+                    "required": ["url", "path", "title", "depth", "bytes"],
+                    "additionalProperties": false
+                }
+            },
+            "skipped": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "url": { "type": "string" },
+                        "reason": { "type": "string" }
+                    },
+                    "required": ["url", "reason"],
+                    "additionalProperties": false
+                }
+            },
+            "error": { "type": "string" },
+            "started_at": { "type": "integer" },
+            "finished_at": { "type": "integer" }
+        },
+        "required": ["id", "start_url", "folder", "status", "max_depth", "max_pages", "queued", "pages", "skipped", "started_at"],
+        "additionalProperties": false
+    })
+}
+
+/// Checks the crawl arguments against the web access rules and the vault, for both the
+/// approval preview and the crawl itself.
+fn crawl_config(db: &Db, args: &Value) -> Result<CrawlConfig, ToolError> {
+    let start = parse_url(&require_string_arg(args, "url")?)?;
+    let host = normalize_host(
+        start
+            .host_str()
+            .ok_or_else(|| ToolError::new("URL missing host"))?,
+    )?;
+    let rules = load_web_access_rules(db)?;
+    ensure_url_allowed(&rules, &start, &Method::GET)?;
+
+    let folder = match args
+        .get("vault_folder")
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().trim_matches('/'))
+        .filter(|v| !v.is_empty())
+    {
+        Some(folder) => folder.to_string(),
+        None => format!("{WEB_CRAWLS_DIR}/{}", sanitize_segment(&host)),
+    };
+    let folder = resolve_vault_path(db, &folder)?.display_path;
+    let patterns = |key: &str| {
+        args.get(key)
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+    Ok(CrawlConfig {
+        host,
+        folder,
+        max_depth: args
+            .get("max_depth")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MAX_DEPTH)
+            .min(MAX_DEPTH),
+        max_pages: args
+            .get("max_pages")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MAX_PAGES)
+            .clamp(1, MAX_PAGES),
+        include: patterns("include"),
+        exclude: patterns("exclude"),
+        max_bytes: args
+            .get("max_bytes_per_page")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MAX_BYTES as u64) as usize,
+        timeout_ms: args
+            .get("timeout_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_TIMEOUT_MS),
+        use_cache: args.get("cache").and_then(|v| v.as_bool()).unwrap_or(true),
+        policy: WebPolicy::load(db)?
+            .with_user_agent(args.get("user_agent").and_then(|v| v.as_str())),
+        start,
+    })
+}
+
+fn register_crawl_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: "web.crawl".to_string(),
+        description: "Crawl an approved site in the background, saving each HTML page as a Markdown note with links rewritten between the saved notes. Existing notes are never overwritten; a taken path gets a numeric suffix. Stays on the start URL's host. Returns a job id; poll web.crawl_status for progress and the manifest.".to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string" },
+                "vault_folder": { "type": "string" },
+                "max_depth": { "type": "integer", "minimum": 0, "maximum": MAX_DEPTH },
+                "max_pages": { "type": "integer", "minimum": 1, "maximum": MAX_PAGES },
+                "include": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "URL path globs to follow, like '/docs/**'"
+                },
+                "exclude": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "URL path globs never to follow; wins over include"
+                },
+                "max_bytes_per_page": { "type": "integer", "minimum": 1 },
+                "timeout_ms": { "type": "integer", "minimum": 1 },
+                "user_agent": { "type": "string" },
+                "cache": { "type": "boolean" }
+            },
+            "required": ["url"],
+            "additionalProperties": false
+        }),
+        result_schema: json!({
+            "type": "object",
+            "properties": {
+                "job_id": { "type": "string" },
+                "status": { "type": "string" },
+                "url": { "type": "string" },
+                "folder": { "type": "string" },
+                "max_depth": { "type": "integer" },
+                "max_pages": { "type": "integer" }
+            },
+            "required": ["job_id", "status", "url", "folder", "max_depth", "max_pages"],
+            "additionalProperties": false
+        }),
+        requires_approval: true,
+        result_mode: ToolResultMode::Inline,
+    };
+
+    let preview_db = db.clone();
+    let handler = Arc::new(move |args: Value, ctx: ToolExecutionContext| {
+        let config = crawl_config(&db, &args)?;
+
+        let job = WebCrawlJob {
+            id: Uuid::new_v4().to_string(),
+            start_url: config.start.to_string(),
+            folder: config.folder.clone(),
+            status: CRAWL_RUNNING.to_string(),
+            max_depth: config.max_depth,
+            max_pages: config.max_pages,
+            queued: 1,
+            pages: Vec::new(),
+            skipped: Vec::new(),
+            error: None,
+            started_at: chrono::Utc::now().timestamp_millis(),
+            finished_at: None,
+            cancellation: CancellationToken::new(),
+        };
+        let result = json!({
+            "job_id": job.id,
+            "status": job.status,
+            "url": job.start_url,
+            "folder": job.folder,
+            "max_depth": job.max_depth,
+            "max_pages": job.max_pages
+        });
+        let job_id = job.id.clone();
+        // The crawl gets its own token: it outlives this call, so the run's cancellation and
+        // the tool timeout must not stop it. `web.crawl_cancel` trips it instead.
+        let cancellation = job.cancellation.clone();
+        {
+            let mut jobs = jobs().lock().unwrap();
+            prune_finished_jobs(&mut jobs);
+            jobs.insert(job_id.clone(), job);
+        }
+
+        let db = db.clone();
+        std::thread::spawn(move || {
+            let outcome = run_crawl(&db, &job_id, &config, &cancellation, &ctx);
+            let (status, error) = match outcome {
+                Ok(()) if cancellation.is_cancelled() => (CRAWL_CANCELLED, None),
+                Ok(()) => (CRAWL_COMPLETED, None),
+                Err(err) => (CRAWL_FAILED, Some(err.message)),
+            };
+            let mut pages = 0;
+            update_job(&job_id, |job| {
+                job.status = status.to_string();
+                job.error = error;
+                job.queued = 0;
+                job.finished_at = Some(chrono::Utc::now().timestamp_millis());
+                pages = job.pages.len() as u64;
+            });
+            ctx.report(ToolProgress {
+                message: format!("Crawl {status}: {pages} pages saved"),
+                current: Some(pages),
+                total: Some(config.max_pages),
+                data: Some(json!({ "job_id": job_id, "status": status })),
+            });
+        });
+
+        Ok(result)
+    });
+
+    let preview = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+        let config = crawl_config(&preview_db, &args)?;
+        Ok(json!({
+            "url": config.start.to_string(),
+            "folder": config.folder,
+            "max_depth": config.max_depth,
+            "max_pages": config.max_pages,
+            "include": config.include,
+            "exclude": config.exclude
+        }))
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: Some(preview),
+    })
+}
+
+fn register_status_tool(registry: &mut ToolRegistry) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: "web.crawl_status".to_string(),
+        description: "Get the progress of a web.crawl job, with the manifest of saved pages and skipped URLs.".to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {
+                "job_id": { "type": "string" }
+            },
+            "required": ["job_id"],
+            "additionalProperties": false
+        }),
+        result_schema: job_schema(),
+        requires_approval: false,
+        result_mode: ToolResultMode::Auto,
+    };
+
+    let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+        let job_id = require_string_arg(&args, "job_id")?;
+        let job = get_web_crawl_job(&job_id).ok_or_else(|| {
+            ToolError::not_found(format!("Unknown crawl job '{job_id}'"))
+                .with_hint("Crawl jobs are kept in memory until the app restarts.")
+        })?;
+        Ok(json!(job))
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: None,
+    })
+}
+
+fn register_cancel_tool(registry: &mut ToolRegistry) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: "web.crawl_cancel".to_string(),
+        description: "Stop a running web.crawl job. Pages saved so far are kept.".to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {
+                "job_id": { "type": "string" }
+            },
+            "required": ["job_id"],
+            "additionalProperties": false
+        }),
+        result_schema: json!({
+            "type": "object",
+            "properties": {
+                "job_id": { "type": "string" },
+                "status": { "type": "string" }
+            },
+            "required": ["job_id", "status"],
+            "additionalProperties": false
+        }),
+        requires_approval: false,
+        result_mode: ToolResultMode::Inline,
+    };
+
+    let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+        let job_id = require_string_arg(&args, "job_id")?;
+        if !cancel_web_crawl_job(&job_id) {
+            return Err(ToolError::not_found(format!(
+                "Unknown crawl job '{job_id}'"
+            )));
+        }
+        let status = get_web_crawl_job(&job_id)
+            .map(|job| job.status)
+            .unwrap_or_default();
+        Ok(json!({ "job_id": job_id, "status": status }))
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: None,
+    })
+}
+
+fn prune_finished_jobs(jobs: &mut HashMap<String, WebCrawlJob>) {
+    let mut finished = jobs
+        .values()
+        .filter(|job| job.status != CRAWL_RUNNING)
+        .map(|job| (job.started_at, job.id.clone()))
+        .collect::<Vec<_>>();
+    if finished.len() < MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    for (_, id) in finished.iter().take(finished.len() + 1 - MAX_FINISHED_JOBS) {
+        jobs.remove(id);
+    }
+}
+
+/// Fetches pages breadth-first until the queue, the depth limit or the page limit runs out,
+/// then rewrites links between the saved notes.
+fn run_crawl(
+    db: &Db,
+    job_id: &str,
+    config: &CrawlConfig,
+    cancellation: &CancellationToken,
+    ctx: &ToolExecutionContext,
+) -> Result<(), ToolError> {
+    let rules = load_web_access_rules(db)?;
+    let client = build_client(
+        config.timeout_ms,
+        &config.policy.user_agent,
+        &rules,
+        &Method::GET,
+        Some(&config.host),
+        true,
+    )?;
+
+    let mut queue = VecDeque::from([(config.start.clone(), 0)]);
+    let mut seen = HashSet::from([crawl_key(&config.start)]);
+    // Crawl key of every saved URL (requested and final) -> note path inside the folder.
+    let mut saved: HashMap<String, String> = HashMap::new();
+    let mut used_paths = HashSet::new();
+    let mut notes: Vec<(String, String)> = Vec::new();
+    let skip = |url: &Url, reason: String| {
+        update_job(job_id, |job| {
+            job.skipped.push(SkippedPage {
+                url: url.to_string(),
+                reason,
+            })
+        });
+    };
+
+    while let Some((url, depth)) = queue.pop_front() {
+        if cancellation.is_cancelled() || notes.len() as u64 >= config.max_pages {
+            break;
+        }
+        update_job(job_id, |job| job.queued = queue.len() as u64 + 1);
+        if let Err(err) = ensure_url_allowed(&rules, &url, &Method::GET) {
+            skip(&url, err.message);
+            continue;
+        }
+
+        let response = match config.policy.send(
+            &client,
+            PolicyRequest {
+                method: Method::GET,
+                url: &url,
+                use_cache: config.use_cache,
+                max_bytes: config.max_bytes,
+            },
+            |request| request,
+            |response| read_limited_body(response, config.max_bytes),
+        ) {
+            Ok(response) => response,
+            Err(err) => {
+                skip(&url, err.message);
+                continue;
+            }
+        };
+        if !response.status.is_success() {
+            skip(&url, format!("HTTP {}", response.status.as_u16()));
+            continue;
+        }
+        let final_host = response.url.host_str().map(normalize_host).transpose()?;
+        if final_host.as_deref() != Some(config.host.as_str()) {
+            skip(&url, "Redirected to a different host".to_string());
+            continue;
+        }
+        let final_key = crawl_key(&response.url);
+        if final_key != crawl_key(&url) && !seen.insert(final_key.clone()) {
+            skip(&url, format!("Redirected to {final_key}, already crawled"));
+            continue;
+        }
+
+        let content_type = response
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let body = String::from_utf8_lossy(&response.body).to_string();
+        if !is_html_content(&content_type, &body) {
+            skip(&url, format!("Not an HTML page ({content_type})"));
+            continue;
+        }
+
+        let document = Html::parse_document(&body);
+        let page = extract_readable(&document, &response.url);
+        let title = page
+            .metadata
+            .title
+            .clone()
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| extract_title(&document));
+        let note = build_clip_note(
+            &title,
+            response.url.as_str(),
+            &page.metadata,
+            &page.markdown,
+        );
+        let path = create_note(
+            db,
+            &config.folder,
+            &page_note_path(&response.url),
+            &note,
+            &mut used_paths,
+        )?;
+
+        saved.insert(crawl_key(&url), path.clone());
+        saved.insert(final_key, path.clone());
+        notes.push((path.clone(), note.clone()));
+        let display_path = format!("{}/{path}", config.folder);
+        update_job(job_id, |job| {
+            job.pages.push(CrawledPage {
+                url: response.url.to_string(),
+                path: display_path.clone(),
+                title,
+                depth,
+                bytes: note.len() as u64,
+            })
+        });
+        ctx.report(ToolProgress {
+            message: format!("Saved {display_path}"),
+            current: Some(notes.len() as u64),
+            total: Some(config.max_pages),
+            data: Some(
+                json!({ "job_id": job_id, "url": response.url.to_string(), "path": display_path }),
+            ),
+        });
+
+        if depth >= config.max_depth {
+            continue;
+        }
+        for link in extract_links_from_document(&document, &response.url, true, usize::MAX) {
+            let Ok(link) = Url::parse(&link) else {
+                continue;
+            };
+            if !path_selected(link.path(), &config.include, &config.exclude) {
+                continue;
+            }
+            if seen.insert(crawl_key(&link)) {
+                queue.push_back((link, depth + 1));
+            }
+        }
+    }
+
+    for (path, note) in &notes {
+        let rewritten = rewrite_links(note, path, &saved);
+        if &rewritten != note {
+            write_note(db, &config.folder, path, &rewritten)?;
+        }
+    }
+    Ok(())
+}
+
+/// Saves a page's first version at `path`, or at `-2`, `-3`… when this crawl already used
+/// that path or a file exists there. Notes already in the vault are never overwritten.
+/// Returns the path the note was saved at.
+fn create_note(
+    db: &Db,
+    folder: &str,
+    path: &str,
+    note: &str,
+    used: &mut HashSet<String>,
+) -> Result<String, ToolError> {
+    loop {
+        let candidate = unique_note_path(path, used);
+        let resolved = resolve_vault_path(db, &format!("{folder}/{candidate}"))?;
+        if let Some(parent) = resolved.full_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| ToolError::new(format!("Failed to create directories: {err}")))?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&resolved.full_path);
+        match file {
+            Ok(mut file) => {
+                file.write_all(note.as_bytes())
+                    .map_err(|err| ToolError::new(format!("Failed to write note: {err}")))?;
+                return Ok(candidate);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(ToolError::new(format!("Failed to write note: {err}"))),
+        }
+    }
+}
+
+/// Replaces a note this crawl created.
+fn write_note(db: &Db, folder: &str, path: &str, note: &str) -> Result<(), ToolError> {
+    let resolved = resolve_vault_path(db, &format!("{folder}/{path}"))?;
+    if let Some(parent) = resolved.full_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|err| ToolError::new(format!("Failed to create directories: {err}")))?;
+    }
+    std::fs::write(&resolved.full_path, note)
+        .map_err(|err| ToolError::new(format!("Failed to write note: {err}")))
+}
+
+/// The URL without its fragment, which identifies a page for deduplication.
+fn crawl_key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// Empty `include` follows every path; `exclude` wins over `include`.
+fn path_selected(path: &str, include: &[String], exclude: &[String]) -> bool {
+    if exclude.iter().any(|pattern| glob_matches(pattern, path)) {
+        return false;
+    }
+    include.is_empty() || include.iter().any(|pattern| glob_matches(pattern, path))
+}
+
+/// Note path inside the crawl folder mirroring the URL path: `/docs/` becomes
+/// `docs/index.md` and `/docs/intro.html` becomes `docs/intro.md`.
+fn page_note_path(url: &Url) -> String {
+    let mut segments = url
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|segment| !segment.is_empty())
+                .map(sanitize_segment)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if url.path().ends_with('/') || segments.is_empty() {
+        segments.push("index".to_string());
+    }
+    let last = segments.pop().unwrap_or_default();
+    let mut stem = match last.rsplit_once('.') {
+        Some((stem, ext)) if PAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()) => {
+            stem.to_string()
+        }
+        _ => last,
+    };
+    if let Some(query) = url.query().filter(|query| !query.is_empty()) {
+        let query = sanitize_segment(query);
+        stem = format!("{stem}_{}", query.chars().take(60).collect::<String>());
+    }
+    segments.push(stem);
+    // Dot-only segments would escape the folder or hide the file.
+    let segments = segments
+        .into_iter()
+        .map(|segment| {
+            if segment.trim_matches('.').is_empty() {
+                "_".to_string()
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("{}.md", segments.join("/"))
+}
+
+fn unique_note_path(path: &str, used: &mut HashSet<String>) -> String {
+    let mut candidate = path.to_string();
+    let stem = path.trim_end_matches(".md");
+    let mut counter = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{stem}-{counter}.md");
+        counter += 1;
+    }
+    candidate
+}
+
+/// Points link reference definitions at saved pages to the local notes, relative to `from`.
+fn rewrite_links(note: &str, from: &str, saved: &HashMap<String, String>) -> String {
+    let reference = Regex::new(r"^\[(\d+)\]: (\S+)$").expect("valid regex");
+    let mut out = note
+        .lines()
+        .map(|line| {
+            let Some(captures) = reference.captures(line) else {
+                return line.to_string();
+            };
+            let Ok(url) = Url::parse(&captures[2]) else {
+                return line.to_string();
+            };
+            match saved.get(&crawl_key(&url)) {
+                Some(target) => {
+                    let mut link = relative_path(from, target);
+                    if let Some(fragment) = url.fragment() {
+                        link.push('#');
+                        link.push_str(fragment);
+                    }
+                    format!("[{}]: {link}", &captures[1])
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if note.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// Path to `to` from the directory containing `from`; both are relative to the same folder.
+fn relative_path(from: &str, to: &str) -> String {
+    let from_dirs = from.split('/').collect::<Vec<_>>();
+    let from_dirs = &from_dirs[..from_dirs.len() - 1];
+    let to_parts = to.split('/').collect::<Vec<_>>();
+    let common = from_dirs
+        .iter()
+        .zip(&to_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts = vec![".."; from_dirs.len() - common];
+    parts.extend(&to_parts[common..]);
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::PreferenceOperations;
+    use crate::tools::{upsert_web_access_rule, WebAccessRuleInput};
+
+    fn url(input: &str) -> Url {
+        Url::parse(input).unwrap()
+    }
+
+    #[test]
+    fn maps_urls_to_note_paths() {
+        assert_eq!(page_note_path(&url("https://docs.rs/")), "index.md");
+        assert_eq!(
+            page_note_path(&url("https://docs.rs/guide/")),
+            "guide/index.md"
+        );
+        assert_eq!(
+            page_note_path(&url("https://docs.rs/guide/intro.html#setup")),
+            "guide/intro.md"
+        );
+        assert_eq!(page_note_path(&url("https://docs.rs/v1.2")), "v1.2.md");
+        assert_eq!(
+            page_note_path(&url("https://docs.rs/search?q=serde json")),
+            "search_q_serde_20json.md"
+        );
+
+        let mut used = HashSet::new();
+        assert_eq!(unique_note_path("a/index.md", &mut used), "a/index.md");
+        assert_eq!(unique_note_path("a/index.md", &mut used), "a/index-2.md");
+
+        assert!(path_selected("/docs/a/b", &["/docs/**".to_string()], &[]));
+        assert!(!path_selected("/blog/a", &["/docs/**".to_string()], &[]));
+        assert!(!path_selected(
+            "/docs/old/a",
+            &[],
+            &["/docs/old/**".to_string()]
+        ));
+    }
+
+    #[test]
+    fn crawled_pages_never_overwrite_existing_notes() {
+        let vault = std::env::temp_dir().join(format!("crawl-vault-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(vault.join("Notes")).unwrap();
+        std::fs::write(vault.join("Notes/index.md"), "my notes").unwrap();
+        let db_path = std::env::temp_dir().join(format!("crawl-{}.db", Uuid::new_v4()));
+        let mut db = Db::new(db_path.to_str().unwrap()).unwrap();
+        db.run_migrations().unwrap();
+        db.set_preference("plugins.files.vault_root", vault.to_str().unwrap())
+            .unwrap();
+
+        let mut used = HashSet::new();
+        let first = create_note(&db, "Notes", "index.md", "page one", &mut used).unwrap();
+        let second = create_note(&db, "Notes", "index.md", "page two", &mut used).unwrap();
+        assert_eq!(first, "index-2.md");
+        assert_eq!(second, "index-3.md");
+        write_note(&db, "Notes", &first, "page one, linked").unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(vault.join("Notes").join(name)).unwrap();
+        assert_eq!(read("index.md"), "my notes");
+        assert_eq!(read("index-2.md"), "page one, linked");
+        assert_eq!(read("index-3.md"), "page two");
+        let _ = std::fs::remove_dir_all(vault);
+    }
+
+    #[test]
+    fn crawls_require_approval_and_preview_their_limits() {
+        let vault = std::env::temp_dir().join(format!("crawl-vault-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&vault).unwrap();
+        let db_path = std::env::temp_dir().join(format!("crawl-{}.db", Uuid::new_v4()));
+        let mut db = Db::new(db_path.to_str().unwrap()).unwrap();
+        db.run_migrations().unwrap();
+        db.set_preference("plugins.files.vault_root", vault.to_str().unwrap())
+            .unwrap();
+        upsert_web_access_rule(
+            &db,
+            WebAccessRuleInput {
+                id: None,
+                host: "docs.example.com".to_string(),
+                path_prefix: None,
+                methods: Vec::new(),
+                allow_private: false,
+                deny: false,
+                expires_at: None,
+                note: None,
+            },
+        )
+        .unwrap();
+        let mut registry = ToolRegistry::new();
+        register_web_crawl_tools(&mut registry, db).unwrap();
+
+        let tool = registry.get("web.crawl").expect("missing tool");
+        assert!(tool.metadata.requires_approval);
+        let preview = tool.preview.as_ref().expect("web.crawl has a preview");
+        let shown = preview(
+            json!({
+                "url": "https://docs.example.com/guide/",
+                "vault_folder": "Docs/Guide",
+                "max_depth": 1,
+                "max_pages": 10
+            }),
+            ToolExecutionContext::default(),
+        )
+        .unwrap();
+        assert_eq!(shown["folder"], "Docs/Guide");
+        assert_eq!(shown["max_depth"], 1);
+        assert_eq!(shown["max_pages"], 10);
+        assert!(preview(
+            json!({ "url": "https://other.example.com/" }),
+            ToolExecutionContext::default()
+        )
+        .is_err());
+        let _ = std::fs::remove_dir_all(vault);
+    }
+
+    #[test]
+    fn rewrites_links_between_saved_pages() {
+        let saved = HashMap::from([
+            ("https://docs.rs/".to_string(), "index.md".to_string()),
+            (
+                "https://docs.rs/guide/intro".to_string(),
+                "guide/intro.md".to_string(),
+            ),
+            (
+                "https://docs.rs/guide/setup".to_string(),
+                "guide/setup.md".to_string(),
+            ),
+        ]);
+        let note = "See [intro][1], [home][2] and [elsewhere][3].\n\n\
+                    [1]: https://docs.rs/guide/intro#install\n\
+                    [2]: https://docs.rs/\n\
+                    [3]: https://example.com/\n";
+        assert_eq!(
+            rewrite_links(note, "guide/setup.md", &saved),
+            "See [intro][1], [home][2] and [elsewhere][3].\n\n\
+             [1]: intro.md#install\n\
+             [2]: ../index.md\n\
+             [3]: https://example.com/\n"
+        );
+        assert_eq!(
+            relative_path("index.md", "guide/intro.md"),
+            "guide/intro.md"
+        );
+        assert_eq!(relative_path("a/b/c.md", "a/d/e.md"), "../d/e.md");
+    }
+}
@@ -0,0 +1,855 @@
+//! Politeness and caching shared by the web tools. Every request goes out with one
+//! configured User-Agent, is checked against the host's robots.txt, waits for the host's
+//! rate limit, and GETs are answered from an on-disk cache that revalidates with
+//! ETag/Last-Modified once an entry's TTL has passed.
+
+use crate::db::{Db, PreferenceOperations};
+use crate::tools::ToolError;
+use chrono::Utc;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CACHE_CONTROL, COOKIE, ETAG,
+    IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, PROXY_AUTHORIZATION, SET_COOKIE, VARY,
+};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::api::path;
+use url::Url;
+
+use super::web::DEFAULT_USER_AGENT;
+
+pub(crate) const PREF_USER_AGENT: &str = "plugins.web.user_agent";
+const PREF_RESPECT_ROBOTS: &str = "plugins.web.respect_robots";
+/// Seconds a cached response is served without revalidation; `0` disables the cache.
+const PREF_CACHE_TTL_SECS: &str = "plugins.web.cache_ttl_secs";
+/// JSON `{"default": {...}, "hosts": {"example.com": {...}}}` of [`RateLimit`]s.
+const PREF_RATE_LIMITS: &str = "plugins.web.rate_limits";
+
+const APP_NAMESPACE_DIR: &str = "dev.michalmlak.ai_agent";
+const WEB_CACHE_DIR: &str = "web_cache";
+const DEFAULT_CACHE_TTL_SECS: u64 = 3600;
+const MAX_CACHED_BODY_BYTES: usize = 10_485_760;
+const MAX_ROBOTS_BYTES: u64 = 512 * 1024;
+const ROBOTS_TTL: Duration = Duration::from_secs(24 * 3600);
+/// robots.txt that could not be fetched blocks the host, but only briefly.
+const ROBOTS_FAILURE_TTL: Duration = Duration::from_secs(600);
+/// Longest a request waits for its rate-limit slot before failing as rate limited.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub(super) struct RateLimit {
+    /// `0` means no spacing between requests.
+    pub requests_per_minute: u32,
+    pub max_concurrent: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 60,
+            max_concurrent: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct RateLimits {
+    default: RateLimit,
+    hosts: HashMap<String, RateLimit>,
+}
+
+pub(super) struct WebPolicy {
+    pub user_agent: String,
+    respect_robots: bool,
+    cache: Option<HttpCache>,
+    rate_limits: RateLimits,
+}
+
+/// What [`WebPolicy::send`] sends. Only body-less GETs with `use_cache` touch the cache.
+pub(super) struct PolicyRequest<'a> {
+    pub method: Method,
+    pub url: &'a Url,
+    pub use_cache: bool,
+    /// Cached bodies are cut to this size, as `read_body` cuts fresh ones.
+    pub max_bytes: usize,
+}
+
+/// A response read through [`WebPolicy::send`], either fresh from the network or cached.
+pub(super) struct HttpResponse {
+    pub url: Url,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    pub truncated: bool,
+    pub from_cache: bool,
+}
+
+impl WebPolicy {
+    pub fn load(db: &Db) -> Result<Self, ToolError> {
+        let pref = |key: &str| {
+            PreferenceOperations::get_preference(db, key)
+                .map(|value| {
+                    value
+                        .map(|v| v.trim().to_string())
+                        .filter(|v| !v.is_empty())
+                })
+                .map_err(|err| ToolError::new(format!("Failed to load {key}: {err}")))
+        };
+        let user_agent = pref(PREF_USER_AGENT)?.unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+        let respect_robots = pref(PREF_RESPECT_ROBOTS)?
+            .map(|value| value != "false")
+            .unwrap_or(true);
+        let ttl_secs = match pref(PREF_CACHE_TTL_SECS)? {
+            Some(value) => value.parse::<u64>().map_err(|_| {
+                ToolError::new(format!("Invalid {PREF_CACHE_TTL_SECS}: expected seconds"))
+            })?,
+            None => DEFAULT_CACHE_TTL_SECS,
+        };
+        let cache = if ttl_secs == 0 {
+            None
+        } else {
+            path::app_data_dir(&tauri::Config::default()).map(|dir| HttpCache {
+                dir: dir.join(APP_NAMESPACE_DIR).join(WEB_CACHE_DIR),
+                ttl: Duration::from_secs(ttl_secs),
+            })
+        };
+        let rate_limits = match pref(PREF_RATE_LIMITS)? {
+            Some(raw) => serde_json::from_str(&raw)
+                .map_err(|err| ToolError::new(format!("Invalid {PREF_RATE_LIMITS}: {err}")))?,
+            None => RateLimits::default(),
+        };
+        Ok(Self {
+            user_agent,
+            respect_robots,
+            cache,
+            rate_limits,
+        })
+    }
+
+    /// Per-call `user_agent` argument, which replaces the configured one for this call.
+    pub fn with_user_agent(mut self, user_agent: Option<&str>) -> Self {
+        if let Some(user_agent) = user_agent.map(str::trim).filter(|ua| !ua.is_empty()) {
+            self.user_agent = user_agent.to_string();
+        }
+        self
+    }
+
+    fn rate_limit(&self, host: &str) -> RateLimit {
+        self.rate_limits
+            .hosts
+            .get(host)
+            .copied()
+            .unwrap_or(self.rate_limits.default)
+    }
+
+    /// Sends `request` under the policy. `prepare` adds headers and a body; `read_body`
+    /// reads at most `max_bytes` of the response. Only complete 200 responses to requests
+    /// without credentials are cached.
+    pub fn send(
+        &self,
+        client: &Client,
+        request: PolicyRequest<'_>,
+        prepare: impl FnOnce(RequestBuilder) -> RequestBuilder,
+        read_body: impl FnOnce(Response) -> Result<(Vec<u8>, bool), ToolError>,
+    ) -> Result<HttpResponse, ToolError> {
+        let PolicyRequest {
+            method,
+            url,
+            use_cache,
+            max_bytes,
+        } = request;
+        let host = url
+            .host_str()
+            .ok_or_else(|| ToolError::new("URL missing host"))?
+            .to_ascii_lowercase();
+        let crawl_delay = if self.respect_robots {
+            robots_check(client, url, &self.user_agent)?
+        } else {
+            None
+        };
+
+        let is_get = method == Method::GET;
+        let mut request = prepare(client.request(method, url.as_str()))
+            .build()
+            .map_err(|err| ToolError::validation(format!("Invalid request: {err}")))?;
+        // The cache is keyed by URL alone, so a credentialed response must never be
+        // stored or served where an anonymous request for the same URL could read it.
+        let cache = self
+            .cache
+            .as_ref()
+            .filter(|_| use_cache && is_get && !has_credentials(request.headers()));
+        let cached = cache.and_then(|cache| cache.load(url));
+        if let Some((entry, body)) = &cached {
+            if entry.is_fresh(Utc::now().timestamp_millis()) {
+                return Ok(entry.response(body.clone(), max_bytes));
+            }
+        }
+
+        let _permit = limiter().acquire(&host, self.rate_limit(&host), crawl_delay)?;
+        if let Some((entry, _)) = &cached {
+            let validators = [(ETAG, IF_NONE_MATCH), (LAST_MODIFIED, IF_MODIFIED_SINCE)];
+            for (stored, conditional) in validators {
+                if let Some(value) = entry
+                    .header(stored.as_str())
+                    .and_then(|value| HeaderValue::from_str(value).ok())
+                {
+                    request.headers_mut().insert(conditional, value);
+                }
+            }
+        }
+        let response = client
+            .execute(request)
+            .map_err(|err| ToolError::upstream(format!("Request failed: {err}")))?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let (Some(cache), Some((mut entry, body))) = (cache, cached) {
+                entry.refresh(response.headers(), cache.ttl);
+                cache.store(url, &entry, &body);
+                return Ok(entry.response(body, max_bytes));
+            }
+        }
+
+        let final_url = response.url().clone();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let (body, truncated) = read_body(response)?;
+        if let Some(cache) = cache {
+            if status == StatusCode::OK && !truncated && body.len() <= MAX_CACHED_BODY_BYTES {
+                if let Some(entry) = CacheEntry::new(&final_url, status, &headers, cache.ttl) {
+                    cache.store(url, &entry, &body);
+                }
+            }
+        }
+        Ok(HttpResponse {
+            url: final_url,
+            status,
+            headers,
+            body,
+            truncated,
+            from_cache: false,
+        })
+    }
+}
+
+struct HttpCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    final_url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    stored_at: i64,
+    max_age_secs: u64,
+}
+
+impl HttpCache {
+    fn key(url: &Url) -> String {
+        Sha256::digest(url.as_str().as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    fn load(&self, url: &Url) -> Option<(CacheEntry, Vec<u8>)> {
+        let key = Self::key(url);
+        let meta = std::fs::read(self.dir.join(format!("{key}.json"))).ok()?;
+        let entry = serde_json::from_slice::<CacheEntry>(&meta)
+            .ok()
+            .filter(|entry| Url::parse(&entry.final_url).is_ok())?;
+        let body = std::fs::read(self.dir.join(format!("{key}.body"))).ok()?;
+        Some((entry, body))
+    }
+
+    /// Best effort: a cache that cannot be written only costs a refetch later.
+    fn store(&self, url: &Url, entry: &CacheEntry, body: &[u8]) {
+        let key = Self::key(url);
+        let result = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(self.dir.join(format!("{key}.body")), body))
+            .and_then(|_| {
+                let meta = serde_json::to_vec(entry).unwrap_or_default();
+                std::fs::write(self.dir.join(format!("{key}.json")), meta)
+            });
+        if let Err(err) = result {
+            log::warn!("Failed to write web cache entry for {url}: {err}");
+        }
+    }
+}
+
+impl CacheEntry {
+    /// `None` when the response forbids storing it, or varies by request headers the cache
+    /// key does not include.
+    fn new(url: &Url, status: StatusCode, headers: &HeaderMap, ttl: Duration) -> Option<Self> {
+        if headers.contains_key(VARY) {
+            return None;
+        }
+        let max_age_secs = max_age(headers, ttl)?;
+        Some(Self {
+            final_url: url.to_string(),
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| *name != SET_COOKIE)
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            stored_at: Utc::now().timestamp_millis(),
+            max_age_secs,
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn is_fresh(&self, now_ms: i64) -> bool {
+        now_ms - self.stored_at < (self.max_age_secs as i64).saturating_mul(1000)
+    }
+
+    /// Restarts the TTL after a 304, taking any updated validators from it.
+    fn refresh(&mut self, headers: &HeaderMap, ttl: Duration) {
+        for name in [ETAG, LAST_MODIFIED, CACHE_CONTROL] {
+            if let Some(value) = headers.get(&name).and_then(|v| v.to_str().ok()) {
+                self.headers
+                    .retain(|(key, _)| !key.eq_ignore_ascii_case(name.as_str()));
+                self.headers.push((name.to_string(), value.to_string()));
+            }
+        }
+        let stored = self.headers.iter().filter_map(|(key, value)| {
+            Some((
+                HeaderName::from_bytes(key.as_bytes()).ok()?,
+                HeaderValue::from_str(value).ok()?,
+            ))
+        });
+        self.max_age_secs = max_age(&stored.collect::<HeaderMap>(), ttl).unwrap_or(0);
+        self.stored_at = Utc::now().timestamp_millis();
+    }
+
+    fn response(&self, mut body: Vec<u8>, max_bytes: usize) -> HttpResponse {
+        let truncated = body.len() > max_bytes;
+        body.truncate(max_bytes);
+        HttpResponse {
+            url: Url::parse(&self.final_url).expect("load only returns entries with valid URLs"),
+            status: StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK),
+            headers: self
+                .headers
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((
+                        HeaderName::from_bytes(key.as_bytes()).ok()?,
+                        HeaderValue::from_str(value).ok()?,
+                    ))
+                })
+                .collect(),
+            body,
+            truncated,
+            from_cache: true,
+        }
+    }
+}
+
+fn has_credentials(headers: &HeaderMap) -> bool {
+    [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION]
+        .iter()
+        .any(|name| headers.contains_key(name))
+}
+
+/// Freshness lifetime from `Cache-Control`, falling back to the configured TTL. `no-cache`
+/// stores the response but revalidates every time; `no-store` is not stored at all.
+fn max_age(headers: &HeaderMap, ttl: Duration) -> Option<u64> {
+    let directives = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    if directives.iter().any(|d| d == "no-store") {
+        return None;
+    }
+    if directives.iter().any(|d| d == "no-cache") {
+        return Some(0);
+    }
+    let max_age = directives
+        .iter()
+        .find_map(|d| d.strip_prefix("max-age="))
+        .and_then(|secs| secs.trim_matches('"').parse::<u64>().ok());
+    Some(max_age.unwrap_or(ttl.as_secs()))
+}
+
+#[derive(Debug, Clone)]
+enum RobotsFile {
+    AllowAll,
+    DisallowAll,
+    Rules(String),
+}
+
+static ROBOTS: OnceLock<Mutex<HashMap<String, (Instant, RobotsFile)>>> = OnceLock::new();
+
+/// Fails when robots.txt disallows `url` for `user_agent`; otherwise returns the
+/// Crawl-delay that applies to it.
+fn robots_check(
+    client: &Client,
+    url: &Url,
+    user_agent: &str,
+) -> Result<Option<Duration>, ToolError> {
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    if path == "/robots.txt" {
+        return Ok(None);
+    }
+    let origin = url.origin().ascii_serialization();
+    let robots = ROBOTS.get_or_init(Default::default);
+    let cached = robots
+        .lock()
+        .unwrap()
+        .get(&origin)
+        .filter(|(expires, _)| *expires > Instant::now())
+        .map(|(_, file)| file.clone());
+    let file = match cached {
+        Some(file) => file,
+        None => {
+            let (file, ttl) = fetch_robots(client, &origin);
+            robots
+                .lock()
+                .unwrap()
+                .insert(origin.clone(), (Instant::now() + ttl, file.clone()));
+            file
+        }
+    };
+    let rules = match file {
+        RobotsFile::AllowAll => return Ok(None),
+        RobotsFile::DisallowAll => {
+            return Err(ToolError::permission(format!(
+                "{origin}/robots.txt could not be fetched, so the site is treated as disallowed"
+            ))
+            .with_hint("Retry later; unreachable robots.txt files are rechecked after 10 minutes"))
+        }
+        RobotsFile::Rules(body) => RobotsRules::parse(&body, user_agent),
+    };
+    if !rules.allows(&path) {
+        return Err(ToolError::permission(format!(
+            "robots.txt of {origin} disallows {path} for this user agent"
+        ))
+        .with_hint("Do not retry this URL; look for the content elsewhere"));
+    }
+    Ok(rules.crawl_delay)
+}
+
+fn fetch_robots(client: &Client, origin: &str) -> (RobotsFile, Duration) {
+    use std::io::Read;
+    let response = match client.get(format!("{origin}/robots.txt")).send() {
+        Ok(response) => response,
+        Err(_) => return (RobotsFile::DisallowAll, ROBOTS_FAILURE_TTL),
+    };
+    let status = response.status();
+    if status.is_success() {
+        let mut body = String::new();
+        if response
+            .take(MAX_ROBOTS_BYTES)
+            .read_to_string(&mut body)
+            .is_err()
+        {
+            return (RobotsFile::AllowAll, ROBOTS_TTL);
+        }
+        (RobotsFile::Rules(body), ROBOTS_TTL)
+    } else if status.is_server_error() {
+        (RobotsFile::DisallowAll, ROBOTS_FAILURE_TTL)
+    } else {
+        // Missing (4xx) or redirected off the allowed hosts: no restrictions.
+        (RobotsFile::AllowAll, ROBOTS_TTL)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct RobotsRules {
+    /// `(allow, pattern)` pairs of the group that applies to the user agent.
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// Rules of the groups naming the user agent's product token, or of the `*` groups
+    /// when none does. Groups for the same agent are merged, as RFC 9309 specifies.
+    fn parse(body: &str, user_agent: &str) -> Self {
+        let token = user_agent
+            .split(|c: char| c == '/' || c.is_whitespace())
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let mut groups: Vec<(Vec<String>, RobotsRules)> = Vec::new();
+        let mut in_agents = false;
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push((Vec::new(), RobotsRules::default()));
+                        in_agents = true;
+                    }
+                    if let Some((agents, _)) = groups.last_mut() {
+                        agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                key @ ("allow" | "disallow") => {
+                    in_agents = false;
+                    if let Some((_, rules)) = groups.last_mut() {
+                        // An empty Disallow allows everything, so it adds no rule.
+                        if !value.is_empty() {
+                            rules.rules.push((key == "allow", value.to_string()));
+                        }
+                    }
+                }
+                "crawl-delay" => {
+                    in_agents = false;
+                    if let Some((_, rules)) = groups.last_mut() {
+                        rules.crawl_delay = value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                            .map(Duration::from_secs_f64);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let named = groups
+            .iter()
+            .any(|(agents, _)| agents.iter().any(|agent| *agent == token));
+        let mut merged = RobotsRules::default();
+        for (agents, rules) in groups {
+            let applies = if named {
+                agents.iter().any(|agent| *agent == token)
+            } else {
+                agents.iter().any(|agent| agent == "*")
+            };
+            if applies {
+                merged.rules.extend(rules.rules);
+                merged.crawl_delay = merged.crawl_delay.or(rules.crawl_delay);
+            }
+        }
+        merged
+    }
+
+    /// The longest matching pattern decides; on a tie Allow wins.
+    fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| robots_pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .map(|(allow, _)| *allow)
+            .unwrap_or(true)
+    }
+}
+
+/// Prefix match supporting `*` (any run of characters) and a trailing `$` (end of path).
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !path.starts_with(first) {
+        return false;
+    }
+    let mut pos = first.len();
+    let rest = parts.collect::<Vec<_>>();
+    for (index, part) in rest.iter().enumerate() {
+        if anchored && index == rest.len() - 1 {
+            return path[pos..].ends_with(part);
+        }
+        match path[pos..].find(part) {
+            Some(offset) => pos += offset + part.len(),
+            None => return false,
+        }
+    }
+    !anchored || pos == path.len()
+}
+
+#[derive(Debug)]
+struct HostSlot {
+    in_flight: u32,
+    next_start: Instant,
+}
+
+/// Process-wide per-host limiter: requests to a host start at least
+/// `60s / requests_per_minute` (or its robots.txt Crawl-delay) apart, and at most
+/// `max_concurrent` run at once.
+struct HostLimiter {
+    hosts: Mutex<HashMap<String, HostSlot>>,
+    released: Condvar,
+}
+
+struct HostPermit<'a> {
+    limiter: &'a HostLimiter,
+    host: String,
+}
+
+fn limiter() -> &'static HostLimiter {
+    static LIMITER: OnceLock<HostLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| HostLimiter {
+        hosts: Mutex::new(HashMap::new()),
+        released: Condvar::new(),
+    })
+}
+
+impl HostLimiter {
+    fn acquire(
+        &self,
+        host: &str,
+        limit: RateLimit,
+        crawl_delay: Option<Duration>,
+    ) -> Result<HostPermit<'_>, ToolError> {
+        let deadline = Instant::now() + MAX_RATE_LIMIT_WAIT;
+        let mut hosts = self.hosts.lock().unwrap();
+        loop {
+            let slot = hosts.entry(host.to_string()).or_insert_with(|| HostSlot {
+                in_flight: 0,
+                next_start: Instant::now(),
+            });
+            if slot.in_flight < limit.max_concurrent.max(1) {
+                break;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(ToolError::rate_limited(format!(
+                    "Too many concurrent requests to {host}"
+                )));
+            }
+            hosts = self.released.wait_timeout(hosts, deadline - now).unwrap().0;
+        }
+
+        let interval = match limit.requests_per_minute {
+            0 => Duration::ZERO,
+            rpm => Duration::from_secs(60) / rpm,
+        }
+        .max(crawl_delay.unwrap_or(Duration::ZERO));
+        let now = Instant::now();
+        let slot = hosts.get_mut(host).expect("slot inserted above");
+        let start = slot.next_start.max(now);
+        let wait = start - now;
+        if wait > MAX_RATE_LIMIT_WAIT {
+            let mut error = ToolError::rate_limited(format!("Rate limit for {host} reached"));
+            error.retry_after_ms = Some(wait.as_millis() as u64);
+            return Err(error);
+        }
+        slot.next_start = start + interval;
+        slot.in_flight += 1;
+        drop(hosts);
+
+        std::thread::sleep(wait);
+        Ok(HostPermit {
+            limiter: self,
+            host: host.to_string(),
+        })
+    }
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.limiter.hosts.lock().unwrap().get_mut(&self.host) {
+            slot.in_flight = slot.in_flight.saturating_sub(1);
+        }
+        self.limiter.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::mock_server::MockServer;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn robots_rules_pick_the_matching_group() {
+        let body = "\
+# comment
+User-agent: *
+Disallow: /private
+Allow: /private/public$
+
+User-agent: ai-agent
+User-agent: other-bot
+Disallow: /drafts/
+Allow: /drafts/*.html$
+Crawl-delay: 2.5
+
+User-agent: ai-agent
+Disallow: /*?session=
+";
+        let rules = RobotsRules::parse(body, "ai-agent/1.0");
+        assert_eq!(rules.crawl_delay, Some(Duration::from_millis(2500)));
+        assert!(rules.allows("/private"));
+        assert!(!rules.allows("/drafts/notes.txt"));
+        assert!(rules.allows("/drafts/post.html"));
+        assert!(!rules.allows("/drafts/post.html?x=1"));
+        assert!(!rules.allows("/search?q=1&session=abc"));
+
+        let rules = RobotsRules::parse(body, "curl/8.0");
+        assert!(!rules.allows("/private/secret"));
+        assert!(rules.allows("/private/public"));
+        assert!(!rules.allows("/private/public/more"));
+        assert!(rules.allows("/drafts/notes.txt"));
+        assert_eq!(rules.crawl_delay, None);
+
+        assert!(RobotsRules::parse("User-agent: *\nDisallow:\n", "x").allows("/anything"));
+    }
+
+    #[test]
+    fn stale_entries_are_revalidated_with_validators() {
+        let server = MockServer::start();
+        server.route_with_headers(
+            "GET",
+            "/page",
+            200,
+            &[("ETag", "\"v1\""), ("Cache-Control", "no-cache")],
+            json!({ "version": 1 }),
+        );
+        let dir = std::env::temp_dir().join(format!("web-cache-{}", uuid::Uuid::new_v4()));
+        let policy = WebPolicy {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            respect_robots: false,
+            cache: Some(HttpCache {
+                dir: dir.clone(),
+                ttl: Duration::from_secs(3600),
+            }),
+            rate_limits: RateLimits {
+                default: RateLimit {
+                    requests_per_minute: 0,
+                    max_concurrent: 4,
+                },
+                hosts: HashMap::new(),
+            },
+        };
+        let client = Client::new();
+        let url = Url::parse(&format!("{}/page", server.url())).unwrap();
+        let send = || {
+            policy.send(
+                &client,
+                PolicyRequest {
+                    method: Method::GET,
+                    url: &url,
+                    use_cache: true,
+                    max_bytes: 1_000,
+                },
+                |request| request,
+                |response| Ok((response.bytes().unwrap().to_vec(), false)),
+            )
+        };
+
+        let first = send().unwrap();
+        assert!(!first.from_cache);
+        assert_eq!(first.body, br#"{"version":1}"#);
+
+        server.route("GET", "/page", 304, Value::Null);
+        let second = send().unwrap();
+        assert!(second.from_cache);
+        assert_eq!(second.status, StatusCode::OK);
+        assert_eq!(second.body, br#"{"version":1}"#);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].header("If-None-Match"), Some("\"v1\""));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn credentialed_requests_and_varying_responses_bypass_the_cache() {
+        let server = MockServer::start();
+        server.route("GET", "/private", 200, json!({ "user": "me" }));
+        server.route_with_headers(
+            "GET",
+            "/varies",
+            200,
+            &[("Vary", "Accept-Language")],
+            json!({ "lang": "en" }),
+        );
+        let dir = std::env::temp_dir().join(format!("web-cache-{}", uuid::Uuid::new_v4()));
+        let policy = WebPolicy {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            respect_robots: false,
+            cache: Some(HttpCache {
+                dir: dir.clone(),
+                ttl: Duration::from_secs(3600),
+            }),
+            rate_limits: RateLimits {
+                default: RateLimit {
+                    requests_per_minute: 0,
+                    max_concurrent: 4,
+                },
+                hosts: HashMap::new(),
+            },
+        };
+        let client = Client::new();
+        let send = |path: &str, token: Option<&str>| {
+            let url = Url::parse(&format!("{}{path}", server.url())).unwrap();
+            policy
+                .send(
+                    &client,
+                    PolicyRequest {
+                        method: Method::GET,
+                        url: &url,
+                        use_cache: true,
+                        max_bytes: 1_000,
+                    },
+                    |request| match token {
+                        Some(token) => request.bearer_auth(token),
+                        None => request,
+                    },
+                    |response| Ok((response.bytes().unwrap().to_vec(), false)),
+                )
+                .unwrap()
+        };
+
+        assert!(!send("/private", Some("secret")).from_cache);
+        assert!(!send("/private", None).from_cache);
+        assert!(!send("/varies", None).from_cache);
+        assert!(!send("/varies", None).from_cache);
+        assert_eq!(server.requests().len(), 4);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn limiter_spaces_requests_to_the_same_host() {
+        let limit = RateLimit {
+            requests_per_minute: 600,
+            max_concurrent: 1,
+        };
+        let started = Instant::now();
+        drop(limiter().acquire("spacing.test", limit, None).unwrap());
+        drop(limiter().acquire("spacing.test", limit, None).unwrap());
+        assert!(started.elapsed() >= Duration::from_millis(100));
+
+        let slow = limiter()
+            .acquire("delay.test", limit, Some(Duration::from_secs(120)))
+            .unwrap();
+        drop(slow);
+        let error = limiter()
+            .acquire("delay.test", limit, None)
+            .err()
+            .expect("second request must wait longer than allowed");
+        assert_eq!(error.kind, crate::tools::ToolErrorKind::RateLimited);
+        assert!(error.retry_after_ms.unwrap() > 60_000);
+    }
+}
@@ -0,0 +1,545 @@
+//! `web.search` over a pluggable backend: a self-hosted SearxNG instance, Brave Search or
+//! Tavily. Results are normalized so the agent sees the same shape from every backend, and
+//! each one says whether its host is already on the `web.fetch` allowlist.
+
+use super::web::normalize_host;
+use super::web_allowlist::{ensure_url_allowed, load_web_access_rules};
+use super::web_policy::WebPolicy;
+use crate::db::{Db, PreferenceOperations};
+use crate::tools::{
+    ToolDefinition, ToolError, ToolExecutionContext, ToolMetadata, ToolRegistry, ToolResultMode,
+};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+pub const PREF_SEARCH_BACKEND: &str = "plugins.web.search_backend";
+pub const PREF_SEARXNG_URL: &str = "plugins.web.searxng_url";
+pub const PREF_BRAVE_API_KEY: &str = "plugins.web.brave_api_key";
+pub const PREF_TAVILY_API_KEY: &str = "plugins.web.tavily_api_key";
+
+const BRAVE_API_URL: &str = "https://api.search.brave.com/res/v1/web/search";
+const TAVILY_API_URL: &str = "https://api.tavily.com/search";
+const DEFAULT_MAX_RESULTS: usize = 10;
+const MAX_RESULTS: usize = 20;
+const SEARCH_TIMEOUT_MS: u64 = 15_000;
+const BACKEND_IDS: [&str; 3] = ["searxng", "brave", "tavily"];
+
+/// One normalized search hit.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+    /// Publication date as the backend reports it, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub query: String,
+    pub max_results: usize,
+    /// `day`, `week`, `month` or `year`.
+    pub freshness: Option<String>,
+    pub language: Option<String>,
+}
+
+pub trait SearchBackend {
+    fn id(&self) -> &'static str;
+    fn search(
+        &self,
+        client: &Client,
+        request: &SearchRequest,
+    ) -> Result<Vec<SearchResult>, ToolError>;
+}
+
+/// A SearxNG instance with the JSON output format enabled (`search.formats` in its
+/// settings.yml).
+pub struct SearxngBackend {
+    base_url: String,
+}
+
+impl SearxngBackend {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl SearchBackend for SearxngBackend {
+    fn id(&self) -> &'static str {
+        "searxng"
+    }
+
+    fn search(
+        &self,
+        client: &Client,
+        request: &SearchRequest,
+    ) -> Result<Vec<SearchResult>, ToolError> {
+        let mut builder = client
+            .get(format!("{}/search", self.base_url))
+            .query(&[("q", request.query.as_str()), ("format", "json")]);
+        if let Some(freshness) = request.freshness.as_deref() {
+            builder = builder.query(&[("time_range", freshness)]);
+        }
+        if let Some(language) = request.language.as_deref() {
+            builder = builder.query(&[("language", language)]);
+        }
+        let body = search_json(builder, "SearxNG")?;
+        Ok(parse_searxng(&body))
+    }
+}
+
+pub struct BraveBackend {
+    api_key: String,
+    api_url: String,
+}
+
+impl BraveBackend {
+    pub fn new(api_key: &str) -> Self {
+        Self::with_api_url(api_key, BRAVE_API_URL)
+    }
+
+    pub fn with_api_url(api_key: &str, api_url: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            api_url: api_url.to_string(),
+        }
+    }
+}
+
+impl SearchBackend for BraveBackend {
+    fn id(&self) -> &'static str {
+        "brave"
+    }
+
+    fn search(
+        &self,
+        client: &Client,
+        request: &SearchRequest,
+    ) -> Result<Vec<SearchResult>, ToolError> {
+        let mut builder = client
+            .get(&self.api_url)
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .query(&[("q", request.query.as_str())])
+            .query(&[("count", request.max_results.to_string())]);
+        if let Some(freshness) = request.freshness.as_deref() {
+            let code = match freshness {
+                "day" => "pd",
+                "week" => "pw",
+                "month" => "pm",
+                _ => "py",
+            };
+            builder = builder.query(&[("freshness", code)]);
+        }
+        if let Some(language) = request.language.as_deref() {
+            builder = builder.query(&[("search_lang", language)]);
+        }
+        let body = search_json(builder, "Brave Search")?;
+        Ok(parse_brave(&body))
+    }
+}
+
+pub struct TavilyBackend {
+    api_key: String,
+    api_url: String,
+}
+
+impl TavilyBackend {
+    pub fn new(api_key: &str) -> Self {
+        Self::with_api_url(api_key, TAVILY_API_URL)
+    }
+
+    pub fn with_api_url(api_key: &str, api_url: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            api_url: api_url.to_string(),
+        }
+    }
+}
+
+impl SearchBackend for TavilyBackend {
+    fn id(&self) -> &'static str {
+        "tavily"
+    }
+
+    fn search(
+        &self,
+        client: &Client,
+        request: &SearchRequest,
+    ) -> Result<Vec<SearchResult>, ToolError> {
+        let mut payload = json!({
+            "query": request.query,
+            "max_results": request.max_results,
+        });
+        if let Some(freshness) = request.freshness.as_deref() {
+            payload["time_range"] = json!(freshness);
+        }
+        let builder = client
+            .post(&self.api_url)
+            .bearer_auth(&self.api_key)
+            .json(&payload);
+        let body = search_json(builder, "Tavily")?;
+        Ok(parse_tavily(&body))
+    }
+}
+
+/// The backend named by `requested`, or by the `plugins.web.search_backend` preference, or
+/// else the first one that is configured.
+pub fn configured_backend(
+    db: &Db,
+    requested: Option<&str>,
+) -> Result<Box<dyn SearchBackend>, ToolError> {
+    let preferred = match requested.filter(|id| !id.is_empty()) {
+        Some(id) => Some(id.to_string()),
+        None => setting(db, PREF_SEARCH_BACKEND, "WEB_SEARCH_BACKEND"),
+    };
+    let searxng = setting(db, PREF_SEARXNG_URL, "SEARXNG_URL");
+    let brave = setting(db, PREF_BRAVE_API_KEY, "BRAVE_SEARCH_API_KEY");
+    let tavily = setting(db, PREF_TAVILY_API_KEY, "TAVILY_API_KEY");
+
+    let backend_for = |id: &str| -> Option<Box<dyn SearchBackend>> {
+        match id {
+            "searxng" => searxng
+                .as_deref()
+                .map(|url| Box::new(SearxngBackend::new(url)) as Box<dyn SearchBackend>),
+            "brave" => brave
+                .as_deref()
+                .map(|key| Box::new(BraveBackend::new(key)) as Box<dyn SearchBackend>),
+            "tavily" => tavily
+                .as_deref()
+                .map(|key| Box::new(TavilyBackend::new(key)) as Box<dyn SearchBackend>),
+            _ => None,
+        }
+    };
+
+    match preferred {
+        Some(id) => {
+            if !BACKEND_IDS.contains(&id.as_str()) {
+                return Err(ToolError::validation(format!(
+                    "Unknown search backend '{id}'. Use 'searxng', 'brave' or 'tavily'."
+                )));
+            }
+            backend_for(&id).ok_or_else(|| {
+                ToolError::validation(format!("Search backend '{id}' is not configured"))
+                    .with_hint(setup_hint())
+            })
+        }
+        None => BACKEND_IDS
+            .iter()
+            .find_map(|id| backend_for(id))
+            .ok_or_else(|| {
+                ToolError::validation("No web search backend is configured").with_hint(setup_hint())
+            }),
+    }
+}
+
+pub fn register_web_search_tool(registry: &mut ToolRegistry, db: Db) -> Result<(), String> {
+    let metadata = ToolMetadata {
+        name: "web.search".to_string(),
+        description: "Search the web for current information (news, prices, weather, docs). Returns title, url, snippet and date per result. Results on hosts that are not approved yet can be opened with web.fetch after passing the result url to web.approve_domain.".to_string(),
+        args_schema: json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string", "minLength": 1 },
+                "max_results": { "type": "integer", "minimum": 1, "maximum": MAX_RESULTS },
+                "freshness": { "type": "string", "enum": ["day", "week", "month", "year"] },
+                "language": { "type": "string", "description": "Language code such as 'en'." },
+                "backend": { "type": "string", "enum": BACKEND_IDS }
+            },
+            "required": ["query"],
+            "additionalProperties": false
+        }),
+        result_schema: json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "backend": { "type": "string" },
+                "results": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "title": { "type": "string" },
+                            "url": { "type": "string" },
+                            "snippet": { "type": "string" },
+                            "date": { "type": "string" },
+                            "host": { "type": "string" },
+                            "approved": { "type": "boolean" }
+                        },
+                        "required": ["title", "url", "snippet", "host", "approved"]
+                    }
+                }
+            },
+            "required": ["query", "backend", "results"]
+        }),
+        requires_approval: false,
+        result_mode: ToolResultMode::Auto,
+    };
+
+    let handler = Arc::new(move |args: Value, _ctx: ToolExecutionContext| {
+        let query = args
+            .get("query")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| ToolError::validation("Missing 'query'"))?
+            .to_string();
+        let request = SearchRequest {
+            query,
+            max_results: args
+                .get("max_results")
+                .and_then(|v| v.as_u64())
+                .map(|v| (v as usize).clamp(1, MAX_RESULTS))
+                .unwrap_or(DEFAULT_MAX_RESULTS),
+            freshness: args
+                .get("freshness")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            language: args
+                .get("language")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        };
+        let backend = configured_backend(&db, args.get("backend").and_then(|v| v.as_str()))?;
+        let client = Client::builder()
+            .timeout(Duration::from_millis(SEARCH_TIMEOUT_MS))
+            .user_agent(WebPolicy::load(&db)?.user_agent)
+            .build()
+            .map_err(|err| ToolError::new(format!("Failed to build client: {err}")))?;
+
+        let mut results = backend.search(&client, &request)?;
+        results.truncate(request.max_results);
+        let rules = load_web_access_rules(&db)?;
+        let results = results
+            .into_iter()
+            .map(|result| {
+                let url = Url::parse(&result.url).ok();
+                let host = url
+                    .as_ref()
+                    .and_then(|url| url.host_str().and_then(|host| normalize_host(host).ok()))
+                    .unwrap_or_default();
+                let approved = url
+                    .as_ref()
+                    .is_some_and(|url| ensure_url_allowed(&rules, url, &Method::GET).is_ok());
+                let mut value = json!(result);
+                value["host"] = json!(host);
+                value["approved"] = json!(approved);
+                value
+            })
+            .collect::<Vec<_>>();
+
+        Ok(json!({
+            "query": request.query,
+            "backend": backend.id(),
+            "results": results
+        }))
+    });
+
+    registry.register(ToolDefinition {
+        metadata,
+        handler,
+        preview: None,
+    })
+}
+
+fn setting(db: &Db, key: &str, env_var: &str) -> Option<String> {
+    let non_empty = |value: String| {
+        let value = value.trim().to_string();
+        (!value.is_empty()).then_some(value)
+    };
+    PreferenceOperations::get_preference(db, key)
+        .ok()
+        .flatten()
+        .and_then(non_empty)
+        .or_else(|| std::env::var(env_var).ok().and_then(non_empty))
+}
+
+fn setup_hint() -> String {
+    format!(
+        "Set {PREF_SEARXNG_URL}, {PREF_BRAVE_API_KEY} or {PREF_TAVILY_API_KEY} in preferences (or SEARXNG_URL, BRAVE_SEARCH_API_KEY or TAVILY_API_KEY)."
+    )
+}
+
+fn search_json(builder: RequestBuilder, service: &str) -> Result<Value, ToolError> {
+    let response = builder
+        .send()
+        .map_err(|err| ToolError::upstream(format!("{service} request failed: {err}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ToolError::from_http_response(
+            &response,
+            format!("{service} error: HTTP {status}"),
+        ));
+    }
+    response
+        .json::<Value>()
+        .map_err(|err| ToolError::upstream(format!("Failed to parse {service} response: {err}")))
+}
+
+fn parse_searxng(body: &Value) -> Vec<SearchResult> {
+    collect_results(body.get("results"), "content", &["publishedDate"])
+}
+
+fn parse_brave(body: &Value) -> Vec<SearchResult> {
+    collect_results(
+        body.pointer("/web/results"),
+        "description",
+        &["page_age", "age"],
+    )
+}
+
+fn parse_tavily(body: &Value) -> Vec<SearchResult> {
+    collect_results(body.get("results"), "content", &["published_date"])
+}
+
+fn collect_results(
+    items: Option<&Value>,
+    snippet_key: &str,
+    date_keys: &[&str],
+) -> Vec<SearchResult> {
+    let text = |item: &Value, key: &str| {
+        item.get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| strip_tags(v).trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    items
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let url = text(item, "url")?;
+            Some(SearchResult {
+                title: text(item, "title").unwrap_or_else(|| url.clone()),
+                snippet: text(item, snippet_key).unwrap_or_default(),
+                date: date_keys.iter().find_map(|key| text(item, key)),
+                url,
+            })
+        })
+        .collect()
+}
+
+/// Drops the highlight markup some backends put in titles and snippets.
+fn strip_tags(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut in_tag = false;
+    for ch in value.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(ch),
+            _ => {}
+        }
+    }
+    out.replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::mock_server::MockServer;
+
+    fn request(query: &str) -> SearchRequest {
+        SearchRequest {
+            query: query.to_string(),
+            max_results: 5,
+            freshness: Some("week".to_string()),
+            language: None,
+        }
+    }
+
+    #[test]
+    fn backends_normalize_to_one_shape() {
+        let brave = parse_brave(&json!({
+            "web": { "results": [{
+                "title": "Rust <strong>1.80</strong>",
+                "url": "https://blog.rust-lang.org/1.80",
+                "description": "Released &amp; stable",
+                "page_age": "2024-07-25T00:00:00"
+            }]}
+        }));
+        assert_eq!(
+            brave,
+            vec![SearchResult {
+                title: "Rust 1.80".to_string(),
+                url: "https://blog.rust-lang.org/1.80".to_string(),
+                snippet: "Released & stable".to_string(),
+                date: Some("2024-07-25T00:00:00".to_string()),
+            }]
+        );
+
+        let tavily = parse_tavily(&json!({
+            "results": [
+                { "title": "", "url": "https://example.com/a", "content": "A" },
+                { "title": "No url", "content": "skipped" }
+            ]
+        }));
+        assert_eq!(tavily.len(), 1);
+        assert_eq!(tavily[0].title, "https://example.com/a");
+        assert_eq!(tavily[0].date, None);
+    }
+
+    #[test]
+    fn searxng_backend_queries_the_instance() {
+        let server = MockServer::start();
+        server.route(
+            "GET",
+            "/search",
+            200,
+            json!({ "results": [{
+                "title": "Weather",
+                "url": "https://weather.example/berlin",
+                "content": "Sunny",
+                "publishedDate": "2024-05-01"
+            }]}),
+        );
+        let backend = SearxngBackend::new(&format!("{}/", server.url()));
+        let results = backend
+            .search(&Client::new(), &request("berlin weather"))
+            .expect("search failed");
+        assert_eq!(results[0].snippet, "Sunny");
+        assert_eq!(results[0].date.as_deref(), Some("2024-05-01"));
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.param("q").as_deref(), Some("berlin weather"));
+        assert_eq!(sent.param("format").as_deref(), Some("json"));
+        assert_eq!(sent.param("time_range").as_deref(), Some("week"));
+    }
+
+    #[test]
+    fn api_key_backends_authenticate_and_surface_rate_limits() {
+        let server = MockServer::start();
+        server
+            .route("GET", "/brave", 429, json!({ "error": "quota" }))
+            .route("POST", "/tavily", 200, json!({ "results": [] }));
+        let brave = BraveBackend::with_api_url("b-key", &format!("{}/brave", server.url()));
+        let err = brave
+            .search(&Client::new(), &request("q"))
+            .expect_err("429 should fail");
+        assert_eq!(err.kind, crate::tools::ToolErrorKind::RateLimited);
+
+        let tavily = TavilyBackend::with_api_url("t-key", &format!("{}/tavily", server.url()));
+        assert!(tavily
+            .search(&Client::new(), &request("q"))
+            .expect("search failed")
+            .is_empty());
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("x-subscription-token"), Some("b-key"));
+        assert_eq!(requests[0].param("freshness").as_deref(), Some("pw"));
+        assert_eq!(requests[1].header("authorization"), Some("Bearer t-key"));
+        assert_eq!(requests[1].json()["time_range"], "week");
+    }
+}
@@ -0,0 +1,493 @@
+mod sources;
+mod vault;
+mod webhook;
+
+pub use webhook::PREF_WEBHOOK_PORT;
+
+use crate::db::{AgentTrigger, AgentTriggerEvent, AgentTriggerOperations, PreferenceOperations};
+use crate::events::{
+    AgentEvent, EVENT_AGENT_TRIGGER_RUN_COMPLETED, EVENT_AGENT_TRIGGER_RUN_STARTED,
+};
+use crate::scheduler::{UnattendedRunRequest, UnattendedRunner};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use vault::VaultWatcher;
+
+pub const SOURCE_GMAIL: &str = "gmail";
+pub const SOURCE_TODOIST: &str = "todoist";
+pub const SOURCE_VAULT: &str = "vault";
+pub const SOURCE_WEBHOOK: &str = "webhook";
+
+const TICK_INTERVAL: Duration = Duration::from_secs(15);
+const INTEGRATION_POLL_INTERVAL: Duration = Duration::from_secs(120);
+const RATE_LIMIT_WINDOW_MS: i64 = 60 * 60 * 1000;
+const MAX_EVENTS_PER_RUN: u32 = 20;
+const PROMPT_PAYLOAD_MAX_CHARS: usize = 8_000;
+const WEBHOOK_SECRET_MIN_LEN: usize = 16;
+
+/// A change observed by a trigger source. `dedupe_key` must be stable for the same change so
+/// repeated polls, webhook retries and agent-caused echoes are only processed once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriggerEvent {
+    pub dedupe_key: String,
+    pub summary: String,
+    pub payload: Value,
+}
+
+pub fn validate_trigger_config(source: &str, config: &Value) -> Result<(), String> {
+    let string_field = |key: &str| {
+        config
+            .get(key)
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    match source {
+        SOURCE_GMAIL => {
+            if string_field("label").is_none() && string_field("query").is_none() {
+                return Err("Gmail triggers need a 'label' or a 'query'".to_string());
+            }
+        }
+        SOURCE_TODOIST => {
+            if string_field("project_id").is_none() {
+                return Err("Todoist triggers need a 'project_id'".to_string());
+            }
+        }
+        SOURCE_VAULT => {
+            vault::VaultTriggerConfig::from_value(config)?;
+        }
+        SOURCE_WEBHOOK => {
+            let secret = string_field("secret").unwrap_or_default();
+            if secret.len() < WEBHOOK_SECRET_MIN_LEN {
+                return Err(format!(
+                    "Webhook triggers need a 'secret' of at least {WEBHOOK_SECRET_MIN_LEN} characters"
+                ));
+            }
+        }
+        other => {
+            return Err(format!(
+                "Unknown trigger source '{other}': expected gmail, todoist, vault or webhook"
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Watches trigger sources and starts an unattended agent run when new events arrive.
+/// Events are batched per trigger: while a run is in progress new events stay pending and
+/// go out with the next run. Events over the hourly run limit also stay pending, until the
+/// limit allows another run.
+#[derive(Clone)]
+pub struct TriggerEngine {
+    runner: UnattendedRunner,
+    running: Arc<Mutex<HashSet<String>>>,
+    /// Rate-limited triggers and when, in ms, they may run again.
+    not_before: Arc<Mutex<HashMap<String, i64>>>,
+    last_polled: Arc<Mutex<HashMap<String, Instant>>>,
+    vault: Arc<VaultWatcher>,
+}
+
+impl TriggerEngine {
+    pub fn new(runner: UnattendedRunner) -> Self {
+        Self {
+            runner,
+            running: Arc::new(Mutex::new(HashSet::new())),
+            not_before: Arc::new(Mutex::new(HashMap::new())),
+            last_polled: Arc::new(Mutex::new(HashMap::new())),
+            vault: Arc::new(VaultWatcher::default()),
+        }
+    }
+
+    pub fn start(&self) {
+        match self.runner.db().fail_interrupted_agent_trigger_events() {
+            Ok(count) if count > 0 => {
+                log::warn!("[triggers] marked {} interrupted events as failed", count);
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("[triggers] failed to clean up interrupted events: {}", err),
+        }
+
+        let port = PreferenceOperations::get_preference(self.runner.db(), PREF_WEBHOOK_PORT)
+            .ok()
+            .flatten()
+            .and_then(|value| value.trim().parse::<u16>().ok());
+        if let Some(port) = port {
+            webhook::start_listener(self.clone(), port);
+        }
+
+        let engine = self.clone();
+        std::thread::spawn(move || loop {
+            engine.tick();
+            std::thread::sleep(TICK_INTERVAL);
+        });
+    }
+
+    fn tick(&self) {
+        let triggers = match self.runner.db().get_agent_triggers() {
+            Ok(triggers) => triggers,
+            Err(err) => {
+                log::error!("[triggers] failed to load triggers: {}", err);
+                return;
+            }
+        };
+
+        for trigger in triggers.iter().filter(|trigger| trigger.enabled) {
+            let due = match trigger.source.as_str() {
+                SOURCE_VAULT => true,
+                SOURCE_GMAIL | SOURCE_TODOIST => self
+                    .last_polled
+                    .lock()
+                    .unwrap()
+                    .get(&trigger.id)
+                    .map(|at| at.elapsed() >= INTEGRATION_POLL_INTERVAL)
+                    .unwrap_or(true),
+                _ => false,
+            };
+            if due {
+                if let Err(err) = self.poll(trigger) {
+                    log::warn!(
+                        "[triggers] poll failed for {} ({}): {}",
+                        trigger.id,
+                        trigger.name,
+                        err
+                    );
+                }
+            }
+            self.dispatch(trigger);
+        }
+    }
+
+    /// Polls one trigger's source right away and dispatches anything new.
+    pub fn poll_now(&self, trigger_id: &str) -> Result<usize, String> {
+        let trigger = self.load_trigger(trigger_id)?;
+        let count = self.poll(&trigger)?;
+        self.dispatch(&trigger);
+        Ok(count)
+    }
+
+    fn poll(&self, trigger: &AgentTrigger) -> Result<usize, String> {
+        self.last_polled
+            .lock()
+            .unwrap()
+            .insert(trigger.id.clone(), Instant::now());
+
+        let db = self.runner.db();
+        // The first poll of an integration only records what already exists, so enabling
+        // a trigger does not replay the whole inbox.
+        let baseline = trigger.cursor.is_none();
+        let is_known = |key: &str| {
+            db.has_agent_trigger_event(&trigger.id, key)
+                .unwrap_or(false)
+        };
+        let result = match trigger.source.as_str() {
+            SOURCE_GMAIL => sources::poll_gmail(db, &trigger.config, baseline, &is_known),
+            SOURCE_TODOIST => sources::poll_todoist(db, &trigger.config, &is_known),
+            SOURCE_VAULT => self.vault.poll(db, trigger),
+            _ => return Ok(0),
+        };
+
+        let events = match result {
+            Ok(events) => {
+                if trigger.last_error.is_some() {
+                    let _ = db.set_agent_trigger_error(&trigger.id, None);
+                }
+                events
+            }
+            Err(err) => {
+                let _ = db.set_agent_trigger_error(&trigger.id, Some(&err));
+                return Err(err);
+            }
+        };
+
+        let baseline = baseline && trigger.source != SOURCE_VAULT;
+        let count = self.record_events(trigger, &events, baseline)?;
+        if baseline {
+            let cursor = json!({ "baseline_at": Utc::now().timestamp_millis() });
+            db.set_agent_trigger_cursor(&trigger.id, Some(&cursor))
+                .map_err(|e| e.to_string())?;
+            return Ok(0);
+        }
+        Ok(count)
+    }
+
+    /// Accepts an event pushed to the webhook listener. Returns false for duplicates.
+    pub fn ingest_webhook(
+        &self,
+        trigger: &AgentTrigger,
+        event: TriggerEvent,
+    ) -> Result<bool, String> {
+        let count = self.record_events(trigger, std::slice::from_ref(&event), false)?;
+        if count > 0 {
+            self.dispatch(trigger);
+        }
+        Ok(count > 0)
+    }
+
+    fn record_events(
+        &self,
+        trigger: &AgentTrigger,
+        events: &[TriggerEvent],
+        baseline: bool,
+    ) -> Result<usize, String> {
+        let status = if baseline { "baseline" } else { "pending" };
+        let mut recorded = 0;
+        for event in events {
+            let inserted = self
+                .runner
+                .db()
+                .record_agent_trigger_event(
+                    &trigger.id,
+                    &event.dedupe_key,
+                    &event.summary,
+                    &event.payload,
+                    status,
+                )
+                .map_err(|e| e.to_string())?;
+            if inserted {
+                recorded += 1;
+            }
+        }
+        Ok(recorded)
+    }
+
+    fn dispatch(&self, trigger: &AgentTrigger) {
+        if !trigger.enabled || self.running.lock().unwrap().contains(&trigger.id) {
+            return;
+        }
+        let now = Utc::now().timestamp_millis();
+        if let Some(not_before) = self.not_before.lock().unwrap().get(&trigger.id) {
+            if now < *not_before {
+                return;
+            }
+        }
+        let db = self.runner.db();
+        let pending = match db.get_pending_agent_trigger_events(&trigger.id, MAX_EVENTS_PER_RUN) {
+            Ok(pending) if !pending.is_empty() => pending,
+            Ok(_) => return,
+            Err(err) => {
+                log::error!(
+                    "[triggers] failed to load pending events for {}: {}",
+                    trigger.id,
+                    err
+                );
+                return;
+            }
+        };
+        let event_ids: Vec<String> = pending.iter().map(|event| event.id.clone()).collect();
+
+        let since = now - RATE_LIMIT_WINDOW_MS;
+        let recent_runs = db
+            .count_agent_trigger_runs_since(&trigger.id, since)
+            .unwrap_or(0);
+        if recent_runs >= trigger.max_runs_per_hour {
+            let first_run = db
+                .first_agent_trigger_run_since(&trigger.id, since)
+                .ok()
+                .flatten();
+            let not_before = rate_limit_lifts_at(first_run, now);
+            log::warn!(
+                "[triggers] deferring {} events for {} ({}) until {}: rate limit reached ({} runs per hour)",
+                event_ids.len(),
+                trigger.id,
+                trigger.name,
+                not_before,
+                trigger.max_runs_per_hour
+            );
+            self.not_before
+                .lock()
+                .unwrap()
+                .insert(trigger.id.clone(), not_before);
+            return;
+        }
+        self.not_before.lock().unwrap().remove(&trigger.id);
+
+        if !self.running.lock().unwrap().insert(trigger.id.clone()) {
+            return;
+        }
+        let run_id = Uuid::new_v4().to_string();
+        if let Err(err) = db.dispatch_agent_trigger_events(&trigger.id, &event_ids, &run_id) {
+            log::error!(
+                "[triggers] failed to dispatch events for {}: {}",
+                trigger.id,
+                err
+            );
+            self.running.lock().unwrap().remove(&trigger.id);
+            return;
+        }
+
+        let engine = self.clone();
+        let trigger = trigger.clone();
+        std::thread::spawn(move || {
+            engine.execute_run(&trigger, &run_id, &pending);
+            engine.running.lock().unwrap().remove(&trigger.id);
+        });
+    }
+
+    fn execute_run(&self, trigger: &AgentTrigger, run_id: &str, events: &[AgentTriggerEvent]) {
+        log::info!(
+            "[triggers] firing {} ({}) run_id={} events={}",
+            trigger.id,
+            trigger.name,
+            run_id,
+            events.len()
+        );
+        self.publish(
+            EVENT_AGENT_TRIGGER_RUN_STARTED,
+            json!({
+                "trigger_id": trigger.id,
+                "trigger_name": trigger.name,
+                "run_id": run_id,
+                "event_count": events.len(),
+            }),
+        );
+
+        let request = UnattendedRunRequest {
+            conversation_id: trigger.conversation_id.clone(),
+            conversation_name: trigger.name.clone(),
+            prompt: build_trigger_prompt(trigger, events),
+            provider: trigger.provider.clone(),
+            model: trigger.model.clone(),
+            custom_backend_id: trigger.custom_backend_id.clone(),
+            system_prompt: trigger.system_prompt.clone(),
+            approval_policy: trigger.approval_policy.clone(),
+        };
+        let (status, conversation_id, message_id, error) = match self.runner.run(request) {
+            Ok(result) => (
+                result.status(),
+                Some(result.conversation_id),
+                Some(result.message_id),
+                result.outcome.error,
+            ),
+            Err(err) => ("failed", None, None, Some(err)),
+        };
+
+        let db = self.runner.db();
+        if let Err(err) = db.finish_agent_trigger_run(
+            run_id,
+            status,
+            conversation_id.as_deref(),
+            message_id.as_deref(),
+            error.as_deref(),
+        ) {
+            log::error!("[triggers] failed to record run {}: {}", run_id, err);
+        }
+        let _ = db.set_agent_trigger_error(&trigger.id, error.as_deref());
+        if let Some(error) = error.as_deref() {
+            log::warn!(
+                "[triggers] run {} for {} failed: {}",
+                run_id,
+                trigger.id,
+                error
+            );
+        }
+
+        self.publish(
+            EVENT_AGENT_TRIGGER_RUN_COMPLETED,
+            json!({
+                "trigger_id": trigger.id,
+                "trigger_name": trigger.name,
+                "run_id": run_id,
+                "event_count": events.len(),
+                "status": status,
+                "conversation_id": conversation_id,
+                "message_id": message_id,
+                "error": error,
+            }),
+        );
+    }
+
+    fn load_trigger(&self, trigger_id: &str) -> Result<AgentTrigger, String> {
+        self.runner
+            .db()
+            .get_agent_trigger_by_id(trigger_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Trigger not found: {trigger_id}"))
+    }
+
+    fn publish(&self, event_type: &str, mut payload: Value) {
+        let timestamp_ms = Utc::now().timestamp_millis();
+        if let Some(object) = payload.as_object_mut() {
+            object.insert("timestamp_ms".to_string(), json!(timestamp_ms));
+        }
+        self.runner
+            .event_bus()
+            .publish(AgentEvent::new_with_timestamp(
+                event_type,
+                payload,
+                timestamp_ms,
+            ));
+    }
+}
+
+/// When a rate-limited trigger may run again: once its earliest run in the window ages out.
+fn rate_limit_lifts_at(first_run_ms: Option<i64>, now_ms: i64) -> i64 {
+    first_run_ms
+        .map(|at| at + RATE_LIMIT_WINDOW_MS)
+        .unwrap_or(now_ms + RATE_LIMIT_WINDOW_MS)
+        .max(now_ms)
+}
+
+fn build_trigger_prompt(trigger: &AgentTrigger, events: &[AgentTriggerEvent]) -> String {
+    let summaries = events
+        .iter()
+        .map(|event| format!("- {}", event.summary))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let payloads = Value::Array(events.iter().map(|event| event.payload.clone()).collect());
+    let mut payload_text = serde_json::to_string_pretty(&payloads).unwrap_or_default();
+    if payload_text.chars().count() > PROMPT_PAYLOAD_MAX_CHARS {
+        payload_text = payload_text
+            .chars()
+            .take(PROMPT_PAYLOAD_MAX_CHARS)
+            .collect();
+        payload_text.push_str("\n… (truncated)");
+    }
+    format!(
+        "{}\n\nTrigger \"{}\" fired for {} new {} event(s):\n{}\n\nEvent payloads:\n```json\n{}\n```",
+        trigger.prompt.trim(),
+        trigger.name,
+        events.len(),
+        trigger.source,
+        summaries,
+        payload_text
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_configs_are_validated_per_source() {
+        assert!(validate_trigger_config(SOURCE_GMAIL, &json!({ "label": "Receipts" })).is_ok());
+        assert!(validate_trigger_config(SOURCE_GMAIL, &json!({})).is_err());
+        assert!(validate_trigger_config(SOURCE_TODOIST, &json!({ "project_id": "42" })).is_ok());
+        assert!(validate_trigger_config(
+            SOURCE_VAULT,
+            &json!({ "path_prefix": "Inbox/", "events": ["created"] })
+        )
+        .is_ok());
+        assert!(
+            validate_trigger_config(SOURCE_VAULT, &json!({ "path_prefix": "../outside" })).is_err()
+        );
+        assert!(validate_trigger_config(SOURCE_VAULT, &json!({ "events": ["renamed"] })).is_err());
+        assert!(validate_trigger_config(SOURCE_WEBHOOK, &json!({ "secret": "short" })).is_err());
+        assert!(validate_trigger_config("slack", &json!({})).is_err());
+    }
+
+    #[test]
+    fn rate_limited_triggers_wait_for_their_earliest_run_to_age_out() {
+        let now = 10 * RATE_LIMIT_WINDOW_MS;
+        let first_run = now - RATE_LIMIT_WINDOW_MS + 60_000;
+        assert_eq!(rate_limit_lifts_at(Some(first_run), now), now + 60_000);
+        assert_eq!(rate_limit_lifts_at(None, now), now + RATE_LIMIT_WINDOW_MS);
+        assert_eq!(
+            rate_limit_lifts_at(Some(now - 2 * RATE_LIMIT_WINDOW_MS), now),
+            now
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use super::TriggerEvent;
+use crate::db::Db;
+use crate::integrations::endpoints::{GMAIL_API, TODOIST_REST_API};
+use crate::tools::{get_access_token, get_connection, get_google_access_token};
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const SOURCE_HTTP_TIMEOUT_SECS: u64 = 30;
+const GMAIL_POLL_MAX_RESULTS: u32 = 25;
+
+fn http_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(SOURCE_HTTP_TIMEOUT_SECS))
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+fn config_str<'a>(config: &'a Value, key: &str) -> Option<&'a str> {
+    config
+        .get(key)
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn get_json(request: reqwest::blocking::RequestBuilder, service: &str) -> Result<Value, String> {
+    let response = request
+        .send()
+        .map_err(|err| format!("Failed to call {service} API: {err}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{service} API error: HTTP {status}"));
+    }
+    response
+        .json::<Value>()
+        .map_err(|err| format!("Failed to parse {service} response: {err}"))
+}
+
+/// Lists recent messages matching the trigger's label and query. During the baseline poll
+/// only ids are collected; otherwise headers are fetched for messages not seen before.
+pub fn poll_gmail(
+    db: &Db,
+    config: &Value,
+    baseline: bool,
+    is_known: &dyn Fn(&str) -> bool,
+) -> Result<Vec<TriggerEvent>, String> {
+    let connection = get_connection(
+        db,
+        config_str(config, "connection_id").unwrap_or(""),
+        "gmail",
+    )
+    .map_err(|err| err.message)?;
+    let token = get_google_access_token(db, &connection).map_err(|err| err.message)?;
+    let api = GMAIL_API.base_url(db, &connection.id);
+
+    let mut query_parts = Vec::new();
+    if let Some(label) = config_str(config, "label") {
+        query_parts.push(format!("label:{}", label.replace(' ', "-")));
+    }
+    if let Some(query) = config_str(config, "query") {
+        query_parts.push(query.to_string());
+    }
+    let query = query_parts.join(" ");
+
+    let client = http_client();
+    let listing = get_json(
+        client
+            .get(format!("{api}/users/me/messages"))
+            .query(&[("q", query.as_str())])
+            .query(&[("maxResults", GMAIL_POLL_MAX_RESULTS.to_string())])
+            .bearer_auth(&token),
+        "Gmail",
+    )?;
+
+    let mut events = Vec::new();
+    let messages = listing
+        .get("messages")
+        .and_then(|value| value.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for message in messages {
+        let Some(id) = message.get("id").and_then(|value| value.as_str()) else {
+            continue;
+        };
+        let dedupe_key = format!("gmail:{id}");
+        if is_known(&dedupe_key) {
+            continue;
+        }
+        if baseline {
+            events.push(TriggerEvent {
+                dedupe_key,
+                summary: format!("Gmail message {id}"),
+                payload: json!({ "id": id }),
+            });
+            continue;
+        }
+
+        let detail = get_json(
+            client
+                .get(format!("{api}/users/me/messages/{id}"))
+                .query(&[
+                    ("format", "metadata"),
+                    ("metadataHeaders", "From"),
+                    ("metadataHeaders", "Subject"),
+                    ("metadataHeaders", "Date"),
+                ])
+                .bearer_auth(&token),
+            "Gmail",
+        )?;
+        let header = |name: &str| {
+            detail
+                .pointer("/payload/headers")
+                .and_then(|value| value.as_array())
+                .and_then(|headers| {
+                    headers.iter().find(|header| {
+                        header
+                            .get("name")
+                            .and_then(|value| value.as_str())
+                            .map(|value| value.eq_ignore_ascii_case(name))
+                            .unwrap_or(false)
+                    })
+                })
+                .and_then(|header| header.get("value"))
+                .and_then(|value| value.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let from = header("From");
+        let subject = header("Subject");
+        events.push(TriggerEvent {
+            dedupe_key,
+            summary: format!("{from}: {subject}"),
+            payload: json!({
+                "id": id,
+                "thread_id": detail.get("threadId"),
+                "from": from,
+                "subject": subject,
+                "date": header("Date"),
+                "snippet": detail.get("snippet"),
+                "label_ids": detail.get("labelIds"),
+            }),
+        });
+    }
+    Ok(events)
+}
+
+/// Lists open tasks in the configured project and reports the ones not seen before.
+pub fn poll_todoist(
+    db: &Db,
+    config: &Value,
+    is_known: &dyn Fn(&str) -> bool,
+) -> Result<Vec<TriggerEvent>, String> {
+    let connection = get_connection(
+        db,
+        config_str(config, "connection_id").unwrap_or(""),
+        "todoist",
+    )
+    .map_err(|err| err.message)?;
+    let token = get_access_token(&connection).map_err(|err| err.message)?;
+    let project_id = config_str(config, "project_id")
+        .ok_or_else(|| "Todoist triggers need a 'project_id'".to_string())?;
+
+    let tasks = get_json(
+        http_client()
+            .get(TODOIST_REST_API.url(db, &connection, "/tasks"))
+            .query(&[("project_id", project_id)])
+            .bearer_auth(token),
+        "Todoist",
+    )?;
+
+    let mut events = Vec::new();
+    for task in tasks.as_array().cloned().unwrap_or_default() {
+        let id = match task.get("id") {
+            Some(Value::String(id)) => id.clone(),
+            Some(Value::Number(id)) => id.to_string(),
+            _ => continue,
+        };
+        let dedupe_key = format!("todoist:{id}");
+        if is_known(&dedupe_key) {
+            continue;
+        }
+        let content = task
+            .get("content")
+            .and_then(|value| value.as_str())
+            .unwrap_or("")
+            .to_string();
+        events.push(TriggerEvent {
+            dedupe_key,
+            summary: format!("Todoist task: {content}"),
+            payload: json!({
+                "id": id,
+                "content": content,
+                "description": task.get("description"),
+                "project_id": task.get("project_id"),
+                "labels": task.get("labels"),
+                "priority": task.get("priority"),
+                "due": task.get("due"),
+                "url": task.get("url"),
+            }),
+        });
+    }
+    Ok(events)
+}
@@ -0,0 +1,193 @@
+use super::TriggerEvent;
+use crate::db::{AgentTrigger, Db};
+use crate::tools::{get_vault_root, normalize_relative_path, to_display_path};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+const MAX_SCAN_DEPTH: usize = 12;
+const MAX_SCAN_FILES: usize = 20_000;
+const VAULT_EVENT_KINDS: [&str; 3] = ["created", "modified", "deleted"];
+
+/// Relative path to modification time in ms.
+type Snapshot = BTreeMap<String, i64>;
+
+pub struct VaultTriggerConfig {
+    pub path_prefix: String,
+    pub events: Vec<String>,
+}
+
+impl VaultTriggerConfig {
+    pub fn from_value(config: &Value) -> Result<Self, String> {
+        let path_prefix = config
+            .get("path_prefix")
+            .and_then(|value| value.as_str())
+            .unwrap_or("")
+            .trim()
+            .trim_start_matches("./")
+            .to_string();
+        if !path_prefix.is_empty() {
+            normalize_relative_path(&path_prefix).map_err(|err| err.message)?;
+        }
+
+        let events = match config.get("events").and_then(|value| value.as_array()) {
+            Some(values) => values
+                .iter()
+                .filter_map(|value| value.as_str())
+                .map(str::to_string)
+                .collect::<Vec<_>>(),
+            None => vec!["created".to_string()],
+        };
+        if events.is_empty() {
+            return Err("Vault triggers need at least one event kind".to_string());
+        }
+        if let Some(unknown) = events
+            .iter()
+            .find(|kind| !VAULT_EVENT_KINDS.contains(&kind.as_str()))
+        {
+            return Err(format!(
+                "Unknown vault event '{unknown}': expected created, modified or deleted"
+            ));
+        }
+
+        Ok(Self {
+            path_prefix,
+            events,
+        })
+    }
+}
+
+/// Polling file watcher. Each trigger keeps an in-memory snapshot of its folder; the first
+/// scan after startup only takes the snapshot, so changes made while the app was closed are
+/// not reported.
+#[derive(Default)]
+pub struct VaultWatcher {
+    snapshots: Mutex<HashMap<String, Snapshot>>,
+}
+
+impl VaultWatcher {
+    pub fn poll(&self, db: &Db, trigger: &AgentTrigger) -> Result<Vec<TriggerEvent>, String> {
+        let config = VaultTriggerConfig::from_value(&trigger.config)?;
+        let root = get_vault_root(db).map_err(|err| err.message)?;
+        let scan_root = if config.path_prefix.is_empty() {
+            root.clone()
+        } else {
+            root.join(&config.path_prefix)
+        };
+
+        let mut current = Snapshot::new();
+        if scan_root.is_dir() {
+            scan_dir(&root, &scan_root, MAX_SCAN_DEPTH, &mut current);
+        }
+
+        let previous = self
+            .snapshots
+            .lock()
+            .unwrap()
+            .insert(trigger.id.clone(), current.clone());
+        Ok(match previous {
+            Some(previous) => diff_snapshots(&previous, &current, &config.events),
+            None => Vec::new(),
+        })
+    }
+}
+
+fn scan_dir(root: &Path, current: &Path, depth: usize, snapshot: &mut Snapshot) {
+    if depth == 0 || snapshot.len() >= MAX_SCAN_FILES {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(current) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(metadata) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        if metadata.file_type().is_symlink() {
+            continue;
+        }
+        if metadata.is_dir() {
+            scan_dir(root, &entry.path(), depth - 1, snapshot);
+        } else if metadata.is_file() {
+            let modified_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_millis() as i64)
+                .unwrap_or(0);
+            snapshot.insert(to_display_path(root, &entry.path()), modified_ms);
+            if snapshot.len() >= MAX_SCAN_FILES {
+                return;
+            }
+        }
+    }
+}
+
+fn diff_snapshots(previous: &Snapshot, current: &Snapshot, kinds: &[String]) -> Vec<TriggerEvent> {
+    let wants = |kind: &str| kinds.iter().any(|value| value == kind);
+    let mut events = Vec::new();
+    for (path, modified_ms) in current {
+        let kind = match previous.get(path) {
+            None => "created",
+            Some(previous_ms) if previous_ms != modified_ms => "modified",
+            Some(_) => continue,
+        };
+        if wants(kind) {
+            events.push(vault_event(kind, path, *modified_ms));
+        }
+    }
+    if wants("deleted") {
+        for (path, modified_ms) in previous {
+            if !current.contains_key(path) {
+                events.push(vault_event("deleted", path, *modified_ms));
+            }
+        }
+    }
+    events
+}
+
+fn vault_event(kind: &str, path: &str, modified_ms: i64) -> TriggerEvent {
+    TriggerEvent {
+        dedupe_key: format!("vault:{kind}:{path}:{modified_ms}"),
+        summary: format!("{kind} {path}"),
+        payload: json!({
+            "event": kind,
+            "path": path,
+            "modified_at": modified_ms,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_diff_reports_requested_kinds() {
+        let previous =
+            Snapshot::from([("Inbox/a.md".to_string(), 1), ("Inbox/b.md".to_string(), 1)]);
+        let current =
+            Snapshot::from([("Inbox/a.md".to_string(), 2), ("Inbox/c.md".to_string(), 3)]);
+
+        let created = diff_snapshots(&previous, &current, &["created".to_string()]);
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].dedupe_key, "vault:created:Inbox/c.md:3");
+
+        let all = diff_snapshots(&previous, &current, &VAULT_EVENT_KINDS.map(str::to_string));
+        let summaries: Vec<&str> = all.iter().map(|event| event.summary.as_str()).collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "modified Inbox/a.md",
+                "created Inbox/c.md",
+                "deleted Inbox/b.md"
+            ]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use super::{TriggerEngine, TriggerEvent, SOURCE_WEBHOOK};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Preference holding the local port for the webhook listener. The listener is off when unset.
+pub const PREF_WEBHOOK_PORT: &str = "triggers.webhook_port";
+
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 256 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct WebhookRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl WebhookRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+enum ReadError {
+    Malformed,
+    TooLarge,
+}
+
+/// Binds to loopback only; exposing it further is left to a reverse proxy or tunnel.
+pub fn start_listener(engine: TriggerEngine, port: u16) {
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::error!(
+                "[triggers] failed to bind webhook listener on port {}: {}",
+                port,
+                err
+            );
+            return;
+        }
+    };
+    log::info!(
+        "[triggers] webhook listener on http://127.0.0.1:{}/hooks/",
+        port
+    );
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let engine = engine.clone();
+            std::thread::spawn(move || handle_connection(&engine, stream));
+        }
+    });
+}
+
+fn handle_connection(engine: &TriggerEngine, mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let (status, body) = match read_request(&mut stream) {
+        Ok(request) => handle_request(engine, &request),
+        Err(ReadError::TooLarge) => (413, json!({ "error": "Payload too large" })),
+        Err(ReadError::Malformed) => (400, json!({ "error": "Malformed request" })),
+    };
+    let _ = respond_json(&mut stream, status, &body);
+}
+
+fn handle_request(engine: &TriggerEngine, request: &WebhookRequest) -> (u16, Value) {
+    let Some(trigger_id) = request
+        .path
+        .split('?')
+        .next()
+        .and_then(|path| path.strip_prefix("/hooks/"))
+        .filter(|id| !id.is_empty() && !id.contains('/'))
+    else {
+        return (404, json!({ "error": "Not found" }));
+    };
+    if request.method != "POST" {
+        return (405, json!({ "error": "Only POST is supported" }));
+    }
+
+    // Unknown ids and wrong secrets answer the same way so ids cannot be probed.
+    let trigger = match engine.load_trigger(trigger_id) {
+        Ok(trigger) if trigger.source == SOURCE_WEBHOOK => trigger,
+        _ => return (401, json!({ "error": "Unauthorized" })),
+    };
+    let expected = trigger
+        .config
+        .get("secret")
+        .and_then(|value| value.as_str())
+        .unwrap_or("");
+    let provided = request.header("X-Trigger-Secret").unwrap_or("");
+    if expected.is_empty() || !constant_time_eq(expected.as_bytes(), provided.as_bytes()) {
+        return (401, json!({ "error": "Unauthorized" }));
+    }
+    if !trigger.enabled {
+        return (404, json!({ "error": "Trigger is disabled" }));
+    }
+
+    let event = webhook_event(request);
+    match engine.ingest_webhook(&trigger, event) {
+        Ok(accepted) => (202, json!({ "accepted": accepted, "duplicate": !accepted })),
+        Err(err) => {
+            log::error!(
+                "[triggers] failed to record webhook for {}: {}",
+                trigger.id,
+                err
+            );
+            (500, json!({ "error": "Failed to record event" }))
+        }
+    }
+}
+
+fn webhook_event(request: &WebhookRequest) -> TriggerEvent {
+    let dedupe_key = request
+        .header("X-Dedupe-Key")
+        .or_else(|| request.header("Idempotency-Key"))
+        .map(|key| format!("webhook:{key}"))
+        .unwrap_or_else(|| {
+            let mut hasher = Sha256::new();
+            hasher.update(&request.body);
+            let digest = hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>();
+            format!("sha256:{digest}")
+        });
+    let payload = serde_json::from_slice::<Value>(&request.body)
+        .unwrap_or_else(|_| json!({ "body": String::from_utf8_lossy(&request.body) }));
+    let summary = match request.header("X-Event-Summary") {
+        Some(summary) => summary.to_string(),
+        None => format!("Webhook delivery ({} bytes)", request.body.len()),
+    };
+    TriggerEvent {
+        dedupe_key,
+        summary,
+        payload,
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<WebhookRequest, ReadError> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(position) = find_header_end(&buffer) {
+            break position;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            return Err(ReadError::TooLarge);
+        }
+        let read = stream.read(&mut chunk).map_err(|_| ReadError::Malformed)?;
+        if read == 0 {
+            return Err(ReadError::Malformed);
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    let method = request_line.next().ok_or(ReadError::Malformed)?.to_string();
+    let path = request_line.next().ok_or(ReadError::Malformed)?.to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let content_length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("Content-Length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_BODY_BYTES {
+        return Err(ReadError::TooLarge);
+    }
+
+    let mut body = buffer[header_end + 4..].to_vec();
+    while body.len() < content_length {
+        let read = stream.read(&mut chunk).map_err(|_| ReadError::Malformed)?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(content_length);
+
+    Ok(WebhookRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn find_header_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left.iter()
+        .zip(right)
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+fn respond_json(stream: &mut TcpStream, status: u16, body: &Value) -> std::io::Result<()> {
+    let reason = match status {
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
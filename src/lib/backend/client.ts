@@ -24,14 +24,18 @@ import type {
   BranchStats,
   DBMessage,
   IntegrationMetadata,
+  IntegrationHealth,
+  DiscoveredResource,
   McpServer,
   CreateMcpServerInput,
   UpdateMcpServerInput,
   IntegrationConnection,
   CreateIntegrationConnectionInput,
   UpdateIntegrationConnectionInput,
+  IntegrationSyncState,
   GoogleCalendarListItem,
   OAuthStartResponse,
+  DeviceOAuthStartResponse,
   OAuthSessionStatus
 } from '$lib/types';
 import type {
@@ -40,7 +44,37 @@ import type {
   UpdateCustomBackendInput
 } from '$lib/types/customBackend';
 import type { Attachment, FileMetadata } from '$lib/types/attachments';
-import type { ToolMetadata } from '$lib/types/tools';
+import type {
+  ToolMetadata,
+  ToolApprovalRule,
+  CreateToolApprovalRuleInput,
+  AuditLogEntry,
+  AuditLogQuery,
+  WebAccessRule,
+  WebAccessRuleInput,
+  WebAuthProfile,
+  SaveWebAuthProfileInput,
+  WebCrawlJob
+} from '$lib/types/tools';
+import type {
+  ScheduledJob,
+  ScheduledJobRun,
+  CreateScheduledJobInput,
+  UpdateScheduledJobInput
+} from '$lib/types/scheduler';
+import type {
+  AgentTrigger,
+  AgentTriggerEvent,
+  CreateAgentTriggerInput,
+  UpdateAgentTriggerInput
+} from '$lib/types/triggers';
+import type {
+  FeedItem,
+  FeedItemQuery,
+  FeedSubscription,
+  CreateFeedSubscriptionInput,
+  UpdateFeedSubscriptionInput
+} from '$lib/types/feeds';
 import type {
   ToolExecutionApprovalScope,
   ToolExecutionProposedPayload
@@ -378,12 +412,62 @@ class BackendClient {
     return invoke('delete_integration_connection', { id });
   }
 
-  async testIntegrationConnection(id: string): Promise<{ ok: boolean; status: number }> {
+  async testIntegrationConnection(id: string): Promise<IntegrationHealth> {
     return invoke('test_integration_connection', { id });
   }
 
-  async startGoogleOAuth(integrationId: string): Promise<OAuthStartResponse> {
-    return invoke('start_google_oauth', { integrationId });
+  async discoverIntegrationResources(connectionId: string): Promise<DiscoveredResource[]> {
+    return invoke('discover_integration_resources', { connectionId });
+  }
+
+  async getIntegrationSettings(connectionId: string): Promise<Record<string, unknown>> {
+    return invoke('get_integration_settings', { connectionId });
+  }
+
+  /** Validates the settings against the plugin's settings schema before saving. */
+  async saveIntegrationSettings(
+    connectionId: string,
+    settings: Record<string, unknown>
+  ): Promise<Record<string, unknown>> {
+    return invoke('save_integration_settings', { connectionId, settings });
+  }
+
+  async listIntegrationSyncStates(connectionId?: string): Promise<IntegrationSyncState[]> {
+    return invoke('list_integration_sync_states', { connectionId });
+  }
+
+  /** Starts a background sync; resolves to false when one is already running. */
+  async syncIntegrationNow(connectionId: string): Promise<boolean> {
+    return invoke('sync_integration_now', { connectionId });
+  }
+
+  async clearIntegrationCache(connectionId: string): Promise<void> {
+    return invoke('clear_integration_cache', { connectionId });
+  }
+
+  /** Browser sign-in. Pass `connectionId` to reconnect an existing connection. */
+  async startIntegrationOAuth(
+    integrationId: string,
+    connectionId?: string,
+    scopes?: string[]
+  ): Promise<OAuthStartResponse> {
+    return invoke('start_integration_oauth', { integrationId, connectionId, scopes });
+  }
+
+  /** Device code sign-in; show `user_code` and `verification_uri` to the user. */
+  async startIntegrationDeviceOAuth(
+    integrationId: string,
+    connectionId?: string
+  ): Promise<DeviceOAuthStartResponse> {
+    return invoke('start_integration_device_oauth', { integrationId, connectionId });
+  }
+
+  /** Re-authorizes a connection with extra scopes, keeping the ones already granted. */
+  async upgradeIntegrationScopes(
+    connectionId: string,
+    scopes: string[]
+  ): Promise<OAuthStartResponse> {
+    return invoke('upgrade_integration_scopes', { connectionId, scopes });
   }
 
   async listGoogleCalendars(connectionId: string): Promise<GoogleCalendarListItem[]> {
@@ -437,15 +521,166 @@ class BackendClient {
   async resolveToolExecutionApproval(
     approvalId: string,
     approved: boolean,
-    scope?: ToolExecutionApprovalScope
+    scope?: ToolExecutionApprovalScope,
+    modifiedArgs?: Record<string, unknown>,
+    feedback?: string,
+    grantTtlMs?: number
   ): Promise<void> {
     return invoke('resolve_tool_execution_approval', {
       approval_id: approvalId,
       approved,
-      scope
+      scope,
+      modified_args: modifiedArgs,
+      feedback,
+      grant_ttl_ms: grantTtlMs
     });
   }
 
+  async listToolApprovalRules(): Promise<ToolApprovalRule[]> {
+    return invoke('list_tool_approval_rules', {});
+  }
+
+  async createToolApprovalRule(input: CreateToolApprovalRuleInput): Promise<ToolApprovalRule> {
+    return invoke('create_tool_approval_rule', { input });
+  }
+
+  async deleteToolApprovalRule(id: string): Promise<boolean> {
+    return invoke('delete_tool_approval_rule', { id });
+  }
+
+  async listAuditLog(query?: AuditLogQuery): Promise<AuditLogEntry[]> {
+    return invoke('list_audit_log', { query });
+  }
+
+  /** Returns the matching audit log entries as JSON Lines or CSV text. */
+  async exportAuditLog(format: 'jsonl' | 'csv', query?: AuditLogQuery): Promise<string> {
+    return invoke('export_audit_log', { query, format });
+  }
+
+  async listWebAccessRules(): Promise<WebAccessRule[]> {
+    return invoke('list_web_access_rules');
+  }
+
+  /** Creates a rule, or edits the one with `input.id`. */
+  async saveWebAccessRule(input: WebAccessRuleInput): Promise<WebAccessRule> {
+    return invoke('save_web_access_rule', { input });
+  }
+
+  async revokeWebAccessRule(id: string): Promise<boolean> {
+    return invoke('revoke_web_access_rule', { id });
+  }
+
+  async listWebAuthProfiles(): Promise<WebAuthProfile[]> {
+    return invoke('list_web_auth_profiles');
+  }
+
+  /** Creates a profile, or edits the one with `input.id`; omit `secret` to keep the stored one. */
+  async saveWebAuthProfile(input: SaveWebAuthProfileInput): Promise<WebAuthProfile> {
+    return invoke('save_web_auth_profile', { input });
+  }
+
+  async deleteWebAuthProfile(id: string): Promise<boolean> {
+    return invoke('delete_web_auth_profile', { id });
+  }
+
+  async listWebCrawls(): Promise<WebCrawlJob[]> {
+    return invoke('list_web_crawls');
+  }
+
+  async cancelWebCrawl(jobId: string): Promise<boolean> {
+    return invoke('cancel_web_crawl', { job_id: jobId });
+  }
+
+  // ============ Feeds ============
+
+  async listFeedSubscriptions(): Promise<FeedSubscription[]> {
+    return invoke('list_feed_subscriptions');
+  }
+
+  /** Subscribes and starts a first poll in the background. */
+  async createFeedSubscription(input: CreateFeedSubscriptionInput): Promise<FeedSubscription> {
+    return invoke('create_feed_subscription', { input });
+  }
+
+  async updateFeedSubscription(
+    input: UpdateFeedSubscriptionInput
+  ): Promise<FeedSubscription | null> {
+    return invoke('update_feed_subscription', { input });
+  }
+
+  async deleteFeedSubscription(id: string): Promise<boolean> {
+    return invoke('delete_feed_subscription', { id });
+  }
+
+  /**
+   * Starts a poll now; resolves to false when one is already running. New items are
+   * announced with a `feed.items.added` event.
+   */
+  async refreshFeed(id: string): Promise<boolean> {
+    return invoke('refresh_feed', { id });
+  }
+
+  async listFeedItems(query: FeedItemQuery = {}): Promise<FeedItem[]> {
+    return invoke('list_feed_items', { query });
+  }
+
+  async markFeedItemsRead(ids: string[]): Promise<number> {
+    return invoke('mark_feed_items_read', { ids });
+  }
+
+  // ============ Scheduled Jobs ============
+
+  async listScheduledJobs(): Promise<ScheduledJob[]> {
+    return invoke('list_scheduled_jobs', {});
+  }
+
+  async createScheduledJob(input: CreateScheduledJobInput): Promise<ScheduledJob> {
+    return invoke('create_scheduled_job', { input });
+  }
+
+  async updateScheduledJob(input: UpdateScheduledJobInput): Promise<ScheduledJob | null> {
+    return invoke('update_scheduled_job', { input });
+  }
+
+  async deleteScheduledJob(id: string): Promise<boolean> {
+    return invoke('delete_scheduled_job', { id });
+  }
+
+  async runScheduledJobNow(id: string): Promise<ScheduledJobRun> {
+    return invoke('run_scheduled_job_now', { id });
+  }
+
+  async listScheduledJobRuns(jobId: string, limit?: number): Promise<ScheduledJobRun[]> {
+    return invoke('list_scheduled_job_runs', { job_id: jobId, limit });
+  }
+
+  // ============ Triggers ============
+
+  async listAgentTriggers(): Promise<AgentTrigger[]> {
+    return invoke('list_agent_triggers', {});
+  }
+
+  async createAgentTrigger(input: CreateAgentTriggerInput): Promise<AgentTrigger> {
+    return invoke('create_agent_trigger', { input });
+  }
+
+  async updateAgentTrigger(input: UpdateAgentTriggerInput): Promise<AgentTrigger | null> {
+    return invoke('update_agent_trigger', { input });
+  }
+
+  async deleteAgentTrigger(id: string): Promise<boolean> {
+    return invoke('delete_agent_trigger', { id });
+  }
+
+  async listAgentTriggerEvents(triggerId: string, limit?: number): Promise<AgentTriggerEvent[]> {
+    return invoke('list_agent_trigger_events', { trigger_id: triggerId, limit });
+  }
+
+  /** Polls the trigger's source immediately; resolves to the number of new events. */
+  async pollAgentTriggerNow(id: string): Promise<number> {
+    return invoke('poll_agent_trigger_now', { id });
+  }
+
   async setToolApprovalOverride(
     toolName: string,
     requiresApproval: boolean | null
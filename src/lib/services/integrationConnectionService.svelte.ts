@@ -4,6 +4,7 @@ import type {
     CreateIntegrationConnectionInput,
     UpdateIntegrationConnectionInput
 } from "$lib/types/integrationConnection";
+import type { IntegrationHealth } from "$lib/types/integrations";
 
 export class IntegrationConnectionService {
     connections = $state<IntegrationConnection[]>([]);
@@ -82,11 +83,11 @@ export class IntegrationConnectionService {
         }
     }
 
-    public async testConnection(id: string): Promise<{ ok: boolean; status: number } | null> {
+    public async testConnection(id: string): Promise<IntegrationHealth | null> {
         this.loading = true;
         this.error = null;
         try {
-            const result = await invoke<{ ok: boolean; status: number }>("test_integration_connection", { id });
+            const result = await invoke<IntegrationHealth>("test_integration_connection", { id });
             return result;
         } catch (error) {
             const message = error instanceof Error ? error.message : String(error);
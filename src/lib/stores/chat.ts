@@ -28,6 +28,7 @@ import type {
   ConversationUpdatedPayload,
   MessageSavedPayload,
   ToolExecutionCompletedPayload,
+  ToolExecutionProgressPayload,
   ToolExecutionStartedPayload,
   ToolExecutionApprovalScope,
   ToolExecutionDecisionPayload,
@@ -94,6 +95,7 @@ export type ToolActivityEntry = {
   completed_at?: number;
   duration_ms?: number;
   error?: string;
+  progress?: { message: string; current?: number | null; total?: number | null };
 };
 
 function getToolCallsForMessage(messageId: string): ToolCallRecord[] | undefined {
@@ -548,7 +550,9 @@ export async function startAgentEvents() {
         return;
       }
 
-      if (payload.message_id) {
+      // Sub-agent tool calls stay in the activity list; the parent's agent.delegate call
+      // represents them on the message.
+      if (payload.message_id && !payload.parent_execution_id) {
         ensureAssistantMessageForToolExecution(payload.message_id, payload.timestamp_ms);
         upsertToolCall(payload.message_id, payload.execution_id, {
           tool_name: payload.tool_name,
@@ -586,6 +590,27 @@ export async function startAgentEvents() {
       });
     }
 
+    if (event.event_type === AGENT_EVENT_TYPES.TOOL_EXECUTION_PROGRESS) {
+      const payload = event.payload as ToolExecutionProgressPayload;
+      if (payload.message_id && cancelledAssistantMessageIds.has(payload.message_id)) {
+        return;
+      }
+      toolActivity.update((entries) =>
+        entries.map((entry) =>
+          entry.execution_id === payload.execution_id && entry.status === 'running'
+            ? {
+                ...entry,
+                progress: {
+                  message: payload.message,
+                  current: payload.current,
+                  total: payload.total,
+                },
+              }
+            : entry
+        )
+      );
+    }
+
     if (event.event_type === AGENT_EVENT_TYPES.TOOL_EXECUTION_COMPLETED) {
       const payload = event.payload as ToolExecutionCompletedPayload;
       if (payload.message_id && cancelledAssistantMessageIds.has(payload.message_id)) {
@@ -596,7 +621,7 @@ export async function startAgentEvents() {
         return;
       }
 
-      if (payload.message_id) {
+      if (payload.message_id && !payload.parent_execution_id) {
         ensureAssistantMessageForToolExecution(payload.message_id, payload.timestamp_ms);
         upsertToolCall(payload.message_id, payload.execution_id, {
           tool_name: payload.tool_name,
@@ -671,6 +696,9 @@ export async function startAgentEvents() {
 
     if (event.event_type === AGENT_EVENT_TYPES.AGENT_PHASE_CHANGED) {
       const payload = event.payload as AgentPhaseChangedPayload;
+      if (payload.parent_session_id) {
+        return;
+      }
       agentPhase.set(payload.phase as PhaseKind);
       if (isNeedsHumanInputPhase(payload.phase)) {
         finalizeRunningToolCalls('Awaiting user input', Date.now());
@@ -687,6 +715,9 @@ export async function startAgentEvents() {
       event.event_type === AGENT_EVENT_TYPES.AGENT_PLAN_ADJUSTED
     ) {
       const payload = event.payload as AgentPlanPayload;
+      if (payload.parent_session_id) {
+        return;
+      }
       const plan = payload.plan as AgentPlan;
       agentPlan.set(plan);
       agentPlanSteps.set(plan?.steps || []);
@@ -694,6 +725,9 @@ export async function startAgentEvents() {
 
     if (event.event_type === AGENT_EVENT_TYPES.AGENT_STEP_PROPOSED) {
       const payload = event.payload as AgentStepProposedPayload;
+      if (payload.parent_session_id) {
+        return;
+      }
       const step = payload.step as AgentPlanStep;
       if (step?.id) {
         updatePlanStep(step.id, { status: step.status });
@@ -702,11 +736,17 @@ export async function startAgentEvents() {
 
     if (event.event_type === AGENT_EVENT_TYPES.AGENT_STEP_STARTED) {
       const payload = event.payload as AgentStepStartedPayload;
+      if (payload.parent_session_id) {
+        return;
+      }
       updatePlanStep(payload.step_id, { status: 'Executing' });
     }
 
     if (event.event_type === AGENT_EVENT_TYPES.AGENT_STEP_COMPLETED) {
       const payload = event.payload as AgentStepCompletedPayload;
+      if (payload.parent_session_id) {
+        return;
+      }
       updatePlanStep(payload.step_id, { status: payload.success ? 'Completed' : 'Failed' });
     }
   });
@@ -1078,10 +1118,18 @@ export function clearConversation() {
 export async function resolveToolApproval(
   approvalId: string,
   approved: boolean,
-  scope?: ToolExecutionApprovalScope
+  scope?: ToolExecutionApprovalScope,
+  modifiedArgs?: Record<string, unknown>,
+  feedback?: string
 ) {
   try {
-    await backend.resolveToolExecutionApproval(approvalId, approved, scope);
+    await backend.resolveToolExecutionApproval(
+      approvalId,
+      approved,
+      scope,
+      modifiedArgs,
+      feedback
+    );
   } catch (error) {
     console.error('Failed to resolve tool approval:', error);
   }
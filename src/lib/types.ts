@@ -21,14 +21,25 @@ import type {
 import type { Attachment, FileMetadata } from './types/attachments';
 import type { AgentEvent, AgentEventType } from './types/events';
 import type { ToolMetadata } from './types/tools';
-import type { IntegrationMetadata, GoogleCalendarListItem } from './types/integrations';
+import type {
+    IntegrationMetadata,
+    IntegrationCapability,
+    IntegrationHealth,
+    DiscoveredResource,
+    GoogleCalendarListItem
+} from './types/integrations';
 import type { McpServer, CreateMcpServerInput, UpdateMcpServerInput } from './types/mcpServer';
 import type {
     IntegrationConnection,
     CreateIntegrationConnectionInput,
-    UpdateIntegrationConnectionInput
+    UpdateIntegrationConnectionInput,
+    IntegrationSyncState
 } from './types/integrationConnection';
-import type { OAuthStartResponse, OAuthSessionStatus } from './types/oauth';
+import type {
+    OAuthStartResponse,
+    DeviceOAuthStartResponse,
+    OAuthSessionStatus
+} from './types/oauth';
 
 // Re-export everything
 export type {
@@ -44,6 +55,9 @@ export type {
     ToolExecutionDbRecord,
     ToolMetadata,
     IntegrationMetadata,
+    IntegrationCapability,
+    IntegrationHealth,
+    DiscoveredResource,
     GoogleCalendarListItem,
     McpServer,
     CreateMcpServerInput,
@@ -51,7 +65,9 @@ export type {
     IntegrationConnection,
     CreateIntegrationConnectionInput,
     UpdateIntegrationConnectionInput,
+    IntegrationSyncState,
     OAuthStartResponse,
+    DeviceOAuthStartResponse,
     OAuthSessionStatus
 };
 
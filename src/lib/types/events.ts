@@ -1,3 +1,6 @@
+import type { ScheduledJobRun } from './scheduler';
+import type { AgentTriggerEventStatus } from './triggers';
+
 export const AGENT_EVENT_TYPES = {
   MESSAGE_SAVED: 'message.saved',
   CONVERSATION_UPDATED: 'conversation.updated',
@@ -9,6 +12,7 @@ export const AGENT_EVENT_TYPES = {
   ASSISTANT_STREAM_COMPLETED: 'assistant.stream.completed',
   TOOL_EXECUTION_STARTED: 'tool.execution.started',
   TOOL_EXECUTION_COMPLETED: 'tool.execution.completed',
+  TOOL_EXECUTION_PROGRESS: 'tool.execution.progress',
   TOOL_EXECUTION_PROPOSED: 'tool.execution.proposed',
   TOOL_EXECUTION_APPROVED: 'tool.execution.approved',
   TOOL_EXECUTION_DENIED: 'tool.execution.denied',
@@ -19,6 +23,12 @@ export const AGENT_EVENT_TYPES = {
   AGENT_STEP_STARTED: 'agent.step.started',
   AGENT_STEP_COMPLETED: 'agent.step.completed',
   AGENT_COMPLETED: 'agent.completed',
+  SCHEDULED_JOB_RUN_STARTED: 'scheduled_job.run.started',
+  SCHEDULED_JOB_RUN_COMPLETED: 'scheduled_job.run.completed',
+  AGENT_TRIGGER_RUN_STARTED: 'trigger.run.started',
+  AGENT_TRIGGER_RUN_COMPLETED: 'trigger.run.completed',
+  INTEGRATION_SYNC_COMPLETED: 'integration.sync.completed',
+  FEED_ITEMS_ADDED: 'feed.items.added',
 } as const;
 
 export type AgentEventType = typeof AGENT_EVENT_TYPES[keyof typeof AGENT_EVENT_TYPES];
@@ -43,9 +53,21 @@ export interface EventToolExecution {
   duration_ms: number;
   timestamp_ms: number;
   error?: string | null;
+  error_kind?: ToolErrorKind | null;
   iteration_number: number;
 }
 
+export type ToolErrorKind =
+  | 'validation'
+  | 'not_found'
+  | 'permission'
+  | 'rate_limited'
+  | 'timeout'
+  | 'upstream'
+  | 'denied'
+  | 'cancelled'
+  | 'internal';
+
 export type AgentEventPayloadMap = {
   'message.saved': MessageSavedPayload;
   'conversation.updated': ConversationUpdatedPayload;
@@ -57,6 +79,7 @@ export type AgentEventPayloadMap = {
   'assistant.stream.completed': AssistantStreamCompletedPayload;
   'tool.execution.started': ToolExecutionStartedPayload;
   'tool.execution.completed': ToolExecutionCompletedPayload;
+  'tool.execution.progress': ToolExecutionProgressPayload;
   'tool.execution.proposed': ToolExecutionProposedPayload;
   'tool.execution.approved': ToolExecutionDecisionPayload;
   'tool.execution.denied': ToolExecutionDecisionPayload;
@@ -67,6 +90,12 @@ export type AgentEventPayloadMap = {
   'agent.step.started': AgentStepStartedPayload;
   'agent.step.completed': AgentStepCompletedPayload;
   'agent.completed': AgentCompletedPayload;
+  'scheduled_job.run.started': ScheduledJobRunPayload;
+  'scheduled_job.run.completed': ScheduledJobRunPayload;
+  'trigger.run.started': AgentTriggerRunPayload;
+  'trigger.run.completed': AgentTriggerRunPayload;
+  'integration.sync.completed': IntegrationSyncCompletedPayload;
+  'feed.items.added': FeedItemsAddedPayload;
 };
 
 export interface EventAttachment {
@@ -129,7 +158,15 @@ export interface AssistantStreamCompletedPayload {
   timestamp_ms: number;
 }
 
-export interface ToolExecutionStartedPayload {
+/** Present on events emitted by a sub-agent spawned via agent.delegate. */
+export interface SubAgentEventScope {
+  parent_session_id?: string;
+  parent_step_id?: string;
+  parent_execution_id?: string;
+  agent_depth?: number;
+}
+
+export interface ToolExecutionStartedPayload extends SubAgentEventScope {
   execution_id: string;
   tool_name: string;
   args: Record<string, unknown>;
@@ -152,7 +189,7 @@ export interface ToolExecutionProposedPayload {
   timestamp_ms: number;
 }
 
-export type ToolExecutionApprovalScope = 'once' | 'conversation' | 'always';
+export type ToolExecutionApprovalScope = 'once' | 'session' | 'conversation' | 'always';
 
 export interface ToolExecutionDecisionPayload {
   execution_id: string;
@@ -160,17 +197,26 @@ export interface ToolExecutionDecisionPayload {
   tool_name: string;
   iteration: number;
   scope?: ToolExecutionApprovalScope;
+  modified?: boolean;
+  original_args?: Record<string, unknown>;
+  args?: Record<string, unknown>;
+  preview?: unknown;
+  feedback?: string | null;
   conversation_id?: string;
   message_id?: string;
   timestamp_ms: number;
 }
 
-export interface ToolExecutionCompletedPayload {
+export interface ToolExecutionCompletedPayload extends SubAgentEventScope {
   execution_id: string;
   tool_name: string;
   result?: unknown;
   success: boolean;
   error?: string;
+  error_kind?: ToolErrorKind | null;
+  retryable?: boolean | null;
+  hint?: string | null;
+  attempts?: number;
   duration_ms: number;
   iteration: number;
   conversation_id?: string;
@@ -178,17 +224,29 @@ export interface ToolExecutionCompletedPayload {
   timestamp_ms: number;
 }
 
-export interface AgentPhaseChangedPayload {
+export interface ToolExecutionProgressPayload extends SubAgentEventScope {
+  execution_id: string;
+  tool_name: string;
+  message: string;
+  current?: number | null;
+  total?: number | null;
+  data?: unknown;
+  conversation_id?: string;
+  message_id?: string;
+  timestamp_ms: number;
+}
+
+export interface AgentPhaseChangedPayload extends SubAgentEventScope {
   session_id: string;
   phase: unknown;
 }
 
-export interface AgentPlanPayload {
+export interface AgentPlanPayload extends SubAgentEventScope {
   session_id: string;
   plan: unknown;
 }
 
-export interface AgentStepProposedPayload {
+export interface AgentStepProposedPayload extends SubAgentEventScope {
   session_id: string;
   step: unknown;
   risk: string;
@@ -196,12 +254,12 @@ export interface AgentStepProposedPayload {
   preview?: unknown;
 }
 
-export interface AgentStepStartedPayload {
+export interface AgentStepStartedPayload extends SubAgentEventScope {
   session_id: string;
   step_id: string;
 }
 
-export interface AgentStepCompletedPayload {
+export interface AgentStepCompletedPayload extends SubAgentEventScope {
   session_id: string;
   step_id: string;
   success: boolean;
@@ -209,11 +267,50 @@ export interface AgentStepCompletedPayload {
   error?: string | null;
 }
 
-export interface AgentCompletedPayload {
+export interface AgentCompletedPayload extends SubAgentEventScope {
   session_id: string;
   response: string;
 }
 
+export interface ScheduledJobRunPayload {
+  job_id: string;
+  job_name: string;
+  run: ScheduledJobRun;
+  timestamp_ms: number;
+}
+
+export interface AgentTriggerRunPayload {
+  trigger_id: string;
+  trigger_name: string;
+  run_id: string;
+  event_count: number;
+  /** Only set on `trigger.run.completed`. */
+  status?: AgentTriggerEventStatus;
+  conversation_id?: string | null;
+  message_id?: string | null;
+  error?: string | null;
+  timestamp_ms: number;
+}
+
+export interface IntegrationSyncCompletedPayload {
+  connection_id: string;
+  integration_id: string;
+  status: 'ok' | 'error';
+  /** Cached rows written or removed. */
+  changed: number;
+  errors: string[];
+  timestamp_ms: number;
+}
+
+export interface FeedItemsAddedPayload {
+  feed_id: string;
+  url: string;
+  title?: string | null;
+  /** Entries stored by this poll. */
+  added: number;
+  timestamp_ms: number;
+}
+
 export interface AgentEvent<T extends AgentEventType = AgentEventType> {
   event_type: T;
   payload: AgentEventPayloadMap[T];
@@ -0,0 +1,56 @@
+/** An RSS, Atom or JSON Feed subscription polled in the background. */
+export interface FeedSubscription {
+  id: string;
+  url: string;
+  /** Custom title, or the one the feed declares. */
+  title?: string;
+  site_url?: string;
+  poll_interval_minutes: number;
+  enabled: boolean;
+  etag?: string;
+  last_modified?: string;
+  last_polled_at?: number;
+  next_poll_at?: number;
+  last_error?: string;
+  unread_count: number;
+  created_at: number;
+  updated_at: number;
+}
+
+export interface CreateFeedSubscriptionInput {
+  url: string;
+  title?: string | null;
+  /** Defaults to 60; at least 5. */
+  poll_interval_minutes?: number | null;
+}
+
+export interface UpdateFeedSubscriptionInput {
+  id: string;
+  /** An empty string clears a custom title. */
+  title?: string | null;
+  poll_interval_minutes?: number | null;
+  enabled?: boolean | null;
+}
+
+export interface FeedItem {
+  id: string;
+  feed_id: string;
+  feed_title?: string;
+  guid: string;
+  title: string;
+  url?: string;
+  /** Plain-text summary, shortened. */
+  summary?: string;
+  author?: string;
+  published_at?: number;
+  fetched_at: number;
+  read_at?: number;
+}
+
+export interface FeedItemQuery {
+  feed_id?: string | null;
+  unread_only?: boolean;
+  /** Inclusive lower bound on the published (or fetched) time, in ms. */
+  since?: number | null;
+  limit?: number | null;
+}
@@ -36,3 +36,15 @@ export interface UpdateIntegrationConnectionInput {
     last_error?: string;
     last_sync_at?: number;
 }
+
+export interface IntegrationSyncState {
+    connection_id: string;
+    /** `gmail_messages`, `todoist_items` or `calendar:{calendar_id}`. */
+    resource: string;
+    cursor?: string;
+    status: 'ok' | 'error' | 'stale';
+    last_error?: string;
+    item_count: number;
+    last_synced_at?: number;
+    updated_at: number;
+}
@@ -1,3 +1,16 @@
+export type IntegrationCapability =
+    | 'sync'
+    | 'webhook_ingest'
+    | 'action_execute'
+    | 'discovery'
+    | 'health_check';
+
+export interface IntegrationScope {
+    label: string;
+    scope: string;
+}
+
+/** Plugin manifest. */
 export interface IntegrationMetadata {
     id: string;
     name: string;
@@ -5,7 +18,23 @@ export interface IntegrationMetadata {
     description: string;
     auth_type: string;
     category: string;
-    capabilities: string[];
+    capabilities: IntegrationCapability[];
+    scopes: IntegrationScope[];
+    /** JSON Schema of the per-connection settings object. */
+    settings_schema: Record<string, unknown>;
+}
+
+export interface IntegrationHealth {
+    ok: boolean;
+    status?: number;
+    message?: string;
+}
+
+export interface DiscoveredResource {
+    kind: string;
+    id: string;
+    name: string;
+    metadata: Record<string, unknown>;
 }
 
 export interface GoogleCalendarListItem {
@@ -3,6 +3,14 @@ export interface OAuthStartResponse {
     auth_url: string;
 }
 
+export interface DeviceOAuthStartResponse {
+    session_id: string;
+    user_code: string;
+    verification_uri: string;
+    verification_uri_complete?: string;
+    expires_in: number;
+}
+
 export interface OAuthSessionStatus {
     status: string;
     connection_id?: string;
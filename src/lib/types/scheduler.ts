@@ -0,0 +1,62 @@
+export type ScheduledJobApprovalPolicy = 'deny' | 'allow' | 'ask';
+export type ScheduledJobRunStatus = 'running' | 'succeeded' | 'failed' | 'cancelled';
+
+export interface ScheduledJob {
+  id: string;
+  name: string;
+  prompt: string;
+  schedule: string;
+  provider: string;
+  model: string;
+  custom_backend_id?: string;
+  system_prompt?: string;
+  conversation_id?: string;
+  approval_policy: ScheduledJobApprovalPolicy;
+  enabled: boolean;
+  next_run_at?: number;
+  last_run_at?: number;
+  last_status?: ScheduledJobRunStatus;
+  created_at: number;
+  updated_at: number;
+}
+
+export interface CreateScheduledJobInput {
+  name: string;
+  prompt: string;
+  schedule: string;
+  provider: string;
+  model: string;
+  custom_backend_id?: string;
+  system_prompt?: string;
+  conversation_id?: string;
+  approval_policy?: ScheduledJobApprovalPolicy;
+  enabled?: boolean;
+}
+
+export interface UpdateScheduledJobInput {
+  id: string;
+  name?: string;
+  prompt?: string;
+  schedule?: string;
+  provider?: string;
+  model?: string;
+  custom_backend_id?: string;
+  system_prompt?: string;
+  /** Empty string switches back to a new conversation per run. */
+  conversation_id?: string;
+  approval_policy?: ScheduledJobApprovalPolicy;
+  enabled?: boolean;
+}
+
+export interface ScheduledJobRun {
+  id: string;
+  job_id: string;
+  triggered_by: 'schedule' | 'manual';
+  status: ScheduledJobRunStatus;
+  conversation_id?: string;
+  message_id?: string;
+  output_preview?: string;
+  error?: string;
+  started_at: number;
+  finished_at?: number;
+}
@@ -8,3 +8,166 @@ export interface ToolMetadata {
   requires_approval: boolean;
   result_mode: ToolResultMode;
 }
+
+export type ToolApprovalRuleAction = 'allow' | 'ask' | 'deny';
+export type ToolApprovalRuleScope = 'global' | 'conversation' | 'session';
+export type ToolApprovalConditionOp =
+  | 'equals'
+  | 'not_equals'
+  | 'glob'
+  | 'not_glob'
+  | 'regex'
+  | 'not_regex';
+
+export interface ToolApprovalArgCondition {
+  arg: string;
+  op: ToolApprovalConditionOp;
+  value: string;
+}
+
+export interface ToolApprovalRule {
+  id: string;
+  name?: string;
+  tool_pattern: string;
+  arg_conditions: ToolApprovalArgCondition[];
+  action: ToolApprovalRuleAction;
+  scope: ToolApprovalRuleScope;
+  conversation_id?: string;
+  session_id?: string;
+  expires_at?: number;
+  created_at: number;
+}
+
+export interface CreateToolApprovalRuleInput {
+  name?: string | null;
+  tool_pattern: string;
+  arg_conditions?: ToolApprovalArgCondition[];
+  action: ToolApprovalRuleAction;
+  scope: ToolApprovalRuleScope;
+  conversation_id?: string | null;
+  session_id?: string | null;
+  expires_at?: number | null;
+}
+
+export type AuditDecision =
+  | 'approved'
+  | 'modified'
+  | 'auto_approved'
+  | 'denied'
+  | 'timed_out'
+  | 'cancelled';
+
+export type AuditOutcome = 'success' | 'error' | 'not_executed';
+
+/** One side-effecting tool call. Entries are append-only and outlive their conversation. */
+export interface AuditLogEntry {
+  id: string;
+  created_at: number;
+  execution_id: string;
+  tool_name: string;
+  integration_id?: string;
+  connection_id?: string;
+  account_label?: string;
+  args: Record<string, unknown>;
+  decision: AuditDecision;
+  /** 'user', 'rule', 'conversation_override', 'global_override' or 'unattended'. */
+  approved_by?: string;
+  approval_rule_id?: string;
+  outcome: AuditOutcome;
+  error?: string;
+  error_kind?: string;
+  external_resource_id?: string;
+  conversation_id?: string;
+  message_id?: string;
+  duration_ms: number;
+}
+
+export interface AuditLogQuery {
+  /** Exact tool name, or a prefix ending in `*` such as `gmail.*`. */
+  tool_name?: string | null;
+  integration_id?: string | null;
+  connection_id?: string | null;
+  conversation_id?: string | null;
+  decision?: AuditDecision | null;
+  outcome?: AuditOutcome | null;
+  since?: number | null;
+  until?: number | null;
+  limit?: number | null;
+  offset?: number | null;
+}
+
+/** Which URLs the web tools may reach. Deny rules win over allow rules. */
+export interface WebAccessRule {
+  id: string;
+  /** Exact host, or `*.example.com` for example.com and all of its subdomains. */
+  host: string;
+  /** Paths equal to or below this prefix. */
+  path_prefix?: string;
+  /** Upper-case HTTP methods; absent means every method. */
+  methods?: string[];
+  allow_private: boolean;
+  deny: boolean;
+  /** Unix seconds. */
+  expires_at?: number;
+  /** Unix seconds. */
+  approved_at: number;
+  note?: string;
+}
+
+export interface WebAccessRuleInput {
+  id?: string | null;
+  host: string;
+  path_prefix?: string | null;
+  methods?: string[];
+  allow_private?: boolean;
+  deny?: boolean;
+  expires_at?: number | null;
+  note?: string | null;
+}
+
+export type WebAuthKind = 'bearer' | 'basic' | 'api_key' | 'hmac';
+
+/** Stored credentials for `web.request`; the secret is never sent back. */
+export interface WebAuthProfile {
+  id: string;
+  name: string;
+  kind: WebAuthKind;
+  /** Exact hosts or `*.example.com`. */
+  host_patterns: string[];
+  /** Header for `api_key`, or the signature header for `hmac` (default `X-Signature`). */
+  header_name?: string;
+  /** User name for `basic`. */
+  username?: string;
+  created_at: number;
+  updated_at: number;
+}
+
+export interface SaveWebAuthProfileInput {
+  id?: string | null;
+  name: string;
+  kind: WebAuthKind;
+  host_patterns: string[];
+  header_name?: string | null;
+  username?: string | null;
+  /** Required for new profiles; omit on edit to keep the stored secret. */
+  secret?: string | null;
+}
+
+export type WebCrawlStatus = 'running' | 'completed' | 'cancelled' | 'failed';
+
+/** A background `web.crawl` job and its manifest so far. */
+export interface WebCrawlJob {
+  id: string;
+  start_url: string;
+  /** Vault folder the notes are written under. */
+  folder: string;
+  status: WebCrawlStatus;
+  max_depth: number;
+  max_pages: number;
+  queued: number;
+  pages: { url: string; path: string; title: string; depth: number; bytes: number }[];
+  skipped: { url: string; reason: string }[];
+  error?: string;
+  started_at: number;
+  finished_at?: number;
+}
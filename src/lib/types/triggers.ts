@@ -0,0 +1,89 @@
+import type { ScheduledJobApprovalPolicy } from './scheduler';
+
+export type AgentTriggerSource = 'gmail' | 'todoist' | 'vault' | 'webhook';
+export type AgentTriggerEventStatus =
+  | 'baseline'
+  | 'pending'
+  | 'running'
+  | 'succeeded'
+  | 'failed'
+  | 'cancelled'
+  | 'rate_limited';
+
+/**
+ * Source-specific settings:
+ * - gmail: `{ connection_id?, label?, query? }` (label or query required)
+ * - todoist: `{ connection_id?, project_id }`
+ * - vault: `{ path_prefix?, events?: ('created' | 'modified' | 'deleted')[] }`
+ * - webhook: `{ secret }` (at least 16 characters, sent as `X-Trigger-Secret`)
+ */
+export type AgentTriggerConfig = Record<string, unknown>;
+
+export interface AgentTrigger {
+  id: string;
+  name: string;
+  source: AgentTriggerSource;
+  config: AgentTriggerConfig;
+  prompt: string;
+  provider: string;
+  model: string;
+  custom_backend_id?: string;
+  system_prompt?: string;
+  conversation_id?: string;
+  approval_policy: ScheduledJobApprovalPolicy;
+  enabled: boolean;
+  max_runs_per_hour: number;
+  cursor?: unknown;
+  last_fired_at?: number;
+  last_error?: string;
+  created_at: number;
+  updated_at: number;
+}
+
+export interface CreateAgentTriggerInput {
+  name: string;
+  source: AgentTriggerSource;
+  config?: AgentTriggerConfig;
+  prompt: string;
+  provider: string;
+  model: string;
+  custom_backend_id?: string;
+  system_prompt?: string;
+  conversation_id?: string;
+  approval_policy?: ScheduledJobApprovalPolicy;
+  enabled?: boolean;
+  max_runs_per_hour?: number;
+}
+
+export interface UpdateAgentTriggerInput {
+  id: string;
+  name?: string;
+  /** Replacing the config resets the trigger's cursor. */
+  config?: AgentTriggerConfig;
+  prompt?: string;
+  provider?: string;
+  model?: string;
+  custom_backend_id?: string;
+  system_prompt?: string;
+  /** Empty string switches back to a new conversation per run. */
+  conversation_id?: string;
+  approval_policy?: ScheduledJobApprovalPolicy;
+  enabled?: boolean;
+  max_runs_per_hour?: number;
+}
+
+export interface AgentTriggerEvent {
+  id: string;
+  trigger_id: string;
+  dedupe_key: string;
+  summary: string;
+  payload: unknown;
+  status: AgentTriggerEventStatus;
+  run_id?: string;
+  conversation_id?: string;
+  message_id?: string;
+  error?: string;
+  created_at: number;
+  dispatched_at?: number;
+  finished_at?: number;
+}